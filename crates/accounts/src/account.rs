@@ -1,5 +1,7 @@
 //! Player account data structures
 
+use crate::folder_rights::FolderRights;
+use gserver_core::TileCoord;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::collections::HashMap;
@@ -143,10 +145,10 @@ pub struct Account {
     /// Starting level
     pub level: String,
 
-    /// X position (in pixels, divide by 16 for tiles)
+    /// X position (in tiles, as stored in the account file)
     pub x: f32,
 
-    /// Y position (in pixels, divide by 16 for tiles)
+    /// Y position (in tiles, as stored in the account file)
     pub y: f32,
 
     /// Z position
@@ -251,12 +253,31 @@ pub struct Account {
     /// Ban length
     pub ban_length: String,
 
+    /// Staff account that set the ban (BANISSUER)
+    pub ban_issuer: String,
+
+    /// Ban expiry unix time (BANNEDUNTIL), 0 if the ban lasts until lifted
+    pub banned_until: u64,
+
+    /// Client platform of the last login (PLATFORM)
+    pub platform: String,
+
+    /// PC id of the last login's identity (PCID)
+    pub pc_id: String,
+
+    /// Hard disk id of the last login's identity (HDDID)
+    pub hdd_id: String,
+
     /// Comments
     pub comments: String,
 
     /// Email
     pub email: String,
 
+    /// Password hash (PASSWORD), or a legacy plaintext password; empty if
+    /// none is set (see [`crate::check_password`])
+    pub password: String,
+
     /// Local rights (staff permissions)
     pub local_rights: u32,
 
@@ -275,6 +296,21 @@ pub struct Account {
     /// Last folder accessed
     pub last_folder: String,
 
+    /// Friends (account names from FRIEND entries)
+    pub friends: Vec<String>,
+
+    /// Ignored accounts (IGNORE entries), whose PMs aren't delivered
+    pub ignored: Vec<String>,
+
+    /// Only friends can send PMs (PMFRIENDSONLY)
+    pub pm_friends_only: bool,
+
+    /// Muted until this unix time (MUTEDUNTIL), 0 if not muted
+    pub muted_until: u64,
+
+    /// Jailed until this unix time (JAILEDUNTIL), 0 if not jailed
+    pub jailed_until: u64,
+
     /// Gani attributes (30 animation strings)
     /// # C++ Equivalence
     /// Matches `std::array<std::string, 30> ganiAttributes` in Character.h
@@ -341,6 +377,11 @@ impl Default for Account {
             banned: 0,
             ban_reason: String::new(),
             ban_length: String::new(),
+            ban_issuer: String::new(),
+            banned_until: 0,
+            platform: String::new(),
+            pc_id: String::new(),
+            hdd_id: String::new(),
             comments: String::new(),
             email: String::new(),
             local_rights: 0,
@@ -349,6 +390,12 @@ impl Default for Account {
             weapons: Vec::new(),
             folder_rights: Vec::new(),
             last_folder: String::new(),
+            friends: Vec::new(),
+            ignored: Vec::new(),
+            pm_friends_only: false,
+            muted_until: 0,
+            jailed_until: 0,
+            password: String::new(),
             gani_attributes: [
                 String::new(), String::new(), String::new(), String::new(), String::new(),
                 String::new(), String::new(), String::new(), String::new(), String::new(),
@@ -458,10 +505,13 @@ impl Account {
         self.is_staff() || self.local_rights & PLPERM_ANYRIGHT != 0
     }
 
-    /// Get position in tiles (16 pixels = 1 tile)
+    /// Get position in tiles
+    ///
+    /// Account files store X/Y in tiles already; convert with
+    /// [`TileCoord::to_pixels`] or [`TileCoord::to_half_tiles`] for packets.
     #[inline]
-    pub fn get_tile_pos(&self) -> (f32, f32) {
-        (self.x / 16.0, self.y / 16.0)
+    pub fn get_tile_pos(&self) -> (TileCoord, TileCoord) {
+        (TileCoord(self.x), TileCoord(self.y))
     }
 
     /// Check if account has a specific weapon
@@ -485,45 +535,94 @@ impl Account {
         }
     }
 
+    /// Check if an account is on the friend list (case-insensitive)
+    pub fn is_friend(&self, account: &str) -> bool {
+        self.friends.iter().any(|f| f.eq_ignore_ascii_case(account))
+    }
+
+    /// Check if an account is on the ignore list (case-insensitive)
+    pub fn is_ignoring(&self, account: &str) -> bool {
+        self.ignored.iter().any(|i| i.eq_ignore_ascii_case(account))
+    }
+
+    /// Check if a PM from an account is delivered to this one
+    pub fn accepts_pm_from(&self, account: &str) -> bool {
+        !self.is_ignoring(account) && (!self.pm_friends_only || self.is_friend(account))
+    }
+
+    /// Add an account to the friend list
+    ///
+    /// # Returns
+    /// `false` if it was already on it
+    pub fn add_friend(&mut self, account: &str) -> bool {
+        add_name(&mut self.friends, account)
+    }
+
+    /// Remove an account from the friend list
+    ///
+    /// # Returns
+    /// `false` if it wasn't on it
+    pub fn remove_friend(&mut self, account: &str) -> bool {
+        remove_name(&mut self.friends, account)
+    }
+
+    /// Add an account to the ignore list
+    ///
+    /// # Returns
+    /// `false` if it was already on it
+    pub fn ignore(&mut self, account: &str) -> bool {
+        add_name(&mut self.ignored, account)
+    }
+
+    /// Remove an account from the ignore list
+    ///
+    /// # Returns
+    /// `false` if it wasn't on it
+    pub fn unignore(&mut self, account: &str) -> bool {
+        remove_name(&mut self.ignored, account)
+    }
+
+    /// Check if the account is muted at a unix time
+    pub fn is_muted_at(&self, now: u64) -> bool {
+        self.muted_until > now
+    }
+
+    /// Check if the account is jailed at a unix time
+    pub fn is_jailed_at(&self, now: u64) -> bool {
+        self.jailed_until > now
+    }
+
+    /// Check if the account is banned at a unix time
+    pub fn is_banned_at(&self, now: u64) -> bool {
+        self.banned != 0 && (self.banned_until == 0 || self.banned_until > now)
+    }
+
+    /// Replace the password with a hash of `password`
+    ///
+    /// # Errors
+    /// `AccountError::InvalidData` if hashing fails
+    pub fn set_password(&mut self, password: &str) -> crate::Result<()> {
+        self.password = crate::password::hash_password(password)?;
+        Ok(())
+    }
+
+    /// Get the parsed folder rights of this account
+    ///
+    /// # C++ Equivalence
+    /// Matches the `FilePermissions` list built from FOLDERRIGHT lines
+    pub fn folder_permissions(&self) -> FolderRights {
+        FolderRights::parse(&self.folder_rights)
+    }
+
     /// Get folder rights for a specific path
     ///
     /// # C++ Equivalence
     /// Matches `FilePermissions::getPermission()` in the C++ code
     ///
     /// Returns the permission string (e.g., "rw", "r", "-") for the given path
-    /// or None if no matching folder right is found
+    /// (relative to the server directory), or None if no folder right covers it
     pub fn get_folder_rights(&self, path: &str) -> Option<String> {
-        for folder_right in &self.folder_rights {
-            // Format: "rw accounts/*" or "r weapons/*"
-            let parts: Vec<&str> = folder_right.splitn(2, ' ').collect();
-            if parts.len() == 2 {
-                let permission = parts[0];
-                let pattern = parts[1];
-
-                // Simple pattern matching (supports * wildcard)
-                if self.matches_folder_pattern(path, pattern) {
-                    return Some(permission.to_string());
-                }
-            }
-        }
-        None
-    }
-
-    /// Check if a path matches a folder pattern
-    ///
-    /// # Examples
-    /// - "accounts/test.txt" matches "accounts/*"
-    /// - "config/serveroptions.txt" matches "config/*"
-    fn matches_folder_pattern(&self, path: &str, pattern: &str) -> bool {
-        if pattern.ends_with("/*") {
-            let prefix = &pattern[..pattern.len() - 2];
-            path.starts_with(prefix) || path == prefix.trim_end_matches('/')
-        } else if pattern.ends_with('*') {
-            let prefix = &pattern[..pattern.len() - 1];
-            path.starts_with(prefix)
-        } else {
-            path == pattern
-        }
+        self.folder_permissions().permission(path).map(|access| access.to_string())
     }
 
     /// Check if player has opened a specific chest
@@ -588,3 +687,19 @@ impl Account {
         self.flags.get(key).map(|v| v.is_set()).unwrap_or(false)
     }
 }
+
+/// Add a name to an account list unless it is on it already (case-insensitive)
+fn add_name(list: &mut Vec<String>, name: &str) -> bool {
+    if name.is_empty() || list.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+        return false;
+    }
+    list.push(name.to_string());
+    true
+}
+
+/// Remove a name from an account list (case-insensitive)
+fn remove_name(list: &mut Vec<String>, name: &str) -> bool {
+    let before = list.len();
+    list.retain(|n| !n.eq_ignore_ascii_case(name));
+    list.len() != before
+}
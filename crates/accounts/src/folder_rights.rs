@@ -0,0 +1,242 @@
+//! Per-account folder rights
+//!
+//! # Purpose
+//! Staff accounts carry FOLDERRIGHT lines that scope what they may read and
+//! write through the RC file browser, independent of the global
+//! foldersconfig.txt whitelist.
+//!
+//! # Format
+//! ```text
+//! FOLDERRIGHT rw levels/*         // read and write every file in levels/
+//! FOLDERRIGHT r  images/*.png     // read PNGs in images/
+//! FOLDERRIGHT -  config/*         // listed, but no access
+//! ```
+//!
+//! Folders are relative to the server directory and don't include
+//! subfolders; `levels/*` doesn't cover `levels/old/`. When several lines
+//! match a file, the first one wins.
+//!
+//! # C++ Equivalence
+//! Matches `FilePermissions` and the folder list built in
+//! `PlayerRC::msgPLI_RC_FILEBROWSER_START`
+
+use gserver_core::wildcard_match;
+
+/// Read/write access to a file or folder
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FolderAccess {
+    /// Can list and download
+    pub read: bool,
+
+    /// Can upload, delete and rename
+    pub write: bool,
+}
+
+impl FolderAccess {
+    /// No access
+    pub const NONE: Self = Self { read: false, write: false };
+
+    /// Parse a rights string ("rw", "r", "w", "-")
+    pub fn parse(rights: &str) -> Self {
+        Self {
+            read: rights.contains(['r', 'R']),
+            write: rights.contains(['w', 'W']),
+        }
+    }
+
+    /// Combine two access levels
+    pub fn union(self, other: Self) -> Self {
+        Self { read: self.read || other.read, write: self.write || other.write }
+    }
+}
+
+impl std::fmt::Display for FolderAccess {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.read, self.write) {
+            (true, true) => f.write_str("rw"),
+            (true, false) => f.write_str("r"),
+            (false, true) => f.write_str("w"),
+            (false, false) => f.write_str("-"),
+        }
+    }
+}
+
+/// One FOLDERRIGHT line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FolderRight {
+    /// Granted access
+    pub access: FolderAccess,
+
+    /// Folder relative to the server directory, without a trailing slash
+    /// (empty for the server directory itself)
+    pub folder: String,
+
+    /// File name wildcard inside the folder
+    pub wildcard: String,
+}
+
+impl FolderRight {
+    /// Parse a FOLDERRIGHT value ("rw levels/*")
+    ///
+    /// # Returns
+    /// `None` if the line has no path or the folder leaves the server directory
+    pub fn parse(line: &str) -> Option<Self> {
+        let (rights, pattern) = line.trim().split_once(char::is_whitespace)?;
+        let pattern = pattern.trim().replace('\\', "/");
+
+        let (folder, wildcard) = match pattern.rfind('/') {
+            Some(pos) => (&pattern[..pos], &pattern[pos + 1..]),
+            None => ("", pattern.as_str()),
+        };
+        let folder = folder.trim_matches('/');
+        if folder.split('/').any(|part| part == "..") {
+            return None;
+        }
+
+        Some(Self {
+            access: FolderAccess::parse(rights),
+            folder: folder.to_string(),
+            wildcard: if wildcard.is_empty() { "*".to_string() } else { wildcard.to_string() },
+        })
+    }
+
+    /// Check if this right covers a folder (case-insensitive)
+    pub fn covers_folder(&self, folder: &str) -> bool {
+        self.folder.eq_ignore_ascii_case(folder.trim_matches('/'))
+    }
+
+    /// Check if this right covers a file in `folder`
+    pub fn covers_file(&self, folder: &str, name: &str) -> bool {
+        self.covers_folder(folder) && wildcard_match(&self.wildcard, name)
+    }
+}
+
+impl std::fmt::Display for FolderRight {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.folder.is_empty() {
+            write!(f, "{} {}", self.access, self.wildcard)
+        } else {
+            write!(f, "{} {}/{}", self.access, self.folder, self.wildcard)
+        }
+    }
+}
+
+/// Parsed folder rights of an account
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FolderRights {
+    rights: Vec<FolderRight>,
+}
+
+impl FolderRights {
+    /// Parse FOLDERRIGHT values, skipping malformed lines
+    pub fn parse<S: AsRef<str>>(lines: &[S]) -> Self {
+        Self {
+            rights: lines.iter().filter_map(|line| FolderRight::parse(line.as_ref())).collect(),
+        }
+    }
+
+    /// Get the parsed rights in account order
+    pub fn iter(&self) -> impl Iterator<Item = &FolderRight> {
+        self.rights.iter()
+    }
+
+    /// Check if the account has no folder rights
+    pub fn is_empty(&self) -> bool {
+        self.rights.is_empty()
+    }
+
+    /// Get the access the first matching right grants to a file, given as a
+    /// path relative to the server directory ("levels/start.nw")
+    ///
+    /// # Returns
+    /// `None` if no right covers the file. Paths that climb out of their
+    /// folder are never covered.
+    ///
+    /// # C++ Equivalence
+    /// Matches `FilePermissions::getPermission()`
+    pub fn permission(&self, path: &str) -> Option<FolderAccess> {
+        let path = path.replace('\\', "/");
+        if path.split('/').any(|part| part == ".." || part == ".") {
+            return None;
+        }
+        let (folder, name) = path.rsplit_once('/').unwrap_or(("", path.as_str()));
+
+        self.rights.iter()
+            .find(|right| right.covers_file(folder, name))
+            .map(|right| right.access)
+    }
+
+    /// Get the access to a file (no access if no right covers it)
+    #[inline]
+    pub fn file_access(&self, path: &str) -> FolderAccess {
+        self.permission(path).unwrap_or(FolderAccess::NONE)
+    }
+
+    /// Get the combined access of every right on a folder
+    ///
+    /// # Returns
+    /// `None` if the folder isn't in the account's folder list
+    pub fn folder_access(&self, folder: &str) -> Option<FolderAccess> {
+        self.rights.iter()
+            .filter(|right| right.covers_folder(folder))
+            .map(|right| right.access)
+            .reduce(FolderAccess::union)
+    }
+
+    /// Check if a file can be read
+    #[inline]
+    pub fn can_read(&self, path: &str) -> bool {
+        self.file_access(path).read
+    }
+
+    /// Check if a file can be written
+    #[inline]
+    pub fn can_write(&self, path: &str) -> bool {
+        self.file_access(path).write
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rights() {
+        let right = FolderRight::parse("rw levels/*").unwrap();
+        assert_eq!(right.access, FolderAccess { read: true, write: true });
+        assert_eq!((right.folder.as_str(), right.wildcard.as_str()), ("levels", "*"));
+
+        let right = FolderRight::parse("r world\\images/*.png").unwrap();
+        assert_eq!(right.access.to_string(), "r");
+        assert_eq!(right.folder, "world/images");
+        assert_eq!(right.to_string(), "r world/images/*.png");
+
+        assert_eq!(FolderRight::parse("- config/").unwrap().wildcard, "*");
+        assert_eq!(FolderRight::parse("rw *.txt").unwrap().folder, "");
+        assert!(FolderRight::parse("rw").is_none());
+        assert!(FolderRight::parse("rw ../*").is_none());
+    }
+
+    #[test]
+    fn test_file_access_first_match_wins() {
+        let rights = FolderRights::parse(&["r levels/secret*", "rw levels/*", "r images/*.png"]);
+
+        assert!(rights.can_write("levels/start.nw"));
+        assert!(rights.can_read("Levels/START.nw"));
+        assert!(!rights.can_write("levels/secret.nw"));
+        assert!(rights.can_read("images/logo.png"));
+        assert!(!rights.can_read("images/notes.txt"));
+        assert!(!rights.can_read("levels/old/start.nw"));
+        assert!(!rights.can_read("levels/../config/serveroptions.txt"));
+        assert!(!rights.can_read("accounts/admin.txt"));
+    }
+
+    #[test]
+    fn test_folder_access_combines_rights() {
+        let rights = FolderRights::parse(&["r levels/*.nw", "w levels/*.txt", "- config/*"]);
+
+        assert_eq!(rights.folder_access("levels/"), Some(FolderAccess { read: true, write: true }));
+        assert_eq!(rights.folder_access("config"), Some(FolderAccess::NONE));
+        assert_eq!(rights.folder_access("accounts"), None);
+    }
+}
@@ -0,0 +1,227 @@
+//! Account import from other server distributions
+//!
+//! # Purpose
+//! Converts account files written by other GServer forks into the GRACC001
+//! files [`AccountLoader`](crate::AccountLoader) reads and writes. The forks
+//! differ in:
+//!
+//! - The header: `GRACC001`, another `GRACC` version, or none at all
+//! - Key names: long names from older servers (`RUPEES`, `SWORDPOWER`,
+//!   `ADMINRIGHTS`) and lowercase keys
+//! - Lists: one `WEAPONS bomb,bow` line instead of a `WEAPON` line each
+//!
+//! Lines may come in any order. Fields without an equivalent are kept, like
+//! unknown fields on a normal load, and reported along with values that don't
+//! parse.
+
+use crate::account::Account;
+use crate::error::{AccountError, Result};
+use crate::loader::AccountLoader;
+use std::fmt;
+
+/// Key names used by other servers, and ours
+const ALIASES: &[(&str, &str)] = &[
+    ("NICKNAME", "NICK"),
+    ("CURLEVEL", "LEVEL"),
+    ("PLAYERLEVEL", "LEVEL"),
+    ("MAXPOWER", "MAXHP"),
+    ("POWER", "HP"),
+    ("RUPEES", "GRALATS"),
+    ("DARTS", "ARROWS"),
+    ("GLOVEPOWER", "GLOVEP"),
+    ("SWORDPOWER", "SWORDP"),
+    ("SHIELDPOWER", "SHIELDP"),
+    ("BOMBPOWER", "BOMBP"),
+    ("BOWPOWER", "BOWP"),
+    ("BOWIMG", "BOW"),
+    ("HEADIMG", "HEAD"),
+    ("BODYIMG", "BODY"),
+    ("SWORDIMG", "SWORD"),
+    ("SHIELDIMG", "SHIELD"),
+    ("MAGIC", "MP"),
+    ("MAGICPOINTS", "MP"),
+    ("ALIGNMENT", "AP"),
+    ("ALIGNMENTCOUNTER", "APCOUNTER"),
+    ("ONLINESECS", "ONSECS"),
+    ("ONLINETIME", "ONSECS"),
+    ("LASTIP", "IP"),
+    ("ADMINRIGHTS", "LOCALRIGHTS"),
+    ("ADMINIP", "IPRANGE"),
+];
+
+/// GRACC001 fields with a string value
+const TEXT_FIELDS: &[&str] = &[
+    "NAME", "NICK", "COMMUNITYNAME", "LEVEL", "ANI", "BOW", "HEAD", "BODY", "SWORD", "SHIELD", "COLORS", "IP",
+    "LANGUAGE", "BANREASON", "BANLENGTH", "BANISSUER", "COMMENTS", "EMAIL", "IPRANGE", "WEAPON", "FOLDERRIGHT", "LASTFOLDER",
+    "FLAG", "CHEST", "FRIEND", "IGNORE", "PASSWORD", "PLATFORM", "PCID", "HDDID",
+];
+
+/// GRACC001 fields with a numeric value
+const NUMBER_FIELDS: &[&str] = &[
+    "X", "Y", "Z", "MAXHP", "HP", "SPRITE", "GRALATS", "ARROWS", "BOMBS", "GLOVEP", "SWORDP", "SHIELDP", "BOMBP",
+    "BOWP", "STATUS", "MP", "AP", "APCOUNTER", "ONSECS", "KILLS", "DEATHS", "RATING", "DEVIATION", "LASTSPARTIME",
+    "BANNED", "BANNEDUNTIL", "LOCALRIGHTS", "LOADONLY", "PMFRIENDSONLY", "MUTEDUNTIL", "JAILEDUNTIL",
+];
+
+/// A line of an imported file that didn't map cleanly
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportIssue {
+    /// Line number in the source file (1-based)
+    pub line: usize,
+    /// Key as written in the source file
+    pub key: String,
+    /// What was wrong
+    pub kind: ImportIssueKind,
+}
+
+/// Kind of [`ImportIssue`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportIssueKind {
+    /// No GRACC001 equivalent; kept as an extra field
+    UnknownField,
+    /// A numeric field whose value isn't a number; the default is kept
+    InvalidValue(String),
+}
+
+impl fmt::Display for ImportIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ImportIssueKind::UnknownField => write!(f, "line {}: {} has no equivalent, kept as is", self.line, self.key),
+            ImportIssueKind::InvalidValue(value) => {
+                write!(f, "line {}: {} '{}' is not a number, left at default", self.line, self.key, value)
+            }
+        }
+    }
+}
+
+/// Result of importing one account file
+#[derive(Debug, Clone)]
+pub struct AccountImport {
+    /// The converted account
+    pub account: Account,
+    /// Header of the source file (`None` for files without one)
+    pub header: Option<String>,
+    /// Keys that were renamed, as `(source, ours)`
+    pub renamed: Vec<(String, String)>,
+    /// Lines that didn't map cleanly
+    pub issues: Vec<ImportIssue>,
+}
+
+/// Convert an account file from another server
+///
+/// # Arguments
+/// * `name` - Account name to use if the file has no `NAME` line (usually the file stem)
+/// * `content` - The file's text
+///
+/// # Returns
+/// The account and what was changed on the way
+pub fn import_account(name: &str, content: &str) -> Result<AccountImport> {
+    let mut lines = content.lines().enumerate().peekable();
+    let header = match lines.peek() {
+        Some((_, first)) if first.trim().starts_with("GRACC") => {
+            let header = first.trim().to_string();
+            lines.next();
+            Some(header)
+        }
+        Some(_) => None,
+        None => return Err(AccountError::InvalidFormat("Empty file".to_string())),
+    };
+
+    let mut import = AccountImport {
+        account: Account { name: name.to_string(), ..Default::default() },
+        header,
+        renamed: Vec::new(),
+        issues: Vec::new(),
+    };
+
+    for (index, line) in lines {
+        let line = line.trim();
+        let Some((source_key, value)) = line.split_once(char::is_whitespace) else {
+            continue;
+        };
+        let value = value.trim();
+        let key = canonical_key(source_key);
+        let ours = if key == "WEAPONS" { "WEAPON" } else { key.as_str() };
+        if ours != source_key && !import.renamed.iter().any(|(from, _)| from == source_key) {
+            import.renamed.push((source_key.to_string(), ours.to_string()));
+        }
+
+        let issue = |kind| ImportIssue { line: index + 1, key: source_key.to_string(), kind };
+        if key == "WEAPONS" {
+            for weapon in value.split(',').map(str::trim).filter(|w| !w.is_empty()) {
+                AccountLoader::parse_account_field(&mut import.account, "WEAPON", weapon);
+            }
+            continue;
+        }
+        if NUMBER_FIELDS.contains(&key.as_str()) && value.parse::<f64>().is_err() {
+            import.issues.push(issue(ImportIssueKind::InvalidValue(value.to_string())));
+            continue;
+        }
+        if !is_known_field(&key) {
+            import.issues.push(issue(ImportIssueKind::UnknownField));
+        }
+        AccountLoader::parse_account_field(&mut import.account, &key, value);
+    }
+
+    if import.account.nick.is_empty() {
+        import.account.nick = import.account.name.clone();
+    }
+    Ok(import)
+}
+
+/// Uppercase a key and replace another server's name for it with ours
+fn canonical_key(key: &str) -> String {
+    let key = key.to_uppercase();
+    match ALIASES.iter().find(|(alias, _)| *alias == key) {
+        Some((_, ours)) => ours.to_string(),
+        None => key,
+    }
+}
+
+/// Check if a key is a GRACC001 field (`ATTR1`-`ATTR30` are kept as extras but are ours too)
+fn is_known_field(key: &str) -> bool {
+    let attr = key.strip_prefix("ATTR").and_then(|n| n.parse::<u8>().ok());
+    TEXT_FIELDS.contains(&key) || NUMBER_FIELDS.contains(&key) || matches!(attr, Some(1..=30))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_renames_and_reports() {
+        let content = "GRACC002\r\nnickname Ally\r\nRUPEES 250\r\nWEAPONS bomb, bow\r\nX abc\r\n\
+                       ATTR3 hat0.png\r\nGUILDRANK 4\r\nlevel onlinestartlocal.nw\r\n";
+        let import = import_account("Alice", content).unwrap();
+
+        assert_eq!(import.header.as_deref(), Some("GRACC002"));
+        assert_eq!(import.account.name, "Alice");
+        assert_eq!(import.account.nick, "Ally");
+        assert_eq!(import.account.gralats, 250);
+        assert_eq!(import.account.level, "onlinestartlocal.nw");
+        assert!(import.account.has_weapon("bomb") && import.account.has_weapon("bow"));
+        assert_eq!(import.account.x, Account::default().x);
+        assert_eq!(import.account.extra.get("GUILDRANK").map(String::as_str), Some("4"));
+        assert_eq!(import.renamed, [
+            ("nickname".to_string(), "NICK".to_string()),
+            ("RUPEES".to_string(), "GRALATS".to_string()),
+            ("WEAPONS".to_string(), "WEAPON".to_string()),
+            ("level".to_string(), "LEVEL".to_string()),
+        ]);
+        assert_eq!(import.issues.iter().map(ToString::to_string).collect::<Vec<_>>(), [
+            "line 5: X 'abc' is not a number, left at default",
+            "line 7: GUILDRANK has no equivalent, kept as is",
+        ]);
+    }
+
+    #[test]
+    fn test_import_without_header() {
+        let import = import_account("bob", "NAME Bob\nADMINRIGHTS 65535\n").unwrap();
+        assert_eq!(import.header, None);
+        assert_eq!((import.account.name.as_str(), import.account.nick.as_str()), ("Bob", "Bob"));
+        assert!(import.account.is_staff());
+        assert!(import.issues.is_empty());
+
+        assert!(import_account("empty", "").is_err());
+    }
+}
@@ -5,8 +5,12 @@
 //! ## Features
 //!
 //! - Account file loading from disk
+//! - Account saving (GRACC001 format)
+//! - Account import from other server distributions
 //! - Staff rights validation
 //! - Player permissions
+//! - Per-account folder rights
+//! - Hashed passwords for local authentication
 //! - Default account fallback
 //!
 //! ## Usage
@@ -25,11 +29,19 @@
 
 mod account;
 mod error;
+mod folder_rights;
+mod import;
 mod loader;
+mod password;
 
 pub use account::{
-    Account, PlayerPermissions,
-    PLPERM_WARPTO, PLPERM_DISCONNECT, PLPERM_ANYRIGHT, PLPERM_INVISIBLE
+    Account, FlagStore, FlagValue, PlayerPermissions,
+    PLPERM_WARPTO, PLPERM_DISCONNECT, PLPERM_ANYRIGHT, PLPERM_INVISIBLE, PLPERM_ADMINMSG, PLPERM_SETRIGHTS,
+    PLPERM_BAN, PLPERM_SETCOMMENTS, PLPERM_SETATTRIBUTES, PLPERM_NPCCONTROL, PLPERM_UPDATELEVEL,
+    PLPERM_SETSERVEROPTIONS, PLPERM_VIEWATTRIBUTES
 };
 pub use error::{AccountError, Result};
+pub use folder_rights::{FolderAccess, FolderRight, FolderRights};
+pub use import::{import_account, AccountImport, ImportIssue, ImportIssueKind};
 pub use loader::AccountLoader;
+pub use password::{check_password, hash_password, PasswordCheck};
@@ -1,6 +1,6 @@
 //! Account file loading
 
-use super::{account::Account, error::{AccountError, Result}};
+use super::{account::{Account, FlagValue}, error::{AccountError, Result}};
 use std::path::{Path, PathBuf};
 use std::fs;
 use tracing::{debug, warn};
@@ -57,18 +57,189 @@ impl AccountLoader {
         let account_path = self.find_account_file(account_name)?;
 
         // Load and parse the file
-        let account = self.parse_account_file(&account_path)?;
+        let mut account = self.parse_account_file(&account_path)?;
+
+        // A new account starts as a copy of the default account
+        // C++: Account::loadAccount copies defaultaccount.txt, then sets the account name
+        if self.find_existing_account_file(account_name).is_none() {
+            account.name = account_name.to_string();
+        }
 
         debug!("Loaded account: {} from {:?}", account.name, account_path);
         Ok(account)
     }
 
-    /// Find account file (case-insensitive search)
-    fn find_account_file(&self, account_name: &str) -> Result<PathBuf> {
+    /// Save an account to disk
+    ///
+    /// # Arguments
+    /// * `account` - The account to save
+    ///
+    /// # Behavior
+    /// Writes `accounts/ACCOUNTNAME.txt`, reusing the existing file's casing if
+    /// one exists. The file is written to a temporary path first and then
+    /// renamed, so a crash mid-save never leaves a truncated account.
+    ///
+    /// # C++ Equivalence
+    /// Matches `Account::saveAccount()` in Account.cpp
+    pub fn save(&self, account: &Account) -> Result<()> {
+        if account.name.is_empty() || account.name.eq_ignore_ascii_case("defaultaccount") {
+            return Err(AccountError::InvalidData(
+                format!("Refusing to save account with name '{}'", account.name)
+            ));
+        }
+
+        fs::create_dir_all(&self.accounts_dir)?;
+
+        let path = self.find_existing_account_file(&account.name)
+            .unwrap_or_else(|| self.accounts_dir.join(format!("{}.txt", account.name)));
+        let temp_path = path.with_extension("txt.tmp");
+
+        fs::write(&temp_path, Self::serialize_account(account))?;
+        fs::rename(&temp_path, &path)?;
+
+        debug!("Saved account: {} to {:?}", account.name, path);
+        Ok(())
+    }
+
+    /// Serialize an account to the GRACC001 text format
+    pub fn serialize_account(account: &Account) -> String {
+        use std::fmt::Write;
+
+        // CRLF line endings, like the files written by the C++ server
+        let mut out = String::from("GRACC001\r\n");
+        let mut field = |key: &str, value: &dyn std::fmt::Display| {
+            let _ = write!(out, "{} {}\r\n", key, value);
+        };
+
+        field("NAME", &account.name);
+        field("NICK", &account.nick);
+        if !account.community_name.is_empty() {
+            field("COMMUNITYNAME", &account.community_name);
+        }
+        field("LEVEL", &account.level);
+        field("X", &account.x);
+        field("Y", &account.y);
+        field("Z", &account.z);
+        field("MAXHP", &account.max_hp);
+        field("HP", &account.hp);
+        field("ANI", &account.ani);
+        field("SPRITE", &account.sprite);
+        field("GRALATS", &account.gralats);
+        field("ARROWS", &account.arrows);
+        field("BOMBS", &account.bombs);
+        field("GLOVEP", &account.glove_power);
+        field("SWORDP", &account.sword_power);
+        field("SHIELDP", &account.shield_power);
+        field("BOMBP", &account.bomb_power);
+        field("BOWP", &account.bow_power);
+        field("BOW", &account.bow);
+        field("HEAD", &account.head);
+        field("BODY", &account.body);
+        field("SWORD", &account.sword);
+        field("SHIELD", &account.shield);
+        field("COLORS", &account.colors);
+        field("STATUS", &account.status);
+        field("MP", &account.mp);
+        field("AP", &account.ap);
+        field("APCOUNTER", &account.ap_counter);
+        field("ONSECS", &account.onsecs);
+        field("IP", &account.ip);
+        field("LANGUAGE", &account.language);
+        field("KILLS", &account.kills);
+        field("DEATHS", &account.deaths);
+        field("RATING", &account.rating);
+        field("DEVIATION", &account.deviation);
+        field("LASTSPARTIME", &account.last_spar_time);
+        field("BANNED", &account.banned);
+        field("BANREASON", &account.ban_reason);
+        field("BANLENGTH", &account.ban_length);
+        if !account.ban_issuer.is_empty() {
+            field("BANISSUER", &account.ban_issuer);
+        }
+        if account.banned_until != 0 {
+            field("BANNEDUNTIL", &account.banned_until);
+        }
+        if !account.platform.is_empty() {
+            field("PLATFORM", &account.platform);
+        }
+        if !account.pc_id.is_empty() {
+            field("PCID", &account.pc_id);
+        }
+        if !account.hdd_id.is_empty() {
+            field("HDDID", &account.hdd_id);
+        }
+        field("COMMENTS", &account.comments);
+        field("EMAIL", &account.email);
+        if !account.password.is_empty() {
+            field("PASSWORD", &account.password);
+        }
+        field("LOCALRIGHTS", &account.local_rights);
+        field("IPRANGE", &account.ip_range);
+        field("LOADONLY", &account.load_only);
+        for weapon in &account.weapons {
+            field("WEAPON", weapon);
+        }
+        for right in &account.folder_rights {
+            field("FOLDERRIGHT", right);
+        }
+        for friend in &account.friends {
+            field("FRIEND", friend);
+        }
+        for ignored in &account.ignored {
+            field("IGNORE", ignored);
+        }
+        if account.pm_friends_only {
+            field("PMFRIENDSONLY", &1);
+        }
+        if account.muted_until != 0 {
+            field("MUTEDUNTIL", &account.muted_until);
+        }
+        if account.jailed_until != 0 {
+            field("JAILEDUNTIL", &account.jailed_until);
+        }
+        field("LASTFOLDER", &account.last_folder);
+
+        // Sorted so that saving the same account twice gives the same file
+        let mut chests: Vec<_> = account.saved_chests.iter().collect();
+        chests.sort_by(|a, b| a.0.cmp(b.0));
+        for (level, positions) in chests {
+            for (x, y) in positions {
+                field("CHEST", &format!("{}:{}:{}", x, y, level));
+            }
+        }
+
+        let mut flags: Vec<_> = account.flags.flags.iter().collect();
+        flags.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, value) in flags {
+            let value = value.as_str();
+            if value.is_empty() {
+                field("FLAG", name);
+            } else {
+                field("FLAG", &format!("{}={}", name, value));
+            }
+        }
+
+        let mut extra: Vec<_> = account.extra.iter().collect();
+        extra.sort_by(|a, b| a.0.cmp(b.0));
+        for (key, value) in extra {
+            field(key, value);
+        }
+
+        out
+    }
+
+    /// Check if an account file exists (case-insensitive)
+    pub fn exists(&self, account_name: &str) -> bool {
+        self.find_existing_account_file(account_name).is_some()
+    }
+
+    /// Find an existing account file (exact, then case-insensitive), without
+    /// falling back to the default account
+    fn find_existing_account_file(&self, account_name: &str) -> Option<PathBuf> {
         // First, try exact match
         let exact_path = self.accounts_dir.join(format!("{}.txt", account_name));
         if exact_path.exists() {
-            return Ok(exact_path);
+            return Some(exact_path);
         }
 
         // Case-insensitive search
@@ -81,12 +252,21 @@ impl AccountLoader {
                         .unwrap_or("");
 
                     if filename.eq_ignore_ascii_case(account_name) {
-                        return Ok(path);
+                        return Some(path);
                     }
                 }
             }
         }
 
+        None
+    }
+
+    /// Find account file (case-insensitive search, falling back to the default account)
+    fn find_account_file(&self, account_name: &str) -> Result<PathBuf> {
+        if let Some(path) = self.find_existing_account_file(account_name) {
+            return Ok(path);
+        }
+
         // Fall back to default account
         let default_path = self.accounts_dir.join("defaultaccount.txt");
         if default_path.exists() {
@@ -133,7 +313,7 @@ impl AccountLoader {
             let key = parts[0].trim();
             let value = parts[1].trim();
 
-            self.parse_account_field(&mut account, key, value);
+            Self::parse_account_field(&mut account, key, value);
         }
 
         // Set nick from name if not present
@@ -145,7 +325,7 @@ impl AccountLoader {
     }
 
     /// Parse a single account field
-    fn parse_account_field(&self, account: &mut Account, key: &str, value: &str) {
+    pub(crate) fn parse_account_field(account: &mut Account, key: &str, value: &str) {
         match key {
             "NAME" => account.name = value.to_string(),
             "NICK" => account.nick = value.to_string(),
@@ -187,8 +367,14 @@ impl AccountLoader {
             "BANNED" => account.banned = value.parse().unwrap_or(account.banned),
             "BANREASON" => account.ban_reason = value.to_string(),
             "BANLENGTH" => account.ban_length = value.to_string(),
+            "BANISSUER" => account.ban_issuer = value.to_string(),
+            "BANNEDUNTIL" => account.banned_until = value.parse().unwrap_or(0),
+            "PLATFORM" => account.platform = value.to_string(),
+            "PCID" => account.pc_id = value.to_string(),
+            "HDDID" => account.hdd_id = value.to_string(),
             "COMMENTS" => account.comments = value.to_string(),
             "EMAIL" => account.email = value.to_string(),
+            "PASSWORD" => account.password = value.to_string(),
             "LOCALRIGHTS" => account.local_rights = value.parse().unwrap_or(account.local_rights),
             "IPRANGE" => account.ip_range = value.to_string(),
             "LOADONLY" => account.load_only = value.parse().unwrap_or(account.load_only),
@@ -204,6 +390,29 @@ impl AccountLoader {
             "LASTFOLDER" => {
                 account.last_folder = value.to_string();
             }
+            "FRIEND" => {
+                account.add_friend(value);
+            }
+            "IGNORE" => {
+                account.ignore(value);
+            }
+            "PMFRIENDSONLY" => account.pm_friends_only = value == "1",
+            "MUTEDUNTIL" => account.muted_until = value.parse().unwrap_or(0),
+            "JAILEDUNTIL" => account.jailed_until = value.parse().unwrap_or(0),
+            "FLAG" => {
+                // Format: "name=value" or "name"
+                let (name, flag_value) = value.split_once('=').unwrap_or((value, ""));
+                account.set_flag(name, FlagValue::String(flag_value.to_string()));
+            }
+            "CHEST" => {
+                // Format: "x:y:levelname"
+                let mut parts = value.splitn(3, ':');
+                if let (Some(x), Some(y), Some(level)) = (parts.next(), parts.next(), parts.next()) {
+                    if let (Ok(x), Ok(y)) = (x.parse(), y.parse()) {
+                        account.add_chest(level, x, y);
+                    }
+                }
+            }
             _ => {
                 // Store unknown fields in extra map
                 account.extra.insert(key.to_string(), value.to_string());
@@ -215,6 +424,7 @@ impl AccountLoader {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::account::PLPERM_WARPTO;
     use std::fs::{self, File};
     use std::io::Write;
 
@@ -278,4 +488,56 @@ LOCALRIGHTS 0
         assert_eq!(account.nick, "Default");
         assert!(!account.is_staff());
     }
+
+    #[test]
+    fn test_save_roundtrip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let loader = AccountLoader::new(temp_dir.path());
+
+        let mut account = Account {
+            name: "SavedPlayer".to_string(),
+            nick: "Saved".to_string(),
+            gralats: 42,
+            ..Default::default()
+        };
+        account.add_weapon("bomb".to_string());
+        account.add_chest("onlinestartlocal.nw", 10, 12);
+        account.set_flag("quest1", FlagValue::String("done".to_string()));
+        account.extra.insert("ATTR1".to_string(), "hat0.png".to_string());
+        account.add_friend("Buddy");
+        account.ignore("Spammer");
+        account.pm_friends_only = true;
+        account.jailed_until = 5000;
+        account.banned = 1;
+        account.ban_issuer = "Manager".to_string();
+        account.banned_until = 6000;
+        account.pc_id = "02e2465a2bf38f8a".to_string();
+        account.set_password("secret").unwrap();
+
+        loader.save(&account).unwrap();
+        let loaded = loader.load("savedplayer").unwrap();
+
+        assert_eq!(loaded.name, "SavedPlayer");
+        assert_eq!(loaded.gralats, 42);
+        assert!(loaded.has_weapon("bomb"));
+        assert!(loaded.has_chest("onlinestartlocal.nw", 10, 12));
+        assert!(loaded.has_flag("quest1"));
+        assert_eq!(loaded.extra.get("ATTR1").map(String::as_str), Some("hat0.png"));
+        assert!(loaded.is_friend("buddy") && loaded.is_ignoring("SPAMMER") && loaded.pm_friends_only);
+        assert!(loaded.accepts_pm_from("Buddy") && !loaded.accepts_pm_from("stranger"));
+        assert!(loaded.is_jailed_at(4999) && !loaded.is_jailed_at(5000) && !loaded.is_muted_at(0));
+        assert!(loaded.is_banned_at(5999) && !loaded.is_banned_at(6000) && loaded.ban_issuer == "Manager");
+        assert_eq!((loaded.pc_id.as_str(), loaded.hdd_id.as_str()), ("02e2465a2bf38f8a", ""));
+        assert_eq!(crate::check_password(&loaded.password, "secret"), crate::PasswordCheck::Valid);
+        assert!(!temp_dir.path().join("accounts/SavedPlayer.txt.tmp").exists());
+    }
+
+    #[test]
+    fn test_save_rejects_default_account() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let loader = AccountLoader::new(temp_dir.path());
+        let account = Account { name: "defaultaccount".to_string(), ..Default::default() };
+
+        assert!(loader.save(&account).is_err());
+    }
 }
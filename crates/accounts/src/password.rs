@@ -0,0 +1,88 @@
+//! # Account Passwords
+//!
+//! With `localauth` on, the server checks login passwords against the
+//! PASSWORD field of the account file instead of trusting the list server.
+//! Passwords are stored as salted argon2id hashes in PHC format:
+//!
+//! ```text
+//! PASSWORD $argon2id$v=19$m=19456,t=2,p=1${salt}${hash}
+//! ```
+//!
+//! Files written by hand or imported from other servers may hold the
+//! password in plaintext. It's still accepted, and the login that uses it
+//! replaces it with a hash ([`PasswordCheck::Legacy`]).
+
+use crate::error::{AccountError, Result};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+
+/// PHC prefix of the hashes this module writes
+const HASH_PREFIX: &str = "$argon2";
+
+/// Outcome of checking a login password
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordCheck {
+    /// Matches the stored hash
+    Valid,
+    /// Matches a plaintext password, which should be rehashed now
+    Legacy,
+    /// Wrong password, or the account has none
+    Invalid,
+}
+
+/// Hash a password with a new random salt
+///
+/// # Errors
+/// `AccountError::InvalidData` if argon2 rejects the input
+pub fn hash_password(password: &str) -> Result<String> {
+    let salt = SaltString::generate(&mut rand::rngs::OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| AccountError::InvalidData(format!("Failed to hash password: {}", e)))
+}
+
+/// Check if a stored password is a hash rather than plaintext
+pub fn is_hashed(stored: &str) -> bool {
+    stored.starts_with(HASH_PREFIX)
+}
+
+/// Check a login password against the stored one
+///
+/// # Arguments
+/// * `stored` - PASSWORD field of the account (hash or legacy plaintext)
+/// * `given` - Password sent with the login
+pub fn check_password(stored: &str, given: &str) -> PasswordCheck {
+    if stored.is_empty() {
+        return PasswordCheck::Invalid;
+    }
+    if is_hashed(stored) {
+        let valid = PasswordHash::new(stored)
+            .is_ok_and(|hash| Argon2::default().verify_password(given.as_bytes(), &hash).is_ok());
+        return if valid { PasswordCheck::Valid } else { PasswordCheck::Invalid };
+    }
+
+    // Don't let the comparison time tell how much of the password matched
+    let same = stored.len() == given.len()
+        && stored.bytes().zip(given.bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0;
+    if same { PasswordCheck::Legacy } else { PasswordCheck::Invalid }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_and_check() {
+        let hash = hash_password("hunter2").unwrap();
+        assert!(is_hashed(&hash));
+        assert_ne!(hash, hash_password("hunter2").unwrap());
+        assert_eq!(check_password(&hash, "hunter2"), PasswordCheck::Valid);
+        assert_eq!(check_password(&hash, "hunter3"), PasswordCheck::Invalid);
+
+        assert_eq!(check_password("hunter2", "hunter2"), PasswordCheck::Legacy);
+        assert_eq!(check_password("hunter2", "hunter"), PasswordCheck::Invalid);
+        assert_eq!(check_password("", ""), PasswordCheck::Invalid);
+        assert_eq!(check_password("$argon2id$garbage", "x"), PasswordCheck::Invalid);
+    }
+}
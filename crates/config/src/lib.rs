@@ -2,9 +2,15 @@
 //!
 //! Loads server configuration from all config files, just like the C++ version.
 
-use std::collections::HashSet;
+pub mod translations;
+pub mod wordfilter;
+
+pub use translations::Translations;
+pub use wordfilter::{FilterCheck, FilterResult, WordFilter};
+
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::path::Path;
 
 /// Complete server configuration from all config files
@@ -28,6 +34,22 @@ pub struct ServerConfig {
     pub server_port: u16,
     /// Server interface (from "serverinterface" option)
     pub server_interface: String,
+    /// Port of the TLS listener (from "tlsport" option, default: 0 = no TLS)
+    pub tls_port: u16,
+    /// TLS certificate chain, relative to the server folder (from "tlscert" option, default: tls/cert.pem)
+    pub tls_cert: String,
+    /// TLS private key, relative to the server folder (from "tlskey" option, default: tls/key.pem)
+    pub tls_key: String,
+    /// Connections start with a PROXY v2 header from a load balancer (from "proxyprotocol" option, default: false)
+    pub proxy_protocol: bool,
+    /// Proxies whose PROXY headers are used, empty for none (from "proxytrusted" option, comma-separated)
+    pub proxy_trusted: Vec<IpAddr>,
+    /// MaxMind country database, relative to the server folder, empty for none (from "geoipdb" option)
+    pub geoip_db: String,
+    /// Countries allowed to log in, empty for all (from "geoipallow" option, comma-separated ISO codes)
+    pub geoip_allow: Vec<String>,
+    /// Countries refused at login (from "geoipdeny" option, comma-separated ISO codes)
+    pub geoip_deny: Vec<String>,
     /// Local IP (from "localip" option)
     pub local_ip: String,
     /// UPnP enabled (from "upnp" option)
@@ -38,6 +60,12 @@ pub struct ServerConfig {
     pub list_ip: String,
     /// List server port (from "listport" option)
     pub list_port: u16,
+    /// More listservers to register with at the same time, as `host:port`
+    /// (from "listservers" option, comma-separated, default: none)
+    pub list_servers: Vec<String>,
+    /// Failed listserver registrations in a row after which staff chat is
+    /// alerted (from "listfailurealert" option, default: 3, 0 = no alerts)
+    pub list_failure_alert: u32,
 
     // Server type
     /// Only staff allowed (from "onlystaff" option)
@@ -70,6 +98,8 @@ pub struct ServerConfig {
     pub sword_limit: u8,
     /// Shield limit (from "shieldlimit" option)
     pub shield_limit: u8,
+    /// Maximum nickname length, excluding the guild tag (from "maxnicklength" option, default: 223)
+    pub max_nick_length: usize,
 
     // Script settings
     /// GS2 default (from "gs2default" option)
@@ -80,6 +110,175 @@ pub struct ServerConfig {
     pub serverside: bool,
     /// Save levels (from "savelevels" option)
     pub save_levels: bool,
+    /// KB the values of one serverside GS2 run (the console's `:gs2`) or Lua
+    /// event may hold (from "scriptmemorylimit" option, default: 4096,
+    /// 0 = unlimited)
+    pub script_memory_limit: usize,
+    /// Lua instructions one event of a Lua script may run (from
+    /// "luainstructionlimit" option, default: 1000000, 0 = unlimited)
+    pub lua_instruction_limit: u64,
+    /// Instructions one GS2 run may execute (from "gs2instructionlimit"
+    /// option, default: 1000000, 0 = unlimited)
+    pub gs2_instruction_limit: u64,
+    /// Previous versions kept of each weapon and class edited from NC or RC
+    /// (from "scripthistory" option, default: 10, 0 = none)
+    pub script_history: usize,
+
+    // Game loop
+    /// Game ticks per second (from "tickrate" option, default: 20)
+    pub tick_rate: u32,
+    /// Seconds between autosaves, 0 to only save on shutdown (from
+    /// "autosaveinterval" option, default: 300)
+    pub autosave_interval: u64,
+    /// Tiles within which players get each other's props, 0 for the whole
+    /// level or gmap (from "interestrange" option, default: 64)
+    pub interest_range: u32,
+    /// Extra tiles before a player in range goes out of range again (from "interesthysteresis" option, default: 8)
+    pub interest_hysteresis: u32,
+    /// Seconds between checks of scripts/ for class files edited on disk, 0
+    /// to only pick up NC edits (from "classcheckinterval" option, default: 5)
+    pub class_check_interval: u64,
+    /// Seconds between checks of the loaded levels for files edited on disk,
+    /// 0 for RC reloads only (from "levelcheckinterval" option, default: 5)
+    pub level_check_interval: u64,
+    /// Milliseconds a packet handler may take before a warning is logged, 0
+    /// for no warnings (from "slowhandlerms" option, default: 5)
+    pub slow_handler_ms: u64,
+    /// Seconds between logs of the busiest packet handlers, 0 to turn them
+    /// off (from "handlersummaryinterval" option, default: 300)
+    pub handler_summary_interval: u64,
+    /// Seconds between aggregations of the player statistics into
+    /// leaderboards, 0 to not keep statistics (from "statsinterval" option,
+    /// default: 60)
+    pub stats_interval: u64,
+    /// Gralats an account may gain within `economy_alert_window` before staff
+    /// chat is alerted, 0 for no alerts (from "economyalertgain" option,
+    /// default: 10000)
+    pub economy_alert_gain: u64,
+    /// Seconds the gains of `economy_alert_gain` are summed over (from
+    /// "economyalertwindow" option, default: 60)
+    pub economy_alert_window: u64,
+    /// Level jailed players are kept on, empty to turn jailing off (from
+    /// "jaillevel" option, default: empty)
+    pub jail_level: String,
+    /// Seconds players are warned with admin messages before a shutdown, 0
+    /// to shut down at once (from "shutdowncountdown" option, default: 0)
+    pub shutdown_countdown: u64,
+    /// Position updates sent per second of a moving serverside NPC (from
+    /// "npcmoverate" option, default: 10)
+    pub npc_move_rate: u32,
+
+    // Idle
+    /// Seconds without any data before a connection is dropped (from "protocoltimeout" option, default: 300)
+    pub protocol_timeout: u64,
+    /// Minutes without moving or chatting before a player is marked away (from "afkminutes" option, default: 5, 0 = never)
+    pub afk_minutes: u64,
+    /// Minutes without moving or chatting before a non-staff player is disconnected (from "idledisconnectminutes" option, default: 20, 0 = never)
+    pub idle_disconnect_minutes: u64,
+    /// Seconds of silence before the server sends a keepalive (from "keepaliveinterval" option, default: 30, 0 = never)
+    pub keepalive_interval: u64,
+    /// Seconds a dropped player's session is held for a reconnect to resume
+    /// (from "reconnectgrace" option, default: 0 = never)
+    pub reconnect_grace: u64,
+    /// What happens when a player logs in on an account that is already in use
+    /// (from "duplicatelogin" option: kickold or rejectnew, default: kickold)
+    pub duplicate_login: DuplicateLogin,
+    /// Check login passwords against the account files instead of leaving it to
+    /// the list server (from "localauth" option, default: false)
+    pub local_auth: bool,
+    /// Have the list server verify every player login (from "verifylogins" option, default: false)
+    pub verify_logins: bool,
+    /// Hours a verified account may still log in while the list server is unreachable
+    /// (from "verifycachehours" option, default: 72, 0 = never)
+    pub verify_cache_hours: u64,
+    /// Days the IPs of each account are remembered for alt detection
+    /// (from "iphistorydays" option, default: 90, 0 = not recorded)
+    pub ip_history_days: u64,
+    /// Hardware ids in login identities that are refused (from "bannedhardware" option)
+    pub banned_hardware: Vec<String>,
+    /// Accounts whose sessions are recorded to `recordings/` for replay
+    /// (from "recordsessions" option, default: none)
+    pub record_sessions: Vec<String>,
+    /// Seconds a login held for staff approval waits before it's refused
+    /// (from "loginapprovaltimeout" option, default: 120)
+    pub login_approval_timeout: u64,
+    /// Tell RCs when a connection's handler panics (from "panicnotifyrc" option, default: true)
+    pub panic_notify_rc: bool,
+    /// Seconds the tick loop, listserver client or accept loop may go without
+    /// a heartbeat (from "watchdogtimeout" option, default: 30, 0 = off)
+    pub watchdog_timeout: u64,
+    /// What a stall does (from "watchdogaction" option: log, restart)
+    pub watchdog_action: String,
+
+    // Outbound backpressure
+    /// Queued bytes above which cosmetic packets are dropped (from "outboundsoftlimit" option, default: 262144)
+    pub outbound_soft_limit: usize,
+    /// Queued bytes at which the client is disconnected (from "outboundhardlimit" option, default: 1048576)
+    pub outbound_hard_limit: usize,
+    /// Seconds a bundle write may block before the client is disconnected (from "outboundstalltimeout" option, default: 30, 0 = never)
+    pub outbound_stall_timeout: u64,
+    /// Seconds a client may stall in the middle of a bundle before it's disconnected (from "bundlereadtimeout" option, default: 10, 0 = never)
+    pub bundle_read_timeout: u64,
+    /// Longest bundle accepted from a player client (from "maxbundleplayer" option, default: 65535)
+    pub max_bundle_player: usize,
+    /// Longest bundle accepted from an RC (from "maxbundlerc" option, default: 65535)
+    pub max_bundle_rc: usize,
+    /// Largest file an RC may upload, sent at RC login and lowered to `maxbundlerc` (from "rcmaxuploadsize" option, default: 20971520)
+    pub rc_max_upload_size: u64,
+
+    // Bandwidth shaping
+    /// KB/s of gameplay packets per connection (from "bandwidthlimit" option, default: 0 = unlimited)
+    pub bandwidth_limit: u64,
+    /// KB/s of file transfers per connection (from "filebandwidthlimit" option, default: 0 = unlimited)
+    pub file_bandwidth_limit: u64,
+    /// KB/s of gameplay packets for all connections (from "serverbandwidthlimit" option, default: 0 = unlimited)
+    pub server_bandwidth_limit: u64,
+    /// KB/s of file transfers for all connections (from "serverfilebandwidthlimit" option, default: 0 = unlimited)
+    pub server_file_bandwidth_limit: u64,
+
+    // Integrity
+    /// Disconnect clients whose PLI_PACKETCOUNT doesn't match (from "packetcountdisconnect" option)
+    pub packet_count_disconnect: bool,
+    /// Response to PLI_TAMPERCHECK (from "tamperaction" option: ignore, log, warnrc, disconnect)
+    pub tamper_action: String,
+
+    // Compression
+    /// Bundle compression levels and GEN_5 thresholds
+    pub compression: CompressionConfig,
+
+    // Webhook
+    /// Event webhook (Discord-compatible)
+    pub webhook: WebhookConfig,
+
+    // Logging
+    /// Log levels, log files and output format
+    pub logging: LoggingConfig,
+
+    // Privacy
+    /// What chat is written to the chat logs, and for how long
+    pub privacy: PrivacyConfig,
+
+    // Chat commands
+    /// Player chat commands (`/who`, `/pm`, ...)
+    pub chat_commands: ChatCommandsConfig,
+
+    // Respawn
+    /// Respawning players whose hearts reach 0
+    pub respawn: RespawnConfig,
+
+    // Service
+    /// File the server's process ID is written to, relative to the working directory (from "pidfile" option, empty = none)
+    pub pid_file: String,
+    /// Address of the HTTP health check, e.g. 127.0.0.1:14803 (from "healthaddress" option, empty = disabled)
+    pub health_address: String,
+    /// Unix socket of the operator console, relative to the server folder (from "consolesocket" option, empty = disabled)
+    pub console_socket: String,
+
+    // Backups
+    /// Seconds between automatic backups (from "backupinterval" option, 0 = disabled)
+    pub backup_interval: u64,
+    /// Number of backups to keep (from "backupretention" option, default: 7)
+    pub backup_retention: usize,
 
     // Path
     /// Server folder path
@@ -92,6 +291,12 @@ pub struct ServerConfig {
     pub hq_level: u8,
     /// NPC-Server IP (from "ns_ip" option)
     pub ns_ip: String,
+    /// Server browser icon URL (from "list_icon" option)
+    pub list_icon: String,
+    /// Server browser banner URL (from "list_banner" option)
+    pub list_banner: String,
+    /// Server browser category tags (from "list_tags" option, comma-separated)
+    pub list_tags: Vec<String>,
 
     // ========== From allowedversions.txt ==========
     /// Allowed client versions per generation
@@ -102,8 +307,8 @@ pub struct ServerConfig {
     pub ip_bans: HashSet<String>,
 
     // ========== From rules.txt ==========
-    /// Word filter (banned words)
-    pub word_filter: HashSet<String>,
+    /// Word filter rules
+    pub word_filter: WordFilter,
 
     // ========== From servermessage.html ==========
     /// Server welcome message (HTML)
@@ -120,6 +325,297 @@ pub struct ServerConfig {
     // ========== From defaultaccount.txt ==========
     /// Default account settings
     pub default_account: DefaultAccount,
+
+    // ========== From translations/*.po ==========
+    /// Translations of server texts
+    pub translations: Translations,
+
+    // ========== From updatepackages.txt ==========
+    /// Update packages clients can download as a whole
+    pub update_packages: Vec<UpdatePackage>,
+}
+
+/// Bundle compression settings from serveroptions.txt
+///
+/// Read once at startup; changing them needs a restart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionConfig {
+    /// zlib level 0-9 (from "zliblevel" option, default: 6)
+    pub zlib_level: u32,
+    /// bzip2 level 1-9 (from "bz2level" option, default: 6)
+    pub bz2_level: u32,
+    /// GEN_5 bundles larger than this are zlib compressed (from "gen5zlibthreshold" option, default: 55)
+    pub gen5_zlib_threshold: usize,
+    /// GEN_5 bundles larger than this are bzip2 compressed (from "gen5bz2threshold" option, default: 8192)
+    pub gen5_bz2_threshold: usize,
+    /// Reuse zlib encoders between bundles (from "compressionpool" option, default: false)
+    pub encoder_pool: bool,
+    /// Move each connection's GEN_5 zlib cutoff by how well its bundles near it compress
+    /// (from "gen5adaptive" option, default: false)
+    pub gen5_adaptive: bool,
+}
+
+impl Default for CompressionConfig {
+    /// Matches the C++ server's fixed `zcompressI`/`bzcompressI` settings
+    fn default() -> Self {
+        Self {
+            zlib_level: 6,
+            bz2_level: 6,
+            gen5_zlib_threshold: 55,
+            gen5_bz2_threshold: 0x2000,
+            encoder_pool: false,
+            gen5_adaptive: false,
+        }
+    }
+}
+
+/// Event webhook settings from serveroptions.txt
+///
+/// Read once at startup; changing them needs a restart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookConfig {
+    /// http:// URL events are POSTed to (from "webhookurl" option, empty = disabled)
+    pub url: String,
+    /// Events to post (from "webhookevents" option: join, leave, staff, serverup, serverdown, chat)
+    pub events: Vec<String>,
+    /// Levels whose chat is posted (from "webhookchatlevels" option)
+    pub chat_levels: Vec<String>,
+    /// Maximum posts per minute, extra events are dropped (from "webhookratelimit" option, default: 30)
+    pub rate_limit: u32,
+    /// Message templates by event name (from "webhooktemplate.<event>" options)
+    pub templates: HashMap<String, String>,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            events: ["join", "leave", "staff", "serverup", "serverdown"].map(String::from).to_vec(),
+            chat_levels: Vec::new(),
+            rate_limit: 30,
+            templates: HashMap::new(),
+        }
+    }
+}
+
+/// How private messages are written to the chat logs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PmLogging {
+    /// Not logged (`off`)
+    Off,
+    /// Logged with pseudonyms instead of account names (`anonymized`)
+    Anonymized,
+    /// Logged with account names (`full`)
+    Full,
+}
+
+impl PmLogging {
+    /// Parse a PM logging mode
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "off" | "false" => Some(Self::Off),
+            "anonymized" => Some(Self::Anonymized),
+            "full" | "true" => Some(Self::Full),
+            _ => None,
+        }
+    }
+}
+
+/// Chat log settings from serveroptions.txt
+///
+/// Changes apply on reload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrivacyConfig {
+    /// Write player chat to `logs/chat/` (from "chatlog" option, default: false)
+    pub chat_log: bool,
+    /// How PMs are logged (from "chatlogpms" option: off, anonymized or full; default: off)
+    pub chat_log_pms: PmLogging,
+    /// Log RC chat (from "chatlogrc" option, default: true)
+    pub chat_log_rc: bool,
+    /// Days of chat logs kept (from "chatlogretention" option, default: 30, 0 = all)
+    pub chat_log_retention: u64,
+}
+
+impl Default for PrivacyConfig {
+    fn default() -> Self {
+        Self { chat_log: false, chat_log_pms: PmLogging::Off, chat_log_rc: true, chat_log_retention: 30 }
+    }
+}
+
+/// Logging settings from serveroptions.txt
+///
+/// Read once at startup. Levels can be changed at runtime from RC
+/// (`/loglevel`); files and format need a restart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoggingConfig {
+    /// Default level: trace, debug, info, warn, error or off (from "loglevel" option, default: info)
+    pub level: String,
+    /// Per-module levels, e.g. `network=debug, scripting=warn` (from "logfilters" option)
+    pub filters: Vec<(String, String)>,
+    /// Write logs to logs/ in the server folder (from "logfile" option, default: true)
+    pub file: bool,
+    /// When to start a new log file (from "logrotation" option: daily, hourly, never)
+    pub rotation: LogRotation,
+    /// Number of log files to keep, 0 = all (from "logretention" option, default: 14)
+    pub retention: usize,
+    /// One JSON object per line instead of text (from "logjson" option, default: false)
+    pub json: bool,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".into(),
+            filters: Vec::new(),
+            file: true,
+            rotation: LogRotation::Daily,
+            retention: 14,
+            json: false,
+        }
+    }
+}
+
+/// Player chat command settings from serveroptions.txt
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatCommandsConfig {
+    /// Handle chat starting with `/` as commands (from "chatcommands" option, default: true)
+    pub enabled: bool,
+    /// Who may use which command, e.g. `warp=staff, who=off` (from "chatcommandpermissions" option, default: warp=staff)
+    pub permissions: Vec<(String, ChatCommandPermission)>,
+    /// Seconds between uses of a command per player, e.g. `unstick=30` (from "chatcommandcooldowns" option, default: unstick=30)
+    pub cooldowns: Vec<(String, u64)>,
+    /// Level `/unstick` warps to (from "unstickmelevel" option, empty = the start location)
+    pub unstick_level: String,
+    /// X `/unstick` warps to (from "unstickmex" option, default: 30)
+    pub unstick_x: f32,
+    /// Y `/unstick` warps to (from "unstickmey" option, default: 30.5)
+    pub unstick_y: f32,
+}
+
+impl ChatCommandsConfig {
+    /// Get who may use a command (everyone unless configured)
+    pub fn permission(&self, command: &str) -> ChatCommandPermission {
+        self.permissions.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(command))
+            .map_or(ChatCommandPermission::All, |(_, permission)| *permission)
+    }
+
+    /// Get the seconds between uses of a command (0 = no cooldown)
+    pub fn cooldown(&self, command: &str) -> u64 {
+        self.cooldowns.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(command))
+            .map_or(0, |(_, seconds)| *seconds)
+    }
+}
+
+impl Default for ChatCommandsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            permissions: vec![("warp".into(), ChatCommandPermission::Staff)],
+            cooldowns: vec![("unstick".into(), 30)],
+            unstick_level: String::new(),
+            unstick_x: 30.0,
+            unstick_y: 30.5,
+        }
+    }
+}
+
+/// Respawn settings from serveroptions.txt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RespawnConfig {
+    /// Respawn players whose hearts reach 0 (from "respawn" option, default: false)
+    pub enabled: bool,
+    /// Seconds between dying and respawning (from "respawndelay" option, default: 5)
+    pub delay: u64,
+    /// Gralats dropped on death (from "deathdrops" option, `gralats=10%`)
+    pub gralats: DropAmount,
+    /// Arrows dropped on death (from "deathdrops" option, `arrows=5`)
+    pub arrows: DropAmount,
+    /// Bombs dropped on death (from "deathdrops" option, `bombs=5`)
+    pub bombs: DropAmount,
+}
+
+impl Default for RespawnConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            delay: 5,
+            gralats: DropAmount::Count(0),
+            arrows: DropAmount::Count(0),
+            bombs: DropAmount::Count(0),
+        }
+    }
+}
+
+/// How much of something a dying player drops
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropAmount {
+    /// A fixed number (`5`)
+    Count(u32),
+    /// A share of what the player has (`10%`)
+    Percent(u32),
+}
+
+impl DropAmount {
+    /// Parse `5` or `10%`
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        match value.strip_suffix('%') {
+            Some(percent) => percent.trim().parse().ok().filter(|p| *p <= 100).map(Self::Percent),
+            None => value.parse().ok().map(Self::Count),
+        }
+    }
+
+    /// Get how much is dropped out of `held`
+    pub fn of(&self, held: u32) -> u32 {
+        match *self {
+            Self::Count(count) => count.min(held),
+            Self::Percent(percent) => (u64::from(held) * u64::from(percent) / 100) as u32,
+        }
+    }
+}
+
+/// Who may use a chat command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatCommandPermission {
+    /// Every player (`all`)
+    All,
+    /// Staff accounts only (`staff`)
+    Staff,
+    /// Nobody, the command is turned off (`off`)
+    Off,
+}
+
+impl ChatCommandPermission {
+    /// Parse a permission name
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "all" => Some(Self::All),
+            "staff" => Some(Self::Staff),
+            "off" => Some(Self::Off),
+            _ => None,
+        }
+    }
+}
+
+/// Log file rotation period
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogRotation {
+    Daily,
+    Hourly,
+    Never,
+}
+
+/// Handling of a second login on an account that is in use
+///
+/// RC logins don't count; staff can be on RC and in the game at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateLogin {
+    /// Disconnect the player already online and let the new login in (C++ behavior)
+    KickOld,
+    /// Refuse the new login
+    RejectNew,
 }
 
 /// Folder configuration from foldersconfig.txt
@@ -139,6 +635,26 @@ pub enum FolderType {
     Level,
 }
 
+/// Update package from updatepackages.txt
+///
+/// # Format
+/// ```text
+/// PACKAGE base
+/// VERSION 3
+/// FILE logo.png
+/// FILE idle.gani
+/// PACKAGEEND
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdatePackage {
+    /// Package name requested by clients
+    pub name: String,
+    /// Package version, raised whenever its files change
+    pub version: u32,
+    /// File names (looked up like PLI_WANTFILE requests)
+    pub files: Vec<String>,
+}
+
 /// Default account settings from defaultaccount.txt
 #[derive(Debug, Clone)]
 pub struct DefaultAccount {
@@ -330,11 +846,21 @@ impl Default for ServerConfig {
             server_ip: "AUTO".into(),
             server_port: 14802, // C++ default!
             server_interface: "AUTO".into(),
+            tls_port: 0,
+            tls_cert: "tls/cert.pem".into(),
+            tls_key: "tls/key.pem".into(),
+            proxy_protocol: false,
+            proxy_trusted: Vec::new(),
+            geoip_db: String::new(),
+            geoip_allow: Vec::new(),
+            geoip_deny: Vec::new(),
             local_ip: "AUTO".into(),
             upnp: true,
             max_players: 128,
             list_ip: "listserver.graal.in".into(),
             list_port: 14900,
+            list_servers: Vec::new(),
+            list_failure_alert: 3,
             only_staff: false,
             generation: ServerGeneration::Classic,
             staff_accounts: vec![],
@@ -348,16 +874,78 @@ impl Default for ServerConfig {
             heart_limit: 3,
             sword_limit: 3,
             shield_limit: 3,
+            max_nick_length: 223,
             gs2_default: false,
             putnpc_enabled: true,
             serverside: false,
             save_levels: false,
+            tick_rate: 20,
+            autosave_interval: 300,
+            interest_range: 64,
+            interest_hysteresis: 8,
+            class_check_interval: 5,
+            script_memory_limit: 4096,
+            lua_instruction_limit: 1_000_000,
+            gs2_instruction_limit: 1_000_000,
+            script_history: 10,
+            level_check_interval: 5,
+            slow_handler_ms: 5,
+            handler_summary_interval: 300,
+            stats_interval: 60,
+            economy_alert_gain: 10_000,
+            economy_alert_window: 60,
+            jail_level: String::new(),
+            shutdown_countdown: 0,
+            npc_move_rate: 10,
+            protocol_timeout: 300,
+            afk_minutes: 5,
+            idle_disconnect_minutes: 20,
+            keepalive_interval: 30,
+            reconnect_grace: 0,
+            duplicate_login: DuplicateLogin::KickOld,
+            local_auth: false,
+            verify_logins: false,
+            verify_cache_hours: 72,
+            ip_history_days: 90,
+            banned_hardware: vec![],
+            record_sessions: vec![],
+            login_approval_timeout: 120,
+            panic_notify_rc: true,
+            watchdog_timeout: 30,
+            watchdog_action: "log".into(),
+            outbound_soft_limit: 0x40000,
+            outbound_hard_limit: 0x100000,
+            outbound_stall_timeout: 30,
+            bundle_read_timeout: 10,
+            max_bundle_player: 0xFFFF,
+            max_bundle_rc: 0xFFFF,
+            rc_max_upload_size: 20_971_520,
+            bandwidth_limit: 0,
+            file_bandwidth_limit: 0,
+            server_bandwidth_limit: 0,
+            server_file_bandwidth_limit: 0,
+            packet_count_disconnect: false,
+            tamper_action: "log".into(),
+            compression: CompressionConfig::default(),
+            webhook: WebhookConfig::default(),
+            logging: LoggingConfig::default(),
+            privacy: PrivacyConfig::default(),
+            chat_commands: ChatCommandsConfig::default(),
+            respawn: RespawnConfig::default(),
+            pid_file: String::new(),
+            health_address: String::new(),
+            console_socket: String::new(),
+            backup_interval: 0,
+            backup_retention: 7,
             server_folder: "servers/default".into(),
 
             // adminconfig.txt defaults
             hq_password: String::new(),
             hq_level: 1, // Bronze
             ns_ip: "AUTO".into(),
+            list_icon: String::new(),
+            list_banner: String::new(),
+            list_tags: Vec::new(),
 
             // allowedversions.txt defaults
             allowed_versions: AllowedVersions::default(),
@@ -366,7 +954,7 @@ impl Default for ServerConfig {
             ip_bans: HashSet::new(),
 
             // rules.txt defaults
-            word_filter: HashSet::new(),
+            word_filter: WordFilter::default(),
 
             // servermessage.html defaults
             server_message: String::new(),
@@ -379,6 +967,12 @@ impl Default for ServerConfig {
 
             // defaultaccount.txt defaults
             default_account: DefaultAccount::default(),
+
+            // translations/ defaults
+            translations: Translations::new(),
+
+            // updatepackages.txt defaults
+            update_packages: vec![],
         }
     }
 }
@@ -404,8 +998,14 @@ impl ServerConfig {
     /// - ./servers/default/serverflags.txt (server flags)
     /// - ./servers/default/accounts/ (account files)
     pub fn load_default() -> Result<Self, Box<dyn std::error::Error>> {
-        let base_path = "servers/default";
+        Self::load_from_folder("servers/default")
+    }
 
+    /// Load configuration from a server folder
+    ///
+    /// Loads the same files as [`load_default`](Self::load_default), from
+    /// `base_path/config/` instead of `servers/default/config/`.
+    pub fn load_from_folder(base_path: &str) -> Result<Self, Box<dyn std::error::Error>> {
         // Load serveroptions.txt (required)
         let mut config = Self::load_from_file(&format!("{}/config/serveroptions.txt", base_path))?;
 
@@ -449,6 +1049,15 @@ impl ServerConfig {
             config.parse_defaultaccount(&content);
         }
 
+        // Load updatepackages.txt (optional)
+        if let Ok(content) = fs::read_to_string(format!("{}/config/updatepackages.txt", base_path)) {
+            config.parse_updatepackages(&content);
+        }
+
+        // Load translations/*.po (optional)
+        config.translations = Translations::load_dir(format!("{}/translations", base_path));
+
+        config.server_folder = base_path.into();
         Ok(config)
     }
 
@@ -487,6 +1096,22 @@ impl ServerConfig {
                 self.server_port = value.parse().unwrap_or(14802);
             }
             "serverinterface" => self.server_interface = value.into(),
+            "tlsport" => {
+                self.tls_port = value.parse().unwrap_or(0);
+            }
+            "tlscert" => self.tls_cert = value.into(),
+            "tlskey" => self.tls_key = value.into(),
+            "proxyprotocol" => {
+                self.proxy_protocol = value.parse().unwrap_or(false);
+            }
+            "proxytrusted" => {
+                self.proxy_trusted = value.split(',').filter_map(|ip| ip.trim().parse().ok()).collect();
+            }
+            "geoipdb" => self.geoip_db = value.into(),
+            "geoipallow" | "geoipdeny" => {
+                let countries = value.split(',').map(|c| c.trim().to_ascii_uppercase()).filter(|c| !c.is_empty()).collect();
+                if key == "geoipallow" { self.geoip_allow = countries } else { self.geoip_deny = countries }
+            }
             "localip" => self.local_ip = value.into(),
             "upnp" => {
                 self.upnp = value.parse().unwrap_or(true);
@@ -498,6 +1123,12 @@ impl ServerConfig {
             "listport" => {
                 self.list_port = value.parse().unwrap_or(14900);
             }
+            "listservers" => {
+                self.list_servers = value.split(',').map(str::trim).filter(|s| !s.is_empty()).map(Into::into).collect();
+            }
+            "listfailurealert" => {
+                self.list_failure_alert = value.parse().unwrap_or(3);
+            }
             "onlystaff" => {
                 self.only_staff = value.parse().unwrap_or(false);
             }
@@ -551,6 +1182,9 @@ impl ServerConfig {
             "shieldlimit" => {
                 self.shield_limit = value.parse().unwrap_or(3);
             }
+            "maxnicklength" => {
+                self.max_nick_length = value.parse().unwrap_or(223);
+            }
             "gs2default" => {
                 self.gs2_default = value.parse().unwrap_or(false);
             }
@@ -563,6 +1197,281 @@ impl ServerConfig {
             "savelevels" => {
                 self.save_levels = value.parse().unwrap_or(false);
             }
+            "tickrate" => {
+                self.tick_rate = value.parse().unwrap_or(20);
+            }
+            "autosaveinterval" => {
+                self.autosave_interval = value.parse().unwrap_or(300);
+            }
+            "packetcountdisconnect" => {
+                self.packet_count_disconnect = value.parse().unwrap_or(false);
+            }
+            "tamperaction" => {
+                self.tamper_action = value.to_string();
+            }
+            "zliblevel" => {
+                self.compression.zlib_level = value.parse::<u32>().unwrap_or(6).min(9);
+            }
+            "bz2level" => {
+                self.compression.bz2_level = value.parse::<u32>().unwrap_or(6).clamp(1, 9);
+            }
+            "gen5zlibthreshold" => {
+                self.compression.gen5_zlib_threshold = value.parse().unwrap_or(55);
+            }
+            "gen5bz2threshold" => {
+                self.compression.gen5_bz2_threshold = value.parse().unwrap_or(0x2000);
+            }
+            "compressionpool" => {
+                self.compression.encoder_pool = value.parse().unwrap_or(false);
+            }
+            "gen5adaptive" => {
+                self.compression.gen5_adaptive = value.parse().unwrap_or(false);
+            }
+            "backupinterval" => {
+                self.backup_interval = value.parse().unwrap_or(0);
+            }
+            "backupretention" => {
+                self.backup_retention = value.parse().unwrap_or(7);
+            }
+            "interestrange" => {
+                self.interest_range = value.parse().unwrap_or(64);
+            }
+            "interesthysteresis" => {
+                self.interest_hysteresis = value.parse().unwrap_or(8);
+            }
+            "scriptmemorylimit" => {
+                self.script_memory_limit = value.parse().unwrap_or(4096);
+            }
+            "scripthistory" => {
+                self.script_history = value.parse().unwrap_or(10);
+            }
+            "luainstructionlimit" => {
+                self.lua_instruction_limit = value.parse().unwrap_or(1_000_000);
+            }
+            "gs2instructionlimit" => {
+                self.gs2_instruction_limit = value.parse().unwrap_or(1_000_000);
+            }
+            "classcheckinterval" => {
+                self.class_check_interval = value.parse().unwrap_or(5);
+            }
+            "levelcheckinterval" => {
+                self.level_check_interval = value.parse().unwrap_or(5);
+            }
+            "slowhandlerms" => {
+                self.slow_handler_ms = value.parse().unwrap_or(5);
+            }
+            "handlersummaryinterval" => {
+                self.handler_summary_interval = value.parse().unwrap_or(300);
+            }
+            "statsinterval" => {
+                self.stats_interval = value.parse().unwrap_or(60);
+            }
+            "economyalertgain" => {
+                self.economy_alert_gain = value.parse().unwrap_or(10_000);
+            }
+            "economyalertwindow" => {
+                self.economy_alert_window = value.parse().unwrap_or(60);
+            }
+            "jaillevel" => {
+                self.jail_level = value.to_string();
+            }
+            "shutdowncountdown" => {
+                self.shutdown_countdown = value.parse().unwrap_or(0);
+            }
+            "npcmoverate" => {
+                self.npc_move_rate = value.parse().unwrap_or(10);
+            }
+            "protocoltimeout" => {
+                self.protocol_timeout = value.parse().unwrap_or(300);
+            }
+            "afkminutes" => {
+                self.afk_minutes = value.parse().unwrap_or(5);
+            }
+            "idledisconnectminutes" => {
+                self.idle_disconnect_minutes = value.parse().unwrap_or(20);
+            }
+            "reconnectgrace" => {
+                self.reconnect_grace = value.parse().unwrap_or(0);
+            }
+            "localauth" => {
+                self.local_auth = value.parse().unwrap_or(false);
+            }
+            "verifylogins" => {
+                self.verify_logins = value.parse().unwrap_or(false);
+            }
+            "verifycachehours" => {
+                self.verify_cache_hours = value.parse().unwrap_or(72);
+            }
+            "iphistorydays" => {
+                self.ip_history_days = value.parse().unwrap_or(90);
+            }
+            "bannedhardware" => {
+                self.banned_hardware = value
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect();
+            }
+            "recordsessions" => {
+                self.record_sessions = value
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect();
+            }
+            "loginapprovaltimeout" => {
+                self.login_approval_timeout = value.parse().unwrap_or(120);
+            }
+            "panicnotifyrc" => {
+                self.panic_notify_rc = value.parse().unwrap_or(true);
+            }
+            "watchdogtimeout" => {
+                self.watchdog_timeout = value.parse().unwrap_or(30);
+            }
+            "watchdogaction" => {
+                self.watchdog_action = value.to_string();
+            }
+            "duplicatelogin" => {
+                self.duplicate_login = match value.to_lowercase().as_str() {
+                    "rejectnew" => DuplicateLogin::RejectNew,
+                    _ => DuplicateLogin::KickOld,
+                };
+            }
+            "chatlog" => self.privacy.chat_log = value.parse().unwrap_or(false),
+            "chatlogpms" => self.privacy.chat_log_pms = PmLogging::parse(value).unwrap_or(PmLogging::Off),
+            "chatlogrc" => self.privacy.chat_log_rc = value.parse().unwrap_or(true),
+            "chatlogretention" => self.privacy.chat_log_retention = value.parse().unwrap_or(30),
+            "webhookurl" => self.webhook.url = value.into(),
+            "webhookevents" => {
+                self.webhook.events = value
+                    .split(',')
+                    .map(|s| s.trim().to_lowercase())
+                    .filter(|s| !s.is_empty())
+                    .collect();
+            }
+            "webhookchatlevels" => {
+                self.webhook.chat_levels = value
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect();
+            }
+            "webhookratelimit" => {
+                self.webhook.rate_limit = value.parse().unwrap_or(30);
+            }
+            key if key.starts_with("webhooktemplate.") => {
+                let event = key["webhooktemplate.".len()..].to_lowercase();
+                self.webhook.templates.insert(event, value.to_string());
+            }
+            "loglevel" => self.logging.level = value.to_lowercase(),
+            "logfilters" => {
+                self.logging.filters = value
+                    .split(',')
+                    .filter_map(|f| f.split_once('='))
+                    .map(|(module, level)| (module.trim().to_string(), level.trim().to_lowercase()))
+                    .filter(|(module, level)| !module.is_empty() && !level.is_empty())
+                    .collect();
+            }
+            "logfile" => {
+                self.logging.file = value.parse().unwrap_or(true);
+            }
+            "logrotation" => {
+                self.logging.rotation = match value.to_lowercase().as_str() {
+                    "hourly" => LogRotation::Hourly,
+                    "never" => LogRotation::Never,
+                    _ => LogRotation::Daily,
+                };
+            }
+            "logretention" => {
+                self.logging.retention = value.parse().unwrap_or(14);
+            }
+            "logjson" => {
+                self.logging.json = value.parse().unwrap_or(false);
+            }
+            "chatcommands" => {
+                self.chat_commands.enabled = value.parse().unwrap_or(true);
+            }
+            "chatcommandpermissions" => {
+                self.chat_commands.permissions = value
+                    .split(',')
+                    .filter_map(|p| p.split_once('='))
+                    .filter_map(|(command, permission)| {
+                        Some((command.trim().to_lowercase(), ChatCommandPermission::parse(permission)?))
+                    })
+                    .filter(|(command, _)| !command.is_empty())
+                    .collect();
+            }
+            "chatcommandcooldowns" => {
+                self.chat_commands.cooldowns = value
+                    .split(',')
+                    .filter_map(|c| c.split_once('='))
+                    .filter_map(|(command, seconds)| Some((command.trim().to_lowercase(), seconds.trim().parse().ok()?)))
+                    .filter(|(command, _)| !command.is_empty())
+                    .collect();
+            }
+            "respawn" => {
+                self.respawn.enabled = value.parse().unwrap_or(false);
+            }
+            "respawndelay" => {
+                self.respawn.delay = value.parse().unwrap_or(5);
+            }
+            "deathdrops" => {
+                for (item, amount) in value.split(',').filter_map(|d| d.split_once('=')) {
+                    let Some(amount) = DropAmount::parse(amount) else { continue };
+                    match item.trim().to_lowercase().as_str() {
+                        "gralats" | "rupees" => self.respawn.gralats = amount,
+                        "arrows" | "darts" => self.respawn.arrows = amount,
+                        "bombs" => self.respawn.bombs = amount,
+                        _ => {}
+                    }
+                }
+            }
+            "unstickmelevel" => self.chat_commands.unstick_level = value.into(),
+            "unstickmex" => {
+                self.chat_commands.unstick_x = value.parse().unwrap_or(30.0);
+            }
+            "unstickmey" => {
+                self.chat_commands.unstick_y = value.parse().unwrap_or(30.5);
+            }
+            "keepaliveinterval" => {
+                self.keepalive_interval = value.parse().unwrap_or(30);
+            }
+            "outboundsoftlimit" => {
+                self.outbound_soft_limit = value.parse().unwrap_or(0x40000);
+            }
+            "outboundhardlimit" => {
+                self.outbound_hard_limit = value.parse().unwrap_or(0x100000);
+            }
+            "outboundstalltimeout" => {
+                self.outbound_stall_timeout = value.parse().unwrap_or(30);
+            }
+            "bundlereadtimeout" => {
+                self.bundle_read_timeout = value.parse().unwrap_or(10);
+            }
+            "maxbundleplayer" => {
+                self.max_bundle_player = value.parse().unwrap_or(0xFFFF);
+            }
+            "maxbundlerc" => {
+                self.max_bundle_rc = value.parse().unwrap_or(0xFFFF);
+            }
+            "rcmaxuploadsize" => {
+                self.rc_max_upload_size = value.parse().unwrap_or(20_971_520);
+            }
+            "bandwidthlimit" => {
+                self.bandwidth_limit = value.parse().unwrap_or(0);
+            }
+            "filebandwidthlimit" => {
+                self.file_bandwidth_limit = value.parse().unwrap_or(0);
+            }
+            "serverbandwidthlimit" => {
+                self.server_bandwidth_limit = value.parse().unwrap_or(0);
+            }
+            "serverfilebandwidthlimit" => {
+                self.server_file_bandwidth_limit = value.parse().unwrap_or(0);
+            }
+            "pidfile" => self.pid_file = value.into(),
+            "healthaddress" => self.health_address = value.into(),
+            "consolesocket" => self.console_socket = value.into(),
             _ => {
                 // tracing::debug!("Unknown config option: {} = {}", key, value);
             }
@@ -585,6 +1494,11 @@ impl ServerConfig {
                     "hq_password" => self.hq_password = value.into(),
                     "hq_level" => self.hq_level = value.parse().unwrap_or(1),
                     "ns_ip" => self.ns_ip = value.into(),
+                    "list_icon" => self.list_icon = value.into(),
+                    "list_banner" => self.list_banner = value.into(),
+                    "list_tags" => {
+                        self.list_tags = value.split(',').map(str::trim).filter(|t| !t.is_empty()).map(Into::into).collect();
+                    }
                     _ => {}
                 }
             }
@@ -642,11 +1556,7 @@ impl ServerConfig {
 
     /// Parse rules.txt (word filter)
     fn parse_wordfilter(&mut self, content: &str) {
-        self.word_filter = content
-            .lines()
-            .filter(|line| !line.trim().is_empty() && !line.trim().starts_with('#'))
-            .map(|line| line.trim().to_lowercase())
-            .collect();
+        self.word_filter = WordFilter::parse(content);
     }
 
     /// Parse foldersconfig.txt
@@ -681,6 +1591,35 @@ impl ServerConfig {
     }
 
     /// Parse serverflags.txt (one flag per line)
+    /// Parse updatepackages.txt
+    fn parse_updatepackages(&mut self, content: &str) {
+        let mut packages = Vec::new();
+        let mut current: Option<UpdatePackage> = None;
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (word, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let rest = rest.trim();
+            match (word, current.as_mut()) {
+                ("PACKAGE", _) => {
+                    packages.extend(current.take());
+                    current = Some(UpdatePackage { name: rest.into(), version: 1, files: vec![] });
+                }
+                ("VERSION", Some(package)) => package.version = rest.parse().unwrap_or(1),
+                ("FILE", Some(package)) if !rest.is_empty() => package.files.push(rest.into()),
+                ("PACKAGEEND", Some(_)) => packages.extend(current.take()),
+                _ => {}
+            }
+        }
+        packages.extend(current);
+
+        self.update_packages = packages.into_iter().filter(|p| !p.name.is_empty()).collect();
+    }
+
     fn parse_serverflags(&mut self, content: &str) {
         self.server_flags = content
             .lines()
@@ -689,6 +1628,21 @@ impl ServerConfig {
             .collect();
     }
 
+    /// Serialize server flags in serverflags.txt format (one flag per line)
+    pub fn serverflags_to_string(&self) -> String {
+        let mut content = String::new();
+        for flag in &self.server_flags {
+            content.push_str(flag);
+            content.push_str("\r\n");
+        }
+        content
+    }
+
+    /// Find an update package by name (case-insensitive)
+    pub fn update_package(&self, name: &str) -> Option<&UpdatePackage> {
+        self.update_packages.iter().find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+
     /// Parse defaultaccount.txt
     fn parse_defaultaccount(&mut self, content: &str) {
         let mut account = self.default_account.clone();
@@ -758,6 +1712,35 @@ impl ServerConfig {
         self.default_account = account;
     }
 
+    /// Get the listservers to register with, `listip:listport` first
+    ///
+    /// A `listservers` entry without a port uses 14900; repeated ones are
+    /// skipped.
+    pub fn listserver_addresses(&self) -> Vec<(String, u16)> {
+        let mut addresses = vec![(self.list_ip.clone(), self.list_port)];
+        for entry in &self.list_servers {
+            let address = match entry.rsplit_once(':') {
+                Some((host, port)) => match port.parse() {
+                    Ok(port) => (host.to_string(), port),
+                    Err(_) => {
+                        tracing::warn!("Ignoring listserver {}: bad port", entry);
+                        continue;
+                    }
+                },
+                None => (entry.clone(), 14900),
+            };
+            if !addresses.iter().any(|(host, port)| host.eq_ignore_ascii_case(&address.0) && *port == address.1) {
+                addresses.push(address);
+            }
+        }
+        addresses
+    }
+
+    /// Get the bind address for the TLS listener, if `tlsport` is set
+    pub fn tls_bind_address(&self) -> Option<SocketAddr> {
+        (self.tls_port != 0).then(|| SocketAddr::new(self.bind_address().ip(), self.tls_port))
+    }
+
     /// Get the bind address for the TCP listener
     pub fn bind_address(&self) -> SocketAddr {
         let ip = if self.server_interface == "AUTO" {
@@ -781,15 +1764,152 @@ impl ServerConfig {
         tracing::info!("    Description: {}", self.description);
         tracing::info!("    URL: {}", self.url);
         tracing::info!("    Bind: {} (port {})", self.bind_address(), self.server_port);
+        if let Some(tls) = self.tls_bind_address() {
+            tracing::info!("    TLS: {} ({}, {})", tls, self.tls_cert, self.tls_key);
+        }
+        if self.proxy_protocol {
+            if self.proxy_trusted.is_empty() {
+                tracing::warn!("    PROXY Protocol: on, but no proxytrusted addresses, so no headers are read");
+            } else {
+                tracing::info!("    PROXY Protocol: from {}",
+                    self.proxy_trusted.iter().map(IpAddr::to_string).collect::<Vec<_>>().join(", "));
+            }
+        }
+        if !self.geoip_db.is_empty() {
+            tracing::info!("    GeoIP: {} (allow: [{}], deny: [{}])", self.geoip_db,
+                self.geoip_allow.join(", "), self.geoip_deny.join(", "));
+        }
         tracing::info!("    Max Players: {}", self.max_players);
         tracing::info!("    Generation: {:?}", self.generation);
         tracing::info!("    Staff Accounts: {}", self.staff_accounts.len());
         tracing::info!("    Only Staff: {}", self.only_staff);
         tracing::info!("    Default Weapons: {}", self.default_weapons);
+        tracing::info!("    Tick Rate: {} Hz", self.tick_rate);
+        tracing::info!("    Autosave Interval: {}s", self.autosave_interval);
+        tracing::info!("    Interest Range: {} tiles (+{} to leave)", self.interest_range, self.interest_hysteresis);
+        tracing::info!("    Script Memory Limit: {}", match self.script_memory_limit {
+            0 => "unlimited".to_string(),
+            kb => format!("{} KB", kb),
+        });
+        tracing::info!("    Lua Instruction Limit: {}", match self.lua_instruction_limit {
+            0 => "unlimited".to_string(),
+            count => count.to_string(),
+        });
+        tracing::info!("    GS2 Instruction Limit: {}", match self.gs2_instruction_limit {
+            0 => "unlimited".to_string(),
+            count => count.to_string(),
+        });
+        tracing::info!("    Script History: {} versions", self.script_history);
+        tracing::info!("    Class Check Interval: {}", match self.class_check_interval {
+            0 => "off".to_string(),
+            secs => format!("{}s", secs),
+        });
+        tracing::info!("    Level Check Interval: {}", match self.level_check_interval {
+            0 => "off".to_string(),
+            secs => format!("{}s", secs),
+        });
+        tracing::info!("    Slow Handler Warning: {}, summary every {}", match self.slow_handler_ms {
+            0 => "off".to_string(),
+            ms => format!("{}ms", ms),
+        }, match self.handler_summary_interval {
+            0 => "never".to_string(),
+            secs => format!("{}s", secs),
+        });
+        tracing::info!("    Player Statistics: {}", match self.stats_interval {
+            0 => "off".to_string(),
+            secs => format!("leaderboards every {}s", secs),
+        });
+        tracing::info!("    Economy Alerts: {}", match self.economy_alert_gain {
+            0 => "off".to_string(),
+            gain => format!("{} gralats within {}s", gain, self.economy_alert_window),
+        });
+        if !self.jail_level.is_empty() {
+            tracing::info!("    Jail Level: {}", self.jail_level);
+        }
+        tracing::info!("    Shutdown Countdown: {}s", self.shutdown_countdown);
+        tracing::info!("    NPC Move Rate: {}/s", self.npc_move_rate);
+        tracing::info!("    Idle: away after {}m, disconnect after {}m, timeout {}s",
+            self.afk_minutes, self.idle_disconnect_minutes, self.protocol_timeout);
+        tracing::info!("    Keepalive Interval: {}s", self.keepalive_interval);
+        if self.reconnect_grace > 0 {
+            tracing::info!("    Reconnect Grace: {}s", self.reconnect_grace);
+        }
+        tracing::info!("    Duplicate Login: {:?}", self.duplicate_login);
+        if self.local_auth {
+            tracing::info!("    Local Authentication: enabled");
+        }
+        if self.verify_logins {
+            tracing::info!("    Login Verification: enabled, {}h offline cache", self.verify_cache_hours);
+        }
+        tracing::info!("    IP History: {} days", self.ip_history_days);
+        tracing::info!("    Banned Hardware: {}, approval timeout {}s",
+            self.banned_hardware.len(), self.login_approval_timeout);
+        if !self.record_sessions.is_empty() {
+            tracing::info!("    Recording Sessions: {}", self.record_sessions.join(", "));
+        }
+        tracing::info!("    Panic Notices to RC: {}", self.panic_notify_rc);
+        tracing::info!("    Watchdog: {}s, {}", self.watchdog_timeout, self.watchdog_action);
+        if !self.list_servers.is_empty() {
+            tracing::info!("    More Listservers: {}", self.list_servers.join(", "));
+        }
+        tracing::info!("    Listserver Alerts: after {} failed registrations", self.list_failure_alert);
+        tracing::info!("    Outbound Queue: drop above {} bytes, disconnect at {}, stall timeout {}s",
+            self.outbound_soft_limit, self.outbound_hard_limit, self.outbound_stall_timeout);
+        tracing::info!("    Bundle Read Timeout: {}s", self.bundle_read_timeout);
+        tracing::info!("    Bundle Limits: players {} bytes, RC {} bytes, RC uploads {} bytes",
+            self.max_bundle_player, self.max_bundle_rc, self.rc_max_upload_size);
+        let limit = |kbs: u64| match kbs {
+            0 => "unlimited".to_string(),
+            kbs => format!("{} KB/s", kbs),
+        };
+        tracing::info!("    Bandwidth: gameplay {}, files {} per connection; gameplay {}, files {} in total",
+            limit(self.bandwidth_limit), limit(self.file_bandwidth_limit),
+            limit(self.server_bandwidth_limit), limit(self.server_file_bandwidth_limit));
+        tracing::info!("    Logging: {}{}{}", self.logging.level,
+            self.logging.filters.iter().map(|(m, l)| format!(", {}={}", m, l)).collect::<String>(),
+            match (self.logging.file, self.logging.rotation) {
+                (false, _) => String::new(),
+                (true, rotation) => format!(", logs/ {:?} keep {}", rotation, self.logging.retention).to_lowercase(),
+            });
+        if self.chat_commands.enabled {
+            tracing::info!("    Chat Commands: enabled{}{}",
+                self.chat_commands.permissions.iter().map(|(c, p)| format!(", {}={:?}", c, p).to_lowercase()).collect::<String>(),
+                self.chat_commands.cooldowns.iter().map(|(c, s)| format!(", {} every {}s", c, s)).collect::<String>());
+        }
+        if self.respawn.enabled {
+            tracing::info!("    Respawn: after {}s, dropping gralats {:?}, arrows {:?}, bombs {:?}",
+                self.respawn.delay, self.respawn.gralats, self.respawn.arrows, self.respawn.bombs);
+        }
+        if !self.pid_file.is_empty() || !self.health_address.is_empty() || !self.console_socket.is_empty() {
+            tracing::info!("    Service: PID file {}, health check {}, console socket {}",
+                if self.pid_file.is_empty() { "none" } else { &self.pid_file },
+                if self.health_address.is_empty() { "disabled" } else { &self.health_address },
+                if self.console_socket.is_empty() { "disabled" } else { &self.console_socket });
+        }
+        if self.privacy.chat_log {
+            tracing::info!("    Chat Log: PMs {:?}, RC chat {}, kept {}", self.privacy.chat_log_pms, self.privacy.chat_log_rc,
+                match self.privacy.chat_log_retention {
+                    0 => "forever".to_string(),
+                    days => format!("{} days", days),
+                });
+        }
+        if !self.webhook.url.is_empty() {
+            tracing::info!("    Webhook: {} ({}, max {}/min)",
+                self.webhook.url, self.webhook.events.join(", "), self.webhook.rate_limit);
+        }
+        tracing::info!("    Backups: every {}s, keep {}", self.backup_interval, self.backup_retention);
+        tracing::info!("    Compression: zlib {}, bz2 {}, GEN_5 cutoffs {}/{} bytes{}{}",
+            self.compression.zlib_level, self.compression.bz2_level,
+            self.compression.gen5_zlib_threshold, self.compression.gen5_bz2_threshold,
+            if self.compression.gen5_adaptive { " (adaptive)" } else { "" },
+            if self.compression.encoder_pool { ", pooled" } else { "" });
         tracing::info!("");
         tracing::info!("  [config/adminconfig.txt]");
         tracing::info!("    HQ Level: {} (0=Hidden, 1=Bronze, 2=Silver, 3=Gold)", self.hq_level);
         tracing::info!("    NS IP: {}", self.ns_ip);
+        if !(self.list_icon.is_empty() && self.list_banner.is_empty() && self.list_tags.is_empty()) {
+            tracing::info!("    Listing: icon {}, banner {}, tags [{}]", self.list_icon, self.list_banner, self.list_tags.join(", "));
+        }
         tracing::info!("");
         tracing::info!("  [config/allowedversions.txt]");
         if let Some(ref ver) = self.allowed_versions.original {
@@ -809,7 +1929,7 @@ impl ServerConfig {
         tracing::info!("    Banned IPs: {}", self.ip_bans.len());
         tracing::info!("");
         tracing::info!("  [config/rules.txt]");
-        tracing::info!("    Filtered words: {}", self.word_filter.len());
+        tracing::info!("    Filter rules: {} ({} escalations)", self.word_filter.len(), self.word_filter.escalations.len());
         tracing::info!("");
         tracing::info!("  [config/servermessage.html]");
         if self.server_message.is_empty() {
@@ -831,6 +1951,12 @@ impl ServerConfig {
         tracing::info!("    HP: {}/{}", self.default_account.hp, self.default_account.max_hp);
         tracing::info!("    Weapons: {}", self.default_account.weapons.len());
         tracing::info!("");
+        tracing::info!("  [translations/]");
+        tracing::info!("    Languages: {}", self.translations.len());
+        tracing::info!("");
+        tracing::info!("  [config/updatepackages.txt]");
+        tracing::info!("    Packages: {}", self.update_packages.len());
+        tracing::info!("");
         tracing::info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     }
 }
@@ -853,10 +1979,376 @@ mod tests {
 name = Test Server
 serverport = 9999
 maxplayers = 50
+tickrate = 30
 "#;
         let config = ServerConfig::parse(config_text).unwrap();
         assert_eq!(config.name, "Test Server");
         assert_eq!(config.server_port, 9999);
         assert_eq!(config.max_players, 50);
+        assert_eq!(config.tick_rate, 30);
+    }
+
+    #[test]
+    fn test_parse_updatepackages() {
+        let mut config = ServerConfig::default();
+        config.parse_updatepackages(r#"
+# Base content
+PACKAGE base
+VERSION 3
+FILE logo.png
+FILE idle.gani
+PACKAGEEND
+
+PACKAGE sounds
+FILE hit.wav
+"#);
+
+        assert_eq!(config.update_packages.len(), 2);
+        let base = config.update_package("BASE").unwrap();
+        assert_eq!(base.version, 3);
+        assert_eq!(base.files, vec!["logo.png", "idle.gani"]);
+        assert_eq!(config.update_package("sounds").unwrap().version, 1);
+        assert!(config.update_package("missing").is_none());
+    }
+
+    #[test]
+    fn test_parse_compression_options() {
+        let config_text = r#"
+zliblevel = 12
+bz2level = 0
+gen5zlibthreshold = 100
+compressionpool = true
+gen5adaptive = true
+"#;
+        let config = ServerConfig::parse(config_text).unwrap();
+        assert_eq!(config.compression, CompressionConfig {
+            zlib_level: 9,
+            bz2_level: 1,
+            gen5_zlib_threshold: 100,
+            gen5_bz2_threshold: 0x2000,
+            encoder_pool: true,
+            gen5_adaptive: true,
+        });
+    }
+
+    #[test]
+    fn test_parse_interest_range() {
+        let defaults = ServerConfig::default();
+        assert_eq!((defaults.interest_range, defaults.interest_hysteresis), (64, 8));
+        let config = ServerConfig::parse("interestrange = 0\ninteresthysteresis = 4").unwrap();
+        assert_eq!((config.interest_range, config.interest_hysteresis), (0, 4));
+    }
+
+    #[test]
+    fn test_parse_script_history() {
+        assert_eq!(ServerConfig::default().script_history, 10);
+        assert_eq!(ServerConfig::parse("scripthistory = 0").unwrap().script_history, 0);
+    }
+
+    #[test]
+    fn test_parse_script_memory_limit() {
+        assert_eq!(ServerConfig::default().script_memory_limit, 4096);
+        assert_eq!(ServerConfig::parse("scriptmemorylimit = 0").unwrap().script_memory_limit, 0);
+        assert_eq!(ServerConfig::default().lua_instruction_limit, 1_000_000);
+        assert_eq!(ServerConfig::parse("luainstructionlimit = 5000").unwrap().lua_instruction_limit, 5000);
+        assert_eq!(ServerConfig::default().gs2_instruction_limit, 1_000_000);
+        assert_eq!(ServerConfig::parse("gs2instructionlimit = 0").unwrap().gs2_instruction_limit, 0);
+    }
+
+    #[test]
+    fn test_parse_class_check_interval() {
+        assert_eq!(ServerConfig::default().class_check_interval, 5);
+        assert_eq!(ServerConfig::parse("classcheckinterval = 0").unwrap().class_check_interval, 0);
+    }
+
+    #[test]
+    fn test_parse_level_check_interval() {
+        assert_eq!(ServerConfig::default().level_check_interval, 5);
+        assert_eq!(ServerConfig::parse("levelcheckinterval = 0").unwrap().level_check_interval, 0);
+    }
+
+    #[test]
+    fn test_parse_handler_timing_options() {
+        let config = ServerConfig::default();
+        assert_eq!((config.slow_handler_ms, config.handler_summary_interval), (5, 300));
+        let config = ServerConfig::parse("slowhandlerms = 20\nhandlersummaryinterval = 0").unwrap();
+        assert_eq!((config.slow_handler_ms, config.handler_summary_interval), (20, 0));
+    }
+
+    #[test]
+    fn test_parse_stats_interval() {
+        assert_eq!(ServerConfig::default().stats_interval, 60);
+        assert_eq!(ServerConfig::parse("statsinterval = 0").unwrap().stats_interval, 0);
+    }
+
+    #[test]
+    fn test_parse_economy_alert_options() {
+        let config = ServerConfig::default();
+        assert_eq!((config.economy_alert_gain, config.economy_alert_window), (10_000, 60));
+        let config = ServerConfig::parse("economyalertgain = 500\neconomyalertwindow = 10").unwrap();
+        assert_eq!((config.economy_alert_gain, config.economy_alert_window), (500, 10));
+    }
+
+    #[test]
+    fn test_parse_shutdown_countdown() {
+        assert_eq!(ServerConfig::default().shutdown_countdown, 0);
+        assert_eq!(ServerConfig::parse("shutdowncountdown = 60").unwrap().shutdown_countdown, 60);
+    }
+
+    #[test]
+    fn test_parse_npc_move_rate() {
+        assert_eq!(ServerConfig::default().npc_move_rate, 10);
+        assert_eq!(ServerConfig::parse("npcmoverate = 4").unwrap().npc_move_rate, 4);
+    }
+
+    #[test]
+    fn test_parse_reconnect_grace() {
+        assert_eq!(ServerConfig::default().reconnect_grace, 0);
+        assert_eq!(ServerConfig::parse("reconnectgrace = 45").unwrap().reconnect_grace, 45);
+    }
+
+    #[test]
+    fn test_parse_tls() {
+        let config = ServerConfig::default();
+        assert_eq!((config.tls_bind_address(), config.tls_cert.as_str()), (None, "tls/cert.pem"));
+
+        let config = ServerConfig::parse("serverinterface = 127.0.0.1\ntlsport = 14803\ntlskey = certs/server.key").unwrap();
+        assert_eq!(config.tls_bind_address(), Some("127.0.0.1:14803".parse().unwrap()));
+        assert_eq!(config.tls_key, "certs/server.key");
+    }
+
+    #[test]
+    fn test_parse_proxy_protocol() {
+        assert!(!ServerConfig::default().proxy_protocol);
+        let config = ServerConfig::parse("proxyprotocol = true\nproxytrusted = 10.0.0.5, bogus, ::1").unwrap();
+        assert!(config.proxy_protocol);
+        assert_eq!(config.proxy_trusted, ["10.0.0.5".parse::<IpAddr>().unwrap(), "::1".parse().unwrap()]);
+    }
+
+    #[test]
+    fn test_parse_geoip() {
+        let config = ServerConfig::parse("geoipdb = GeoLite2-Country.mmdb\ngeoipallow = de, at,ch\ngeoipdeny =").unwrap();
+        assert_eq!(config.geoip_db, "GeoLite2-Country.mmdb");
+        assert_eq!(config.geoip_allow, ["DE", "AT", "CH"]);
+        assert!(config.geoip_deny.is_empty());
+    }
+
+    #[test]
+    fn test_parse_listing_extras() {
+        let mut config = ServerConfig::default();
+        config.parse_adminconfig("hq_level = 2\nlist_icon = https://example.com/icon.png\nlist_tags = pvp, , roleplay");
+        assert_eq!(config.list_icon, "https://example.com/icon.png");
+        assert!(config.list_banner.is_empty());
+        assert_eq!(config.list_tags, ["pvp", "roleplay"]);
+    }
+
+    #[test]
+    fn test_parse_login_verification() {
+        let config = ServerConfig::default();
+        assert!(!config.verify_logins);
+        assert_eq!(config.verify_cache_hours, 72);
+        let config = ServerConfig::parse("verifylogins = true\nverifycachehours = 0").unwrap();
+        assert!(config.verify_logins);
+        assert_eq!(config.verify_cache_hours, 0);
+    }
+
+    #[test]
+    fn test_parse_ip_history_days() {
+        assert_eq!(ServerConfig::default().ip_history_days, 90);
+        assert_eq!(ServerConfig::parse("iphistorydays = 30").unwrap().ip_history_days, 30);
+    }
+
+    #[test]
+    fn test_parse_login_policy_options() {
+        let config = ServerConfig::parse("bannedhardware = abc123, def456,\nloginapprovaltimeout = 30").unwrap();
+        assert_eq!(config.banned_hardware, ["abc123", "def456"]);
+        assert_eq!(config.login_approval_timeout, 30);
+    }
+
+    #[test]
+    fn test_parse_record_sessions() {
+        assert!(ServerConfig::default().record_sessions.is_empty());
+        assert_eq!(ServerConfig::parse("recordsessions = Bob, alice").unwrap().record_sessions, ["Bob", "alice"]);
+    }
+
+    #[test]
+    fn test_parse_panic_notify_rc() {
+        assert!(ServerConfig::default().panic_notify_rc);
+        assert!(!ServerConfig::parse("panicnotifyrc = false").unwrap().panic_notify_rc);
+    }
+
+    #[test]
+    fn test_parse_watchdog() {
+        let config = ServerConfig::parse("watchdogtimeout = 10\nwatchdogaction = restart").unwrap();
+        assert_eq!((config.watchdog_timeout, config.watchdog_action.as_str()), (10, "restart"));
+        assert_eq!(ServerConfig::default().watchdog_timeout, 30);
+    }
+
+    #[test]
+    fn test_parse_listserver_alerts() {
+        assert_eq!(ServerConfig::parse("listfailurealert = 5").unwrap().list_failure_alert, 5);
+        assert_eq!(ServerConfig::parse("listfailurealert = x").unwrap().list_failure_alert, 3);
+    }
+
+    #[test]
+    fn test_listserver_addresses() {
+        let config = ServerConfig::parse("listip = list.example.com\nlistport = 14922\n\
+            listservers = other.example.net:14900, LIST.example.com:14922, third.example.org, bad:port").unwrap();
+        assert_eq!(config.listserver_addresses(), [
+            ("list.example.com".to_string(), 14922),
+            ("other.example.net".to_string(), 14900),
+            ("third.example.org".to_string(), 14900),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_local_auth() {
+        assert!(!ServerConfig::default().local_auth);
+        assert!(ServerConfig::parse("localauth = true").unwrap().local_auth);
+    }
+
+    #[test]
+    fn test_parse_duplicate_login() {
+        assert_eq!(ServerConfig::default().duplicate_login, DuplicateLogin::KickOld);
+        assert_eq!(ServerConfig::parse("duplicatelogin = RejectNew").unwrap().duplicate_login, DuplicateLogin::RejectNew);
+        assert_eq!(ServerConfig::parse("duplicatelogin = kickold").unwrap().duplicate_login, DuplicateLogin::KickOld);
+    }
+
+    #[test]
+    fn test_parse_jail_level() {
+        assert!(ServerConfig::default().jail_level.is_empty());
+        assert_eq!(ServerConfig::parse("jaillevel = jail.nw").unwrap().jail_level, "jail.nw");
+    }
+
+    #[test]
+    fn test_parse_logging_options() {
+        assert_eq!(ServerConfig::default().logging, LoggingConfig::default());
+
+        let config_text = r#"
+loglevel = WARN
+logfilters = network=debug, scripting = Trace,broken
+logfile = false
+logrotation = hourly
+logretention = 3
+logjson = true
+"#;
+        let config = ServerConfig::parse(config_text).unwrap();
+        assert_eq!(config.logging, LoggingConfig {
+            level: "warn".into(),
+            filters: vec![("network".into(), "debug".into()), ("scripting".into(), "trace".into())],
+            file: false,
+            rotation: LogRotation::Hourly,
+            retention: 3,
+            json: true,
+        });
+    }
+
+    #[test]
+    fn test_parse_chat_command_options() {
+        let defaults = ServerConfig::default().chat_commands;
+        assert_eq!(defaults.permission("WARP"), ChatCommandPermission::Staff);
+        assert_eq!((defaults.permission("who"), defaults.cooldown("unstick"), defaults.cooldown("pm")),
+            (ChatCommandPermission::All, 30, 0));
+
+        let config_text = r#"
+chatcommandpermissions = who=off, warp = all, pm=broken
+chatcommandcooldowns = pm=2, unstick=x
+unstickmelevel = unstick.nw
+unstickmex = 12.5
+"#;
+        let config = ServerConfig::parse(config_text).unwrap().chat_commands;
+        assert_eq!(config.permissions, vec![
+            ("who".to_string(), ChatCommandPermission::Off),
+            ("warp".to_string(), ChatCommandPermission::All),
+        ]);
+        assert_eq!(config.cooldowns, vec![("pm".to_string(), 2)]);
+        assert_eq!((config.unstick_level.as_str(), config.unstick_x, config.unstick_y), ("unstick.nw", 12.5, 30.5));
+        assert!(!ServerConfig::parse("chatcommands = false").unwrap().chat_commands.enabled);
+    }
+
+    #[test]
+    fn test_parse_respawn_options() {
+        assert_eq!(ServerConfig::default().respawn, RespawnConfig::default());
+
+        let config = ServerConfig::parse("respawn = true\nrespawndelay = 3\ndeathdrops = gralats=10%, Arrows = 5, bombs=200%, hearts=1").unwrap();
+        assert_eq!(config.respawn, RespawnConfig {
+            enabled: true,
+            delay: 3,
+            gralats: DropAmount::Percent(10),
+            arrows: DropAmount::Count(5),
+            bombs: DropAmount::Count(0),
+        });
+        assert_eq!((config.respawn.gralats.of(255), config.respawn.arrows.of(3)), (25, 3));
+    }
+
+    #[test]
+    fn test_parse_outbound_limits() {
+        let defaults = ServerConfig::default();
+        assert_eq!((defaults.outbound_soft_limit, defaults.outbound_hard_limit), (0x40000, 0x100000));
+
+        let config = ServerConfig::parse("outboundsoftlimit = 65536\noutboundhardlimit = 524288\noutboundstalltimeout = 0").unwrap();
+        assert_eq!((config.outbound_soft_limit, config.outbound_hard_limit), (65536, 524288));
+        assert_eq!(config.outbound_stall_timeout, 0);
+        assert_eq!(defaults.bundle_read_timeout, 10);
+        assert_eq!(ServerConfig::parse("bundlereadtimeout = 3").unwrap().bundle_read_timeout, 3);
+
+        let config = ServerConfig::parse("maxbundleplayer = 8192\nmaxbundlerc = 32768\nrcmaxuploadsize = 1048576").unwrap();
+        assert_eq!((config.max_bundle_player, config.max_bundle_rc, config.rc_max_upload_size), (8192, 32768, 1_048_576));
+    }
+
+    #[test]
+    fn test_parse_bandwidth_limits() {
+        assert_eq!(ServerConfig::default().server_file_bandwidth_limit, 0);
+
+        let config = ServerConfig::parse("bandwidthlimit = 64
+filebandwidthlimit = 32
+serverbandwidthlimit = 4096
+serverfilebandwidthlimit = x").unwrap();
+        assert_eq!((config.bandwidth_limit, config.file_bandwidth_limit), (64, 32));
+        assert_eq!((config.server_bandwidth_limit, config.server_file_bandwidth_limit), (4096, 0));
+    }
+
+    #[test]
+    fn test_parse_service_options() {
+        let defaults = ServerConfig::default();
+        assert!(defaults.pid_file.is_empty() && defaults.health_address.is_empty() && defaults.console_socket.is_empty());
+
+        let config = ServerConfig::parse("pidfile = /run/gserver.pid
+healthaddress = 0.0.0.0:14803
+consolesocket = console.sock").unwrap();
+        assert_eq!(config.pid_file, "/run/gserver.pid");
+        assert_eq!(config.health_address, "0.0.0.0:14803");
+        assert_eq!(config.console_socket, "console.sock");
+    }
+
+    #[test]
+    fn test_parse_webhook_options() {
+        let config_text = r#"
+webhookurl = http://127.0.0.1:8080/hook
+webhookevents = Join, chat
+webhookchatlevels = onlinestartlocal.nw
+webhooktemplate.join = **{account}** joined on {level}
+"#;
+        let config = ServerConfig::parse(config_text).unwrap();
+        assert_eq!(config.webhook.url, "http://127.0.0.1:8080/hook");
+        assert_eq!(config.webhook.events, ["join", "chat"]);
+        assert_eq!(config.webhook.chat_levels, ["onlinestartlocal.nw"]);
+        assert_eq!(config.webhook.rate_limit, 30);
+        assert_eq!(config.webhook.templates["join"], "**{account}** joined on {level}");
+    }
+
+    #[test]
+    fn test_parse_privacy_options() {
+        assert_eq!(ServerConfig::default().privacy, PrivacyConfig::default());
+        let config = ServerConfig::parse("chatlog = true
+chatlogpms = Anonymized
+chatlogrc = false
+chatlogretention = 7").unwrap();
+        assert_eq!(config.privacy, PrivacyConfig {
+            chat_log: true,
+            chat_log_pms: PmLogging::Anonymized,
+            chat_log_rc: false,
+            chat_log_retention: 7,
+        });
     }
 }
@@ -0,0 +1,210 @@
+//! Server text translations (translations/*.po)
+//!
+//! Each file in `translations/` is a gettext catalog named after the language
+//! it translates to (`deutsch.po`, `español.po`, ...). Message ids are the
+//! English texts the server sends, so English needs no catalog and any text
+//! without a translation is sent as is.
+//!
+//! ```text
+//! # Disconnect messages
+//! msgid "You were kicked by the word filter"
+//! msgstr "Du wurdest vom Wortfilter gekickt"
+//! ```
+//!
+//! Languages are matched case-insensitively against the client's PLI_LANGUAGE
+//! value. `english.po` may exist but is only used if it's non-empty.
+//!
+//! This mirrors the C++ server's TranslationManager (`TS_Load`/`TS_Translate`).
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Language used when a client doesn't send one
+pub const DEFAULT_LANGUAGE: &str = "English";
+
+/// Translations for all loaded languages
+#[derive(Debug, Clone, Default)]
+pub struct Translations {
+    /// Catalogs keyed by lowercase language name
+    languages: HashMap<String, HashMap<String, String>>,
+}
+
+impl Translations {
+    /// Create an empty set (every text stays English)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load every `*.po` file in a directory
+    ///
+    /// A missing directory yields an empty set; unreadable files are skipped.
+    pub fn load_dir<P: AsRef<Path>>(dir: P) -> Self {
+        let mut translations = Self::new();
+        let Ok(entries) = fs::read_dir(dir.as_ref()) else {
+            return translations;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("po") {
+                continue;
+            }
+            let Some(language) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+
+            match fs::read_to_string(&path) {
+                Ok(content) => translations.add_catalog(language, &content),
+                Err(e) => tracing::warn!("Failed to read translation {:?}: {}", path, e),
+            }
+        }
+
+        translations
+    }
+
+    /// Parse a .po catalog and add it under a language name
+    pub fn add_catalog(&mut self, language: &str, content: &str) {
+        let catalog = parse_po(content);
+        if !catalog.is_empty() {
+            self.languages.insert(language.to_lowercase(), catalog);
+        }
+    }
+
+    /// Get the names of all languages with translations (lowercase)
+    pub fn languages(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.languages.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Get the number of languages with translations
+    pub fn len(&self) -> usize {
+        self.languages.len()
+    }
+
+    /// Check if no translations are loaded
+    pub fn is_empty(&self) -> bool {
+        self.languages.is_empty()
+    }
+
+    /// Translate an English text
+    ///
+    /// Falls back to `text` if the language or the message isn't translated.
+    pub fn translate<'a>(&'a self, language: &str, text: &'a str) -> &'a str {
+        self.languages.get(&language.to_lowercase())
+            .and_then(|catalog| catalog.get(text))
+            .map(String::as_str)
+            .unwrap_or(text)
+    }
+}
+
+/// Which string a continuation line belongs to
+#[derive(Clone, Copy, PartialEq)]
+enum PoField {
+    None,
+    Id,
+    Str,
+}
+
+/// Parse `msgid`/`msgstr` pairs, skipping untranslated (empty) entries
+fn parse_po(content: &str) -> HashMap<String, String> {
+    let mut catalog = HashMap::new();
+    let mut field = PoField::None;
+    let (mut id, mut text) = (String::new(), String::new());
+
+    let mut finish = |id: &mut String, text: &mut String| {
+        if !id.is_empty() && !text.is_empty() {
+            catalog.insert(std::mem::take(id), std::mem::take(text));
+        }
+        id.clear();
+        text.clear();
+    };
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("msgid ") {
+            finish(&mut id, &mut text);
+            field = PoField::Id;
+            id = unquote(rest);
+        } else if let Some(rest) = line.strip_prefix("msgstr ") {
+            field = PoField::Str;
+            text = unquote(rest);
+        } else if line.starts_with('"') {
+            match field {
+                PoField::Id => id.push_str(&unquote(line)),
+                PoField::Str => text.push_str(&unquote(line)),
+                PoField::None => {}
+            }
+        } else {
+            // msgctxt, msgid_plural, ... aren't used by the server
+            field = PoField::None;
+        }
+    }
+    finish(&mut id, &mut text);
+
+    catalog
+}
+
+/// Decode a quoted .po string
+fn unquote(s: &str) -> String {
+    let inner = s.trim().strip_prefix('"').unwrap_or(s);
+    let inner = inner.strip_suffix('"').unwrap_or(inner);
+
+    let mut result = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some(other) => result.push(other),
+            None => {}
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEUTSCH: &str = r#"
+# Header entry
+msgid ""
+msgstr "Content-Type: text/plain; charset=UTF-8\n"
+
+msgid "You were kicked by the word filter"
+msgstr "Du wurdest vom Wortfilter gekickt"
+
+msgid "Welcome back"
+msgstr ""
+"Willkommen "
+"zur\"uck"
+
+msgid "Untranslated"
+msgstr ""
+"#;
+
+    #[test]
+    fn test_translate_with_fallback() {
+        let mut translations = Translations::new();
+        translations.add_catalog("deutsch", DEUTSCH);
+        translations.add_catalog("english", "");
+
+        assert_eq!(translations.languages(), vec!["deutsch"]);
+        assert_eq!(translations.translate("Deutsch", "You were kicked by the word filter"),
+            "Du wurdest vom Wortfilter gekickt");
+        assert_eq!(translations.translate("deutsch", "Welcome back"), "Willkommen zur\"uck");
+        assert_eq!(translations.translate("deutsch", "Untranslated"), "Untranslated");
+        assert_eq!(translations.translate("svenska", "Welcome back"), "Welcome back");
+        assert_eq!(translations.translate(DEFAULT_LANGUAGE, "Welcome back"), "Welcome back");
+    }
+}
@@ -0,0 +1,665 @@
+//! Word filter (rules.txt)
+//!
+//! Parses the C++ rules.txt format and applies its rules to chat, private
+//! messages, nicknames and guild tags.
+//!
+//! # rules.txt Format
+//!
+//! ```text
+//! WARNMESSAGE Please don't use rude words
+//! SHOWWORDSTORC false
+//! MUTETIME 60
+//! ESCALATE 5 mute
+//! ESCALATE 10 kick
+//!
+//! RULE
+//! CHECK pm chat nick toall guild
+//! MATCH f?ck                    (? matches any character, * any run of characters)
+//! PRECISION 80%                 (share or number of characters that must match)
+//! WORDPOSITION start            (start, end, part or full)
+//! SEVERITY 2
+//! ACTION replace tellrc
+//! WARNMESSAGE Watch your language.
+//! RULEEND
+//!
+//! RULE
+//! MATCH /n[o0]+b/               (regular expression, case-insensitive)
+//! ACTION warn
+//! RULEEND
+//! ```
+//!
+//! # Actions
+//!
+//! - `replace` - Mask the matched word with `*`
+//! - `warn` - Block the whole text and show the warn message instead
+//! - `log` - Write the match to the server log
+//! - `tellrc` - Notify connected RCs
+//! - `mute` - Mute the player for `MUTETIME` seconds
+//! - `kick` - Disconnect the player
+//!
+//! Every match also adds the rule's severity (default 1) to the player's
+//! filter points. `ESCALATE <points> <warn|mute|kick>` lines apply an extra
+//! action once a player's points reach the threshold.
+//!
+//! Lines outside RULE blocks that aren't settings are treated as single
+//! words to replace, so old one-word-per-line files keep working.
+//!
+//! # C++ Equivalence
+//! Matches `WordFilter::load` and `WordFilter::apply` in WordFilter.cpp
+
+use regex::Regex;
+
+/// Where a text being filtered comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FilterCheck {
+    /// Chat bubble (PLPROP_CURCHAT)
+    Chat,
+    /// Nickname (PLPROP_NICKNAME)
+    Nick,
+    /// Private message
+    Pm,
+    /// Message to all (PLI_TOALL)
+    ToAll,
+    /// Guild tag (the part of the nickname in parentheses)
+    Guild,
+}
+
+impl FilterCheck {
+    /// All checks (used when a rule has no CHECK line)
+    pub const ALL: [FilterCheck; 5] = [
+        FilterCheck::Chat, FilterCheck::Nick, FilterCheck::Pm, FilterCheck::ToAll, FilterCheck::Guild,
+    ];
+
+    /// Parse a CHECK name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "chat" => Some(FilterCheck::Chat),
+            "nick" => Some(FilterCheck::Nick),
+            "pm" => Some(FilterCheck::Pm),
+            "toall" => Some(FilterCheck::ToAll),
+            "guild" => Some(FilterCheck::Guild),
+            _ => None,
+        }
+    }
+
+    /// Get the CHECK name
+    pub fn name(&self) -> &'static str {
+        match self {
+            FilterCheck::Chat => "chat",
+            FilterCheck::Nick => "nick",
+            FilterCheck::Pm => "pm",
+            FilterCheck::ToAll => "toall",
+            FilterCheck::Guild => "guild",
+        }
+    }
+}
+
+/// Which part of a word a wildcard pattern has to match
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WordPosition {
+    /// Beginning of the word
+    Start,
+    /// End of the word
+    End,
+    /// Anywhere in the word
+    Part,
+    /// The whole word
+    #[default]
+    Full,
+}
+
+/// How many characters of a wildcard pattern have to match
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    /// Share of the pattern length (`PRECISION 80%`)
+    Percent(u8),
+    /// Number of characters (`PRECISION 5`)
+    Chars(usize),
+}
+
+impl Precision {
+    /// Characters required for a pattern of `len` characters
+    fn required(&self, len: usize) -> usize {
+        match *self {
+            Precision::Percent(p) => (len * p.min(100) as usize).div_ceil(100),
+            Precision::Chars(n) => n.min(len),
+        }
+    }
+}
+
+impl Default for Precision {
+    fn default() -> Self {
+        Precision::Percent(100)
+    }
+}
+
+/// Escalation applied once a player collects enough filter points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Escalation {
+    /// Show the warn message
+    Warn,
+    /// Mute for `MUTETIME` seconds
+    Mute,
+    /// Disconnect
+    Kick,
+}
+
+impl Escalation {
+    /// Parse an escalation name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "warn" => Some(Escalation::Warn),
+            "mute" => Some(Escalation::Mute),
+            "kick" => Some(Escalation::Kick),
+            _ => None,
+        }
+    }
+}
+
+/// Actions of a rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FilterActions {
+    /// Mask matched words
+    pub replace: bool,
+    /// Block the text and show the warn message
+    pub warn: bool,
+    /// Log the match
+    pub log: bool,
+    /// Notify RCs
+    pub tell_rc: bool,
+    /// Mute the player
+    pub mute: bool,
+    /// Disconnect the player
+    pub kick: bool,
+}
+
+impl FilterActions {
+    fn parse(value: &str) -> Self {
+        let mut actions = Self::default();
+        for action in value.split_whitespace() {
+            match action.to_ascii_lowercase().as_str() {
+                "replace" => actions.replace = true,
+                "warn" => actions.warn = true,
+                "log" => actions.log = true,
+                "tellrc" => actions.tell_rc = true,
+                "mute" => actions.mute = true,
+                // C++ ban/jail have no equivalent yet; disconnecting is the closest
+                "kick" | "ban" | "jail" => actions.kick = true,
+                other => tracing::warn!("rules.txt: unknown action '{}'", other),
+            }
+        }
+        actions
+    }
+
+    fn merge(&mut self, other: &FilterActions) {
+        self.replace |= other.replace;
+        self.warn |= other.warn;
+        self.log |= other.log;
+        self.tell_rc |= other.tell_rc;
+        self.mute |= other.mute;
+        self.kick |= other.kick;
+    }
+}
+
+/// Rule pattern
+#[derive(Debug, Clone)]
+pub enum FilterPattern {
+    /// Lowercase pattern where `?` matches any character
+    Wildcard(Vec<char>),
+    /// Regular expression (also used for wildcards containing `*`)
+    Regex(Regex),
+}
+
+/// A single RULE block
+#[derive(Debug, Clone)]
+pub struct FilterRule {
+    /// Texts the rule applies to
+    pub checks: Vec<FilterCheck>,
+    /// What to match
+    pub pattern: FilterPattern,
+    /// Original MATCH value
+    pub source: String,
+    /// Characters that must match (wildcards only)
+    pub precision: Precision,
+    /// Part of the word to match (wildcards only)
+    pub position: WordPosition,
+    /// What to do on a match
+    pub actions: FilterActions,
+    /// Filter points added on a match
+    pub severity: u32,
+    /// Message shown instead of the text (falls back to the global one)
+    pub warn_message: Option<String>,
+}
+
+impl FilterRule {
+    /// Create a rule from a MATCH value with default settings
+    ///
+    /// `/.../` values are regular expressions; anything else is a wildcard
+    /// pattern. Returns `None` for an invalid regular expression.
+    pub fn new(pattern: &str) -> Option<Self> {
+        let pattern = pattern.trim();
+        let compiled = if let Some(re) = pattern.strip_prefix('/').and_then(|p| p.strip_suffix('/')) {
+            FilterPattern::Regex(Regex::new(&format!("(?i){}", re)).ok()?)
+        } else if pattern.contains('*') {
+            let re: String = pattern.split('*')
+                .map(|part| regex::escape(part).replace(r"\?", r"\w"))
+                .collect::<Vec<_>>()
+                .join(r"\w*");
+            FilterPattern::Regex(Regex::new(&format!(r"(?i)\b{}\b", re)).ok()?)
+        } else {
+            FilterPattern::Wildcard(pattern.to_lowercase().chars().collect())
+        };
+
+        Some(Self {
+            checks: FilterCheck::ALL.to_vec(),
+            pattern: compiled,
+            source: pattern.to_string(),
+            precision: Precision::default(),
+            position: WordPosition::default(),
+            actions: FilterActions { replace: true, ..Default::default() },
+            severity: 1,
+            warn_message: None,
+        })
+    }
+
+    /// Find matches as character ranges of `chars`
+    fn find(&self, text: &str, chars: &[char]) -> Vec<(usize, usize)> {
+        match &self.pattern {
+            FilterPattern::Regex(re) => re.find_iter(text)
+                .filter(|m| !m.as_str().is_empty())
+                .map(|m| (text[..m.start()].chars().count(), text[..m.end()].chars().count()))
+                .collect(),
+            FilterPattern::Wildcard(pattern) => words(chars)
+                .filter(|&(start, end)| self.word_matches(pattern, &chars[start..end]))
+                .collect(),
+        }
+    }
+
+    fn word_matches(&self, pattern: &[char], word: &[char]) -> bool {
+        let len = pattern.len();
+        if len == 0 || word.len() < len {
+            return false;
+        }
+
+        let required = self.precision.required(len).max(1);
+        let window_matches = |window: &[char]| {
+            let matched = pattern.iter().zip(window)
+                .filter(|(p, c)| **p == '?' || c.to_lowercase().eq(std::iter::once(**p)))
+                .count();
+            matched >= required
+        };
+
+        match self.position {
+            WordPosition::Full => word.len() == len && window_matches(word),
+            WordPosition::Start => window_matches(&word[..len]),
+            WordPosition::End => window_matches(&word[word.len() - len..]),
+            WordPosition::Part => word.windows(len).any(window_matches),
+        }
+    }
+}
+
+/// Result of filtering a text
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FilterResult {
+    /// Text after masking (unchanged if nothing was replaced)
+    pub text: String,
+    /// The text must not be shown (a `warn` rule matched)
+    pub blocked: bool,
+    /// Matched words, as they appeared in the text
+    pub matched: Vec<String>,
+    /// Combined actions of all matched rules
+    pub actions: FilterActions,
+    /// Filter points of all matched rules
+    pub severity: u32,
+    /// Warn message of the first matched rule that has one, otherwise the global one
+    pub warn_message: String,
+}
+
+impl FilterResult {
+    /// Check if no rule matched
+    pub fn is_clean(&self) -> bool {
+        self.matched.is_empty()
+    }
+}
+
+/// Word filter loaded from rules.txt
+#[derive(Debug, Clone)]
+pub struct WordFilter {
+    /// Rules in file order
+    pub rules: Vec<FilterRule>,
+    /// Default warn message (WARNMESSAGE outside a rule)
+    pub warn_message: String,
+    /// Include the matched words in RC notifications (SHOWWORDSTORC)
+    pub show_words_to_rc: bool,
+    /// Mute duration in seconds (MUTETIME)
+    pub mute_time: u64,
+    /// Escalation thresholds, sorted by points (ESCALATE)
+    pub escalations: Vec<(u32, Escalation)>,
+}
+
+impl Default for WordFilter {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            warn_message: "Please don't use rude words".to_string(),
+            show_words_to_rc: false,
+            mute_time: 60,
+            escalations: Vec::new(),
+        }
+    }
+}
+
+impl WordFilter {
+    /// Parse rules.txt
+    ///
+    /// Invalid lines are logged and skipped.
+    pub fn parse(content: &str) -> Self {
+        let mut filter = Self::default();
+        let mut rule: Option<RuleBuilder> = None;
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+                continue;
+            }
+
+            let (key, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let value = value.trim();
+
+            if let Some(builder) = rule.as_mut() {
+                match key.to_ascii_uppercase().as_str() {
+                    "RULEEND" => {
+                        if let Some(r) = rule.take().and_then(RuleBuilder::build) {
+                            filter.rules.push(r);
+                        }
+                    }
+                    "CHECK" => builder.checks = value.split_whitespace().filter_map(FilterCheck::from_name).collect(),
+                    "MATCH" => builder.pattern = Some(value.to_string()),
+                    "PRECISION" => builder.precision = parse_precision(value),
+                    "WORDPOSITION" => builder.position = parse_position(value),
+                    "ACTION" => builder.actions = Some(FilterActions::parse(value)),
+                    "SEVERITY" => builder.severity = value.parse().unwrap_or(1),
+                    "WARNMESSAGE" => builder.warn_message = Some(value.to_string()),
+                    _ => tracing::warn!("rules.txt: unknown rule line '{}'", line),
+                }
+                continue;
+            }
+
+            match key.to_ascii_uppercase().as_str() {
+                "RULE" => rule = Some(RuleBuilder::default()),
+                "WARNMESSAGE" => filter.warn_message = value.to_string(),
+                "SHOWWORDSTORC" => filter.show_words_to_rc = value.eq_ignore_ascii_case("true"),
+                "MUTETIME" => filter.mute_time = value.parse().unwrap_or(60),
+                "ESCALATE" => {
+                    let mut parts = value.split_whitespace();
+                    let points = parts.next().and_then(|p| p.parse().ok());
+                    let action = parts.next().and_then(Escalation::from_name);
+                    match (points, action) {
+                        (Some(points), Some(action)) => filter.escalations.push((points, action)),
+                        _ => tracing::warn!("rules.txt: invalid escalation '{}'", line),
+                    }
+                }
+                // Plain word (old one-word-per-line format)
+                _ => match FilterRule::new(line) {
+                    Some(r) => filter.rules.push(r),
+                    None => tracing::warn!("rules.txt: invalid word '{}'", line),
+                },
+            }
+        }
+
+        if rule.is_some() {
+            tracing::warn!("rules.txt: RULE without RULEEND at end of file");
+        }
+
+        filter.escalations.sort_by_key(|&(points, _)| points);
+        filter
+    }
+
+    /// Get the number of rules
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Check if there are no rules
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Apply all rules for `check` to a text
+    pub fn apply(&self, text: &str, check: FilterCheck) -> FilterResult {
+        let mut chars: Vec<char> = text.chars().collect();
+        let original = chars.clone();
+        let mut result = FilterResult::default();
+        let mut warn_message = None;
+
+        for rule in self.rules.iter().filter(|r| r.checks.contains(&check)) {
+            let matches = rule.find(text, &original);
+            if matches.is_empty() {
+                continue;
+            }
+
+            for &(start, end) in &matches {
+                result.matched.push(original[start..end].iter().collect());
+                if rule.actions.replace {
+                    chars[start..end].iter_mut().for_each(|c| *c = '*');
+                }
+            }
+
+            result.actions.merge(&rule.actions);
+            result.severity += rule.severity;
+            if warn_message.is_none() {
+                warn_message = rule.warn_message.clone();
+            }
+        }
+
+        result.blocked = result.actions.warn;
+        result.text = chars.into_iter().collect();
+        result.warn_message = warn_message.unwrap_or_else(|| self.warn_message.clone());
+        result
+    }
+
+    /// Apply the nickname rules to a nickname and the guild rules to its guild tag
+    ///
+    /// The guild tag is the text in parentheses at the end: `Name (Guild)`.
+    pub fn apply_nickname(&self, nickname: &str) -> FilterResult {
+        let Some((name, guild)) = split_guild(nickname) else {
+            return self.apply(nickname, FilterCheck::Nick);
+        };
+
+        let mut result = self.apply(name, FilterCheck::Nick);
+        let guild_result = self.apply(guild, FilterCheck::Guild);
+
+        result.text = format!("{} ({})", result.text, guild_result.text);
+        result.blocked |= guild_result.blocked;
+        result.matched.extend(guild_result.matched);
+        result.actions.merge(&guild_result.actions);
+        result.severity += guild_result.severity;
+        result
+    }
+
+    /// Get the strongest escalation reached when a player's points go from
+    /// `before` to `after`
+    pub fn escalation(&self, before: u32, after: u32) -> Option<Escalation> {
+        self.escalations.iter()
+            .filter(|&&(points, _)| points > before && points <= after)
+            .map(|&(_, action)| action)
+            .max()
+    }
+}
+
+/// Split `Name (Guild)` into its name and guild tag
+pub fn split_guild(nickname: &str) -> Option<(&str, &str)> {
+    let trimmed = nickname.trim_end();
+    let inner = trimmed.strip_suffix(')')?;
+    let open = inner.rfind('(')?;
+    Some((inner[..open].trim_end(), &inner[open + 1..]))
+}
+
+#[derive(Default)]
+struct RuleBuilder {
+    checks: Vec<FilterCheck>,
+    pattern: Option<String>,
+    precision: Precision,
+    position: WordPosition,
+    actions: Option<FilterActions>,
+    severity: u32,
+    warn_message: Option<String>,
+}
+
+impl RuleBuilder {
+    fn build(self) -> Option<FilterRule> {
+        let Some(pattern) = self.pattern else {
+            tracing::warn!("rules.txt: RULE without MATCH");
+            return None;
+        };
+        let Some(mut rule) = FilterRule::new(&pattern) else {
+            tracing::warn!("rules.txt: invalid MATCH '{}'", pattern);
+            return None;
+        };
+
+        if !self.checks.is_empty() {
+            rule.checks = self.checks;
+        }
+        rule.precision = self.precision;
+        rule.position = self.position;
+        if let Some(actions) = self.actions {
+            rule.actions = actions;
+        }
+        rule.severity = if self.severity == 0 { 1 } else { self.severity };
+        rule.warn_message = self.warn_message;
+        Some(rule)
+    }
+}
+
+fn parse_precision(value: &str) -> Precision {
+    match value.strip_suffix('%') {
+        Some(p) => Precision::Percent(p.trim().parse().unwrap_or(100)),
+        None => value.parse().map(Precision::Chars).unwrap_or_default(),
+    }
+}
+
+fn parse_position(value: &str) -> WordPosition {
+    match value.to_ascii_lowercase().as_str() {
+        "start" => WordPosition::Start,
+        "end" => WordPosition::End,
+        "part" => WordPosition::Part,
+        _ => WordPosition::Full,
+    }
+}
+
+/// Iterate over the words (runs of alphanumeric characters) of a text
+fn words(chars: &[char]) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        while pos < chars.len() && !chars[pos].is_alphanumeric() {
+            pos += 1;
+        }
+        if pos >= chars.len() {
+            return None;
+        }
+        let start = pos;
+        while pos < chars.len() && chars[pos].is_alphanumeric() {
+            pos += 1;
+        }
+        Some((start, pos))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: &str = "\
+WARNMESSAGE Please don't use rude words
+ESCALATE 3 mute
+ESCALATE 6 kick
+
+RULE
+CHECK chat toall
+MATCH f?ck
+WORDPOSITION start
+ACTION replace tellrc
+RULEEND
+
+RULE
+CHECK toall guild
+MATCH badguild
+PRECISION 80%
+SEVERITY 3
+ACTION warn log
+WARNMESSAGE No.
+RULEEND
+
+RULE
+MATCH /n[o0]+b/
+ACTION replace
+RULEEND
+";
+
+    #[test]
+    fn test_parse() {
+        let filter = WordFilter::parse(RULES);
+        assert_eq!(filter.len(), 3);
+        assert_eq!(filter.warn_message, "Please don't use rude words");
+        assert_eq!(filter.escalations, vec![(3, Escalation::Mute), (6, Escalation::Kick)]);
+        assert_eq!(filter.rules[0].position, WordPosition::Start);
+        assert_eq!(filter.rules[1].precision, Precision::Percent(80));
+    }
+
+    #[test]
+    fn test_replace_and_checks() {
+        let filter = WordFilter::parse(RULES);
+
+        let result = filter.apply("what the FECKING hell", FilterCheck::ToAll);
+        assert_eq!(result.text, "what the ******* hell");
+        assert!(result.actions.tell_rc);
+        assert!(!result.blocked);
+
+        // Rule 1 doesn't check nicknames
+        assert!(filter.apply("fecking", FilterCheck::Nick).is_clean());
+
+        let result = filter.apply("you n00b", FilterCheck::Pm);
+        assert_eq!(result.text, "you ****");
+    }
+
+    #[test]
+    fn test_block_with_precision() {
+        let filter = WordFilter::parse(RULES);
+
+        // 8 of 10 characters match
+        let result = filter.apply("hi badguxxd", FilterCheck::ToAll);
+        assert!(result.is_clean());
+        let result = filter.apply("hi badgu1ld", FilterCheck::ToAll);
+        assert!(result.blocked);
+        assert_eq!(result.warn_message, "No.");
+        assert_eq!(result.severity, 3);
+    }
+
+    #[test]
+    fn test_nickname_guild() {
+        let filter = WordFilter::parse(RULES);
+
+        let result = filter.apply_nickname("n00b (badguild)");
+        assert_eq!(result.text, "**** (badguild)");
+        assert!(result.blocked);
+        assert_eq!(split_guild("Bob"), None);
+        assert_eq!(split_guild("Bob (Staff)"), Some(("Bob", "Staff")));
+    }
+
+    #[test]
+    fn test_escalation() {
+        let filter = WordFilter::parse(RULES);
+        assert_eq!(filter.escalation(0, 2), None);
+        assert_eq!(filter.escalation(2, 3), Some(Escalation::Mute));
+        assert_eq!(filter.escalation(0, 7), Some(Escalation::Kick));
+        assert_eq!(filter.escalation(3, 5), None);
+    }
+
+    #[test]
+    fn test_plain_word_lines() {
+        let filter = WordFilter::parse("darn\n*heck*\n");
+        assert_eq!(filter.apply("darn it", FilterCheck::Chat).text, "**** it");
+        assert_eq!(filter.apply("oh checkers", FilterCheck::Chat).text, "oh ********");
+        assert!(filter.apply("darned", FilterCheck::Chat).is_clean());
+    }
+}
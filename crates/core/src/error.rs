@@ -1,4 +1,86 @@
 //! Core error types for GServer
+//!
+//! # Subsystem Errors
+//!
+//! Crates describe their failures with their own enums (`ProtocolError`,
+//! `LoginError`, ...) and wrap them in [`GServerError::Subsystem`]. Each one
+//! says how loud it is ([`Severity`]) and whether the connection that hit it
+//! should be dropped, so the connection loop can decide both in one place
+//! instead of every handler picking a log level.
+//!
+//! [`GServerError::with_context`] attaches the player, packet type and
+//! offset the error happened at.
+
+use crate::types::PlayerID;
+use std::fmt;
+
+/// How loudly an error is logged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Expected noise (a client asking for a missing file)
+    Debug,
+
+    /// Normal operation (a client dropping its connection)
+    Info,
+
+    /// Misbehaving client or recoverable fault
+    Warn,
+
+    /// Server-side fault that needs attention
+    Error,
+}
+
+/// Failure of one subsystem, carried by [`GServerError::Subsystem`]
+pub trait SubsystemError: std::error::Error + Send + Sync + 'static {
+    /// How loudly to log the error
+    fn severity(&self) -> Severity;
+
+    /// Whether the connection that hit the error should be dropped
+    fn disconnects(&self) -> bool;
+}
+
+/// Where an error happened
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// Player whose connection hit the error
+    pub player_id: Option<PlayerID>,
+
+    /// Decoded type of the packet being handled
+    pub packet_type: Option<u8>,
+
+    /// Offset of the packet in its bundle
+    pub offset: Option<usize>,
+}
+
+impl ErrorContext {
+    /// Context for a player's connection
+    pub fn player(id: PlayerID) -> Self {
+        Self { player_id: Some(id), ..Self::default() }
+    }
+
+    /// Add the packet being handled
+    pub fn packet(mut self, packet_type: u8, offset: usize) -> Self {
+        self.packet_type = Some(packet_type);
+        self.offset = Some(offset);
+        self
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(id) = self.player_id {
+            parts.push(format!("player {}", id.get()));
+        }
+        if let Some(packet_type) = self.packet_type {
+            parts.push(format!("packet {}", packet_type));
+        }
+        if let Some(offset) = self.offset {
+            parts.push(format!("offset {}", offset));
+        }
+        f.write_str(&parts.join(", "))
+    }
+}
 
 #[derive(thiserror::Error, Debug)]
 pub enum GServerError {
@@ -28,6 +110,142 @@ pub enum GServerError {
 
     #[error("Invalid data: {0}")]
     InvalidData(String),
+
+    #[error("ID space exhausted: every ID up to {0} is in use")]
+    IdsExhausted(u64),
+
+    #[error("{0}")]
+    Subsystem(Box<dyn SubsystemError>),
+
+    #[error("{source} ({context})")]
+    Context {
+        context: ErrorContext,
+        source: Box<GServerError>,
+    },
+}
+
+impl GServerError {
+    /// Wrap a subsystem error
+    pub fn subsystem<E: SubsystemError>(error: E) -> Self {
+        Self::Subsystem(Box::new(error))
+    }
+
+    /// Attach where the error happened
+    ///
+    /// Context already on the error is kept; `context` only fills in what's
+    /// missing, so the innermost (most precise) context wins.
+    pub fn with_context(self, context: ErrorContext) -> Self {
+        match self {
+            Self::Context { context: inner, source } => Self::Context {
+                context: ErrorContext {
+                    player_id: inner.player_id.or(context.player_id),
+                    packet_type: inner.packet_type.or(context.packet_type),
+                    offset: inner.offset.or(context.offset),
+                },
+                source,
+            },
+            error => Self::Context { context, source: Box::new(error) },
+        }
+    }
+
+    /// Get the attached context, if any
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Self::Context { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Get the error without its context
+    pub fn root(&self) -> &Self {
+        match self {
+            Self::Context { source, .. } => source.root(),
+            error => error,
+        }
+    }
+
+    /// Get the subsystem error of type `E`, if this is one
+    pub fn downcast_ref<E: SubsystemError>(&self) -> Option<&E> {
+        match self.root() {
+            Self::Subsystem(error) => {
+                let error: &(dyn std::error::Error + 'static) = error.as_ref();
+                error.downcast_ref::<E>()
+            }
+            _ => None,
+        }
+    }
+
+    /// How loudly to log the error
+    pub fn severity(&self) -> Severity {
+        match self.root() {
+            Self::Subsystem(error) => error.severity(),
+            Self::NotFound(_) => Severity::Debug,
+            Self::Io(_) => Severity::Info,
+            Self::Protocol(_) | Self::InvalidData(_) | Self::Network(_) => Severity::Warn,
+            Self::Compression(_) | Self::Encryption(_) | Self::Script(_) => Severity::Warn,
+            Self::Config(_) | Self::IdsExhausted(_) => Severity::Error,
+            Self::Context { .. } => unreachable!("root() strips context"),
+        }
+    }
+
+    /// Whether the connection that hit the error should be dropped
+    ///
+    /// Socket and stream failures leave the connection unusable; a bad
+    /// packet only costs that packet.
+    pub fn disconnects(&self) -> bool {
+        match self.root() {
+            Self::Subsystem(error) => error.disconnects(),
+            Self::Io(_) | Self::Network(_) | Self::Compression(_) | Self::Encryption(_) => true,
+            Self::IdsExhausted(_) => true,
+            Self::Protocol(_) | Self::InvalidData(_) | Self::Script(_) => false,
+            Self::Config(_) | Self::NotFound(_) => false,
+            Self::Context { .. } => unreachable!("root() strips context"),
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, GServerError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(thiserror::Error, Debug, PartialEq)]
+    #[error("bad handshake")]
+    struct Handshake;
+
+    impl SubsystemError for Handshake {
+        fn severity(&self) -> Severity {
+            Severity::Info
+        }
+
+        fn disconnects(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_context_keeps_innermost_fields() {
+        let error = GServerError::InvalidData("short".into())
+            .with_context(ErrorContext::default().packet(7, 12))
+            .with_context(ErrorContext::player(PlayerID(3)).packet(1, 0));
+
+        let context = error.context().unwrap();
+        assert_eq!(context.player_id, Some(PlayerID(3)));
+        assert_eq!((context.packet_type, context.offset), (Some(7), Some(12)));
+        assert_eq!(error.to_string(), "Invalid data: short (player 3, packet 7, offset 12)");
+    }
+
+    #[test]
+    fn test_subsystem_policy_passes_through_context() {
+        let error = GServerError::subsystem(Handshake).with_context(ErrorContext::player(PlayerID(1)));
+        assert_eq!(error.severity(), Severity::Info);
+        assert!(error.disconnects());
+        assert_eq!(error.downcast_ref::<Handshake>(), Some(&Handshake));
+
+        let error = GServerError::NotFound("start.nw".into());
+        assert_eq!(error.severity(), Severity::Debug);
+        assert!(!error.disconnects());
+        assert!(error.downcast_ref::<Handshake>().is_none());
+    }
+}
@@ -1,22 +1,68 @@
 //! ID generation with segmented ranges
+//!
+//! # Recycling
+//!
+//! Released IDs are reused once they've been quarantined for a while, so a
+//! packet still in flight for a disconnected player can't land on the player
+//! who gets its ID next. Fresh IDs are handed out up to a cap (the GSHORT
+//! range for player IDs); once fresh IDs run out and nothing has left
+//! quarantine, allocation fails with [`GServerError::IdsExhausted`] instead of
+//! wrapping onto IDs that are still in use.
 
+use crate::error::{GServerError, Result};
 use parking_lot::Mutex;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::atomic::{self, AtomicU64};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Largest value a GSHORT can carry, and so the largest player ID
+pub const MAX_GSHORT_ID: u16 = 28767;
+
+/// How long a released player ID is kept out of circulation
+pub const DEFAULT_ID_QUARANTINE: Duration = Duration::from_secs(30);
 
 /// Thread-safe ID generator with segmented ranges
 pub struct IdGenerator<T: Copy + Into<u64> + TryFrom<u64> + Eq + std::hash::Hash> {
     segments: Mutex<HashMap<T, Vec<T>>>,
     next_id: Arc<AtomicU64>,
+
+    /// Largest ID handed out (inclusive)
+    max_id: u64,
+
+    /// How long released IDs wait before reuse
+    quarantine: Duration,
+
+    /// Released IDs and when they were released, oldest first
+    released: Mutex<VecDeque<(T, Instant)>>,
+
     phantom: std::marker::PhantomData<T>,
 }
 
 impl<T: Copy + Into<u64> + TryFrom<u64> + Eq + std::hash::Hash> IdGenerator<T> {
+    /// Create a generator covering the whole range of `T`, without quarantine
     pub fn new() -> Self {
+        Self::with_limit(u64::MAX, Duration::ZERO)
+    }
+
+    /// Create a generator for player IDs: capped to the GSHORT range, with
+    /// the default quarantine
+    pub fn for_players() -> Self {
+        Self::with_limit(MAX_GSHORT_ID as u64, DEFAULT_ID_QUARANTINE)
+    }
+
+    /// Create a generator with an ID cap and a quarantine period
+    ///
+    /// # Arguments
+    /// * `max_id` - Largest ID to hand out (inclusive)
+    /// * `quarantine` - How long a released ID waits before reuse
+    pub fn with_limit(max_id: u64, quarantine: Duration) -> Self {
         Self {
             segments: Mutex::new(HashMap::new()),
             next_id: Arc::new(AtomicU64::new(0)),
+            max_id,
+            quarantine,
+            released: Mutex::new(VecDeque::new()),
             phantom: std::marker::PhantomData,
         }
     }
@@ -25,20 +71,59 @@ impl<T: Copy + Into<u64> + TryFrom<u64> + Eq + std::hash::Hash> IdGenerator<T> {
     pub fn create_segment(&self, range: std::ops::Range<T>) {
         let mut segments = self.segments.lock();
         let ids: Vec<T> = (range.start.into()..range.end.into())
-            .map(|v| T::try_from(v).ok())
-            .filter_map(|v| v)
+            .filter_map(|v| T::try_from(v).ok())
             .collect();
         segments.insert(range.start, ids);
     }
 
     /// Get the next available ID
-    pub fn get_available_id(&self) -> T {
-        loop {
-            let id = self.next_id.fetch_add(1, atomic::Ordering::Relaxed);
-            if let Ok(id) = T::try_from(id) {
-                return id;
+    ///
+    /// Reuses the oldest released ID that finished its quarantine, otherwise
+    /// hands out a fresh one.
+    ///
+    /// # Errors
+    /// `GServerError::IdsExhausted` if every ID up to the cap is in use or
+    /// still quarantined
+    pub fn get_available_id(&self) -> Result<T> {
+        self.get_available_id_at(Instant::now())
+    }
+
+    /// Return an ID so it can be reused after the quarantine period
+    pub fn release(&self, id: T) {
+        self.release_at(id, Instant::now());
+    }
+
+    /// Get the number of released IDs waiting for reuse
+    pub fn released_count(&self) -> usize {
+        self.released.lock().len()
+    }
+
+    fn get_available_id_at(&self, now: Instant) -> Result<T> {
+        {
+            let mut released = self.released.lock();
+            if released.front().is_some_and(|&(_, at)| now.saturating_duration_since(at) >= self.quarantine) {
+                let (id, _) = released.pop_front().expect("front checked above");
+                return Ok(id);
             }
         }
+
+        // Never move past max_id + 1, so exhaustion doesn't wrap the counter
+        let id = self.next_id.fetch_update(atomic::Ordering::Relaxed, atomic::Ordering::Relaxed, |id| {
+            (id <= self.max_id).then(|| id.saturating_add(1))
+        });
+        let Ok(id) = id else {
+            return Err(GServerError::IdsExhausted(self.max_id));
+        };
+
+        // IDs only grow, so once one doesn't fit in T none after it will
+        T::try_from(id).map_err(|_| GServerError::IdsExhausted(id.saturating_sub(1)))
+    }
+
+    fn release_at(&self, id: T, now: Instant) {
+        let mut released = self.released.lock();
+        if !released.iter().any(|&(other, _)| other == id) {
+            released.push_back((id, now));
+        }
     }
 }
 
@@ -55,8 +140,44 @@ mod tests {
     #[test]
     fn test_id_generation() {
         let gen = IdGenerator::<u16>::new();
-        let id1 = gen.get_available_id();
-        let id2 = gen.get_available_id();
+        let id1 = gen.get_available_id().unwrap();
+        let id2 = gen.get_available_id().unwrap();
         assert_ne!(id1, id2);
     }
+
+    #[test]
+    fn test_exhaustion_is_an_error() {
+        let gen = IdGenerator::<u16>::with_limit(2, Duration::ZERO);
+        let ids: Vec<u16> = (0..3).map(|_| gen.get_available_id().unwrap()).collect();
+        assert_eq!(ids, [0, 1, 2]);
+
+        assert!(matches!(gen.get_available_id(), Err(GServerError::IdsExhausted(2))));
+        assert!(matches!(gen.get_available_id(), Err(GServerError::IdsExhausted(2))));
+
+        gen.release(1);
+        assert_eq!(gen.get_available_id().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_released_ids_wait_for_quarantine() {
+        let gen = IdGenerator::<u16>::with_limit(1, Duration::from_secs(30));
+        let start = Instant::now();
+        assert_eq!(gen.get_available_id_at(start).unwrap(), 0);
+        assert_eq!(gen.get_available_id_at(start).unwrap(), 1);
+
+        gen.release_at(0, start);
+        gen.release_at(0, start);
+        assert_eq!(gen.released_count(), 1);
+        assert!(gen.get_available_id_at(start + Duration::from_secs(10)).is_err());
+        assert_eq!(gen.get_available_id_at(start + Duration::from_secs(30)).unwrap(), 0);
+        assert_eq!(gen.released_count(), 0);
+    }
+
+    #[test]
+    fn test_player_ids_capped_to_gshort() {
+        let gen = IdGenerator::<u16>::for_players();
+        gen.next_id.store(MAX_GSHORT_ID as u64, atomic::Ordering::Relaxed);
+        assert_eq!(gen.get_available_id().unwrap(), MAX_GSHORT_ID);
+        assert!(gen.get_available_id().is_err());
+    }
 }
@@ -4,8 +4,12 @@ mod error;
 mod types;
 mod idgen;
 mod positions;
+mod wildcard;
+mod paths;
 
 pub use error::*;
 pub use types::*;
 pub use idgen::*;
 pub use positions::*;
+pub use wildcard::wildcard_match;
+pub use paths::resolve_ignore_case;
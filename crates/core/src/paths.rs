@@ -0,0 +1,52 @@
+//! Case-insensitive path resolution for content authored on Windows
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Find a path under `base` whose components match `relative` ignoring case
+///
+/// Each component is tried as written first, so only names that differ in
+/// case cost a directory read. Graal content is mostly made on Windows,
+/// where `Images/Logo.PNG` and `images/logo.png` are the same file.
+///
+/// # Returns
+/// The path on disk, or `None` if nothing matches or `relative` leaves `base`
+/// (`..`, absolute paths)
+pub fn resolve_ignore_case(base: &Path, relative: &str) -> Option<PathBuf> {
+    let mut path = base.to_path_buf();
+    for component in Path::new(relative).components() {
+        let name = match component {
+            Component::Normal(name) => name.to_str()?,
+            Component::CurDir => continue,
+            _ => return None,
+        };
+        let exact = path.join(name);
+        if exact.exists() {
+            path = exact;
+            continue;
+        }
+        let found = fs::read_dir(&path).ok()?.flatten()
+            .find(|entry| entry.file_name().to_str().is_some_and(|n| n.eq_ignore_ascii_case(name)))?;
+        path = found.path();
+    }
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_ignore_case() {
+        let dir = std::env::temp_dir().join(format!("gserver-paths-{}", std::process::id()));
+        fs::create_dir_all(dir.join("Images")).unwrap();
+        fs::write(dir.join("Images/Logo.PNG"), b"png").unwrap();
+
+        assert_eq!(resolve_ignore_case(&dir, "images/logo.png"), Some(dir.join("Images/Logo.PNG")));
+        assert_eq!(resolve_ignore_case(&dir, "./Images"), Some(dir.join("Images")));
+        assert_eq!(resolve_ignore_case(&dir, "images/missing.png"), None);
+        assert_eq!(resolve_ignore_case(&dir, "../images"), None);
+        assert_eq!(resolve_ignore_case(&dir, ""), Some(dir.clone()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,6 +1,120 @@
 //! Position types for game objects
+//!
+//! # Units
+//!
+//! Graal mixes three coordinate units:
+//!
+//! - tiles - account files, level boards (64x64), script `x`/`y`
+//! - half-tiles - the GCHAR X/Y player props and PLO_PLAYERWARP
+//! - pixels - X2/Y2 props (16 per tile)
+//!
+//! [`TileCoord`], [`HalfTile`] and [`PixelCoord`] wrap one axis in one unit
+//! so conversions are explicit instead of scattered `* 16` / `/ 8` / `* 2`.
 
 use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Pixels per tile
+pub const PIXELS_PER_TILE: i32 = 16;
+
+/// Pixels per half-tile
+pub const PIXELS_PER_HALF_TILE: i32 = 8;
+
+/// One axis of a position in tiles (fractional for smooth movement)
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct TileCoord(pub f32);
+
+/// One axis of a position in half-tiles (the GCHAR X/Y prop unit)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct HalfTile(pub i16);
+
+/// One axis of a position in pixels
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct PixelCoord(pub i32);
+
+impl TileCoord {
+    /// Convert to pixels (truncating, like the C++ `(int)(x * 16)`)
+    #[inline]
+    pub fn to_pixels(self) -> PixelCoord {
+        PixelCoord((self.0 * PIXELS_PER_TILE as f32) as i32)
+    }
+
+    /// Convert to half-tiles (truncating, like the C++ `(char)(x * 2)`)
+    #[inline]
+    pub fn to_half_tiles(self) -> HalfTile {
+        HalfTile((self.0 * 2.0) as i16)
+    }
+
+    /// Get the board tile this coordinate is in
+    #[inline]
+    pub fn tile_index(self) -> i32 {
+        self.0.floor() as i32
+    }
+}
+
+impl HalfTile {
+    /// Convert to tiles
+    #[inline]
+    pub fn to_tiles(self) -> TileCoord {
+        TileCoord(self.0 as f32 / 2.0)
+    }
+
+    /// Convert to pixels
+    #[inline]
+    pub fn to_pixels(self) -> PixelCoord {
+        PixelCoord(self.0 as i32 * PIXELS_PER_HALF_TILE)
+    }
+}
+
+impl PixelCoord {
+    /// Convert to tiles
+    #[inline]
+    pub fn to_tiles(self) -> TileCoord {
+        TileCoord(self.0 as f32 / PIXELS_PER_TILE as f32)
+    }
+
+    /// Convert to half-tiles (rounding toward negative infinity)
+    #[inline]
+    pub fn to_half_tiles(self) -> HalfTile {
+        HalfTile(self.0.div_euclid(PIXELS_PER_HALF_TILE) as i16)
+    }
+
+    /// Get the board tile this coordinate is in
+    #[inline]
+    pub fn tile_index(self) -> i32 {
+        self.0.div_euclid(PIXELS_PER_TILE)
+    }
+}
+
+impl From<HalfTile> for TileCoord {
+    fn from(value: HalfTile) -> Self {
+        value.to_tiles()
+    }
+}
+
+impl From<PixelCoord> for TileCoord {
+    fn from(value: PixelCoord) -> Self {
+        value.to_tiles()
+    }
+}
+
+impl From<TileCoord> for PixelCoord {
+    fn from(value: TileCoord) -> Self {
+        value.to_pixels()
+    }
+}
+
+impl From<HalfTile> for PixelCoord {
+    fn from(value: HalfTile) -> Self {
+        value.to_pixels()
+    }
+}
+
+impl fmt::Display for TileCoord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
 
 /// Tile-based position (for tiles, NPCs, etc.)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -17,8 +131,8 @@ impl TilePosition {
     /// Convert tile position to pixel position (1 tile = 16 pixels)
     pub const fn to_pixels(self) -> PixelPosition {
         PixelPosition {
-            x: self.x as f32 * 16.0,
-            y: self.y as f32 * 16.0,
+            x: (self.x * PIXELS_PER_TILE) as f32,
+            y: (self.y * PIXELS_PER_TILE) as f32,
         }
     }
 }
@@ -38,8 +152,8 @@ impl PixelPosition {
     /// Convert pixel position to tile position
     pub fn to_tiles(self) -> TilePosition {
         TilePosition {
-            x: (self.x / 16.0).floor() as i32,
-            y: (self.y / 16.0).floor() as i32,
+            x: (self.x / PIXELS_PER_TILE as f32).floor() as i32,
+            y: (self.y / PIXELS_PER_TILE as f32).floor() as i32,
         }
     }
 
@@ -71,6 +185,25 @@ mod tests {
         assert_eq!(tile_pos.y, 3);
     }
 
+    #[test]
+    fn test_axis_conversions() {
+        let x = TileCoord(30.5);
+        assert_eq!(x.to_pixels(), PixelCoord(488));
+        assert_eq!(x.to_half_tiles(), HalfTile(61));
+        assert_eq!(HalfTile(61).to_tiles(), x);
+        assert_eq!(HalfTile(61).to_pixels(), PixelCoord(488));
+        assert_eq!(PixelCoord(488).to_tiles(), x);
+        assert_eq!(PixelCoord(488).to_half_tiles(), HalfTile(61));
+        assert_eq!(TileCoord::from(PixelCoord(20)), TileCoord(1.25));
+    }
+
+    #[test]
+    fn test_negative_coords_round_down() {
+        assert_eq!(PixelCoord(-1).to_half_tiles(), HalfTile(-1));
+        assert_eq!(PixelCoord(-1).tile_index(), -1);
+        assert_eq!(TileCoord(-0.5).tile_index(), -1);
+    }
+
     #[test]
     fn test_distance_calculation() {
         let pos1 = PixelPosition::new(0.0, 0.0);
@@ -0,0 +1,46 @@
+//! Wildcard matching for file and folder patterns
+
+/// Match a name against a `*`/`?` wildcard (case-insensitive)
+///
+/// # C++ Equivalence
+/// Matches `CString::match()`
+pub fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+
+    // Greedy match with backtracking to the last '*'
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.png", "Head1.PNG"));
+        assert!(wildcard_match("*", "anything.gani"));
+        assert!(wildcard_match("body?.png", "body2.png"));
+        assert!(wildcard_match("*a*b", "xaxxb"));
+        assert!(!wildcard_match("*.png", "sound.wav"));
+        assert!(!wildcard_match("body?.png", "body10.png"));
+    }
+}
@@ -0,0 +1,302 @@
+//! # Weather and Ambience
+//!
+//! Server-driven screen effects (rain, snow, night tint) for the whole world,
+//! one gmap or one level. Each effect is an overlay image at a reserved
+//! showimg index, so it doesn't collide with script images; intensity is
+//! 0-100 and off removes the overlay.
+//!
+//! When a player's level is covered by several settings of the same effect,
+//! the most specific one wins: level, then gmap, then world. Turning an effect
+//! off on a level or gmap keeps it off there whatever the world has. Changes can be
+//! scheduled ahead ([`Ambience::schedule`]) to run weather cycles.
+
+use gserver_protocol::ShowImg;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::time::Instant;
+
+/// First showimg index used by ambience overlays
+pub const AMBIENCE_IMAGE_BASE: u8 = 200;
+
+/// Highest intensity
+pub const MAX_INTENSITY: u8 = 100;
+
+/// An ambience effect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AmbienceEffect {
+    /// Falling rain overlay
+    Rain,
+    /// Falling snow overlay
+    Snow,
+    /// Dark blue tint over the screen
+    Night,
+}
+
+impl AmbienceEffect {
+    /// Every effect
+    pub const ALL: [AmbienceEffect; 3] = [Self::Rain, Self::Snow, Self::Night];
+
+    /// Parse an effect name (case-insensitive)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "rain" => Some(Self::Rain),
+            "snow" => Some(Self::Snow),
+            "night" => Some(Self::Night),
+            _ => None,
+        }
+    }
+
+    /// Get the effect name
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Rain => "rain",
+            Self::Snow => "snow",
+            Self::Night => "night",
+        }
+    }
+
+    /// Get the showimg index reserved for the effect
+    pub fn image_index(self) -> u8 {
+        AMBIENCE_IMAGE_BASE + self as u8
+    }
+
+    /// Build the overlay for the effect
+    ///
+    /// # Arguments
+    /// * `intensity` - 0-100, `None` hides the overlay
+    pub fn image(self, intensity: Option<u8>) -> ShowImg {
+        let Some(intensity) = intensity else {
+            // An empty image name removes the overlay
+            return ShowImg::new(self.image_index(), 0.0, 0.0, String::new());
+        };
+        let intensity = intensity.min(MAX_INTENSITY);
+
+        match self {
+            Self::Rain | Self::Snow => {
+                let file = format!("ambience_{}.gif", self.name());
+                ShowImg::new(self.image_index(), 0.0, 0.0, file)
+                    .with_transparent(true)
+                    .with_parallax(true)
+                    .with_animation(String::new(), u32::from(intensity))
+            }
+            Self::Night => {
+                // Darker with intensity, keeping a blue cast
+                let shade = 255 - (u16::from(intensity) * 200 / 100) as u8;
+                ShowImg::new(self.image_index(), 0.0, 0.0, "ambience_night.png".to_string())
+                    .with_color(shade, shade, shade.max(96))
+                    .with_transparent(true)
+                    .with_zoom(16 * 256)
+            }
+        }
+    }
+}
+
+impl fmt::Display for AmbienceEffect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Where an ambience setting applies (names are stored lowercase)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AmbienceScope {
+    /// Every level
+    All,
+    /// Every level of a gmap
+    Gmap(String),
+    /// One level
+    Level(String),
+}
+
+impl AmbienceScope {
+    /// Parse a scope: empty or `all`, a `.gmap` file, or a level name
+    pub fn parse(text: &str) -> Self {
+        let text = text.trim().to_lowercase();
+        if text.is_empty() || text == "all" {
+            Self::All
+        } else if text.ends_with(".gmap") {
+            Self::Gmap(text)
+        } else {
+            Self::Level(text)
+        }
+    }
+
+    /// Rank for "most specific wins"
+    fn specificity(&self) -> u8 {
+        match self {
+            Self::All => 0,
+            Self::Gmap(_) => 1,
+            Self::Level(_) => 2,
+        }
+    }
+}
+
+impl fmt::Display for AmbienceScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::All => f.write_str("all levels"),
+            Self::Gmap(name) | Self::Level(name) => f.write_str(name),
+        }
+    }
+}
+
+/// A scheduled ambience change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmbienceTransition {
+    /// When the change happens
+    pub at: Instant,
+    /// Where it applies
+    pub scope: AmbienceScope,
+    /// Which effect
+    pub effect: AmbienceEffect,
+    /// New intensity, `None` turns the effect off
+    pub intensity: Option<u8>,
+}
+
+/// Current and scheduled ambience settings
+#[derive(Debug, Default)]
+pub struct Ambience {
+    /// `None` is an explicit off (only kept for gmaps and levels)
+    settings: HashMap<(AmbienceScope, AmbienceEffect), Option<u8>>,
+    gmaps: HashMap<String, HashSet<String>>,
+    scheduled: Vec<AmbienceTransition>,
+}
+
+impl Ambience {
+    /// Create ambience with every effect off
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the levels of a gmap (needed before gmap settings cover them)
+    pub fn register_gmap(&mut self, gmap: &str, levels: impl IntoIterator<Item = String>) {
+        let levels = levels.into_iter().map(|level| level.to_lowercase()).collect();
+        self.gmaps.insert(gmap.to_lowercase(), levels);
+    }
+
+    /// Check if a gmap's levels are known
+    pub fn has_gmap(&self, gmap: &str) -> bool {
+        self.gmaps.contains_key(&gmap.to_lowercase())
+    }
+
+    /// Change an effect now
+    ///
+    /// # Returns
+    /// `true` if the setting changed
+    pub fn set(&mut self, scope: AmbienceScope, effect: AmbienceEffect, intensity: Option<u8>) -> bool {
+        let intensity = intensity.map(|i| i.min(MAX_INTENSITY));
+        if intensity.is_none() && scope == AmbienceScope::All {
+            return self.settings.remove(&(scope, effect)).is_some();
+        }
+        self.settings.insert((scope, effect), intensity) != Some(intensity)
+    }
+
+    /// Schedule a change
+    pub fn schedule(&mut self, transition: AmbienceTransition) {
+        self.scheduled.push(transition);
+        self.scheduled.sort_by_key(|t| t.at);
+    }
+
+    /// Apply the scheduled changes that are due
+    ///
+    /// # Returns
+    /// The (scope, effect) pairs that changed, in schedule order
+    pub fn run_due(&mut self, now: Instant) -> Vec<(AmbienceScope, AmbienceEffect)> {
+        let due = self.scheduled.partition_point(|t| t.at <= now);
+        let mut changed = Vec::new();
+        for t in self.scheduled.drain(..due).collect::<Vec<_>>() {
+            if self.set(t.scope.clone(), t.effect, t.intensity) {
+                changed.push((t.scope, t.effect));
+            }
+        }
+        changed
+    }
+
+    /// Get the scheduled changes, soonest first
+    pub fn scheduled(&self) -> &[AmbienceTransition] {
+        &self.scheduled
+    }
+
+    /// Drop every scheduled change
+    pub fn clear_schedule(&mut self) {
+        self.scheduled.clear();
+    }
+
+    /// Check if a scope covers a level
+    pub fn covers(&self, scope: &AmbienceScope, level: &str) -> bool {
+        match scope {
+            AmbienceScope::All => true,
+            AmbienceScope::Level(name) => name.eq_ignore_ascii_case(level),
+            AmbienceScope::Gmap(gmap) => self.gmaps.get(gmap)
+                .is_some_and(|levels| levels.contains(&level.to_lowercase())),
+        }
+    }
+
+    /// Get an effect's intensity on a level
+    ///
+    /// # Returns
+    /// The most specific setting covering the level, `None` if the effect is off
+    pub fn effective(&self, level: &str, effect: AmbienceEffect) -> Option<u8> {
+        self.settings.iter()
+            .filter(|((scope, e), _)| *e == effect && self.covers(scope, level))
+            .max_by_key(|((scope, _), _)| scope.specificity())
+            .and_then(|(_, &intensity)| intensity)
+    }
+
+    /// Get the overlays a player entering a level needs
+    pub fn images_for(&self, level: &str) -> Vec<ShowImg> {
+        AmbienceEffect::ALL.iter()
+            .filter_map(|&effect| self.effective(level, effect).map(|i| effect.image(Some(i))))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_most_specific_setting_wins() {
+        let mut ambience = Ambience::new();
+        ambience.register_gmap("World.gmap", ["world_a1.nw".to_string(), "world_a2.nw".to_string()]);
+        ambience.set(AmbienceScope::All, AmbienceEffect::Rain, Some(30));
+        ambience.set(AmbienceScope::parse("world.gmap"), AmbienceEffect::Rain, Some(80));
+        ambience.set(AmbienceScope::parse("World_A2.nw"), AmbienceEffect::Rain, None);
+        ambience.set(AmbienceScope::parse("world_a2.nw"), AmbienceEffect::Rain, Some(5));
+
+        assert_eq!(ambience.effective("house.nw", AmbienceEffect::Rain), Some(30));
+        assert_eq!(ambience.effective("WORLD_A1.nw", AmbienceEffect::Rain), Some(80));
+        assert_eq!(ambience.effective("world_a2.nw", AmbienceEffect::Rain), Some(5));
+        assert_eq!(ambience.effective("house.nw", AmbienceEffect::Night), None);
+        assert_eq!(ambience.images_for("house.nw").len(), 1);
+
+        // Off on a level overrides the world setting
+        assert!(ambience.set(AmbienceScope::parse("house.nw"), AmbienceEffect::Rain, None));
+        assert_eq!(ambience.effective("house.nw", AmbienceEffect::Rain), None);
+        assert_eq!(ambience.effective("world_a1.nw", AmbienceEffect::Rain), Some(80));
+        assert!(ambience.images_for("house.nw").is_empty());
+    }
+
+    #[test]
+    fn test_scheduled_transitions() {
+        let mut ambience = Ambience::new();
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let change = |secs, intensity| AmbienceTransition {
+            at: at(secs),
+            scope: AmbienceScope::All,
+            effect: AmbienceEffect::Snow,
+            intensity,
+        };
+        ambience.schedule(change(120, None));
+        ambience.schedule(change(60, Some(50)));
+
+        assert!(ambience.run_due(at(59)).is_empty());
+        assert_eq!(ambience.run_due(at(60)), [(AmbienceScope::All, AmbienceEffect::Snow)]);
+        assert_eq!(ambience.effective("a.nw", AmbienceEffect::Snow), Some(50));
+        ambience.run_due(at(500));
+        assert_eq!(ambience.effective("a.nw", AmbienceEffect::Snow), None);
+        assert!(ambience.scheduled().is_empty());
+    }
+}
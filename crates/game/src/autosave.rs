@@ -0,0 +1,252 @@
+//! # Autosave Service
+//!
+//! This module periodically saves game state so a crash loses at most one
+//! autosave interval of progress.
+//!
+//! # Architecture
+//!
+//! Each kind of persistent state (accounts, server flags, levels) implements
+//! [`AutosaveTarget`] and only writes what changed since its last save. The
+//! service runs on its own task, separate from the tick loop, and saves on a
+//! blocking thread because saving does file I/O.
+//!
+//! Saves are scheduled every `interval ± jitter` so that several servers
+//! sharing a disk don't all write at the same moment. A zero interval
+//! disables the periodic saves. When the service is shut down it performs
+//! one final save of every target, with or without periodic saves.
+//!
+//! # C++ Equivalence
+//!
+//! Replaces the periodic `saveAccount()` / `saveServerFlags()` calls in
+//! `Server::doTimedEvents` (every 5 minutes).
+
+use gserver_core::Result;
+use rand::Rng;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// Default autosave interval (seconds)
+pub const DEFAULT_AUTOSAVE_INTERVAL: u64 = 300;
+
+/// Something the autosave service can save
+pub trait AutosaveTarget: Send + Sync {
+    /// Target name (for logging)
+    fn name(&self) -> &str;
+
+    /// Save everything that changed since the last save
+    ///
+    /// # Returns
+    /// The number of items written (0 if nothing was dirty)
+    fn save(&self) -> Result<usize>;
+}
+
+/// Autosave schedule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutosaveConfig {
+    /// Time between saves, zero for no periodic saves
+    pub interval: Duration,
+
+    /// Maximum random offset applied to each interval
+    pub jitter: Duration,
+}
+
+impl AutosaveConfig {
+    /// Create a schedule with the given interval and 10% jitter
+    pub fn with_interval(interval: Duration) -> Self {
+        Self {
+            interval,
+            jitter: interval / 10,
+        }
+    }
+
+    /// Get the delay until the next save (`interval ± jitter`)
+    pub fn next_delay(&self) -> Duration {
+        let jitter_ms = self.jitter.as_millis() as i64;
+        if jitter_ms == 0 {
+            return self.interval;
+        }
+
+        let offset = rand::thread_rng().gen_range(-jitter_ms..=jitter_ms);
+        let delay_ms = (self.interval.as_millis() as i64 + offset).max(1);
+        Duration::from_millis(delay_ms as u64)
+    }
+}
+
+impl Default for AutosaveConfig {
+    fn default() -> Self {
+        Self::with_interval(Duration::from_secs(DEFAULT_AUTOSAVE_INTERVAL))
+    }
+}
+
+/// Periodic saver for all registered targets
+pub struct AutosaveService {
+    /// Save schedule
+    config: AutosaveConfig,
+
+    /// Registered targets, saved in registration order
+    targets: Vec<Arc<dyn AutosaveTarget>>,
+}
+
+impl AutosaveService {
+    /// Create a service with no targets
+    pub fn new(config: AutosaveConfig) -> Self {
+        Self {
+            config,
+            targets: Vec::new(),
+        }
+    }
+
+    /// Register a target
+    pub fn add_target(&mut self, target: Arc<dyn AutosaveTarget>) {
+        tracing::debug!("Registered autosave target '{}'", target.name());
+        self.targets.push(target);
+    }
+
+    /// Get the save schedule
+    pub fn config(&self) -> &AutosaveConfig {
+        &self.config
+    }
+
+    /// Save all targets now
+    ///
+    /// A failing target is logged and does not stop the others from saving.
+    ///
+    /// # Returns
+    /// The total number of items written
+    pub fn save_all(&self) -> usize {
+        let start = Instant::now();
+        let mut total = 0;
+
+        for target in &self.targets {
+            match target.save() {
+                Ok(count) => {
+                    if count > 0 {
+                        tracing::debug!("Autosave '{}': saved {} items", target.name(), count);
+                    }
+                    total += count;
+                }
+                Err(e) => {
+                    tracing::error!("Autosave '{}' failed: {}", target.name(), e);
+                }
+            }
+        }
+
+        if total > 0 {
+            tracing::info!("Autosave complete: {} items in {:?}", total, start.elapsed());
+        }
+        total
+    }
+
+    /// Save all targets on a blocking thread
+    async fn save_all_blocking(self: &Arc<Self>) {
+        let service = self.clone();
+        if let Err(e) = tokio::task::spawn_blocking(move || service.save_all()).await {
+            tracing::error!("Autosave failed: {}", e);
+        }
+    }
+
+    /// Run until `shutdown` becomes `true` (or its sender is dropped),
+    /// then perform a final save
+    pub async fn run(self, mut shutdown: watch::Receiver<bool>) {
+        let service = Arc::new(self);
+        let periodic = !service.config.interval.is_zero();
+        if periodic {
+            tracing::info!("Autosave started: every {:?} (±{:?}), {} targets",
+                service.config.interval, service.config.jitter, service.targets.len());
+        } else {
+            tracing::info!("Autosave started: on shutdown only, {} targets", service.targets.len());
+        }
+
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(service.config.next_delay()), if periodic => {
+                    service.save_all_blocking().await;
+                }
+                result = shutdown.changed() => {
+                    if result.is_err() || *shutdown.borrow() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        tracing::info!("Autosave shutting down, performing final save");
+        service.save_all_blocking().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountingTarget {
+        saves: AtomicUsize,
+        fail: bool,
+    }
+
+    impl AutosaveTarget for CountingTarget {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn save(&self) -> Result<usize> {
+            if self.fail {
+                return Err(gserver_core::GServerError::InvalidData("disk full".into()));
+            }
+            self.saves.fetch_add(1, Ordering::Relaxed);
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn test_jitter_bounds() {
+        let config = AutosaveConfig::with_interval(Duration::from_secs(100));
+        for _ in 0..100 {
+            let delay = config.next_delay();
+            assert!(delay >= Duration::from_secs(90) && delay <= Duration::from_secs(110));
+        }
+    }
+
+    #[test]
+    fn test_failing_target_does_not_block_others() {
+        let mut service = AutosaveService::new(AutosaveConfig::default());
+        let good = Arc::new(CountingTarget { saves: AtomicUsize::new(0), fail: false });
+        service.add_target(Arc::new(CountingTarget { saves: AtomicUsize::new(0), fail: true }));
+        service.add_target(good.clone());
+
+        assert_eq!(service.save_all(), 1);
+        assert_eq!(good.saves.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_final_save_on_shutdown() {
+        let mut service = AutosaveService::new(AutosaveConfig::with_interval(Duration::from_secs(3600)));
+        let target = Arc::new(CountingTarget { saves: AtomicUsize::new(0), fail: false });
+        service.add_target(target.clone());
+
+        let (tx, rx) = watch::channel(false);
+        let handle = tokio::spawn(service.run(rx));
+        tx.send(true).unwrap();
+        handle.await.unwrap();
+
+        assert_eq!(target.saves.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_zero_interval_saves_on_shutdown_only() {
+        let mut service = AutosaveService::new(AutosaveConfig::with_interval(Duration::ZERO));
+        let target = Arc::new(CountingTarget { saves: AtomicUsize::new(0), fail: false });
+        service.add_target(target.clone());
+
+        let (tx, rx) = watch::channel(false);
+        let handle = tokio::spawn(service.run(rx));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(target.saves.load(Ordering::Relaxed), 0);
+
+        tx.send(true).unwrap();
+        handle.await.unwrap();
+        assert_eq!(target.saves.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,210 @@
+//! # Carrying and Throwing
+//!
+//! A player lifts something by setting the CarrySprite prop (and CarryNPC for
+//! an NPC) and throws it with PLI_THROWCARRIED. The server checks the pickup
+//! before anyone else sees it:
+//!
+//! - The carry sprite must be a known object ([`CarriedObject`]).
+//! - An NPC must be on the player's level, flagged `canbecarried`, within
+//!   [`NPC_REACH_PIXELS`] and not already carried by someone else.
+//!
+//! A thrown object lands [`THROW_DISTANCE_PIXELS`] ahead of the thrower and
+//! hurts the players it lands on ([`CarriedObject::impact_power`]). A thrown
+//! NPC is moved to where it lands; bushes, vases and stones break there on
+//! the clients.
+//!
+//! # C++ Equivalence
+//! Matches `PlayerClient::msgPLI_THROWCARRIED` and the carry props of
+//! `PlayerClient::setProps`.
+
+use gserver_core::PlayerID;
+use std::collections::HashMap;
+
+/// CarrySprite value for carrying nothing
+pub const CARRY_NONE: u8 = 255;
+
+/// Farthest an NPC can be lifted from (pixels, centre to centre)
+pub const NPC_REACH_PIXELS: i32 = 48;
+
+/// How far a thrown object flies (pixels)
+pub const THROW_DISTANCE_PIXELS: i32 = 64;
+
+/// Distance from the landing spot at which players are hit (pixels)
+pub const IMPACT_RADIUS_PIXELS: i32 = 16;
+
+/// Level size in pixels (64 tiles of 16 pixels)
+const LEVEL_PIXELS: i32 = 64 * 16;
+
+/// Something a player can carry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CarriedObject {
+    /// Bomb (sprite 0)
+    Bomb,
+    /// Bush (sprite 1)
+    Bush,
+    /// Stone (sprite 3)
+    Stone,
+    /// Vase (sprite 5)
+    Vase,
+    /// Sign (sprite 7)
+    Sign,
+    /// Super bomb (sprite 61)
+    SuperBomb,
+    /// Jolt bomb (sprite 87)
+    JoltBomb,
+    /// Hot jolt bomb (sprite 88)
+    HotJoltBomb,
+    /// Hot bomb (sprite 200)
+    HotBomb,
+    /// Black stone (sprite 201)
+    BlackStone,
+    /// A level NPC by id
+    Npc(u32),
+}
+
+impl CarriedObject {
+    /// Get the carried object from the CarrySprite and CarryNPC props
+    ///
+    /// # Returns
+    /// `Ok(None)` when carrying nothing, `Err(sprite)` for an unknown sprite
+    pub fn from_props(carry_sprite: u8, carry_npc: u32) -> Result<Option<Self>, u8> {
+        if carry_npc != 0 {
+            return Ok(Some(Self::Npc(carry_npc)));
+        }
+        Ok(Some(match carry_sprite {
+            CARRY_NONE => return Ok(None),
+            0 => Self::Bomb,
+            1 => Self::Bush,
+            3 => Self::Stone,
+            5 => Self::Vase,
+            7 => Self::Sign,
+            61 => Self::SuperBomb,
+            87 => Self::JoltBomb,
+            88 => Self::HotJoltBomb,
+            200 => Self::HotBomb,
+            201 => Self::BlackStone,
+            other => return Err(other),
+        }))
+    }
+
+    /// Get the damage done to a player hit by the object (half hearts)
+    ///
+    /// Bombs explode on the client instead of hitting anyone.
+    pub fn impact_power(self) -> u8 {
+        match self {
+            Self::Bush | Self::Vase | Self::Sign | Self::Npc(_) => 1,
+            Self::Stone | Self::BlackStone => 2,
+            Self::Bomb | Self::SuperBomb | Self::JoltBomb | Self::HotJoltBomb | Self::HotBomb => 0,
+        }
+    }
+}
+
+/// Check if an NPC is close enough to lift
+pub fn within_reach(player: (i32, i32), npc: (i32, i32)) -> bool {
+    distance_squared(player, npc) <= NPC_REACH_PIXELS * NPC_REACH_PIXELS
+}
+
+/// Get where a thrown object lands
+///
+/// # Arguments
+/// * `from` - Thrower position in pixels
+/// * `direction` - Thrower direction (0 up, 1 left, 2 down, 3 right)
+///
+/// # Returns
+/// The landing spot in pixels, kept inside the level
+pub fn throw_landing(from: (i32, i32), direction: u8) -> (i32, i32) {
+    let (dx, dy) = match direction % 4 {
+        0 => (0, -THROW_DISTANCE_PIXELS),
+        1 => (-THROW_DISTANCE_PIXELS, 0),
+        2 => (0, THROW_DISTANCE_PIXELS),
+        _ => (THROW_DISTANCE_PIXELS, 0),
+    };
+    let clamp = |v: i32| v.clamp(0, LEVEL_PIXELS - 16);
+    (clamp(from.0 + dx), clamp(from.1 + dy))
+}
+
+/// Check if a player at `target` is hit by an object landing at `landing`
+pub fn hits(landing: (i32, i32), target: (i32, i32)) -> bool {
+    distance_squared(landing, target) <= IMPACT_RADIUS_PIXELS * IMPACT_RADIUS_PIXELS
+}
+
+fn distance_squared(a: (i32, i32), b: (i32, i32)) -> i32 {
+    let (dx, dy) = (a.0 - b.0, a.1 - b.1);
+    dx * dx + dy * dy
+}
+
+/// Which player carries which NPC
+#[derive(Debug, Default)]
+pub struct CarryTracker {
+    npcs: HashMap<u32, PlayerID>,
+}
+
+impl CarryTracker {
+    /// Create a tracker with nothing carried
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Claim an NPC for a player
+    ///
+    /// # Returns
+    /// `false` if another player carries it
+    pub fn claim_npc(&mut self, player: PlayerID, npc: u32) -> bool {
+        self.release(player);
+        match self.npcs.get(&npc) {
+            Some(&carrier) if carrier != player => false,
+            _ => {
+                self.npcs.insert(npc, player);
+                true
+            }
+        }
+    }
+
+    /// Release whatever NPC a player carries
+    ///
+    /// # Returns
+    /// The NPC that was carried
+    pub fn release(&mut self, player: PlayerID) -> Option<u32> {
+        let npc = self.npcs.iter().find(|&(_, &carrier)| carrier == player).map(|(&npc, _)| npc)?;
+        self.npcs.remove(&npc);
+        Some(npc)
+    }
+
+    /// Get the player carrying an NPC
+    pub fn carrier(&self, npc: u32) -> Option<PlayerID> {
+        self.npcs.get(&npc).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_carried_object_from_props() {
+        assert_eq!(CarriedObject::from_props(CARRY_NONE, 0), Ok(None));
+        assert_eq!(CarriedObject::from_props(1, 0), Ok(Some(CarriedObject::Bush)));
+        assert_eq!(CarriedObject::from_props(CARRY_NONE, 9), Ok(Some(CarriedObject::Npc(9))));
+        assert_eq!(CarriedObject::from_props(42, 0), Err(42));
+    }
+
+    #[test]
+    fn test_throw_and_impact() {
+        assert_eq!(throw_landing((160, 160), 3), (224, 160));
+        assert_eq!(throw_landing((8, 8), 0), (8, 0));
+        assert!(hits((224, 160), (230, 170)));
+        assert!(!hits((224, 160), (160, 160)));
+        assert!(within_reach((100, 100), (130, 120)));
+        assert!(!within_reach((100, 100), (160, 100)));
+    }
+
+    #[test]
+    fn test_npc_has_one_carrier() {
+        let mut tracker = CarryTracker::new();
+        assert!(tracker.claim_npc(PlayerID(1), 7));
+        assert!(!tracker.claim_npc(PlayerID(2), 7));
+        assert_eq!(tracker.release(PlayerID(1)), Some(7));
+        assert!(tracker.claim_npc(PlayerID(2), 7));
+        assert_eq!(tracker.carrier(7), Some(PlayerID(2)));
+    }
+}
@@ -0,0 +1,218 @@
+//! # Script Classes
+//!
+//! This module loads and tracks the server's script classes
+//! (`scripts/<name>.txt`). A class is plain script code that weapons and
+//! NPCs pull in with `join("name")`; the file holds the script and nothing
+//! else, with the clientside part after `//#CLIENTSIDE` like a weapon.
+//!
+//! Classes change at runtime when NC edits one or someone edits the file.
+//! [`ClassManager::reload_changed`] picks up edits made on disk, and
+//! [`WeaponManager::invalidate_class`](crate::WeaponManager::invalidate_class)
+//! finds the weapons that have to be rebuilt.
+//!
+//! # C++ Equivalence
+//! Matches the `scripts/` class files loaded by `TServer::loadClasses`
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// A script class
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptClass {
+    /// Class name (file name without `.txt`)
+    pub name: String,
+
+    /// Full class script
+    pub script: String,
+
+    /// Modification time of the file (unix seconds), 0 if never saved
+    pub mod_time: u64,
+}
+
+impl ScriptClass {
+    /// Get the clientside part of the script (after `//#CLIENTSIDE`)
+    pub fn clientside_script(&self) -> &str {
+        match self.script.find("//#CLIENTSIDE") {
+            Some(pos) => &self.script[pos..],
+            None => "",
+        }
+    }
+}
+
+/// Class Manager
+///
+/// # Purpose
+/// Tracks all classes known to the server and provides lookup by name.
+/// Names are case-insensitive, like `join()`.
+///
+/// # Thread Safety
+/// All operations are thread-safe using DashMap for concurrent access.
+pub struct ClassManager {
+    /// All classes
+    /// Key: lowercase class name, Value: class
+    classes: dashmap::DashMap<String, Arc<ScriptClass>>,
+
+    /// Directory containing class files
+    classes_dir: PathBuf,
+}
+
+impl ClassManager {
+    /// Create an empty class manager
+    ///
+    /// # Arguments
+    /// * `classes_dir` - Directory containing `<name>.txt` class files
+    pub fn new<P: Into<PathBuf>>(classes_dir: P) -> Self {
+        Self {
+            classes: dashmap::DashMap::new(),
+            classes_dir: classes_dir.into(),
+        }
+    }
+
+    /// Load all classes from the classes directory
+    ///
+    /// # Returns
+    /// The number of classes loaded. A missing directory loads nothing.
+    pub fn load_all(&self) -> usize {
+        let loaded = self.class_files().iter().filter(|path| self.load_file(path).is_some()).count();
+        tracing::info!("Loaded {} classes from {:?}", loaded, self.classes_dir);
+        loaded
+    }
+
+    /// Load a single class file
+    pub fn load_file(&self, path: &Path) -> Option<Arc<ScriptClass>> {
+        let name = path.file_stem()?.to_str()?.to_string();
+        let bytes = fs::read(path).ok()?;
+        let class = Arc::new(ScriptClass {
+            name,
+            script: String::from_utf8_lossy(&bytes).into_owned(),
+            mod_time: file_mod_time(path),
+        });
+        self.classes.insert(class.name.to_lowercase(), class.clone());
+        Some(class)
+    }
+
+    /// Reload classes whose files changed since they were loaded
+    ///
+    /// New files are loaded and classes whose file was deleted are removed.
+    ///
+    /// # Returns
+    /// Names of the classes that were added, changed or removed
+    pub fn reload_changed(&self) -> Vec<String> {
+        let mut changed = Vec::new();
+        let mut on_disk = HashSet::new();
+
+        for path in self.class_files() {
+            let Some(name) = path.file_stem().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            on_disk.insert(name.to_lowercase());
+            let known = self.get_class(name).map(|c| c.mod_time);
+            if known != Some(file_mod_time(&path)) {
+                if let Some(class) = self.load_file(&path) {
+                    changed.push(class.name.clone());
+                }
+            }
+        }
+
+        // Classes that only exist in memory (set_class failed to save) stay
+        let removed: Vec<String> = self.classes.iter()
+            .filter(|c| c.mod_time != 0 && !on_disk.contains(c.key()))
+            .map(|c| c.name.clone())
+            .collect();
+        for name in removed {
+            self.classes.remove(&name.to_lowercase());
+            changed.push(name);
+        }
+        changed
+    }
+
+    /// Add or replace a class and save it to its file
+    ///
+    /// # Returns
+    /// The new class. It is kept even if saving fails.
+    pub fn set_class(&self, name: &str, script: &str) -> io::Result<Arc<ScriptClass>> {
+        let path = self.classes_dir.join(format!("{}.txt", name));
+        let saved = fs::create_dir_all(&self.classes_dir).and_then(|_| fs::write(&path, script));
+
+        let class = Arc::new(ScriptClass {
+            name: name.to_string(),
+            script: script.to_string(),
+            mod_time: if saved.is_ok() { file_mod_time(&path) } else { 0 },
+        });
+        self.classes.insert(name.to_lowercase(), class.clone());
+        saved.map(|_| class)
+    }
+
+    /// Remove a class by name (the file is left alone)
+    pub fn remove_class(&self, name: &str) -> Option<Arc<ScriptClass>> {
+        self.classes.remove(&name.to_lowercase()).map(|(_, c)| c)
+    }
+
+    /// Get a class by name
+    pub fn get_class(&self, name: &str) -> Option<Arc<ScriptClass>> {
+        self.classes.get(&name.to_lowercase()).map(|c| c.clone())
+    }
+
+    /// Get the names of all classes
+    pub fn class_names(&self) -> Vec<String> {
+        self.classes.iter().map(|c| c.name.clone()).collect()
+    }
+
+    /// Get the number of classes
+    pub fn class_count(&self) -> usize {
+        self.classes.len()
+    }
+
+    /// Get the classes directory
+    pub fn classes_dir(&self) -> &Path {
+        &self.classes_dir
+    }
+
+    /// List the `.txt` files in the classes directory
+    fn class_files(&self) -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(&self.classes_dir) else {
+            return Vec::new();
+        };
+        entries.flatten()
+            .map(|e| e.path())
+            .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "txt"))
+            .collect()
+    }
+}
+
+/// Get the modification time of a file (unix seconds), 0 if unknown
+pub(crate) fn file_mod_time(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_and_reload_classes() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = ClassManager::new(dir.path());
+        manager.set_class("movement", "function onCreated() {}\n//#CLIENTSIDE\nfunction onTimeout() {}\n").unwrap();
+        assert_eq!(manager.get_class("Movement").unwrap().clientside_script(), "//#CLIENTSIDE\nfunction onTimeout() {}\n");
+
+        let other = ClassManager::new(dir.path());
+        assert_eq!(other.load_all(), 1);
+        assert!(other.reload_changed().is_empty());
+
+        fs::write(dir.path().join("shop.txt"), "//#CLIENTSIDE\n").unwrap();
+        fs::remove_file(dir.path().join("movement.txt")).unwrap();
+        let mut changed = other.reload_changed();
+        changed.sort();
+        assert_eq!(changed, ["movement", "shop"]);
+        assert_eq!(other.class_names(), ["shop"]);
+    }
+}
@@ -0,0 +1,518 @@
+//! # Event Bus
+//!
+//! This module provides typed notifications between subsystems.
+//!
+//! # Purpose
+//!
+//! A player logging in has many side effects: the listserver player list is
+//! updated, RCs are notified, scripts receive `onPlayerLogin`, metrics count a
+//! session. Instead of the connection code calling each of these directly, it
+//! publishes a [`GameEvent`] and every interested subsystem subscribes.
+//!
+//! # Delivery
+//!
+//! Events are delivered through a Tokio broadcast channel:
+//! - Publishing never blocks and never fails (no subscribers is fine)
+//! - Each subscriber receives every event published after it subscribed
+//! - A subscriber that falls more than `capacity` events behind skips the
+//!   oldest ones and receives `RecvError::Lagged`
+//!
+//! Requests that must not be skipped (sanctions, record edits, disconnects)
+//! are [`GameCommand`]s instead, sent over the [`CommandQueue`] to the one
+//! task that carries them out.
+//!
+//! # Example
+//!
+//! ```rust
+//! use gserver_game::events::{EventBus, GameEvent};
+//! use gserver_core::PlayerID;
+//!
+//! let bus = EventBus::new();
+//! let mut rx = bus.subscribe();
+//!
+//! bus.publish(GameEvent::PlayerLeft { id: PlayerID::new(1), account: "Bob".into() });
+//! assert!(matches!(rx.try_recv(), Ok(GameEvent::PlayerLeft { .. })));
+//! ```
+
+use crate::ambience::{AmbienceEffect, AmbienceScope};
+use crate::freeze::ControlAction;
+use crate::groups::InstanceKey;
+use crate::hits::HitArea;
+use crate::moderation::{RecordEdit, Sanction};
+use crate::social::SocialEdit;
+use gserver_core::PlayerID;
+use gserver_protocol::PacketTypeOut;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc};
+
+/// Default number of events buffered per subscriber
+pub const DEFAULT_EVENT_CAPACITY: usize = 1024;
+
+/// Notification published by one subsystem for the others
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    /// A player finished logging in
+    PlayerJoined {
+        /// Player ID
+        id: PlayerID,
+        /// Account name
+        account: String,
+        /// Starting level
+        level: String,
+    },
+
+    /// A player disconnected
+    PlayerLeft {
+        /// Player ID
+        id: PlayerID,
+        /// Account name
+        account: String,
+    },
+
+    /// Tiles on a level were changed (PLI_BOARDMODIFY)
+    LevelModified {
+        /// Level name
+        level: String,
+        /// Modified rectangle, in tiles
+        x: u8,
+        y: u8,
+        width: u8,
+        height: u8,
+    },
+
+    /// A player said something (PLI_TOALL)
+    ChatMessage {
+        /// Sender
+        id: PlayerID,
+        /// Level the sender is on
+        level: String,
+        /// Message text
+        message: String,
+    },
+
+    /// A flag was set or deleted
+    FlagChanged {
+        /// Player whose flag changed, `None` for server flags
+        id: Option<PlayerID>,
+        /// Flag name
+        name: String,
+        /// New value, `None` if the flag was deleted
+        value: Option<String>,
+    },
+
+    /// A client failed an integrity check and RCs should be told
+    IntegrityViolation {
+        /// Offending player
+        id: PlayerID,
+        /// Account name
+        account: String,
+        /// What was detected
+        reason: String,
+    },
+
+    /// A word filter rule with `tellrc` matched
+    WordFilterMatch {
+        /// Player who wrote the text
+        id: PlayerID,
+        /// Account name
+        account: String,
+        /// Where the text came from (`toall`, `nick`, ...)
+        check: String,
+        /// Matched words (empty unless SHOWWORDSTORC is set)
+        words: Vec<String>,
+    },
+
+    /// An RC said something in staff chat (PLI_RC_CHAT)
+    RcChat {
+        /// Sending RC
+        id: PlayerID,
+        /// Sender's account name
+        account: String,
+        /// Message text
+        message: String,
+        /// Account the message is for (`/msg`), `None` for the whole channel
+        to: Option<String>,
+    },
+
+    /// Something staff should know about (bans, server errors)
+    StaffNotice {
+        /// Notice text
+        message: String,
+    },
+
+    /// A serverside script failed to compile or run
+    ScriptError {
+        /// Owner name (weapon, class, `npc{id}`)
+        script: String,
+        /// Source line, if known
+        line: Option<usize>,
+        /// Player the script ran for (if any)
+        player: Option<PlayerID>,
+        /// Error text
+        message: String,
+    },
+
+    /// A line was said in a listserver IRC channel
+    IrcMessage {
+        /// Channel name
+        channel: String,
+        /// Name shown as the sender
+        from: String,
+        /// Message text
+        message: String,
+        /// Player on this server who said it, `None` for scripts and other servers
+        sender: Option<PlayerID>,
+    },
+
+    /// An ambience effect changed (RC, script or schedule)
+    AmbienceChanged {
+        /// Where it changed
+        scope: AmbienceScope,
+        /// Which effect
+        effect: AmbienceEffect,
+    },
+
+    /// Everyone on a level should join a group (`gr.setlevelgroup`)
+    LevelGroupSet {
+        /// Level name
+        level: String,
+        /// Group name (empty leaves groups)
+        group: String,
+    },
+
+    /// A packet for the players of one level instance
+    InstancePacket {
+        /// Level and group
+        instance: InstanceKey,
+        /// Player who caused it and already knows
+        except: Option<PlayerID>,
+        /// Packet type
+        packet_type: PacketTypeOut,
+        /// Packet body
+        data: Vec<u8>,
+    },
+
+    /// A packet for every player on a level, whatever their group
+    /// (serverside NPCs, which all instances share)
+    LevelPacket {
+        /// Level name
+        level: String,
+        /// Packet type
+        packet_type: PacketTypeOut,
+        /// Packet body
+        data: Vec<u8>,
+    },
+
+    /// A level's links, signs, chests or baddies were reloaded from disk;
+    /// its players have to fetch it again
+    LevelReloaded {
+        /// Level name
+        level: String,
+    },
+
+    /// A packet for one player (scripted pushes and the like)
+    PlayerPacket {
+        /// Receiving player
+        player: PlayerID,
+        /// Packet type
+        packet_type: PacketTypeOut,
+        /// Packet body
+        data: Vec<u8>,
+    },
+
+    /// A player moved or changed how it looks (see [`crate::interest`])
+    PlayerPropsChanged {
+        /// The player
+        player: PlayerID,
+        /// `{GCHAR prop}{value}` of the props the players in range get,
+        /// empty if only the position changed (a level warp)
+        data: Vec<u8>,
+    },
+
+    /// A carried object was thrown and landed (see [`crate::carry`])
+    ObjectThrown {
+        /// Player who threw it
+        thrower: PlayerID,
+        /// Instance it landed in
+        instance: InstanceKey,
+        /// Landing spot in pixels
+        landing: (i32, i32),
+        /// Damage to players it lands on (half hearts)
+        power: u8,
+    },
+
+    /// A sword swing or explosion hit an area (see [`crate::hits`])
+    AreaHit {
+        /// Player who swung or set off the explosion
+        attacker: PlayerID,
+        /// Instance it happened in
+        instance: InstanceKey,
+        /// Hit area
+        area: HitArea,
+        /// Damage (half hearts)
+        power: u8,
+    },
+
+    /// A weapon was rebuilt and players who have it need the new build
+    /// (see [`crate::weapons::WeaponBuild`])
+    WeaponChanged {
+        /// Weapon name
+        name: String,
+        /// Checksum of the new build
+        checksum: u32,
+    },
+
+    /// A player sent a PM (PLI_PRIVATEMESSAGE)
+    PrivateMessage {
+        /// Sender
+        from: PlayerID,
+        /// Sender's account, checked against each recipient's lists
+        account: String,
+        /// Recipients
+        to: Vec<PlayerID>,
+        /// PLO_PRIVATEMESSAGE body
+        data: Vec<u8>,
+    },
+
+    /// An admin message popup (PLO_RC_ADMINMESSAGE) for one player, or for
+    /// everyone with `to: None`
+    AdminMessage {
+        /// Recipient
+        to: Option<PlayerID>,
+        /// Shown before the message (`Admin <account>`, `Server`)
+        from: String,
+        /// Message text
+        message: String,
+    },
+
+    /// The listserver answered the verification of a player's login (logins
+    /// waiting for it are told here; [`GameCommand::LoginVerified`] acts on it)
+    LoginVerified {
+        /// Player that logged in
+        player: PlayerID,
+        /// Account it logged in on
+        account: String,
+        /// The listserver accepted the login
+        verified: bool,
+    },
+}
+
+/// Request for the server to act on an account or player
+///
+/// Commands go through the [`CommandQueue`], not the [`EventBus`]: a
+/// lagging subscriber skips events, but a sanction or a disconnect must not
+/// be lost.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameCommand {
+    /// Freeze or release the players of an account (RC `/freeze` and friends)
+    ControlRequested {
+        /// Account name
+        account: String,
+        /// What to do
+        action: ControlAction,
+    },
+
+    /// Mute, jail or warp home an account (RC `/mute` and friends, scripts)
+    SanctionRequested {
+        /// Account name
+        account: String,
+        /// What to do
+        sanction: Sanction,
+    },
+
+    /// Disconnect a player with a message (another login took its account)
+    DisconnectRequested {
+        /// Player to disconnect
+        player: PlayerID,
+        /// Disconnect message
+        reason: String,
+    },
+
+    /// Change the comments, ban record or gralats of an account (RC dialogs,
+    /// `/ban`, `/setgralats`)
+    RecordEdited {
+        /// Account name
+        account: String,
+        /// The change
+        edit: RecordEdit,
+    },
+
+    /// Change the friend or ignore list of an online account (RC `/social`)
+    SocialEdited {
+        /// Account name
+        account: String,
+        /// The change
+        edit: SocialEdit,
+    },
+
+    /// Staff rights changed; online connections re-evaluate theirs (see `staffrights`)
+    StaffRightsChanged {
+        /// Account whose rights were set, `None` after the staff list changed
+        account: Option<String>,
+        /// New rights of that account
+        rights: Option<u32>,
+    },
+
+    /// The listserver answered the verification of a player's login; the
+    /// answer is cached and a rejected player is disconnected
+    LoginVerified {
+        /// Player that logged in
+        player: PlayerID,
+        /// Account it logged in on
+        account: String,
+        /// The listserver accepted the login
+        verified: bool,
+    },
+}
+
+/// Broadcast bus for [`GameEvent`]s
+///
+/// # Thread Safety
+/// Cloning the bus is cheap; all clones publish to the same subscribers.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    /// Broadcast sender (subscribers are created from it)
+    sender: broadcast::Sender<GameEvent>,
+}
+
+impl EventBus {
+    /// Create a bus with the default capacity
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_EVENT_CAPACITY)
+    }
+
+    /// Create a bus buffering up to `capacity` events per subscriber
+    pub fn with_capacity(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// Publish an event to all current subscribers
+    ///
+    /// # Returns
+    /// The number of subscribers the event was delivered to
+    pub fn publish(&self, event: GameEvent) -> usize {
+        tracing::trace!("Event: {:?}", event);
+        self.sender.send(event).unwrap_or(0)
+    }
+
+    /// Subscribe to all future events
+    pub fn subscribe(&self) -> broadcast::Receiver<GameEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Get the number of active subscribers
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Unbounded queue of [`GameCommand`]s with a single consumer
+///
+/// # Thread Safety
+/// Cloning the queue is cheap; all clones send to the same consumer.
+#[derive(Debug, Clone)]
+pub struct CommandQueue {
+    /// Sending end
+    sender: mpsc::UnboundedSender<GameCommand>,
+
+    /// Receiving end, until the consumer takes it
+    receiver: Arc<Mutex<Option<mpsc::UnboundedReceiver<GameCommand>>>>,
+}
+
+impl CommandQueue {
+    /// Create an empty queue
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self { sender, receiver: Arc::new(Mutex::new(Some(receiver))) }
+    }
+
+    /// Queue a command
+    ///
+    /// # Returns
+    /// `false` if the consumer is gone
+    pub fn send(&self, command: GameCommand) -> bool {
+        tracing::trace!("Command: {:?}", command);
+        self.sender.send(command).is_ok()
+    }
+
+    /// Take the receiving end; only the first call gets it
+    pub fn take_receiver(&self) -> Option<mpsc::UnboundedReceiver<GameCommand>> {
+        self.receiver.lock().take()
+    }
+}
+
+impl Default for CommandQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_publish_without_subscribers() {
+        let bus = EventBus::new();
+        let delivered = bus.publish(GameEvent::FlagChanged {
+            id: None,
+            name: "server.test".into(),
+            value: Some("1".into()),
+        });
+        assert_eq!(delivered, 0);
+    }
+
+    #[tokio::test]
+    async fn test_all_subscribers_receive() {
+        let bus = EventBus::new();
+        let mut rx1 = bus.subscribe();
+        let mut rx2 = bus.clone().subscribe();
+        assert_eq!(bus.subscriber_count(), 2);
+
+        let event = GameEvent::ChatMessage {
+            id: PlayerID::new(3),
+            level: "onlinestartlocal.nw".into(),
+            message: "hello".into(),
+        };
+        assert_eq!(bus.publish(event.clone()), 2);
+
+        assert_eq!(rx1.recv().await.unwrap(), event);
+        assert_eq!(rx2.recv().await.unwrap(), event);
+    }
+
+    #[test]
+    fn test_lagged_subscriber() {
+        let bus = EventBus::with_capacity(2);
+        let mut rx = bus.subscribe();
+
+        for i in 0..4 {
+            bus.publish(GameEvent::PlayerLeft { id: PlayerID::new(i), account: String::new() });
+        }
+
+        assert!(matches!(rx.try_recv(), Err(broadcast::error::TryRecvError::Lagged(2))));
+        assert!(matches!(rx.try_recv(), Ok(GameEvent::PlayerLeft { .. })));
+    }
+
+    #[test]
+    fn test_commands_are_not_dropped() {
+        let commands = CommandQueue::new();
+        for i in 0..DEFAULT_EVENT_CAPACITY as u16 * 2 {
+            commands.send(GameCommand::DisconnectRequested { player: PlayerID::new(i), reason: String::new() });
+        }
+
+        let mut rx = commands.clone().take_receiver().unwrap();
+        assert!(commands.take_receiver().is_none());
+        for i in 0..DEFAULT_EVENT_CAPACITY as u16 * 2 {
+            assert_eq!(rx.try_recv().unwrap(), GameCommand::DisconnectRequested { player: PlayerID::new(i), reason: String::new() });
+        }
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,91 @@
+//! # Frozen Players
+//!
+//! Scripts (`freezeplayer2`, `unfreezeplayer`, `fullstop`) and RC (`/freeze`,
+//! `/unfreeze`, `/fullstop`) can stop a player. The client is told with
+//! PLO_FREEZEPLAYER2 or PLO_FULLSTOP, and the server remembers the player is
+//! frozen so movement props sent anyway are ignored until PLO_UNFREEZEPLAYER.
+
+use gserver_core::PlayerID;
+use gserver_protocol::PacketTypeOut;
+use std::collections::HashMap;
+
+/// A freeze request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlAction {
+    /// Stop the player's movement (PLO_FREEZEPLAYER2)
+    Freeze,
+    /// Stop the player's input and scripts too (PLO_FULLSTOP)
+    FullStop,
+    /// Release either kind of freeze (PLO_UNFREEZEPLAYER)
+    Unfreeze,
+}
+
+impl ControlAction {
+    /// Get the packet that tells the client
+    pub fn packet_type(self) -> PacketTypeOut {
+        match self {
+            Self::Freeze => PacketTypeOut::FreezePlayer2,
+            Self::FullStop => PacketTypeOut::FullStop,
+            Self::Unfreeze => PacketTypeOut::UnfreezePlayer,
+        }
+    }
+}
+
+/// Which players are frozen and how
+#[derive(Debug, Default)]
+pub struct FrozenPlayers {
+    players: HashMap<PlayerID, ControlAction>,
+}
+
+impl FrozenPlayers {
+    /// Create with nobody frozen
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a request
+    ///
+    /// # Returns
+    /// `false` if it changes nothing (unfreezing a player who isn't frozen,
+    /// or repeating the current freeze)
+    pub fn apply(&mut self, id: PlayerID, action: ControlAction) -> bool {
+        match action {
+            ControlAction::Unfreeze => self.players.remove(&id).is_some(),
+            freeze => self.players.insert(id, freeze) != Some(freeze),
+        }
+    }
+
+    /// Check if a player's movement is ignored
+    pub fn is_frozen(&self, id: PlayerID) -> bool {
+        self.players.contains_key(&id)
+    }
+
+    /// Get how a player is frozen
+    pub fn action(&self, id: PlayerID) -> Option<ControlAction> {
+        self.players.get(&id).copied()
+    }
+
+    /// Remove a player (on disconnect)
+    pub fn forget_player(&mut self, id: PlayerID) {
+        self.players.remove(&id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_freeze_until_released() {
+        let mut frozen = FrozenPlayers::new();
+        let id = PlayerID(4);
+        assert!(!frozen.apply(id, ControlAction::Unfreeze));
+        assert!(frozen.apply(id, ControlAction::Freeze));
+        assert!(!frozen.apply(id, ControlAction::Freeze));
+        assert!(frozen.apply(id, ControlAction::FullStop));
+        assert!(frozen.is_frozen(id));
+
+        assert!(frozen.apply(id, ControlAction::Unfreeze));
+        assert!(!frozen.is_frozen(id));
+    }
+}
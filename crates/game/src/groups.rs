@@ -0,0 +1,226 @@
+//! # Player Groups and Level Instances
+//!
+//! A player can be put in a group with the `gr.setgroup` trigger action
+//! (`gr.setlevelgroup` puts everyone on the player's level in it). Players of
+//! different groups on the same level are in separate instances of it: each
+//! (level, group) pair has its own dropped items and NPC props, and item and
+//! NPC prop updates only reach players of the same instance. Everything else
+//! sent to a level (player props, chat, hits) still reaches every player on
+//! it. Players without a group share the level's main instance.
+//!
+//! A group change takes effect when the player next enters a level. An
+//! instance is dropped once its group has no members left, so an instanced
+//! dungeon starts fresh for the next party.
+//!
+//! # C++ Equivalence
+//! Matches the `gr.setgroup` / `gr.setlevelgroup` trigger commands.
+
+use gserver_core::PlayerID;
+use std::collections::HashMap;
+
+/// Largest stored NPC prop history before it is reset to the latest update
+pub const MAX_NPC_PROPS_LEN: usize = 4096;
+
+/// One instance of a level
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InstanceKey {
+    /// Level name (lowercase)
+    pub level: String,
+    /// Group, `None` for the main instance
+    pub group: Option<String>,
+}
+
+impl InstanceKey {
+    /// Create a key
+    pub fn new(level: &str, group: Option<&str>) -> Self {
+        Self {
+            level: level.to_lowercase(),
+            group: group.map(str::to_string),
+        }
+    }
+}
+
+/// Item and NPC state of one level instance
+#[derive(Debug, Clone, Default)]
+pub struct LevelInstance {
+    /// Dropped items by (x, y) in half tiles
+    items: HashMap<(u8, u8), u8>,
+
+    /// Prop updates of each NPC, in arrival order
+    npc_props: HashMap<u32, Vec<u8>>,
+}
+
+impl LevelInstance {
+    /// Add a dropped item (replacing one at the same spot)
+    pub fn add_item(&mut self, x: u8, y: u8, item: u8) {
+        self.items.insert((x, y), item);
+    }
+
+    /// Remove the item at a spot
+    pub fn remove_item(&mut self, x: u8, y: u8) -> Option<u8> {
+        self.items.remove(&(x, y))
+    }
+
+    /// Get the dropped items as (x, y, item)
+    pub fn items(&self) -> Vec<(u8, u8, u8)> {
+        let mut items: Vec<_> = self.items.iter().map(|(&(x, y), &item)| (x, y, item)).collect();
+        items.sort_unstable();
+        items
+    }
+
+    /// Record an NPC prop update
+    ///
+    /// Updates are kept as sent and replayed in order, so later props win on
+    /// the client. A history over [`MAX_NPC_PROPS_LEN`] is reset to the
+    /// latest update.
+    pub fn update_npc(&mut self, id: u32, props: &[u8]) {
+        let history = self.npc_props.entry(id).or_default();
+        if history.len() + props.len() > MAX_NPC_PROPS_LEN {
+            history.clear();
+        }
+        history.extend_from_slice(props);
+    }
+
+    /// Get the recorded props of every NPC
+    pub fn npcs(&self) -> Vec<(u32, &[u8])> {
+        let mut npcs: Vec<_> = self.npc_props.iter().map(|(&id, props)| (id, props.as_slice())).collect();
+        npcs.sort_unstable_by_key(|&(id, _)| id);
+        npcs
+    }
+
+    /// Check if the instance holds nothing
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty() && self.npc_props.is_empty()
+    }
+}
+
+/// Group trigger actions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupTrigger {
+    /// `gr.setgroup,<group>` - Put the player in a group (empty leaves it)
+    SetGroup(String),
+    /// `gr.setlevelgroup,<group>` - Put everyone on the player's level in a group
+    SetLevelGroup(String),
+}
+
+impl GroupTrigger {
+    /// Parse a trigger action string
+    ///
+    /// # Returns
+    /// `None` if the action isn't a group trigger
+    pub fn parse(action: &str) -> Option<Self> {
+        let (command, group) = action.split_once(',').unwrap_or((action, ""));
+        let group = group.trim().to_string();
+        match command.trim() {
+            "gr.setgroup" => Some(Self::SetGroup(group)),
+            "gr.setlevelgroup" => Some(Self::SetLevelGroup(group)),
+            _ => None,
+        }
+    }
+}
+
+/// Player groups and the level instances they use
+#[derive(Debug, Default)]
+pub struct Groups {
+    players: HashMap<PlayerID, String>,
+    instances: HashMap<InstanceKey, LevelInstance>,
+}
+
+impl Groups {
+    /// Create with no groups
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Put a player in a group (empty name leaves the group)
+    ///
+    /// # Returns
+    /// The previous group
+    pub fn set_group(&mut self, id: PlayerID, group: &str) -> Option<String> {
+        let previous = if group.is_empty() {
+            self.players.remove(&id)
+        } else {
+            self.players.insert(id, group.to_string())
+        };
+        if let Some(previous) = &previous {
+            self.drop_unused(previous);
+        }
+        previous
+    }
+
+    /// Get a player's group
+    pub fn group(&self, id: PlayerID) -> Option<&str> {
+        self.players.get(&id).map(String::as_str)
+    }
+
+    /// Get the instance of a level a player is in
+    pub fn instance_key(&self, id: PlayerID, level: &str) -> InstanceKey {
+        InstanceKey::new(level, self.group(id))
+    }
+
+    /// Get an instance
+    pub fn instance(&self, key: &InstanceKey) -> Option<&LevelInstance> {
+        self.instances.get(key)
+    }
+
+    /// Get an instance, creating it if needed
+    pub fn instance_mut(&mut self, key: InstanceKey) -> &mut LevelInstance {
+        self.instances.entry(key).or_default()
+    }
+
+    /// Get the number of instances with state
+    pub fn instance_count(&self) -> usize {
+        self.instances.len()
+    }
+
+    /// Remove a player (on disconnect)
+    pub fn forget_player(&mut self, id: PlayerID) {
+        if let Some(group) = self.players.remove(&id) {
+            self.drop_unused(&group);
+        }
+    }
+
+    /// Drop a group's instances if it has no members left
+    fn drop_unused(&mut self, group: &str) {
+        if !self.players.values().any(|g| g == group) {
+            self.instances.retain(|key, _| key.group.as_deref() != Some(group));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_groups_get_separate_instances() {
+        let mut groups = Groups::new();
+        let (alice, bob, carol) = (PlayerID(1), PlayerID(2), PlayerID(3));
+        groups.set_group(alice, "party1");
+        groups.set_group(bob, "party1");
+
+        groups.instance_mut(groups.instance_key(alice, "Dungeon.nw")).add_item(10, 20, 3);
+        assert_eq!(groups.instance(&groups.instance_key(bob, "dungeon.nw")).unwrap().items(), [(10, 20, 3)]);
+        assert!(groups.instance(&groups.instance_key(carol, "dungeon.nw")).is_none());
+
+        // The instance lives until the last member leaves the group
+        groups.forget_player(alice);
+        assert_eq!(groups.instance_count(), 1);
+        assert_eq!(groups.set_group(bob, ""), Some("party1".to_string()));
+        assert_eq!(groups.instance_count(), 0);
+    }
+
+    #[test]
+    fn test_parse_trigger_and_npc_history() {
+        assert_eq!(GroupTrigger::parse("gr.setgroup,red team"), Some(GroupTrigger::SetGroup("red team".into())));
+        assert_eq!(GroupTrigger::parse("gr.setlevelgroup"), Some(GroupTrigger::SetLevelGroup(String::new())));
+        assert_eq!(GroupTrigger::parse("gr.appendfile,x"), None);
+
+        let mut instance = LevelInstance::default();
+        instance.update_npc(5, b"ab");
+        instance.update_npc(5, b"cd");
+        assert_eq!(instance.npcs(), [(5, &b"abcd"[..])]);
+        instance.update_npc(5, &vec![b'x'; MAX_NPC_PROPS_LEN]);
+        assert_eq!(instance.npcs()[0].1.len(), MAX_NPC_PROPS_LEN);
+    }
+}
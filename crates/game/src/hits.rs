@@ -0,0 +1,129 @@
+//! # Server-Side Hit Detection
+//!
+//! Sword swings (PLI_HITOBJECTS) and explosions (PLI_EXPLOSION) report an
+//! area, not victims. The server works out what is inside the area from its
+//! own copy of the level and player positions and only notifies those
+//! targets, so a client can't hurt players on the far side of the level.
+//!
+//! Every target is treated as a [`TARGET_SIZE_PIXELS`] square at its
+//! position (the top-left corner, as sent in props); the area hits it if the
+//! circle and the square overlap. A sword hit further than
+//! [`SWORD_REACH_PIXELS`] from its attacker is dropped.
+//!
+//! # C++ Equivalence
+//! Replaces the forwarding of `PlayerClient::msgPLI_HITOBJECTS` and
+//! `PlayerClient::msgPLI_EXPLOSION`, which trust the clients to decide hits.
+
+use gserver_core::PlayerID;
+
+/// Side of a player, NPC or baddy hit box (pixels)
+pub const TARGET_SIZE_PIXELS: i32 = 32;
+
+/// Radius of a sword hit around the reported point (pixels)
+pub const SWORD_RADIUS_PIXELS: i32 = 16;
+
+/// Farthest a sword hit can be from the centre of its attacker (pixels)
+pub const SWORD_REACH_PIXELS: i32 = 48;
+
+/// Largest explosion radius accepted from a client (tiles)
+pub const MAX_EXPLOSION_RADIUS: u8 = 8;
+
+/// An area that hurts what it touches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HitArea {
+    /// Centre X (pixels)
+    pub x: i32,
+    /// Centre Y (pixels)
+    pub y: i32,
+    /// Radius (pixels)
+    pub radius: i32,
+}
+
+impl HitArea {
+    /// The area of a sword hit at a point
+    pub fn sword(x: i32, y: i32) -> Self {
+        Self { x, y, radius: SWORD_RADIUS_PIXELS }
+    }
+
+    /// The area of an explosion
+    ///
+    /// # Arguments
+    /// * `radius` - Radius in tiles, capped at [`MAX_EXPLOSION_RADIUS`]
+    pub fn explosion(x: i32, y: i32, radius: u8) -> Self {
+        Self { x, y, radius: i32::from(radius.min(MAX_EXPLOSION_RADIUS)) * 16 }
+    }
+
+    /// Check if the centre is close enough to an attacker at `pos`
+    /// (top-left, pixels) for a sword hit
+    pub fn within_reach(&self, pos: (i32, i32)) -> bool {
+        let (dx, dy) = (self.x - (pos.0 + TARGET_SIZE_PIXELS / 2), self.y - (pos.1 + TARGET_SIZE_PIXELS / 2));
+        dx * dx + dy * dy <= SWORD_REACH_PIXELS * SWORD_REACH_PIXELS
+    }
+
+    /// Check if the area touches a target at `pos` (top-left, pixels)
+    pub fn touches(&self, pos: (i32, i32)) -> bool {
+        // Closest point of the target square to the centre
+        let nearest_x = self.x.clamp(pos.0, pos.0 + TARGET_SIZE_PIXELS);
+        let nearest_y = self.y.clamp(pos.1, pos.1 + TARGET_SIZE_PIXELS);
+        let (dx, dy) = (self.x - nearest_x, self.y - nearest_y);
+        dx * dx + dy * dy <= self.radius * self.radius
+    }
+}
+
+/// Something that can be hit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HitTarget {
+    /// A player
+    Player(PlayerID),
+    /// A level NPC by id
+    Npc(u32),
+    /// A baddy by index in the level
+    Baddy(usize),
+}
+
+/// Get the targets an area touches
+///
+/// # Arguments
+/// * `area` - Hit area
+/// * `candidates` - Targets with their positions (top-left, pixels)
+pub fn targets_in(area: &HitArea, candidates: impl IntoIterator<Item = (HitTarget, (i32, i32))>) -> Vec<HitTarget> {
+    candidates.into_iter()
+        .filter(|&(_, pos)| area.touches(pos))
+        .map(|(target, _)| target)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sword_touches_adjacent_box_only() {
+        let area = HitArea::sword(100, 100);
+        assert!(area.touches((90, 90)));
+        assert!(area.touches((110, 80)));
+        assert!(!area.touches((120, 120)));
+        assert!(!area.touches((40, 100)));
+    }
+
+    #[test]
+    fn test_sword_reach() {
+        let area = HitArea::sword(100, 100);
+        assert!(area.within_reach((84, 84)));
+        assert!(area.within_reach((120, 60)));
+        assert!(!area.within_reach((200, 84)));
+        assert!(!area.within_reach((84, 600)));
+    }
+
+    #[test]
+    fn test_targets_in_explosion() {
+        let area = HitArea::explosion(160, 160, 2);
+        let hit = targets_in(&area, [
+            (HitTarget::Player(PlayerID(1)), (170, 150)),
+            (HitTarget::Npc(4), (100, 160)),
+            (HitTarget::Baddy(0), (300, 300)),
+        ]);
+        assert_eq!(hit, [HitTarget::Player(PlayerID(1)), HitTarget::Npc(4)]);
+        assert_eq!(HitArea::explosion(0, 0, 200).radius, i32::from(MAX_EXPLOSION_RADIUS) * 16);
+    }
+}
@@ -0,0 +1,151 @@
+//! # Interest Management
+//!
+//! On a big gmap a player only needs the props of the players around it.
+//! [`InterestMap`] keeps, on top of a [`SpatialIndex`], which pairs of
+//! players are in range of each other:
+//!
+//! - A pair comes into range within `range` tiles and goes out of range past
+//!   `range + hysteresis`, so a player walking along the edge doesn't flap
+//!   in and out
+//! - Range is symmetric: a player that sees another is seen by it
+//! - Each update of a player returns who came into range (they exchange
+//!   full props), who went out of range (they exchange leaves) and who is
+//!   still in range (they get the player's prop updates)
+
+use crate::spatial::{SpatialIndex, WorldPos};
+use gserver_core::PlayerID;
+use std::collections::{HashMap, HashSet};
+
+/// How far players see each other, in tiles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InterestRange {
+    /// Distance at which players come into range, 0 for the whole space
+    pub range: i32,
+
+    /// Extra distance before they go out of range again
+    pub hysteresis: i32,
+}
+
+/// What an update changed for the other players
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InterestChange {
+    /// Came into range
+    pub entered: Vec<PlayerID>,
+
+    /// Went out of range
+    pub left: Vec<PlayerID>,
+
+    /// Still in range
+    pub viewers: Vec<PlayerID>,
+}
+
+/// The players in range of each player
+#[derive(Debug, Default)]
+pub struct InterestMap {
+    index: SpatialIndex,
+    in_range: HashMap<PlayerID, HashSet<PlayerID>>,
+}
+
+impl InterestMap {
+    /// Create an empty map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move a player and work out who comes into and goes out of range
+    pub fn update(&mut self, id: PlayerID, pos: WorldPos, range: InterestRange) -> InterestChange {
+        let (enter, keep) = match range.range {
+            0 => (None, None),
+            r => (Some(r), Some(r + range.hysteresis.max(0))),
+        };
+        let near: HashSet<PlayerID> = self.index.within(&pos, keep).into_iter().filter(|&other| other != id).collect();
+        let current = self.in_range.get(&id).cloned().unwrap_or_default();
+
+        let mut change = InterestChange {
+            left: current.difference(&near).copied().collect(),
+            ..Default::default()
+        };
+        for &other in &near {
+            if current.contains(&other) {
+                change.viewers.push(other);
+                continue;
+            }
+            let distance = self.index.position(other).and_then(|p| p.distance(&pos));
+            if distance.is_some_and(|d| enter.is_none_or(|enter| d <= enter)) {
+                change.entered.push(other);
+            }
+        }
+        self.index.insert(id, pos);
+
+        for &other in &change.left {
+            self.unlink(id, other);
+        }
+        for &other in &change.entered {
+            self.in_range.entry(id).or_default().insert(other);
+            self.in_range.entry(other).or_default().insert(id);
+        }
+        for list in [&mut change.entered, &mut change.left, &mut change.viewers] {
+            list.sort_by_key(|id| id.get());
+        }
+        change
+    }
+
+    /// Take a player out
+    ///
+    /// # Returns
+    /// The players it was in range of
+    pub fn remove(&mut self, id: PlayerID) -> Vec<PlayerID> {
+        self.index.remove(id);
+        let mut former: Vec<PlayerID> = self.in_range.remove(&id).unwrap_or_default().into_iter().collect();
+        for other in &former {
+            if let Some(set) = self.in_range.get_mut(other) {
+                set.remove(&id);
+            }
+        }
+        former.sort_by_key(|id| id.get());
+        former
+    }
+
+    /// Get the players in range of a player
+    pub fn in_range(&self, id: PlayerID) -> Vec<PlayerID> {
+        self.in_range.get(&id).map(|set| set.iter().copied().collect()).unwrap_or_default()
+    }
+
+    fn unlink(&mut self, a: PlayerID, b: PlayerID) {
+        for (from, to) in [(a, b), (b, a)] {
+            if let Some(set) = self.in_range.get_mut(&from) {
+                set.remove(&to);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RANGE: InterestRange = InterestRange { range: 40, hysteresis: 8 };
+
+    #[test]
+    fn test_enter_and_leave_with_hysteresis() {
+        let mut map = InterestMap::new();
+        let (a, b, c) = (PlayerID(1), PlayerID(2), PlayerID(3));
+        assert_eq!(map.update(a, WorldPos::new("world.gmap", 10, 10), RANGE), InterestChange::default());
+        map.update(c, WorldPos::new("other.nw", 10, 10), RANGE);
+
+        // Out of range, then in
+        assert!(map.update(b, WorldPos::new("world.gmap", 60, 10), RANGE).entered.is_empty());
+        assert_eq!(map.update(b, WorldPos::new("world.gmap", 50, 10), RANGE).entered, [a]);
+
+        // Past the range but inside the hysteresis: still seen
+        assert_eq!(map.update(b, WorldPos::new("world.gmap", 55, 40), RANGE).viewers, [a]);
+        assert_eq!(map.update(a, WorldPos::new("world.gmap", 5, 0), RANGE).left, [b]);
+        assert!(map.in_range(b).is_empty());
+
+        // The whole space, across cells
+        let whole = InterestRange { range: 0, hysteresis: 0 };
+        assert_eq!(map.update(b, WorldPos::new("world.gmap", 500, 500), whole).entered, [a]);
+        assert_eq!(map.remove(a), [b]);
+        assert!(map.in_range(b).is_empty());
+    }
+}
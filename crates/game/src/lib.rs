@@ -5,19 +5,63 @@
 //! ## Modules
 //!
 //! - `player` - Player management and state
-//! - `manager` - Player collection management
-//! - `properties` - Player property definitions
+//! - `manager` - Sharded player registry with snapshots and indexes
+//! - `properties` - Player property definitions and wire codec
 //! - `handlers` - Packet handlers for game logic
 //! - `account` - Player account management
+//! - `weapons` - Server weapon definitions
+//! - `classes` - Script classes joined by weapons and NPCs
+//! - `events` - Event bus for cross-subsystem notifications
+//! - `tick` - Fixed-timestep game loop and timers
+//! - `autosave` - Periodic saving of accounts, flags and levels
+//! - `ambience` - Weather and screen tint settings per level and gmap
+//! - `groups` - Player groups and per-group level instances
+//! - `carry` - Carrying and throwing bushes, vases and NPCs
+//! - `hits` - Server-side sword and explosion hit detection
+//! - `physics` - Blocking NPC shapes and push-away
+//! - `freeze` - Frozen and fullstopped players
+//! - `social` - Friend and ignore list changes
+//! - `moderation` - Mutes, jail and warp-home sanctions
+//! - `spatial` - Players bucketed by position for range queries
+//! - `interest` - Which players are in range of each other
 
 pub mod player;
 pub mod manager;
 pub mod properties;
 pub mod handlers;
 pub mod account;
+pub mod weapons;
+pub mod classes;
+pub mod events;
+pub mod tick;
+pub mod autosave;
+pub mod ambience;
+pub mod groups;
+pub mod carry;
+pub mod hits;
+pub mod physics;
+pub mod freeze;
+pub mod social;
+pub mod moderation;
+pub mod spatial;
+pub mod interest;
 
 // Re-export commonly used types
 pub use player::{Player, PlayerType, PlayerState};
-pub use manager::PlayerManager;
+pub use manager::{PlayerManager, PlayerSnapshot};
 pub use properties::PlayerProperties;
 pub use account::{Account, AccountManager};
+pub use weapons::{Weapon, WeaponBuild, WeaponManager};
+pub use classes::{ClassManager, ScriptClass};
+pub use events::{CommandQueue, EventBus, GameCommand, GameEvent};
+pub use tick::{GameTimer, TickLoop, TickStats};
+pub use autosave::{AutosaveConfig, AutosaveService, AutosaveTarget};
+pub use ambience::{Ambience, AmbienceEffect, AmbienceScope};
+pub use groups::{Groups, GroupTrigger, InstanceKey};
+pub use carry::{CarriedObject, CarryTracker};
+pub use hits::{HitArea, HitTarget};
+pub use freeze::{ControlAction, FrozenPlayers};
+pub use social::SocialEdit;
+pub use moderation::{RecordEdit, Sanction};
+pub use spatial::{SpatialIndex, WorldPos};
+pub use interest::{InterestChange, InterestMap, InterestRange};
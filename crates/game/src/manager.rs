@@ -1,22 +1,101 @@
 //! # Player Manager
 //!
 //! This module manages the collection of all connected players.
+//!
+//! # Architecture
+//!
+//! Players live in [`SHARD_COUNT`] shards keyed by player ID, each behind its
+//! own `RwLock`, so logins and logouts on different shards never contend.
+//! Two secondary indexes sit next to the shards:
+//!
+//! - by account (lowercase) - an account can be online as a client and an RC
+//! - by level (lowercase) - players whose current level is that level
+//!
+//! # Snapshots
+//!
+//! Broadcast paths call [`PlayerManager::snapshot`] and iterate the returned
+//! list without holding any lock. Every add/remove/index change bumps an
+//! epoch; the snapshot is rebuilt the first time it's asked for after the
+//! epoch moved, and shared (one `Arc` clone) until then.
 
 use crate::player::Player;
 use gserver_core::PlayerID;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
+/// Number of player shards (power of two)
+pub const SHARD_COUNT: usize = 16;
+
+/// Players of one shard
+type Shard = RwLock<HashMap<PlayerID, PlayerEntry>>;
+
+/// A player and the index keys it's filed under
+struct PlayerEntry {
+    player: Arc<Player>,
+
+    /// Lowercase account name ("" until known)
+    account: String,
+
+    /// Lowercase current level ("" until known)
+    level: String,
+}
+
+/// Point-in-time list of all players
+///
+/// # Purpose
+/// Lets broadcasts iterate every player without holding manager locks.
+/// Players added or removed after the snapshot was taken aren't reflected.
+#[derive(Default)]
+pub struct PlayerSnapshot {
+    /// Epoch the snapshot was built at
+    pub epoch: u64,
+
+    /// Players, sorted by ID
+    pub players: Vec<Arc<Player>>,
+}
+
+impl PlayerSnapshot {
+    /// Iterate the players
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<Player>> {
+        self.players.iter()
+    }
+
+    /// Get the number of players
+    pub fn len(&self) -> usize {
+        self.players.len()
+    }
+
+    /// Check if the snapshot has no players
+    pub fn is_empty(&self) -> bool {
+        self.players.is_empty()
+    }
+}
+
 /// Player Manager
 ///
 /// # Purpose
 /// Tracks all connected players and provides lookup/management functions.
 ///
 /// # Thread Safety
-/// All operations are thread-safe using DashMap for concurrent access.
+/// All operations are thread-safe. Lookups by ID take one shard read lock;
+/// index lookups take the index read lock plus one shard read lock per hit.
 pub struct PlayerManager {
-    /// All players
-    /// Key: PlayerID, Value: Player handle
-    players: Arc<dashmap::DashMap<PlayerID, Arc<Player>>>,
+    /// Players sharded by ID
+    shards: Box<[Shard]>,
+
+    /// Lowercase account name -> players logged in on it
+    by_account: RwLock<HashMap<String, Vec<PlayerID>>>,
+
+    /// Lowercase level name -> players on it
+    by_level: RwLock<HashMap<String, Vec<PlayerID>>>,
+
+    /// Bumped on every change
+    epoch: AtomicU64,
+
+    /// Last built snapshot
+    snapshot: RwLock<Arc<PlayerSnapshot>>,
 }
 
 impl PlayerManager {
@@ -29,28 +108,71 @@ impl PlayerManager {
         tracing::debug!("Creating PlayerManager");
 
         Self {
-            players: Arc::new(dashmap::DashMap::new()),
+            shards: (0..SHARD_COUNT).map(|_| RwLock::new(HashMap::new())).collect(),
+            by_account: RwLock::new(HashMap::new()),
+            by_level: RwLock::new(HashMap::new()),
+            epoch: AtomicU64::new(0),
+            snapshot: RwLock::new(Arc::new(PlayerSnapshot::default())),
         }
     }
 
+    /// Get the shard a player ID belongs to
+    #[inline]
+    fn shard(&self, id: PlayerID) -> &Shard {
+        &self.shards[id.get() as usize & (SHARD_COUNT - 1)]
+    }
+
+    /// Record a change so the next snapshot is rebuilt
+    #[inline]
+    fn bump_epoch(&self) {
+        self.epoch.fetch_add(1, Ordering::Release);
+    }
+
     /// Add a player to the manager
     ///
+    /// The player is indexed under the account and level in its properties;
+    /// a player already registered with the same ID is replaced.
+    ///
     /// # Arguments
     /// * `player` - The player to add
-    #[inline]
     pub fn add_player(&self, player: Arc<Player>) {
         tracing::debug!("Adding player {}", player.id.get());
-        self.players.insert(player.id, player);
+
+        let (account, level) = {
+            let props = player.properties.lock();
+            (props.account_name.to_lowercase(), props.cur_level.to_lowercase())
+        };
+        let id = player.id;
+
+        let previous = self.shard(id).write().insert(id, PlayerEntry {
+            player,
+            account: account.clone(),
+            level: level.clone(),
+        });
+        if let Some(previous) = previous {
+            unindex(&self.by_account, &previous.account, id);
+            unindex(&self.by_level, &previous.level, id);
+        }
+        index(&self.by_account, &account, id);
+        index(&self.by_level, &level, id);
+        self.bump_epoch();
     }
 
     /// Remove a player from the manager
     ///
     /// # Arguments
     /// * `id` - The player ID to remove
-    #[inline]
-    pub fn remove_player(&self, id: PlayerID) {
+    ///
+    /// # Returns
+    /// The removed player, if it was registered
+    pub fn remove_player(&self, id: PlayerID) -> Option<Arc<Player>> {
         tracing::debug!("Removing player {}", id.get());
-        self.players.remove(&id);
+
+        let entry = self.shard(id).write().remove(&id)?;
+        unindex(&self.by_account, &entry.account, id);
+        unindex(&self.by_level, &entry.level, id);
+        self.bump_epoch();
+        Some(entry.player)
     }
 
     /// Get a player by ID
@@ -62,7 +184,69 @@ impl PlayerManager {
     /// `Some(player)` if found, `None` otherwise
     #[inline]
     pub fn get_player(&self, id: PlayerID) -> Option<Arc<Player>> {
-        self.players.get(&id).map(|entry| entry.clone())
+        self.shard(id).read().get(&id).map(|entry| entry.player.clone())
+    }
+
+    /// Get the players logged in on an account (case-insensitive)
+    pub fn get_by_account(&self, account: &str) -> Vec<Arc<Player>> {
+        self.lookup(&self.by_account, account)
+    }
+
+    /// Get the players on a level (case-insensitive)
+    pub fn players_on_level(&self, level: &str) -> Vec<Arc<Player>> {
+        self.lookup(&self.by_level, level)
+    }
+
+    /// Re-index a player under a new account name
+    ///
+    /// # Returns
+    /// `false` if the player isn't registered
+    pub fn set_account(&self, id: PlayerID, account: &str) -> bool {
+        self.reindex(id, account, |entry| &mut entry.account, &self.by_account)
+    }
+
+    /// Re-index a player under its new current level
+    ///
+    /// # Returns
+    /// `false` if the player isn't registered
+    pub fn set_level(&self, id: PlayerID, level: &str) -> bool {
+        self.reindex(id, level, |entry| &mut entry.level, &self.by_level)
+    }
+
+    /// Get a snapshot of all players
+    ///
+    /// # Purpose
+    /// For broadcasts: iterate the result without holding any manager lock.
+    /// The snapshot is cached until the next change, so repeated calls
+    /// between changes only clone an `Arc`.
+    pub fn snapshot(&self) -> Arc<PlayerSnapshot> {
+        let epoch = self.epoch.load(Ordering::Acquire);
+        {
+            let cached = self.snapshot.read();
+            if cached.epoch == epoch {
+                return cached.clone();
+            }
+        }
+
+        // Changes made while collecting bump the epoch past `epoch`, so the
+        // next call rebuilds again instead of serving a stale list
+        let mut players: Vec<Arc<Player>> = self.shards.iter()
+            .flat_map(|shard| shard.read().values().map(|entry| entry.player.clone()).collect::<Vec<_>>())
+            .collect();
+        players.sort_by_key(|player| player.id.get());
+        let snapshot = Arc::new(PlayerSnapshot { epoch, players });
+
+        let mut cached = self.snapshot.write();
+        if cached.epoch < epoch {
+            *cached = snapshot.clone();
+        }
+        snapshot
+    }
+
+    /// Get the current epoch (changes on every add, remove or re-index)
+    #[inline]
+    pub fn epoch(&self) -> u64 {
+        self.epoch.load(Ordering::Acquire)
     }
 
     /// Get the number of players
@@ -71,7 +255,55 @@ impl PlayerManager {
     /// The current number of players in the manager
     #[inline]
     pub fn player_count(&self) -> usize {
-        self.players.len()
+        self.shards.iter().map(|shard| shard.read().len()).sum()
+    }
+
+    /// Resolve an index entry to players
+    fn lookup(&self, index: &RwLock<HashMap<String, Vec<PlayerID>>>, key: &str) -> Vec<Arc<Player>> {
+        let ids = index.read().get(&key.to_lowercase()).cloned().unwrap_or_default();
+        ids.into_iter().filter_map(|id| self.get_player(id)).collect()
+    }
+
+    /// Move a player from its current key in `index` to `key`
+    fn reindex(
+        &self,
+        id: PlayerID,
+        key: &str,
+        field: impl Fn(&mut PlayerEntry) -> &mut String,
+        index_map: &RwLock<HashMap<String, Vec<PlayerID>>>,
+    ) -> bool {
+        let key = key.to_lowercase();
+        let mut shard = self.shard(id).write();
+        let Some(entry) = shard.get_mut(&id) else {
+            return false;
+        };
+
+        let current = field(entry);
+        if *current != key {
+            unindex(index_map, current, id);
+            index(index_map, &key, id);
+            *current = key;
+            self.bump_epoch();
+        }
+        true
+    }
+}
+
+/// File a player under a key (empty keys aren't indexed)
+fn index(map: &RwLock<HashMap<String, Vec<PlayerID>>>, key: &str, id: PlayerID) {
+    if !key.is_empty() {
+        map.write().entry(key.to_string()).or_default().push(id);
+    }
+}
+
+/// Remove a player from a key, dropping the key once it's empty
+fn unindex(map: &RwLock<HashMap<String, Vec<PlayerID>>>, key: &str, id: PlayerID) {
+    let mut map = map.write();
+    if let Some(ids) = map.get_mut(key) {
+        ids.retain(|&other| other != id);
+        if ids.is_empty() {
+            map.remove(key);
+        }
     }
 }
 
@@ -86,6 +318,16 @@ mod tests {
     use super::*;
     use crate::player::PlayerType;
 
+    fn player(id: u16, account: &str, level: &str) -> Arc<Player> {
+        let player = Player::new(PlayerID::new(id), PlayerType::Player);
+        {
+            let mut props = player.properties.lock();
+            props.account_name = account.to_string();
+            props.cur_level = level.to_string();
+        }
+        Arc::new(player)
+    }
+
     #[test]
     fn test_manager_creation() {
         let manager = PlayerManager::new();
@@ -118,4 +360,44 @@ mod tests {
         let not_found = manager.get_player(PlayerID::new(999));
         assert!(not_found.is_none());
     }
+
+    #[test]
+    fn test_account_and_level_indexes() {
+        let manager = PlayerManager::new();
+        manager.add_player(player(1, "Stefan", "onlinestartlocal.nw"));
+        manager.add_player(player(17, "stefan", "cave.nw"));
+        manager.add_player(player(2, "Unixmad", "onlinestartlocal.nw"));
+
+        assert_eq!(manager.get_by_account("STEFAN").len(), 2);
+        assert_eq!(manager.players_on_level("OnlineStartLocal.nw").len(), 2);
+
+        assert!(manager.set_level(PlayerID::new(1), "cave.nw"));
+        assert_eq!(manager.players_on_level("cave.nw").len(), 2);
+        assert_eq!(manager.players_on_level("onlinestartlocal.nw").len(), 1);
+        assert!(!manager.set_level(PlayerID::new(99), "cave.nw"));
+
+        manager.remove_player(PlayerID::new(17));
+        assert_eq!(manager.get_by_account("stefan").len(), 1);
+        assert_eq!(manager.players_on_level("cave.nw").len(), 1);
+    }
+
+    #[test]
+    fn test_snapshot_cached_until_change() {
+        let manager = PlayerManager::new();
+        manager.add_player(player(33, "a", ""));
+        manager.add_player(player(1, "b", ""));
+
+        let first = manager.snapshot();
+        let ids: Vec<u16> = first.iter().map(|p| p.id.get()).collect();
+        assert_eq!(ids, [1, 33]);
+        assert!(Arc::ptr_eq(&first, &manager.snapshot()));
+
+        manager.remove_player(PlayerID::new(1));
+        let second = manager.snapshot();
+        assert_eq!(second.len(), 1);
+        assert!(second.epoch > first.epoch);
+
+        // Old snapshots stay valid while held
+        assert_eq!(first.len(), 2);
+    }
 }
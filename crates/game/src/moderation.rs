@@ -0,0 +1,124 @@
+//! # Moderation
+//!
+//! Sanctions staff put on an account from RC (`/mute`, `/jail`, ...) or
+//! scripts (`moderation.*`). Mutes and jail time are stored in the account
+//! with an expiry, so they survive relogging:
+//!
+//! - Muted accounts can't chat, PM or message everyone
+//! - Jailed accounts are kept on the `jaillevel` level; warps elsewhere
+//!   send them back until the time is up or they are unjailed
+//! - Warp home sends the player to the start location of new accounts
+//!
+//! Freezing is a [`ControlAction`](crate::ControlAction) and isn't stored.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Expiry of a sanction without a duration
+pub const FOREVER: u64 = u64::MAX;
+
+/// A sanction on an account
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sanction {
+    /// Mute for some minutes (`None` until unmuted)
+    Mute(Option<u64>),
+    /// Lift a mute
+    Unmute,
+    /// Jail for some minutes (`None` until unjailed)
+    Jail(Option<u64>),
+    /// Release from jail and warp home
+    Unjail,
+    /// Warp to the start location
+    WarpHome,
+}
+
+impl Sanction {
+    /// Parse a command name and its optional duration in minutes
+    ///
+    /// # Errors
+    /// Unknown commands and durations that aren't a positive number
+    pub fn parse(command: &str, minutes: Option<&str>) -> Result<Self, String> {
+        let minutes = match minutes.map(str::trim).filter(|m| !m.is_empty()) {
+            Some(m) => match m.parse::<u64>() {
+                Ok(m) if m > 0 => Some(m),
+                _ => return Err(format!("Invalid duration: {} (minutes)", m)),
+            },
+            None => None,
+        };
+        Ok(match command.trim().to_lowercase().as_str() {
+            "mute" => Self::Mute(minutes),
+            "unmute" => Self::Unmute,
+            "jail" => Self::Jail(minutes),
+            "unjail" => Self::Unjail,
+            "warphome" => Self::WarpHome,
+            other => return Err(format!("Unknown sanction: {}", other)),
+        })
+    }
+
+    /// Get the expiry of a mute or jail starting at `now` (unix seconds)
+    pub fn expiry(minutes: Option<u64>, now: u64) -> u64 {
+        minutes.map_or(FOREVER, |m| now.saturating_add(m.saturating_mul(60)))
+    }
+
+    /// Describe the sanction to the player it applies to
+    pub fn notice(&self) -> String {
+        let length = |minutes: &Option<u64>| match minutes {
+            Some(1) => " for 1 minute".to_string(),
+            Some(m) => format!(" for {} minutes", m),
+            None => String::new(),
+        };
+        match self {
+            Self::Mute(minutes) => format!("You have been muted{}", length(minutes)),
+            Self::Unmute => "You are no longer muted".to_string(),
+            Self::Jail(minutes) => format!("You have been jailed{}", length(minutes)),
+            Self::Unjail => "You have been released from jail".to_string(),
+            Self::WarpHome => "You have been warped home".to_string(),
+        }
+    }
+}
+
+/// A change to an account's staff comments, ban record or gralats (RC
+/// dialogs, `/ban`, `/setgralats`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordEdit {
+    /// Replace the staff comments
+    Comments(String),
+    /// Ban the account, or lift its ban
+    Ban {
+        banned: bool,
+        reason: String,
+        /// Staff account that set it
+        issuer: String,
+        /// Expiry unix time, 0 until lifted
+        until: u64,
+    },
+    /// Set the account's gralats
+    Gralats {
+        amount: u32,
+        /// Staff account that set them
+        issuer: String,
+    },
+}
+
+/// Get the current time in unix seconds, the unit sanction expiries use
+pub fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sanctions() {
+        assert_eq!(Sanction::parse("mute", Some("10")), Ok(Sanction::Mute(Some(10))));
+        assert_eq!(Sanction::parse("Jail", None), Ok(Sanction::Jail(None)));
+        assert_eq!(Sanction::parse("warphome", Some("")), Ok(Sanction::WarpHome));
+        assert!(Sanction::parse("mute", Some("0")).is_err());
+        assert!(Sanction::parse("ban", None).is_err());
+
+        assert_eq!(Sanction::expiry(Some(2), 1000), 1120);
+        assert_eq!(Sanction::expiry(None, 1000), FOREVER);
+        assert_eq!(Sanction::Mute(Some(1)).notice(), "You have been muted for 1 minute");
+        assert_eq!(Sanction::Jail(None).notice(), "You have been jailed");
+    }
+}
@@ -0,0 +1,102 @@
+//! # Blocking and Push-Away
+//!
+//! NPCs given a blocking shape (`setshape 1, width, height`) can't be walked
+//! through. When a player's reported position overlaps one, the server
+//! pushes the player out along the shortest way (PLO_PUSHAWAY); scripts can
+//! push players too.
+//!
+//! Positions are top-left corners in pixels, like the X2/Y2 props. A player
+//! collides with the lower half of their sprite ([`player_box`]).
+
+/// Largest push sent in one PLO_PUSHAWAY (pixels per axis)
+pub const MAX_PUSH_PIXELS: i32 = 64;
+
+/// An axis-aligned box in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundingBox {
+    /// Left edge
+    pub x: i32,
+    /// Top edge
+    pub y: i32,
+    /// Width
+    pub width: i32,
+    /// Height
+    pub height: i32,
+}
+
+impl BoundingBox {
+    /// Create a box
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    /// Check if two boxes overlap (touching edges don't)
+    pub fn overlaps(&self, other: &BoundingBox) -> bool {
+        self.x < other.x + other.width && other.x < self.x + self.width
+            && self.y < other.y + other.height && other.y < self.y + self.height
+    }
+
+    /// Get the smallest move that takes this box out of `blocker`
+    ///
+    /// # Returns
+    /// `(dx, dy)` with one axis zero, or `None` if the boxes don't overlap
+    pub fn push_out(&self, blocker: &BoundingBox) -> Option<(i32, i32)> {
+        if !self.overlaps(blocker) {
+            return None;
+        }
+        let left = blocker.x - (self.x + self.width);
+        let right = blocker.x + blocker.width - self.x;
+        let up = blocker.y - (self.y + self.height);
+        let down = blocker.y + blocker.height - self.y;
+
+        let dx = if -left < right { left } else { right };
+        let dy = if -up < down { up } else { down };
+        Some(if dx.abs() <= dy.abs() { (dx, 0) } else { (0, dy) })
+    }
+}
+
+/// Get the collision box of a player at a position
+pub fn player_box(pos: (i32, i32)) -> BoundingBox {
+    BoundingBox::new(pos.0 + 8, pos.1 + 16, 16, 16)
+}
+
+/// Get the push that takes a player out of every blocker it overlaps
+///
+/// Blockers are resolved one after another from the pushed position, and
+/// the total is capped at [`MAX_PUSH_PIXELS`] per axis.
+///
+/// # Returns
+/// `None` if the player doesn't overlap any blocker
+pub fn push_away(pos: (i32, i32), blockers: impl IntoIterator<Item = BoundingBox>) -> Option<(i32, i32)> {
+    let (mut dx, mut dy) = (0, 0);
+    let mut pushed = false;
+    for blocker in blockers {
+        if let Some((px, py)) = player_box((pos.0 + dx, pos.1 + dy)).push_out(&blocker) {
+            dx += px;
+            dy += py;
+            pushed = true;
+        }
+    }
+    pushed.then(|| (dx.clamp(-MAX_PUSH_PIXELS, MAX_PUSH_PIXELS), dy.clamp(-MAX_PUSH_PIXELS, MAX_PUSH_PIXELS)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_out_shortest_side() {
+        let player = BoundingBox::new(100, 100, 16, 16);
+        assert_eq!(player.push_out(&BoundingBox::new(110, 90, 32, 40)), Some((-6, 0)));
+        assert_eq!(player.push_out(&BoundingBox::new(90, 112, 40, 32)), Some((0, -4)));
+        assert_eq!(player.push_out(&BoundingBox::new(116, 100, 16, 16)), None);
+    }
+
+    #[test]
+    fn test_push_away_blockers() {
+        // Player body at (108, 116)-(124, 132)
+        let blockers = [BoundingBox::new(120, 100, 32, 64), BoundingBox::new(0, 0, 16, 16)];
+        assert_eq!(push_away((100, 100), blockers), Some((-4, 0)));
+        assert_eq!(push_away((300, 300), blockers), None);
+    }
+}
@@ -23,7 +23,20 @@
 //! - **PropertyEloRating**: ELO rating + deviation
 //! - **PropertyVoid**: No data
 //! - **PropertyUnsafeByte**: Raw byte without validation
-
+//!
+//! # Wire Codec
+//!
+//! [`PROP_TABLE`] lists every property with its [`PropType`] and the oldest
+//! [`ClientVersion`] that knows it. [`encode_props`] and [`decode_props`] are
+//! driven by the table, so the login response ([`LOGIN_PROPS`]), the
+//! PLO_OTHERPLPROPS broadcast ([`OTHER_PLAYER_PROPS`]) and PLI_PLAYERPROPS
+//! all share one encoding per property.
+
+use bytes::{Buf, BufMut, BytesMut};
+use gserver_core::{HalfTile, PixelCoord, Result, TileCoord};
+use gserver_protocol::codecs::*;
+use gserver_protocol::packet_builder::build_other_player_props;
+use gserver_protocol::ProtocolError;
 use serde::{Deserialize, Serialize};
 use std::time::Instant;
 
@@ -371,10 +384,10 @@ pub struct PlayerProperties {
     /// Player ID
     pub id: u16,
 
-    /// X position (tile coordinate, divide by 16 for pixels)
+    /// X position (tiles; x2 holds the pixel position)
     pub x: i16,
 
-    /// Y position (tile coordinate, divide by 16 for pixels)
+    /// Y position (tiles; y2 holds the pixel position)
     pub y: i16,
 
     /// Sprite and direction
@@ -577,37 +590,37 @@ impl PlayerProperties {
         }
     }
 
-    /// Get X position in pixels (tile coordinate * 16)
-    pub fn x_pixels(&self) -> i16 {
-        self.x * 16
+    /// Get X position in pixels
+    pub fn x_pixels(&self) -> PixelCoord {
+        TileCoord(self.x as f32).to_pixels()
     }
 
-    /// Get Y position in pixels (tile coordinate * 16)
-    pub fn y_pixels(&self) -> i16 {
-        self.y * 16
+    /// Get Y position in pixels
+    pub fn y_pixels(&self) -> PixelCoord {
+        TileCoord(self.y as f32).to_pixels()
     }
 
-    /// Get Z position in pixels (tile coordinate * 16, offset by 800)
-    pub fn z_pixels(&self) -> i16 {
-        self.z * 16
+    /// Get Z position in pixels (offset by 800)
+    pub fn z_pixels(&self) -> PixelCoord {
+        TileCoord(self.z as f32).to_pixels()
     }
 
-    /// Set X position from pixels
-    pub fn set_x_pixels(&mut self, pixels: i16) {
-        self.x = pixels / 16;
-        self.x2 = pixels;
+    /// Set X position from pixels (X keeps the tile the pixel is in)
+    pub fn set_x_pixels(&mut self, pixels: PixelCoord) {
+        self.x = pixels.tile_index() as i16;
+        self.x2 = pixels.0 as i16;
     }
 
-    /// Set Y position from pixels
-    pub fn set_y_pixels(&mut self, pixels: i16) {
-        self.y = pixels / 16;
-        self.y2 = pixels;
+    /// Set Y position from pixels (Y keeps the tile the pixel is in)
+    pub fn set_y_pixels(&mut self, pixels: PixelCoord) {
+        self.y = pixels.tile_index() as i16;
+        self.y2 = pixels.0 as i16;
     }
 
-    /// Set Z position from pixels
-    pub fn set_z_pixels(&mut self, pixels: i16) {
-        self.z = pixels / 16;
-        self.z2 = pixels;
+    /// Set Z position from pixels (Z keeps the tile the pixel is in)
+    pub fn set_z_pixels(&mut self, pixels: PixelCoord) {
+        self.z = pixels.tile_index() as i16;
+        self.z2 = pixels.0 as i16;
     }
 
     /// Get hitpoints in hearts (cur_power is in halves)
@@ -640,6 +653,713 @@ impl Default for PlayerProperties {
     }
 }
 
+/// Wire encoding of a property value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropType {
+    /// No value
+    Void,
+    /// `{GCHAR}`
+    GChar,
+    /// `{GSHORT}`
+    GShort,
+    /// `{GINT}` (3 bytes)
+    GInt,
+    /// `{GINT5}` (5 bytes)
+    GInt5,
+    /// `{GCHAR length}{string}`
+    String,
+    /// Five `{GCHAR}` colors
+    Colors,
+    /// `{GCHAR 0}`, or five `{GCHAR}` colors when the first isn't 0
+    EffectColors,
+    /// `{GCHAR power}`, or `{GCHAR power + 30}{GCHAR length}{image}`
+    SwordPower,
+    /// `{GCHAR power}`, or `{GCHAR power + 10}{GCHAR length}{image}`
+    ShieldPower,
+    /// Gani string for 2.x clients; `{GCHAR power}` or
+    /// `{GCHAR length + 10}{image}` (bow gif) before that
+    GaniOrBowGif,
+    /// `{GCHAR preset}` (< 100), or `{GCHAR length + 100}{image}`
+    HeadGif,
+    /// `{GCHAR type}{GINT npc id}`
+    AttachNpc,
+    /// `{GSHORT abs(pixels) << 1 | negative}`
+    PixelCoordinate,
+}
+
+impl PropType {
+    /// Get the length of an encoded value at the start of `data`
+    ///
+    /// Gani props are measured as 2.x gani strings.
+    ///
+    /// # Returns
+    /// `None` if `data` is too short to tell
+    pub fn value_len(self, data: &[u8]) -> Option<usize> {
+        let gchar = |pos: usize| data.get(pos).map(|b| b.saturating_sub(32) as usize);
+        match self {
+            Self::Void => Some(0),
+            Self::GChar => Some(1),
+            Self::GShort | Self::PixelCoordinate => Some(2),
+            Self::GInt => Some(3),
+            Self::AttachNpc => Some(4),
+            Self::GInt5 | Self::Colors => Some(5),
+            Self::String | Self::GaniOrBowGif => gchar(0).map(|n| 1 + n),
+            Self::EffectColors => gchar(0).map(|n| if n > 0 { 5 } else { 1 }),
+            Self::SwordPower => gchar(0).and_then(|sp| if sp > 4 { gchar(1).map(|n| 2 + n) } else { Some(1) }),
+            Self::ShieldPower => gchar(0).and_then(|sp| if sp > 3 { gchar(1).map(|n| 2 + n) } else { Some(1) }),
+            Self::HeadGif => gchar(0).map(|n| if n < 100 { 1 } else { 1 + n - 100 }),
+        }
+    }
+}
+
+/// Client generation, for property availability
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ClientVersion {
+    /// 1.x clients (bow gif instead of gani, no gani attributes)
+    Classic,
+
+    /// 2.x clients before 2.19
+    #[default]
+    V2,
+
+    /// 2.19+ clients (OS type, code page, pixel positions, community name)
+    V2_19,
+
+    /// v6 clients
+    V6,
+}
+
+/// Wire type and availability of one player property
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PropDef {
+    /// The property
+    pub prop: PlayerProp,
+
+    /// How its value is encoded
+    pub ty: PropType,
+
+    /// Oldest client that knows the property
+    pub since: ClientVersion,
+}
+
+impl PropDef {
+    /// Check if a client version knows this property
+    #[inline]
+    pub fn available(&self, version: ClientVersion) -> bool {
+        version >= self.since
+    }
+}
+
+macro_rules! prop_table {
+    ($($prop:ident: $ty:ident $(since $since:ident)?),* $(,)?) => {
+        [$(PropDef {
+            prop: PlayerProp::$prop,
+            ty: PropType::$ty,
+            since: prop_table!(@since $($since)?),
+        }),*]
+    };
+    (@since) => { ClientVersion::Classic };
+    (@since $since:ident) => { ClientVersion::$since };
+}
+
+/// Every player property, indexed by property ID
+///
+/// # C++ Equivalence
+/// Follows the value layouts of `Player::getProp` and `Player::setProps` in
+/// PlayerProps.cpp
+pub static PROP_TABLE: [PropDef; PlayerProp::PlayerPropCount as usize] = prop_table![
+    Nickname: String,
+    MaxPower: GChar,
+    CurPower: GChar,
+    RupeesCount: GInt,
+    ArrowsCount: GChar,
+    BombsCount: GChar,
+    GlovePower: GChar,
+    BombPower: GChar,
+    SwordPower: SwordPower,
+    ShieldPower: ShieldPower,
+    Gani: GaniOrBowGif,
+    HeadGif: HeadGif,
+    CurChat: String,
+    Colors: Colors,
+    Id: GShort,
+    X: GChar,
+    Y: GChar,
+    Sprite: GChar,
+    Status: GChar,
+    CarrySprite: GChar,
+    CurLevel: String,
+    HorseGif: String,
+    HorseBushes: GChar,
+    EffectColors: EffectColors,
+    CarryNPC: GInt,
+    ApCounter: GShort,
+    MagicPoints: GChar,
+    KillsCount: GInt,
+    DeathsCount: GInt,
+    OnlineSecs: GInt,
+    IpAddr: GInt5,
+    UdpPort: GInt,
+    Alignment: GChar,
+    AdditFlags: GChar,
+    AccountName: String,
+    BodyImg: String,
+    Rating: GInt,
+    GAttrib1: String since V2,
+    GAttrib2: String since V2,
+    GAttrib3: String since V2,
+    GAttrib4: String since V2,
+    GAttrib5: String since V2,
+    AttachNPC: AttachNpc,
+    GmapLevelX: GChar,
+    GmapLevelY: GChar,
+    Z: GChar,
+    GAttrib6: String since V2,
+    GAttrib7: String since V2,
+    GAttrib8: String since V2,
+    GAttrib9: String since V2,
+    JoinLeaveLvl: GChar,
+    Disconnect: Void,
+    Language: String,
+    PlayerListStatus: GChar,
+    GAttrib10: String since V2,
+    GAttrib11: String since V2,
+    GAttrib12: String since V2,
+    GAttrib13: String since V2,
+    GAttrib14: String since V2,
+    GAttrib15: String since V2,
+    GAttrib16: String since V2,
+    GAttrib17: String since V2,
+    GAttrib18: String since V2,
+    GAttrib19: String since V2,
+    GAttrib20: String since V2,
+    GAttrib21: String since V2,
+    GAttrib22: String since V2,
+    GAttrib23: String since V2,
+    GAttrib24: String since V2,
+    GAttrib25: String since V2,
+    GAttrib26: String since V2,
+    GAttrib27: String since V2,
+    GAttrib28: String since V2,
+    GAttrib29: String since V2,
+    GAttrib30: String since V2,
+    OsType: String since V2_19,
+    TextCodePage: GInt since V2_19,
+    OnlineSecs2: GInt5 since V2_19,
+    X2: PixelCoordinate since V2_19,
+    Y2: PixelCoordinate since V2_19,
+    Z2: PixelCoordinate since V2_19,
+    PlayerListCategory: GChar since V2_19,
+    CommunityName: String since V2_19,
+    Unknown83: GInt5 since V6,
+];
+
+/// Properties sent to a player about themselves after login
+///
+/// # C++ Equivalence
+/// Matches the `__sendLogin` list in PlayerClient.cpp
+pub const LOGIN_PROPS: &[PlayerProp] = &[
+    PlayerProp::Nickname, PlayerProp::MaxPower, PlayerProp::CurPower, PlayerProp::RupeesCount,
+    PlayerProp::ArrowsCount, PlayerProp::BombsCount, PlayerProp::GlovePower, PlayerProp::BombPower,
+    PlayerProp::SwordPower, PlayerProp::ShieldPower, PlayerProp::Gani, PlayerProp::HeadGif,
+    PlayerProp::Colors, PlayerProp::Id, PlayerProp::X, PlayerProp::Y, PlayerProp::Sprite,
+    PlayerProp::CurLevel, PlayerProp::IpAddr, PlayerProp::AccountName, PlayerProp::BodyImg,
+];
+
+/// Properties broadcast to other players (PLO_OTHERPLPROPS)
+///
+/// # C++ Equivalence
+/// Matches the `__getLogin` list in Player.cpp
+pub const OTHER_PLAYER_PROPS: &[PlayerProp] = &[
+    PlayerProp::Nickname, PlayerProp::CurPower, PlayerProp::SwordPower, PlayerProp::ShieldPower,
+    PlayerProp::Gani, PlayerProp::HeadGif, PlayerProp::CurChat, PlayerProp::Colors, PlayerProp::Id,
+    PlayerProp::X, PlayerProp::Y, PlayerProp::Sprite, PlayerProp::Status, PlayerProp::CarrySprite,
+    PlayerProp::CurLevel, PlayerProp::HorseGif, PlayerProp::HorseBushes, PlayerProp::EffectColors,
+    PlayerProp::CarryNPC, PlayerProp::AccountName, PlayerProp::BodyImg, PlayerProp::GAttrib1,
+    PlayerProp::GAttrib2, PlayerProp::GAttrib3, PlayerProp::GAttrib4, PlayerProp::GAttrib5,
+    PlayerProp::AttachNPC, PlayerProp::GmapLevelX, PlayerProp::GmapLevelY, PlayerProp::Z,
+    PlayerProp::X2, PlayerProp::Y2, PlayerProp::Z2, PlayerProp::CommunityName,
+];
+
+/// A decoded property value
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropValue {
+    /// No value ([`PropType::Void`])
+    Void,
+
+    /// Any numeric type, as the number on the wire
+    Int(i64),
+
+    /// [`PropType::String`]
+    String(String),
+
+    /// [`PropType::Colors`] and [`PropType::EffectColors`]
+    Colors([u8; 5]),
+
+    /// [`PropType::SwordPower`]
+    SwordPower(PropertySwordPower),
+
+    /// [`PropType::ShieldPower`]
+    ShieldPower(PropertyShieldPower),
+
+    /// [`PropType::GaniOrBowGif`]
+    Gani(PropertyGaniOrBowGif),
+
+    /// [`PropType::HeadGif`]
+    HeadGif(PropertyHeadGif),
+
+    /// [`PropType::AttachNpc`]
+    AttachNpc(PropertyAttachNPC),
+}
+
+impl PlayerProp {
+    /// Get the table entry of this property
+    #[inline]
+    pub fn def(self) -> &'static PropDef {
+        &PROP_TABLE[self as usize]
+    }
+
+    /// Get the index of a gani attribute property (GAttrib1 is 0)
+    pub fn gani_attrib_index(self) -> Option<usize> {
+        Self::gani_attribs().iter().position(|&p| p == self)
+    }
+}
+
+/// Z is sent in half-tiles, offset so the ground plane isn't 0
+const Z_OFFSET: i64 = 50;
+
+impl PlayerProperties {
+    /// Get a property as the value sent on the wire
+    pub fn prop_value(&self, prop: PlayerProp) -> PropValue {
+        use PropValue::{Colors, Int, String as Str};
+
+        if let Some(index) = prop.gani_attrib_index() {
+            return Str(self.gani_attribs[index].clone());
+        }
+        match prop {
+            PlayerProp::Nickname => Str(self.nickname.clone()),
+            PlayerProp::MaxPower => Int(self.max_power.into()),
+            PlayerProp::CurPower => Int(self.cur_power.into()),
+            PlayerProp::RupeesCount => Int(self.rupees_count.into()),
+            PlayerProp::ArrowsCount => Int(self.arrows_count.into()),
+            PlayerProp::BombsCount => Int(self.bombs_count.into()),
+            PlayerProp::GlovePower => Int(self.glove_power.into()),
+            PlayerProp::BombPower => Int(self.bomb_power.into()),
+            PlayerProp::SwordPower => PropValue::SwordPower(self.sword_power.clone()),
+            PlayerProp::ShieldPower => PropValue::ShieldPower(self.shield_power.clone()),
+            PlayerProp::Gani => PropValue::Gani(self.gani.clone()),
+            PlayerProp::HeadGif => PropValue::HeadGif(self.head_gif.clone()),
+            PlayerProp::CurChat => Str(self.cur_chat.clone()),
+            PlayerProp::Colors => Colors(self.colors),
+            PlayerProp::Id => Int(self.id.into()),
+            PlayerProp::X => Int(PixelCoord(self.x2.into()).to_half_tiles().0.into()),
+            PlayerProp::Y => Int(PixelCoord(self.y2.into()).to_half_tiles().0.into()),
+            PlayerProp::Sprite => Int((self.sprite.sprite as i64) * 4 + (self.sprite.direction % 4) as i64),
+            PlayerProp::Status => Int(self.status.into()),
+            PlayerProp::CarrySprite => Int(self.carry_sprite.into()),
+            PlayerProp::CurLevel => Str(self.cur_level.clone()),
+            PlayerProp::HorseGif => Str(self.horse_gif.clone()),
+            PlayerProp::HorseBushes => Int(self.horse_bushes.into()),
+            PlayerProp::EffectColors => Colors(self.effect_colors),
+            PlayerProp::CarryNPC => Int(self.carry_npc.into()),
+            PlayerProp::ApCounter => Int(self.ap_counter.into()),
+            PlayerProp::MagicPoints => Int(self.magic_points.into()),
+            PlayerProp::KillsCount => Int(self.kills_count.into()),
+            PlayerProp::DeathsCount => Int(self.deaths_count.into()),
+            PlayerProp::OnlineSecs => Int(self.online_secs.into()),
+            PlayerProp::IpAddr => Int(self.ip_addr),
+            PlayerProp::UdpPort => Int(self.udp_port.into()),
+            PlayerProp::Alignment => Int(self.alignment.into()),
+            PlayerProp::AdditFlags => Int(self.addit_flags.into()),
+            PlayerProp::AccountName => Str(self.account_name.clone()),
+            PlayerProp::BodyImg => Str(self.body_img.clone()),
+            // 12 bits of rating, 9 of deviation
+            PlayerProp::Rating => Int(((self.rating.rating as i64 & 0xFFF) << 9) | (self.rating.deviation as i64 & 0x1FF)),
+            PlayerProp::AttachNPC => PropValue::AttachNpc(self.attach_npc.clone()),
+            PlayerProp::GmapLevelX => Int(self.gmap_level_x.into()),
+            PlayerProp::GmapLevelY => Int(self.gmap_level_y.into()),
+            PlayerProp::Z => Int(PixelCoord(self.z2.into()).to_half_tiles().0 as i64 + Z_OFFSET),
+            PlayerProp::JoinLeaveLvl => Int(self.join_leave_lvl.into()),
+            PlayerProp::Language => Str(self.language.clone()),
+            PlayerProp::PlayerListStatus => Int(self.player_list_status.into()),
+            PlayerProp::OsType => Str(self.os_type.clone()),
+            PlayerProp::TextCodePage => Int(self.text_code_page.into()),
+            PlayerProp::OnlineSecs2 => Int(self.online_secs2),
+            PlayerProp::X2 => Int(self.x2.into()),
+            PlayerProp::Y2 => Int(self.y2.into()),
+            PlayerProp::Z2 => Int(self.z2.into()),
+            PlayerProp::PlayerListCategory => Int(self.player_list_category.into()),
+            PlayerProp::CommunityName => Str(self.community_name.clone()),
+            PlayerProp::Unknown83 => Int(self.unknown_83),
+            _ => PropValue::Void,
+        }
+    }
+
+    /// Set a property from a decoded value and mark it modified
+    ///
+    /// # Returns
+    /// `false` if the value doesn't have the property's type
+    pub fn set_prop(&mut self, prop: PlayerProp, value: PropValue) -> bool {
+        if let (Some(index), PropValue::String(s)) = (prop.gani_attrib_index(), &value) {
+            self.gani_attribs[index] = s.clone();
+            self.mod_times.mark_modified(prop);
+            return true;
+        }
+
+        match (prop, value) {
+            (PlayerProp::Nickname, PropValue::String(s)) => self.nickname = s,
+            (PlayerProp::MaxPower, PropValue::Int(v)) => self.max_power = v as u8,
+            (PlayerProp::CurPower, PropValue::Int(v)) => self.cur_power = v as u8,
+            (PlayerProp::RupeesCount, PropValue::Int(v)) => self.rupees_count = v as u32,
+            (PlayerProp::ArrowsCount, PropValue::Int(v)) => self.arrows_count = v as u8,
+            (PlayerProp::BombsCount, PropValue::Int(v)) => self.bombs_count = v as u8,
+            (PlayerProp::GlovePower, PropValue::Int(v)) => self.glove_power = v as u8,
+            (PlayerProp::BombPower, PropValue::Int(v)) => self.bomb_power = v as u8,
+            (PlayerProp::SwordPower, PropValue::SwordPower(v)) => self.sword_power = v,
+            (PlayerProp::ShieldPower, PropValue::ShieldPower(v)) => self.shield_power = v,
+            (PlayerProp::Gani, PropValue::Gani(v)) => self.gani = v,
+            (PlayerProp::HeadGif, PropValue::HeadGif(v)) => self.head_gif = v,
+            (PlayerProp::CurChat, PropValue::String(s)) => self.cur_chat = s,
+            (PlayerProp::Colors, PropValue::Colors(c)) => self.colors = c,
+            (PlayerProp::Id, PropValue::Int(v)) => self.id = v as u16,
+            (PlayerProp::X, PropValue::Int(v)) => self.set_x_pixels(HalfTile(v as i16).to_pixels()),
+            (PlayerProp::Y, PropValue::Int(v)) => self.set_y_pixels(HalfTile(v as i16).to_pixels()),
+            (PlayerProp::Sprite, PropValue::Int(v)) => {
+                self.sprite = PropertySprite { sprite: (v / 4) as u8, direction: (v % 4) as u8 };
+            }
+            (PlayerProp::Status, PropValue::Int(v)) => self.status = v as u8,
+            (PlayerProp::CarrySprite, PropValue::Int(v)) => self.carry_sprite = v as u8,
+            (PlayerProp::CurLevel, PropValue::String(s)) => self.cur_level = s,
+            (PlayerProp::HorseGif, PropValue::String(s)) => self.horse_gif = s,
+            (PlayerProp::HorseBushes, PropValue::Int(v)) => self.horse_bushes = v as u8,
+            (PlayerProp::EffectColors, PropValue::Colors(c)) => self.effect_colors = c,
+            (PlayerProp::CarryNPC, PropValue::Int(v)) => self.carry_npc = v as u32,
+            (PlayerProp::ApCounter, PropValue::Int(v)) => self.ap_counter = v as u16,
+            (PlayerProp::MagicPoints, PropValue::Int(v)) => self.magic_points = v as u8,
+            (PlayerProp::KillsCount, PropValue::Int(v)) => self.kills_count = v as u32,
+            (PlayerProp::DeathsCount, PropValue::Int(v)) => self.deaths_count = v as u32,
+            (PlayerProp::OnlineSecs, PropValue::Int(v)) => self.online_secs = v as u32,
+            (PlayerProp::IpAddr, PropValue::Int(v)) => self.ip_addr = v,
+            (PlayerProp::UdpPort, PropValue::Int(v)) => self.udp_port = v as u32,
+            (PlayerProp::Alignment, PropValue::Int(v)) => self.alignment = v as u8,
+            (PlayerProp::AdditFlags, PropValue::Int(v)) => self.addit_flags = v as u8,
+            (PlayerProp::AccountName, PropValue::String(s)) => self.account_name = s,
+            (PlayerProp::BodyImg, PropValue::String(s)) => self.body_img = s,
+            (PlayerProp::Rating, PropValue::Int(v)) => {
+                self.rating = PropertyEloRating { rating: ((v >> 9) & 0xFFF) as f32, deviation: (v & 0x1FF) as f32 };
+            }
+            (PlayerProp::AttachNPC, PropValue::AttachNpc(v)) => self.attach_npc = v,
+            (PlayerProp::GmapLevelX, PropValue::Int(v)) => self.gmap_level_x = v as u8,
+            (PlayerProp::GmapLevelY, PropValue::Int(v)) => self.gmap_level_y = v as u8,
+            (PlayerProp::Z, PropValue::Int(v)) => self.set_z_pixels(HalfTile((v - Z_OFFSET) as i16).to_pixels()),
+            (PlayerProp::JoinLeaveLvl, PropValue::Int(v)) => self.join_leave_lvl = v as u8,
+            (PlayerProp::Disconnect, PropValue::Void) => self.disconnect = true,
+            (PlayerProp::Language, PropValue::String(s)) => self.language = s,
+            (PlayerProp::PlayerListStatus, PropValue::Int(v)) => self.player_list_status = v as u8,
+            (PlayerProp::OsType, PropValue::String(s)) => self.os_type = s,
+            (PlayerProp::TextCodePage, PropValue::Int(v)) => self.text_code_page = v as u32,
+            (PlayerProp::OnlineSecs2, PropValue::Int(v)) => self.online_secs2 = v,
+            (PlayerProp::X2, PropValue::Int(v)) => self.set_x_pixels(PixelCoord(v as i32)),
+            (PlayerProp::Y2, PropValue::Int(v)) => self.set_y_pixels(PixelCoord(v as i32)),
+            (PlayerProp::Z2, PropValue::Int(v)) => self.set_z_pixels(PixelCoord(v as i32)),
+            (PlayerProp::PlayerListCategory, PropValue::Int(v)) => self.player_list_category = v as u8,
+            (PlayerProp::CommunityName, PropValue::String(s)) => self.community_name = s,
+            (PlayerProp::Unknown83, PropValue::Int(v)) => self.unknown_83 = v,
+            _ => return false,
+        }
+        self.mod_times.mark_modified(prop);
+        true
+    }
+}
+
+/// Write a `{GCHAR length}{bytes}` prop string
+///
+/// Unlike [`write_gstring`], the length can go up to 223.
+fn write_prop_string(buf: &mut BytesMut, bytes: &[u8], max_len: usize) {
+    let len = bytes.len().min(max_len);
+    buf.put_u8(len as u8 + 32);
+    buf.put_slice(&bytes[..len]);
+}
+
+/// Read `len` bytes as a (possibly non-UTF-8) prop string
+fn read_prop_bytes(buf: &mut BytesMut, len: usize) -> Result<String> {
+    if buf.remaining() < len {
+        return Err(ProtocolError::Truncated { field: "prop string", needed: len, remaining: buf.remaining() }.into());
+    }
+    Ok(String::from_utf8_lossy(&buf.split_to(len)).into_owned())
+}
+
+/// Read an unsigned GCHAR (0-223)
+fn read_guchar(buf: &mut BytesMut) -> Result<u8> {
+    Ok(read_gchar(buf)? as u8)
+}
+
+/// Encode a value as `ty`
+///
+/// # Returns
+/// `false` (writing nothing) if the value doesn't have that type
+pub fn encode_value(ty: PropType, value: &PropValue, version: ClientVersion, buf: &mut BytesMut) -> bool {
+    match (ty, value) {
+        (PropType::Void, _) => {}
+        (PropType::GChar, PropValue::Int(v)) => write_gchar(buf, *v as i8),
+        (PropType::GShort, PropValue::Int(v)) => write_gshort(buf, *v as i16),
+        (PropType::GInt, PropValue::Int(v)) => write_gint(buf, *v as i32),
+        (PropType::GInt5, PropValue::Int(v)) => write_guint5(buf, *v as u32),
+        (PropType::PixelCoordinate, PropValue::Int(v)) => {
+            let raw = ((v.unsigned_abs() as u16) << 1) | (*v < 0) as u16;
+            write_gshort(buf, raw as i16);
+        }
+        (PropType::String, PropValue::String(s)) => write_prop_string(buf, s.as_bytes(), 223),
+        (PropType::Colors, PropValue::Colors(colors)) => {
+            colors.iter().for_each(|&c| write_gchar(buf, c as i8));
+        }
+        (PropType::EffectColors, PropValue::Colors(colors)) => {
+            if colors[0] == 0 {
+                write_gchar(buf, 0);
+            } else {
+                colors.iter().for_each(|&c| write_gchar(buf, c as i8));
+            }
+        }
+        (PropType::SwordPower, PropValue::SwordPower(sword)) => {
+            let power = sword.power.unwrap_or(0);
+            if sword.image.is_empty() {
+                write_gchar(buf, power);
+            } else {
+                write_gchar(buf, power.wrapping_add(30));
+                write_prop_string(buf, sword.image.as_bytes(), 223);
+            }
+        }
+        (PropType::ShieldPower, PropValue::ShieldPower(shield)) => {
+            let power = shield.power.unwrap_or(0);
+            if shield.image.is_empty() {
+                write_gchar(buf, power as i8);
+            } else {
+                write_gchar(buf, power.wrapping_add(10) as i8);
+                write_prop_string(buf, shield.image.as_bytes(), 223);
+            }
+        }
+        (PropType::GaniOrBowGif, PropValue::Gani(gani)) => match gani {
+            PropertyGaniOrBowGif::Gani(name) if version >= ClientVersion::V2 => {
+                write_prop_string(buf, name.as_bytes(), 223);
+            }
+            // Classic clients only know the bow; a gani shows as no bow
+            PropertyGaniOrBowGif::Gani(_) => write_gchar(buf, 0),
+            PropertyGaniOrBowGif::BowGif { gif, power } => {
+                if gif.is_empty() {
+                    write_gchar(buf, *power as i8);
+                } else {
+                    let len = gif.len().min(213);
+                    write_gchar(buf, (len + 10) as u8 as i8);
+                    buf.put_slice(&gif.as_bytes()[..len]);
+                }
+            }
+        },
+        (PropType::HeadGif, PropValue::HeadGif(head)) => match head {
+            PropertyHeadGif::Preset(id) => write_gchar(buf, (*id).min(99) as i8),
+            PropertyHeadGif::Image(image) => {
+                let len = image.len().min(123);
+                write_gchar(buf, (len + 100) as u8 as i8);
+                buf.put_slice(&image.as_bytes()[..len]);
+            }
+        },
+        (PropType::AttachNpc, PropValue::AttachNpc(npc)) => {
+            write_gchar(buf, npc.type_code as i8);
+            write_gint(buf, npc.npc_id as i32);
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// Decode a value of type `ty`
+///
+/// # Errors
+/// `ProtocolError::Truncated` if the buffer ends inside the value
+pub fn decode_value(ty: PropType, version: ClientVersion, buf: &mut BytesMut) -> Result<PropValue> {
+    let value = match ty {
+        PropType::Void => PropValue::Void,
+        PropType::GChar => PropValue::Int(read_guchar(buf)?.into()),
+        PropType::GShort => PropValue::Int(read_gshort(buf)?.into()),
+        PropType::GInt => PropValue::Int(read_gint(buf)?.into()),
+        PropType::GInt5 => PropValue::Int(read_guint5(buf)?.into()),
+        PropType::PixelCoordinate => {
+            let raw = read_gshort(buf)? as u16;
+            let pixels = (raw >> 1) as i64;
+            PropValue::Int(if raw & 1 != 0 { -pixels } else { pixels })
+        }
+        PropType::String => {
+            let len = read_guchar(buf)? as usize;
+            PropValue::String(read_prop_bytes(buf, len)?)
+        }
+        PropType::Colors => {
+            let mut colors = [0; 5];
+            for color in &mut colors {
+                *color = read_guchar(buf)?;
+            }
+            PropValue::Colors(colors)
+        }
+        PropType::EffectColors => {
+            let mut colors = [0; 5];
+            colors[0] = read_guchar(buf)?;
+            if colors[0] > 0 {
+                for color in &mut colors[1..] {
+                    *color = read_guchar(buf)?;
+                }
+            }
+            PropValue::Colors(colors)
+        }
+        PropType::SwordPower => {
+            let power = read_guchar(buf)?;
+            PropValue::SwordPower(if power > 4 {
+                let len = read_guchar(buf)? as usize;
+                PropertySwordPower { power: Some(power as i8 - 30), image: read_prop_bytes(buf, len)? }
+            } else {
+                PropertySwordPower { power: Some(power as i8), image: String::new() }
+            })
+        }
+        PropType::ShieldPower => {
+            let power = read_guchar(buf)?;
+            PropValue::ShieldPower(if power > 3 {
+                let len = read_guchar(buf)? as usize;
+                PropertyShieldPower { power: Some(power.saturating_sub(10)), image: read_prop_bytes(buf, len)? }
+            } else {
+                PropertyShieldPower { power: Some(power), image: String::new() }
+            })
+        }
+        PropType::GaniOrBowGif if version >= ClientVersion::V2 => {
+            let len = read_guchar(buf)? as usize;
+            PropValue::Gani(PropertyGaniOrBowGif::Gani(read_prop_bytes(buf, len)?))
+        }
+        PropType::GaniOrBowGif => {
+            let n = read_guchar(buf)?;
+            PropValue::Gani(if n < 10 {
+                PropertyGaniOrBowGif::BowGif { gif: String::new(), power: n }
+            } else {
+                PropertyGaniOrBowGif::BowGif { gif: read_prop_bytes(buf, n as usize - 10)?, power: 0 }
+            })
+        }
+        PropType::HeadGif => {
+            let n = read_guchar(buf)?;
+            PropValue::HeadGif(if n < 100 {
+                PropertyHeadGif::Preset(n)
+            } else {
+                PropertyHeadGif::Image(read_prop_bytes(buf, n as usize - 100)?)
+            })
+        }
+        PropType::AttachNpc => {
+            let type_code = read_guchar(buf)?;
+            let npc_id = read_gint(buf)? as u32;
+            PropValue::AttachNpc(PropertyAttachNPC { npc_id, type_code })
+        }
+    };
+    Ok(value)
+}
+
+/// Write one `{GCHAR prop}{value}` pair
+///
+/// # Returns
+/// `false` (writing nothing) if the client version doesn't know the
+/// property or the value doesn't have its type
+pub fn encode_prop(prop: PlayerProp, value: &PropValue, version: ClientVersion, buf: &mut BytesMut) -> bool {
+    let def = prop.def();
+    if !def.available(version) {
+        return false;
+    }
+
+    let start = buf.len();
+    write_gchar(buf, prop as u8 as i8);
+    if !encode_value(def.ty, value, version, buf) {
+        buf.truncate(start);
+        return false;
+    }
+    true
+}
+
+/// Encode a list of a player's properties
+///
+/// Properties the client version doesn't know are skipped.
+///
+/// # C++ Equivalence
+/// Matches `Player::getProps`
+pub fn encode_props(props: &PlayerProperties, list: &[PlayerProp], version: ClientVersion, buf: &mut BytesMut) {
+    for &prop in list {
+        encode_prop(prop, &props.prop_value(prop), version, buf);
+    }
+}
+
+/// Decode a value returned by [`split_props`]
+pub fn decode_prop(prop: PlayerProp, value: &[u8], version: ClientVersion) -> Result<PropValue> {
+    decode_value(prop.def().ty, version, &mut BytesMut::from(value))
+}
+
+/// Decode a `{GCHAR prop}{value}` list
+///
+/// # Errors
+/// `ProtocolError::Malformed` for an unknown property, or
+/// `ProtocolError::Truncated` if a value runs past the end
+pub fn decode_props(data: &[u8], version: ClientVersion) -> Result<Vec<(PlayerProp, PropValue)>> {
+    let mut buf = BytesMut::from(data);
+    let mut props = Vec::new();
+
+    while buf.has_remaining() {
+        let id = read_guchar(&mut buf)?;
+        let prop = PlayerProp::from_u8(id).ok_or_else(|| ProtocolError::Malformed {
+            packet: "PlayerProps",
+            reason: format!("unknown property {}", id),
+        })?;
+        props.push((prop, decode_value(prop.def().ty, version, &mut buf)?));
+    }
+    Ok(props)
+}
+
+/// Build PLO_OTHERPLPROPS, as broadcast to the players around a player
+///
+/// # Packet Format
+/// ```text
+/// {PLO_OTHERPLPROPS}{GSHORT player id}[{GCHAR prop}{value}]...
+/// ```
+pub fn build_other_props_packet(props: &PlayerProperties, version: ClientVersion) -> BytesMut {
+    let mut data = BytesMut::new();
+    encode_props(props, OTHER_PLAYER_PROPS, version, &mut data);
+
+    let mut buf = BytesMut::new();
+    build_other_player_props(&mut buf, props.id, &data);
+    buf
+}
+
+/// Split a PLI_PLAYERPROPS payload into raw property values
+///
+/// # Format
+/// The payload is a sequence of `{GCHAR prop}{value}`, where the value
+/// layout depends on the property (fixed width, `{GCHAR length}{string}`,
+/// or one of the combined sword/shield/head/effect encodings).
+///
+/// # Returns
+/// Each property with its encoded value (without the property byte). Splitting
+/// stops at the first unknown property or truncated value.
+///
+/// # C++ Equivalence
+/// Follows the value layouts read by `PlayerClient::setProps` in PlayerProps.cpp
+pub fn split_props(data: &[u8]) -> Vec<(PlayerProp, &[u8])> {
+    let mut props = Vec::new();
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let Some(prop) = PlayerProp::from_u8(data[pos].saturating_sub(32)) else { break };
+        let start = pos + 1;
+
+        match prop.def().ty.value_len(&data[start..]) {
+            Some(len) if start + len <= data.len() => {
+                props.push((prop, &data[start..start + len]));
+                pos = start + len;
+            }
+            _ => break,
+        }
+    }
+
+    props
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -658,6 +1378,80 @@ mod tests {
         assert_eq!(PlayerProp::PlayerPropCount as u8, 84);
     }
 
+    #[test]
+    fn test_split_props() {
+        // Nickname "Bob", sprite 2, head preset 5, sword 3 (no image)
+        let data = [32, 35, b'B', b'o', b'b', 32 + 17, 34, 32 + 11, 37, 32 + 8, 35];
+        let props = split_props(&data);
+
+        assert_eq!(props, vec![
+            (PlayerProp::Nickname, &data[1..5]),
+            (PlayerProp::Sprite, &data[6..7]),
+            (PlayerProp::HeadGif, &data[8..9]),
+            (PlayerProp::SwordPower, &data[10..11]),
+        ]);
+
+        // Truncated string stops splitting
+        assert!(split_props(&[32, 40, b'x']).is_empty());
+    }
+
+    #[test]
+    fn test_prop_table_is_indexed_by_id() {
+        for (id, def) in PROP_TABLE.iter().enumerate() {
+            assert_eq!(def.prop as usize, id);
+        }
+        assert_eq!(PlayerProp::X2.def().ty, PropType::PixelCoordinate);
+        assert_eq!(PlayerProp::GAttrib6.gani_attrib_index(), Some(5));
+    }
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        let mut props = PlayerProperties::new();
+        props.nickname = "Bob".to_string();
+        props.id = 300;
+        props.set_x_pixels(PixelCoord(-24));
+        props.set_y_pixels(PixelCoord(488));
+        props.head_gif = PropertyHeadGif::Image("head19.png".to_string());
+        props.sword_power = PropertySwordPower { image: "sword2.png".to_string(), power: Some(2) };
+        props.shield_power = PropertyShieldPower { image: String::new(), power: Some(1) };
+        props.effect_colors = [4, 1, 2, 3, 0];
+        props.gani_attribs[0] = "hat0.png".to_string();
+
+        let mut buf = BytesMut::new();
+        encode_props(&props, OTHER_PLAYER_PROPS, ClientVersion::V2_19, &mut buf);
+
+        let mut decoded = PlayerProperties::new();
+        for (prop, value) in decode_props(&buf, ClientVersion::V2_19).unwrap() {
+            assert!(decoded.set_prop(prop, value), "{:?}", prop);
+        }
+        for &prop in OTHER_PLAYER_PROPS {
+            assert_eq!(decoded.prop_value(prop), props.prop_value(prop), "{:?}", prop);
+        }
+        assert_eq!((decoded.x2, decoded.y2), (-24, 488));
+        assert!(decoded.mod_times.get_mod_time(PlayerProp::Nickname).is_some());
+
+        // split_props measures every value the encoder wrote
+        assert_eq!(split_props(&buf).len(), OTHER_PLAYER_PROPS.len());
+    }
+
+    #[test]
+    fn test_encode_skips_unavailable_props() {
+        let props = PlayerProperties::new();
+        let mut buf = BytesMut::new();
+        encode_props(&props, &[PlayerProp::X2, PlayerProp::GAttrib1], ClientVersion::V2, &mut buf);
+        assert_eq!(&buf[..], &[32 + 37, 32]);
+
+        // Values of the wrong type write nothing
+        assert!(!encode_prop(PlayerProp::Id, &PropValue::String("x".into()), ClientVersion::V2_19, &mut buf));
+        assert_eq!(buf.len(), 2);
+
+        // Names longer than a signed GCHAR still round-trip
+        let long = "a".repeat(150);
+        buf.clear();
+        assert!(encode_prop(PlayerProp::Nickname, &PropValue::String(long.clone()), ClientVersion::V2, &mut buf));
+        assert_eq!(decode_props(&buf, ClientVersion::V2).unwrap(), vec![(PlayerProp::Nickname, PropValue::String(long))]);
+    }
+
     #[test]
     fn test_player_prop_from_u8() {
         assert_eq!(PlayerProp::from_u8(0), Some(PlayerProp::Nickname));
@@ -688,15 +1482,15 @@ mod tests {
         // Test tile to pixel conversion
         props.x = 10;
         props.y = 20;
-        assert_eq!(props.x_pixels(), 160);
-        assert_eq!(props.y_pixels(), 320);
+        assert_eq!(props.x_pixels(), PixelCoord(160));
+        assert_eq!(props.y_pixels(), PixelCoord(320));
 
         // Test pixel to tile conversion
-        props.set_x_pixels(160);
-        props.set_y_pixels(320);
+        props.set_x_pixels(PixelCoord(167));
+        props.set_y_pixels(PixelCoord(320));
         assert_eq!(props.x, 10);
         assert_eq!(props.y, 20);
-        assert_eq!(props.x2, 160);
+        assert_eq!(props.x2, 167);
         assert_eq!(props.y2, 320);
     }
 
@@ -0,0 +1,65 @@
+//! # Friends and Ignore Lists
+//!
+//! Each account keeps a friend list, an ignore list and a "PMs from friends
+//! only" switch, saved in the account file. PMs from ignored accounts (and
+//! from non-friends with the switch on) are dropped without telling the
+//! sender.
+//!
+//! Players change their lists with trigger actions; RC changes anyone's
+//! with `/social`. The lists stay on the server and the PM relay checks
+//! them, so no client learns who ignores it.
+
+/// A change to an account's friend or ignore list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SocialEdit {
+    /// `gr.addfriend,<account>`
+    AddFriend(String),
+    /// `gr.removefriend,<account>`
+    RemoveFriend(String),
+    /// `gr.ignore,<account>`
+    Ignore(String),
+    /// `gr.unignore,<account>`
+    Unignore(String),
+    /// `gr.pmfriendsonly,<1|0>`
+    FriendsOnly(bool),
+}
+
+impl SocialEdit {
+    /// Parse a command and its argument (a trigger action without `gr.`)
+    ///
+    /// # Returns
+    /// `None` for other commands, or an empty account name
+    pub fn parse(command: &str, value: &str) -> Option<Self> {
+        let value = value.trim();
+        let edit = match command.trim().to_lowercase().as_str() {
+            "pmfriendsonly" => return Some(Self::FriendsOnly(matches!(value, "1" | "true" | "on"))),
+            "addfriend" => Self::AddFriend,
+            "removefriend" => Self::RemoveFriend,
+            "ignore" => Self::Ignore,
+            "unignore" => Self::Unignore,
+            _ => return None,
+        };
+        (!value.is_empty()).then(|| edit(value.to_string()))
+    }
+
+    /// Parse a trigger action string (`gr.addfriend,Bob`)
+    pub fn parse_trigger(action: &str) -> Option<Self> {
+        let (command, value) = action.split_once(',').unwrap_or((action, ""));
+        Self::parse(command.trim().strip_prefix("gr.")?, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_social_edits() {
+        assert_eq!(SocialEdit::parse_trigger("gr.addfriend, Bob"), Some(SocialEdit::AddFriend("Bob".into())));
+        assert_eq!(SocialEdit::parse_trigger("gr.unignore,x"), Some(SocialEdit::Unignore("x".into())));
+        assert_eq!(SocialEdit::parse_trigger("gr.pmfriendsonly,1"), Some(SocialEdit::FriendsOnly(true)));
+        assert_eq!(SocialEdit::parse_trigger("gr.ignore,"), None);
+        assert_eq!(SocialEdit::parse_trigger("gr.setgroup,red"), None);
+        assert_eq!(SocialEdit::parse("RemoveFriend", "Bob"), Some(SocialEdit::RemoveFriend("Bob".into())));
+    }
+}
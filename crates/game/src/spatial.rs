@@ -0,0 +1,117 @@
+//! # Spatial Index
+//!
+//! Players bucketed by position in square cells of [`CELL_TILES`], one grid
+//! per space (a level instance, or a whole gmap). Range queries only look at
+//! the cells overlapping the range, so they cost the same on an empty gmap
+//! corner as on a full level.
+
+use gserver_core::PlayerID;
+use std::collections::{HashMap, HashSet};
+
+/// Width and height of a cell, in tiles
+pub const CELL_TILES: i32 = 16;
+
+/// A position in a space, in tiles
+///
+/// On a gmap, positions count from the top left of the whole map.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WorldPos {
+    /// The level instance or gmap
+    pub space: String,
+    pub x: i32,
+    pub y: i32,
+}
+
+impl WorldPos {
+    /// Create a position
+    pub fn new(space: impl Into<String>, x: i32, y: i32) -> Self {
+        Self { space: space.into(), x, y }
+    }
+
+    /// Get the distance to another position (the larger of the two axes),
+    /// `None` in another space
+    pub fn distance(&self, other: &WorldPos) -> Option<i32> {
+        (self.space == other.space).then(|| (self.x - other.x).abs().max((self.y - other.y).abs()))
+    }
+
+    fn cell(&self) -> (i32, i32) {
+        (self.x.div_euclid(CELL_TILES), self.y.div_euclid(CELL_TILES))
+    }
+}
+
+/// Players by cell
+#[derive(Debug, Default)]
+pub struct SpatialIndex {
+    cells: HashMap<(String, i32, i32), HashSet<PlayerID>>,
+    positions: HashMap<PlayerID, WorldPos>,
+}
+
+impl SpatialIndex {
+    /// Create an empty index
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Place a player, moving it from its previous cell
+    pub fn insert(&mut self, id: PlayerID, pos: WorldPos) {
+        self.remove(id);
+        let (cx, cy) = pos.cell();
+        self.cells.entry((pos.space.clone(), cx, cy)).or_default().insert(id);
+        self.positions.insert(id, pos);
+    }
+
+    /// Take a player out of the index
+    ///
+    /// # Returns
+    /// Where the player was
+    pub fn remove(&mut self, id: PlayerID) -> Option<WorldPos> {
+        let pos = self.positions.remove(&id)?;
+        let (cx, cy) = pos.cell();
+        let key = (pos.space.clone(), cx, cy);
+        if let Some(cell) = self.cells.get_mut(&key) {
+            cell.remove(&id);
+            if cell.is_empty() {
+                self.cells.remove(&key);
+            }
+        }
+        Some(pos)
+    }
+
+    /// Get a player's position
+    pub fn position(&self, id: PlayerID) -> Option<&WorldPos> {
+        self.positions.get(&id)
+    }
+
+    /// Get the players within `range` tiles of a position, `None` for the
+    /// whole space
+    pub fn within(&self, pos: &WorldPos, range: Option<i32>) -> Vec<PlayerID> {
+        let Some(range) = range else {
+            return self.positions.iter()
+                .filter(|(_, other)| other.space == pos.space)
+                .map(|(&id, _)| id)
+                .collect();
+        };
+
+        let cells = |centre: i32| (centre - range).div_euclid(CELL_TILES)..=(centre + range).div_euclid(CELL_TILES);
+        let mut found = Vec::new();
+        for cx in cells(pos.x) {
+            for cy in cells(pos.y) {
+                let Some(cell) = self.cells.get(&(pos.space.clone(), cx, cy)) else { continue };
+                found.extend(cell.iter().copied().filter(|id| {
+                    self.positions[id].distance(pos).is_some_and(|d| d <= range)
+                }));
+            }
+        }
+        found
+    }
+
+    /// Get the number of players indexed
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Check if no player is indexed
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+}
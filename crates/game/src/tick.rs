@@ -0,0 +1,351 @@
+//! # Game Tick Loop
+//!
+//! This module provides the central fixed-timestep loop that drives all
+//! time-based game logic.
+//!
+//! # Architecture
+//!
+//! The loop runs at a fixed rate (`tickrate` in serveroptions.txt, default 20 Hz).
+//! Systems register named timers with an interval; on every tick the loop runs
+//! each timer whose interval has elapsed. Intervals are rounded to whole ticks.
+//!
+//! ```text
+//! tick 0 ──┬─ npc movement, watchdog (every tick)
+//!          └─ world time (every 100 ticks at 20 Hz)
+//! ```
+//!
+//! # C++ Equivalence
+//!
+//! Replaces the world time update of `Server::doTimedEvents` in Server.cpp
+//! (every 5 seconds). Its level timers (baddy respawn, bombs, horse
+//! lifetimes) and NPC timeouts have no serverside state to drive here yet;
+//! they get a [`GameTimer`] when they do. Autosave runs on its own task (see
+//! the `autosave` module) because it does blocking file I/O.
+//!
+//! # Overruns
+//!
+//! If a tick takes longer than its budget (`1 / tickrate`), a warning is
+//! logged and the overrun is counted in [`TickStats`]. Missed ticks are
+//! skipped rather than replayed in a burst.
+
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+
+/// Default tick rate (Hz)
+pub const DEFAULT_TICK_RATE: u32 = 20;
+
+/// Timers driven by the tick loop
+///
+/// # C++ Equivalence
+/// Intervals match the C++ server's timed events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameTimer {
+    /// Advance Graal world time (PLO_NEWWORLDTIME)
+    WorldTime,
+}
+
+impl GameTimer {
+    /// Get the timer name (used in logs and stats)
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameTimer::WorldTime => "world_time",
+        }
+    }
+
+    /// Get the default interval of this timer
+    pub fn default_interval(&self) -> Duration {
+        match self {
+            GameTimer::WorldTime => Duration::from_secs(5),
+        }
+    }
+}
+
+/// Calculate the current Graal world time
+///
+/// # C++ Equivalence
+/// Matches `Server::calculateNWTime()`: one unit every 5 seconds since
+/// the Graal epoch (day 11078 of the unix epoch).
+pub fn world_time() -> u32 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    (now.saturating_sub(11078 * 24 * 60 * 60) / 5) as u32
+}
+
+/// Information passed to every timer callback
+#[derive(Debug, Clone, Copy)]
+pub struct TickContext {
+    /// Tick number (starts at 0)
+    pub tick: u64,
+
+    /// Time the tick started
+    pub now: Instant,
+
+    /// Time since the previous tick
+    pub delta: Duration,
+}
+
+/// Tick timing statistics
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TickStats {
+    /// Ticks executed
+    pub ticks: u64,
+
+    /// Ticks that exceeded their budget
+    pub overruns: u64,
+
+    /// Duration of the last tick
+    pub last_duration: Duration,
+
+    /// Longest tick so far
+    pub max_duration: Duration,
+
+    /// Total time spent in ticks (for averaging)
+    pub total_duration: Duration,
+}
+
+impl TickStats {
+    /// Average tick duration
+    pub fn average_duration(&self) -> Duration {
+        if self.ticks == 0 {
+            return Duration::ZERO;
+        }
+        self.total_duration / self.ticks as u32
+    }
+
+    fn record(&mut self, duration: Duration, overrun: bool) {
+        self.ticks += 1;
+        self.last_duration = duration;
+        self.max_duration = self.max_duration.max(duration);
+        self.total_duration += duration;
+        if overrun {
+            self.overruns += 1;
+        }
+    }
+}
+
+/// Callback run by a timer
+pub type TimerCallback = Box<dyn FnMut(&TickContext) + Send>;
+
+/// A registered timer
+struct Timer {
+    name: String,
+    interval_ticks: u64,
+    next_tick: u64,
+    callback: TimerCallback,
+}
+
+/// Fixed-timestep game loop
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use gserver_game::tick::{GameTimer, TickLoop};
+///
+/// # async fn example() {
+/// let mut tick_loop = TickLoop::new(20);
+/// tick_loop.add_game_timer(GameTimer::WorldTime, |ctx| {
+///     println!("tick {}", ctx.tick);
+/// });
+///
+/// let (_shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+/// tokio::spawn(tick_loop.run(shutdown_rx));
+/// # }
+/// ```
+pub struct TickLoop {
+    /// Tick length
+    tick_duration: Duration,
+
+    /// Registered timers
+    timers: Vec<Timer>,
+
+    /// Current tick number
+    tick: u64,
+
+    /// Start of the previous tick
+    last_tick: Option<Instant>,
+
+    /// Timing statistics (shared so they can be read while the loop runs)
+    stats: Arc<Mutex<TickStats>>,
+}
+
+impl TickLoop {
+    /// Create a new tick loop
+    ///
+    /// # Arguments
+    /// * `tick_rate` - Ticks per second (clamped to 1..=1000)
+    pub fn new(tick_rate: u32) -> Self {
+        let tick_rate = tick_rate.clamp(1, 1000);
+
+        Self {
+            tick_duration: Duration::from_secs(1) / tick_rate,
+            timers: Vec::new(),
+            tick: 0,
+            last_tick: None,
+            stats: Arc::new(Mutex::new(TickStats::default())),
+        }
+    }
+
+    /// Use an existing statistics handle (e.g. one held by the server context)
+    pub fn with_stats(mut self, stats: Arc<Mutex<TickStats>>) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// Register a timer
+    ///
+    /// # Arguments
+    /// * `name` - Timer name (for logging)
+    /// * `interval` - How often to run; rounded to whole ticks, at least one
+    /// * `callback` - Function to run
+    pub fn add_timer<F>(&mut self, name: &str, interval: Duration, callback: F)
+    where
+        F: FnMut(&TickContext) + Send + 'static,
+    {
+        let interval_ticks = (interval.as_nanos() / self.tick_duration.as_nanos()).max(1) as u64;
+
+        tracing::debug!("Registered tick timer '{}' every {} ticks", name, interval_ticks);
+        self.timers.push(Timer {
+            name: name.to_string(),
+            interval_ticks,
+            next_tick: self.tick,
+            callback: Box::new(callback),
+        });
+    }
+
+    /// Register one of the standard game timers at its default interval
+    pub fn add_game_timer<F>(&mut self, timer: GameTimer, callback: F)
+    where
+        F: FnMut(&TickContext) + Send + 'static,
+    {
+        self.add_timer(timer.name(), timer.default_interval(), callback);
+    }
+
+    /// Get the tick budget (`1 / tick_rate`)
+    pub fn tick_duration(&self) -> Duration {
+        self.tick_duration
+    }
+
+    /// Get a handle to the tick statistics
+    pub fn stats(&self) -> Arc<Mutex<TickStats>> {
+        self.stats.clone()
+    }
+
+    /// Run a single tick
+    ///
+    /// # Returns
+    /// How long the tick took
+    pub fn tick_once(&mut self) -> Duration {
+        let now = Instant::now();
+        let ctx = TickContext {
+            tick: self.tick,
+            now,
+            delta: self.last_tick.map(|t| now - t).unwrap_or(self.tick_duration),
+        };
+
+        for timer in &mut self.timers {
+            if ctx.tick >= timer.next_tick {
+                tracing::trace!("Tick {}: running timer '{}'", ctx.tick, timer.name);
+                (timer.callback)(&ctx);
+                timer.next_tick = ctx.tick + timer.interval_ticks;
+            }
+        }
+
+        let elapsed = now.elapsed();
+        let overrun = elapsed > self.tick_duration;
+        if overrun {
+            tracing::warn!("Tick {} overran its budget: {:?} > {:?}", ctx.tick, elapsed, self.tick_duration);
+        }
+        self.stats.lock().record(elapsed, overrun);
+
+        self.last_tick = Some(now);
+        self.tick += 1;
+        elapsed
+    }
+
+    /// Run the loop until `shutdown` becomes `true` (or its sender is dropped)
+    pub async fn run(mut self, mut shutdown: watch::Receiver<bool>) {
+        let mut interval = tokio::time::interval(self.tick_duration);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        tracing::info!("Tick loop started: {:?} per tick, {} timers",
+            self.tick_duration, self.timers.len());
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    self.tick_once();
+                }
+                result = shutdown.changed() => {
+                    if result.is_err() || *shutdown.borrow() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        let stats = self.stats.lock().clone();
+        tracing::info!("Tick loop stopped after {} ticks ({} overruns, avg {:?}, max {:?})",
+            stats.ticks, stats.overruns, stats.average_duration(), stats.max_duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[test]
+    fn test_timer_intervals() {
+        let mut tick_loop = TickLoop::new(20);
+        let fast = Arc::new(AtomicU64::new(0));
+        let slow = Arc::new(AtomicU64::new(0));
+
+        let f = fast.clone();
+        tick_loop.add_timer("fast", Duration::from_millis(50), move |_| { f.fetch_add(1, Ordering::Relaxed); });
+        let s = slow.clone();
+        tick_loop.add_game_timer(GameTimer::WorldTime, move |_| { s.fetch_add(1, Ordering::Relaxed); });
+
+        for _ in 0..200 {
+            tick_loop.tick_once();
+        }
+
+        assert_eq!(fast.load(Ordering::Relaxed), 200);
+        assert_eq!(slow.load(Ordering::Relaxed), 2);
+        assert_eq!(tick_loop.stats().lock().ticks, 200);
+    }
+
+    #[test]
+    fn test_overrun_counted() {
+        let mut tick_loop = TickLoop::new(1000);
+        tick_loop.add_timer("slow", Duration::ZERO, |_| std::thread::sleep(Duration::from_millis(3)));
+
+        tick_loop.tick_once();
+
+        let stats = tick_loop.stats().lock().clone();
+        assert_eq!(stats.overruns, 1);
+        assert!(stats.max_duration >= Duration::from_millis(3));
+    }
+
+    #[tokio::test]
+    async fn test_run_until_shutdown() {
+        let tick_loop = TickLoop::new(100);
+        let stats = tick_loop.stats();
+        let (tx, rx) = watch::channel(false);
+
+        let handle = tokio::spawn(tick_loop.run(rx));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        tx.send(true).unwrap();
+        handle.await.unwrap();
+
+        assert!(stats.lock().ticks > 0);
+    }
+
+    #[test]
+    fn test_world_time_advances() {
+        assert!(world_time() > 0);
+    }
+}
@@ -0,0 +1,493 @@
+//! # Weapon Manager
+//!
+//! This module loads and tracks the server's weapon definitions
+//! (`weapons/weapon-*.txt`).
+//!
+//! # File Format
+//!
+//! ```text
+//! GRAWP001
+//! REALNAME -gr_movement
+//! IMAGE wbomb1.png
+//! SCRIPT
+//! //#CLIENTSIDE
+//! ...
+//! SCRIPTEND
+//! ```
+//!
+//! # Classes
+//!
+//! A weapon's clientside script is sent to players together with the
+//! clientside parts of the classes it joins (`join("name")`), as a
+//! [`WeaponBuild`]. The manager keeps which weapons join which class, so
+//! editing a class only rebuilds the weapons that use it
+//! ([`WeaponManager::invalidate_class`]), and each build has a checksum that
+//! changes with its code.
+//!
+//! # C++ Equivalence
+//! Matches `CWeapon::loadWeapon` in Weapon.cpp
+
+use crate::classes::{file_mod_time, ClassManager};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Weapon file header
+const WEAPON_HEADER: &str = "GRAWP001";
+
+/// A server weapon definition
+#[derive(Debug, Clone, PartialEq)]
+pub struct Weapon {
+    /// Weapon name (e.g., "-gr_movement")
+    pub name: String,
+
+    /// Weapon icon image
+    pub image: String,
+
+    /// Full weapon script (serverside and clientside parts)
+    pub script: String,
+
+    /// Modification time of the weapon (unix seconds)
+    pub mod_time: u64,
+}
+
+impl Weapon {
+    /// Create a new weapon
+    pub fn new(name: impl Into<String>, image: impl Into<String>, script: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            image: image.into(),
+            script: script.into(),
+            mod_time: 0,
+        }
+    }
+
+    /// Parse a weapon from the GRAWP001 file format
+    ///
+    /// # Returns
+    /// The parsed weapon, or `None` if the header or name is missing
+    pub fn parse(content: &str) -> Option<Self> {
+        let mut lines = content.lines();
+        if lines.next()?.trim() != WEAPON_HEADER {
+            return None;
+        }
+
+        let mut name = String::new();
+        let mut image = String::new();
+        let mut script = String::new();
+        let mut in_script = false;
+
+        for line in lines {
+            if in_script {
+                if line.trim() == "SCRIPTEND" {
+                    in_script = false;
+                } else {
+                    script.push_str(line);
+                    script.push('\n');
+                }
+                continue;
+            }
+
+            let line = line.trim();
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            match key {
+                "REALNAME" => name = value.trim().to_string(),
+                "IMAGE" => image = value.trim().to_string(),
+                "SCRIPT" => in_script = true,
+                _ => {}
+            }
+        }
+
+        if name.is_empty() {
+            return None;
+        }
+
+        Some(Self { name, image, script, mod_time: 0 })
+    }
+
+    /// Write the weapon in the GRAWP001 file format
+    pub fn to_text(&self) -> String {
+        let mut script = self.script.replace("\r\n", "\n");
+        if !script.is_empty() && !script.ends_with('\n') {
+            script.push('\n');
+        }
+        format!("{}\nREALNAME {}\nIMAGE {}\nSCRIPT\n{}SCRIPTEND\n", WEAPON_HEADER, self.name, self.image, script)
+    }
+
+    /// Get the name of the weapon's file (`weapon{name}.txt`)
+    ///
+    /// # C++ Equivalence
+    /// Like `TWeapon::saveWeapon`, `\` and `/` become `_`, `*` becomes `@`,
+    /// `:` becomes `;` and `?` becomes `!`, so weapons keep the files the C++
+    /// server wrote. Control characters also become `_`.
+    pub fn file_name(name: &str) -> String {
+        let name: String = name.chars()
+            .map(|c| match c {
+                '\\' | '/' => '_',
+                '*' => '@',
+                ':' => ';',
+                '?' => '!',
+                c if c.is_control() => '_',
+                c => c,
+            })
+            .collect();
+        format!("weapon{}.txt", name)
+    }
+
+    /// Get the clientside part of the script (after `//#CLIENTSIDE`)
+    pub fn clientside_script(&self) -> &str {
+        match self.script.find("//#CLIENTSIDE") {
+            Some(pos) => &self.script[pos..],
+            None => "",
+        }
+    }
+
+    /// Get the serverside part of the script (before `//#CLIENTSIDE`)
+    pub fn serverside_script(&self) -> &str {
+        match self.script.find("//#CLIENTSIDE") {
+            Some(pos) => &self.script[..pos],
+            None => &self.script,
+        }
+    }
+
+    /// Get the classes the script joins
+    ///
+    /// Only `join("name")` calls with a literal name are found, in the order
+    /// they appear, each once.
+    pub fn joined_classes(&self) -> Vec<String> {
+        let mut classes: Vec<String> = Vec::new();
+        let mut rest = self.script.as_str();
+        while let Some(pos) = rest.find("join") {
+            let preceded_by_word = rest[..pos].chars().next_back().is_some_and(|c| c.is_alphanumeric() || c == '_');
+            rest = &rest[pos + 4..];
+            if preceded_by_word {
+                continue;
+            }
+            let Some(args) = rest.trim_start().strip_prefix('(') else {
+                continue;
+            };
+            let Some(args) = args.trim_start().strip_prefix('"') else {
+                continue;
+            };
+            let Some(end) = args.find('"') else {
+                continue;
+            };
+            let name = &args[..end];
+            if !name.is_empty() && args[end + 1..].trim_start().starts_with(')')
+                && !classes.iter().any(|c| c.eq_ignore_ascii_case(name))
+            {
+                classes.push(name.to_string());
+            }
+        }
+        classes
+    }
+}
+
+/// A weapon's clientside code as sent to players
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeaponBuild {
+    /// Weapon name
+    pub name: String,
+
+    /// Weapon icon image
+    pub image: String,
+
+    /// Clientside script followed by the clientside parts of joined classes
+    pub script: String,
+
+    /// Joined classes that were found, in join order
+    pub classes: Vec<String>,
+
+    /// CRC32 of the script; clients keep the weapon until it changes
+    pub checksum: u32,
+}
+
+impl WeaponBuild {
+    /// Build a weapon with the current version of its classes
+    ///
+    /// Classes that don't exist are skipped.
+    pub fn new(weapon: &Weapon, classes: &ClassManager) -> Self {
+        let mut script = weapon.clientside_script().to_string();
+        let mut found = Vec::new();
+        for name in weapon.joined_classes() {
+            let Some(class) = classes.get_class(&name) else {
+                tracing::debug!("Weapon {} joins missing class {}", weapon.name, name);
+                continue;
+            };
+            let clientside = class.clientside_script();
+            if !script.is_empty() && !script.ends_with('\n') {
+                script.push('\n');
+            }
+            // The class's own //#CLIENTSIDE marker is dropped
+            script.push_str(clientside.strip_prefix("//#CLIENTSIDE").unwrap_or(clientside).trim_start_matches(['\r', '\n']));
+            found.push(class.name.clone());
+        }
+
+        let mut crc = flate2::Crc::new();
+        crc.update(script.as_bytes());
+        Self {
+            name: weapon.name.clone(),
+            image: weapon.image.clone(),
+            script,
+            classes: found,
+            checksum: crc.sum(),
+        }
+    }
+}
+
+/// Weapon Manager
+///
+/// # Purpose
+/// Tracks all weapons known to the server and provides lookup by name.
+///
+/// # Thread Safety
+/// All operations are thread-safe using DashMap for concurrent access.
+pub struct WeaponManager {
+    /// All weapons
+    /// Key: weapon name, Value: weapon definition
+    weapons: dashmap::DashMap<String, Arc<Weapon>>,
+
+    /// Weapons joining each class
+    /// Key: lowercase class name, Value: weapon names
+    dependents: dashmap::DashMap<String, BTreeSet<String>>,
+
+    /// Builds sent to players, dropped when the weapon or a class changes
+    builds: dashmap::DashMap<String, Arc<WeaponBuild>>,
+
+    /// Directory containing weapon files
+    weapons_dir: PathBuf,
+}
+
+impl WeaponManager {
+    /// Create an empty weapon manager
+    ///
+    /// # Arguments
+    /// * `weapons_dir` - Directory containing `weapon-*.txt` files
+    pub fn new<P: Into<PathBuf>>(weapons_dir: P) -> Self {
+        Self {
+            weapons: dashmap::DashMap::new(),
+            dependents: dashmap::DashMap::new(),
+            builds: dashmap::DashMap::new(),
+            weapons_dir: weapons_dir.into(),
+        }
+    }
+
+    /// Load all weapons from the weapons directory
+    ///
+    /// # Returns
+    /// The number of weapons loaded. A missing directory loads nothing.
+    pub fn load_all(&self) -> usize {
+        let entries = match fs::read_dir(&self.weapons_dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                tracing::warn!("Failed to read weapons directory {:?}: {}", self.weapons_dir, e);
+                return 0;
+            }
+        };
+
+        let mut loaded = 0;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_weapon_file = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with("weapon") && n.ends_with(".txt"))
+                .unwrap_or(false);
+
+            if is_weapon_file && self.load_file(&path).is_some() {
+                loaded += 1;
+            }
+        }
+
+        tracing::info!("Loaded {} weapons from {:?}", loaded, self.weapons_dir);
+        loaded
+    }
+
+    /// Load a single weapon file
+    pub fn load_file(&self, path: &Path) -> Option<Arc<Weapon>> {
+        let content = fs::read_to_string(path).ok()?;
+        let Some(mut weapon) = Weapon::parse(&content) else {
+            tracing::warn!("Invalid weapon file: {:?}", path);
+            return None;
+        };
+
+        weapon.mod_time = file_mod_time(path);
+
+        let weapon = Arc::new(weapon);
+        self.insert(weapon.clone());
+        Some(weapon)
+    }
+
+    /// Add or replace a weapon
+    pub fn add_weapon(&self, weapon: Weapon) {
+        self.insert(Arc::new(weapon));
+    }
+
+    /// Add or replace a weapon and save it to its file
+    ///
+    /// # Returns
+    /// The new weapon. It is kept even if saving fails.
+    pub fn save_weapon(&self, mut weapon: Weapon) -> io::Result<Arc<Weapon>> {
+        let path = self.weapons_dir.join(Weapon::file_name(&weapon.name));
+        let saved = fs::create_dir_all(&self.weapons_dir).and_then(|_| fs::write(&path, weapon.to_text()));
+        weapon.mod_time = if saved.is_ok() { file_mod_time(&path) } else { 0 };
+
+        let weapon = Arc::new(weapon);
+        self.insert(weapon.clone());
+        saved.map(|_| weapon)
+    }
+
+    /// Remove a weapon by name
+    pub fn remove_weapon(&self, name: &str) -> Option<Arc<Weapon>> {
+        let removed = self.weapons.remove(name).map(|(_, w)| w)?;
+        self.untrack(name);
+        Some(removed)
+    }
+
+    /// Get the build of a weapon that is sent to players
+    ///
+    /// Builds are kept until the weapon is replaced or
+    /// [`invalidate_class`](Self::invalidate_class) drops them.
+    pub fn build(&self, name: &str, classes: &ClassManager) -> Option<Arc<WeaponBuild>> {
+        if let Some(build) = self.builds.get(name) {
+            return Some(build.clone());
+        }
+        let weapon = self.get_weapon(name)?;
+        let build = Arc::new(WeaponBuild::new(&weapon, classes));
+        self.builds.insert(name.to_string(), build.clone());
+        Some(build)
+    }
+
+    /// Get the build of a weapon if one was made since it last changed
+    pub fn cached_build(&self, name: &str) -> Option<Arc<WeaponBuild>> {
+        self.builds.get(name).map(|b| b.clone())
+    }
+
+    /// Get the weapons that join a class
+    pub fn dependents(&self, class: &str) -> Vec<String> {
+        self.dependents.get(&class.to_lowercase())
+            .map(|weapons| weapons.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Drop the builds of every weapon that joins a class
+    ///
+    /// # Returns
+    /// The weapons that have to be rebuilt and sent again
+    pub fn invalidate_class(&self, class: &str) -> Vec<String> {
+        let weapons = self.dependents(class);
+        for name in &weapons {
+            self.builds.remove(name);
+        }
+        weapons
+    }
+
+    /// Store a weapon and record which classes it joins
+    fn insert(&self, weapon: Arc<Weapon>) {
+        self.untrack(&weapon.name);
+        for class in weapon.joined_classes() {
+            self.dependents.entry(class.to_lowercase()).or_default().insert(weapon.name.clone());
+        }
+        self.weapons.insert(weapon.name.clone(), weapon);
+    }
+
+    /// Forget a weapon's build and class dependencies
+    fn untrack(&self, name: &str) {
+        self.builds.remove(name);
+        self.dependents.retain(|_, weapons| {
+            weapons.remove(name);
+            !weapons.is_empty()
+        });
+    }
+
+    /// Get a weapon by name
+    pub fn get_weapon(&self, name: &str) -> Option<Arc<Weapon>> {
+        self.weapons.get(name).map(|w| w.clone())
+    }
+
+    /// Get the names of all weapons
+    pub fn weapon_names(&self) -> Vec<String> {
+        self.weapons.iter().map(|w| w.key().clone()).collect()
+    }
+
+    /// Get the number of weapons
+    pub fn weapon_count(&self) -> usize {
+        self.weapons.len()
+    }
+
+    /// Get the weapons directory
+    pub fn weapons_dir(&self) -> &Path {
+        &self.weapons_dir
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEAPON: &str = "GRAWP001\nREALNAME -gr_movement\nIMAGE wbomb1.png\nSCRIPT\nfunction onCreated() {}\n//#CLIENTSIDE\nif (timeout) {}\nSCRIPTEND\n";
+
+    #[test]
+    fn test_parse_weapon() {
+        let weapon = Weapon::parse(WEAPON).unwrap();
+        assert_eq!(weapon.name, "-gr_movement");
+        assert_eq!(weapon.image, "wbomb1.png");
+        assert_eq!(weapon.serverside_script(), "function onCreated() {}\n");
+        assert_eq!(weapon.clientside_script(), "//#CLIENTSIDE\nif (timeout) {}\n");
+        assert!(Weapon::parse("GRAWP002\nREALNAME x\n").is_none());
+        assert_eq!(Weapon::file_name("-Shop/Buy*"), "weapon-Shop_Buy@.txt");
+        assert_eq!(Weapon::file_name("a:b?c"), "weapona;b!c.txt");
+    }
+
+    #[test]
+    fn test_load_all() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("weapon-gr_movement.txt"), WEAPON).unwrap();
+        fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let manager = WeaponManager::new(dir.path());
+        assert_eq!(manager.load_all(), 1);
+        let weapon = manager.get_weapon("-gr_movement").unwrap();
+        assert_eq!(weapon.to_text(), WEAPON);
+        assert!(manager.remove_weapon("-gr_movement").is_some());
+        assert_eq!(manager.weapon_count(), 0);
+
+        manager.save_weapon(Weapon::new("shop/*buy", "", "x = 1;")).unwrap();
+        let saved = fs::read_to_string(dir.path().join("weaponshop_@buy.txt")).unwrap();
+        assert_eq!(Weapon::parse(&saved).unwrap().script, "x = 1;\n");
+    }
+
+    #[test]
+    fn test_joined_classes() {
+        let weapon = Weapon::new("-shop", "", "//#CLIENTSIDE\njoin(\"shop\");\nthis.join( \"GUI\" );\nrejoin(\"no\");\njoin(\"shop\");\njoin(name);\n");
+        assert_eq!(weapon.joined_classes(), ["shop", "GUI"]);
+    }
+
+    #[test]
+    fn test_class_dependencies_and_builds() {
+        let dir = tempfile::tempdir().unwrap();
+        let classes = ClassManager::new(dir.path());
+        classes.set_class("gui", "//#CLIENTSIDE\nfunction onShow() {}\n").unwrap();
+
+        let manager = WeaponManager::new(dir.path());
+        manager.add_weapon(Weapon::new("-shop", "", "//#CLIENTSIDE\njoin(\"gui\");\n"));
+        manager.add_weapon(Weapon::new("-bank", "", "//#CLIENTSIDE\njoin(\"GUI\");\n"));
+        manager.add_weapon(Weapon::new("-plain", "", "//#CLIENTSIDE\n"));
+        assert_eq!(manager.dependents("gui"), ["-bank", "-shop"]);
+
+        let first = manager.build("-shop", &classes).unwrap();
+        assert_eq!(first.script, "//#CLIENTSIDE\njoin(\"gui\");\nfunction onShow() {}\n");
+        assert_eq!(first.classes, ["gui"]);
+
+        classes.set_class("gui", "//#CLIENTSIDE\nfunction onShow() { show(); }\n").unwrap();
+        assert_eq!(manager.build("-shop", &classes).unwrap().checksum, first.checksum);
+        assert_eq!(manager.invalidate_class("gui"), ["-bank", "-shop"]);
+        assert_ne!(manager.build("-shop", &classes).unwrap().checksum, first.checksum);
+
+        manager.remove_weapon("-bank");
+        manager.add_weapon(Weapon::new("-shop", "", "//#CLIENTSIDE\n"));
+        assert!(manager.dependents("gui").is_empty());
+    }
+}
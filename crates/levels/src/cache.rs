@@ -6,6 +6,7 @@ use crate::level::{Level, LevelId};
 use crate::parser::LevelLoader;
 use crate::Result;
 use dashmap::DashMap;
+use gserver_core::resolve_ignore_case;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -55,7 +56,7 @@ struct CacheEntry {
 
 /// Level cache with LRU eviction
 pub struct LevelCache {
-    /// Cached levels indexed by level name
+    /// Cached levels indexed by lowercase level name
     cache: DashMap<String, CacheEntry>,
 
     /// Path to levels directory
@@ -89,7 +90,7 @@ impl LevelCache {
     /// This is the primary method for accessing levels.
     pub async fn get(&self, level_name: &str) -> Result<Arc<Level>> {
         // Check cache first
-        if let Some(entry) = self.cache.get(level_name) {
+        if let Some(entry) = self.cache.get(&level_name.to_lowercase()) {
             // Clone the Arc to return
             // Note: We don't update last_accessed here to avoid locking issues
             return Ok(Arc::clone(&entry.level));
@@ -101,8 +102,9 @@ impl LevelCache {
 
     /// Load a level from disk and cache it
     async fn load_level(&self, level_name: &str) -> Result<Arc<Level>> {
-        // Build path to level file
-        let level_path = self.levels_dir.join(level_name);
+        // Build path to level file (names from Windows-made content may differ in case)
+        let level_path = resolve_ignore_case(&self.levels_dir, level_name)
+            .unwrap_or_else(|| self.levels_dir.join(level_name));
 
         // Load the level
         let level = LevelLoader::load_file(&level_path)?;
@@ -126,7 +128,7 @@ impl LevelCache {
 
         // Add to cache
         let level_arc = Arc::clone(&entry.level);
-        self.cache.insert(level_name.to_string(), entry);
+        self.cache.insert(level_name.to_lowercase(), entry);
         self.memory_usage.fetch_add(size_bytes, std::sync::atomic::Ordering::Relaxed);
 
         Ok(level_arc)
@@ -141,13 +143,64 @@ impl LevelCache {
         self.load_level(level_name).await
     }
 
+    /// Reparse the cached levels whose file changed on disk
+    ///
+    /// # Returns
+    /// `(old, new)` per reloaded level (see [`Self::reload_from_disk`])
+    pub fn reload_changed(&self) -> Vec<(Arc<Level>, Arc<Level>)> {
+        let changed: Vec<String> = self.cache.iter()
+            .filter(|entry| file_mod_time(&entry.level.file_path).is_some_and(|t| t != entry.level.mod_time))
+            .map(|entry| entry.key().clone())
+            .collect();
+        changed.iter().filter_map(|name| self.reload_from_disk(name)).collect()
+    }
+
+    /// Reparse a cached level from its file, in place
+    ///
+    /// The new level keeps the players, NPCs and state of the cached one;
+    /// what comes from the file (board, links, signs, chests, baddies, flags)
+    /// is replaced, board changes not saved yet included.
+    ///
+    /// # Returns
+    /// `(old, new)`, or `None` if the level isn't cached or fails to parse
+    pub fn reload_from_disk(&self, level_name: &str) -> Option<(Arc<Level>, Arc<Level>)> {
+        let key = level_name.to_lowercase();
+        let old = Arc::clone(&self.cache.get(&key)?.level);
+        let mut level = match LevelLoader::load_file(&old.file_path) {
+            Ok(level) => level,
+            Err(e) => {
+                tracing::warn!("Failed to reload level '{}': {}", old.name, e);
+                return None;
+            }
+        };
+        level.state = Arc::clone(&old.state);
+        level.players = Arc::clone(&old.players);
+        level.npcs = Arc::clone(&old.npcs);
+
+        let size_bytes = Self::estimate_size(&level);
+        let new = Arc::new(level);
+        if let Some(mut entry) = self.cache.get_mut(&key) {
+            self.memory_usage.fetch_sub(entry.size_bytes, std::sync::atomic::Ordering::Relaxed);
+            self.memory_usage.fetch_add(size_bytes, std::sync::atomic::Ordering::Relaxed);
+            entry.level = Arc::clone(&new);
+            entry.loaded_at = Self::current_time();
+            entry.size_bytes = size_bytes;
+        }
+        Some((old, new))
+    }
+
     /// Remove a level from cache
     pub fn remove(&self, level_name: &str) {
-        if let Some((_, entry)) = self.cache.remove(level_name) {
+        if let Some((_, entry)) = self.cache.remove(&level_name.to_lowercase()) {
             self.memory_usage.fetch_sub(entry.size_bytes, std::sync::atomic::Ordering::Relaxed);
         }
     }
 
+    /// Get all levels currently in the cache
+    pub fn loaded_levels(&self) -> Vec<Arc<Level>> {
+        self.cache.iter().map(|entry| Arc::clone(&entry.level)).collect()
+    }
+
     /// Clear all cached levels
     pub fn clear(&self) {
         self.cache.clear();
@@ -267,6 +320,12 @@ impl LevelCache {
     }
 }
 
+/// Get a file's modification time, as levels record it
+fn file_mod_time(path: &Path) -> Option<u32> {
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_secs() as u32)
+}
+
 /// Cache statistics
 #[derive(Debug, Clone)]
 pub struct CacheStats {
@@ -308,6 +367,10 @@ BOARD 0 0 2 0 AAAA
 
         assert_eq!(level.name, "test.nw");
         assert!(level.is_loaded());
+
+        // Level names are matched ignoring case, as on Windows
+        std::fs::write(levels_dir.join("Cave.NW"), level_data).unwrap();
+        assert_eq!(cache.get("cave.nw").await.unwrap().name, "Cave.NW");
     }
 
     #[tokio::test]
@@ -384,6 +447,7 @@ BOARD 0 0 2 0 AAAA
 
         // Load level
         let level1 = cache.get("test.nw").await.unwrap();
+        assert!(Arc::ptr_eq(&level1, &cache.get("Test.NW").await.unwrap()));
 
         // Reload level
         let level2 = cache.reload("test.nw").await.unwrap();
@@ -391,4 +455,29 @@ BOARD 0 0 2 0 AAAA
         assert_eq!(level1.name, level2.name);
         assert_eq!(level1.id, level2.id);
     }
+
+    #[tokio::test]
+    async fn test_reload_changed() {
+        let temp_dir = TempDir::new().unwrap();
+        let level_path = temp_dir.path().join("test.nw");
+        std::fs::write(&level_path, "GLEVNW01\nBOARD 0 0 2 0 AAAA\n").unwrap();
+
+        let cache = LevelCache::new(temp_dir.path(), CacheConfig { auto_cleanup: false, ..Default::default() });
+        let old = cache.get("test.nw").await.unwrap();
+        old.add_npc(7, "door.png".into(), 1.0, 2.0);
+        assert!(cache.reload_changed().is_empty());
+
+        std::fs::write(&level_path, "GLEVNW01\nBOARD 0 0 2 0 AAAB\nSIGN 1 2\nHi\nSIGNEND\n").unwrap();
+        let file = std::fs::File::options().write(true).open(&level_path).unwrap();
+        file.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(5)).unwrap();
+
+        let reloaded = cache.reload_changed();
+        assert_eq!(reloaded.len(), 1);
+        let (_, new) = &reloaded[0];
+        assert_eq!(new.tiles.read().changed_rect(&old.tiles.read(), 0), Some((1, 0, 1, 1)));
+        assert!(new.objects_differ(&old));
+        assert!(new.get_npc(7).is_some());
+        assert!(Arc::ptr_eq(new, &cache.get("test.nw").await.unwrap()));
+        assert!(cache.reload_changed().is_empty());
+    }
 }
@@ -0,0 +1,318 @@
+//! Level validation
+//!
+//! Checks a .nw level for the mistakes [`LevelLoader`] skips over or stops
+//! at, and reports all of them with their line:
+//!
+//! - **Tiles**: BOARD rows out of bounds, with the wrong length or with
+//!   characters outside the base64 tile alphabet (which load as tile 0)
+//! - **Links**: LINK lines that don't have a level, a rectangle and a
+//!   destination
+//! - **NPCs**: bad NPC headers, scripts without NPCEND, and unbalanced
+//!   brackets or unterminated strings in the script
+//!
+//! Whether link targets exist depends on the other levels, so the targets
+//! are returned for the caller to look up.
+
+use crate::parser::LevelLoader;
+use std::fmt;
+
+/// Kind of problem in a level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelIssueKind {
+    /// Header or a line the level loader rejects
+    Format,
+    /// BOARD data
+    Tiles,
+    /// LINK line
+    Link,
+    /// NPC header or script
+    Npc,
+}
+
+impl fmt::Display for LevelIssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LevelIssueKind::Format => "format",
+            LevelIssueKind::Tiles => "tiles",
+            LevelIssueKind::Link => "link",
+            LevelIssueKind::Npc => "npc",
+        })
+    }
+}
+
+/// A problem found in a level
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LevelIssue {
+    /// Line number (1-based), 0 if the level loader didn't say
+    pub line: usize,
+    /// Column (1-based), 0 if the whole line is meant
+    pub column: usize,
+    /// What part of the level it is in
+    pub kind: LevelIssueKind,
+    /// Description
+    pub message: String,
+}
+
+impl fmt::Display for LevelIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (0, _) => write!(f, "{}: {}", self.kind, self.message),
+            (_, 0) => write!(f, "line {}: {}: {}", self.line, self.kind, self.message),
+            (_, column) => write!(f, "line {}:{}: {}: {}", self.line, column, self.kind, self.message),
+        }
+    }
+}
+
+/// A LINK line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LevelLink {
+    /// Line number (1-based)
+    pub line: usize,
+    /// Target level name
+    pub target: String,
+}
+
+/// Result of checking one level
+#[derive(Debug, Clone, Default)]
+pub struct LevelCheck {
+    /// Header of a binary level (GR-V1.0x, Z3-V1.0x), which isn't checked
+    pub binary: Option<String>,
+    /// Links to other levels
+    pub links: Vec<LevelLink>,
+    /// Problems found
+    pub issues: Vec<LevelIssue>,
+}
+
+impl LevelCheck {
+    fn issue(&mut self, line: usize, column: usize, kind: LevelIssueKind, message: impl Into<String>) {
+        self.issues.push(LevelIssue { line, column, kind, message: message.into() });
+    }
+}
+
+/// Check the text of a .nw level
+///
+/// # Arguments
+/// * `data` - Contents of the level file
+///
+/// # Returns
+/// The links and problems found; an empty `issues` means the level is fine
+/// apart from link targets, which the caller checks.
+pub fn check_level(data: &str) -> LevelCheck {
+    let mut check = LevelCheck::default();
+    let lines: Vec<&str> = data.lines().collect();
+
+    let header = lines.first().map(|l| l.trim()).unwrap_or_default();
+    if header.starts_with("GR-V") || header.starts_with("Z3-V") {
+        check.binary = Some(header.chars().take(8).collect());
+        return check;
+    }
+    if header != "GLEVNW01" {
+        check.issue(1, 0, LevelIssueKind::Format, format!("header is '{}', expected GLEVNW01", header));
+        return check;
+    }
+
+    let mut i = 1;
+    while i < lines.len() {
+        let number = i + 1;
+        let line = lines[i].trim();
+        let keyword = line.split_whitespace().next().unwrap_or_default();
+        match keyword {
+            "BOARD" => check_board(&mut check, number, lines[i]),
+            "LINK" => check_link(&mut check, number, line),
+            "NPC" => i = check_npc(&mut check, &lines, i),
+            "SIGN" => i = skip_block(&lines, i, "SIGNEND"),
+            "BADDY" => i = skip_block(&lines, i, "BADDYEND"),
+            _ => {}
+        }
+        i += 1;
+    }
+
+    // What the loader still refuses; tile problems already make it fail
+    if !check.issues.iter().any(|issue| issue.kind == LevelIssueKind::Tiles) {
+        if let Err(e) = LevelLoader::parse(data, String::new(), Default::default(), 0) {
+            check.issue(0, 0, LevelIssueKind::Format, e.to_string());
+        }
+    }
+    check
+}
+
+/// `BOARD x y width layer data`, two base64 characters per tile
+fn check_board(check: &mut LevelCheck, number: usize, line: &str) {
+    let parts: Vec<&str> = line.split_whitespace().skip(1).collect();
+    let [x, y, width, layer, data] = parts.as_slice() else {
+        check.issue(number, 0, LevelIssueKind::Tiles, format!("BOARD needs x, y, width, layer and data, got {} values", parts.len()));
+        return;
+    };
+    let (Ok(x), Ok(y), Ok(width), Ok(_)) = (x.parse::<usize>(), y.parse::<usize>(), width.parse::<usize>(), layer.parse::<u8>()) else {
+        check.issue(number, 0, LevelIssueKind::Tiles, "BOARD position, width and layer must be numbers");
+        return;
+    };
+    if y >= 64 || width == 0 || x + width > 64 {
+        check.issue(number, 0, LevelIssueKind::Tiles, format!("row x={} y={} width={} is outside the 64x64 board", x, y, width));
+    }
+    if data.len() != width * 2 {
+        check.issue(number, 0, LevelIssueKind::Tiles, format!("{} tiles need {} characters, got {}", width, width * 2, data.len()));
+    }
+    let start = line.rfind(data).unwrap_or_default();
+    if let Some((offset, c)) = data.char_indices().find(|(_, c)| !c.is_ascii_alphanumeric() && *c != '+' && *c != '/') {
+        check.issue(number, start + offset + 1, LevelIssueKind::Tiles, format!("invalid tile character '{}'", c));
+    }
+}
+
+/// `LINK level x y width height newx newy`
+///
+/// # C++ Equivalence
+/// Like `TLevel::loadNW()`, extra words before the last six values are part
+/// of the level name.
+fn check_link(check: &mut LevelCheck, number: usize, line: &str) {
+    let parts: Vec<&str> = line.split_whitespace().skip(1).collect();
+    if parts.len() < 7 {
+        check.issue(number, 0, LevelIssueKind::Link, format!("LINK needs level, x, y, width, height, newx and newy, got {} values", parts.len()));
+        return;
+    }
+    let (name, values) = parts.split_at(parts.len() - 6);
+    if let Some(bad) = values[..4].iter().find(|v| v.parse::<f32>().is_err()) {
+        check.issue(number, 0, LevelIssueKind::Link, format!("link rectangle value '{}' is not a number", bad));
+        return;
+    }
+    check.links.push(LevelLink { line: number, target: name.join(" ") });
+}
+
+/// `NPC image x y`, then the script up to NPCEND
+///
+/// # Returns
+/// Index of the NPCEND line (or the last line)
+fn check_npc(check: &mut LevelCheck, lines: &[&str], start: usize) -> usize {
+    let parts: Vec<&str> = lines[start].split_whitespace().skip(1).collect();
+    let position_ok = parts.len() == 3 && parts[1..].iter().all(|v| v.parse::<f32>().is_ok());
+    if !position_ok {
+        check.issue(start + 1, 0, LevelIssueKind::Npc, "NPC needs image (or -), x and y");
+    }
+
+    let end = lines[start + 1..].iter().position(|l| l.trim() == "NPCEND").map(|p| start + 1 + p);
+    if end.is_none() {
+        check.issue(start + 1, 0, LevelIssueKind::Npc, "script has no NPCEND");
+    }
+    let script_end = end.unwrap_or(lines.len());
+    check_script(check, &lines[start + 1..script_end], start + 2);
+    end.unwrap_or(lines.len() - 1)
+}
+
+/// Look for unbalanced brackets and unterminated strings in an NPC script
+///
+/// # Arguments
+/// * `first_line` - Line number of the first script line
+fn check_script(check: &mut LevelCheck, script: &[&str], first_line: usize) {
+    let mut open: Vec<(char, usize, usize)> = Vec::new();
+    let mut in_comment = false;
+
+    for (index, line) in script.iter().enumerate() {
+        let number = first_line + index;
+        let mut chars = line.chars().enumerate().peekable();
+        while let Some((col, c)) = chars.next() {
+            if in_comment {
+                if c == '*' && chars.peek().is_some_and(|&(_, n)| n == '/') {
+                    chars.next();
+                    in_comment = false;
+                }
+                continue;
+            }
+            match c {
+                '/' if chars.peek().is_some_and(|&(_, n)| n == '/') => break,
+                '/' if chars.peek().is_some_and(|&(_, n)| n == '*') => {
+                    chars.next();
+                    in_comment = true;
+                }
+                '"' | '\'' => {
+                    let mut closed = false;
+                    while let Some((_, s)) = chars.next() {
+                        if s == '\\' {
+                            chars.next();
+                        } else if s == c {
+                            closed = true;
+                            break;
+                        }
+                    }
+                    if !closed {
+                        check.issue(number, col + 1, LevelIssueKind::Npc, "unterminated string");
+                    }
+                }
+                '(' | '[' | '{' => open.push((c, number, col + 1)),
+                ')' | ']' | '}' => {
+                    let expected = match c { ')' => '(', ']' => '[', _ => '{' };
+                    match open.pop() {
+                        Some((opener, _, _)) if opener == expected => {}
+                        Some((opener, line, column)) => check.issue(number, col + 1, LevelIssueKind::Npc,
+                            format!("'{}' closes '{}' from line {}:{}", c, opener, line, column)),
+                        None => check.issue(number, col + 1, LevelIssueKind::Npc, format!("'{}' without a matching '{}'", c, expected)),
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for (opener, line, column) in open {
+        check.issue(line, column, LevelIssueKind::Npc, format!("'{}' is never closed", opener));
+    }
+}
+
+/// Skip a SIGN or BADDY block, whose text isn't level syntax
+fn skip_block(lines: &[&str], start: usize, end: &str) -> usize {
+    lines[start + 1..].iter().position(|l| l.trim() == end).map_or(lines.len() - 1, |p| start + 1 + p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_reports_problems() {
+        let level = [
+            "GLEVNW01",
+            &format!("BOARD 0 0 64 0 {}", "AA".repeat(64)),
+            "BOARD 0 1 2 0 AA!A",
+            "BOARD 60 2 8 0 AAAA",
+            "LINK level2.nw 0 0 1 64 playerx 30",
+            "LINK old world.nw 63 0 1 64 0 playery",
+            "LINK broken.nw 0 0",
+            "NPC - 10 10",
+            "if (playerenters) {",
+            "  message \"hi (\"; // {",
+            "  /* ] */ setimg(\"a.png\";",
+            "}",
+            "NPCEND",
+            "NPC door.png ten 5",
+            "say \"oops",
+        ].join("\r\n");
+
+        let check = check_level(&level);
+        assert_eq!(check.links, [
+            LevelLink { line: 5, target: "level2.nw".into() },
+            LevelLink { line: 6, target: "old world.nw".into() },
+        ]);
+        let issues: Vec<String> = check.issues.iter().map(ToString::to_string).collect();
+        assert_eq!(issues, [
+            "line 3:17: tiles: invalid tile character '!'",
+            "line 4: tiles: row x=60 y=2 width=8 is outside the 64x64 board",
+            "line 4: tiles: 8 tiles need 16 characters, got 4",
+            "line 7: link: LINK needs level, x, y, width, height, newx and newy, got 3 values",
+            "line 12:1: npc: '}' closes '(' from line 11:17",
+            "line 9:19: npc: '{' is never closed",
+            "line 14: npc: NPC needs image (or -), x and y",
+            "line 14: npc: script has no NPCEND",
+            "line 15:5: npc: unterminated string",
+        ]);
+    }
+
+    #[test]
+    fn test_check_clean_and_binary_levels() {
+        let level = "GLEVNW01\nBOARD 0 0 2 0 AB+/\nNPC - 1 2\nif (weapon) { x = \"}\\\"\"; }\nNPCEND\nSIGN 1 1\nNPC (\nSIGNEND\n";
+        let check = check_level(level);
+        assert!(check.issues.is_empty(), "{:?}", check.issues);
+        assert!(check.links.is_empty());
+
+        assert_eq!(check_level("GR-V1.03\u{0}\u{1}").binary.as_deref(), Some("GR-V1.03"));
+        assert_eq!(check_level("").issues[0].to_string(), "line 1: format: header is '', expected GLEVNW01");
+    }
+}
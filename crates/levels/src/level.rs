@@ -6,6 +6,7 @@ use crate::tiles::LevelTiles;
 use crate::Result;
 use gserver_core::PlayerID;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use parking_lot::RwLock;
 
@@ -71,6 +72,46 @@ pub struct Level {
 
     /// Height overrides for terrain generation
     pub height_overrides: Option<Vec<f64>>,
+
+    /// Tiles changed since the level was loaded or last saved
+    pub modified: Arc<AtomicBool>,
+
+    /// Level-wide rules from the level's NPC scripts
+    pub flags: LevelFlags,
+}
+
+/// Level-wide rules, set by statements in the level's NPC scripts
+///
+/// ```text
+/// sparringzone;       // sparring: deaths drop nothing
+/// noplayerkilling;    // no_player_killing: players can't hurt each other
+/// nohome;             // no_home: players can't set their home here
+/// noexplosions;       // no_explosions: bombs don't explode
+/// ```
+/// The server reads them when the level loads, wherever they are in the
+/// script (`if (created) { sparringzone; }` counts).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LevelFlags {
+    pub sparring: bool,
+    pub no_player_killing: bool,
+    pub no_home: bool,
+    pub no_explosions: bool,
+}
+
+impl LevelFlags {
+    /// Set the flags named by the statements of one script line
+    pub fn scan_line(&mut self, line: &str) {
+        let code = line.split("//").next().unwrap_or_default();
+        for statement in code.split([';', '{', '}']) {
+            match statement.trim().to_ascii_lowercase().as_str() {
+                "sparringzone" => self.sparring = true,
+                "noplayerkilling" => self.no_player_killing = true,
+                "nohome" => self.no_home = true,
+                "noexplosions" => self.no_explosions = true,
+                _ => {}
+            }
+        }
+    }
 }
 
 /// Reference to an NPC
@@ -87,10 +128,16 @@ pub struct NPCRef {
 
     /// Y position (pixels)
     pub y: f32,
+
+    /// Players can lift it (`canbecarried`)
+    pub carryable: bool,
+
+    /// Blocking shape (pixels) from `setshape 1, width, height`
+    pub shape: Option<(u16, u16)>,
 }
 
 /// A chest that contains items
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Chest {
     /// X position (tiles)
     pub x: u8,
@@ -106,7 +153,7 @@ pub struct Chest {
 }
 
 /// A link to another level
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Link {
     /// X position (tiles)
     pub x: u8,
@@ -131,7 +178,7 @@ pub struct Link {
 }
 
 /// A sign with text
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Sign {
     /// X position (tiles)
     pub x: u8,
@@ -144,7 +191,7 @@ pub struct Sign {
 }
 
 /// An enemy (baddy)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Baddy {
     /// X position (pixels)
     pub x: f32,
@@ -187,6 +234,8 @@ impl Level {
             baddies: Vec::new(),
             map_position: None,
             height_overrides: None,
+            modified: Arc::new(AtomicBool::new(false)),
+            flags: LevelFlags::default(),
         }
     }
 
@@ -239,6 +288,8 @@ impl Level {
             baddies: Vec::new(),
             map_position: None,
             height_overrides: None,
+            modified: Arc::new(AtomicBool::new(false)),
+            flags: LevelFlags::default(),
         }
     }
 
@@ -284,7 +335,48 @@ impl Level {
     /// Add an NPC to this level
     pub fn add_npc(&self, id: u32, image: String, x: f32, y: f32) {
         let mut npcs = self.npcs.write();
-        npcs.push(NPCRef { id, image, x, y });
+        npcs.push(NPCRef { id, image, x, y, carryable: false, shape: None });
+    }
+
+    /// Check if the links, signs, chests or baddies differ from another version of the level
+    pub fn objects_differ(&self, other: &Level) -> bool {
+        self.links != other.links || self.signs != other.signs
+            || self.chests != other.chests || self.baddies != other.baddies
+    }
+
+    /// Get an NPC of this level
+    pub fn get_npc(&self, id: u32) -> Option<NPCRef> {
+        self.npcs.read().iter().find(|npc| npc.id == id).cloned()
+    }
+
+    /// Set whether players can lift an NPC (`canbecarried` / `cannotbecarried`)
+    ///
+    /// # Returns
+    /// `false` if the NPC isn't on this level
+    pub fn set_npc_carryable(&self, id: u32, carryable: bool) -> bool {
+        self.npcs.write().iter_mut()
+            .find(|npc| npc.id == id)
+            .map(|npc| npc.carryable = carryable)
+            .is_some()
+    }
+
+    /// Set or clear an NPC's blocking shape (`setshape`)
+    ///
+    /// # Returns
+    /// `false` if the NPC isn't on this level
+    pub fn set_npc_shape(&self, id: u32, shape: Option<(u16, u16)>) -> bool {
+        self.npcs.write().iter_mut()
+            .find(|npc| npc.id == id)
+            .map(|npc| npc.shape = shape)
+            .is_some()
+    }
+
+    /// Move an NPC (pixels)
+    pub fn move_npc(&self, id: u32, x: f32, y: f32) {
+        if let Some(npc) = self.npcs.write().iter_mut().find(|npc| npc.id == id) {
+            npc.x = x;
+            npc.y = y;
+        }
     }
 
     /// Remove an NPC from this level
@@ -299,8 +391,21 @@ impl Level {
     }
 
     /// Set tile at position in layer
+    ///
+    /// Marks the level as modified so it is picked up by the next save.
     pub fn set_tile(&self, x: u8, y: u8, layer: u8, tile: u16) {
         self.tiles.write().set_tile(x, y, layer, tile);
+        self.modified.store(true, Ordering::Relaxed);
+    }
+
+    /// Check if tiles changed since the level was loaded or last saved
+    pub fn is_modified(&self) -> bool {
+        self.modified.load(Ordering::Relaxed)
+    }
+
+    /// Mark the level as saved
+    pub fn clear_modified(&self) {
+        self.modified.store(false, Ordering::Relaxed);
     }
 
     /// Check if level is on a map
@@ -405,4 +510,22 @@ mod tests {
         level.set_state(LevelState::Loaded);
         assert!(level.is_loaded());
     }
+
+    #[test]
+    fn test_level_npc_state() {
+        let level = Level::new(1, "testlevel.nw".to_string());
+        level.add_npc(7, "block.png".to_string(), 32.0, 48.0);
+        assert!(!level.get_npc(7).unwrap().carryable);
+
+        assert!(level.set_npc_carryable(7, true));
+        assert!(!level.set_npc_carryable(8, true));
+        level.move_npc(7, 96.0, 48.0);
+
+        assert!(level.set_npc_shape(7, Some((32, 16))));
+
+        let npc = level.get_npc(7).unwrap();
+        assert!(npc.carryable);
+        assert_eq!((npc.x, npc.y), (96.0, 48.0));
+        assert_eq!(npc.shape, Some((32, 16)));
+    }
 }
@@ -5,10 +5,11 @@
 //! ## Features
 //! - .nw level file format parser (GLEVNW01)
 //! - Multi-layer tile system (base + additional layers)
-//! - Board changes and modifications
+//! - Board changes and modifications (saved back into .nw files)
 //! - Level caching and lazy loading
 //! - Spatial indexing for queries
 //! - gmap/bigmap support
+//! - Level validation (tiles, links, NPC scripts)
 //!
 //! ## Level Format
 //!
@@ -22,14 +23,18 @@ pub mod error;
 pub mod level;
 pub mod tiles;
 pub mod parser;
+pub mod writer;
 pub mod cache;
 pub mod map;
 pub mod manager;
+pub mod check;
 
 pub use error::{LevelError, Result};
-pub use level::{Level, LevelId, MapPosition};
-pub use tiles::{Tile, TileLayer, LevelTiles, MAX_TILE_COUNT};
+pub use level::{Level, LevelFlags, LevelId, MapPosition};
+pub use tiles::{Tile, TileLayer, LevelTiles, TileTypes, MAX_TILE_COUNT};
 pub use parser::LevelLoader;
+pub use writer::LevelWriter;
 pub use cache::LevelCache;
 pub use map::{Map, MapType};
 pub use manager::{LevelManager, SimpleLevelProvider};
+pub use check::{check_level, LevelCheck, LevelIssue, LevelIssueKind, LevelLink};
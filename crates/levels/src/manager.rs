@@ -4,6 +4,7 @@
 
 use crate::cache::LevelCache;
 use crate::level::Level;
+use crate::writer::LevelWriter;
 use crate::Result;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -79,6 +80,64 @@ impl LevelManager {
         self.cache.stats()
     }
 
+    /// Save every cached level whose tiles were modified
+    ///
+    /// # Returns
+    /// The number of levels saved. Levels that fail to save stay marked as
+    /// modified and are retried on the next call.
+    pub fn save_modified_levels(&self) -> usize {
+        let mut saved = 0;
+
+        for level in self.cache.loaded_levels() {
+            if !level.is_modified() {
+                continue;
+            }
+
+            // Clear first so changes made during the write are not lost
+            level.clear_modified();
+            match LevelWriter::save_board(&level) {
+                Ok(()) => saved += 1,
+                Err(e) => {
+                    level.modified.store(true, std::sync::atomic::Ordering::Relaxed);
+                    tracing::warn!("Failed to save level '{}': {}", level.name, e);
+                }
+            }
+        }
+
+        saved
+    }
+
+    /// Find the loaded level an NPC is on
+    pub fn find_npc(&self, id: u32) -> Option<Arc<Level>> {
+        self.cache.loaded_levels().into_iter().find(|level| level.get_npc(id).is_some())
+    }
+
+    /// Reparse the loaded levels whose file changed on disk
+    ///
+    /// # Returns
+    /// `(old, new)` per reloaded level
+    pub fn reload_changed(&self) -> Vec<(Arc<Level>, Arc<Level>)> {
+        self.cache.reload_changed()
+    }
+
+    /// Reparse a loaded level from its file, changed or not
+    ///
+    /// # Returns
+    /// `(old, new)`, or `None` if the level isn't loaded or fails to parse
+    pub fn reload_level(&self, name: &str) -> Option<(Arc<Level>, Arc<Level>)> {
+        self.cache.reload_from_disk(name)
+    }
+
+    /// Get the loaded levels
+    pub fn loaded_levels(&self) -> Vec<Arc<Level>> {
+        self.cache.loaded_levels()
+    }
+
+    /// Get a level if it's loaded, without loading it
+    pub fn loaded_level(&self, name: &str) -> Option<Arc<Level>> {
+        self.cache.loaded_levels().into_iter().find(|level| level.name.eq_ignore_ascii_case(name))
+    }
+
     /// Clear the level cache
     pub fn clear_cache(&self) {
         self.cache.clear();
@@ -146,7 +205,7 @@ mod tests {
     #[tokio::test]
     async fn test_level_manager_default_level() {
         // Create a manager with an empty directory
-        let temp_dir = tempfile::TempDir::unwrap();
+        let temp_dir = tempfile::TempDir::new().unwrap();
         let manager = LevelManager::new(temp_dir.path());
 
         // Request a level that doesn't exist
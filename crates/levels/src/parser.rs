@@ -89,8 +89,9 @@ impl LevelLoader {
                 i += skip;
             } else if let Some(_rest) = line.strip_prefix("NPC") {
                 // NPCs are handled separately in the full implementation
-                // For now, skip to NPCEND
+                // For now, skip to NPCEND, picking up the level flags on the way
                 while i < lines.len() && lines[i].trim() != "NPCEND" {
+                    level.flags.scan_line(lines[i]);
                     i += 1;
                 }
             }
@@ -430,6 +431,13 @@ SIGNEND
         assert!(!level.signs.is_empty());
     }
 
+    #[test]
+    fn test_parse_level_flags() {
+        let data = "GLEVNW01\nNPC - 30 30\nif (created) { sparringzone; NoHome; }\n// noexplosions;\nNPCEND\nSIGN 1 1\nnoplayerkilling;\nSIGNEND\n";
+        let level = LevelLoader::parse(data, "arena.nw".to_string(), std::path::PathBuf::from("arena.nw"), 0).unwrap();
+        assert_eq!(level.flags, crate::LevelFlags { sparring: true, no_home: true, ..Default::default() });
+    }
+
     #[test]
     fn test_parse_board_tiles() {
         let data = r#"GLEVNW01
@@ -4,6 +4,7 @@
 //! Each tile is represented by a 16-bit index.
 
 use crate::error::{LevelError, Result};
+use std::path::Path;
 
 /// Maximum number of tiles per level (64x64)
 pub const MAX_TILE_COUNT: usize = 64 * 64;
@@ -17,6 +18,15 @@ pub const BASE_LAYER: u8 = 0;
 /// Empty tile value (no tile)
 pub const EMPTY_TILE: u16 = 0xFFFF;
 
+/// Tile type of walls
+pub const TILE_TYPE_BLOCKING: u8 = 22;
+
+/// Tile type of bushes and rocks that can be thrown over but not walked through
+pub const TILE_TYPE_THROW_THROUGH: u8 = 20;
+
+/// Tile size in pixels
+const TILE_PIXELS: f32 = 16.0;
+
 /// A single tile with its index and optional attributes
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Tile {
@@ -177,12 +187,44 @@ impl LevelTiles {
         }
     }
 
+    /// Get the rectangle of a layer's tiles that differ from another board
+    ///
+    /// # Returns
+    /// `(x, y, width, height)` in tiles, or `None` if the layers are the same
+    pub fn changed_rect(&self, other: &LevelTiles, layer_id: u8) -> Option<(u8, u8, u8, u8)> {
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (u8::MAX, u8::MAX, 0, 0);
+        for y in 0..64 {
+            for x in 0..64 {
+                if self.get_tile(x, y, layer_id) != other.get_tile(x, y, layer_id) {
+                    (min_x, min_y) = (min_x.min(x), min_y.min(y));
+                    (max_x, max_y) = (max_x.max(x), max_y.max(y));
+                }
+            }
+        }
+        (min_x <= max_x).then(|| (min_x, min_y, max_x - min_x + 1, max_y - min_y + 1))
+    }
+
     /// Check if a layer exists
     #[inline]
     pub fn has_layer(&self, layer_id: u8) -> bool {
         self.get_layer(layer_id).is_some()
     }
 
+    /// Check if a box (pixels) is on a blocking base layer tile or off the board
+    ///
+    /// # Arguments
+    /// * `types` - Tile types of the tileset
+    /// * `x`, `y` - Top-left corner
+    /// * `width`, `height` - Size of the box
+    pub fn is_blocked(&self, types: &TileTypes, x: f32, y: f32, width: f32, height: f32) -> bool {
+        let board = 64.0 * TILE_PIXELS;
+        if x < 0.0 || y < 0.0 || x + width > board || y + height > board {
+            return true;
+        }
+        let tiles = |from: f32, size: f32| (from / TILE_PIXELS) as u8..=(((from + size) / TILE_PIXELS).ceil() as u8).saturating_sub(1);
+        tiles(y, height).any(|ty| tiles(x, width).any(|tx| types.blocks(self.get_tile(tx, ty, BASE_LAYER))))
+    }
+
     /// Get all layer IDs that exist
     pub fn layer_ids(&self) -> impl Iterator<Item = u8> + '_ {
         self.layers
@@ -270,6 +312,42 @@ impl Default for LevelTiles {
     }
 }
 
+/// Tile types of the tileset, one byte per tile index (`tiletypes.dat`)
+///
+/// Tiles past the end of the table are walkable.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TileTypes {
+    types: Vec<u8>,
+}
+
+impl TileTypes {
+    /// Create a table from its bytes
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self { types: bytes.to_vec() }
+    }
+
+    /// Load a table, or an empty one if the file can't be read
+    pub fn load(path: &Path) -> Self {
+        match std::fs::read(path) {
+            Ok(bytes) => Self::from_bytes(&bytes),
+            Err(e) => {
+                tracing::debug!("No tile types from {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Get the type of a tile
+    pub fn tile_type(&self, tile: u16) -> u8 {
+        self.types.get(tile as usize).copied().unwrap_or(0)
+    }
+
+    /// Check if a tile can't be walked through
+    pub fn blocks(&self, tile: u16) -> bool {
+        matches!(self.tile_type(tile), TILE_TYPE_BLOCKING | TILE_TYPE_THROW_THROUGH)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -328,4 +406,20 @@ mod tests {
         layer.set(32, 32, 3);
         assert_eq!(layer.get(32, 32), 3);
     }
+
+    #[test]
+    fn test_blocking_tiles() {
+        let mut table = vec![0; 8];
+        table[5] = TILE_TYPE_BLOCKING;
+        let types = TileTypes::from_bytes(&table);
+        assert!(types.blocks(5) && !types.blocks(4) && !types.blocks(4000));
+
+        let mut tiles = LevelTiles::with_base_fill(0);
+        tiles.set_tile(10, 10, BASE_LAYER, 5);
+        assert!(tiles.is_blocked(&types, 150.0, 150.0, 16.0, 16.0));
+        assert!(!tiles.is_blocked(&types, 176.0, 160.0, 32.0, 32.0));
+        assert!(!tiles.is_blocked(&types, 128.0, 144.0, 32.0, 16.0));
+        assert!(tiles.is_blocked(&types, 1010.0, 0.0, 16.0, 16.0));
+        assert!(tiles.is_blocked(&types, -1.0, 0.0, 16.0, 16.0));
+    }
 }
@@ -0,0 +1,167 @@
+//! Level file writer
+//!
+//! Saves modified tiles back into Graal .nw level files.
+//!
+//! The parser does not keep NPC scripts or other sections it doesn't
+//! understand, so the writer never regenerates a whole file. It replaces
+//! the BOARD lines of the original file and copies every other line
+//! through unchanged.
+
+use crate::level::Level;
+use crate::tiles::{LevelTiles, BASE_LAYER, EMPTY_TILE};
+use crate::{LevelError, Result};
+use std::fs;
+
+/// Base64 alphabet used by BOARD lines
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Level file writer
+pub struct LevelWriter;
+
+impl LevelWriter {
+    /// Save the tiles of a level into its file
+    ///
+    /// # Behavior
+    /// 1. Reads the level's original file
+    /// 2. Replaces all BOARD lines with the current tiles
+    /// 3. Writes to a temporary file and renames it over the original
+    ///
+    /// # Errors
+    /// Returns an error if the level has no file (e.g. a generated default
+    /// level) or if the file can't be read or written.
+    ///
+    /// # C++ Equivalence
+    /// Matches the BOARD section written by `Level::saveLevel()` in Level.cpp
+    pub fn save_board(level: &Level) -> Result<()> {
+        if level.file_path.as_os_str().is_empty() {
+            return Err(LevelError::NotFound(format!("{} has no level file", level.name)));
+        }
+
+        let original = fs::read_to_string(&level.file_path)?;
+        let board_lines = Self::board_lines(&level.tiles.read());
+        let output = Self::replace_board(&original, &board_lines);
+
+        let temp_path = level.file_path.with_extension("nw.tmp");
+        fs::write(&temp_path, output)?;
+        fs::rename(&temp_path, &level.file_path)?;
+
+        tracing::debug!("Saved level board: {:?}", level.file_path);
+        Ok(())
+    }
+
+    /// Generate BOARD lines for all layers
+    ///
+    /// The base layer is always written in full; rows of other layers that
+    /// are entirely empty are skipped.
+    pub fn board_lines(tiles: &LevelTiles) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        let mut layer_ids: Vec<u8> = tiles.layer_ids().collect();
+        layer_ids.sort_unstable();
+
+        for layer_id in layer_ids {
+            let Some(layer) = tiles.get_layer(layer_id) else { continue };
+
+            for y in 0..64u8 {
+                let row: Vec<u16> = (0..64u8).map(|x| layer.get(x, y)).collect();
+                if layer_id != BASE_LAYER && row.iter().all(|&t| t == EMPTY_TILE) {
+                    continue;
+                }
+
+                let mut line = format!("BOARD 0 {} 64 {} ", y, layer_id);
+                for tile in row {
+                    let tile = if tile == EMPTY_TILE { 0 } else { tile };
+                    line.push(BASE64_CHARS[((tile >> 6) & 0x3F) as usize] as char);
+                    line.push(BASE64_CHARS[(tile & 0x3F) as usize] as char);
+                }
+                lines.push(line);
+            }
+        }
+
+        lines
+    }
+
+    /// Replace the BOARD lines of a level file, keeping everything else
+    ///
+    /// New BOARD lines are placed where the first original BOARD line was,
+    /// or right after the header if the file had none.
+    fn replace_board(original: &str, board_lines: &[String]) -> String {
+        let mut output = String::with_capacity(original.len());
+        let mut inserted = false;
+        let mut in_npc = false;
+
+        for (i, line) in original.lines().enumerate() {
+            let trimmed = line.trim();
+
+            // Don't touch anything inside NPC scripts
+            if trimmed.starts_with("NPC") && trimmed != "NPCEND" {
+                in_npc = true;
+            } else if trimmed == "NPCEND" {
+                in_npc = false;
+            }
+
+            if !in_npc && trimmed.starts_with("BOARD") {
+                if !inserted {
+                    Self::push_lines(&mut output, board_lines);
+                    inserted = true;
+                }
+                continue;
+            }
+
+            output.push_str(line);
+            output.push('\n');
+
+            if i == 0 && !original.contains("\nBOARD") {
+                Self::push_lines(&mut output, board_lines);
+                inserted = true;
+            }
+        }
+
+        output
+    }
+
+    fn push_lines(output: &mut String, lines: &[String]) {
+        for line in lines {
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::LevelLoader;
+
+    #[test]
+    fn test_save_board_roundtrip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("test.nw");
+        let board_row = "AA".repeat(64);
+        let content = format!(
+            "GLEVNW01\nBOARD 0 0 64 0 {}\nNPC - 30 30\nBOARD this is script text\nNPCEND\nLINK next.nw 0 0 1 1 30 30\n",
+            board_row
+        );
+        fs::write(&path, content).unwrap();
+
+        let level = LevelLoader::load_file(&path).unwrap();
+        level.set_tile(5, 0, 0, 0x3FF);
+        level.set_tile(63, 63, 0, 7);
+        LevelWriter::save_board(&level).unwrap();
+
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(saved.contains("BOARD this is script text"));
+        assert!(saved.contains("LINK next.nw"));
+        assert_eq!(saved.matches("BOARD 0 ").count(), 64);
+
+        let reloaded = LevelLoader::load_file(&path).unwrap();
+        assert_eq!(reloaded.get_tile(5, 0, 0), 0x3FF);
+        assert_eq!(reloaded.get_tile(63, 63, 0), 7);
+    }
+
+    #[test]
+    fn test_save_default_level_fails() {
+        let level = Level::create_default("generated.nw".to_string());
+        assert!(LevelWriter::save_board(&level).is_err());
+    }
+}
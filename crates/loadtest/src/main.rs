@@ -0,0 +1,272 @@
+//! GServer load test
+//!
+//! Connects many simulated clients to a running server. Each one logs in,
+//! then walks, chats and warps at random until the test ends; the report
+//! gives login and warp latency percentiles and, with `--server-pid`, the
+//! server's peak memory and CPU use.
+//!
+//! ```text
+//! gserver-loadtest --target 127.0.0.1:14802 --clients 256 --duration 60 --server-pid $(pidof gserver)
+//! ```
+
+mod resources;
+mod stats;
+
+use gserver_network::simclient::SimClient;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use resources::{Sample, Usage};
+use stats::Latencies;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn, Level};
+
+const USAGE: &str = "\
+Usage: gserver-loadtest [options]
+
+  --target <host:port>       Server to connect to (default 127.0.0.1:14802)
+  --clients <n>              Simulated clients (default 128)
+  --duration <secs>          Time every client keeps acting (default 30)
+  --ramp <ms>                Delay between client connects (default 20)
+  --interval <ms>            Time between a client's actions (default 250)
+  --levels <a.nw,b.nw>       Levels clients warp between (default onlinestartlocal.nw)
+  --account-prefix <name>    Account names are <name><index> (default loadtest)
+  --server-pid <pid>         Sample the server's memory and CPU from /proc
+  --seed <n>                 Seed for the action choices (default 0)";
+
+/// Chat lines the clients pick from
+const MESSAGES: [&str; 4] = ["hello", "anyone around?", "load test in progress", "brb"];
+
+/// Command line options
+#[derive(Debug, Clone)]
+struct Options {
+    target: String,
+    clients: usize,
+    duration: Duration,
+    ramp: Duration,
+    interval: Duration,
+    levels: Vec<String>,
+    account_prefix: String,
+    server_pid: Option<u32>,
+    seed: u64,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            target: "127.0.0.1:14802".to_string(),
+            clients: 128,
+            duration: Duration::from_secs(30),
+            ramp: Duration::from_millis(20),
+            interval: Duration::from_millis(250),
+            levels: vec!["onlinestartlocal.nw".to_string()],
+            account_prefix: "loadtest".to_string(),
+            server_pid: None,
+            seed: 0,
+        }
+    }
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{} requires a value", flag));
+            let number = |text: &String| text.parse::<u64>().map_err(|_| format!("{}: not a number: {}", flag, text));
+            match flag.as_str() {
+                "--target" => options.target = value()?.clone(),
+                "--clients" => options.clients = number(value()?)? as usize,
+                "--duration" => options.duration = Duration::from_secs(number(value()?)?),
+                "--ramp" => options.ramp = Duration::from_millis(number(value()?)?),
+                "--interval" => options.interval = Duration::from_millis(number(value()?)?),
+                "--levels" => options.levels = value()?.split(',').map(str::to_string).collect(),
+                "--account-prefix" => options.account_prefix = value()?.clone(),
+                "--server-pid" => options.server_pid = Some(number(value()?)? as u32),
+                "--seed" => options.seed = number(value()?)?,
+                other => return Err(format!("unknown option {}", other)),
+            }
+        }
+        if options.levels.iter().any(|l| l.is_empty()) {
+            return Err("--levels: empty level name".to_string());
+        }
+        Ok(options)
+    }
+}
+
+/// What one client saw
+#[derive(Debug, Default)]
+struct ClientReport {
+    logged_in: bool,
+    /// Error that ended the client early
+    error: Option<String>,
+    login: Latencies,
+    warp: Latencies,
+    actions: u64,
+    bytes_sent: u64,
+    bytes_received: u64,
+}
+
+/// Log in, then act until the deadline
+async fn run_client(index: usize, options: Arc<Options>, deadline: Instant) -> ClientReport {
+    let mut report = ClientReport::default();
+    let mut rng = StdRng::seed_from_u64(options.seed.wrapping_add(index as u64));
+    let account = format!("{}{}", options.account_prefix, index);
+
+    let mut client = match SimClient::connect(&options.target, &account, rng.gen_range(1..=127)).await {
+        Ok((client, latency)) => {
+            report.logged_in = true;
+            report.login.record(latency);
+            client
+        }
+        Err(e) => {
+            report.error = Some(format!("login: {}", e));
+            return report;
+        }
+    };
+
+    // A real client answers the login warp with PLI_LEVELWARP
+    let result = async {
+        report.warp.record(client.warp(&options.levels[0]).await?);
+
+        let mut ticker = tokio::time::interval(options.interval);
+        while Instant::now() < deadline {
+            ticker.tick().await;
+            match rng.gen_range(0..100) {
+                0..=69 => client.walk(rng.gen()).await?,
+                70..=89 => client.chat(MESSAGES[rng.gen_range(0..MESSAGES.len())]).await?,
+                _ => {
+                    let level = &options.levels[rng.gen_range(0..options.levels.len())];
+                    report.warp.record(client.warp(level).await?);
+                }
+            }
+            report.actions += 1;
+        }
+        Ok::<_, gserver_core::GServerError>(())
+    }.await;
+
+    if let Err(e) = result {
+        report.error = Some(e.to_string());
+    }
+    report.bytes_sent = client.bytes_sent();
+    report.bytes_received = client.bytes_received();
+    report
+}
+
+/// Sample the server once a second until the test ends
+async fn sample_server(pid: u32, until: Instant) -> Usage {
+    let mut usage = Usage::default();
+    let mut ticker = tokio::time::interval(Duration::from_secs(1));
+    while Instant::now() < until {
+        ticker.tick().await;
+        match Sample::read(pid) {
+            Some(sample) => usage.record(Instant::now(), sample),
+            None => {
+                warn!("Server process {} is gone", pid);
+                break;
+            }
+        }
+    }
+    usage
+}
+
+fn print_report(options: &Options, reports: Vec<ClientReport>, usage: Option<Usage>, elapsed: Duration) {
+    let mut login = Latencies::default();
+    let mut warp = Latencies::default();
+    let (mut logged_in, mut actions, mut sent, mut received) = (0, 0, 0, 0);
+    let mut errors = Vec::new();
+    for report in reports {
+        logged_in += report.logged_in as usize;
+        actions += report.actions;
+        sent += report.bytes_sent;
+        received += report.bytes_received;
+        login.merge(report.login);
+        warp.merge(report.warp);
+        errors.extend(report.error);
+    }
+
+    let secs = elapsed.as_secs_f64();
+    println!();
+    println!("Target     {}", options.target);
+    println!("Clients    {} logged in of {}, {} ended early", logged_in, options.clients, errors.len());
+    println!("Actions    {} ({:.0}/s)", actions, actions as f64 / secs);
+    println!("Traffic    sent {} KiB ({:.1} KiB/s), received {} KiB ({:.1} KiB/s)",
+        sent / 1024, sent as f64 / 1024.0 / secs, received / 1024, received as f64 / 1024.0 / secs);
+    println!("Login      {}", login);
+    println!("Warp       {}", warp);
+    match usage {
+        Some(usage) if !usage.is_empty() => println!("Server     peak RSS {} KiB, CPU {:.1}% of one core",
+            usage.peak_rss_kib(), usage.cpu_percent().unwrap_or_default()),
+        Some(_) => println!("Server     no samples (is /proc available?)"),
+        None => {}
+    }
+
+    errors.sort();
+    errors.dedup();
+    for error in errors.iter().take(10) {
+        println!("  error: {}", error);
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tracing_subscriber::fmt()
+        .with_max_level(Level::INFO)
+        .init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+    let options = Arc::new(Options::parse(&args).map_err(|e| format!("{}\n\n{}", e, USAGE))?);
+
+    info!("Starting {} clients against {}", options.clients, options.target);
+    let started = Instant::now();
+    let ramp_time = options.ramp * options.clients as u32;
+    let deadline = started + ramp_time + options.duration;
+
+    let sampler = options.server_pid.map(|pid| tokio::spawn(sample_server(pid, deadline)));
+
+    let mut tasks = Vec::with_capacity(options.clients);
+    for index in 0..options.clients {
+        tasks.push(tokio::spawn(run_client(index, options.clone(), deadline)));
+        tokio::time::sleep(options.ramp).await;
+    }
+    info!("All clients started, running until {:?} from now", deadline.saturating_duration_since(Instant::now()));
+
+    let mut reports = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        reports.push(task.await?);
+    }
+    let usage = match sampler {
+        Some(sampler) => Some(sampler.await?),
+        None => None,
+    };
+
+    print_report(&options, reports, usage, started.elapsed());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let options = Options::parse(&args("--clients 512 --duration 5 --levels a.nw,b.nw --server-pid 42")).unwrap();
+        assert_eq!(options.clients, 512);
+        assert_eq!(options.duration, Duration::from_secs(5));
+        assert_eq!(options.levels, ["a.nw", "b.nw"]);
+        assert_eq!(options.server_pid, Some(42));
+        assert_eq!(options.target, "127.0.0.1:14802");
+
+        assert!(Options::parse(&args("--clients")).is_err());
+        assert!(Options::parse(&args("--clients many")).is_err());
+        assert!(Options::parse(&args("--bogus 1")).is_err());
+    }
+}
@@ -0,0 +1,100 @@
+//! # Server Resource Usage
+//!
+//! Samples the memory and CPU time of the server process from `/proc`
+//! (Linux only). The server has to run on the same machine as the load test
+//! and is picked with `--server-pid`.
+
+use std::time::{Duration, Instant};
+
+/// Clock ticks per second of `/proc/<pid>/stat` times (USER_HZ, 100 on Linux)
+const CLOCK_TICKS: f64 = 100.0;
+
+/// One reading of the server process
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    /// Resident memory in KiB
+    pub rss_kib: u64,
+    /// User + system CPU time
+    pub cpu: Duration,
+}
+
+impl Sample {
+    /// Read `/proc/<pid>/status` and `/proc/<pid>/stat`
+    ///
+    /// # Returns
+    /// `None` once the process is gone (or off Linux)
+    pub fn read(pid: u32) -> Option<Self> {
+        let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        Some(Self { rss_kib: parse_rss(&status)?, cpu: parse_cpu(&stat)? })
+    }
+}
+
+/// Peak memory and average CPU over a run
+#[derive(Debug, Clone, Default)]
+pub struct Usage {
+    first: Option<(Instant, Sample)>,
+    last: Option<(Instant, Sample)>,
+    peak_rss_kib: u64,
+}
+
+impl Usage {
+    pub fn record(&mut self, at: Instant, sample: Sample) {
+        self.first.get_or_insert((at, sample));
+        self.last = Some((at, sample));
+        self.peak_rss_kib = self.peak_rss_kib.max(sample.rss_kib);
+    }
+
+    /// CPU use between the first and last sample, in percent of one core
+    pub fn cpu_percent(&self) -> Option<f64> {
+        let ((start, first), (end, last)) = (self.first?, self.last?);
+        let wall = end.duration_since(start).as_secs_f64();
+        (wall > 0.0).then(|| last.cpu.saturating_sub(first.cpu).as_secs_f64() / wall * 100.0)
+    }
+
+    pub fn peak_rss_kib(&self) -> u64 {
+        self.peak_rss_kib
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.first.is_none()
+    }
+}
+
+/// `VmRSS:    12345 kB`
+fn parse_rss(status: &str) -> Option<u64> {
+    let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// utime and stime are fields 14 and 15; the command (field 2) may contain
+/// spaces, so count from its closing parenthesis
+fn parse_cpu(stat: &str) -> Option<Duration> {
+    let fields = stat[stat.rfind(')')? + 1..].split_whitespace().collect::<Vec<_>>();
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some(Duration::from_secs_f64((utime + stime) as f64 / CLOCK_TICKS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proc() {
+        assert_eq!(parse_rss("Name:\tgserver\nVmPeak:\t  9000 kB\nVmRSS:\t  4321 kB\n"), Some(4321));
+        assert_eq!(parse_rss("Name:\tgserver\n"), None);
+
+        let stat = "4242 (g server) S 1 4242 4242 0 -1 4194560 100 0 0 0 250 50 0 0 20 0 9 0 123 456 789";
+        assert_eq!(parse_cpu(stat), Some(Duration::from_secs(3)));
+
+        let start = Instant::now();
+        let mut usage = Usage::default();
+        assert!(usage.is_empty());
+        usage.record(start, Sample { rss_kib: 1000, cpu: Duration::from_secs(1) });
+        usage.record(start + Duration::from_secs(2), Sample { rss_kib: 3000, cpu: Duration::from_secs(2) });
+        usage.record(start + Duration::from_secs(4), Sample { rss_kib: 2000, cpu: Duration::from_secs(3) });
+        assert_eq!(usage.peak_rss_kib(), 3000);
+        assert_eq!(usage.cpu_percent(), Some(50.0));
+    }
+}
@@ -0,0 +1,87 @@
+//! # Latency Statistics
+//!
+//! Each client records its own samples; the runner merges them once all
+//! clients are done, so nothing is shared while the test runs.
+
+use std::fmt;
+use std::time::Duration;
+
+/// Latency samples of one kind (login, warp)
+#[derive(Debug, Clone, Default)]
+pub struct Latencies {
+    samples: Vec<Duration>,
+}
+
+impl Latencies {
+    pub fn record(&mut self, sample: Duration) {
+        self.samples.push(sample);
+    }
+
+    pub fn merge(&mut self, other: Latencies) {
+        self.samples.extend(other.samples);
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Nearest-rank percentile
+    ///
+    /// # Arguments
+    /// * `p` - Percentile, 0-100
+    ///
+    /// # Returns
+    /// `None` without samples
+    pub fn percentile(&self, p: f64) -> Option<Duration> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let mut sorted = self.samples.clone();
+        sorted.sort_unstable();
+        let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+        Some(sorted[rank.clamp(1, sorted.len()) - 1])
+    }
+}
+
+impl fmt::Display for Latencies {
+    /// `n=… p50=… p90=… p99=… max=…` in milliseconds
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "n=0");
+        }
+        let ms = |p: f64| self.percentile(p).unwrap_or_default().as_secs_f64() * 1000.0;
+        write!(f, "n={} p50={:.1}ms p90={:.1}ms p99={:.1}ms max={:.1}ms",
+            self.len(), ms(50.0), ms(90.0), ms(99.0), ms(100.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles() {
+        let mut latencies = Latencies::default();
+        assert_eq!(latencies.percentile(50.0), None);
+        assert_eq!(latencies.to_string(), "n=0");
+
+        for ms in (1..=100).rev() {
+            latencies.record(Duration::from_millis(ms));
+        }
+        assert_eq!(latencies.percentile(0.0), Some(Duration::from_millis(1)));
+        assert_eq!(latencies.percentile(50.0), Some(Duration::from_millis(50)));
+        assert_eq!(latencies.percentile(99.0), Some(Duration::from_millis(99)));
+        assert_eq!(latencies.percentile(100.0), Some(Duration::from_millis(100)));
+
+        let mut other = Latencies::default();
+        other.record(Duration::from_millis(500));
+        latencies.merge(other);
+        assert_eq!(latencies.len(), 101);
+        assert_eq!(latencies.percentile(100.0), Some(Duration::from_millis(500)));
+        assert!(latencies.to_string().starts_with("n=101 p50=51.0ms"));
+    }
+}
@@ -0,0 +1,64 @@
+//! Bundle compression throughput
+//!
+//! Compares pooled and unpooled zlib encoders at a few levels, and bzip2, on
+//! bundle sizes typical for each GEN_5 compression type.
+//!
+//! Run with `cargo bench -p gserver-network --bench compression`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use gserver_config::CompressionConfig;
+use gserver_network::compression::Compressor;
+
+/// Packet-like data: GChar-encoded text lines with some repetition
+fn bundle(len: usize) -> Vec<u8> {
+    let line = b"\x2a\x20\x21\x22Hello from level onlinestartlocal.nw at 30.5,30.5\n";
+    line.iter().copied().cycle()
+        .enumerate()
+        .map(|(i, b)| if i % 97 == 0 { (i % 64) as u8 + 32 } else { b })
+        .take(len)
+        .collect()
+}
+
+fn bench_zlib(c: &mut Criterion) {
+    let mut group = c.benchmark_group("zlib");
+
+    // Small bundles (movement, chat) dominate on busy servers
+    for len in [256, 4096] {
+        let data = bundle(len);
+        group.throughput(Throughput::Bytes(len as u64));
+
+        for level in [1, 6, 9] {
+            for pooled in [false, true] {
+                let compressor = Compressor::new(CompressionConfig {
+                    zlib_level: level,
+                    encoder_pool: pooled,
+                    ..Default::default()
+                });
+                let name = format!("level{}{}", level, if pooled { "-pooled" } else { "" });
+                group.bench_with_input(BenchmarkId::new(name, len), &data, |b, data| {
+                    b.iter(|| compressor.zlib(black_box(data)).unwrap())
+                });
+            }
+        }
+    }
+    group.finish();
+}
+
+fn bench_bz2(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bz2");
+
+    // GEN_5 only uses bzip2 above 8KB
+    let data = bundle(0x8000);
+    group.throughput(Throughput::Bytes(data.len() as u64));
+
+    for level in [1, 6, 9] {
+        let compressor = Compressor::new(CompressionConfig { bz2_level: level, ..Default::default() });
+        group.bench_with_input(BenchmarkId::new(format!("level{}", level), data.len()), &data, |b, data| {
+            b.iter(|| compressor.bz2(black_box(data)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_zlib, bench_bz2);
+criterion_main!(benches);
@@ -0,0 +1,227 @@
+//! # Alt Detection
+//!
+//! Every player login records the account and its IP in `iphistory.txt`.
+//! Accounts that logged in from the same IP are likely alts of each other:
+//!
+//! - RC `/alts <account>` lists an account's IPs and the accounts seen on them
+//! - A player logging in from an IP a banned account used is reported in
+//!   staff chat, so bans evaded with a new account are visible
+//!
+//! Logins older than `iphistorydays` are dropped, and each account and IP
+//! keeps its [`MAX_ENTRIES`] most recent partners.
+//!
+//! A login only marks the history dirty; [`IpHistoryAutosave`](crate::autosave::IpHistoryAutosave)
+//! writes it on each autosave and at shutdown, so logins don't wait on the file.
+//!
+//! # File Format
+//! ```text
+//! {lowercase account} {ip} {unix seconds of the last login}
+//! ```
+
+use gserver_accounts::AccountLoader;
+use gserver_core::Result;
+use parking_lot::Mutex;
+use std::collections::{BTreeSet, HashMap};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// IP history file in the server folder
+pub const HISTORY_FILE: &str = "iphistory.txt";
+
+/// IPs kept per account, and accounts kept per IP
+pub const MAX_ENTRIES: usize = 32;
+
+/// Last login of each account from each IP
+#[derive(Debug, Default)]
+pub struct IpHistory {
+    /// Where the history is saved, `None` to keep it in memory
+    path: Option<PathBuf>,
+    logins: Mutex<HashMap<(String, IpAddr), u64>>,
+
+    /// Changed since it was last written
+    dirty: AtomicBool,
+}
+
+impl IpHistory {
+    /// Create an empty history that isn't saved
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the history file, starting empty if there is none
+    pub fn load(path: &Path) -> Self {
+        let mut logins = HashMap::new();
+        if let Ok(content) = std::fs::read_to_string(path) {
+            for line in content.lines() {
+                let mut fields = line.split_whitespace();
+                let (Some(account), Some(ip), Some(at)) = (fields.next(), fields.next(), fields.next()) else {
+                    continue;
+                };
+                if let (Ok(ip), Ok(at)) = (ip.parse(), at.parse()) {
+                    logins.insert((account.to_lowercase(), ip), at);
+                }
+            }
+        }
+        Self { path: Some(path.to_path_buf()), logins: Mutex::new(logins), dirty: AtomicBool::new(false) }
+    }
+
+    /// Remember a login and drop what's past retention
+    ///
+    /// # Arguments
+    /// * `retention_days` - `iphistorydays`; 0 records nothing
+    pub fn record(&self, account: &str, ip: IpAddr, now: u64, retention_days: u64) {
+        if retention_days == 0 {
+            return;
+        }
+        let account = account.to_lowercase();
+        {
+            let mut logins = self.logins.lock();
+            logins.insert((account.clone(), ip), now);
+            let cutoff = now.saturating_sub(retention_days * 86400);
+            logins.retain(|_, at| *at >= cutoff);
+            keep_newest(&mut logins, |(a, _)| *a == account);
+            keep_newest(&mut logins, |(_, i)| *i == ip);
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Get the IPs an account logged in from, newest first
+    pub fn ips_of(&self, account: &str) -> Vec<(IpAddr, u64)> {
+        let account = account.to_lowercase();
+        let mut ips: Vec<_> = self.logins.lock().iter()
+            .filter(|((a, _), _)| *a == account)
+            .map(|((_, ip), at)| (*ip, *at))
+            .collect();
+        ips.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        ips
+    }
+
+    /// Get the accounts that logged in from an IP, sorted
+    pub fn accounts_on(&self, ip: IpAddr) -> Vec<String> {
+        let mut accounts: Vec<String> = self.logins.lock().keys().filter(|(_, i)| *i == ip).map(|(a, _)| a.clone()).collect();
+        accounts.sort();
+        accounts
+    }
+
+    /// Get the other accounts that logged in from an account's IPs, sorted
+    pub fn alts_of(&self, account: &str) -> Vec<String> {
+        let account = account.to_lowercase();
+        let logins = self.logins.lock();
+        let ips: BTreeSet<IpAddr> = logins.keys().filter(|(a, _)| *a == account).map(|(_, ip)| *ip).collect();
+        let alts: BTreeSet<&String> = logins.keys()
+            .filter(|(a, ip)| *a != account && ips.contains(ip))
+            .map(|(a, _)| a)
+            .collect();
+        alts.into_iter().cloned().collect()
+    }
+
+    /// Write the history file if it changed since the last write
+    ///
+    /// It's written aside and renamed over the old file. On failure it stays
+    /// dirty and is retried next time.
+    ///
+    /// # Returns
+    /// The number of files written (0 or 1)
+    pub fn save_dirty(&self) -> Result<usize> {
+        let Some(path) = &self.path else {
+            return Ok(0);
+        };
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return Ok(0);
+        }
+        let mut entries: Vec<(String, IpAddr, u64)> = self.logins.lock().iter()
+            .map(|((account, ip), at)| (account.clone(), *ip, *at))
+            .collect();
+        entries.sort();
+        let content: String = entries.iter().map(|(account, ip, at)| format!("{} {} {}\n", account, ip, at)).collect();
+        let temp = path.with_extension("txt.tmp");
+        if let Err(e) = std::fs::write(&temp, content).and_then(|()| std::fs::rename(&temp, path)) {
+            self.dirty.store(true, Ordering::Relaxed);
+            return Err(e.into());
+        }
+        Ok(1)
+    }
+}
+
+/// Drop all but the [`MAX_ENTRIES`] newest logins matching a filter
+fn keep_newest(logins: &mut HashMap<(String, IpAddr), u64>, matches: impl Fn(&(String, IpAddr)) -> bool) {
+    let mut matching: Vec<_> = logins.iter().filter(|(key, _)| matches(key)).map(|(key, at)| (key.clone(), *at)).collect();
+    if matching.len() <= MAX_ENTRIES {
+        return;
+    }
+    matching.sort_by_key(|(_, at)| std::cmp::Reverse(*at));
+    for (key, _) in matching.into_iter().skip(MAX_ENTRIES) {
+        logins.remove(&key);
+    }
+}
+
+/// Get the alts of an account that are banned
+pub fn banned_alts(history: &IpHistory, server_dir: &Path, account: &str, now: u64) -> Vec<String> {
+    let loader = AccountLoader::new(server_dir);
+    history.alts_of(account).into_iter()
+        .filter(|alt| loader.exists(alt) && loader.load(alt).is_ok_and(|a| a.is_banned_at(now)))
+        .collect()
+}
+
+/// Describe an account's IPs and alts for RC `/alts`
+pub fn describe(history: &IpHistory, server_dir: &Path, account: &str, now: u64) -> String {
+    let ips = history.ips_of(account);
+    if ips.is_empty() {
+        return format!("No logins recorded for {}", account);
+    }
+    let banned = banned_alts(history, server_dir, account, now);
+    let alts: Vec<String> = history.alts_of(account).into_iter()
+        .map(|alt| if banned.contains(&alt) { format!("{} (banned)", alt) } else { alt })
+        .collect();
+    let ips: Vec<String> = ips.iter().map(|(ip, _)| ip.to_string()).collect();
+    format!("Alts of {}: {}; IPs: {}", account,
+        if alts.is_empty() { "none".to_string() } else { alts.join(", ") }, ips.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(last: u8) -> IpAddr {
+        IpAddr::from([10, 0, 0, last])
+    }
+
+    #[test]
+    fn test_history_survives_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(HISTORY_FILE);
+
+        let history = IpHistory::load(&path);
+        history.record("Bob", ip(1), 1_000, 90);
+        history.record("Alice", ip(1), 2_000, 90);
+        history.record("alice", ip(2), 3_000, 90);
+        history.record("Carl", ip(3), 3_000, 90);
+        history.record("Dave", ip(2), 4_000, 0);
+        assert_eq!(history.alts_of("ALICE"), ["bob"]);
+        assert_eq!(history.alts_of("bob"), ["alice"]);
+        assert!(history.alts_of("carl").is_empty());
+        assert!(!path.exists());
+        assert_eq!(history.save_dirty().unwrap(), 1);
+        assert_eq!(history.save_dirty().unwrap(), 0);
+
+        let reloaded = IpHistory::load(&path);
+        assert_eq!(reloaded.ips_of("alice"), [(ip(2), 3_000), (ip(1), 2_000)]);
+
+        // Bob's login is past a day of retention by the next login
+        reloaded.record("Eve", ip(9), 1_000 + 86_401, 1);
+        assert!(reloaded.ips_of("bob").is_empty());
+        assert_eq!(reloaded.ips_of("alice").len(), 2);
+    }
+
+    #[test]
+    fn test_limits_per_ip() {
+        let history = IpHistory::new();
+        for n in 0..MAX_ENTRIES as u64 + 5 {
+            history.record(&format!("alt{}", n), ip(1), 1_000 + n, 90);
+        }
+        assert_eq!(history.alts_of("alt36").len(), MAX_ENTRIES - 1);
+        assert!(history.ips_of("alt0").is_empty());
+        assert_eq!(describe(&history, Path::new("servers/test"), "nobody", 0), "No logins recorded for nobody");
+    }
+}
@@ -0,0 +1,224 @@
+//! # Ambience Control
+//!
+//! Applies weather and tint changes ([`gserver_game::ambience`]) from RC
+//! (`/ambience`), scripts (`ambience.set`, `ambience.clear`) and the schedule,
+//! and publishes [`GameEvent::AmbienceChanged`] for each change. The server's
+//! ambience relay sends the new overlay to every player on an affected level;
+//! players warping in get the overlays of their new level.
+//!
+//! Gmap scopes are resolved against `world/<name>.gmap` the first time they
+//! are used.
+//!
+//! [`GameEvent::AmbienceChanged`]: gserver_game::GameEvent::AmbienceChanged
+
+use gserver_core::{GServerError, Result};
+use gserver_game::ambience::{AmbienceTransition, MAX_INTENSITY};
+use gserver_game::{Ambience, AmbienceEffect, AmbienceScope, EventBus, GameEvent};
+use gserver_levels::map::MapLoader;
+use gserver_protocol::ShowImg;
+use gserver_scripting::AmbienceHandler;
+use parking_lot::Mutex;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// A requested ambience change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmbienceRequest {
+    /// Which effect
+    pub effect: AmbienceEffect,
+    /// New intensity, `None` turns the effect off
+    pub intensity: Option<u8>,
+    /// Where it applies
+    pub scope: AmbienceScope,
+    /// Time until the change
+    pub delay: Duration,
+}
+
+impl AmbienceRequest {
+    /// Parse the arguments of `/ambience <effect> <0-100|off> [scope] [in <seconds>]`
+    pub fn parse(args: &[&str]) -> std::result::Result<Self, String> {
+        let usage = || "Usage: /ambience <rain|snow|night> <0-100|off> [all|level|gmap] [in <seconds>]".to_string();
+        let (effect, intensity, rest) = match args {
+            [effect, intensity, rest @ ..] => (effect, intensity, rest),
+            _ => return Err(usage()),
+        };
+
+        let effect = AmbienceEffect::from_name(effect).ok_or_else(|| format!("Unknown effect: {}", effect))?;
+        let intensity = match *intensity {
+            "off" => None,
+            value => match value.parse::<u8>() {
+                Ok(value) if value <= MAX_INTENSITY => Some(value),
+                _ => return Err(format!("Intensity must be 0-{} or off", MAX_INTENSITY)),
+            },
+        };
+        let (scope, delay) = match rest {
+            [] => ("", None),
+            ["in", delay] => ("", Some(delay)),
+            [scope] => (*scope, None),
+            [scope, "in", delay] => (*scope, Some(delay)),
+            _ => return Err(usage()),
+        };
+        let delay = match delay {
+            Some(delay) => Duration::from_secs(delay.parse().map_err(|_| usage())?),
+            None => Duration::ZERO,
+        };
+
+        Ok(Self { effect, intensity, scope: AmbienceScope::parse(scope), delay })
+    }
+}
+
+/// Current ambience plus the plumbing to announce changes
+#[derive(Debug)]
+pub struct AmbienceService {
+    state: Mutex<Ambience>,
+    events: EventBus,
+    world_dir: PathBuf,
+}
+
+impl AmbienceService {
+    /// Create the service
+    ///
+    /// # Arguments
+    /// * `events` - Event bus for [`GameEvent::AmbienceChanged`]
+    /// * `world_dir` - Folder holding the gmap files
+    pub fn new(events: EventBus, world_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            state: Mutex::new(Ambience::new()),
+            events,
+            world_dir: world_dir.into(),
+        }
+    }
+
+    /// Get the ambience state
+    pub fn state(&self) -> &Mutex<Ambience> {
+        &self.state
+    }
+
+    /// Apply or schedule a change
+    ///
+    /// # Errors
+    /// Fails for a gmap scope whose file can't be loaded
+    pub fn apply(&self, request: AmbienceRequest, now: Instant) -> Result<()> {
+        if let AmbienceScope::Gmap(gmap) = &request.scope {
+            self.load_gmap(gmap)?;
+        }
+
+        let mut state = self.state.lock();
+        if request.delay.is_zero() {
+            if state.set(request.scope.clone(), request.effect, request.intensity) {
+                drop(state);
+                self.publish(request.scope, request.effect);
+            }
+        } else {
+            state.schedule(AmbienceTransition {
+                at: now + request.delay,
+                scope: request.scope,
+                effect: request.effect,
+                intensity: request.intensity,
+            });
+        }
+        Ok(())
+    }
+
+    /// Apply the scheduled changes that are due
+    ///
+    /// # Returns
+    /// Number of changes published
+    pub fn run_due(&self, now: Instant) -> usize {
+        let changed = self.state.lock().run_due(now);
+        let count = changed.len();
+        for (scope, effect) in changed {
+            self.publish(scope, effect);
+        }
+        count
+    }
+
+    /// Get the overlays for a level
+    pub fn images_for(&self, level: &str) -> Vec<ShowImg> {
+        self.state.lock().images_for(level)
+    }
+
+    /// Get the overlay of one effect on a level (hidden if the effect is off)
+    pub fn image(&self, level: &str, effect: AmbienceEffect) -> ShowImg {
+        effect.image(self.state.lock().effective(level, effect))
+    }
+
+    /// Check if a change of `scope` affects a level
+    pub fn covers(&self, scope: &AmbienceScope, level: &str) -> bool {
+        self.state.lock().covers(scope, level)
+    }
+
+    /// Describe the scheduled changes for RC
+    pub fn schedule_lines(&self, now: Instant) -> Vec<String> {
+        self.state.lock().scheduled().iter()
+            .map(|t| {
+                let intensity = t.intensity.map_or("off".to_string(), |i| i.to_string());
+                let secs = t.at.saturating_duration_since(now).as_secs();
+                format!("{} {} on {} in {}s", t.effect, intensity, t.scope, secs)
+            })
+            .collect()
+    }
+
+    fn load_gmap(&self, gmap: &str) -> Result<()> {
+        if self.state.lock().has_gmap(gmap) {
+            return Ok(());
+        }
+        let map = MapLoader::load_gmap(self.world_dir.join(gmap))
+            .map_err(|e| GServerError::NotFound(format!("gmap {}: {}", gmap, e)))?;
+        self.state.lock().register_gmap(gmap, map.levels.into_values());
+        Ok(())
+    }
+
+    fn publish(&self, scope: AmbienceScope, effect: AmbienceEffect) {
+        self.events.publish(GameEvent::AmbienceChanged { scope, effect });
+    }
+}
+
+impl AmbienceHandler for AmbienceService {
+    fn set(&self, effect: &str, intensity: Option<u8>, scope: &str, delay: f64) -> std::result::Result<(), String> {
+        let effect = AmbienceEffect::from_name(effect).ok_or_else(|| format!("Unknown effect: {}", effect))?;
+        let request = AmbienceRequest {
+            effect,
+            intensity,
+            scope: AmbienceScope::parse(scope),
+            delay: Duration::from_secs_f64(delay.max(0.0)),
+        };
+        self.apply(request, Instant::now()).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request() {
+        let request = AmbienceRequest::parse(&["Rain", "60", "world.gmap", "in", "30"]).unwrap();
+        assert_eq!(request, AmbienceRequest {
+            effect: AmbienceEffect::Rain,
+            intensity: Some(60),
+            scope: AmbienceScope::Gmap("world.gmap".into()),
+            delay: Duration::from_secs(30),
+        });
+        assert_eq!(AmbienceRequest::parse(&["night", "off"]).unwrap().scope, AmbienceScope::All);
+        assert!(AmbienceRequest::parse(&["fog", "10"]).is_err());
+        assert!(AmbienceRequest::parse(&["snow", "101"]).is_err());
+    }
+
+    #[test]
+    fn test_changes_are_published() {
+        let events = EventBus::new();
+        let mut rx = events.subscribe();
+        let service = AmbienceService::new(events, "world");
+        let now = Instant::now();
+
+        AmbienceHandler::set(&service, "snow", Some(40), "house.nw", 0.0).unwrap();
+        AmbienceHandler::set(&service, "snow", None, "house.nw", 10.0).unwrap();
+        assert!(matches!(rx.try_recv(), Ok(GameEvent::AmbienceChanged { effect: AmbienceEffect::Snow, .. })));
+        assert_eq!(service.images_for("house.nw").len(), 1);
+        assert!(AmbienceHandler::set(&service, "rain", Some(1), "missing.gmap", 0.0).is_err());
+
+        assert_eq!(service.run_due(now + Duration::from_secs(11)), 1);
+        assert!(service.image("house.nw", AmbienceEffect::Snow).image.is_empty());
+    }
+}
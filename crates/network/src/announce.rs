@@ -0,0 +1,177 @@
+//! # Admin Messages and Announcements
+//!
+//! Server-wide popups (PLO_RC_ADMINMESSAGE) for players. Every source goes
+//! through [`Announcements`], which publishes [`GameEvent::AdminMessage`];
+//! the server's player relay sends the popup to the target, or to every
+//! player (not RCs) when there is none:
+//!
+//! - RC admin messages (PLI_RC_ADMINMESSAGE, PLI_RC_PRIVADMINMESSAGE)
+//! - RC `/announce [in <seconds>] <text>` for messages sent later
+//! - Scripts with `sendrpgmessage`
+//! - The shutdown countdown (`shutdowncountdown` seconds of warnings)
+//!
+//! # Packet Format (PLO_RC_ADMINMESSAGE)
+//! ```text
+//! {sender}:§{message}
+//! ```
+//! `§` is the byte `0xA7`, which the client shows as a line break.
+//!
+//! # C++ Equivalence
+//! Matches `PlayerRC::msgPLI_RC_ADMINMESSAGE`, which sends
+//! `"Admin " << account << ":\xa7" << message` to every client.
+
+use gserver_core::PlayerID;
+use gserver_game::{EventBus, GameEvent};
+use gserver_scripting::AdminMessageHandler;
+use parking_lot::Mutex;
+use std::time::{Duration, Instant};
+
+/// Line break in admin messages
+const LINE_BREAK: u8 = 0xA7;
+
+/// Remaining seconds at which the shutdown countdown warns (below the start)
+const COUNTDOWN_MARKS: [u64; 7] = [300, 120, 60, 30, 10, 5, 1];
+
+/// Sender shown for messages that don't come from an RC
+pub const SERVER_SENDER: &str = "Server";
+
+/// Encode a PLO_RC_ADMINMESSAGE body
+pub fn admin_message_data(from: &str, message: &str) -> Vec<u8> {
+    let mut data = Vec::with_capacity(from.len() + message.len() + 2);
+    data.extend_from_slice(from.as_bytes());
+    data.push(b':');
+    data.push(LINE_BREAK);
+    data.extend(message.bytes().filter(|&b| b != b'\r').map(|b| if b == b'\n' { LINE_BREAK } else { b }));
+    data
+}
+
+/// Get the remaining seconds at which a shutdown countdown warns players
+///
+/// # Returns
+/// `seconds` itself, then each of [`COUNTDOWN_MARKS`] below it
+pub fn countdown_marks(seconds: u64) -> Vec<u64> {
+    if seconds == 0 {
+        return Vec::new();
+    }
+    std::iter::once(seconds)
+        .chain(COUNTDOWN_MARKS.into_iter().filter(|&mark| mark < seconds))
+        .collect()
+}
+
+/// Sends admin messages now or later
+#[derive(Debug)]
+pub struct Announcements {
+    events: EventBus,
+
+    /// Messages waiting to be sent, with the time they are due
+    scheduled: Mutex<Vec<(Instant, String)>>,
+}
+
+impl Announcements {
+    /// Create the service
+    ///
+    /// # Arguments
+    /// * `events` - Event bus for [`GameEvent::AdminMessage`]
+    pub fn new(events: EventBus) -> Self {
+        Self { events, scheduled: Mutex::new(Vec::new()) }
+    }
+
+    /// Show a popup to every player
+    pub fn broadcast(&self, from: &str, message: &str) {
+        self.publish(None, from, message);
+    }
+
+    /// Show a popup to one player
+    pub fn send_to(&self, player: PlayerID, from: &str, message: &str) {
+        self.publish(Some(player), from, message);
+    }
+
+    /// Broadcast a message from the server after a delay
+    pub fn schedule(&self, delay: Duration, message: &str, now: Instant) {
+        self.scheduled.lock().push((now + delay, message.to_string()));
+    }
+
+    /// Broadcast the scheduled messages that are due
+    ///
+    /// # Returns
+    /// Number of messages sent
+    pub fn run_due(&self, now: Instant) -> usize {
+        let due: Vec<String> = {
+            let mut scheduled = self.scheduled.lock();
+            let (due, waiting) = std::mem::take(&mut *scheduled).into_iter().partition(|(at, _)| *at <= now);
+            *scheduled = waiting;
+            due.into_iter().map(|(_, message)| message).collect()
+        };
+        for message in &due {
+            self.broadcast(SERVER_SENDER, message);
+        }
+        due.len()
+    }
+
+    /// Describe the scheduled messages for RC
+    pub fn schedule_lines(&self, now: Instant) -> Vec<String> {
+        self.scheduled.lock().iter()
+            .map(|(at, message)| format!("\"{}\" in {}s", message, at.saturating_duration_since(now).as_secs()))
+            .collect()
+    }
+
+    /// Warn players before a shutdown, returning when the time is up
+    ///
+    /// Warnings go out at each of [`countdown_marks`]; the relays must still
+    /// be running for them to arrive.
+    pub async fn shutdown_countdown(&self, seconds: u64) {
+        let marks = countdown_marks(seconds);
+        for (i, &remaining) in marks.iter().enumerate() {
+            let unit = if remaining == 1 { "second" } else { "seconds" };
+            self.broadcast(SERVER_SENDER, &format!("The server is shutting down in {} {}", remaining, unit));
+            let next = marks.get(i + 1).copied().unwrap_or(0);
+            tokio::time::sleep(Duration::from_secs(remaining - next)).await;
+        }
+    }
+
+    fn publish(&self, to: Option<PlayerID>, from: &str, message: &str) {
+        self.events.publish(GameEvent::AdminMessage { to, from: from.to_string(), message: message.to_string() });
+    }
+}
+
+impl AdminMessageHandler for Announcements {
+    fn send(&self, player: Option<PlayerID>, message: &str) {
+        self.publish(player, SERVER_SENDER, message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_admin_message_data() {
+        assert_eq!(admin_message_data("Admin Bob", "Restart soon\nsave now"), b"Admin Bob:\xa7Restart soon\xa7save now");
+        assert_eq!(countdown_marks(0), Vec::<u64>::new());
+        assert_eq!(countdown_marks(45), [45, 30, 10, 5, 1]);
+        assert_eq!(countdown_marks(60), [60, 30, 10, 5, 1]);
+    }
+
+    #[test]
+    fn test_scheduled_announcements() {
+        let events = EventBus::new();
+        let mut received = events.subscribe();
+        let announcements = Announcements::new(events);
+        let now = Instant::now();
+        announcements.schedule(Duration::from_secs(30), "Event starts", now);
+        announcements.schedule(Duration::from_secs(90), "Event ends", now);
+        assert_eq!(announcements.schedule_lines(now), ["\"Event starts\" in 30s", "\"Event ends\" in 90s"]);
+
+        assert_eq!(announcements.run_due(now), 0);
+        assert_eq!(announcements.run_due(now + Duration::from_secs(30)), 1);
+        assert_eq!(received.try_recv().unwrap(), GameEvent::AdminMessage {
+            to: None,
+            from: SERVER_SENDER.into(),
+            message: "Event starts".into(),
+        });
+        assert_eq!(announcements.schedule_lines(now).len(), 1);
+
+        announcements.send(Some(PlayerID(2)), "hi");
+        assert!(matches!(received.try_recv().unwrap(), GameEvent::AdminMessage { to: Some(PlayerID(2)), .. }));
+    }
+}
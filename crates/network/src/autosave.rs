@@ -0,0 +1,195 @@
+//! # Autosave Targets
+//!
+//! This module connects the server's persistent state to the autosave
+//! service in `gserver-game`.
+//!
+//! # Targets
+//!
+//! - [`AccountAutosave`] - Accounts of connected players that changed
+//! - [`ServerFlagsAutosave`] - `serverflags.txt`, when the flags changed
+//! - [`LevelAutosave`] - Modified level boards (only with `savelevels=true`)
+//! - [`NpcSaveAutosave`] - NPC saves in `npcs/` that changed
+//! - [`IpHistoryAutosave`] - `iphistory.txt`, when logins were recorded
+
+use crate::connection::PlayerConnection;
+use crate::context::ServerContext;
+use gserver_core::{GServerError, PlayerID, Result};
+use gserver_game::AutosaveTarget;
+use parking_lot::Mutex;
+use std::fs;
+use std::sync::Arc;
+
+/// Saves the accounts of all connected players
+///
+/// The changed accounts go on the account save queue, in order with the
+/// saves of disconnecting players (see [`crate::savequeue`]).
+pub struct AccountAutosave {
+    /// All active connections
+    connections: Arc<dashmap::DashMap<PlayerID, Arc<PlayerConnection>>>,
+}
+
+impl AccountAutosave {
+    /// Create a new account autosave target
+    pub fn new(connections: Arc<dashmap::DashMap<PlayerID, Arc<PlayerConnection>>>) -> Self {
+        Self { connections }
+    }
+}
+
+impl AutosaveTarget for AccountAutosave {
+    fn name(&self) -> &str {
+        "accounts"
+    }
+
+    fn save(&self) -> Result<usize> {
+        // Collect first so the map isn't locked while the accounts are snapshotted
+        let connections: Vec<_> = self.connections.iter().map(|e| e.value().clone()).collect();
+        Ok(connections.iter().filter(|conn| conn.queue_account_save()).count())
+    }
+}
+
+/// Saves `serverflags.txt` when the server flags changed
+pub struct ServerFlagsAutosave {
+    /// Shared server state (holds the flags in its config)
+    context: Arc<ServerContext>,
+
+    /// Flags as of the last save (or as loaded)
+    last_saved: Mutex<Vec<String>>,
+}
+
+impl ServerFlagsAutosave {
+    /// Create a new server flags autosave target
+    ///
+    /// The current flags are taken as already saved.
+    pub fn new(context: Arc<ServerContext>) -> Self {
+        let last_saved = context.config().read().server_flags.clone();
+        Self {
+            context,
+            last_saved: Mutex::new(last_saved),
+        }
+    }
+}
+
+impl AutosaveTarget for ServerFlagsAutosave {
+    fn name(&self) -> &str {
+        "serverflags"
+    }
+
+    fn save(&self) -> Result<usize> {
+        let (flags, content) = {
+            let config = self.context.config().read();
+            (config.server_flags.clone(), config.serverflags_to_string())
+        };
+
+        let mut last_saved = self.last_saved.lock();
+        if *last_saved == flags {
+            return Ok(0);
+        }
+
+        // serverflags.txt lives in the server root, not config/
+        let path = self.context.server_dir().join("serverflags.txt");
+        let temp_path = path.with_extension("txt.tmp");
+        fs::write(&temp_path, content)
+            .and_then(|_| fs::rename(&temp_path, &path))
+            .map_err(|e| GServerError::Io(std::io::Error::new(
+                e.kind(),
+                format!("Failed to write {:?}: {}", path, e)
+            )))?;
+
+        *last_saved = flags;
+        Ok(1)
+    }
+}
+
+/// Saves modified level boards
+pub struct LevelAutosave {
+    /// Shared server state (holds the level manager and `savelevels` option)
+    context: Arc<ServerContext>,
+}
+
+impl LevelAutosave {
+    /// Create a new level autosave target
+    pub fn new(context: Arc<ServerContext>) -> Self {
+        Self { context }
+    }
+}
+
+impl AutosaveTarget for LevelAutosave {
+    fn name(&self) -> &str {
+        "levels"
+    }
+
+    fn save(&self) -> Result<usize> {
+        // C++: levels are only written back with the savelevels option
+        if !self.context.config().read().save_levels {
+            return Ok(0);
+        }
+        Ok(self.context.levels().save_modified_levels())
+    }
+}
+
+/// Saves the NPCs whose state changed
+pub struct NpcSaveAutosave {
+    /// Shared server state (holds the NPC saves)
+    context: Arc<ServerContext>,
+}
+
+impl NpcSaveAutosave {
+    /// Create a new NPC save autosave target
+    pub fn new(context: Arc<ServerContext>) -> Self {
+        Self { context }
+    }
+}
+
+impl AutosaveTarget for NpcSaveAutosave {
+    fn name(&self) -> &str {
+        "npcs"
+    }
+
+    fn save(&self) -> Result<usize> {
+        self.context.npc_saves().save_dirty()
+    }
+}
+
+/// Saves the login IP history when logins were recorded
+pub struct IpHistoryAutosave {
+    /// Shared server state (holds the IP history)
+    context: Arc<ServerContext>,
+}
+
+impl IpHistoryAutosave {
+    /// Create a new IP history autosave target
+    pub fn new(context: Arc<ServerContext>) -> Self {
+        Self { context }
+    }
+}
+
+impl AutosaveTarget for IpHistoryAutosave {
+    fn name(&self) -> &str {
+        "iphistory"
+    }
+
+    fn save(&self) -> Result<usize> {
+        self.context.ip_history().save_dirty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_server_flags_saved_only_when_changed() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let context = Arc::new(ServerContext::new(temp_dir.path(), Default::default()));
+        let target = ServerFlagsAutosave::new(context.clone());
+
+        assert_eq!(target.save().unwrap(), 0);
+
+        context.config().write().server_flags.push("server.event=1".to_string());
+        assert_eq!(target.save().unwrap(), 1);
+        assert_eq!(target.save().unwrap(), 0);
+
+        let saved = fs::read_to_string(temp_dir.path().join("serverflags.txt")).unwrap();
+        assert_eq!(saved, "server.event=1\r\n");
+    }
+}
@@ -0,0 +1,155 @@
+//! # Outbound Backpressure
+//!
+//! A client that stops reading leaves its packets in the server's outbound
+//! queue, and every broadcast from other players adds to it. Each connection
+//! caps its queued bytes:
+//!
+//! - Above `outboundsoftlimit`, packets that are cosmetic or superseded by a
+//!   later one ([`is_droppable`]) are dropped instead of queued.
+//! - At `outboundhardlimit` the queue is cleared and the client is
+//!   disconnected ([`SendError::QueueOverflow`]).
+//! - A bundle write that blocks for `outboundstalltimeout` seconds also
+//!   disconnects the client ([`SendError::Stalled`]).
+//!
+//! Only normal packets count: file packets are queued in answer to the
+//! client's own requests and are already bounded by the file size limit.
+
+use crate::error::SendError;
+use gserver_config::ServerConfig;
+use gserver_protocol::PacketTypeOut;
+use std::time::Duration;
+
+/// Packets that can be dropped for a backed-up client
+///
+/// NPC moves are superseded by the next one, world time comes with the next
+/// keepalive; chat and effects only matter when they're fresh. Player props
+/// are only sent as changes, so they're never dropped.
+const DROPPABLE: &[PacketTypeOut] = &[
+    PacketTypeOut::ToAll,
+    PacketTypeOut::NpcMoved,
+    PacketTypeOut::ArrowAdd,
+    PacketTypeOut::Firespy,
+    PacketTypeOut::Explosion,
+    PacketTypeOut::HitObjects,
+    PacketTypeOut::NewWorldTime,
+];
+
+/// Check if a packet may be dropped when the client is backed up
+#[inline]
+pub fn is_droppable(packet_type: PacketTypeOut) -> bool {
+    DROPPABLE.contains(&packet_type)
+}
+
+/// Outbound limits from the server options
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackpressurePolicy {
+    /// Queued bytes above which droppable packets are dropped
+    pub soft_limit: usize,
+
+    /// Queued bytes at which the client is disconnected
+    pub hard_limit: usize,
+
+    /// Longest a bundle write may block
+    pub stall_timeout: Option<Duration>,
+}
+
+impl BackpressurePolicy {
+    /// Build the policy from the server options (0 seconds disables the stall timeout)
+    pub fn from_config(config: &ServerConfig) -> Self {
+        Self {
+            soft_limit: config.outbound_soft_limit,
+            hard_limit: config.outbound_hard_limit.max(config.outbound_soft_limit),
+            stall_timeout: (config.outbound_stall_timeout > 0)
+                .then(|| Duration::from_secs(config.outbound_stall_timeout)),
+        }
+    }
+
+    /// Decide what to do with a packet
+    ///
+    /// # Arguments
+    /// * `queued` - Normal bytes already queued
+    /// * `len` - Length of the packet
+    /// * `packet_type` - Type of the packet
+    pub fn admit(&self, queued: usize, len: usize, packet_type: PacketTypeOut) -> Admission {
+        if queued + len >= self.hard_limit {
+            Admission::Overflow(SendError::QueueOverflow { queued: queued + len, limit: self.hard_limit })
+        } else if queued > self.soft_limit && is_droppable(packet_type) {
+            Admission::Drop
+        } else {
+            Admission::Queue
+        }
+    }
+}
+
+/// Result of [`BackpressurePolicy::admit`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Admission {
+    /// Queue the packet
+    Queue,
+
+    /// Drop the packet, the client is backed up
+    Drop,
+
+    /// Disconnect the client
+    Overflow(SendError),
+}
+
+/// Outbound queue metrics of one connection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueMetrics {
+    /// Normal bytes waiting to be sent
+    pub queued_bytes: usize,
+
+    /// Largest `queued_bytes` seen
+    pub peak_bytes: usize,
+
+    /// Packets dropped above the soft limit
+    pub dropped_packets: u64,
+
+    /// The client is being disconnected for backpressure; nothing more is
+    /// queued or written
+    pub disconnecting: bool,
+}
+
+impl QueueMetrics {
+    /// Record the current queue size
+    pub fn set_queued(&mut self, bytes: usize) {
+        self.queued_bytes = bytes;
+        self.peak_bytes = self.peak_bytes.max(bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_admit() {
+        let policy = BackpressurePolicy { soft_limit: 1000, hard_limit: 4000, stall_timeout: None };
+        let moved = PacketTypeOut::NpcMoved;
+        let warp = PacketTypeOut::PlayerWarp;
+
+        assert_eq!(policy.admit(0, 100, moved), Admission::Queue);
+        assert_eq!(policy.admit(1000, 100, moved), Admission::Queue);
+        assert_eq!(policy.admit(1001, 100, moved), Admission::Drop);
+        assert_eq!(policy.admit(1001, 100, warp), Admission::Queue);
+        assert_eq!(policy.admit(1001, 100, PacketTypeOut::OtherPlayerProps), Admission::Queue);
+        assert_eq!(policy.admit(3900, 100, warp),
+            Admission::Overflow(SendError::QueueOverflow { queued: 4000, limit: 4000 }));
+    }
+
+    #[test]
+    fn test_policy_from_config() {
+        let config = ServerConfig { outbound_soft_limit: 5000, outbound_hard_limit: 100, outbound_stall_timeout: 0, ..Default::default() };
+        let policy = BackpressurePolicy::from_config(&config);
+        assert_eq!((policy.soft_limit, policy.hard_limit, policy.stall_timeout), (5000, 5000, None));
+
+        let policy = BackpressurePolicy::from_config(&ServerConfig::default());
+        assert_eq!(policy.stall_timeout, Some(Duration::from_secs(30)));
+
+        let mut metrics = QueueMetrics::default();
+        metrics.set_queued(300);
+        metrics.set_queued(100);
+        assert_eq!((metrics.queued_bytes, metrics.peak_bytes), (100, 300));
+    }
+}
@@ -0,0 +1,153 @@
+//! # Bandwidth Shaping
+//!
+//! Token buckets limit the bytes sent per second, separately for gameplay
+//! (normal packets) and file transfers, per connection and for the whole
+//! server, so a few big downloads can't starve everyone's gameplay:
+//!
+//! ```text
+//! bandwidthlimit = 64            # KB/s of gameplay per connection
+//! filebandwidthlimit = 32        # KB/s of file transfers per connection
+//! serverbandwidthlimit = 4096    # KB/s of gameplay for all connections
+//! serverfilebandwidthlimit = 1024
+//! ```
+//!
+//! 0 (the default) doesn't limit. A bucket holds at most one second of its
+//! rate. A bundle takes packets while its buckets have tokens and then pays
+//! for what it sent, which may leave a bucket in debt until it refills. Held
+//! packets wait in the queue for the next flush, under the usual
+//! backpressure limits.
+
+use gserver_config::ServerConfig;
+use parking_lot::Mutex;
+use std::time::Instant;
+
+/// Rates of one side, bytes per second (0 = unlimited)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rates {
+    pub gameplay: u64,
+    pub files: u64,
+}
+
+impl Rates {
+    /// Check if neither kind is limited
+    pub fn is_unlimited(&self) -> bool {
+        self.gameplay == 0 && self.files == 0
+    }
+}
+
+/// Bandwidth limits from the server options
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BandwidthLimits {
+    /// Limits of each connection
+    pub connection: Rates,
+
+    /// Limits of all connections together
+    pub server: Rates,
+}
+
+impl BandwidthLimits {
+    /// Read the limits (KB/s in the options)
+    pub fn from_config(config: &ServerConfig) -> Self {
+        Self {
+            connection: Rates { gameplay: config.bandwidth_limit * 1024, files: config.file_bandwidth_limit * 1024 },
+            server: Rates { gameplay: config.server_bandwidth_limit * 1024, files: config.server_file_bandwidth_limit * 1024 },
+        }
+    }
+
+    /// Check if nothing is limited
+    pub fn is_unlimited(&self) -> bool {
+        self.connection.is_unlimited() && self.server.is_unlimited()
+    }
+}
+
+/// A token bucket, refilled at the rate it's asked with
+#[derive(Debug, Default)]
+pub struct TokenBucket {
+    /// Bytes that may be sent, negative after an overshoot
+    tokens: f64,
+    last_refill: Option<Instant>,
+}
+
+impl TokenBucket {
+    /// Create a full bucket
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the bytes that may be sent now
+    ///
+    /// # Arguments
+    /// * `rate` - Bytes per second, 0 for no limit
+    pub fn available(&mut self, rate: u64, now: Instant) -> usize {
+        if rate == 0 {
+            return usize::MAX;
+        }
+        let rate = rate as f64;
+        self.tokens = match self.last_refill {
+            Some(last) => (self.tokens + now.saturating_duration_since(last).as_secs_f64() * rate).min(rate),
+            None => rate,
+        };
+        self.last_refill = Some(now);
+        self.tokens.max(0.0) as usize
+    }
+
+    /// Pay for bytes sent
+    pub fn consume(&mut self, bytes: usize) {
+        self.tokens -= bytes as f64;
+    }
+}
+
+/// Gameplay and file buckets of one connection, or of the server
+#[derive(Debug, Default)]
+pub struct Shaper {
+    gameplay: Mutex<TokenBucket>,
+    files: Mutex<TokenBucket>,
+}
+
+impl Shaper {
+    /// Create full buckets
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the bytes the next bundle may carry, `(gameplay, files)`
+    pub fn available(&self, rates: Rates, now: Instant) -> (usize, usize) {
+        (self.gameplay.lock().available(rates.gameplay, now), self.files.lock().available(rates.files, now))
+    }
+
+    /// Pay for a sent bundle
+    pub fn consume(&self, gameplay: usize, files: usize) {
+        self.gameplay.lock().consume(gameplay);
+        self.files.lock().consume(files);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_token_bucket() {
+        let mut bucket = TokenBucket::new();
+        let now = Instant::now();
+        assert_eq!(bucket.available(0, now), usize::MAX);
+        assert_eq!(bucket.available(1000, now), 1000);
+
+        bucket.consume(1500);
+        assert_eq!(bucket.available(1000, now), 0);
+        assert_eq!(bucket.available(1000, now + Duration::from_millis(1000)), 500);
+        // Never more than one second of tokens
+        assert_eq!(bucket.available(1000, now + Duration::from_secs(60)), 1000);
+    }
+
+    #[test]
+    fn test_limits_from_config() {
+        let config = ServerConfig { bandwidth_limit: 64, server_file_bandwidth_limit: 1024, ..Default::default() };
+        let limits = BandwidthLimits::from_config(&config);
+        assert_eq!(limits.connection, Rates { gameplay: 65536, files: 0 });
+        assert_eq!(limits.server, Rates { gameplay: 0, files: 1024 * 1024 });
+        assert!(!limits.is_unlimited());
+        assert!(BandwidthLimits::from_config(&ServerConfig::default()).is_unlimited());
+    }
+}
@@ -0,0 +1,335 @@
+//! # Staff Comments and Bans
+//!
+//! Staff keep notes on an account (COMMENTS) and ban it with a reason, the
+//! issuing account and an optional expiry, all stored in the account file:
+//!
+//! - PLI_RC_PLAYERCOMMENTSGET/SET - The RC comments dialog (saving needs PLPERM_SETCOMMENTS)
+//! - PLI_RC_PLAYERBANGET/SET - The RC ban dialog (saving needs PLPERM_BAN);
+//!   bans set there last until lifted
+//! - `/ban <account> <minutes> [reason]` - A ban that ends by itself
+//! - `/banpc <account> [reason]`, `/unbanpc <account>` - Ban or lift a ban on
+//!   an account and the PC and hard disk ids it last logged in with
+//!
+//! Edits are [`GameCommand::RecordEdited`] commands, applied to the account's
+//! connections, or to its file if it's offline. Banned players are
+//! disconnected and can't log in until the ban ends; staff still can.
+//!
+//! Identifier bans are kept by the [`BanManager`] in `identitybans.txt`. At
+//! login they join the `bannedhardware` ids checked by
+//! [`BannedHardwarePolicy`](crate::loginpolicy::BannedHardwarePolicy), so they
+//! keep out any non-staff account logging in with a banned id
+//! (see [`crate::identity`]). Ids with whitespace are refused and control
+//! characters in the reason are blanked, so no field can start a new line:
+//!
+//! ```text
+//! {pcid|hddid} {id} {unix seconds the ban ends, 0 = until lifted} {issuer} {reason}
+//! ```
+//!
+//! # Packet Format (PLO_RC_PLAYERCOMMENTSGET, PLI_RC_PLAYERCOMMENTSSET)
+//! ```text
+//! {GSTRING account}{comments}
+//! ```
+//!
+//! # Packet Format (PLO_RC_PLAYERBANGET, PLI_RC_PLAYERBANSET)
+//! ```text
+//! {GSTRING account}{GCHAR banned}{reason}
+//! ```
+//! The GET requests are just `{GSTRING account}`.
+//!
+//! # C++ Equivalence
+//! Matches `PlayerRC::msgPLI_RC_PLAYERCOMMENTSGET` and its siblings; the
+//! issuer and expiry have no C++ counterpart.
+//!
+//! [`GameCommand::RecordEdited`]: gserver_game::GameCommand::RecordEdited
+
+use bytes::{BufMut, BytesMut};
+use gserver_accounts::{Account, AccountLoader};
+use gserver_core::{GServerError, Result};
+use gserver_game::RecordEdit;
+use crate::identity::{is_valid_id, IdentifierKind, Identity};
+use gserver_protocol::codecs::{read_guchar, read_gstring, write_gchar, write_gstring};
+use parking_lot::Mutex;
+use std::path::{Path, PathBuf};
+
+/// Identifier ban file in the server folder
+pub const IDENTITY_BANS_FILE: &str = "identitybans.txt";
+
+/// A ban on a PC or hard disk id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdentityBan {
+    pub kind: IdentifierKind,
+    pub value: String,
+    /// Unix time the ban ends, 0 if it lasts until lifted
+    pub until: u64,
+    pub issuer: String,
+    pub reason: String,
+}
+
+impl IdentityBan {
+    /// Check if the ban is in effect
+    pub fn is_active_at(&self, now: u64) -> bool {
+        self.until == 0 || self.until > now
+    }
+}
+
+/// Bans keyed on login identifiers
+#[derive(Debug, Default)]
+pub struct BanManager {
+    /// Where the bans are saved, `None` to keep them in memory
+    path: Option<PathBuf>,
+    bans: Mutex<Vec<IdentityBan>>,
+}
+
+impl BanManager {
+    /// Create an empty ban list that isn't saved
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the ban file, starting empty if there is none
+    pub fn load(path: &Path) -> Self {
+        let mut bans = Vec::new();
+        if let Ok(content) = std::fs::read_to_string(path) {
+            for line in content.lines() {
+                let mut fields = line.splitn(5, ' ');
+                let (Some(kind), Some(value), Some(until), Some(issuer)) =
+                    (fields.next(), fields.next(), fields.next(), fields.next()) else {
+                    continue;
+                };
+                if !is_valid_id(value) || value.is_empty() {
+                    continue;
+                }
+                if let (Some(kind), Ok(until)) = (IdentifierKind::from_name(kind), until.parse()) {
+                    let reason = fields.next().unwrap_or_default().to_string();
+                    bans.push(IdentityBan { kind, value: value.to_string(), until, issuer: issuer.to_string(), reason });
+                }
+            }
+        }
+        Self { path: Some(path.to_path_buf()), bans: Mutex::new(bans) }
+    }
+
+    /// Ban an identifier, replacing an earlier ban of it
+    ///
+    /// # Returns
+    /// `false` if the id is empty or has whitespace, and wasn't banned
+    pub fn ban(&self, ban: IdentityBan) -> bool {
+        if ban.value.is_empty() || !is_valid_id(&ban.value) {
+            return false;
+        }
+        {
+            let mut bans = self.bans.lock();
+            bans.retain(|b| !(b.kind == ban.kind && b.value.eq_ignore_ascii_case(&ban.value)));
+            bans.push(ban);
+        }
+        self.save();
+        true
+    }
+
+    /// Lift the bans of an identity's identifiers
+    ///
+    /// # Returns
+    /// The number of bans lifted
+    pub fn lift(&self, identity: &Identity) -> usize {
+        let identifiers = identity.identifiers();
+        let lifted = {
+            let mut bans = self.bans.lock();
+            let before = bans.len();
+            bans.retain(|b| !identifiers.iter().any(|(kind, value)| b.kind == *kind && b.value.eq_ignore_ascii_case(value)));
+            before - bans.len()
+        };
+        if lifted > 0 {
+            self.save();
+        }
+        lifted
+    }
+
+    /// Find an active ban on one of an identity's identifiers
+    pub fn find(&self, identity: &Identity, now: u64) -> Option<IdentityBan> {
+        let identifiers = identity.identifiers();
+        self.bans.lock().iter()
+            .find(|b| b.is_active_at(now) && identifiers.iter().any(|(kind, value)| b.kind == *kind && b.value.eq_ignore_ascii_case(value)))
+            .cloned()
+    }
+
+    /// Get the banned ids, for the `bannedhardware` login check
+    pub fn banned_ids(&self, now: u64) -> Vec<String> {
+        self.bans.lock().iter().filter(|b| b.is_active_at(now)).map(|b| b.value.clone()).collect()
+    }
+
+    /// Get the active bans
+    pub fn active(&self, now: u64) -> Vec<IdentityBan> {
+        self.bans.lock().iter().filter(|b| b.is_active_at(now)).cloned().collect()
+    }
+
+    /// Write the ban file, leaving out ended bans
+    ///
+    /// Failures are logged; the bans keep working in memory.
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let now = gserver_game::moderation::unix_now();
+        let content: String = self.active(now).iter()
+            .map(|b| format!("{} {} {} {} {}\n", b.kind.name(), b.value, b.until,
+                b.issuer.replace(|c: char| !c.is_ascii_graphic(), "_"), b.reason.replace(char::is_control, " ")))
+            .collect();
+        if let Err(e) = std::fs::write(path, content) {
+            tracing::warn!("Failed to save {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Apply a comments or ban change to an account
+pub fn apply(account: &mut Account, edit: &RecordEdit) {
+    match edit {
+        RecordEdit::Comments(comments) => account.comments = comments.clone(),
+        RecordEdit::Ban { banned, reason, issuer, until } => {
+            account.banned = *banned as u32;
+            account.ban_reason = reason.clone();
+            account.ban_issuer = if *banned { issuer.clone() } else { String::new() };
+            account.banned_until = if *banned { *until } else { 0 };
+        }
+        RecordEdit::Gralats { amount, .. } => account.gralats = (*amount).min(crate::shops::MAX_GRALATS),
+    }
+}
+
+/// Apply a record change to the file of an account that isn't online
+///
+/// # Returns
+/// The account as it was before the change
+pub fn edit_account_file(server_dir: &Path, account: &str, edit: &RecordEdit) -> Result<Account> {
+    let loader = AccountLoader::new(server_dir);
+    if !loader.exists(account) {
+        return Err(GServerError::NotFound(format!("account {}", account)));
+    }
+    let mut loaded = loader.load(account)
+        .map_err(|e| GServerError::InvalidData(format!("Failed to load account {}: {}", account, e)))?;
+    let before = loaded.clone();
+    apply(&mut loaded, edit);
+    loader.save(&loaded)
+        .map_err(|e| GServerError::InvalidData(format!("Failed to save account {}: {}", account, e)))?;
+    Ok(before)
+}
+
+/// Parse a PLI_RC_PLAYERCOMMENTSGET or PLI_RC_PLAYERBANGET body
+pub fn parse_request(data: &[u8]) -> Result<String> {
+    read_gstring(&mut BytesMut::from(data))
+}
+
+/// Parse a PLI_RC_PLAYERCOMMENTSSET body
+///
+/// # Returns
+/// The account and its new comments
+pub fn parse_comments_set(data: &[u8]) -> Result<(String, String)> {
+    let mut buf = BytesMut::from(data);
+    let account = read_gstring(&mut buf)?;
+    Ok((account, String::from_utf8_lossy(&buf).into_owned()))
+}
+
+/// Parse a PLI_RC_PLAYERBANSET body
+///
+/// # Returns
+/// The account, whether it's banned and the reason
+pub fn parse_ban_set(data: &[u8]) -> Result<(String, bool, String)> {
+    let mut buf = BytesMut::from(data);
+    let account = read_gstring(&mut buf)?;
+    let banned = read_guchar(&mut buf)? != 0;
+    Ok((account, banned, String::from_utf8_lossy(&buf).into_owned()))
+}
+
+/// Build a PLO_RC_PLAYERCOMMENTSGET body
+pub fn comments_data(account: &Account) -> Vec<u8> {
+    let mut data = BytesMut::new();
+    write_gstring(&mut data, &account.name);
+    data.put_slice(account.comments.as_bytes());
+    data.to_vec()
+}
+
+/// Build a PLO_RC_PLAYERBANGET body
+pub fn ban_data(account: &Account, now: u64) -> Vec<u8> {
+    let mut data = BytesMut::new();
+    write_gstring(&mut data, &account.name);
+    write_gchar(&mut data, account.is_banned_at(now) as i8);
+    data.put_slice(account.ban_reason.as_bytes());
+    data.to_vec()
+}
+
+/// Describe an account's ban for RC
+pub fn describe(account: &Account, now: u64) -> String {
+    if !account.is_banned_at(now) {
+        return format!("{} is not banned", account.name);
+    }
+    let issuer = if account.ban_issuer.is_empty() { "unknown".to_string() } else { account.ban_issuer.clone() };
+    let length = match account.banned_until {
+        0 => "until lifted".to_string(),
+        until => format!("for {} more minutes", (until - now).div_ceil(60)),
+    };
+    let reason = if account.ban_reason.is_empty() { "no reason given" } else { &account.ban_reason };
+    format!("{} is banned by {} {}: {}", account.name, issuer, length, reason)
+}
+
+/// Get the disconnect message of a banned player
+pub fn ban_message(reason: &str) -> String {
+    if reason.is_empty() {
+        "You are banned from this server.".to_string()
+    } else {
+        format!("You are banned from this server. Reason: {}", reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ban_record() {
+        let mut account = Account { name: "Griefer".into(), ..Default::default() };
+        assert_eq!(describe(&account, 100), "Griefer is not banned");
+
+        let ban = RecordEdit::Ban { banned: true, reason: "spam".into(), issuer: "Manager".into(), until: 100 + 90 };
+        apply(&mut account, &ban);
+        assert_eq!(describe(&account, 100), "Griefer is banned by Manager for 2 more minutes: spam");
+        assert_eq!(&ban_data(&account, 100)[8..], b"!spam");
+        assert!(!account.is_banned_at(190));
+
+        apply(&mut account, &RecordEdit::Ban { banned: false, reason: String::new(), issuer: "Manager".into(), until: 0 });
+        assert!(!account.is_banned_at(0) && account.ban_issuer.is_empty());
+    }
+
+    #[test]
+    fn test_identity_bans() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(IDENTITY_BANS_FILE);
+        let identity = Identity::parse("win,\"\",02e2465a,54e8d93b");
+
+        let manager = BanManager::load(&path);
+        let ban = |value: &str, until| IdentityBan {
+            kind: IdentifierKind::HddId, value: value.into(), until, issuer: "Manager".into(), reason: "ban evasion".into(),
+        };
+        assert!(manager.ban(ban("54E8D93B", 0)));
+        assert!(manager.ban(ban("other", 100)));
+        assert!(!manager.ban(ban("x\npcid 02e2465a", 0)));
+        manager.ban(IdentityBan { reason: "evasion\npcid 02e2465a 0 Manager".into(), ..ban("later", 0) });
+        assert_eq!(manager.banned_ids(200), ["54E8D93B", "later"]);
+        assert_eq!(manager.find(&identity, 200).map(|b| b.reason), Some("ban evasion".to_string()));
+
+        let reloaded = BanManager::load(&path);
+        assert_eq!(reloaded.active(200).len(), 2);
+        assert_eq!(reloaded.lift(&identity), 1);
+        assert_eq!(reloaded.find(&identity, 200), None);
+    }
+
+    #[test]
+    fn test_packets() {
+        let mut data = BytesMut::new();
+        write_gstring(&mut data, "Griefer");
+        data.put_u8(32 + 1);
+        data.put_slice(b"being rude");
+        assert_eq!(parse_ban_set(&data).unwrap(), ("Griefer".to_string(), true, "being rude".to_string()));
+        assert_eq!(parse_request(&data[..8]).unwrap(), "Griefer");
+
+        let account = Account { name: "Griefer".into(), comments: "warned twice".into(), ..Default::default() };
+        let (name, comments) = parse_comments_set(&comments_data(&account)).unwrap();
+        assert_eq!((name.as_str(), comments.as_str()), ("Griefer", "warned twice"));
+    }
+}
@@ -0,0 +1,298 @@
+//! # Player Chat Commands
+//!
+//! Chat starting with `/` is looked up in the [`ChatCommandRegistry`] before
+//! it becomes the player's chat bubble. A known command is run and the
+//! bubble cleared; anything else stays plain chat. A new command is one
+//! [`ChatCommandRegistry::register`] call, e.g. from a plugin at startup.
+//!
+//! Handlers don't touch the connection; they return [`ChatAction`]s that the
+//! connection carries out (with the word filter for PMs and the jail for
+//! warps).
+//!
+//! # Built-in Commands
+//! - `/who` - Players online
+//! - `/pm <account> <message>` - PM every client logged in on an account
+//! - `/unstick` - Warp to `unstickmelevel` (the start location if unset);
+//!   the Graal chat `unstick me` is the same command
+//! - `/warp <level> [x y]` - Warp to a level (staff only by default)
+//! - `/sethome` - Respawn here from now on (see [`crate::respawn`]); not on
+//!   `nohome` levels
+//!
+//! # Settings
+//! `chatcommandpermissions = warp=staff, who=off` and
+//! `chatcommandcooldowns = unstick=30` apply to every command, built-in or
+//! registered. They're read on each use, so a config reload applies at once.
+//! Turned off commands are plain chat.
+
+use crate::moderation::Destination;
+use crate::ServerContext;
+use dashmap::DashMap;
+use gserver_config::ChatCommandPermission;
+use gserver_core::PlayerID;
+use gserver_game::PlayerType;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Chat text that means `/unstick`
+const UNSTICK_ME: &str = "unstick me";
+
+/// Most players listed by `/who`
+const WHO_LIMIT: usize = 50;
+
+/// One use of a chat command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatCommand {
+    /// Player who typed it
+    pub player: PlayerID,
+
+    /// Their account
+    pub account: String,
+
+    /// Whether they're staff
+    pub is_staff: bool,
+
+    /// Command name, lowercase and without the `/`
+    pub name: String,
+
+    /// Words after the name
+    pub args: Vec<String>,
+
+    /// Text after the name, as typed
+    pub rest: String,
+}
+
+impl ChatCommand {
+    /// Parse a chat text
+    ///
+    /// # Returns
+    /// The command, or `None` if the text isn't one
+    pub fn parse(player: PlayerID, account: &str, is_staff: bool, text: &str) -> Option<Self> {
+        let text = text.trim();
+        let text = match text.eq_ignore_ascii_case(UNSTICK_ME) {
+            true => "unstick",
+            false => text.strip_prefix('/')?,
+        };
+        let (name, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        if name.is_empty() {
+            return None;
+        }
+        Some(Self {
+            player,
+            account: account.to_string(),
+            is_staff,
+            name: name.to_lowercase(),
+            args: rest.split_whitespace().map(String::from).collect(),
+            rest: rest.trim().to_string(),
+        })
+    }
+}
+
+/// What a command wants done for the player
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatAction {
+    /// Tell the player something (admin message popup)
+    Reply(String),
+
+    /// Warp the player
+    Warp(Destination),
+
+    /// Send a PM from the player
+    PrivateMessage { to: Vec<PlayerID>, message: String },
+
+    /// Make the player's position their home
+    SetHome,
+}
+
+/// Handler of one command
+pub type ChatHandler = Arc<dyn Fn(&ChatCommand, &ServerContext) -> Vec<ChatAction> + Send + Sync>;
+
+/// Player chat commands by name, with the cooldowns of each player
+pub struct ChatCommandRegistry {
+    handlers: RwLock<HashMap<String, ChatHandler>>,
+
+    /// (player, command) → last use
+    last_used: DashMap<(PlayerID, String), Instant>,
+}
+
+impl ChatCommandRegistry {
+    /// Create a registry with the built-in commands
+    pub fn new() -> Self {
+        let registry = Self::empty();
+        registry.register("who", who);
+        registry.register("pm", pm);
+        registry.register("unstick", unstick);
+        registry.register("warp", warp);
+        registry.register("sethome", |_, _| vec![ChatAction::SetHome]);
+        registry
+    }
+
+    /// Create a registry without any commands
+    pub fn empty() -> Self {
+        Self { handlers: RwLock::new(HashMap::new()), last_used: DashMap::new() }
+    }
+
+    /// Add or replace a command (names are case-insensitive, without the `/`)
+    pub fn register<F>(&self, name: &str, handler: F)
+    where
+        F: Fn(&ChatCommand, &ServerContext) -> Vec<ChatAction> + Send + Sync + 'static,
+    {
+        self.handlers.write().insert(name.to_lowercase(), Arc::new(handler));
+    }
+
+    /// Check if a command is registered
+    pub fn has_command(&self, name: &str) -> bool {
+        self.handlers.read().contains_key(&name.to_lowercase())
+    }
+
+    /// Run a command, checking its permission and cooldown
+    ///
+    /// # Returns
+    /// What to do for the player, or `None` if the command is unknown or off
+    /// (the text is plain chat then)
+    pub fn dispatch(&self, command: &ChatCommand, context: &ServerContext, now: Instant) -> Option<Vec<ChatAction>> {
+        let handler = self.handlers.read().get(&command.name)?.clone();
+        let (permission, cooldown) = {
+            let config = context.config().read();
+            (config.chat_commands.permission(&command.name), config.chat_commands.cooldown(&command.name))
+        };
+        match permission {
+            ChatCommandPermission::Off => return None,
+            ChatCommandPermission::Staff if !command.is_staff => {
+                return Some(vec![ChatAction::Reply(format!("Only staff can use /{}.", command.name))]);
+            }
+            _ => {}
+        }
+
+        let key = (command.player, command.name.clone());
+        if let Some(last) = self.last_used.get(&key) {
+            let wait = Duration::from_secs(cooldown).saturating_sub(now.duration_since(*last));
+            if !wait.is_zero() {
+                return Some(vec![ChatAction::Reply(
+                    format!("Wait {}s before using /{} again.", wait.as_secs().max(1), command.name))]);
+            }
+        }
+        if cooldown > 0 {
+            self.last_used.insert(key, now);
+        }
+        Some(handler(command, context))
+    }
+
+    /// Forget the cooldowns of a player who left
+    pub fn forget(&self, player: PlayerID) {
+        self.last_used.retain(|(id, _), _| *id != player);
+    }
+}
+
+impl Default for ChatCommandRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for ChatCommandRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut names: Vec<String> = self.handlers.read().keys().cloned().collect();
+        names.sort();
+        f.debug_struct("ChatCommandRegistry").field("commands", &names).finish()
+    }
+}
+
+/// `/who`
+fn who(_: &ChatCommand, context: &ServerContext) -> Vec<ChatAction> {
+    let snapshot = context.players().snapshot();
+    let names: Vec<String> = snapshot.iter()
+        .filter(|p| p.player_type == PlayerType::Player)
+        .map(|p| {
+            let props = p.properties.lock();
+            if props.nickname.is_empty() { props.account_name.clone() } else { props.nickname.clone() }
+        })
+        .collect();
+    let mut listed = names.iter().take(WHO_LIMIT).cloned().collect::<Vec<_>>().join(", ");
+    if names.len() > WHO_LIMIT {
+        listed.push_str(&format!(" and {} more", names.len() - WHO_LIMIT));
+    }
+    vec![ChatAction::Reply(format!("Online ({}): {}", names.len(), listed))]
+}
+
+/// `/pm <account> <message>`
+fn pm(command: &ChatCommand, context: &ServerContext) -> Vec<ChatAction> {
+    let (Some(account), Some((_, message))) = (command.args.first(), command.rest.split_once(char::is_whitespace)) else {
+        return vec![ChatAction::Reply("Usage: /pm <account> <message>".into())];
+    };
+    let to: Vec<PlayerID> = context.players().get_by_account(account).iter()
+        .filter(|p| p.player_type == PlayerType::Player && p.id != command.player)
+        .map(|p| p.id)
+        .collect();
+    if to.is_empty() {
+        return vec![ChatAction::Reply(format!("{} is not online.", account))];
+    }
+    vec![ChatAction::PrivateMessage { to, message: message.trim().to_string() }]
+}
+
+/// `/unstick`
+fn unstick(_: &ChatCommand, context: &ServerContext) -> Vec<ChatAction> {
+    let config = context.config().read();
+    let settings = &config.chat_commands;
+    let to = match settings.unstick_level.is_empty() {
+        true => Destination::home(&config),
+        false => Destination { level: settings.unstick_level.clone(), x: settings.unstick_x, y: settings.unstick_y },
+    };
+    vec![ChatAction::Warp(to)]
+}
+
+/// `/warp <level> [x y]`
+fn warp(command: &ChatCommand, _: &ServerContext) -> Vec<ChatAction> {
+    let Some(level) = command.args.first() else {
+        return vec![ChatAction::Reply("Usage: /warp <level> [x y]".into())];
+    };
+    let coord = |i: usize, default: f32| command.args.get(i).and_then(|v| v.parse().ok()).unwrap_or(default);
+    vec![ChatAction::Warp(Destination { level: level.clone(), x: coord(1, 30.0), y: coord(2, 30.0) })]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gserver_config::ServerConfig as GameConfig;
+
+    fn command(text: &str, is_staff: bool) -> ChatCommand {
+        ChatCommand::parse(PlayerID::new(1), "Bob", is_staff, text).unwrap()
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        let parsed = command("  /PM alice  hello there ", false);
+        assert_eq!((parsed.name.as_str(), parsed.rest.as_str()), ("pm", "alice  hello there"));
+        assert_eq!(parsed.args, ["alice", "hello", "there"]);
+        assert_eq!(ChatCommand::parse(PlayerID::new(1), "Bob", false, "hello /who"), None);
+        assert_eq!(ChatCommand::parse(PlayerID::new(1), "Bob", false, "/"), None);
+        assert_eq!(command("Unstick Me", false).name, "unstick");
+    }
+
+    #[test]
+    fn test_dispatch_permissions_and_cooldowns() {
+        let mut config = GameConfig::default();
+        config.chat_commands.permissions.push(("who".into(), ChatCommandPermission::Off));
+        let context = ServerContext::new("servers/test", config);
+        let registry = ChatCommandRegistry::new();
+        let now = Instant::now();
+
+        assert_eq!(registry.dispatch(&command("/who", false), &context, now), None);
+        assert_eq!(registry.dispatch(&command("/dance", false), &context, now), None);
+        assert_eq!(registry.dispatch(&command("/warp jail.nw", false), &context, now),
+            Some(vec![ChatAction::Reply("Only staff can use /warp.".into())]));
+        assert_eq!(registry.dispatch(&command("/warp jail.nw 12 x", true), &context, now),
+            Some(vec![ChatAction::Warp(Destination { level: "jail.nw".into(), x: 12.0, y: 30.0 })]));
+
+        let warped = registry.dispatch(&command("/unstick", false), &context, now).unwrap();
+        assert!(matches!(warped.as_slice(), [ChatAction::Warp(_)]));
+        assert_eq!(registry.dispatch(&command("/unstick", false), &context, now + Duration::from_secs(10)),
+            Some(vec![ChatAction::Reply("Wait 20s before using /unstick again.".into())]));
+        assert_eq!(registry.dispatch(&command("/unstick", false), &context, now + Duration::from_secs(30)), Some(warped));
+
+        registry.register("Dance", |c, _| vec![ChatAction::Reply(format!("{} dances", c.account))]);
+        assert_eq!(registry.dispatch(&command("/dance", false), &context, now),
+            Some(vec![ChatAction::Reply("Bob dances".into())]));
+    }
+}
@@ -0,0 +1,299 @@
+//! # Chat Logs
+//!
+//! With `chatlog = true`, chat is written to `logs/chat/`, one folder per UTC
+//! day and one file per channel:
+//!
+//! - `level-<level>.log` - PLI_TOALL chat on each level
+//! - `pm.log` - PMs, if `chatlogpms` is `anonymized` or `full`
+//! - `rc.log` - RC chat and `/msg`, unless `chatlogrc = false`
+//!
+//! Day folders older than `chatlogretention` days are deleted when a new day
+//! starts. Anonymized PMs name sender and recipients by pseudonyms that change
+//! on every restart, so they can't be linked to accounts over time.
+//!
+//! RC `/chatlog <account> [span]` searches the logs for an account's lines.
+//!
+//! # File Format
+//! ```text
+//! {YYYYMMDD-HHMMSS UTC}\t{account}\t{message}
+//! ```
+//! PM and `/msg` lines start the message with `-> {recipients}: `.
+
+use crate::context::ServerContext;
+use gserver_config::{PmLogging, PrivacyConfig};
+use gserver_core::PlayerID;
+use gserver_game::GameEvent;
+use gserver_storage::format_timestamp;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::watch;
+
+/// Chat log folder in the server folder
+pub const CHAT_LOG_DIR: &str = "logs/chat";
+
+/// Most lines a search returns (the newest ones)
+pub const SEARCH_LIMIT: usize = 50;
+
+/// One logged line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatLine {
+    /// `YYYYMMDD-HHMMSS` UTC
+    pub stamp: String,
+
+    /// File name without `.log`
+    pub channel: String,
+
+    pub account: String,
+
+    pub message: String,
+}
+
+/// Day-partitioned chat log files
+#[derive(Debug)]
+pub struct ChatLog {
+    dir: PathBuf,
+
+    /// Day (`YYYYMMDD`) and its open files by channel
+    open: Mutex<(String, HashMap<String, File>)>,
+
+    /// Key of the PM pseudonyms
+    salt: u64,
+}
+
+impl ChatLog {
+    /// Create the log; folders are made by the first write
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), open: Mutex::new((String::new(), HashMap::new())), salt: rand::random() }
+    }
+
+    /// Write the chat of an event, if the privacy options log it
+    ///
+    /// # Arguments
+    /// * `accounts` - Account of an online player
+    /// * `now` - Unix seconds
+    pub fn record(&self, event: &GameEvent, privacy: &PrivacyConfig, accounts: impl Fn(PlayerID) -> String, now: u64) -> io::Result<()> {
+        if !privacy.chat_log {
+            return Ok(());
+        }
+        match event {
+            GameEvent::ChatMessage { id, level, message } => {
+                self.write(&format!("level-{}", level), &accounts(*id), message, privacy.chat_log_retention, now)
+            }
+            GameEvent::PrivateMessage { account, to, data, .. } if privacy.chat_log_pms != PmLogging::Off => {
+                let Some(message) = crate::social::pm_message(data) else { return Ok(()) };
+                let name = |account: &str| match privacy.chat_log_pms {
+                    PmLogging::Anonymized => self.pseudonym(account),
+                    _ => account.to_string(),
+                };
+                let recipients: Vec<String> = to.iter().map(|&id| name(&accounts(id))).collect();
+                let message = format!("-> {}: {}", recipients.join(", "), message);
+                self.write("pm", &name(account), &message, privacy.chat_log_retention, now)
+            }
+            GameEvent::RcChat { account, message, to, .. } if privacy.chat_log_rc => {
+                let message = match to {
+                    Some(to) => format!("-> {}: {}", to, message),
+                    None => message.clone(),
+                };
+                self.write("rc", account, &message, privacy.chat_log_retention, now)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Append a line to a channel, starting a new day folder when the day changed
+    fn write(&self, channel: &str, account: &str, message: &str, retention_days: u64, now: u64) -> io::Result<()> {
+        let stamp = format_timestamp(now);
+        let day = &stamp[..8];
+        let channel: String = channel.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || "._-".contains(c) { c } else { '_' })
+            .collect();
+
+        let mut open = self.open.lock();
+        if open.0 != day {
+            *open = (day.to_string(), HashMap::new());
+            self.prune(retention_days, now);
+        }
+        let file = match open.1.entry(channel) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                let dir = self.dir.join(day);
+                fs::create_dir_all(&dir)?;
+                let file = OpenOptions::new().create(true).append(true).open(dir.join(format!("{}.log", entry.key())))?;
+                entry.insert(file)
+            }
+        };
+        // One line per message, whatever the client put in it
+        let message = message.replace(['\n', '\r'], " ");
+        writeln!(file, "{}\t{}\t{}", stamp, account, message)
+    }
+
+    /// Delete the day folders older than the retention
+    ///
+    /// # Returns
+    /// The number of folders deleted
+    pub fn prune(&self, retention_days: u64, now: u64) -> usize {
+        if retention_days == 0 {
+            return 0;
+        }
+        let cutoff = format_timestamp(now.saturating_sub(retention_days * 86400))[..8].to_string();
+        let Ok(entries) = fs::read_dir(&self.dir) else { return 0 };
+        entries.flatten()
+            .filter(|e| e.file_name().to_str().is_some_and(|day| day.len() == 8 && day < cutoff.as_str()))
+            .filter(|e| fs::remove_dir_all(e.path()).is_ok())
+            .count()
+    }
+
+    /// Find an account's lines since a time, oldest first
+    ///
+    /// At most [`SEARCH_LIMIT`] lines are returned, the newest ones.
+    pub fn search(&self, account: &str, since: u64, now: u64) -> Vec<ChatLine> {
+        let (from, to) = (format_timestamp(since), format_timestamp(now));
+        let Ok(entries) = fs::read_dir(&self.dir) else { return Vec::new() };
+        let mut days: Vec<String> = entries.flatten()
+            .filter_map(|e| e.file_name().into_string().ok())
+            .filter(|day| day.len() == 8 && day.as_str() >= &from[..8] && day.as_str() <= &to[..8])
+            .collect();
+        days.sort();
+
+        let mut found = Vec::new();
+        for day in days {
+            let Ok(files) = fs::read_dir(self.dir.join(&day)) else { continue };
+            for path in files.flatten().map(|e| e.path()) {
+                let Some(channel) = path.file_stem().and_then(|s| s.to_str()).map(String::from) else { continue };
+                let Ok(content) = fs::read_to_string(&path) else { continue };
+                found.extend(content.lines()
+                    .filter_map(|line| {
+                        let mut fields = line.splitn(3, '\t');
+                        let (stamp, author, message) = (fields.next()?, fields.next()?, fields.next()?);
+                        (author.eq_ignore_ascii_case(account) && stamp >= from.as_str()).then(|| ChatLine {
+                            stamp: stamp.to_string(),
+                            channel: channel.clone(),
+                            account: author.to_string(),
+                            message: message.to_string(),
+                        })
+                    }));
+            }
+        }
+        found.sort_by(|a, b| a.stamp.cmp(&b.stamp));
+        let excess = found.len().saturating_sub(SEARCH_LIMIT);
+        found.split_off(excess)
+    }
+
+    /// Get the PM pseudonym of an account (until the next restart)
+    pub fn pseudonym(&self, account: &str) -> String {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        (self.salt, account.to_lowercase()).hash(&mut hasher);
+        format!("anon-{:08x}", hasher.finish() as u32)
+    }
+}
+
+/// Parse a search span like `90s`, `30m`, `2h` or `7d` (plain numbers are minutes)
+pub fn parse_span(text: &str) -> Option<u64> {
+    let (number, unit) = text.find(|c: char| !c.is_ascii_digit()).map_or((text, ""), |at| text.split_at(at));
+    let number: u64 = number.parse().ok()?;
+    let unit = match unit {
+        "s" => 1,
+        "" | "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return None,
+    };
+    number.checked_mul(unit)
+}
+
+/// Format search results for RC
+pub fn describe(account: &str, span: &str, lines: &[ChatLine]) -> String {
+    if lines.is_empty() {
+        return format!("No chat from {} in the last {}", account, span);
+    }
+    let shown: Vec<String> = lines.iter()
+        .map(|l| format!("[{} {}] {}", l.stamp, l.channel, l.message))
+        .collect();
+    format!("Chat from {} in the last {} ({} lines): {}", account, span, lines.len(), shown.join("; "))
+}
+
+/// Log chat events until `shutdown` turns true
+pub async fn run(context: Arc<ServerContext>, mut shutdown: watch::Receiver<bool>) {
+    let mut events = context.events().subscribe();
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    let privacy = context.config().read().privacy.clone();
+                    let accounts = |id: PlayerID| context.players().get_player(id)
+                        .map_or_else(|| format!("#{}", id.get()), |p| p.properties.lock().account_name.clone());
+                    if let Err(e) = context.chat_log().record(&event, &privacy, accounts, gserver_game::moderation::unix_now()) {
+                        tracing::warn!("Chat log write failed: {}", e);
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => tracing::warn!("Chat log skipped {} events", skipped),
+                Err(RecvError::Closed) => break,
+            },
+            _ = shutdown.changed() => {
+                if *shutdown.borrow() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_760_400_000;
+
+    #[test]
+    fn test_record_and_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = ChatLog::new(dir.path());
+        let privacy = PrivacyConfig { chat_log: true, chat_log_pms: PmLogging::Anonymized, ..Default::default() };
+        let accounts = |id: PlayerID| if id == PlayerID(1) { "Bob".to_string() } else { "Alice".to_string() };
+
+        let chat = GameEvent::ChatMessage { id: PlayerID(1), level: "town/inn.nw".into(), message: "hi\nall".into() };
+        log.record(&chat, &privacy, accounts, NOW - 7200).unwrap();
+        log.record(&chat, &privacy, accounts, NOW - 60).unwrap();
+        let pm = GameEvent::PrivateMessage {
+            from: PlayerID(1),
+            account: "Bob".into(),
+            to: vec![PlayerID(2)],
+            data: crate::social::pm_data(PlayerID(1), 1, "secret"),
+        };
+        log.record(&pm, &privacy, accounts, NOW).unwrap();
+        let rc = GameEvent::RcChat { id: PlayerID(3), account: "Manager".into(), message: "ok".into(), to: Some("Bob".into()) };
+        log.record(&rc, &PrivacyConfig { chat_log_rc: false, ..privacy.clone() }, accounts, NOW).unwrap();
+
+        let found = log.search("bob", NOW - 3600, NOW);
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].channel.as_str(), found[0].message.as_str()), ("level-town_inn.nw", "hi all"));
+        assert_eq!(log.search("bob", NOW - 86400, NOW).len(), 2);
+
+        let day = &format_timestamp(NOW)[..8];
+        let pms = fs::read_to_string(dir.path().join(day).join("pm.log")).unwrap();
+        assert!(pms.ends_with(&format!("\t{}\t-> {}: secret\n", log.pseudonym("bob"), log.pseudonym("Alice"))), "{}", pms);
+        assert!(!dir.path().join(day).join("rc.log").exists());
+
+        // A new day removes the folders beyond the retention
+        let old = &format_timestamp(NOW - 40 * 86400)[..8];
+        fs::create_dir_all(dir.path().join(old)).unwrap();
+        log.record(&chat, &privacy, accounts, NOW + 86400).unwrap();
+        assert!(!dir.path().join(old).exists() && dir.path().join(day).exists());
+    }
+
+    #[test]
+    fn test_parse_span() {
+        assert_eq!(parse_span("2h"), Some(7200));
+        assert_eq!(parse_span("90s"), Some(90));
+        assert_eq!(parse_span("15"), Some(900));
+        assert_eq!(parse_span("1d"), Some(86400));
+        assert_eq!(parse_span("2w"), None);
+        assert_eq!(parse_span("h"), None);
+    }
+}
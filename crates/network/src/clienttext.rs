@@ -0,0 +1,207 @@
+//! # Client Text Commands
+//!
+//! Newer clients ask the server for services with PLI_REQUESTTEXT (152) and
+//! PLI_SENDTEXT (154). Both carry a comma token string
+//! `{weapon},{type},{option},{params...}`, e.g. `GraalEngine,lister,simplelist`.
+//! The type and option pick a handler from the [`ClientTextRegistry`]; a
+//! handler registered without an option takes every option of its type. A new
+//! command is one [`ClientTextRegistry::register`] call.
+//!
+//! Handlers don't touch the connection; they return [`TextAction`]s: a
+//! PLO_SERVERTEXT line for the player, or a line for the listserver, whose
+//! answer comes back as SVI_REQUESTTEXT (see [`crate::requesttext`]).
+//!
+//! # Built-in Commands
+//! - `irc,...` - The listserver IRC bridge (`GraalEngine` only, see [`crate::irc`])
+//! - `lister,subscriptions` - Answered by the server: an unlimited subscription
+//! - `lister,simplelist`, `lister,rebornlist` - Asked of the listserver as
+//!   `simpleserverlist` and `rebornlist`
+//! - `lister,...`, `pmservers`, `pmguilds`, `pmserverplayers`, `profile` -
+//!   Forwarded to the listserver as sent
+//!
+//! # C++ Equivalence
+//! Mirrors the switches of `Player::msgPLI_REQUESTTEXT` and
+//! `Player::msgPLI_SENDTEXT`.
+
+use crate::irc::{join_tokens, split_tokens};
+use crate::ServerContext;
+use gserver_core::PlayerID;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// One PLI_REQUESTTEXT or PLI_SENDTEXT
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientText {
+    /// Player who sent it
+    pub player: PlayerID,
+
+    /// Their account
+    pub account: String,
+
+    /// The raw text
+    pub text: String,
+
+    /// The text split into comma tokens
+    pub tokens: Vec<String>,
+}
+
+impl ClientText {
+    /// Parse a packet's text
+    pub fn new(player: PlayerID, account: &str, text: &str) -> Self {
+        Self { player, account: account.to_string(), text: text.to_string(), tokens: split_tokens(text) }
+    }
+
+    /// Get the weapon (or engine) the text is for
+    pub fn weapon(&self) -> &str {
+        self.token(0)
+    }
+
+    /// Get the command type
+    pub fn kind(&self) -> &str {
+        self.token(1)
+    }
+
+    /// Get the command option
+    pub fn option(&self) -> &str {
+        self.token(2)
+    }
+
+    /// Get the tokens after the type
+    pub fn args(&self) -> &[String] {
+        self.tokens.get(2..).unwrap_or_default()
+    }
+
+    fn token(&self, index: usize) -> &str {
+        self.tokens.get(index).map_or("", String::as_str)
+    }
+}
+
+/// What a command wants done
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextAction {
+    /// Send the player a PLO_SERVERTEXT line
+    Reply(String),
+
+    /// Ask the listserver on the player's behalf
+    Forward(String),
+}
+
+/// Handler of one command
+pub type ClientTextHandler = Arc<dyn Fn(&ClientText, &ServerContext) -> Vec<TextAction> + Send + Sync>;
+
+/// Client text commands by type and option
+pub struct ClientTextRegistry {
+    /// (type, option) → handler; an empty option takes every option
+    handlers: RwLock<HashMap<(String, String), ClientTextHandler>>,
+}
+
+impl ClientTextRegistry {
+    /// Create a registry with the built-in commands
+    pub fn new() -> Self {
+        let registry = Self::empty();
+        registry.register("irc", None, irc);
+        registry.register("lister", Some("subscriptions"), |text, _| vec![TextAction::Reply(join_tokens(&[
+            text.weapon(), text.kind(), "subscriptions", "unlimited", "Unlimited Subscription", "",
+        ]))]);
+        for (option, asked) in [("simplelist", "simpleserverlist"), ("rebornlist", "rebornlist")] {
+            registry.register("lister", Some(option), move |text, _| {
+                vec![TextAction::Forward(join_tokens(&[text.weapon(), text.kind(), asked]))]
+            });
+        }
+        for kind in ["lister", "pmservers", "pmguilds", "pmserverplayers", "profile"] {
+            registry.register(kind, None, |text, _| vec![TextAction::Forward(text.text.clone())]);
+        }
+        registry
+    }
+
+    /// Create a registry without any commands
+    pub fn empty() -> Self {
+        Self { handlers: RwLock::new(HashMap::new()) }
+    }
+
+    /// Add or replace a command (names are case-insensitive)
+    ///
+    /// # Arguments
+    /// * `option` - `None` to take every option the type has no handler for
+    pub fn register<F>(&self, kind: &str, option: Option<&str>, handler: F)
+    where
+        F: Fn(&ClientText, &ServerContext) -> Vec<TextAction> + Send + Sync + 'static,
+    {
+        self.handlers.write().insert(Self::key(kind, option.unwrap_or("")), Arc::new(handler));
+    }
+
+    /// Check if a text has a handler
+    pub fn has_handler(&self, kind: &str, option: &str) -> bool {
+        self.handler(kind, option).is_some()
+    }
+
+    /// Run the handler of a text
+    ///
+    /// # Returns
+    /// What to do, or `None` if no handler knows the command
+    pub fn dispatch(&self, text: &ClientText, context: &ServerContext) -> Option<Vec<TextAction>> {
+        let handler = self.handler(text.kind(), text.option())?;
+        Some(handler(text, context))
+    }
+
+    fn handler(&self, kind: &str, option: &str) -> Option<ClientTextHandler> {
+        let handlers = self.handlers.read();
+        handlers.get(&Self::key(kind, option)).or_else(|| handlers.get(&Self::key(kind, ""))).cloned()
+    }
+
+    fn key(kind: &str, option: &str) -> (String, String) {
+        (kind.to_ascii_lowercase(), option.to_ascii_lowercase())
+    }
+}
+
+impl Default for ClientTextRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for ClientTextRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut keys: Vec<(String, String)> = self.handlers.read().keys().cloned().collect();
+        keys.sort();
+        f.debug_struct("ClientTextRegistry").field("commands", &keys).finish()
+    }
+}
+
+/// `GraalEngine,irc,...`
+fn irc(text: &ClientText, context: &ServerContext) -> Vec<TextAction> {
+    if text.weapon() == "GraalEngine" && !context.irc().handle_client(text.player, &text.account, text.args()) {
+        tracing::debug!("Connection {} unknown IRC command: {:?}", text.player.get(), text.args());
+    }
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dispatch_client_texts() {
+        let context = ServerContext::new("servers/test", Default::default());
+        let registry = ClientTextRegistry::new();
+        let text = |s: &str| ClientText::new(PlayerID::new(3), "Bob", s);
+
+        assert_eq!(registry.dispatch(&text("GraalEngine,lister,subscriptions"), &context), Some(vec![
+            TextAction::Reply("GraalEngine,lister,subscriptions,unlimited,\"Unlimited Subscription\",\"\"".into()),
+        ]));
+        assert_eq!(registry.dispatch(&text("-Serverlist,lister,simplelist"), &context), Some(vec![
+            TextAction::Forward("-Serverlist,lister,simpleserverlist".into()),
+        ]));
+        assert_eq!(registry.dispatch(&text("GraalEngine,pmservers,all"), &context), Some(vec![
+            TextAction::Forward("GraalEngine,pmservers,all".into()),
+        ]));
+        assert_eq!(registry.dispatch(&text("GraalEngine,irc,join,#graal"), &context), Some(vec![]));
+        assert_eq!(registry.dispatch(&text("GraalEngine,unknown"), &context), None);
+
+        registry.register("Lister", Some("SimpleList"), |_, _| vec![TextAction::Reply("local".into())]);
+        assert_eq!(registry.dispatch(&text("GraalEngine,lister,simplelist"), &context),
+            Some(vec![TextAction::Reply("local".into())]));
+        assert!(registry.has_handler("lister", "serverinfo"));
+    }
+}
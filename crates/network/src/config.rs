@@ -18,6 +18,7 @@
 //! };
 //! ```
 
+use crate::tls::TlsConfig;
 use std::net::SocketAddr;
 use std::time::Duration;
 
@@ -190,6 +191,16 @@ pub struct ServerConfig {
     /// - Larger buffers = fewer write() syscalls
     /// - But consume more memory per connection
     pub write_buffer_size: usize,
+
+    /// Second listener with TLS, if enabled
+    ///
+    /// # Default
+    /// `None` (plain TCP only)
+    ///
+    /// # Notes
+    /// - Connections on it run the same pipeline once the handshake is done
+    /// - See [`crate::tls`]
+    pub tls: Option<TlsConfig>,
 }
 
 impl Default for ServerConfig {
@@ -206,6 +217,7 @@ impl Default for ServerConfig {
             compression_threshold: 256,
             read_buffer_size: 8192,
             write_buffer_size: 8192,
+            tls: None,
         }
     }
 }
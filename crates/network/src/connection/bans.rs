@@ -0,0 +1,182 @@
+//! # RC Comments and Ban Handlers
+//!
+//! This module answers the RC comments and ban dialogs
+//! (PLI_RC_PLAYERCOMMENTSGET/SET, PLI_RC_PLAYERBANGET/SET) and RC `/ban`,
+//! `/banpc` and `/unbanpc`.
+//! Accounts are read from their files; changes go out as
+//! [`GameCommand::RecordEdited`] so online accounts are changed in memory.
+//! Packets and ban rules are in [`crate::bans`].
+
+use super::PlayerConnection;
+use crate::bans::{ban_data, comments_data, describe, parse_ban_set, parse_comments_set, parse_request, IdentityBan};
+use crate::identity::Identity;
+use gserver_accounts::{Account, AccountLoader, PlayerPermissions, PLPERM_BAN, PLPERM_SETCOMMENTS};
+use gserver_core::Result;
+use gserver_game::moderation::unix_now;
+use gserver_game::{GameCommand, RecordEdit};
+use gserver_protocol::{PacketOut, PacketTypeOut};
+
+impl PlayerConnection {
+    /// Check that this is an RC with a right (any RC right if `None`)
+    pub(super) fn has_rc_right(&self, right: Option<PlayerPermissions>) -> bool {
+        self.is_rc() && self.account.lock().as_ref()
+            .is_some_and(|a| a.can_use_rc() && right.is_none_or(|right| a.has_permission(right)))
+    }
+
+    /// Load an account for a dialog, telling the RC if it doesn't exist
+    async fn load_dialog_account(&self, name: &str) -> Result<Option<Account>> {
+        let loader = AccountLoader::new(self.context.server_dir());
+        let loaded = if loader.exists(name) { loader.load(name).ok() } else { None };
+        if loaded.is_none() {
+            let text = format!("Account {} not found", name);
+            self.send_packet(PacketOut::new(PacketTypeOut::ServerText, text.into_bytes())).await?;
+        }
+        Ok(loaded)
+    }
+
+    /// Handle PLI_RC_PLAYERCOMMENTSGET (85)
+    pub(super) async fn handle_rc_player_comments_get(&self, packet_data: &[u8]) -> Result<()> {
+        if !self.has_rc_right(None) {
+            tracing::warn!("Connection {} asked for comments without RC rights", self.player_id.get());
+            return Ok(());
+        }
+        let Some(account) = self.load_dialog_account(&parse_request(packet_data)?).await? else {
+            return Ok(());
+        };
+        self.send_packet(PacketOut::new(PacketTypeOut::RcPlayerCommentsGet, comments_data(&account))).await
+    }
+
+    /// Handle PLI_RC_PLAYERCOMMENTSSET (86)
+    pub(super) async fn handle_rc_player_comments_set(&self, packet_data: &[u8]) -> Result<()> {
+        if !self.has_rc_right(Some(PLPERM_SETCOMMENTS)) {
+            tracing::warn!("Connection {} set comments without the right", self.player_id.get());
+            return Ok(());
+        }
+        let (account, comments) = parse_comments_set(packet_data)?;
+        if self.load_dialog_account(&account).await?.is_none() {
+            return Ok(());
+        }
+        tracing::info!("{} changed the comments of {}", self.get_account_name(), account);
+        self.context.commands().send(GameCommand::RecordEdited { account, edit: RecordEdit::Comments(comments) });
+        Ok(())
+    }
+
+    /// Handle PLI_RC_PLAYERBANGET (87)
+    ///
+    /// The dialog only has the reason, so the issuer and expiry follow as a
+    /// server text line.
+    pub(super) async fn handle_rc_player_ban_get(&self, packet_data: &[u8]) -> Result<()> {
+        if !self.has_rc_right(None) {
+            tracing::warn!("Connection {} asked for a ban without RC rights", self.player_id.get());
+            return Ok(());
+        }
+        let Some(account) = self.load_dialog_account(&parse_request(packet_data)?).await? else {
+            return Ok(());
+        };
+        let now = unix_now();
+        self.send_packet(PacketOut::new(PacketTypeOut::RcPlayerBanGet, ban_data(&account, now))).await?;
+        self.send_packet(PacketOut::new(PacketTypeOut::ServerText, describe(&account, now).into_bytes())).await
+    }
+
+    /// Handle PLI_RC_PLAYERBANSET (88), a ban until lifted
+    pub(super) async fn handle_rc_player_ban_set(&self, packet_data: &[u8]) -> Result<()> {
+        if !self.has_rc_right(Some(PLPERM_BAN)) {
+            tracing::warn!("Connection {} set a ban without the right", self.player_id.get());
+            return Ok(());
+        }
+        let (account, banned, reason) = parse_ban_set(packet_data)?;
+        if self.load_dialog_account(&account).await?.is_none() {
+            return Ok(());
+        }
+        self.send_ban(account, banned, reason, 0);
+        Ok(())
+    }
+
+    /// Ban an account for some minutes (RC `/ban <account> <minutes> [reason]`)
+    pub(super) fn rc_ban(&self, args: &[&str]) -> String {
+        const USAGE: &str = "Usage: /ban <account> <minutes> [reason]";
+        if !self.has_rc_right(Some(PLPERM_BAN)) {
+            return "You don't have the right to ban".to_string();
+        }
+        let [account, minutes, reason @ ..] = args else {
+            return USAGE.to_string();
+        };
+        let Some(minutes) = minutes.parse::<u64>().ok().filter(|&m| m > 0) else {
+            return USAGE.to_string();
+        };
+        if !AccountLoader::new(self.context.server_dir()).exists(account) {
+            return format!("Account {} not found", account);
+        }
+        self.send_ban(account.to_string(), true, reason.join(" "), unix_now() + minutes * 60);
+        format!("{} banned for {} minutes", account, minutes)
+    }
+
+    /// Ban an account and its ids until lifted (RC `/banpc <account> [reason]`)
+    ///
+    /// Without arguments, lists the id bans.
+    pub(super) fn rc_ban_pc(&self, args: &[&str]) -> String {
+        if !self.has_rc_right(Some(PLPERM_BAN)) {
+            return "You don't have the right to ban".to_string();
+        }
+        let [account, reason @ ..] = args else {
+            let bans = self.context.bans().active(unix_now());
+            if bans.is_empty() {
+                return "No PC bans".to_string();
+            }
+            let lines: Vec<String> = bans.iter()
+                .map(|b| format!("{} {} by {}: {}", b.kind.name(), b.value, b.issuer, b.reason))
+                .collect();
+            return format!("PC bans: {}", lines.join("; "));
+        };
+        let Some(identity) = self.stored_identity(account) else {
+            return format!("Account {} not found", account);
+        };
+        let identifiers = identity.identifiers();
+        if identifiers.is_empty() {
+            return format!("No PC id recorded for {}", account);
+        }
+        let reason = reason.join(" ");
+        for (kind, value) in identifiers {
+            self.context.bans().ban(IdentityBan {
+                kind,
+                value: value.to_string(),
+                until: 0,
+                issuer: self.get_account_name(),
+                reason: reason.clone(),
+            });
+        }
+        self.send_ban(account.to_string(), true, reason, 0);
+        format!("{} and their PC banned", account)
+    }
+
+    /// Lift the bans of an account and its ids (RC `/unbanpc <account>`)
+    pub(super) fn rc_unban_pc(&self, account: &str) -> String {
+        if !self.has_rc_right(Some(PLPERM_BAN)) {
+            return "You don't have the right to ban".to_string();
+        }
+        let Some(identity) = self.stored_identity(account) else {
+            return format!("Account {} not found", account);
+        };
+        let lifted = self.context.bans().lift(&identity);
+        self.send_ban(account.to_string(), false, String::new(), 0);
+        format!("{} unbanned, {} PC bans lifted", account, lifted)
+    }
+
+    /// Read the identity an account last logged in with
+    fn stored_identity(&self, account: &str) -> Option<Identity> {
+        let loader = AccountLoader::new(self.context.server_dir());
+        if !loader.exists(account) {
+            return None;
+        }
+        loader.load(account).ok().map(|a| Identity::of_account(&a))
+    }
+
+    fn send_ban(&self, account: String, banned: bool, reason: String, until: u64) {
+        let issuer = self.get_account_name();
+        tracing::info!("{} {} {}: {}", issuer, if banned { "banned" } else { "unbanned" }, account, reason);
+        self.context.commands().send(GameCommand::RecordEdited {
+            account,
+            edit: RecordEdit::Ban { banned, reason, issuer, until },
+        });
+    }
+}
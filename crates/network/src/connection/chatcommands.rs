@@ -0,0 +1,76 @@
+//! # Chat Commands
+//!
+//! This module runs the player chat commands of [`crate::chatcommands`] and
+//! carries out what they return.
+
+use super::PlayerConnection;
+use crate::chatcommands::{ChatAction, ChatCommand};
+use crate::respawn::HOME_FLAG;
+use gserver_accounts::FlagValue;
+use gserver_config::FilterCheck;
+use gserver_core::{Result, TileCoord};
+use gserver_game::GameEvent;
+use gserver_protocol::{PacketOut, PacketTypeOut};
+use std::time::Instant;
+
+impl PlayerConnection {
+    /// Run the chat text as a command if it is one
+    ///
+    /// # Returns
+    /// `true` if it was a command, so the chat must not be shown
+    pub(super) async fn handle_chat_command(&self, chat: &str) -> Result<bool> {
+        if !self.context.config().read().chat_commands.enabled {
+            return Ok(false);
+        }
+        let Some(command) = ChatCommand::parse(self.player_id, &self.get_account_name(), self.is_staff(), chat) else {
+            return Ok(false);
+        };
+        let Some(actions) = self.context.chat_commands().dispatch(&command, &self.context, Instant::now()) else {
+            return Ok(false);
+        };
+
+        tracing::info!("Connection {} used /{}", self.player_id.get(), command.name);
+        for action in actions {
+            match action {
+                ChatAction::Reply(text) => self.send_command_reply(&text).await?,
+                ChatAction::Warp(_) if self.jail().is_some() => {
+                    self.send_command_reply("You can't warp while jailed.").await?;
+                }
+                ChatAction::Warp(to) => self.send_player_warp(&to.level, TileCoord(to.x), TileCoord(to.y)).await?,
+                ChatAction::SetHome => self.set_home().await?,
+                ChatAction::PrivateMessage { to, message } => {
+                    let Some(message) = self.apply_word_filter(&message, FilterCheck::Pm).await? else {
+                        continue;
+                    };
+                    self.context.events().publish(GameEvent::PrivateMessage {
+                        from: self.player_id,
+                        account: self.get_account_name(),
+                        data: crate::social::pm_data(self.player_id, to.len(), &message),
+                        to,
+                    });
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Make the player's position their respawn point, unless the level is `nohome`
+    async fn set_home(&self) -> Result<()> {
+        let level = self.get_level();
+        if self.context.levels().get_level(&level).await?.flags.no_home {
+            return self.send_command_reply("You can't set your home on this level.").await;
+        }
+        let (x, y) = self.get_position();
+        if let Some(account) = self.account.lock().as_mut() {
+            account.set_flag(HOME_FLAG, FlagValue::String(format!("{},{},{}", level, x.0, y.0)));
+        }
+        self.mark_account_dirty();
+        self.send_command_reply("Home set.").await
+    }
+
+    /// Show a command's answer as an admin message from the server
+    async fn send_command_reply(&self, text: &str) -> Result<()> {
+        let data = crate::announce::admin_message_data(crate::announce::SERVER_SENDER, &self.translate(text));
+        self.send_packet(PacketOut::new(PacketTypeOut::RcAdminMessage, data)).await
+    }
+}
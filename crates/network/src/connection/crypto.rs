@@ -0,0 +1,615 @@
+//! # Bundle Compression and Encryption
+//!
+//! This module implements the per-generation bundle encoding used between
+//! the server and Graal clients.
+//!
+//! # Generations
+//!
+//! | Gen | Compression            | Encryption                             |
+//! |-----|------------------------|----------------------------------------|
+//! | 1/6 | none                   | none                                   |
+//! | 2   | zlib                   | none                                   |
+//! | 3   | zlib                   | one inserted byte per bundle           |
+//! | 4   | bzip2                  | XOR of the first 4 words               |
+//! | 5   | none/zlib/bzip2 by size | XOR, limit depends on compression type |
+//!
+//! # Codecs
+//!
+//! Each generation is a [`GraalCodec`] implementation, chosen once by
+//! [`codec_for`] when the login packet is read. A codec holds only its key and
+//! iterators, so it can be driven and tested without a socket. Sending and
+//! receiving keep their own iterator ([`Cipher`]), like the C++ server's
+//! `CFileQueue` and `IPacketHandler` do.
+//!
+//! # Adaptive GEN_5 Cutoff
+//!
+//! With `gen5adaptive=true`, each GEN_5 codec watches the zlib bundles up to
+//! twice its current zlib cutoff. When [`ADAPT_SAMPLES`] of them shrank to
+//! more than 90% of their size, compressing there isn't worth the CPU and the
+//! cutoff doubles (at most to the bzip2 cutoff). Under 70%, it halves again,
+//! never below the configured `gen5zlibthreshold`.
+//!
+//! # C++ Equivalence
+//!
+//! Matches `CEncryption` (CEncryption.cpp) and the compression switch in
+//! `CFileQueue::sendCompress()`.
+
+use crate::compression::{CompressionMethod, Compressor, MethodStats};
+use bytes::{BufMut, BytesMut};
+use gserver_core::{GServerError, Result};
+use gserver_protocol::encryption::Cipher;
+use gserver_protocol::ProtocolError;
+use std::fmt;
+use std::time::Duration;
+
+/// GEN_5 compression type: uncompressed
+pub(crate) const COMPRESS_UNCOMPRESSED: u8 = 0x02;
+
+/// GEN_5 compression type: zlib
+pub(crate) const COMPRESS_ZLIB: u8 = 0x04;
+
+/// GEN_5 compression type: bzip2
+pub(crate) const COMPRESS_BZ2: u8 = 0x06;
+
+/// Bundles near the zlib cutoff measured before an adaptive GEN_5 codec moves it
+pub const ADAPT_SAMPLES: u64 = 32;
+
+/// Compression ratio above which the adaptive zlib cutoff doubles
+const ADAPT_RAISE_RATIO: f64 = 0.9;
+
+/// Compression ratio below which the adaptive zlib cutoff halves
+const ADAPT_LOWER_RATIO: f64 = 0.7;
+
+/// Bundle encoding for one encryption generation
+///
+/// `encode` produces the bundle without its length prefix; `decode` takes the
+/// bundle after the length prefix and returns the newline-separated packets.
+pub trait GraalCodec: fmt::Debug + Send {
+    /// Encryption generation this codec implements (1-6)
+    fn generation(&self) -> u8;
+
+    /// Compress and encrypt an outgoing bundle
+    fn encode(&mut self, data: BytesMut) -> Result<BytesMut>;
+
+    /// Decrypt and decompress an incoming bundle
+    fn decode(&mut self, bundle: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Create the codec for a generation and key
+///
+/// Unknown generations fall back to GEN_1 (no compression).
+///
+/// # C++ Equivalence
+/// Matches `CEncryption::setGen()` followed by `CEncryption::reset(key)`
+pub fn codec_for(gen: u8, key: u8, compressor: &Compressor) -> Box<dyn GraalCodec> {
+    match gen {
+        1 => Box::new(Gen1Codec),
+        2 => Box::new(Gen2Codec::new(compressor.clone())),
+        3 => Box::new(Gen3Codec::new(key, compressor.clone())),
+        4 => Box::new(Gen4Codec::new(key, compressor.clone())),
+        5 => Box::new(Gen5Codec::new(key, compressor.clone())),
+        6 => Box::new(Gen6Codec),
+        _ => {
+            tracing::warn!("Unknown encryption generation {}, defaulting to no compression", gen);
+            Box::new(Gen1Codec)
+        }
+    }
+}
+
+/// GEN_1: no compression, no encryption
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Gen1Codec;
+
+impl GraalCodec for Gen1Codec {
+    fn generation(&self) -> u8 {
+        1
+    }
+
+    fn encode(&mut self, data: BytesMut) -> Result<BytesMut> {
+        Ok(data)
+    }
+
+    fn decode(&mut self, bundle: &[u8]) -> Result<Vec<u8>> {
+        Ok(bundle.to_vec())
+    }
+}
+
+/// GEN_2: zlib compression, no encryption
+#[derive(Debug, Clone)]
+pub(crate) struct Gen2Codec {
+    compressor: Compressor,
+}
+
+impl Gen2Codec {
+    pub(crate) fn new(compressor: Compressor) -> Self {
+        Self { compressor }
+    }
+}
+
+impl GraalCodec for Gen2Codec {
+    fn generation(&self) -> u8 {
+        2
+    }
+
+    fn encode(&mut self, data: BytesMut) -> Result<BytesMut> {
+        self.compressor.zlib(&data).map(|v| BytesMut::from(&v[..]))
+    }
+
+    /// C++: bundle.zuncompressI() - unconditional decompression
+    fn decode(&mut self, bundle: &[u8]) -> Result<Vec<u8>> {
+        Ok(decompress_zlib_unconditional(bundle))
+    }
+}
+
+/// GEN_3: zlib compression with one inserted byte per sent bundle
+///
+/// Incoming bundles are plain zlib; the client encrypts each packet
+/// individually instead.
+#[derive(Debug, Clone)]
+pub(crate) struct Gen3Codec {
+    send: Cipher,
+    compressor: Compressor,
+}
+
+impl Gen3Codec {
+    pub(crate) fn new(key: u8, compressor: Compressor) -> Self {
+        Self { send: Cipher::new(key), compressor }
+    }
+}
+
+impl GraalCodec for Gen3Codec {
+    fn generation(&self) -> u8 {
+        3
+    }
+
+    /// C++: int pos = ((m_iterator & 0x0FFFF) % pBuf.length());
+    fn encode(&mut self, data: BytesMut) -> Result<BytesMut> {
+        let compressed = self.compressor.zlib(&data)?;
+        let pos = ((self.send.step() & 0xFFFF) % (compressed.len() as u32)) as usize;
+
+        let mut result = BytesMut::with_capacity(compressed.len() + 1);
+        result.extend_from_slice(&compressed[..pos]);
+        result.put_u8(b')');
+        result.extend_from_slice(&compressed[pos..]);
+        Ok(result)
+    }
+
+    fn decode(&mut self, bundle: &[u8]) -> Result<Vec<u8>> {
+        Ok(decompress_zlib_unconditional(bundle))
+    }
+}
+
+/// GEN_4: bzip2 compression, XOR of the first 4 words
+#[derive(Debug, Clone)]
+pub(crate) struct Gen4Codec {
+    send: Cipher,
+    recv: Cipher,
+    compressor: Compressor,
+}
+
+impl Gen4Codec {
+    pub(crate) fn new(key: u8, compressor: Compressor) -> Self {
+        Self { send: Cipher::new(key), recv: Cipher::new(key), compressor }
+    }
+}
+
+impl GraalCodec for Gen4Codec {
+    fn generation(&self) -> u8 {
+        4
+    }
+
+    fn encode(&mut self, data: BytesMut) -> Result<BytesMut> {
+        let mut encrypted = self.compressor.bz2(&data)?;
+        self.send.apply(&mut encrypted, 4);
+        Ok(BytesMut::from(&encrypted[..]))
+    }
+
+    /// Decrypts first, then decompresses
+    fn decode(&mut self, bundle: &[u8]) -> Result<Vec<u8>> {
+        let mut decrypted = bundle.to_vec();
+        self.recv.apply(&mut decrypted, 4);
+        decompress_bz2(&decrypted)
+    }
+}
+
+/// GEN_5: compression chosen by size, XOR limit by compression type
+///
+/// # Bundle Format
+/// ```text
+/// {compression type byte}{encrypted, compressed packets}
+/// ```
+#[derive(Debug, Clone)]
+pub(crate) struct Gen5Codec {
+    send: Cipher,
+    recv: Cipher,
+    compressor: Compressor,
+
+    /// Bundles larger than this are zlib compressed (moves when adaptive)
+    zlib_threshold: usize,
+
+    /// zlib bundles near the cutoff since it last moved
+    probe: MethodStats,
+}
+
+impl Gen5Codec {
+    pub(crate) fn new(key: u8, compressor: Compressor) -> Self {
+        let zlib_threshold = compressor.config().gen5_zlib_threshold;
+        Self { send: Cipher::new(key), recv: Cipher::new(key), compressor, zlib_threshold, probe: MethodStats::default() }
+    }
+
+    /// Measure a zlib bundle near the cutoff and move the cutoff once enough were
+    fn adapt(&mut self, bytes_in: usize, bytes_out: usize) {
+        self.probe.bundles += 1;
+        self.probe.bytes_in += bytes_in as u64;
+        self.probe.bytes_out += bytes_out as u64;
+        if self.probe.bundles < ADAPT_SAMPLES {
+            return;
+        }
+
+        let config = self.compressor.config();
+        let ratio = self.probe.ratio().unwrap_or(1.0);
+        let threshold = if ratio > ADAPT_RAISE_RATIO {
+            (self.zlib_threshold * 2).min(config.gen5_bz2_threshold)
+        } else if ratio < ADAPT_LOWER_RATIO {
+            (self.zlib_threshold / 2).max(config.gen5_zlib_threshold)
+        } else {
+            self.zlib_threshold
+        };
+        if threshold != self.zlib_threshold {
+            tracing::debug!("GEN_5: zlib cutoff {} -> {} bytes (ratio {:.2})", self.zlib_threshold, threshold, ratio);
+            self.zlib_threshold = threshold;
+        }
+        self.probe = MethodStats::default();
+    }
+}
+
+impl GraalCodec for Gen5Codec {
+    fn generation(&self) -> u8 {
+        5
+    }
+
+    fn encode(&mut self, data: BytesMut) -> Result<BytesMut> {
+        // C++: if (pSend.length() > 0xFFFC) { printf("** [ERROR] Trying to send a GEN_5 packet over 65532 bytes!  Tossing data.\n"); return; }
+        if data.len() > 0xFFFC {
+            return Err(ProtocolError::BundleTooLarge { size: data.len(), limit: 0xFFFC }.into());
+        }
+
+        // C++: if (pSend.length() > 0x2000) { compressionType = COMPRESS_BZ2; pSend.bzcompressI(); }
+        //     else if (pSend.length() > 55) { compressionType = COMPRESS_ZLIB; pSend.zcompressI(); }
+        // The cutoffs default to the C++ values but come from serveroptions.txt
+        let config = *self.compressor.config();
+        let (mut encrypted, comp_type) = if data.len() > config.gen5_bz2_threshold {
+            (self.compressor.bz2(&data)?, COMPRESS_BZ2)
+        } else if data.len() > self.zlib_threshold {
+            let compressed = self.compressor.zlib(&data)?;
+            if config.gen5_adaptive && data.len() <= self.zlib_threshold * 2 {
+                self.adapt(data.len(), compressed.len());
+            }
+            (compressed, COMPRESS_ZLIB)
+        } else {
+            self.compressor.record(CompressionMethod::None, data.len(), data.len(), Duration::ZERO);
+            (data.to_vec(), COMPRESS_UNCOMPRESSED)
+        };
+
+        let limit = encryption_limit(comp_type);
+        self.send.apply(&mut encrypted, limit);
+
+        // Compression type byte is NOT encrypted
+        // C++: CString data = CString() << (short)(pSend.length() + 1) << (char)compressionType << pSend;
+        let mut result = BytesMut::with_capacity(1 + encrypted.len());
+        result.put_u8(comp_type);
+        result.extend_from_slice(&encrypted);
+
+        tracing::trace!("GEN_5: type={}, {} -> {} bytes, encrypted {} words",
+            comp_type, data.len(), result.len(), limit);
+        Ok(result)
+    }
+
+    /// # C++ Equivalence
+    /// Matches processPacketBundle() in IPacketHandler.h (beta4)
+    fn decode(&mut self, bundle: &[u8]) -> Result<Vec<u8>> {
+        let Some((&comp_type, encrypted_data)) = bundle.split_first() else {
+            return Ok(Vec::new());
+        };
+
+        tracing::debug!("GEN_5: Compression type: 0x{:02x}, encrypted data: {} bytes",
+            comp_type, encrypted_data.len());
+
+        if ![COMPRESS_UNCOMPRESSED, COMPRESS_ZLIB, COMPRESS_BZ2].contains(&comp_type) {
+            return Err(ProtocolError::InvalidCompression(comp_type).into());
+        }
+
+        // DECRYPT FIRST (C++: Encryption.decrypt(bundle))
+        let mut decrypted = encrypted_data.to_vec();
+        self.recv.apply(&mut decrypted, encryption_limit(comp_type));
+
+        match comp_type {
+            COMPRESS_ZLIB => decompress_zlib(&decrypted),
+            COMPRESS_BZ2 => decompress_bz2(&decrypted),
+            _ => Ok(decrypted),
+        }
+    }
+}
+
+/// GEN_6: no compression, no encryption
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Gen6Codec;
+
+impl GraalCodec for Gen6Codec {
+    fn generation(&self) -> u8 {
+        6
+    }
+
+    fn encode(&mut self, data: BytesMut) -> Result<BytesMut> {
+        Ok(data)
+    }
+
+    fn decode(&mut self, bundle: &[u8]) -> Result<Vec<u8>> {
+        Ok(bundle.to_vec())
+    }
+}
+
+/// Get the number of encrypted 4-byte words for a GEN_5 compression type
+///
+/// # C++ Equivalence
+/// Matches `CEncryption::limitFromType()` (CEncryption.cpp:144)
+pub(crate) fn encryption_limit(compression_type: u8) -> i32 {
+    match compression_type {
+        COMPRESS_UNCOMPRESSED => 0x0C,
+        COMPRESS_ZLIB => 0x04,
+        COMPRESS_BZ2 => 0x04,
+        _ => -1,
+    }
+}
+
+/// Decompress zlib data, passing through data without the zlib magic byte
+///
+/// # C++ Equivalence
+/// Matches CString::zuncompressI() - zlib decompression
+pub(crate) fn decompress_zlib(data: &[u8]) -> Result<Vec<u8>> {
+    // Zlib magic byte: 0x78
+    if data.first() != Some(&0x78) {
+        tracing::debug!("Not zlib data (first byte: {:02x?}), returning as-is ({} bytes)",
+            data.first(), data.len());
+        return Ok(data.to_vec());
+    }
+    try_zlib_decompress(data)
+}
+
+/// Decompress zlib data unconditionally (login bundle, GEN_2/3)
+///
+/// # C++ Equivalence
+/// Matches CString::zuncompressI() - unconditional zlib decompression
+/// The C++ code doesn't check for magic bytes, it just tries to decompress.
+/// If decompression fails, it returns the original data (for RC compatibility).
+pub(crate) fn decompress_zlib_unconditional(data: &[u8]) -> Vec<u8> {
+    match try_zlib_decompress(data) {
+        Ok(decompressed) => {
+            tracing::debug!("Zlib decompressed: {} -> {} bytes", data.len(), decompressed.len());
+            decompressed
+        }
+        Err(e) => {
+            // RC sends uncompressed packets after login
+            tracing::debug!("Zlib decompression failed ({}), using data as-is. First 16 bytes: {:02x?}",
+                e, &data[..data.len().min(16)]);
+            data.to_vec()
+        }
+    }
+}
+
+/// Try zlib decompression without logging
+fn try_zlib_decompress(data: &[u8]) -> Result<Vec<u8>> {
+    use flate2::read::ZlibDecoder;
+    use std::io::Read;
+
+    let mut decoder = ZlibDecoder::new(data);
+    let mut decompressed = Vec::new();
+    decoder.read_to_end(&mut decompressed).map_err(|e| {
+        GServerError::Compression(format!("Zlib decompression failed: {}", e))
+    })?;
+    Ok(decompressed)
+}
+
+/// Decompress bzip2 data, passing through data without the "BZh" magic
+///
+/// # C++ Equivalence
+/// Matches CString::bzuncompressI() - bzip2 decompression
+pub(crate) fn decompress_bz2(data: &[u8]) -> Result<Vec<u8>> {
+    use bzip2::read::BzDecoder;
+    use std::io::Read;
+
+    if !data.starts_with(b"BZh") {
+        return Ok(data.to_vec());
+    }
+
+    let mut decoder = BzDecoder::new(data);
+    let mut decompressed = Vec::new();
+    decoder.read_to_end(&mut decompressed).map_err(|e| {
+        GServerError::Compression(format!("BZ2 decompression failed: {}", e))
+    })?;
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gserver_config::CompressionConfig;
+
+    /// Key used by all fixtures
+    const KEY: u8 = 73;
+
+    /// Packet used by all fixtures
+    const PACKET: &[u8] = b"\x22hello\n";
+
+    /// zlib of `PACKET` (default level)
+    const PACKET_ZLIB: [u8; 15] = [
+        0x78, 0x9c, 0x53, 0xca, 0x48, 0xcd, 0xc9, 0xc9, 0xe7, 0x02, 0x00, 0x09, 0x3a, 0x02, 0x41,
+    ];
+
+    fn codec(gen: u8) -> Box<dyn GraalCodec> {
+        codec_for(gen, KEY, &Compressor::default())
+    }
+
+    fn encode(codec: &mut dyn GraalCodec, data: &[u8]) -> Vec<u8> {
+        codec.encode(BytesMut::from(data)).unwrap().to_vec()
+    }
+
+    /// Encode with one side's send iterator and decode with the other side's
+    /// receive iterator, like a client and the server would
+    fn roundtrip(gen: u8, payload: &[u8]) {
+        let mut sender = codec(gen);
+        let mut receiver = codec(gen);
+
+        for _ in 0..3 {
+            let encoded = encode(sender.as_mut(), payload);
+            assert_eq!(receiver.decode(&encoded).unwrap(), payload, "GEN_{}", gen);
+        }
+    }
+
+    #[test]
+    fn test_codec_for_generation() {
+        for gen in 1..=6 {
+            assert_eq!(codec(gen).generation(), gen);
+        }
+        assert_eq!(codec(9).generation(), 1);
+    }
+
+    #[test]
+    fn test_roundtrip_all_generations() {
+        let medium = b"\x2ajust over fifty-five bytes of packet data for zlib\n".repeat(2);
+        let large = (0..0x3000u32).map(|i| (i % 200) as u8 + 32).collect::<Vec<_>>();
+
+        for gen in [1, 2, 4, 5, 6] {
+            roundtrip(gen, PACKET);
+            roundtrip(gen, &medium);
+            roundtrip(gen, &large);
+        }
+    }
+
+    #[test]
+    fn test_gen1_and_gen6_fixtures() {
+        for mut codec in [codec(1), codec(6)] {
+            assert_eq!(encode(codec.as_mut(), PACKET), PACKET);
+            assert_eq!(codec.decode(PACKET).unwrap(), PACKET);
+        }
+    }
+
+    #[test]
+    fn test_gen2_fixtures() {
+        let mut codec = Gen2Codec::new(Compressor::default());
+        assert_eq!(encode(&mut codec, PACKET), PACKET_ZLIB);
+        assert_eq!(codec.decode(&PACKET_ZLIB).unwrap(), PACKET);
+
+        // RC sends uncompressed bundles after login
+        assert_eq!(codec.decode(PACKET).unwrap(), PACKET);
+    }
+
+    #[test]
+    fn test_gen3_fixtures() {
+        let mut codec = Gen3Codec::new(KEY, Compressor::default());
+
+        // ')' inserted at (iterator & 0xFFFF) % len: 1, then 7
+        assert_eq!(encode(&mut codec, PACKET), [
+            0x78, 0x29, 0x9c, 0x53, 0xca, 0x48, 0xcd, 0xc9, 0xc9, 0xe7, 0x02, 0x00, 0x09, 0x3a, 0x02, 0x41,
+        ]);
+        assert_eq!(encode(&mut codec, PACKET), [
+            0x78, 0x9c, 0x53, 0xca, 0x48, 0xcd, 0xc9, 0x29, 0xc9, 0xe7, 0x02, 0x00, 0x09, 0x3a, 0x02, 0x41,
+        ]);
+
+        // Incoming bundles are plain zlib and don't advance the iterator
+        assert_eq!(codec.decode(&PACKET_ZLIB).unwrap(), PACKET);
+    }
+
+    #[test]
+    fn test_gen4_fixtures() {
+        // bzip2 (level 6) of PACKET, first 4 words XOR-encrypted, two bundles in a row
+        let first = [
+            0x23, 0x42, 0xb9, 0xe0, 0x1f, 0x3f, 0xf6, 0xeb, 0x7c, 0x76, 0xc1, 0xd6, 0xe3, 0xf3, 0x6e,
+            0xac, 0x00, 0xd1, 0x00, 0x00, 0x10, 0x10, 0x00, 0x02, 0x44, 0xa0, 0x00, 0x21, 0x80, 0x0c,
+            0x03, 0x2b, 0x2e, 0x1c, 0x5d, 0xc9, 0x14, 0xe1, 0x42, 0x40, 0xb0, 0xd3, 0x5f, 0x6c,
+        ];
+        let second = [
+            0x0f, 0xc3, 0xe9, 0x3a, 0xfb, 0xf3, 0xa2, 0x65, 0x68, 0xff, 0x46, 0x36, 0xa7, 0x70, 0xa3,
+            0x13, 0x00, 0xd1, 0x00, 0x00, 0x10, 0x10, 0x00, 0x02, 0x44, 0xa0, 0x00, 0x21, 0x80, 0x0c,
+            0x03, 0x2b, 0x2e, 0x1c, 0x5d, 0xc9, 0x14, 0xe1, 0x42, 0x40, 0xb0, 0xd3, 0x5f, 0x6c,
+        ];
+
+        let mut codec = Gen4Codec::new(KEY, Compressor::default());
+        assert_eq!(encode(&mut codec, PACKET), first);
+        assert_eq!(encode(&mut codec, PACKET), second);
+
+        let mut codec = Gen4Codec::new(KEY, Compressor::default());
+        assert_eq!(codec.decode(&first).unwrap(), PACKET);
+        assert_eq!(codec.decode(&second).unwrap(), PACKET);
+    }
+
+    #[test]
+    fn test_gen5_fixtures() {
+        // Uncompressed: type byte, then up to 12 XOR-encrypted words
+        let first = [0x02, 0x43, 0x70, 0xb4, 0xba, 0x42, 0x11, 0xa5];
+        let second = [0x02, 0x0d, 0x47, 0x88, 0x8e, 0x58, 0x47, 0x64];
+
+        let mut codec = Gen5Codec::new(KEY, Compressor::default());
+        assert_eq!(encode(&mut codec, PACKET), first);
+        assert_eq!(encode(&mut codec, PACKET), second);
+
+        let mut codec = Gen5Codec::new(KEY, Compressor::default());
+        assert_eq!(codec.decode(&first).unwrap(), PACKET);
+        assert_eq!(codec.decode(&second).unwrap(), PACKET);
+
+        // zlib: 56 bytes, first 4 words XOR-encrypted (compressed by C zlib,
+        // which emits different but compatible bytes than our encoder)
+        let packet = [b"\x22".as_slice(), &[b'a'; 54], b"\n"].concat();
+        let zlib = [0x04, 0x19, 0x84, 0x82, 0x9c, 0x0a, 0x75, 0xdf, 0xcc, 0x2f, 0x61, 0x1e, 0xf6, 0x97];
+        assert_eq!(Gen5Codec::new(KEY, Compressor::default()).decode(&zlib).unwrap(), packet);
+    }
+
+    #[test]
+    fn test_gen5_compression_type_by_size() {
+        let mut codec = Gen5Codec::new(0, Compressor::default());
+        assert_eq!(encode(&mut codec, &[b'a'; 55])[0], COMPRESS_UNCOMPRESSED);
+        assert_eq!(encode(&mut codec, &[b'a'; 56])[0], COMPRESS_ZLIB);
+        assert_eq!(encode(&mut codec, &[b'a'; 0x2001])[0], COMPRESS_BZ2);
+        assert!(codec.encode(BytesMut::from(&[b'a'; 0xFFFD][..])).is_err());
+    }
+
+    #[test]
+    fn test_gen5_configured_thresholds() {
+        let compressor = Compressor::new(CompressionConfig {
+            gen5_zlib_threshold: 100,
+            gen5_bz2_threshold: 200,
+            ..Default::default()
+        });
+        let mut codec = Gen5Codec::new(0, compressor);
+        assert_eq!(encode(&mut codec, &[b'a'; 100])[0], COMPRESS_UNCOMPRESSED);
+        assert_eq!(encode(&mut codec, &[b'a'; 101])[0], COMPRESS_ZLIB);
+        assert_eq!(encode(&mut codec, &[b'a'; 201])[0], COMPRESS_BZ2);
+    }
+
+    #[test]
+    fn test_gen5_adaptive_threshold() {
+        let compressor = Compressor::new(CompressionConfig { gen5_adaptive: true, ..Default::default() });
+        let mut codec = Gen5Codec::new(0, compressor);
+        // Incompressible bundles just over the cutoff raise it
+        for n in 0..ADAPT_SAMPLES as u32 {
+            let data = (0..60u32).map(|i| ((i + n * 60).wrapping_mul(2654435761) >> 24) as u8).collect::<Vec<_>>();
+            assert_eq!(encode(&mut codec, &data)[0], COMPRESS_ZLIB);
+        }
+        assert_eq!(codec.zlib_threshold, 110);
+        assert_eq!(encode(&mut codec, &[b'a'; 100])[0], COMPRESS_UNCOMPRESSED);
+
+        // Compressible ones lower it again, not below the configured cutoff
+        for _ in 0..ADAPT_SAMPLES * 2 {
+            assert_eq!(encode(&mut codec, &[b'a'; 150])[0], COMPRESS_ZLIB);
+        }
+        assert_eq!(codec.zlib_threshold, 55);
+        assert_eq!(codec.compressor.stats().method(CompressionMethod::None).bundles, 1);
+    }
+
+    #[test]
+    fn test_gen5_rejects_invalid_compression_type() {
+        let mut codec = Gen5Codec::new(0, Compressor::default());
+        assert!(codec.decode(&[0x03, 1, 2, 3]).is_err());
+        assert_eq!(codec.decode(&[]).unwrap(), b"");
+    }
+}
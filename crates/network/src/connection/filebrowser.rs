@@ -0,0 +1,286 @@
+//! # RC File Browser Handlers
+//!
+//! This module answers the PLI_RC_FILEBROWSER_* packets. Every operation is
+//! checked against the account's folder rights:
+//!
+//! - Listing a folder (start, cd) needs the folder in the folder list
+//! - Downloading needs read access to the file
+//! - Uploading, deleting, renaming and moving need write access to every
+//!   file involved
+//!
+//! The current folder is the account's LASTFOLDER, so it survives relogs.
+//! Views and packets are built by [`crate::filebrowser`].
+
+use super::PlayerConnection;
+use crate::filebrowser::{
+    dir_list_packet, dir_packet, is_plain_file_name, list_folder, message_packet, normalize_folder, rights_path,
+};
+use bytes::{Buf, BytesMut};
+use gserver_accounts::FolderRights;
+use gserver_core::Result;
+use gserver_protocol::codecs::read_gstring;
+
+impl PlayerConnection {
+    /// Get the folder rights of an RC allowed to use the file browser
+    fn file_browser_rights(&self) -> Option<FolderRights> {
+        if !self.is_rc() {
+            return None;
+        }
+        self.account.lock().as_ref()
+            .filter(|account| account.can_use_rc())
+            .map(|account| account.folder_permissions())
+    }
+
+    /// Get the largest file an RC may upload
+    ///
+    /// An upload is one packet, so `rcmaxuploadsize` is lowered to the RC's
+    /// bundle limit (see [`super::io::bundle_limit`]).
+    pub(super) fn rc_upload_limit(&self) -> u64 {
+        let config = self.context.config().read();
+        let bundle = super::io::bundle_limit(self.codec.lock().generation(), true, &config) as u64;
+        config.rc_max_upload_size.min(bundle).min(i32::MAX as u64)
+    }
+
+    /// Get the current file browser folder (normalized LASTFOLDER)
+    fn file_browser_folder(&self) -> String {
+        self.account.lock().as_ref()
+            .map(|account| normalize_folder(&account.last_folder))
+            .unwrap_or_default()
+    }
+
+    /// Send PLO_RC_FILEBROWSER_DIR for the current folder
+    async fn send_file_browser_dir(&self, rights: &FolderRights) -> Result<()> {
+        let folder = self.file_browser_folder();
+        let files = list_folder(self.context.server_dir(), &folder, rights);
+        self.send_packet(dir_packet(&folder, &files)).await
+    }
+
+    /// Send PLO_RC_FILEBROWSER_MESSAGE
+    async fn send_file_browser_message(&self, text: &str) -> Result<()> {
+        self.send_packet(message_packet(text)).await
+    }
+
+    /// Rescan the game file index after a change under world/
+    fn file_browser_changed(&self, folder: &str) {
+        if folder.eq_ignore_ascii_case("world") || folder.to_lowercase().starts_with("world/") {
+            self.context.files().rescan();
+        }
+    }
+
+    /// Handle PLI_RC_FILEBROWSER_START (89)
+    ///
+    /// # Response Packets Sent
+    /// 1. PLO_RC_FILEBROWSER_DIRLIST - Folders the account has rights to
+    /// 2. PLO_RC_FILEBROWSER_DIR - Current folder (first listed if LASTFOLDER isn't)
+    /// 3. PLO_RC_FILEBROWSER_MESSAGE - Welcome message
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerRC::msgPLI_RC_FILEBROWSER_START`
+    pub(super) async fn handle_rc_file_browser_start(&self) -> Result<()> {
+        let Some(rights) = self.file_browser_rights() else {
+            tracing::warn!("Connection {} opened the file browser without RC rights", self.player_id.get());
+            return Ok(());
+        };
+
+        if rights.folder_access(&self.file_browser_folder()).is_none() {
+            if let Some(first) = rights.iter().next() {
+                let folder = first.folder.clone();
+                if let Some(account) = self.account.lock().as_mut() {
+                    account.last_folder = folder;
+                }
+                self.mark_account_dirty();
+            }
+        }
+
+        self.send_packet(dir_list_packet(&rights)).await?;
+        self.send_file_browser_dir(&rights).await?;
+        self.send_file_browser_message("Welcome to the File Browser.").await
+    }
+
+    /// Handle PLI_RC_FILEBROWSER_CD (90)
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {folder}
+    /// ```
+    pub(super) async fn handle_rc_file_browser_cd(&self, packet_data: &[u8]) -> Result<()> {
+        let Some(rights) = self.file_browser_rights() else {
+            return Ok(());
+        };
+        let folder = normalize_folder(&String::from_utf8_lossy(packet_data));
+
+        if rights.folder_access(&folder).is_none() {
+            tracing::warn!("Connection {} denied file browser folder: {}", self.player_id.get(), folder);
+            return self.send_file_browser_message(&format!("No rights to folder {}", folder)).await;
+        }
+
+        if let Some(account) = self.account.lock().as_mut() {
+            account.last_folder = folder;
+        }
+        self.mark_account_dirty();
+        self.send_file_browser_dir(&rights).await
+    }
+
+    /// Handle PLI_RC_FILEBROWSER_END (91)
+    pub(super) async fn handle_rc_file_browser_end(&self) -> Result<()> {
+        tracing::debug!("Connection {} closed the file browser", self.player_id.get());
+        Ok(())
+    }
+
+    /// Handle PLI_RC_FILEBROWSER_DOWN (92)
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {file name}
+    /// ```
+    pub(super) async fn handle_rc_file_browser_down(&self, packet_data: &[u8]) -> Result<()> {
+        let Some(rights) = self.file_browser_rights() else {
+            return Ok(());
+        };
+        let name = String::from_utf8_lossy(packet_data).trim().to_string();
+        let folder = self.file_browser_folder();
+
+        if !is_plain_file_name(&name) || !rights.can_read(&rights_path(&folder, &name)) {
+            tracing::warn!("Connection {} denied download of {}/{}", self.player_id.get(), folder, name);
+            return self.send_file_browser_message(&format!("No rights to download {}", name)).await;
+        }
+
+        let path = self.context.server_dir().join(&folder).join(&name);
+        if self.send_file_from(&path, &name).await? {
+            self.send_file_browser_message(&format!("Downloading file {}", name)).await?;
+        }
+        Ok(())
+    }
+
+    /// Handle PLI_RC_FILEBROWSER_UP (93)
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {GSTRING file name}{file data}
+    /// ```
+    pub(super) async fn handle_rc_file_browser_up(&self, packet_data: &[u8]) -> Result<()> {
+        let Some(rights) = self.file_browser_rights() else {
+            return Ok(());
+        };
+        let mut buf = BytesMut::from(packet_data);
+        let name = read_gstring(&mut buf)?;
+        let folder = self.file_browser_folder();
+
+        if !is_plain_file_name(&name) || !rights.can_write(&rights_path(&folder, &name)) {
+            tracing::warn!("Connection {} denied upload of {}/{}", self.player_id.get(), folder, name);
+            return self.send_file_browser_message(&format!("No rights to upload {}", name)).await;
+        }
+        let limit = self.rc_upload_limit();
+        if buf.remaining() as u64 > limit {
+            tracing::warn!("Connection {} sent {} bytes for {}/{}, over the {} byte limit",
+                self.player_id.get(), buf.remaining(), folder, name, limit);
+            return self.send_file_browser_message(&format!("{} is larger than the {} byte upload limit", name, limit)).await;
+        }
+
+        let path = self.context.server_dir().join(&folder).join(&name);
+        if let Err(e) = tokio::fs::write(&path, buf.chunk()).await {
+            tracing::warn!("Failed to write upload {:?}: {}", path, e);
+            return self.send_file_browser_message(&format!("Upload of {} failed", name)).await;
+        }
+
+        tracing::info!("{} uploaded {}/{} ({} bytes)", self.get_account_name(), folder, name, buf.remaining());
+        self.file_browser_changed(&folder);
+        self.send_file_browser_message(&format!("Uploaded file {}", name)).await?;
+        self.send_file_browser_dir(&rights).await
+    }
+
+    /// Handle PLI_RC_FILEBROWSER_MOVE (96)
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {GSTRING file name}{new folder}
+    /// ```
+    pub(super) async fn handle_rc_file_browser_move(&self, packet_data: &[u8]) -> Result<()> {
+        let Some(rights) = self.file_browser_rights() else {
+            return Ok(());
+        };
+        let mut buf = BytesMut::from(packet_data);
+        let name = read_gstring(&mut buf)?;
+        let target = normalize_folder(&String::from_utf8_lossy(buf.chunk()));
+        let folder = self.file_browser_folder();
+
+        if !is_plain_file_name(&name)
+            || !rights.can_write(&rights_path(&folder, &name))
+            || !rights.can_write(&rights_path(&target, &name))
+        {
+            return self.send_file_browser_message(&format!("No rights to move {} to {}", name, target)).await;
+        }
+
+        let dir = self.context.server_dir();
+        if let Err(e) = tokio::fs::rename(dir.join(&folder).join(&name), dir.join(&target).join(&name)).await {
+            tracing::warn!("Failed to move {}/{} to {}: {}", folder, name, target, e);
+            return self.send_file_browser_message(&format!("Moving {} failed", name)).await;
+        }
+
+        self.file_browser_changed(&folder);
+        self.file_browser_changed(&target);
+        self.send_file_browser_message(&format!("Moved file {} to {}", name, target)).await?;
+        self.send_file_browser_dir(&rights).await
+    }
+
+    /// Handle PLI_RC_FILEBROWSER_DELETE (97)
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {file name}
+    /// ```
+    pub(super) async fn handle_rc_file_browser_delete(&self, packet_data: &[u8]) -> Result<()> {
+        let Some(rights) = self.file_browser_rights() else {
+            return Ok(());
+        };
+        let name = String::from_utf8_lossy(packet_data).trim().to_string();
+        let folder = self.file_browser_folder();
+
+        if !is_plain_file_name(&name) || !rights.can_write(&rights_path(&folder, &name)) {
+            return self.send_file_browser_message(&format!("No rights to delete {}", name)).await;
+        }
+
+        let path = self.context.server_dir().join(&folder).join(&name);
+        if let Err(e) = tokio::fs::remove_file(&path).await {
+            tracing::warn!("Failed to delete {:?}: {}", path, e);
+            return self.send_file_browser_message(&format!("Deleting {} failed", name)).await;
+        }
+
+        tracing::info!("{} deleted {}/{}", self.get_account_name(), folder, name);
+        self.file_browser_changed(&folder);
+        self.send_file_browser_message(&format!("Deleted file {}", name)).await?;
+        self.send_file_browser_dir(&rights).await
+    }
+
+    /// Handle PLI_RC_FILEBROWSER_RENAME (98)
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {GSTRING old name}{GSTRING new name}
+    /// ```
+    pub(super) async fn handle_rc_file_browser_rename(&self, packet_data: &[u8]) -> Result<()> {
+        let Some(rights) = self.file_browser_rights() else {
+            return Ok(());
+        };
+        let mut buf = BytesMut::from(packet_data);
+        let old_name = read_gstring(&mut buf)?;
+        let new_name = read_gstring(&mut buf)?;
+        let folder = self.file_browser_folder();
+
+        let allowed = [&old_name, &new_name].iter()
+            .all(|name| is_plain_file_name(name) && rights.can_write(&rights_path(&folder, name)));
+        if !allowed {
+            return self.send_file_browser_message(&format!("No rights to rename {} to {}", old_name, new_name)).await;
+        }
+
+        let dir = self.context.server_dir().join(&folder);
+        if let Err(e) = tokio::fs::rename(dir.join(&old_name), dir.join(&new_name)).await {
+            tracing::warn!("Failed to rename {}/{} to {}: {}", folder, old_name, new_name, e);
+            return self.send_file_browser_message(&format!("Renaming {} failed", old_name)).await;
+        }
+
+        self.file_browser_changed(&folder);
+        self.send_file_browser_message(&format!("Renamed file {} to {}", old_name, new_name)).await?;
+        self.send_file_browser_dir(&rights).await
+    }
+}
@@ -0,0 +1,85 @@
+//! # File Transfers
+//!
+//! This module answers file requests with the transfers built by
+//! [`crate::files::encode_file`], queued on the file side of the
+//! [`super::queue::OutboundQueue`] so large files don't block gameplay
+//! packets. Reading and encoding run on the blocking pool as a named task
+//! (`file {name}`, see [`crate::tasks`]).
+
+use super::PlayerConnection;
+use crate::error::FileServeError;
+use crate::files::{encode_file, file_mod_time};
+use crate::tasks::spawn_blocking_named;
+use gserver_core::{ErrorContext, Result};
+use gserver_protocol::{PacketOut, PacketTypeOut};
+use std::path::Path;
+
+impl PlayerConnection {
+    /// Send a file to the client
+    ///
+    /// # Returns
+    /// `true` if the file was queued, `false` if PLO_FILESENDFAILED was sent
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::sendFile` in PlayerClient.cpp
+    pub(super) async fn send_file(&self, name: &str) -> Result<bool> {
+        let Some(path) = self.context.files().find(name) else {
+            self.report_error(FileServeError::NotFound { name: name.to_string() }.into(), ErrorContext::default());
+            self.send_file_failed(name).await?;
+            return Ok(false);
+        };
+        self.send_file_from(&path, name).await
+    }
+
+    /// Send a file from a path, naming it `name` in the transfer
+    ///
+    /// Callers decide whether the connection may read `path`; the file index
+    /// for game clients, folder rights for the RC file browser.
+    ///
+    /// # Returns
+    /// `true` if the file was queued, `false` if PLO_FILESENDFAILED was sent
+    pub(super) async fn send_file_from(&self, path: &Path, name: &str) -> Result<bool> {
+        let (file_path, file_name) = (path.to_path_buf(), name.to_string());
+        let encoded = spawn_blocking_named(&format!("file {}", name), move || {
+            let data = std::fs::read(&file_path)?;
+            let mod_time = file_mod_time(&file_path).unwrap_or(0);
+            Ok::<_, std::io::Error>((data.len(), mod_time, encode_file(&file_name, mod_time, &data)))
+        }).await.unwrap_or_else(|e| Err(std::io::Error::other(e)));
+        let (size, mod_time, packets) = match encoded {
+            Ok(encoded) => encoded,
+            Err(e) => {
+                let error = FileServeError::Unreadable { name: path.display().to_string(), reason: e.to_string() };
+                self.report_error(error.into(), ErrorContext::default());
+                self.send_file_failed(name).await?;
+                return Ok(false);
+            }
+        };
+
+        tracing::debug!("Connection {} sending file {} ({} bytes, modtime {})",
+            self.player_id.get(), name, size, mod_time);
+        for packet in packets {
+            self.send_file_packet(packet).await?;
+        }
+        Ok(true)
+    }
+
+    /// Tell the client a file can't be sent
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {PLO_FILESENDFAILED}{name}
+    /// ```
+    pub(super) async fn send_file_failed(&self, name: &str) -> Result<()> {
+        self.send_packet(PacketOut::new(PacketTypeOut::FileSendFailed, name.as_bytes().to_vec())).await
+    }
+
+    /// Tell the client its cached copy of a file is current
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {PLO_FILEUPTODATE}{name}
+    /// ```
+    pub(super) async fn send_file_up_to_date(&self, name: &str) -> Result<()> {
+        self.send_packet(PacketOut::new(PacketTypeOut::FileUpToDate, name.as_bytes().to_vec())).await
+    }
+}
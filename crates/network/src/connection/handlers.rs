@@ -0,0 +1,2774 @@
+//! # Packet Handlers
+//!
+//! This module dispatches packets received after login to their handlers.
+//!
+//! # Conventions
+//!
+//! Each `handle_*` method handles one PLI_* packet and documents its format
+//! and the C++ handler it mirrors. Handlers that need shared state go through
+//! [`crate::context::ServerContext`].
+
+use super::PlayerConnection;
+use crate::clienttext::{ClientText, TextAction};
+use crate::integrity::{PacketCountResult, TamperAction, TamperReport};
+use bytes::BytesMut;
+use gserver_accounts::FlagValue;
+use gserver_config::translations::DEFAULT_LANGUAGE;
+use gserver_config::wordfilter::{Escalation, FilterCheck};
+use gserver_core::{HalfTile, Result};
+use gserver_game::{GameCommand, GameEvent};
+use gserver_protocol::PacketIn;
+use std::time::{Duration, Instant};
+
+impl PlayerConnection {
+    /// Handle an incoming packet
+    ///
+    /// # Arguments
+    /// * `packet` - The packet to handle
+    ///
+    /// # Implementation
+    /// Dispatches to packet-specific handlers
+    pub(super) async fn handle_packet(&self, packet: PacketIn) -> Result<()> {
+        match packet.packet_type {
+            gserver_protocol::PacketTypeIn::LevelWarp => {
+                self.handle_level_warp(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::PlayerProps => {
+                self.handle_player_props(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::ThrowCarried => {
+                self.handle_throw_carried(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::BoardModify => {
+                self.handle_board_modify(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::ToAll => {
+                self.handle_to_all(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::Language => {
+                self.handle_language(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::MapInfo => {
+                self.handle_map_info(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::RequestText => {
+                self.handle_request_text(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::Shoot => {
+                self.handle_shoot(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::FlagSet => {
+                self.handle_flag_set(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::FlagDel => {
+                self.handle_flag_del(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::WeaponAdd => {
+                self.handle_weapon_add(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::NpcProps => {
+                self.handle_npc_props(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::NpcDel => {
+                self.handle_npc_del(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::NpcWeaponDel => {
+                self.handle_npc_weapon_del(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::BombAdd => {
+                self.handle_bomb_add(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::BombDel => {
+                self.handle_bomb_del(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::ArrowAdd => {
+                self.handle_arrow_add(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::ItemAdd => {
+                self.handle_item_add(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::ItemDel => {
+                self.handle_item_del(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::HurtPlayer => {
+                self.handle_hurt_player(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::HitObjects => {
+                self.handle_hit_objects(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::Explosion => {
+                self.handle_explosion(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::TriggerAction => {
+                self.handle_trigger_action(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::PrivateMessage => {
+                self.handle_private_message(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::WantFile => {
+                self.handle_want_file(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::UpdateFile => {
+                self.handle_update_file(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::UpdateGani => {
+                self.handle_update_gani(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::UpdatePackageRequestFile => {
+                self.handle_update_package_request(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::UpdateScript => {
+                self.handle_update_script(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::UpdateClass => {
+                self.handle_update_class(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::PacketCount => {
+                self.handle_packet_count(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::TamperCheck => {
+                self.handle_tamper_check(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::Ping => {
+                self.handle_ping();
+            }
+            gserver_protocol::PacketTypeIn::SendText => {
+                self.handle_send_text(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::RcChat => {
+                self.handle_rc_chat(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::RcAdminMessage => {
+                self.handle_rc_admin_message(&packet.packet_data);
+            }
+            gserver_protocol::PacketTypeIn::RcPrivAdminMessage => {
+                self.handle_rc_priv_admin_message(&packet.packet_data)?;
+            }
+            gserver_protocol::PacketTypeIn::RcFileBrowserStart => {
+                self.handle_rc_file_browser_start().await?;
+            }
+            gserver_protocol::PacketTypeIn::RcFileBrowserCd => {
+                self.handle_rc_file_browser_cd(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::RcFileBrowserEnd => {
+                self.handle_rc_file_browser_end().await?;
+            }
+            gserver_protocol::PacketTypeIn::RcFileBrowserDown => {
+                self.handle_rc_file_browser_down(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::RcFileBrowserUp => {
+                self.handle_rc_file_browser_up(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::RcFileBrowserMove => {
+                self.handle_rc_file_browser_move(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::RcFileBrowserDelete => {
+                self.handle_rc_file_browser_delete(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::RcFileBrowserRename => {
+                self.handle_rc_file_browser_rename(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::RcUpdateLevels => {
+                self.handle_rc_update_levels(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::RcPlayerCommentsGet => {
+                self.handle_rc_player_comments_get(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::RcPlayerCommentsSet => {
+                self.handle_rc_player_comments_set(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::RcPlayerBanGet => {
+                self.handle_rc_player_ban_get(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::RcPlayerBanSet => {
+                self.handle_rc_player_ban_set(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::NpcServerQuery => {
+                self.handle_npcserver_query().await?;
+            }
+            gserver_protocol::PacketTypeIn::NcWeaponAdd => {
+                self.handle_nc_weapon_add(&packet.packet_data).await?;
+            }
+            gserver_protocol::PacketTypeIn::NcClassEdit | gserver_protocol::PacketTypeIn::NcClassAdd => {
+                self.handle_nc_class_edit(&packet.packet_data).await?;
+            }
+            _ => {
+                tracing::trace!("Connection {} unhandled packet: {:?}",
+                    self.player_id.get(), packet.packet_type);
+            }
+        }
+        Ok(())
+    }
+
+    /// Offer a packet to the plugins before the built-in handler
+    ///
+    /// # Returns
+    /// `None` if no plugin handled it (see [`crate::plugins`])
+    #[cfg(feature = "plugins")]
+    pub(super) async fn handle_plugin_packet(&self, packet_id: u8, data: &[u8]) -> Option<Result<()>> {
+        use crate::plugins::{PacketVerdict, PluginPacket};
+
+        if !self.context.plugins().has_packet_handler(packet_id) {
+            return None;
+        }
+        let account = self.get_account_name();
+        let packet = PluginPacket { player: self.player_id, account: &account, packet_id, data };
+        match self.context.plugins().handle_packet(&packet, &self.context) {
+            PacketVerdict::Continue => None,
+            PacketVerdict::Handled(replies) => {
+                for reply in replies {
+                    if let Err(e) = self.send_packet(reply).await {
+                        return Some(Err(e));
+                    }
+                }
+                Some(Ok(()))
+            }
+        }
+    }
+
+    /// Offer a packet to the plugins (none without the `plugins` feature)
+    #[cfg(not(feature = "plugins"))]
+    pub(super) async fn handle_plugin_packet(&self, _packet_id: u8, _data: &[u8]) -> Option<Result<()>> {
+        None
+    }
+
+    /// Handle level warp packet (PLI_LEVELWARP = 0)
+    ///
+    /// # Purpose
+    /// Called when a player requests to warp to a level.
+    /// This is the primary mechanism for level loading.
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {GINT5 modtime}{GSHORT x*2}{GSHORT y*2}{GSTRING level}
+    /// ```
+    ///
+    /// # Response Packets Sent
+    /// 1. PLO_SIGNATURE - Server signature (if not already sent)
+    /// 2. PLO_LEVELNAME - Level name
+    /// 3. PLO_RAWDATA - Board tile data (64x64 tiles)
+    /// 4. PLO_LEVELMODTIME - Level modification time
+    /// 5. PLO_SETACTIVELEVEL - Set active level
+    /// 6. PLO_NEWWORLDTIME - World time
+    /// 7. PLO_GHOSTICON - Ghost icon status
+    /// 8. PLO_ISLEADER - Leader flag
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_LEVELWARP` and `PlayerClient::sendLevel` in PlayerClient.cpp:1179-1430
+    async fn handle_level_warp(&self, packet_data: &[u8]) -> Result<()> {
+        use bytes::BufMut;
+        use gserver_protocol::{codecs::*, packet_builder::*, packets::PacketTypeOut, PacketOut};
+
+        tracing::info!("Connection {} handling level warp", self.player_id.get());
+
+        // Parse the level warp packet
+        let mut buf = BytesMut::from(packet_data);
+        let mod_time = read_guint5(&mut buf)?;
+        let x = HalfTile(read_gshort(&mut buf)?).to_tiles();
+        let y = HalfTile(read_gshort(&mut buf)?).to_tiles();
+        let level_name = read_gstring(&mut buf)?;
+
+        tracing::info!("Connection {} level warp: mod_time={}, x={}, y={}, level={}",
+            self.player_id.get(), mod_time, x, y, level_name);
+        self.record_gameplay().await?;
+
+        // Jailed players are sent back until they're released (see crate::moderation)
+        if let Some(jail) = self.jail().filter(|jail| !jail.level.eq_ignore_ascii_case(&level_name)) {
+            tracing::info!("Connection {} is jailed, warp to {} refused", self.player_id.get(), level_name);
+            return self.send_player_warp(&jail.level, gserver_core::TileCoord(jail.x), gserver_core::TileCoord(jail.y)).await;
+        }
+
+        // Load the level (the level manager falls back to a default level)
+        let level = self.context.levels().get_level(&level_name).await?;
+        self.context.players().set_level(self.player_id, &level_name);
+        self.respawn.lock().entered(&level_name, x.0, y.0);
+        self.context.player_stats().visited(&self.get_account_name(), &level_name);
+        let previous = self.account.lock().as_mut().map(|a| {
+            (a.x, a.y) = (x.0, y.0);
+            std::mem::replace(&mut a.level, level_name.clone())
+        });
+
+        // Get board data from level
+        let board_data = level.get_board_data();
+        tracing::debug!("Connection {} generated board data: {} bytes",
+            self.player_id.get(), board_data.len());
+
+        // === Send response packets ===
+        // Each packet is built using the packet_builder functions
+
+        // 1. Send PLO_SIGNATURE (73 = more than 8 players)
+        let mut sig_data = Vec::new();
+        {
+            use bytes::BytesMut;
+            let mut buf = BytesMut::new();
+            build_signature(&mut buf, 73);
+            sig_data = buf.to_vec();
+        }
+        let sig_packet = PacketOut::new(PacketTypeOut::Signature, sig_data);
+        self.send_packet(sig_packet).await?;
+        tracing::debug!("Connection {} sent PLO_SIGNATURE", self.player_id.get());
+
+        // 2. Send PLO_LEVELNAME (packet type 6)
+        let mut name_data = Vec::new();
+        {
+            use bytes::BytesMut;
+            let mut buf = BytesMut::new();
+            build_level_name(&mut buf, &level_name);
+            name_data = buf.to_vec();
+        }
+        let name_packet = PacketOut::new(PacketTypeOut::LevelName, name_data);
+        self.send_packet(name_packet).await?;
+        tracing::debug!("Connection {} sent PLO_LEVELNAME: {}", self.player_id.get(), level_name);
+
+        // 3. Send PLO_RAWDATA with board tiles (packet type 100)
+        // This is the main level data packet
+        let mut board_packet_data = Vec::new();
+        {
+            use bytes::BytesMut;
+            let mut buf = BytesMut::new();
+            build_raw_data(&mut buf, board_data.len() as u32, &board_data);
+            board_packet_data = buf.to_vec();
+        }
+        let board_packet = PacketOut::new(PacketTypeOut::RawData, board_packet_data);
+        self.send_packet(board_packet).await?;
+        tracing::debug!("Connection {} sent PLO_RAWDATA: {} bytes", self.player_id.get(), board_data.len());
+
+        // 4. Send PLO_LEVELMODTIME (packet type 39)
+        let mut mod_data = Vec::new();
+        {
+            use bytes::BytesMut;
+            let mut buf = BytesMut::new();
+            build_level_modtime(&mut buf, level.mod_time as u64);
+            mod_data = buf.to_vec();
+        }
+        let mod_packet = PacketOut::new(PacketTypeOut::LevelModTime, mod_data);
+        self.send_packet(mod_packet).await?;
+        tracing::debug!("Connection {} sent PLO_LEVELMODTIME: {}", self.player_id.get(), level.mod_time);
+
+        // 5. Send PLO_SETACTIVELEVEL (packet type 156)
+        let mut active_data = Vec::new();
+        {
+            use bytes::BytesMut;
+            let mut buf = BytesMut::new();
+            build_set_active_level(&mut buf, &level_name);
+            active_data = buf.to_vec();
+        }
+        let active_packet = PacketOut::new(PacketTypeOut::SetActiveLevel, active_data);
+        self.send_packet(active_packet).await?;
+        tracing::debug!("Connection {} sent PLO_SETACTIVELEVEL: {}", self.player_id.get(), level_name);
+
+        // 6. Send PLO_NEWWORLDTIME (packet type 42)
+        let mut time_data = Vec::new();
+        {
+            use bytes::BytesMut;
+            let mut buf = BytesMut::new();
+            build_new_world_time(&mut buf, self.context.world_time());
+            time_data = buf.to_vec();
+        }
+        let time_packet = PacketOut::new(PacketTypeOut::NewWorldTime, time_data);
+        self.send_packet(time_packet).await?;
+        tracing::debug!("Connection {} sent PLO_NEWWORLDTIME", self.player_id.get());
+
+        // 7. Send PLO_GHOSTICON (packet type 174)
+        // 0 = no ghosts (trial accounts)
+        let mut ghost_data = Vec::new();
+        {
+            use bytes::BytesMut;
+            let mut buf = BytesMut::new();
+            build_ghost_icon(&mut buf, 0);
+            ghost_data = buf.to_vec();
+        }
+        let ghost_packet = PacketOut::new(PacketTypeOut::GhostIcon, ghost_data);
+        self.send_packet(ghost_packet).await?;
+        tracing::debug!("Connection {} sent PLO_GHOSTICON", self.player_id.get());
+
+        // 8. Send PLO_ISLEADER (packet type 10)
+        // Tells the client they are the leader of this level
+        let mut leader_data = Vec::new();
+        {
+            use bytes::BytesMut;
+            let mut buf = BytesMut::new();
+            build_is_leader(&mut buf);
+            leader_data = buf.to_vec();
+        }
+        let leader_packet = PacketOut::new(PacketTypeOut::IsLeader, leader_data);
+        self.send_packet(leader_packet).await?;
+        tracing::debug!("Connection {} sent PLO_ISLEADER", self.player_id.get());
+
+        // 9. Weather and tint of the new level, and removal of the old level's
+        let ambience = self.context.ambience();
+        for effect in gserver_game::AmbienceEffect::ALL {
+            let image = ambience.image(&level_name, effect);
+            let was_shown = previous.as_deref().is_some_and(|old| !ambience.image(old, effect).image.is_empty());
+            if !image.image.is_empty() || was_shown {
+                self.send_packet(PacketOut::new(PacketTypeOut::ShowImg, image.to_packet_text().into_bytes())).await?;
+            }
+        }
+
+        // 10. Items and NPC props of the player's instance of the level
+        self.send_instance_state(&level_name).await?;
+
+        // 11. The players in range on the new level (see crate::interest)
+        if !self.is_rc() {
+            self.context.events().publish(GameEvent::PlayerPropsChanged { player: self.player_id, data: Vec::new() });
+        }
+
+        tracing::info!("Connection {} level warp complete, sent {} response packets",
+            self.player_id.get(), 8);
+
+        Ok(())
+    }
+
+    /// Handle player props packet (PLI_PLAYERPROPS = 2)
+    ///
+    /// # Purpose
+    /// Client updates its own properties (position, sprites, etc.)
+    ///
+    /// # Nicknames
+    /// Nickname changes are sanitized (see [`crate::nickname`]) and checked by
+    /// the word filter. The resulting nick is echoed back so the client shows
+    /// what other players see; a filtered nick keeps the previous one.
+    ///
+    /// # Blocking NPCs
+    /// A position inside a blocking NPC shape is answered with PLO_PUSHAWAY.
+    ///
+    /// # Frozen Players
+    /// Position and sprite props are ignored while the player is frozen
+    /// (see [`crate::control`]).
+    ///
+    /// # Muted Players
+    /// Chat bubbles of muted players are cleared (see [`crate::moderation`]).
+    ///
+    /// # Chat Commands
+    /// Chat starting with `/` may be a command (see [`crate::chatcommands`]);
+    /// its bubble is cleared once the command ran.
+    ///
+    /// # Death
+    /// CurPower 0 kills the player when `respawn` is on (see [`crate::respawn`]).
+    ///
+    /// # Gralats
+    /// RupeesCount is stored and goes to the economy ledger (see
+    /// [`crate::economy`]); an increase counts as gralats earned (see
+    /// [`crate::playerstats`]).
+    ///
+    /// # Carrying
+    /// CarrySprite / CarryNPC changes are checked before the player's instance
+    /// sees them (see [`gserver_game::carry`]); a refused pickup is reset on
+    /// the client.
+    ///
+    /// # Other Players
+    /// The other props go to the players in range (see [`crate::interest`]),
+    /// without the chat of commands and muted players.
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::setPropsFromPacket` in PlayerProps.cpp
+    async fn handle_player_props(&self, packet_data: &[u8]) -> Result<()> {
+        use bytes::BufMut;
+        use gserver_core::PixelCoord;
+        use gserver_game::properties::{decode_prop, split_props, PlayerProp, PropValue, OTHER_PLAYER_PROPS};
+
+        tracing::debug!("Connection {} sent PlayerProps: {} bytes",
+            self.player_id.get(), packet_data.len());
+
+        let props = split_props(packet_data);
+        if props.iter().any(|(prop, _)| crate::idle::GAMEPLAY_PROPS.contains(prop)) {
+            self.record_gameplay().await?;
+        }
+
+        // TODO: Store the remaining player properties
+        let mut carry = None;
+        let mut moved = false;
+        let mut forward = BytesMut::new();
+        let frozen = self.context.control().is_frozen(self.player_id);
+        for (prop, raw) in props {
+            let Ok(value) = decode_prop(prop, raw, self.client_version()) else { continue };
+            // Set when what the others see differs from what the client sent
+            let mut replaced = None;
+            match (prop, value) {
+                (PlayerProp::Nickname, PropValue::String(raw)) => {
+                    self.handle_nickname_change(&raw).await?;
+                    if self.disconnect_reason.lock().is_some() {
+                        return Ok(());
+                    }
+                }
+                (PlayerProp::X | PlayerProp::Y | PlayerProp::X2 | PlayerProp::Y2 | PlayerProp::Sprite, PropValue::Int(v)) => {
+                    if frozen {
+                        continue;
+                    }
+                    moved |= prop != PlayerProp::Sprite;
+                    if let Some(account) = self.account.lock().as_mut() {
+                        match prop {
+                            PlayerProp::X => account.x = HalfTile(v as i16).to_tiles().0,
+                            PlayerProp::Y => account.y = HalfTile(v as i16).to_tiles().0,
+                            PlayerProp::X2 => account.x = PixelCoord(v as i32).to_tiles().0,
+                            PlayerProp::Y2 => account.y = PixelCoord(v as i32).to_tiles().0,
+                            _ => account.sprite = v as u32,
+                        }
+                    }
+                }
+                (PlayerProp::CurChat, PropValue::String(chat)) if !chat.is_empty() => {
+                    let command = self.handle_chat_command(&chat).await?;
+                    if command || self.is_muted() {
+                        self.send_own_prop(PlayerProp::CurChat, "").await?;
+                        continue;
+                    }
+                    // A blocked chat was replaced by the warn message in the player's own bubble
+                    let Some(filtered) = self.apply_word_filter(&chat, FilterCheck::Chat).await? else {
+                        if self.disconnect_reason.lock().is_some() {
+                            return Ok(());
+                        }
+                        continue;
+                    };
+                    if filtered != chat {
+                        self.send_own_prop(PlayerProp::CurChat, &filtered).await?;
+                        replaced = Some(PropValue::String(filtered));
+                    }
+                }
+                (PlayerProp::CurPower, PropValue::Int(v)) => {
+                    self.record_power(v.clamp(0, 255) as u8);
+                    if v == 0 {
+                        self.handle_death().await?;
+                    }
+                }
+                (PlayerProp::RupeesCount, PropValue::Int(v)) => {
+                    let gralats = (v.max(0) as u32).min(crate::shops::MAX_GRALATS);
+                    let before = self.account.lock().as_mut().map(|a| std::mem::replace(&mut a.gralats, gralats));
+                    self.mark_account_dirty();
+                    if let Some(before) = before {
+                        self.audit_gralats(before, gralats, crate::economy::GralatSource::Client);
+                        if gralats > before {
+                            self.context.player_stats().add(&self.get_account_name(), crate::playerstats::Stat::Gralats, (gralats - before).into());
+                        }
+                    }
+                }
+                (PlayerProp::CarrySprite, PropValue::Int(v)) => {
+                    carry.get_or_insert(*self.carrying.lock()).0 = v as u8;
+                }
+                (PlayerProp::CarryNPC, PropValue::Int(v)) => {
+                    carry.get_or_insert(*self.carrying.lock()).1 = v as u32;
+                }
+                _ => {}
+            }
+            // Nicknames are echoed once filtered, carrying goes to the instance
+            if OTHER_PLAYER_PROPS.contains(&prop) && !matches!(prop, PlayerProp::Nickname | PlayerProp::CarrySprite | PlayerProp::CarryNPC) {
+                match &replaced {
+                    Some(value) => {
+                        gserver_game::properties::encode_prop(prop, value, self.client_version(), &mut forward);
+                    }
+                    None => {
+                        forward.put_u8(prop as u8 + 32);
+                        forward.extend_from_slice(raw);
+                    }
+                }
+            }
+        }
+        if self.is_authenticated() && !self.is_rc() && (moved || !forward.is_empty()) {
+            self.context.events().publish(GameEvent::PlayerPropsChanged { player: self.player_id, data: forward.to_vec() });
+        }
+
+        if let Some((carry_sprite, carry_npc)) = carry {
+            self.handle_carry_change(carry_sprite, carry_npc).await?;
+        }
+        if moved {
+            self.push_out_of_blockers().await?;
+        }
+        Ok(())
+    }
+
+    /// Push the player out of blocking NPCs they walked into (see [`gserver_game::physics`])
+    async fn push_out_of_blockers(&self) -> Result<()> {
+        use gserver_game::physics::{push_away, BoundingBox};
+        use gserver_protocol::{PacketOut, PacketTypeOut};
+
+        let level = self.context.levels().get_level(&self.get_level()).await?;
+        let blockers: Vec<_> = level.npcs.read().iter()
+            .filter_map(|npc| {
+                let (width, height) = npc.shape?;
+                Some(BoundingBox::new(npc.x as i32, npc.y as i32, width.into(), height.into()))
+            })
+            .collect();
+
+        if let Some((dx, dy)) = push_away(self.get_pixel_position(), blockers) {
+            tracing::debug!("Connection {} pushed away by ({}, {})", self.player_id.get(), dx, dy);
+            let data = crate::control::push_away_data(dx, dy);
+            self.send_packet(PacketOut::new(PacketTypeOut::PushAway, data)).await?;
+        }
+        Ok(())
+    }
+
+    /// Check a pickup and show the carried object to the player's instance
+    ///
+    /// # Arguments
+    /// * `carry_sprite` - CarrySprite prop sent by the client
+    /// * `carry_npc` - CarryNPC prop sent by the client
+    async fn handle_carry_change(&self, carry_sprite: u8, carry_npc: u32) -> Result<()> {
+        use gserver_game::carry::{CarriedObject, CARRY_NONE};
+        use gserver_protocol::{codecs::write_gshort, PacketOut, PacketTypeOut};
+
+        let accepted = match CarriedObject::from_props(carry_sprite, carry_npc) {
+            Ok(Some(CarriedObject::Npc(npc))) => self.claim_carried_npc(npc).await?,
+            Ok(_) => {
+                self.context.carry().lock().release(self.player_id);
+                true
+            }
+            Err(sprite) => {
+                tracing::debug!("Connection {} tried to carry unknown sprite {}", self.player_id.get(), sprite);
+                false
+            }
+        };
+        let (carry_sprite, carry_npc) = if accepted { (carry_sprite, carry_npc) } else { (CARRY_NONE, 0) };
+        *self.carrying.lock() = (carry_sprite, carry_npc);
+
+        let data = carry_props_data(carry_sprite, carry_npc, self.client_version());
+        if !accepted {
+            self.send_packet(PacketOut::new(PacketTypeOut::PlayerProps, data.clone())).await?;
+        }
+
+        let mut buf = BytesMut::new();
+        write_gshort(&mut buf, self.player_id.get() as i16);
+        buf.extend_from_slice(&data);
+        self.publish_to_instance(self.instance_key(), PacketTypeOut::OtherPlayerProps, &buf);
+        Ok(())
+    }
+
+    /// Claim an NPC the player wants to lift
+    ///
+    /// # Returns
+    /// `false` if the NPC isn't on the player's level, isn't `canbecarried`,
+    /// is out of reach or is carried by someone else
+    async fn claim_carried_npc(&self, npc: u32) -> Result<bool> {
+        use gserver_game::carry::within_reach;
+
+        let level = self.context.levels().get_level(&self.get_level()).await?;
+        let Some(npc_ref) = level.get_npc(npc) else { return Ok(false) };
+        if !npc_ref.carryable || !within_reach(self.get_pixel_position(), (npc_ref.x as i32, npc_ref.y as i32)) {
+            return Ok(false);
+        }
+        Ok(self.context.carry().lock().claim_npc(self.player_id, npc))
+    }
+
+    /// Handle throw carried packet (PLI_THROWCARRIED = 11)
+    ///
+    /// # Purpose
+    /// Client throws the object it carries
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {throw data}
+    /// ```
+    ///
+    /// # Server Actions
+    /// - Relays PLO_THROWCARRIED `{GSHORT player_id}{throw data}` to the instance
+    /// - Moves a thrown NPC to its landing spot
+    /// - Publishes [`GameEvent::ObjectThrown`] so players at the landing spot are hurt
+    /// - Clears the carry props
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_THROWCARRIED` in PlayerClientPackets.cpp
+    async fn handle_throw_carried(&self, packet_data: &[u8]) -> Result<()> {
+        use gserver_game::carry::{throw_landing, CarriedObject, CARRY_NONE};
+        use gserver_protocol::{codecs::write_gshort, PacketTypeOut};
+
+        let (carry_sprite, carry_npc) = *self.carrying.lock();
+        let Ok(Some(object)) = CarriedObject::from_props(carry_sprite, carry_npc) else {
+            tracing::debug!("Connection {} threw without carrying anything", self.player_id.get());
+            return Ok(());
+        };
+        self.record_gameplay().await?;
+
+        let direction = self.account.lock().as_ref().map_or(2, |a| (a.sprite % 4) as u8);
+        let landing = throw_landing(self.get_pixel_position(), direction);
+        if let CarriedObject::Npc(npc) = object {
+            self.context.carry().lock().release(self.player_id);
+            let level = self.context.levels().get_level(&self.get_level()).await?;
+            level.move_npc(npc, landing.0 as f32, landing.1 as f32);
+        }
+        *self.carrying.lock() = (CARRY_NONE, 0);
+
+        tracing::debug!("Connection {} threw {:?} to {:?}", self.player_id.get(), object, landing);
+
+        let instance = self.instance_key();
+        let mut buf = BytesMut::new();
+        write_gshort(&mut buf, self.player_id.get() as i16);
+        buf.extend_from_slice(packet_data);
+        self.publish_to_instance(instance.clone(), PacketTypeOut::ThrowCarried, &buf);
+
+        let mut props = BytesMut::new();
+        write_gshort(&mut props, self.player_id.get() as i16);
+        props.extend_from_slice(&carry_props_data(CARRY_NONE, 0, self.client_version()));
+        self.publish_to_instance(instance.clone(), PacketTypeOut::OtherPlayerProps, &props);
+
+        self.context.events().publish(GameEvent::ObjectThrown {
+            thrower: self.player_id,
+            instance,
+            landing,
+            power: object.impact_power(),
+        });
+        Ok(())
+    }
+
+    /// Validate, store and echo a nickname change
+    async fn handle_nickname_change(&self, raw: &str) -> Result<()> {
+        use gserver_game::properties::PlayerProp;
+
+        let Some((account_name, current)) = self.account.lock().as_ref()
+            .map(|a| (a.name.clone(), a.nick.clone()))
+        else {
+            return Ok(());
+        };
+
+        let is_staff = self.is_staff();
+        let sanitized = {
+            let config = self.context.config().read();
+            crate::nickname::sanitize_nickname(raw, &account_name, is_staff, &config)
+        };
+
+        let nick = match self.apply_word_filter(&sanitized, FilterCheck::Nick).await? {
+            Some(nick) => nick,
+            None if self.disconnect_reason.lock().is_some() => return Ok(()),
+            None => current.clone(),
+        };
+
+        if nick != current {
+            if let Some(account) = self.account.lock().as_mut() {
+                account.nick = nick.clone();
+            }
+            self.mark_account_dirty();
+            tracing::debug!("Connection {} changed nick to '{}'", self.player_id.get(), nick);
+        }
+
+        // Echo whenever the client's copy differs from what was stored
+        if nick != raw {
+            self.send_own_prop(PlayerProp::Nickname, &nick).await?;
+        }
+        Ok(())
+    }
+
+    /// Handle board modify packet (PLI_BOARDMODIFY = 1)
+    ///
+    /// # Purpose
+    /// Client modifies tiles on the level (destroying bushes, etc.)
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {GUCHAR x}{GUCHAR y}{GUCHAR width}{GUCHAR height}{STRING tiles}
+    /// ```
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_BOARDMODIFY` in PlayerClientPackets.cpp:77
+    async fn handle_board_modify(&self, packet_data: &[u8]) -> Result<()> {
+        use gserver_protocol::codecs::*;
+
+        let mut buf = BytesMut::from(packet_data);
+        let x = read_gchar(&mut buf)? as usize;
+        let y = read_gchar(&mut buf)? as usize;
+        let w = read_gchar(&mut buf)? as usize;
+        let h = read_gchar(&mut buf)? as usize;
+        let _tiles = read_gstring(&mut buf)?;
+
+        tracing::debug!("Connection {} board modify: x={}, y={}, w={}, h={}",
+            self.player_id.get(), x, y, w, h);
+        // TODO: Update level board and broadcast to other players
+
+        self.context.events().publish(GameEvent::LevelModified {
+            level: self.get_level(),
+            x: x as u8,
+            y: y as u8,
+            width: w as u8,
+            height: h as u8,
+        });
+        Ok(())
+    }
+
+    /// Handle to all packet (PLI_TOALL = 13)
+    ///
+    /// # Purpose
+    /// Client sends a message to all players in the level
+    ///
+    /// # C++ Equivalence
+    /// Matches chat handling in PlayerClient
+    async fn handle_to_all(&self, packet_data: &[u8]) -> Result<()> {
+        use gserver_protocol::codecs::*;
+
+        let mut buf = BytesMut::from(packet_data);
+        let message = read_gstring(&mut buf)?;
+        let Some(message) = self.apply_word_filter(&message, FilterCheck::ToAll).await? else {
+            return Ok(());
+        };
+        self.record_gameplay().await?;
+
+        tracing::info!("Connection {} chat: {}", self.player_id.get(), message);
+        // TODO: Broadcast to all players in the level
+
+        self.context.events().publish(GameEvent::ChatMessage {
+            id: self.player_id,
+            level: self.get_level(),
+            message,
+        });
+        Ok(())
+    }
+
+    /// Handle language packet (PLI_LANGUAGE = 37)
+    ///
+    /// # Purpose
+    /// Client sets its language preference. It is saved on the account and
+    /// used to translate server texts (see [`PlayerConnection::translate`]).
+    ///
+    /// # C++ Equivalence
+    /// Matches `Player::msgPLI_LANGUAGE` in Player.cpp:1347
+    async fn handle_language(&self, packet_data: &[u8]) -> Result<()> {
+        // Language packet sends plain null-terminated string, not GString
+        // C++: pPacket.readString("") - reads until null or end
+        let language = if packet_data.is_empty() {
+            String::new()
+        } else {
+            // Find null terminator or use entire string
+            let end = packet_data.iter().position(|&b| b == 0).unwrap_or(packet_data.len());
+            String::from_utf8_lossy(&packet_data[..end]).to_string()
+        };
+
+        let language = if language.is_empty() {
+            tracing::debug!("Connection {} language: <empty, defaulting to English>", self.player_id.get());
+            DEFAULT_LANGUAGE.to_string()
+        } else {
+            tracing::debug!("Connection {} language: {}", self.player_id.get(), language);
+            language
+        };
+
+        self.set_language(language.clone());
+        let changed = match self.account.lock().as_mut() {
+            Some(account) if account.language != language => {
+                account.language = language;
+                true
+            }
+            _ => false,
+        };
+        if changed {
+            self.mark_account_dirty();
+        }
+        Ok(())
+    }
+
+    /// Handle map info packet (PLI_MAPINFO = 39)
+    ///
+    /// # Purpose
+    /// Client requests map information
+    ///
+    /// # C++ Equivalence
+    /// No explicit handler in C++, packet falls through to msgPLI_NULL
+    async fn handle_map_info(&self, packet_data: &[u8]) -> Result<()> {
+        tracing::debug!("Connection {} map info request: {} bytes", self.player_id.get(), packet_data.len());
+        // No response needed for this packet
+        Ok(())
+    }
+
+    /// Handle request text packet (PLI_REQUESTTEXT = 152)
+    ///
+    /// # Purpose
+    /// Client asks for information from a server-side service (serverlist,
+    /// PM servers, subscriptions), as comma tokens. Run through the client
+    /// text commands (see [`crate::clienttext`]).
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {text}
+    /// ```
+    ///
+    /// # C++ Equivalence
+    /// Matches `Player::msgPLI_REQUESTTEXT`
+    async fn handle_request_text(&self, packet_data: &[u8]) -> Result<()> {
+        self.handle_client_text(packet_data).await
+    }
+
+    /// Handle packet count packet (PLI_PACKETCOUNT = 31)
+    ///
+    /// # Purpose
+    /// Client reports how many packets it sent since the last report. A
+    /// mismatch is logged and counted; with `packetcountdisconnect=true` the
+    /// client is also disconnected.
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {GUSHORT count}
+    /// ```
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_PACKETCOUNT` in PlayerClientPackets.cpp
+    async fn handle_packet_count(&self, packet_data: &[u8]) -> Result<()> {
+        use gserver_protocol::codecs::*;
+
+        let mut buf = BytesMut::from(packet_data);
+        let reported = read_gushort(&mut buf)?;
+
+        let (result, desyncs) = {
+            let mut counter = self.packet_counter.lock();
+            (counter.verify(reported), counter.desyncs())
+        };
+
+        if let PacketCountResult::Desync { counted, reported } = result {
+            tracing::warn!("Connection {} ({}) had an invalid packet count: received {}, reported {} ({} desyncs)",
+                self.player_id.get(), self.get_account_name(), counted, reported, desyncs);
+
+            if self.context.config().read().packet_count_disconnect {
+                self.disconnect("Invalid packet count").await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle tamper check packet (PLI_TAMPERCHECK = 95)
+    ///
+    /// # Purpose
+    /// Client reports that its integrity check failed. The response comes
+    /// from the registered integrity policies, falling back to the
+    /// `tamperaction` option.
+    ///
+    /// # C++ Equivalence
+    /// No handler in C++ (falls through to msgPLI_NULL)
+    async fn handle_tamper_check(&self, packet_data: &[u8]) -> Result<()> {
+        let report = TamperReport {
+            id: self.player_id,
+            account: self.get_account_name(),
+            data: packet_data.to_vec(),
+        };
+
+        let default = {
+            let config = self.context.config().read();
+            TamperAction::from_name(&config.tamper_action).unwrap_or_else(|| {
+                tracing::warn!("Unknown tamperaction '{}', using log", config.tamper_action);
+                TamperAction::Log
+            })
+        };
+        let action = self.context.integrity().evaluate(&report, default);
+        if action == TamperAction::Ignore {
+            return Ok(());
+        }
+
+        tracing::warn!("Connection {} ({}) failed tamper check ({} bytes), action: {}",
+            self.player_id.get(), report.account, report.data.len(), action.name());
+
+        if action >= TamperAction::WarnRc {
+            self.context.events().publish(GameEvent::IntegrityViolation {
+                id: self.player_id,
+                account: report.account.clone(),
+                reason: "failed tamper check".to_string(),
+            });
+        }
+        if action == TamperAction::Disconnect {
+            self.disconnect("Client integrity check failed").await?;
+        }
+        Ok(())
+    }
+
+    /// Handle send text packet (PLI_SENDTEXT = 154)
+    ///
+    /// # Purpose
+    /// Client sends to a server-side service (listserver IRC, serverlist),
+    /// as comma tokens. Run through the client text commands (see
+    /// [`crate::clienttext`]).
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {text}
+    /// ```
+    ///
+    /// # C++ Equivalence
+    /// Matches `Player::msgPLI_SENDTEXT`
+    async fn handle_send_text(&self, packet_data: &[u8]) -> Result<()> {
+        self.handle_client_text(packet_data).await
+    }
+
+    /// Run a PLI_REQUESTTEXT / PLI_SENDTEXT and carry out what it returns
+    async fn handle_client_text(&self, packet_data: &[u8]) -> Result<()> {
+        use gserver_protocol::{PacketOut, PacketTypeOut};
+
+        let text = ClientText::new(self.player_id, &self.get_account_name(), &String::from_utf8_lossy(packet_data));
+        let Some(actions) = self.context.client_texts().dispatch(&text, &self.context) else {
+            tracing::debug!("Connection {} unhandled client text: {:?}", self.player_id.get(), text.tokens);
+            return Ok(());
+        };
+        for action in actions {
+            match action {
+                TextAction::Reply(line) => {
+                    self.send_packet(PacketOut::new(PacketTypeOut::ServerText, line.into_bytes())).await?;
+                }
+                TextAction::Forward(line) => {
+                    if !self.context.listserver().request_for_player(self.player_id, &line) {
+                        tracing::debug!("Connection {} text request dropped, listserver not connected: {}",
+                            self.player_id.get(), line);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle RC chat packet (PLI_RC_CHAT = 79)
+    ///
+    /// # Purpose
+    /// RC sends a chat line. Lines starting with `/` are server commands;
+    /// anything else goes to the staff chat channel (see [`crate::rcchat`]).
+    ///
+    /// # Commands
+    /// - `/msg <account> <text>` - Staff chat line for one account's RCs
+    /// - `/backup` - Create a snapshot of the server folder (needs PLPERM_SETSERVEROPTIONS)
+    /// - `/motd` - Show the server message template
+    /// - `/setmotd <html>` - Replace the server message and save servermessage.html (needs
+    ///   PLPERM_SETSERVEROPTIONS)
+    /// - `/ping [account]` - Show round-trip times (all players' average without an account)
+    /// - `/stats` - Show connection, packet, compression and batching statistics
+    /// - `/status` - Show the uptime, players and each listserver's latency (see [`crate::listlatency`])
+    /// - `/levelstats <level>` - Show a level's players, NPCs, broadcast rates and script time
+    /// - `/scriptstats [name]` - Show the run time and memory of the scripts (whose names
+    ///   contain `name`)
+    /// - `/npcserver` - Show the serverside engine's status (see [`crate::npcstatus`])
+    /// - `/scripthistory <weapon|class> <name>` - List the kept versions of a script;
+    ///   `/scriptdiff ... <version>` shows the lines changed since one and
+    ///   `/scriptrevert ... <version>` puts it back (see [`crate::scripthistory`])
+    /// - `/updatelevel <level>...` - Reload levels from disk and send the changes to their players
+    ///   (needs PLPERM_UPDATELEVEL)
+    /// - `/country [account]` - Show the countries of an account's connections and their
+    ///   levels, or the player count of each country
+    /// - `/listing [icon|banner|tags <value>]` - Show or change the server browser listing
+    ///   extras until the next config reload (needs PLPERM_SETSERVEROPTIONS)
+    /// - `/ambience [<effect> <0-100|off> [scope] [in <seconds>]]` - Change weather and
+    ///   tint, or list scheduled changes (needs PLPERM_SETSERVEROPTIONS)
+    /// - `/loglevel [module] <level>` - Change the log level, or show the filter (needs
+    ///   PLPERM_SETSERVEROPTIONS)
+    /// - `/npcsave [name]` - Show an NPC's saved state, or list the saved NPCs
+    /// - `/npcreset <name>` - Delete an NPC's saved state (needs PLPERM_NPCCONTROL)
+    /// - `/social <account> [addfriend|removefriend|ignore|unignore|pmfriendsonly <value>]` -
+    ///   Show or change an account's friend and ignore lists (needs PLPERM_SETATTRIBUTES)
+    /// - `/announce [[in <seconds>] <text>]` - Send an admin message from the server to
+    ///   every player, now or later, or list the scheduled ones
+    /// - `/mute <account> [minutes]`, `/unmute <account>` - Stop an account's chat and PMs
+    /// - `/jail <account> [minutes]`, `/unjail <account>` - Keep an account on the jail level
+    /// - `/warphome <account>` - Warp an account to the start location (the sanctions need
+    ///   PLPERM_BAN)
+    /// - `/rights <account> [rights]` - Show an account's rights, or set them (hex with `0x`)
+    ///   and apply them to its connections right away; only the issuer's own rights can be
+    ///   set, and not on itself (see [`crate::staffrights`])
+    /// - `/ban <account> <minutes> [reason]` - Ban an account for a while (see [`crate::bans`])
+    /// - `/banpc <account> [reason]`, `/unbanpc <account>` - Ban or unban an account and
+    ///   its PC and hard disk ids; `/banpc` alone lists the id bans
+    /// - `/alts <account>` - List the accounts that logged in from an account's IPs
+    ///   (needs PLPERM_BAN, see [`crate::alts`])
+    /// - `/approve <account>`, `/deny <account>` - Let in or refuse a login held for
+    ///   approval, or list the held logins (needs PLPERM_BAN, see [`crate::loginpolicy`])
+    /// - `/chatlog <account> [span]` - Search the chat logs for an account's lines in the
+    ///   last `span` (`30m`, `2h`, `7d`; default 1h; needs PLPERM_BAN, see [`crate::chatlog`])
+    /// - `/economy <account> [span]` - List an account's gralat changes in the last `span`
+    ///   (default 1h; needs PLPERM_VIEWATTRIBUTES, see [`crate::economy`])
+    /// - `/setgralats <account> <amount>` - Set an account's gralats (needs
+    ///   PLPERM_SETATTRIBUTES)
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerRC::msgPLI_RC_CHAT` in PlayerRCPackets.cpp
+    async fn handle_rc_chat(&self, packet_data: &[u8]) -> Result<()> {
+        use gserver_accounts::PLPERM_SETSERVEROPTIONS;
+        use gserver_protocol::{PacketOut, PacketTypeOut};
+
+        const NO_SERVER_OPTIONS: &str = "You don't have the right to change server options";
+        let text = String::from_utf8_lossy(packet_data).trim().to_string();
+        let can_use_rc = self.account.lock().as_ref().map(|a| a.can_use_rc()).unwrap_or(false);
+        if !can_use_rc {
+            tracing::warn!("Connection {} sent RC chat without RC rights", self.player_id.get());
+            return Ok(());
+        }
+
+        tracing::info!("Connection {} RC chat: {}", self.player_id.get(), text);
+
+        if !text.starts_with('/') || text.starts_with("/msg") {
+            let (to, message) = match crate::rcchat::parse_msg(&text) {
+                Some((to, message)) => (Some(to.to_string()), message.to_string()),
+                None if text.starts_with('/') => return Ok(()),
+                None if text.is_empty() => return Ok(()),
+                None => (None, text),
+            };
+            self.context.events().publish(GameEvent::RcChat {
+                id: self.player_id,
+                account: self.get_account_name(),
+                message,
+                to,
+            });
+            return Ok(());
+        }
+
+        let reply = match text.split_whitespace().next() {
+            Some("/backup") if !self.has_rc_right(Some(PLPERM_SETSERVEROPTIONS)) => NO_SERVER_OPTIONS.to_string(),
+            Some("/backup") => {
+                let backups = self.context.backups().clone();
+                match tokio::task::spawn_blocking(move || backups.create_snapshot()).await {
+                    Ok(Ok(info)) => format!("Backup created: {} ({} bytes)",
+                        info.path.file_name().unwrap_or_default().to_string_lossy(), info.size),
+                    Ok(Err(e)) => format!("Backup failed: {}", e),
+                    Err(e) => format!("Backup failed: {}", e),
+                }
+            }
+            Some("/motd") => {
+                let message = self.context.config().read().server_message.clone();
+                if message.trim().is_empty() {
+                    "No server message set".to_string()
+                } else {
+                    format!("Server message: {}", message.replace("\r\n", "<br>").replace('\n', "<br>"))
+                }
+            }
+            Some("/setmotd") if !self.has_rc_right(Some(PLPERM_SETSERVEROPTIONS)) => NO_SERVER_OPTIONS.to_string(),
+            Some("/setmotd") => {
+                let message = text["/setmotd".len()..].trim().to_string();
+                let path = self.context.server_dir().join("config").join("servermessage.html");
+                self.context.config().write().server_message = message.clone();
+                match std::fs::write(&path, &message) {
+                    Ok(()) => format!("Server message updated by {}", self.get_account_name()),
+                    Err(e) => format!("Server message updated but not saved: {}", e),
+                }
+            }
+            Some("/ping") => {
+                let latency = self.context.latency();
+                match text.split_whitespace().nth(1) {
+                    Some(account) => {
+                        let found = latency.find_account(account);
+                        if found.is_empty() {
+                            format!("No round-trip time for {}", account)
+                        } else {
+                            let times: Vec<String> = found.iter()
+                                .map(|(id, rtt)| format!("#{} {} ms", id.get(), rtt.as_millis()))
+                                .collect();
+                            format!("Ping for {}: {}", account, times.join(", "))
+                        }
+                    }
+                    None => match latency.average() {
+                        Some(rtt) => format!("Average ping: {} ms over {} players", rtt.as_millis(), latency.len()),
+                        None => "No round-trip times yet".to_string(),
+                    },
+                }
+            }
+            Some("/stats") => self.context.stats().summary(),
+            Some("/status") => {
+                let listservers: Vec<String> = self.context.listservers().iter()
+                    .map(|status| {
+                        let (host, port) = status.address();
+                        format!("{} ({}:{})", status.latency_report().describe(), host, port)
+                    })
+                    .collect();
+                format!("Up {}, {} players; {}", crate::motd::format_uptime(self.context.uptime()),
+                    self.context.online_count(), listservers.join("; "))
+            }
+            Some("/updatelevel") => {
+                let names: Vec<String> = text.split_whitespace().skip(1).map(String::from).collect();
+                if names.is_empty() {
+                    "Usage: /updatelevel <level>...".to_string()
+                } else {
+                    self.update_levels(&names)
+                }
+            }
+            Some("/levelstats") => match text.split_whitespace().nth(1) {
+                Some(level) => self.context.level_stats().report(level, &self.context, std::time::Instant::now()).describe(),
+                None => "Usage: /levelstats <level>".to_string(),
+            },
+            Some("/scriptstats") => {
+                crate::levelstats::describe_scripts(&self.context.scripts().stats(), text.split_whitespace().nth(1))
+            }
+            Some("/npcserver") => crate::npcstatus::NpcServerStatus::gather(&self.context).describe(),
+            Some(command @ ("/scripthistory" | "/scriptdiff" | "/scriptrevert")) => {
+                let mut lines = self.rc_script_history(command, &text.split_whitespace().skip(1).collect::<Vec<_>>());
+                let last = lines.pop().unwrap_or_default();
+                for line in lines {
+                    self.send_packet(PacketOut::new(PacketTypeOut::ServerText, line.into_bytes())).await?;
+                }
+                last
+            }
+            Some("/listing") if !self.has_rc_right(Some(PLPERM_SETSERVEROPTIONS)) => NO_SERVER_OPTIONS.to_string(),
+            Some("/listing") => {
+                let status = self.context.listserver();
+                let mut listing = status.listing();
+                let mut args = text.splitn(3, char::is_whitespace).skip(1);
+                let field = args.next();
+                let value = args.next().unwrap_or("").trim();
+                let known = match field {
+                    Some("icon") => { listing.icon = value.to_string(); true }
+                    Some("banner") => { listing.banner = value.to_string(); true }
+                    Some("tags") => {
+                        listing.tags = value.split(',').map(str::trim).filter(|t| !t.is_empty()).map(Into::into).collect();
+                        true
+                    }
+                    Some(_) => false,
+                    None => true,
+                };
+                if !known {
+                    "Usage: /listing [icon|banner|tags <value>]".to_string()
+                } else {
+                    let changed = field.is_some() && self.context.set_listing(listing.clone());
+                    format!("{}icon {}, banner {}, tags [{}]", if changed { "Listing updated: " } else { "Listing: " },
+                        listing.icon, listing.banner, listing.tags.join(", "))
+                }
+            }
+            Some("/country") => {
+                let geoip = self.context.geoip();
+                match text.split_whitespace().nth(1) {
+                    _ if !geoip.is_enabled() => "GeoIP is off (no geoipdb)".to_string(),
+                    Some(account) => {
+                        let found = self.context.players().get_by_account(account);
+                        if found.is_empty() {
+                            format!("{} is not online", account)
+                        } else {
+                            let players: Vec<String> = found.iter()
+                                .map(|player| format!("#{} {:?} on {} from {}", player.id.get(), player.player_type,
+                                    player.properties.lock().cur_level,
+                                    geoip.country(player.id).unwrap_or_else(|| "unknown".to_string())))
+                                .collect();
+                            format!("{}: {}", account, players.join(", "))
+                        }
+                    }
+                    None => {
+                        let counts: Vec<String> = geoip.counts().iter()
+                            .map(|(country, count)| format!("{} {}", country, count))
+                            .collect();
+                        if counts.is_empty() {
+                            "No player countries known".to_string()
+                        } else {
+                            format!("Players by country: {}", counts.join(", "))
+                        }
+                    }
+                }
+            }
+            Some(command @ ("/freeze" | "/unfreeze" | "/fullstop")) => {
+                self.rc_control(command, text.split_whitespace().nth(1))
+            }
+            Some("/ambience") if !self.has_rc_right(Some(PLPERM_SETSERVEROPTIONS)) => NO_SERVER_OPTIONS.to_string(),
+            Some("/ambience") => {
+                use crate::ambience::AmbienceRequest;
+
+                let args: Vec<&str> = text.split_whitespace().skip(1).collect();
+                let now = std::time::Instant::now();
+                if args.is_empty() {
+                    let lines = self.context.ambience().schedule_lines(now);
+                    if lines.is_empty() {
+                        "No ambience changes scheduled".to_string()
+                    } else {
+                        format!("Scheduled: {}", lines.join("; "))
+                    }
+                } else {
+                    match AmbienceRequest::parse(&args) {
+                        Ok(request) => {
+                            let summary = format!("{} {} on {}", request.effect,
+                                request.intensity.map_or("off".to_string(), |i| i.to_string()), request.scope);
+                            let delay = request.delay;
+                            match self.context.ambience().apply(request, now) {
+                                Ok(()) if delay.is_zero() => format!("Ambience: {}", summary),
+                                Ok(()) => format!("Ambience: {} in {}s", summary, delay.as_secs()),
+                                Err(e) => format!("Ambience not changed: {}", e),
+                            }
+                        }
+                        Err(usage) => usage,
+                    }
+                }
+            }
+            Some("/loglevel") if !self.has_rc_right(Some(PLPERM_SETSERVEROPTIONS)) => NO_SERVER_OPTIONS.to_string(),
+            Some("/loglevel") => {
+                let args: Vec<&str> = text.split_whitespace().skip(1).collect();
+                let (module, level) = match args.as_slice() {
+                    [] => (None, None),
+                    [level] => (None, Some(*level)),
+                    [module, level] => (Some(*module), Some(*level)),
+                    _ => (None, Some("")),
+                };
+                match level {
+                    None => format!("Log levels: {}", crate::logging::current_filter().unwrap_or_default()),
+                    Some("") => "Usage: /loglevel [module] <level>".to_string(),
+                    Some(level) => match crate::logging::set_level(module, level) {
+                        Ok(filter) => {
+                            tracing::info!("{} changed log levels to {}", self.get_account_name(), filter);
+                            format!("Log levels: {}", filter)
+                        }
+                        Err(e) => format!("Log levels not changed: {}", e),
+                    },
+                }
+            }
+            Some("/npcsave") => {
+                let saves = self.context.npc_saves();
+                match text.split_whitespace().nth(1) {
+                    Some(name) => match saves.get(name) {
+                        Some(save) => {
+                            let vars: Vec<String> = save.vars.iter().map(|(k, v)| format!("this.{}={}", k, v)).collect();
+                            format!("NPC {}: image {}, level {} at {},{}; {}", save.name,
+                                if save.image.is_empty() { "-" } else { &save.image },
+                                if save.level.is_empty() { "-" } else { &save.level },
+                                save.x, save.y,
+                                if vars.is_empty() { "no variables".to_string() } else { vars.join(", ") })
+                        }
+                        None => format!("No save for NPC {}", name),
+                    },
+                    None => {
+                        let names = saves.names();
+                        if names.is_empty() {
+                            "No NPC saves".to_string()
+                        } else {
+                            format!("NPC saves: {}", names.join(", "))
+                        }
+                    }
+                }
+            }
+            Some("/npcreset") if !self.has_rc_right(Some(gserver_accounts::PLPERM_NPCCONTROL)) => {
+                "You don't have the right to reset NPCs".to_string()
+            }
+            Some("/npcreset") => match text.split_whitespace().nth(1) {
+                Some(name) => match self.context.npc_saves().reset(name) {
+                    Ok(true) => {
+                        tracing::info!("{} reset the save of NPC {}", self.get_account_name(), name);
+                        format!("NPC {} reset", name)
+                    }
+                    Ok(false) => format!("No save for NPC {}", name),
+                    Err(e) => format!("NPC {} reset, but its file wasn't deleted: {}", name, e),
+                },
+                None => "Usage: /npcreset <name>".to_string(),
+            },
+            Some("/social") => {
+                let args: Vec<&str> = text.split_whitespace().skip(1).collect();
+                self.rc_social(&args).await
+            }
+            Some(command @ ("/mute" | "/unmute" | "/jail" | "/unjail" | "/warphome")) => {
+                let args: Vec<&str> = text.split_whitespace().skip(1).collect();
+                self.rc_sanction(&command[1..], &args)
+            }
+            Some("/announce") => {
+                let args: Vec<&str> = text.split_whitespace().skip(1).collect();
+                self.rc_announce(&args)
+            }
+            Some("/rights") => {
+                let args: Vec<&str> = text.split_whitespace().skip(1).collect();
+                self.rc_rights(&args)
+            }
+            Some("/ban") => {
+                let args: Vec<&str> = text.split_whitespace().skip(1).collect();
+                self.rc_ban(&args)
+            }
+            Some("/banpc") => {
+                let args: Vec<&str> = text.split_whitespace().skip(1).collect();
+                self.rc_ban_pc(&args)
+            }
+            Some("/unbanpc") => match text.split_whitespace().nth(1) {
+                Some(account) => self.rc_unban_pc(account),
+                None => "Usage: /unbanpc <account>".to_string(),
+            },
+            Some("/alts") if !self.has_rc_right(Some(gserver_accounts::PLPERM_BAN)) => {
+                "You don't have the right to look up alts".to_string()
+            }
+            Some("/alts") => match text.split_whitespace().nth(1) {
+                Some(account) => crate::alts::describe(self.context.ip_history(), self.context.server_dir(),
+                    account, gserver_game::moderation::unix_now()),
+                None => "Usage: /alts <account>".to_string(),
+            },
+            Some(command @ ("/approve" | "/deny")) => self.rc_resolve_login(command == "/approve", text.split_whitespace().nth(1)),
+            Some("/chatlog") => {
+                let args: Vec<&str> = text.split_whitespace().skip(1).collect();
+                self.rc_chat_log(&args)
+            }
+            Some("/economy") if !self.has_rc_right(Some(gserver_accounts::PLPERM_VIEWATTRIBUTES)) => {
+                "You don't have the right to view attributes".to_string()
+            }
+            Some("/economy") => {
+                let mut args = text.split_whitespace().skip(1);
+                match (args.next(), crate::chatlog::parse_span(args.next().unwrap_or("1h"))) {
+                    (Some(account), Some(span)) => {
+                        let now = gserver_game::moderation::unix_now();
+                        let entries = self.context.economy().search(account, now.saturating_sub(span), now);
+                        crate::economy::describe(account, text.split_whitespace().nth(2).unwrap_or("1h"), &entries)
+                    }
+                    _ => "Usage: /economy <account> [span like 30m, 2h or 7d]".to_string(),
+                }
+            }
+            Some("/setgralats") => self.rc_set_gralats(&text.split_whitespace().skip(1).collect::<Vec<_>>()),
+            _ => return Ok(()),
+        };
+
+        self.send_packet(PacketOut::new(PacketTypeOut::ServerText, reply.into_bytes())).await
+    }
+
+    /// Handle NPC-Server status query (PLI_NPCSERVERQUERY = 94)
+    ///
+    /// # Purpose
+    /// The RC asks where the NPC-Server is before opening an NC connection;
+    /// the answer is PLO_NPCSERVERADDR with this server's address (see
+    /// [`crate::npcstatus::server_addr_data`]). The engine's status stays on
+    /// `/npcserver`. Players without RC rights are ignored.
+    async fn handle_npcserver_query(&self) -> Result<()> {
+        use gserver_protocol::{PacketOut, PacketTypeOut};
+
+        let can_use_rc = self.account.lock().as_ref().map(|a| a.can_use_rc()).unwrap_or(false);
+        if !self.is_rc() || !can_use_rc {
+            tracing::debug!("Connection {} sent NPC-Server query without RC rights", self.player_id.get());
+            return Ok(());
+        }
+        let data = crate::npcstatus::server_addr_data(&self.context.config().read());
+        self.send_packet(PacketOut::new(PacketTypeOut::NpcServerAddr, data)).await
+    }
+
+    /// Handle RC update levels packet (PLI_RC_UPDATELEVELS = 62)
+    ///
+    /// # Purpose
+    /// RC asks for levels to be reloaded from disk (see [`crate::levelreload`]).
+    /// Needs PLPERM_UPDATELEVEL.
+    ///
+    /// # Format
+    /// `{GSHORT count}{GSTRING level}*`
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerRC::msgPLI_RC_UPDATELEVELS` in PlayerRCPackets.cpp
+    async fn handle_rc_update_levels(&self, packet_data: &[u8]) -> Result<()> {
+        use gserver_protocol::codecs::*;
+        use gserver_protocol::{PacketOut, PacketTypeOut};
+
+        let can_use_rc = self.account.lock().as_ref().map(|a| a.can_use_rc()).unwrap_or(false);
+        if !can_use_rc {
+            tracing::warn!("Connection {} sent RC update levels without RC rights", self.player_id.get());
+            return Ok(());
+        }
+
+        let mut buf = BytesMut::from(packet_data);
+        let count = read_gshort(&mut buf)?.max(0);
+        let mut names = Vec::new();
+        for _ in 0..count {
+            names.push(read_gstring(&mut buf)?);
+        }
+        let reply = self.update_levels(&names);
+        self.send_packet(PacketOut::new(PacketTypeOut::ServerText, reply.into_bytes())).await
+    }
+
+    /// Reload levels for an RC and describe what happened
+    fn update_levels(&self, names: &[String]) -> String {
+        if !self.has_rc_right(Some(gserver_accounts::PLPERM_UPDATELEVEL)) {
+            return "You don't have the right to update levels".to_string();
+        }
+        let reloaded = crate::levelreload::reload_levels(&self.context, names);
+        tracing::info!("{} reloaded levels: {}", self.get_account_name(), reloaded.join(", "));
+        let skipped: Vec<&str> = names.iter()
+            .filter(|name| !reloaded.iter().any(|r| r.eq_ignore_ascii_case(name)))
+            .map(String::as_str)
+            .collect();
+        match skipped.is_empty() {
+            true => format!("Levels updated: {}", reloaded.join(", ")),
+            false => format!("Levels updated: {} (not loaded: {})", reloaded.join(", "), skipped.join(", ")),
+        }
+    }
+
+    /// Handle shoot packet (PLI_SHOOT = 17)
+    ///
+    /// # Purpose
+    /// Client fires a projectile
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_SHOOT` in PlayerClientPackets.cpp:1263
+    async fn handle_shoot(&self, packet_data: &[u8]) -> Result<()> {
+        tracing::debug!("Connection {} shoot: {} bytes", self.player_id.get(), packet_data.len());
+        // TODO: Add projectile and broadcast to other players
+        Ok(())
+    }
+
+    /// Handle warp packet (PLI_WARP = 14)
+    ///
+    /// # Purpose
+    /// Server-initiated warp (acknowledgment)
+    async fn handle_warp(&self, packet_data: &[u8]) -> Result<()> {
+        tracing::debug!("Connection {} warp: {} bytes", self.player_id.get(), packet_data.len());
+        Ok(())
+    }
+
+    /// Handle flag set packet (PLI_FLAGSET = 32)
+    ///
+    /// # Purpose
+    /// Client sets a flag variable
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_FLAGSET` in PlayerClientPackets.cpp:516
+    async fn handle_flag_set(&self, packet_data: &[u8]) -> Result<()> {
+        use gserver_protocol::codecs::*;
+
+        let mut buf = BytesMut::from(packet_data);
+        let flag = read_gstring(&mut buf)?;
+
+        tracing::debug!("Connection {} flag set: {}", self.player_id.get(), flag);
+
+        // Format: "flagname" or "flagname=value"
+        let (name, value) = flag.split_once('=').unwrap_or((flag.as_str(), ""));
+        if let Some(account) = self.account.lock().as_mut() {
+            account.set_flag(name, FlagValue::String(value.to_string()));
+        }
+        self.mark_account_dirty();
+
+        self.context.events().publish(GameEvent::FlagChanged {
+            id: Some(self.player_id),
+            name: name.to_string(),
+            value: Some(value.to_string()),
+        });
+        Ok(())
+    }
+
+    /// Handle flag delete packet (PLI_FLAGDEL = 33)
+    ///
+    /// # Purpose
+    /// Client deletes a flag variable
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_FLAGDEL` in PlayerClientPackets.cpp:615
+    async fn handle_flag_del(&self, packet_data: &[u8]) -> Result<()> {
+        use gserver_protocol::codecs::*;
+
+        let mut buf = BytesMut::from(packet_data);
+        let flag = read_gstring(&mut buf)?;
+
+        tracing::debug!("Connection {} flag del: {}", self.player_id.get(), flag);
+
+        if let Some(account) = self.account.lock().as_mut() {
+            account.remove_flag(&flag);
+        }
+        self.mark_account_dirty();
+
+        self.context.events().publish(GameEvent::FlagChanged {
+            id: Some(self.player_id),
+            name: flag,
+            value: None,
+        });
+        Ok(())
+    }
+
+    /// Handle weapon add packet (PLI_WEAPONADD = 24)
+    ///
+    /// # Purpose
+    /// Client requests to add a weapon
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_WEAPONADD` in PlayerClientPackets.cpp:820
+    async fn handle_weapon_add(&self, packet_data: &[u8]) -> Result<()> {
+        tracing::debug!("Connection {} weapon add: {} bytes", self.player_id.get(), packet_data.len());
+        // TODO: Add weapon to player
+        Ok(())
+    }
+
+    /// Handle NPC props packet (PLI_NPCPROPS = 5)
+    ///
+    /// # Purpose
+    /// Client updates NPC properties. The update is kept in the player's
+    /// level instance and relayed to the other players in it.
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {GUINT npc id}{props}
+    /// ```
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_NPCPROPS` in PlayerClientPackets.cpp:144
+    async fn handle_npc_props(&self, packet_data: &[u8]) -> Result<()> {
+        use gserver_protocol::{codecs::*, PacketTypeOut};
+
+        tracing::debug!("Connection {} npc props: {} bytes", self.player_id.get(), packet_data.len());
+        let mut buf = BytesMut::from(packet_data);
+        let npc_id = read_guint(&mut buf)?;
+
+        let instance = self.instance_key();
+        self.context.groups().lock().instance_mut(instance.clone()).update_npc(npc_id, &buf);
+        self.publish_to_instance(instance, PacketTypeOut::NpcProps, packet_data);
+        Ok(())
+    }
+
+    /// Handle NPC delete packet (PLI_NPCDEL = 23)
+    ///
+    /// # Purpose
+    /// Client requests to delete an NPC
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_NPCDEL` in PlayerClientPackets.cpp:719
+    async fn handle_npc_del(&self, packet_data: &[u8]) -> Result<()> {
+        tracing::debug!("Connection {} npc del: {} bytes", self.player_id.get(), packet_data.len());
+        // TODO: Delete NPC
+        Ok(())
+    }
+
+    /// Handle NPC weapon delete packet (PLI_NPCWEAPONDEL = 25)
+    ///
+    /// # Purpose
+    /// Client removes an NPC weapon; it's sent again if the client gets it
+    /// back (see [`crate::weapondelivery`])
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_NPCWEAPONDEL` in PlayerClientPackets.cpp:813
+    async fn handle_npc_weapon_del(&self, packet_data: &[u8]) -> Result<()> {
+        use gserver_protocol::codecs::*;
+
+        let mut buf = BytesMut::from(packet_data);
+        let weapon = read_gstring(&mut buf)?;
+
+        tracing::debug!("Connection {} npc weapon del: {}", self.player_id.get(), weapon);
+        self.weapon_delivery.lock().forget(&weapon);
+        // TODO: Remove weapon from player
+        Ok(())
+    }
+
+    /// Handle bomb add packet (PLI_BOMBADD = 7)
+    ///
+    /// # Purpose
+    /// Client places a bomb
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_BOMBADD` in PlayerClientPackets.cpp:171
+    async fn handle_bomb_add(&self, packet_data: &[u8]) -> Result<()> {
+        tracing::debug!("Connection {} bomb add: {} bytes", self.player_id.get(), packet_data.len());
+        // TODO: Add bomb to level and broadcast
+        Ok(())
+    }
+
+    /// Handle bomb delete packet (PLI_BOMBDEL = 8)
+    ///
+    /// # Purpose
+    /// Client removes a bomb
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_BOMBDEL` in PlayerClientPackets.cpp:194
+    async fn handle_bomb_del(&self, packet_data: &[u8]) -> Result<()> {
+        tracing::debug!("Connection {} bomb del: {} bytes", self.player_id.get(), packet_data.len());
+        // TODO: Remove bomb from level
+        Ok(())
+    }
+
+    /// Handle arrow add packet (PLI_ARROWADD = 11)
+    ///
+    /// # Purpose
+    /// Client fires an arrow
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_ARROWADD` in PlayerClientPackets.cpp:232
+    async fn handle_arrow_add(&self, packet_data: &[u8]) -> Result<()> {
+        tracing::debug!("Connection {} arrow add: {} bytes", self.player_id.get(), packet_data.len());
+        // TODO: Add arrow to level and broadcast
+        Ok(())
+    }
+
+    /// Handle item add packet (PLI_ITEMADD = 9)
+    ///
+    /// # Purpose
+    /// Client drops an item into its level instance; the other players in
+    /// the instance see it.
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {GCHAR x*2}{GCHAR y*2}{GCHAR item}
+    /// ```
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_ITEMADD` in PlayerClientPackets.cpp:282
+    async fn handle_item_add(&self, packet_data: &[u8]) -> Result<()> {
+        use gserver_protocol::{codecs::*, PacketTypeOut};
+
+        let mut buf = BytesMut::from(packet_data);
+        let x = read_guchar(&mut buf)?;
+        let y = read_guchar(&mut buf)?;
+        let item = read_guchar(&mut buf)?;
+        tracing::debug!("Connection {} item add: {} at {},{}", self.player_id.get(), item, x, y);
+
+        let instance = self.instance_key();
+        self.context.groups().lock().instance_mut(instance.clone()).add_item(x, y, item);
+        self.publish_to_instance(instance, PacketTypeOut::ItemAdd, packet_data);
+        Ok(())
+    }
+
+    /// Handle item delete packet (PLI_ITEMDEL = 10)
+    ///
+    /// # Purpose
+    /// Client picks up an item from its level instance
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {GCHAR x*2}{GCHAR y*2}
+    /// ```
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_ITEMDEL` in PlayerClientPackets.cpp:333
+    async fn handle_item_del(&self, packet_data: &[u8]) -> Result<()> {
+        use gserver_protocol::{codecs::*, PacketTypeOut};
+
+        let mut buf = BytesMut::from(packet_data);
+        let x = read_guchar(&mut buf)?;
+        let y = read_guchar(&mut buf)?;
+        tracing::debug!("Connection {} item del at {},{}", self.player_id.get(), x, y);
+
+        let instance = self.instance_key();
+        if self.context.groups().lock().instance_mut(instance.clone()).remove_item(x, y).is_some() {
+            self.publish_to_instance(instance, PacketTypeOut::ItemDel, packet_data);
+        }
+        Ok(())
+    }
+
+    /// Get the player's instance of their current level
+    pub(super) fn instance_key(&self) -> gserver_game::InstanceKey {
+        let level = self.get_level();
+        self.context.groups().lock().instance_key(self.player_id, &level)
+    }
+
+    /// Sanction an account (RC `/mute`, `/jail`, `/warphome` and their undos)
+    ///
+    /// The sanction goes through [`GameCommand::SanctionRequested`], so the
+    /// command relay applies it to whichever connection has the account.
+    /// Needs PLPERM_BAN.
+    fn rc_sanction(&self, command: &str, args: &[&str]) -> String {
+        if !self.has_rc_right(Some(gserver_accounts::PLPERM_BAN)) {
+            return "You don't have the right to sanction accounts".to_string();
+        }
+        let usage = || match command {
+            "mute" | "jail" => format!("Usage: /{} <account> [minutes]", command),
+            _ => format!("Usage: /{} <account>", command),
+        };
+        let (account, minutes) = match args {
+            [account] => (*account, None),
+            [account, minutes] if matches!(command, "mute" | "jail") => (*account, Some(*minutes)),
+            _ => return usage(),
+        };
+        let sanction = match gserver_game::Sanction::parse(command, minutes) {
+            Ok(sanction) => sanction,
+            Err(e) => return e,
+        };
+        if matches!(sanction, gserver_game::Sanction::Jail(_)) && self.context.config().read().jail_level.is_empty() {
+            return "No jail level set (jaillevel)".to_string();
+        }
+        if !gserver_accounts::AccountLoader::new(self.context.server_dir()).exists(account) {
+            return format!("Account {} not found", account);
+        }
+
+        tracing::info!("{} used /{} on {}", self.get_account_name(), command, account);
+        self.context.commands().send(GameCommand::SanctionRequested { account: account.to_string(), sanction });
+        match minutes {
+            Some(minutes) => format!("{} sent to {} ({} minutes)", command, account, minutes),
+            None => format!("{} sent to {}", command, account),
+        }
+    }
+
+    /// Show or set an account's rights (RC `/rights`)
+    fn rc_rights(&self, args: &[&str]) -> String {
+        const USAGE: &str = "Usage: /rights <account> [rights]";
+        let loader = gserver_accounts::AccountLoader::new(self.context.server_dir());
+        match args {
+            [account] if loader.exists(account) => match loader.load(account) {
+                Ok(loaded) => format!("Rights of {}: {:#x}", loaded.name, loaded.local_rights),
+                Err(e) => format!("Can't load account {}: {}", account, e),
+            },
+            [account, value] if loader.exists(account) => {
+                let Some(issuer) = self.account.lock().as_ref()
+                    .filter(|a| a.has_permission(gserver_accounts::PLPERM_SETRIGHTS))
+                    .map(|a| a.local_rights) else {
+                    return "You don't have the right to set rights".to_string();
+                };
+                if account.eq_ignore_ascii_case(&self.get_account_name()) {
+                    return "You can't set your own rights".to_string();
+                }
+                let Some(requested) = crate::staffrights::parse_rights(value) else {
+                    return USAGE.to_string();
+                };
+                let current = match loader.load(account) {
+                    Ok(loaded) => loaded.local_rights,
+                    Err(e) => return format!("Can't load account {}: {}", account, e),
+                };
+                let rights = crate::staffrights::masked_rights(current, requested, issuer);
+                tracing::info!("{} set the rights of {} to {:#x}", self.get_account_name(), account, rights);
+                self.context.commands().send(GameCommand::StaffRightsChanged {
+                    account: Some(account.to_string()),
+                    rights: Some(rights),
+                });
+                format!("Rights of {} set to {:#x}", account, rights)
+            }
+            [account] | [account, _] => format!("No account {}", account),
+            _ => USAGE.to_string(),
+        }
+    }
+
+    /// Set an account's gralats (RC `/setgralats`)
+    fn rc_set_gralats(&self, args: &[&str]) -> String {
+        const USAGE: &str = "Usage: /setgralats <account> <amount>";
+        let [account, amount] = args else {
+            return USAGE.to_string();
+        };
+        let Ok(amount) = amount.parse::<u32>() else {
+            return USAGE.to_string();
+        };
+        let can_set = self.account.lock().as_ref().is_some_and(|a| a.has_permission(gserver_accounts::PLPERM_SETATTRIBUTES));
+        if !can_set {
+            return "You don't have the right to set attributes".to_string();
+        }
+        if !gserver_accounts::AccountLoader::new(self.context.server_dir()).exists(account) {
+            return format!("No account {}", account);
+        }
+        let issuer = self.get_account_name();
+        tracing::info!("{} set the gralats of {} to {}", issuer, account, amount);
+        self.context.commands().send(GameCommand::RecordEdited {
+            account: account.to_string(),
+            edit: gserver_game::RecordEdit::Gralats { amount, issuer },
+        });
+        format!("Gralats of {} set to {}", account, amount)
+    }
+
+    /// Let in or refuse a login held for approval (RC `/approve`, `/deny`)
+    ///
+    /// Without an account, lists the held logins. Needs PLPERM_BAN.
+    fn rc_resolve_login(&self, approved: bool, account: Option<&str>) -> String {
+        if !self.has_rc_right(Some(gserver_accounts::PLPERM_BAN)) {
+            return "You don't have the right to approve logins".to_string();
+        }
+        let approvals = self.context.login_approvals();
+        let Some(account) = account else {
+            let pending = approvals.pending();
+            return if pending.is_empty() {
+                "No logins waiting for approval".to_string()
+            } else {
+                format!("Waiting for approval: {}", pending.join(", "))
+            };
+        };
+        if !approvals.resolve(account, approved) {
+            return format!("No login of {} is waiting for approval", account);
+        }
+        let verb = if approved { "approved" } else { "denied" };
+        tracing::info!("{} {} the login of {}", self.get_account_name(), verb, account);
+        format!("Login of {} {}", account, verb)
+    }
+
+    /// Freeze or unfreeze an account's players (RC `/freeze`, `/unfreeze`, `/fullstop`)
+    ///
+    /// Needs PLPERM_BAN or PLPERM_SETATTRIBUTES.
+    fn rc_control(&self, command: &str, account: Option<&str>) -> String {
+        use gserver_accounts::{PLPERM_BAN, PLPERM_SETATTRIBUTES};
+        use gserver_game::ControlAction;
+
+        if !self.has_rc_right(Some(PLPERM_BAN)) && !self.has_rc_right(Some(PLPERM_SETATTRIBUTES)) {
+            return "You don't have the right to freeze players".to_string();
+        }
+        let action = match command {
+            "/freeze" => ControlAction::Freeze,
+            "/fullstop" => ControlAction::FullStop,
+            _ => ControlAction::Unfreeze,
+        };
+        let Some(account) = account else {
+            return format!("Usage: {} <account>", command);
+        };
+        if !gserver_accounts::AccountLoader::new(self.context.server_dir()).exists(account) {
+            return format!("Account {} not found", account);
+        }
+        self.context.commands().send(GameCommand::ControlRequested {
+            account: account.to_string(),
+            action,
+        });
+        format!("{} sent to {}", &command[1..], account)
+    }
+
+    /// Search the chat logs for an account's lines (RC `/chatlog`)
+    ///
+    /// The logs can hold PMs, so this needs PLPERM_BAN.
+    fn rc_chat_log(&self, args: &[&str]) -> String {
+        if !self.has_rc_right(Some(gserver_accounts::PLPERM_BAN)) {
+            return "You don't have the right to read the chat logs".to_string();
+        }
+        let span_text = args.get(1).copied().unwrap_or("1h");
+        match (args.first(), crate::chatlog::parse_span(span_text)) {
+            (Some(account), Some(span)) => {
+                let now = gserver_game::moderation::unix_now();
+                let lines = self.context.chat_log().search(account, now.saturating_sub(span), now);
+                crate::chatlog::describe(account, span_text, &lines)
+            }
+            _ => "Usage: /chatlog <account> [span like 30m, 2h or 7d]".to_string(),
+        }
+    }
+
+    /// Send or schedule a server announcement (RC `/announce`)
+    fn rc_announce(&self, args: &[&str]) -> String {
+        let announcements = self.context.announcements();
+        let now = std::time::Instant::now();
+        if !self.has_admin_message_right() {
+            return "You don't have the right to send admin messages".to_string();
+        }
+        match args {
+            [] => {
+                let lines = announcements.schedule_lines(now);
+                if lines.is_empty() {
+                    "No announcements scheduled".to_string()
+                } else {
+                    format!("Scheduled: {}", lines.join("; "))
+                }
+            }
+            ["in", seconds, message @ ..] if !message.is_empty() => match seconds.parse::<u64>() {
+                Ok(seconds) => {
+                    announcements.schedule(std::time::Duration::from_secs(seconds), &message.join(" "), now);
+                    format!("Announcement scheduled in {}s", seconds)
+                }
+                Err(_) => "Usage: /announce [in <seconds>] <text>".to_string(),
+            },
+            message => {
+                self.context.broadcast_admin(&message.join(" "));
+                "Announcement sent".to_string()
+            }
+        }
+    }
+
+    /// Show or change an account's lists (RC `/social`)
+    ///
+    /// Changes go through [`GameCommand::SocialEdited`]: the command relay
+    /// applies them to the account's connections, or to its file if it
+    /// isn't online. Needs PLPERM_SETATTRIBUTES.
+    async fn rc_social(&self, args: &[&str]) -> String {
+        use gserver_accounts::AccountLoader;
+        use gserver_game::SocialEdit;
+
+        const USAGE: &str = "Usage: /social <account> [addfriend|removefriend|ignore|unignore|pmfriendsonly <value>]";
+        if !self.has_rc_right(Some(gserver_accounts::PLPERM_SETATTRIBUTES)) {
+            return "You don't have the right to set attributes".to_string();
+        }
+        match args {
+            [account] => {
+                let loader = AccountLoader::new(self.context.server_dir());
+                if !loader.exists(account) {
+                    return format!("No account {}", account);
+                }
+                match loader.load(account) {
+                    Ok(loaded) => crate::social::describe(&loaded),
+                    Err(e) => format!("Can't load account {}: {}", account, e),
+                }
+            }
+            [account, command, value] => match SocialEdit::parse(command, value) {
+                Some(edit) => {
+                    tracing::info!("{} changed the lists of {}: {:?}", self.get_account_name(), account, edit);
+                    self.context.commands().send(GameCommand::SocialEdited { account: account.to_string(), edit });
+                    format!("Lists of {} updated", account)
+                }
+                None => USAGE.to_string(),
+            },
+            _ => USAGE.to_string(),
+        }
+    }
+
+    /// Send a packet to the other players in an instance (see [`gserver_game::groups`])
+    pub(super) fn publish_to_instance(&self, instance: gserver_game::InstanceKey, packet_type: gserver_protocol::PacketTypeOut, data: &[u8]) {
+        self.context.events().publish(GameEvent::InstancePacket {
+            instance,
+            except: Some(self.player_id),
+            packet_type,
+            data: data.to_vec(),
+        });
+    }
+
+    /// Send the items and NPC props of the player's instance of a level
+    async fn send_instance_state(&self, level: &str) -> Result<()> {
+        use gserver_protocol::{codecs::*, PacketOut, PacketTypeOut};
+
+        let (items, npcs) = {
+            let groups = self.context.groups().lock();
+            match groups.instance(&groups.instance_key(self.player_id, level)) {
+                Some(instance) => (
+                    instance.items(),
+                    instance.npcs().into_iter().map(|(id, props)| (id, props.to_vec())).collect(),
+                ),
+                None => (Vec::new(), Vec::new()),
+            }
+        };
+
+        for (x, y, item) in items {
+            let mut buf = BytesMut::new();
+            for value in [x, y, item] {
+                write_gchar(&mut buf, value as i8);
+            }
+            self.send_packet(PacketOut::new(PacketTypeOut::ItemAdd, buf.to_vec())).await?;
+        }
+        for (id, props) in npcs {
+            let mut buf = BytesMut::new();
+            write_gint(&mut buf, id as i32);
+            buf.extend_from_slice(&props);
+            self.send_packet(PacketOut::new(PacketTypeOut::NpcProps, buf.to_vec())).await?;
+        }
+        Ok(())
+    }
+
+    /// Handle hurt player packet (PLI_HURTPLAYER = 12)
+    ///
+    /// # Purpose
+    /// Client hurts another player
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {GUSHORT victim}{GCHAR dx}{GCHAR dy}{GCHAR power}{GINT npc}
+    /// ```
+    ///
+    /// # Server Actions
+    /// Sends PLO_HURTPLAYER `{GSHORT attacker}{dx}{dy}{power}{npc}` to the
+    /// victim, unless the level is `noplayerkilling` (see
+    /// [`gserver_levels::LevelFlags`]). Hits without power, and on players
+    /// that are dead or on another level, are ignored and not counted.
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_HURTPLAYER` in PlayerClientPackets.cpp:756
+    async fn handle_hurt_player(&self, packet_data: &[u8]) -> Result<()> {
+        use gserver_protocol::codecs::*;
+        use gserver_protocol::PacketTypeOut;
+
+        let mut buf = BytesMut::from(packet_data);
+        let player_id = read_gushort(&mut buf)?;
+        let _dx = read_gchar(&mut buf)?;
+        let _dy = read_gchar(&mut buf)?;
+        let power = read_gchar(&mut buf)? as usize;
+        let _npc = read_guint(&mut buf)?;
+
+        tracing::debug!("Connection {} hurt player {}: power={}",
+            self.player_id.get(), player_id, power);
+        if self.context.levels().get_level(&self.get_level()).await?.flags.no_player_killing {
+            tracing::debug!("Connection {} can't hurt players on {}", self.player_id.get(), self.get_level());
+            return Ok(());
+        }
+        // Only players alive on the same level can be hurt
+        let alive = self.context.players().players_on_level(&self.get_level()).into_iter()
+            .any(|player| player.id.get() == player_id && player.properties.lock().cur_power > 0);
+        if power == 0 || !alive {
+            tracing::debug!("Connection {} can't hurt player {} from {}", self.player_id.get(), player_id, self.get_level());
+            return Ok(());
+        }
+        self.context.player_stats().hit(gserver_core::PlayerID(player_id), &self.get_account_name(), std::time::Instant::now());
+
+        let mut data = BytesMut::new();
+        write_gshort(&mut data, self.player_id.get() as i16);
+        data.extend_from_slice(&packet_data[2..]);
+        self.context.events().publish(GameEvent::PlayerPacket {
+            player: gserver_core::PlayerID(player_id),
+            packet_type: PacketTypeOut::HurtPlayer,
+            data: data.to_vec(),
+        });
+        Ok(())
+    }
+
+    /// Handle explosion packet (PLI_EXPLOSION = 20)
+    ///
+    /// # Purpose
+    /// Client causes an explosion
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {GCHAR radius}{GCHAR x}{GCHAR y}{GCHAR power}
+    /// ```
+    /// Radius in tiles, x/y in half tiles, power in half hearts.
+    ///
+    /// # Server Actions
+    /// - Relays PLO_EXPLOSION `{GSHORT player_id}{data}` to the instance
+    /// - Hits what is inside the blast (see [`Self::resolve_hits`])
+    ///
+    /// Nothing happens on `noexplosions` levels (see [`gserver_levels::LevelFlags`]).
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_EXPLOSION` in PlayerClientPackets.cpp:777
+    async fn handle_explosion(&self, packet_data: &[u8]) -> Result<()> {
+        use gserver_game::HitArea;
+        use gserver_protocol::{codecs::*, PacketTypeOut};
+
+        let mut buf = BytesMut::from(packet_data);
+        let radius = read_guchar(&mut buf)?;
+        let x = HalfTile(read_guchar(&mut buf)?.into()).to_pixels().0;
+        let y = HalfTile(read_guchar(&mut buf)?.into()).to_pixels().0;
+        let power = read_guchar(&mut buf)?;
+
+        tracing::debug!("Connection {} explosion: radius={}, x={}, y={}, power={}",
+            self.player_id.get(), radius, x, y, power);
+        if self.context.levels().get_level(&self.get_level()).await?.flags.no_explosions {
+            return Ok(());
+        }
+
+        let mut data = BytesMut::new();
+        write_gshort(&mut data, self.player_id.get() as i16);
+        data.extend_from_slice(packet_data);
+        self.publish_to_instance(self.instance_key(), PacketTypeOut::Explosion, &data);
+
+        self.resolve_hits(HitArea::explosion(x, y, radius), power).await
+    }
+
+    /// Handle hit objects packet (PLI_HITOBJECTS = 36)
+    ///
+    /// # Purpose
+    /// Client swung its sword (or a weapon hit) at a point
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {GCHAR power}{GCHAR x}{GCHAR y}[{GINT npc}]
+    /// ```
+    /// Power in half hearts, x/y in half tiles; `npc` is set when an NPC
+    /// weapon did the hit.
+    ///
+    /// The hit is dropped unless it is within reach of the player's own
+    /// server-side position, or of the `npc` on the player's level.
+    ///
+    /// # C++ Equivalence
+    /// Replaces `PlayerClient::msgPLI_HITOBJECTS`, which relays the hit to
+    /// the whole level (see [`gserver_game::hits`])
+    async fn handle_hit_objects(&self, packet_data: &[u8]) -> Result<()> {
+        use gserver_game::HitArea;
+        use gserver_protocol::codecs::*;
+
+        let mut buf = BytesMut::from(packet_data);
+        let power = read_guchar(&mut buf)?;
+        let x = HalfTile(read_guchar(&mut buf)?.into()).to_pixels().0;
+        let y = HalfTile(read_guchar(&mut buf)?.into()).to_pixels().0;
+
+        let npc = (!buf.is_empty()).then(|| read_guint(&mut buf)).transpose()?;
+
+        tracing::debug!("Connection {} hit objects: x={}, y={}, power={}",
+            self.player_id.get(), x, y, power);
+        self.record_gameplay().await?;
+
+        let area = HitArea::sword(x, y);
+        let attacker = match npc {
+            Some(id) => {
+                let level = self.context.levels().get_level(&self.get_level()).await?;
+                level.get_npc(id).map(|npc| (npc.x as i32, npc.y as i32))
+            }
+            None => Some(self.get_pixel_position()),
+        };
+        if !attacker.is_some_and(|pos| area.within_reach(pos)) {
+            tracing::debug!("Connection {} hit objects out of reach", self.player_id.get());
+            return Ok(());
+        }
+
+        self.resolve_hits(area, power).await
+    }
+
+    /// Hit what is inside an area
+    ///
+    /// # Server Actions
+    /// - Runs `washit` of each hit NPC's script
+    /// - Sends PLO_BADDYHURT `{GCHAR baddy}{GCHAR power}` for each hit baddy
+    /// - Runs `hit` of the attacker's weapon scripts if anything was hit
+    /// - Publishes [`GameEvent::AreaHit`]; the server relay sends
+    ///   PLO_HITOBJECTS to the players inside the area
+    async fn resolve_hits(&self, area: gserver_game::HitArea, power: u8) -> Result<()> {
+        use gserver_game::hits::{targets_in, HitTarget};
+        use gserver_protocol::{codecs::write_gchar, PacketTypeOut};
+        use gserver_scripting::npc_script_name;
+
+        let level = self.context.levels().get_level(&self.get_level()).await?;
+        let candidates: Vec<_> = level.npcs.read().iter()
+            .map(|npc| (HitTarget::Npc(npc.id), (npc.x as i32, npc.y as i32)))
+            .chain(level.baddies.iter().enumerate()
+                .map(|(i, baddy)| (HitTarget::Baddy(i), (baddy.x as i32, baddy.y as i32))))
+            .collect();
+        let targets = targets_in(&area, candidates);
+
+        let instance = self.instance_key();
+        let scripts = self.context.scripts();
+        for &target in &targets {
+            match target {
+                HitTarget::Npc(id) => {
+                    if let Err(e) = scripts.trigger_player_event(&npc_script_name(id), "washit", self.player_id) {
+                        tracing::warn!("NPC {} washit script failed: {}", id, e);
+                    }
+                }
+                HitTarget::Baddy(index) => {
+                    let mut data = BytesMut::new();
+                    write_gchar(&mut data, index as i8);
+                    write_gchar(&mut data, power as i8);
+                    self.publish_to_instance(instance.clone(), PacketTypeOut::BaddyHurt, &data);
+                }
+                HitTarget::Player(_) => {}
+            }
+        }
+
+        if !targets.is_empty() {
+            let weapons = self.account.lock().as_ref().map(|a| a.weapons.clone()).unwrap_or_default();
+            for weapon in weapons {
+                if let Err(e) = scripts.trigger_player_event(&weapon, "hit", self.player_id) {
+                    tracing::warn!("Weapon {} hit script failed: {}", weapon, e);
+                }
+            }
+        }
+
+        self.context.events().publish(GameEvent::AreaHit { attacker: self.player_id, instance, area, power });
+        Ok(())
+    }
+
+    /// Handle trigger action packet (PLI_TRIGGERACTION = 18)
+    ///
+    /// # Purpose
+    /// Client triggers an action (used by NPCs)
+    ///
+    /// # Server Actions
+    /// - `gr.setgroup,<group>` - Put the player in a group
+    /// - `gr.setlevelgroup,<group>` - Put everyone on the player's level in a group
+    /// - `gr.addfriend` / `gr.removefriend` / `gr.ignore` / `gr.unignore,<account>`,
+    ///   `gr.pmfriendsonly,<1|0>` - Change the player's lists
+    /// - `gr.buyitem,<item>` / `gr.sellitem,<item>` - Trade with the NPC
+    /// - `gr.dialog[,<name>]` / `gr.say[,<name>]` - Show what the NPC says
+    /// - `gr.leaderboard,<stat>` - Answer with a leaderboard
+    ///
+    /// See [`gserver_game::groups`], [`gserver_game::social`], [`crate::shops`]
+    /// and [`crate::playerstats`].
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_TRIGGERACTION` in PlayerClientPackets.cpp:981
+    async fn handle_trigger_action(&self, packet_data: &[u8]) -> Result<()> {
+        use gserver_protocol::codecs::*;
+
+        let mut buf = BytesMut::from(packet_data);
+        let npc_id = read_guint(&mut buf)?;
+        let x = read_guchar(&mut buf)?;
+        let y = read_guchar(&mut buf)?;
+        let actions = read_gstring(&mut buf)?;
+
+        tracing::debug!("Connection {} trigger action: {}", self.player_id.get(), actions);
+        match gserver_game::GroupTrigger::parse(&actions) {
+            Some(gserver_game::GroupTrigger::SetGroup(group)) => {
+                self.context.groups().lock().set_group(self.player_id, &group);
+            }
+            Some(gserver_game::GroupTrigger::SetLevelGroup(group)) => {
+                self.context.events().publish(GameEvent::LevelGroupSet { level: self.get_level(), group });
+            }
+            None => {
+                if let Some(edit) = gserver_game::SocialEdit::parse_trigger(&actions) {
+                    self.apply_social_edit(&edit);
+                } else if let Some(trigger) = crate::shops::ShopTrigger::parse(&actions) {
+                    self.handle_shop_trigger(npc_id, &trigger).await?;
+                } else if let Some(stat) = crate::playerstats::parse_trigger(&actions) {
+                    let board = self.context.player_stats().leaderboard(stat);
+                    let data = crate::playerstats::leaderboard_reply(npc_id, x, y, stat, &board);
+                    self.send_packet(gserver_protocol::PacketOut::new(gserver_protocol::PacketTypeOut::TriggerAction, data)).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle private message packet (PLI_PRIVATEMESSAGE = 28)
+    ///
+    /// # Purpose
+    /// Client sends a PM to one or more players. The server relay delivers it
+    /// to the recipients that don't ignore the sender (see [`crate::social`]).
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_PRIVATEMESSAGE` in PlayerClientPackets.cpp
+    async fn handle_private_message(&self, packet_data: &[u8]) -> Result<()> {
+        let (to, message) = crate::social::parse_pm(packet_data)?;
+        if to.is_empty() {
+            return Ok(());
+        }
+        let Some(message) = self.apply_word_filter(&message, FilterCheck::Pm).await? else {
+            return Ok(());
+        };
+
+        tracing::debug!("Connection {} PM to {} players", self.player_id.get(), to.len());
+        self.context.events().publish(GameEvent::PrivateMessage {
+            from: self.player_id,
+            account: self.get_account_name(),
+            data: crate::social::pm_data(self.player_id, to.len(), &message),
+            to,
+        });
+        Ok(())
+    }
+
+    /// Check if the account may send admin messages (PLPERM_ADMINMSG)
+    fn has_admin_message_right(&self) -> bool {
+        self.account.lock().as_ref()
+            .is_some_and(|a| a.can_use_rc() && a.has_permission(gserver_accounts::PLPERM_ADMINMSG))
+    }
+
+    /// Handle RC admin message packet (PLI_RC_ADMINMESSAGE = 63)
+    ///
+    /// # Purpose
+    /// RC sends a popup to every player. The whole packet is the message.
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerRC::msgPLI_RC_ADMINMESSAGE` in PlayerRCPackets.cpp
+    fn handle_rc_admin_message(&self, packet_data: &[u8]) {
+        if !self.has_admin_message_right() {
+            tracing::warn!("Connection {} sent an admin message without the right", self.player_id.get());
+            return;
+        }
+        let message = String::from_utf8_lossy(packet_data);
+        tracing::info!("Connection {} admin message: {}", self.player_id.get(), message);
+        self.context.announcements().broadcast(&format!("Admin {}", self.get_account_name()), &message);
+    }
+
+    /// Handle RC private admin message packet (PLI_RC_PRIVADMINMESSAGE = 64)
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {GUSHORT player id}{message}
+    /// ```
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerRC::msgPLI_RC_PRIVADMINMESSAGE` in PlayerRCPackets.cpp
+    fn handle_rc_priv_admin_message(&self, packet_data: &[u8]) -> Result<()> {
+        use gserver_protocol::codecs::read_gushort;
+
+        if !self.has_admin_message_right() {
+            tracing::warn!("Connection {} sent an admin message without the right", self.player_id.get());
+            return Ok(());
+        }
+        let mut buf = BytesMut::from(packet_data);
+        let player = gserver_core::PlayerID(read_gushort(&mut buf)?);
+        let message = String::from_utf8_lossy(&buf);
+        tracing::info!("Connection {} admin message to {}: {}", self.player_id.get(), player.get(), message);
+        self.context.announcements().send_to(player, &format!("Admin {}", self.get_account_name()), &message);
+        Ok(())
+    }
+
+    /// Handle want file packet (PLI_WANTFILE = 59)
+    ///
+    /// # Purpose
+    /// Client requests a file from the server
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_WANTFILE` in PlayerClientPackets.cpp:734
+    async fn handle_want_file(&self, packet_data: &[u8]) -> Result<()> {
+        // The file name is the rest of the packet
+        let file = String::from_utf8_lossy(packet_data).trim().to_string();
+
+        tracing::info!("Connection {} want file: {}", self.player_id.get(), file);
+        self.send_file(&file).await?;
+        Ok(())
+    }
+
+    /// Handle update file packet (PLI_UPDATEFILE = 58)
+    ///
+    /// # Purpose
+    /// Client checks if its cached copy of a file is current
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {PLI_UPDATEFILE}{GINT5 modtime}{name}
+    /// ```
+    ///
+    /// # Response
+    /// - PLO_FILESENDFAILED if the server doesn't have the file
+    /// - The file (PLO_FILE) if the server's copy is newer
+    /// - PLO_FILEUPTODATE otherwise
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_UPDATEFILE` in PlayerClientPackets.cpp:881
+    async fn handle_update_file(&self, packet_data: &[u8]) -> Result<()> {
+        use gserver_protocol::codecs::*;
+
+        let mut buf = BytesMut::from(packet_data);
+        let modtime = read_guint5(&mut buf)? as u64;
+        let file = String::from_utf8_lossy(&buf).trim().to_string();
+
+        let Some(server_modtime) = self.context.files().mod_time(&file) else {
+            tracing::debug!("Connection {} update file {}: not found", self.player_id.get(), file);
+            return self.send_file_failed(&file).await;
+        };
+
+        tracing::debug!("Connection {} update file {}: client {}, server {}",
+            self.player_id.get(), file, modtime, server_modtime);
+        if server_modtime > modtime {
+            self.send_file(&file).await?;
+        } else {
+            self.send_file_up_to_date(&file).await?;
+        }
+        Ok(())
+    }
+
+    /// Handle update package request (PLI_UPDATEPACKAGEREQUESTFILE = 159)
+    ///
+    /// # Purpose
+    /// Client asks for an update package's version, all of its files, or
+    /// one of its files (see [`crate::packages`] for the packet format)
+    ///
+    /// # Response
+    /// - Version: PLO_UPDATEPACKAGESIZE
+    /// - Update: PLO_UPDATEPACKAGESIZE, the files if the installed version is
+    ///   older (size 0 otherwise), then PLO_UPDATEPACKAGEDONE
+    /// - File: the file, or PLO_FILESENDFAILED if it isn't in the package
+    async fn handle_update_package_request(&self, packet_data: &[u8]) -> Result<()> {
+        use crate::packages::{self, PackageRequest};
+
+        let (name, request) = packages::parse_request(packet_data)?;
+        let Some(package) = self.context.config().read().update_package(&name).cloned() else {
+            tracing::debug!("Connection {} requested unknown update package: {}", self.player_id.get(), name);
+            return self.send_file_failed(&name).await;
+        };
+
+        tracing::debug!("Connection {} update package {} (v{}): {:?}",
+            self.player_id.get(), package.name, package.version, request);
+        match request {
+            PackageRequest::Version => {
+                let size = packages::package_size(&package, self.context.files());
+                self.send_packet(packages::size_packet(&package, size)).await?;
+            }
+            PackageRequest::Update { installed_version } => {
+                if installed_version >= package.version {
+                    self.send_packet(packages::size_packet(&package, 0)).await?;
+                } else {
+                    let size = packages::package_size(&package, self.context.files());
+                    self.send_packet(packages::size_packet(&package, size)).await?;
+                    for file in &package.files {
+                        self.send_file(file).await?;
+                    }
+                }
+                self.send_packet(packages::done_packet(&package)).await?;
+            }
+            PackageRequest::File(file) => {
+                if package.files.iter().any(|f| f.eq_ignore_ascii_case(&file)) {
+                    self.send_file(&file).await?;
+                } else {
+                    self.send_file_failed(&file).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle update gani packet (PLI_UPDATEGANI = 162)
+    ///
+    /// # Purpose
+    /// Client checks if an animation needs updating
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_UPDATEGANI` in PlayerClientPackets.cpp:1396
+    async fn handle_update_gani(&self, packet_data: &[u8]) -> Result<()> {
+        use gserver_protocol::codecs::*;
+
+        let mut buf = BytesMut::from(packet_data);
+        let _checksum = read_guint5(&mut buf)?;
+        let gani = read_gstring(&mut buf)?;
+
+        tracing::debug!("Connection {} update gani: {}", self.player_id.get(), gani);
+        // TODO: Check checksum and send gani bytecode if needed (PLO_LOADGANI)
+        Ok(())
+    }
+
+    /// Handle update script packet (PLI_UPDATESCRIPT = 56)
+    ///
+    /// # Purpose
+    /// Client checks if a weapon script needs updating; a weapon the account
+    /// has is sent again (see [`crate::weapondelivery`])
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_UPDATESCRIPT` in PlayerClientPackets.cpp:1421
+    async fn handle_update_script(&self, packet_data: &[u8]) -> Result<()> {
+        use gserver_protocol::codecs::*;
+
+        let mut buf = BytesMut::from(packet_data);
+        let weapon = read_gstring(&mut buf)?;
+
+        tracing::debug!("Connection {} update script: {}", self.player_id.get(), weapon);
+        if !self.has_weapon(&weapon) {
+            return Ok(());
+        }
+        let Some(build) = self.context.weapons().build(&weapon, self.context.classes()) else {
+            return Ok(());
+        };
+        // The client asked, so whatever it has isn't usable
+        self.weapon_delivery.lock().forget(&weapon);
+        self.deliver_weapon(&build).await?;
+        Ok(())
+    }
+
+    /// Handle update class packet (PLI_UPDATECLASS = 157)
+    ///
+    /// # Purpose
+    /// Client checks if a class needs updating
+    ///
+    /// # C++ Equivalence
+    /// Matches `PlayerClient::msgPLI_UPDATECLASS` in PlayerClientPackets.cpp:1431
+    async fn handle_update_class(&self, packet_data: &[u8]) -> Result<()> {
+        use gserver_protocol::codecs::*;
+
+        let mut buf = BytesMut::from(packet_data);
+        let _checksum = read_guint5(&mut buf)?;
+        let class = read_gstring(&mut buf)?;
+
+        tracing::debug!("Connection {} update class: {}", self.player_id.get(), class);
+        // TODO: Send class bytecode if needed (PLO_RAWDATA)
+        Ok(())
+    }
+
+    /// Run a text through the word filter and carry out the matched actions
+    ///
+    /// # Behavior
+    /// - Muted players can't send chat, PMs or messages to all
+    /// - Matches add the rule severity to the player's filter points; an
+    ///   `ESCALATE` threshold reached this way warns, mutes or kicks
+    /// - Blocked texts are replaced by the warn message in the player's own
+    ///   chat bubble, like the C++ server does
+    ///
+    /// # Returns
+    /// The text to use (masked if a `replace` rule matched), or `None` if it
+    /// must be dropped
+    ///
+    /// # C++ Equivalence
+    /// Matches the `getWordFilter().apply()` calls in PlayerClientPackets.cpp
+    pub async fn apply_word_filter(&self, text: &str, check: FilterCheck) -> Result<Option<String>> {
+        use gserver_game::properties::PlayerProp;
+
+        if check != FilterCheck::Nick && check != FilterCheck::Guild && self.is_muted() {
+            tracing::debug!("Connection {} is muted, dropping {}", self.player_id.get(), check.name());
+            return Ok(None);
+        }
+
+        let (result, escalation, mute_time, show_words) = {
+            let config = self.context.config().read();
+            let filter = &config.word_filter;
+            let result = if check == FilterCheck::Nick { filter.apply_nickname(text) } else { filter.apply(text, check) };
+            if result.is_clean() {
+                return Ok(Some(text.to_string()));
+            }
+
+            let mut points = self.filter_points.lock();
+            let before = *points;
+            *points = points.saturating_add(result.severity);
+            (result.clone(), filter.escalation(before, *points), filter.mute_time, filter.show_words_to_rc)
+        };
+
+        let account = self.get_account_name();
+        if result.actions.log {
+            tracing::warn!("Word filter: {} ({}) used {:?} in {}", account, self.player_id.get(), result.matched, check.name());
+        }
+        if result.actions.tell_rc {
+            self.context.events().publish(GameEvent::WordFilterMatch {
+                id: self.player_id,
+                account,
+                check: check.name().to_string(),
+                words: if show_words { result.matched.clone() } else { Vec::new() },
+            });
+        }
+
+        if result.actions.kick || escalation == Some(Escalation::Kick) {
+            self.disconnect("You were kicked by the word filter").await?;
+            return Ok(None);
+        }
+        if result.actions.mute || escalation == Some(Escalation::Mute) {
+            *self.muted_until.lock() = Some(Instant::now() + Duration::from_secs(mute_time));
+            tracing::info!("Connection {} muted for {}s by the word filter", self.player_id.get(), mute_time);
+        }
+        if result.blocked || escalation == Some(Escalation::Warn) {
+            self.send_own_prop(PlayerProp::CurChat, &self.translate(&result.warn_message)).await?;
+        }
+
+        Ok(if result.blocked { None } else { Some(result.text) })
+    }
+
+    /// Send one of the player's own string properties back to them
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {PLO_PLAYERPROPS}{GCHAR prop}{GCHAR length}{value}
+    /// ```
+    async fn send_own_prop(&self, prop: gserver_game::properties::PlayerProp, value: &str) -> Result<()> {
+        use gserver_game::properties::{encode_prop, PropValue};
+        use gserver_protocol::{PacketOut, PacketTypeOut};
+
+        let mut data = BytesMut::new();
+        encode_prop(prop, &PropValue::String(value.to_string()), self.client_version(), &mut data);
+        self.send_packet(PacketOut::new(PacketTypeOut::PlayerProps, data.to_vec())).await
+    }
+}
+
+/// Encode the CarrySprite and CarryNPC props
+fn carry_props_data(carry_sprite: u8, carry_npc: u32, version: gserver_game::properties::ClientVersion) -> Vec<u8> {
+    use gserver_game::properties::{encode_prop, PlayerProp, PropValue};
+
+    let mut data = BytesMut::new();
+    encode_prop(PlayerProp::CarrySprite, &PropValue::Int(carry_sprite.into()), version, &mut data);
+    encode_prop(PlayerProp::CarryNPC, &PropValue::Int(carry_npc.into()), version, &mut data);
+    data.to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::testutil::test_connection;
+    use gserver_accounts::{Account, PLPERM_BAN, PLPERM_WARPTO};
+    use gserver_config::wordfilter::WordFilter;
+    use gserver_config::ServerConfig as GameConfig;
+    use gserver_core::PlayerID;
+    use gserver_game::properties::{encode_prop, PlayerProp, PropValue};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_filtered_chat_is_forwarded() {
+        let fixture = test_connection(GameConfig { word_filter: WordFilter::parse("badword\n"), ..Default::default() }).await;
+        let (context, conn) = (&fixture.context, &fixture.conn);
+        fixture.login(Account { name: "Talker".into(), ..Default::default() });
+        let mut events = context.events().subscribe();
+
+        let mut data = BytesMut::new();
+        encode_prop(PlayerProp::CurChat, &PropValue::String("a badword here".into()), conn.client_version(), &mut data);
+        conn.handle_player_props(&data).await.unwrap();
+
+        let forwarded = std::iter::from_fn(|| events.try_recv().ok())
+            .find_map(|event| match event {
+                GameEvent::PlayerPropsChanged { data, .. } => Some(data),
+                _ => None,
+            })
+            .unwrap();
+        let text = String::from_utf8_lossy(&forwarded);
+        assert!(text.contains("a ******* here") && !text.contains("badword"), "{}", text);
+    }
+
+    #[tokio::test]
+    async fn test_rc_upload_limit() {
+        let fixture = test_connection(GameConfig { max_bundle_rc: 4096, rc_max_upload_size: 1 << 40, ..Default::default() }).await;
+        let (context, conn) = (&fixture.context, &fixture.conn);
+        assert_eq!(conn.rc_upload_limit(), 4096);
+
+        context.config().write().rc_max_upload_size = 1000;
+        assert_eq!(conn.rc_upload_limit(), 1000);
+    }
+
+    #[tokio::test]
+    async fn test_shop_needs_npc_on_level() {
+        use gserver_scripting::NpcStateHandler;
+
+        let fixture = test_connection(GameConfig::default()).await;
+        let (context, conn) = (&fixture.context, &fixture.conn);
+        std::fs::create_dir_all(fixture.dir.path().join("world")).unwrap();
+        std::fs::write(fixture.dir.path().join("world/shop.nw"), "GLEVNW01\n").unwrap();
+        fixture.login(Account { name: "Buyer".into(), level: "shop.nw".into(), gralats: 50, ..Default::default() });
+        context.npc_saves().set_var("npc3", "buy.arrows", "10,5");
+        let trigger = crate::shops::ShopTrigger::parse("gr.buyitem,Arrows").unwrap();
+
+        conn.handle_shop_trigger(3, &trigger).await.unwrap();
+        assert_eq!(conn.account.lock().as_ref().map(|a| a.gralats), Some(50));
+
+        context.levels().get_level("shop.nw").await.unwrap().add_npc(3, "shop.png".into(), 32.0, 32.0);
+        conn.handle_shop_trigger(3, &trigger).await.unwrap();
+        assert_eq!(conn.account.lock().as_ref().map(|a| a.gralats), Some(40));
+    }
+
+    #[tokio::test]
+    async fn test_hurt_needs_live_victim_on_level() {
+        use gserver_protocol::codecs::{write_gchar, write_gint, write_gshort};
+
+        let fixture = test_connection(GameConfig::default()).await;
+        let (context, conn) = (&fixture.context, &fixture.conn);
+        std::fs::create_dir_all(fixture.dir.path().join("world")).unwrap();
+        std::fs::write(fixture.dir.path().join("world/arena.nw"), "GLEVNW01\n").unwrap();
+        fixture.login(Account { name: "Attacker".into(), level: "arena.nw".into(), ..Default::default() });
+        let victim = Arc::new(gserver_game::Player::new(PlayerID(2), gserver_game::PlayerType::Player));
+        {
+            let mut props = victim.properties.lock();
+            props.cur_level = "elsewhere.nw".into();
+            props.cur_power = 6;
+        }
+        context.players().add_player(victim.clone());
+        let mut events = context.events().subscribe();
+
+        let mut hurt = BytesMut::new();
+        write_gshort(&mut hurt, 2);
+        for value in [0, 0, 2] {
+            write_gchar(&mut hurt, value);
+        }
+        write_gint(&mut hurt, 0);
+        let hurt_sent = |events: &mut tokio::sync::broadcast::Receiver<GameEvent>| {
+            std::iter::from_fn(|| events.try_recv().ok()).any(|event| matches!(event, GameEvent::PlayerPacket { .. }))
+        };
+
+        conn.handle_hurt_player(&hurt).await.unwrap();
+        assert!(!hurt_sent(&mut events));
+
+        context.players().set_level(PlayerID(2), "arena.nw");
+        victim.properties.lock().cur_power = 0;
+        conn.handle_hurt_player(&hurt).await.unwrap();
+        assert!(!hurt_sent(&mut events));
+
+        victim.properties.lock().cur_power = 6;
+        conn.handle_hurt_player(&hurt).await.unwrap();
+        assert!(hurt_sent(&mut events));
+    }
+
+    #[tokio::test]
+    async fn test_ignore_blocks_pms() {
+        use gserver_game::SocialEdit;
+
+        let fixture = test_connection(GameConfig::default()).await;
+        let conn = &fixture.conn;
+        assert!(!conn.accepts_pm_from("Eve"));
+
+        *conn.account.lock() = Some(Account { name: "Alice".into(), ..Default::default() });
+        assert!(conn.accepts_pm_from("Eve"));
+        assert!(conn.apply_social_edit(&SocialEdit::Ignore("Eve".into())));
+        assert!(!conn.apply_social_edit(&SocialEdit::Ignore("eve".into())));
+        assert!(!conn.accepts_pm_from("EVE"));
+        assert!(conn.accepts_pm_from("Bob"));
+
+        assert!(conn.apply_social_edit(&SocialEdit::FriendsOnly(true)));
+        assert!(!conn.accepts_pm_from("Bob"));
+    }
+
+    #[tokio::test]
+    async fn test_social_needs_set_attributes_right() {
+        let fixture = test_connection(GameConfig::default()).await;
+        let conn = &fixture.conn;
+        let mut commands = fixture.context.commands().take_receiver().unwrap();
+        fixture.save_account("Bob");
+        fixture.login_rc("Helper", PLPERM_WARPTO);
+        assert_eq!(conn.rc_social(&["Bob", "ignore", "Eve"]).await, "You don't have the right to set attributes");
+        assert!(commands.try_recv().is_err());
+
+        fixture.grant(gserver_accounts::PLPERM_SETATTRIBUTES);
+        assert_eq!(conn.rc_social(&["Bob", "ignore", "Eve"]).await, "Lists of Bob updated");
+        assert!(matches!(commands.try_recv(), Ok(GameCommand::SocialEdited { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_update_levels_needs_right() {
+        let fixture = test_connection(GameConfig::default()).await;
+        let conn = &fixture.conn;
+        fixture.login_rc("Helper", PLPERM_WARPTO);
+        assert_eq!(conn.update_levels(&["start.nw".into()]), "You don't have the right to update levels");
+
+        fixture.grant(gserver_accounts::PLPERM_UPDATELEVEL);
+        assert!(conn.update_levels(&["start.nw".into()]).starts_with("Levels updated"));
+    }
+
+    #[tokio::test]
+    async fn test_approve_needs_ban_right() {
+        let fixture = test_connection(GameConfig::default()).await;
+        let conn = &fixture.conn;
+        fixture.login_rc("Helper", PLPERM_WARPTO);
+        assert_eq!(conn.rc_resolve_login(true, Some("Bob")), "You don't have the right to approve logins");
+
+        fixture.grant(PLPERM_BAN);
+        assert_eq!(conn.rc_resolve_login(true, Some("Bob")), "No login of Bob is waiting for approval");
+    }
+
+    #[tokio::test]
+    async fn test_chat_log_needs_ban_right() {
+        let fixture = test_connection(GameConfig::default()).await;
+        let conn = &fixture.conn;
+        fixture.login_rc("Helper", PLPERM_WARPTO);
+        assert_eq!(conn.rc_chat_log(&["Bob"]), "You don't have the right to read the chat logs");
+
+        fixture.grant(PLPERM_BAN);
+        assert_ne!(conn.rc_chat_log(&["Bob"]), "You don't have the right to read the chat logs");
+        assert!(conn.rc_chat_log(&["Bob", "soon"]).starts_with("Usage: /chatlog"));
+    }
+
+    #[tokio::test]
+    async fn test_sanction_needs_ban_right() {
+        let fixture = test_connection(GameConfig::default()).await;
+        let conn = &fixture.conn;
+        let mut commands = fixture.context.commands().take_receiver().unwrap();
+        fixture.save_account("Bob");
+        fixture.login_rc("Helper", PLPERM_WARPTO);
+        assert_eq!(conn.rc_sanction("mute", &["Bob", "5"]), "You don't have the right to sanction accounts");
+        assert_eq!(conn.rc_sanction("warphome", &["Bob"]), "You don't have the right to sanction accounts");
+        assert!(commands.try_recv().is_err());
+
+        fixture.grant(PLPERM_BAN);
+        assert_eq!(conn.rc_sanction("mute", &["Bob", "5"]), "mute sent to Bob (5 minutes)");
+        assert!(matches!(commands.try_recv(), Ok(GameCommand::SanctionRequested { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_freeze_needs_existing_account() {
+        let fixture = test_connection(GameConfig::default()).await;
+        let conn = &fixture.conn;
+        let mut commands = fixture.context.commands().take_receiver().unwrap();
+        fixture.login_rc("Helper", PLPERM_WARPTO);
+        assert_eq!(conn.rc_control("/freeze", Some("Nobody")), "You don't have the right to freeze players");
+
+        fixture.grant(PLPERM_BAN);
+        assert_eq!(conn.rc_control("/freeze", Some("Nobody")), "Account Nobody not found");
+        assert!(commands.try_recv().is_err());
+
+        fixture.save_account("Bob");
+        assert_eq!(conn.rc_control("/freeze", Some("Bob")), "freeze sent to Bob");
+        assert!(matches!(commands.try_recv(), Ok(GameCommand::ControlRequested { .. })));
+        assert_eq!(conn.rc_control("/unfreeze", None), "Usage: /unfreeze <account>");
+    }
+}
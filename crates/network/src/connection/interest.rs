@@ -0,0 +1,35 @@
+//! # Player Interest
+//!
+//! This module gives the relay what it needs to tell players in range about
+//! each other (see [`crate::interest`]): where the player is on its gmap and
+//! the PLO_OTHERPLPROPS a player coming into range gets.
+
+use super::PlayerConnection;
+use bytes::BytesMut;
+use gserver_game::properties::{encode_props, ClientVersion, PlayerProp, OTHER_PLAYER_PROPS};
+use gserver_game::WorldPos;
+use gserver_protocol::codecs::write_gshort;
+
+impl PlayerConnection {
+    /// Get the player's position in its space (see [`crate::interest::GmapLayout`])
+    pub fn world_position(&self) -> WorldPos {
+        let (x, y) = self.account.lock().as_ref().map(|a| (a.x, a.y)).unwrap_or_default();
+        self.context.interest().layout().position(&self.instance_key(), x, y)
+    }
+
+    /// Build the PLO_OTHERPLPROPS body showing this player to another
+    ///
+    /// # Arguments
+    /// * `version` - Client version of the player it's sent to
+    pub fn other_props_data(&self, version: ClientVersion) -> Vec<u8> {
+        let mut buf = BytesMut::new();
+        write_gshort(&mut buf, self.player_id.get() as i16);
+        let account = self.account.lock();
+        let Some(account) = account.as_ref() else { return buf.to_vec() };
+        let mut props = self.login_properties(account);
+        props.join_leave_lvl = 1;
+        let list: Vec<PlayerProp> = OTHER_PLAYER_PROPS.iter().copied().chain([PlayerProp::JoinLeaveLvl]).collect();
+        encode_props(&props, &list, version, &mut buf);
+        buf.to_vec()
+    }
+}
@@ -0,0 +1,603 @@
+//! # Connection I/O
+//!
+//! This module moves bundles between the socket and the packet handlers.
+//!
+//! # Bundle Framing
+//!
+//! ```text
+//! {u16 big-endian length}{bundle data}
+//! ```
+//!
+//! The length is NOT GSHORT encoded. The bundle data is compressed and
+//! encrypted per generation (see [`super::crypto`]) and holds newline
+//! separated packets.
+//!
+//! [`read_bundle`] and [`write_bundle`] work on any `AsyncRead`/`AsyncWrite`,
+//! so framing can be tested with in-memory streams.
+//!
+//! # Read Timeouts
+//! An idle client may send nothing for a while: between bundles, only the
+//! protocol timeout (`protocoltimeout`) applies. Once a bundle has started,
+//! each read of the rest must arrive within `bundlereadtimeout`, so a client
+//! that sends one byte and stalls is dropped instead of holding its task.
+//! A [`BundleReader`] keeps the partial header and data when the main loop's
+//! `select!` cancels a read.
+//!
+//! # Size Limits
+//! A bundle longer than [`bundle_limit`] is refused as soon as its header
+//! arrives; the client gets PLO_DISCMESSAGE and is dropped.
+
+use super::crypto;
+use super::{ConnectionState, PlayerConnection};
+use bytes::{BufMut, BytesMut};
+use crate::backpressure::{Admission, BackpressurePolicy};
+use crate::bandwidth::BandwidthLimits;
+use crate::error::{LoginError, SendError};
+use crate::recorder::Recording;
+use gserver_core::{ErrorContext, GServerError, Result};
+use gserver_protocol::{PacketOut, PacketTypeOut, ProtocolError};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::queue::{Budget, OutboundQueue};
+
+/// Longest bundle the framing allows
+const MAX_BUNDLE_LEN: usize = u16::MAX as usize;
+
+/// Longest bundle a GEN_5 client sends
+const GEN5_MAX_BUNDLE_LEN: usize = 65532;
+
+/// Get the longest bundle accepted from a client
+///
+/// The generation's limit, lowered by the `maxbundleplayer` or
+/// `maxbundlerc` ceiling. Before login the player ceiling applies.
+pub fn bundle_limit(generation: u8, is_rc: bool, config: &gserver_config::ServerConfig) -> usize {
+    let generation_limit = match generation {
+        5 => GEN5_MAX_BUNDLE_LEN,
+        _ => MAX_BUNDLE_LEN,
+    };
+    let ceiling = if is_rc { config.max_bundle_rc } else { config.max_bundle_player };
+    generation_limit.min(ceiling)
+}
+
+/// Read one length-prefixed bundle
+///
+/// # Returns
+/// - `Ok(Some(data))` - Bundle data (still compressed/encrypted)
+/// - `Ok(None)` - Connection closed before a new bundle started
+/// - `Err(e)` - Read error or oversized bundle
+pub async fn read_bundle<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<Vec<u8>>> {
+    BundleReader::default().read(reader, MAX_BUNDLE_LEN, None).await
+}
+
+/// The bundle being read, kept across cancelled reads
+///
+/// Reads never go past the current bundle, so nothing of the next one is
+/// buffered.
+#[derive(Debug, Default)]
+pub struct BundleReader {
+    /// Length header and data read so far
+    buf: Vec<u8>,
+
+    /// When the last bytes of the unfinished bundle arrived
+    progress: Option<Instant>,
+}
+
+impl BundleReader {
+    /// Get the bytes the current bundle still lacks
+    fn missing(&self) -> usize {
+        match self.buf[..] {
+            [hi, lo, ..] => 2 + u16::from_be_bytes([hi, lo]) as usize - self.buf.len(),
+            _ => 2 - self.buf.len(),
+        }
+    }
+
+    /// Read the rest of one length-prefixed bundle
+    ///
+    /// Waits as long as it takes for a bundle to start; after that each read
+    /// may take up to `stall`. A header over `limit` bytes is an error. Cancel
+    /// safe: bytes read before the future is dropped stay buffered for the
+    /// next call.
+    ///
+    /// # Returns
+    /// - `Ok(Some(data))` - Bundle data (still compressed/encrypted)
+    /// - `Ok(None)` - Connection closed before a new bundle started
+    /// - `Err(e)` - Read error, oversized or stalled bundle
+    pub async fn read<R: AsyncRead + Unpin>(&mut self, reader: &mut R, limit: usize, stall: Option<Duration>)
+        -> Result<Option<Vec<u8>>> {
+        loop {
+            let missing = self.missing();
+            if missing == 0 {
+                self.progress = None;
+                let mut data = std::mem::take(&mut self.buf);
+                data.drain(..2);
+                return Ok(Some(data));
+            }
+
+            let (received, expected) = (self.buf.len(), self.buf.len() + missing);
+            let mut buf = (&mut self.buf).limit(missing);
+            let read = reader.read_buf(&mut buf);
+            let read = match (self.progress, stall) {
+                (Some(at), Some(after)) => match tokio::time::timeout_at((at + after).into(), read).await {
+                    Ok(read) => read?,
+                    Err(_) => return Err(ProtocolError::BundleStalled { received, expected, after }.into()),
+                },
+                _ => read.await?,
+            };
+            if read == 0 {
+                // A cut off header is a close; cut off data is an error
+                if self.buf.len() < 2 {
+                    return Ok(None);
+                }
+                return Err(GServerError::Io(std::io::ErrorKind::UnexpectedEof.into()));
+            }
+            self.progress = Some(Instant::now());
+
+            if received < 2 && self.buf.len() >= 2 {
+                let bundle_len = u16::from_be_bytes([self.buf[0], self.buf[1]]) as usize;
+                if bundle_len > limit {
+                    return Err(ProtocolError::BundleTooLarge { size: bundle_len, limit }.into());
+                }
+            }
+        }
+    }
+}
+
+/// Write one length-prefixed bundle
+///
+/// # Returns
+/// The number of bytes written, including the length prefix
+pub async fn write_bundle<W: AsyncWrite + Unpin>(writer: &mut W, data: &[u8]) -> Result<usize> {
+    let len = u16::try_from(data.len())
+        .map_err(|_| ProtocolError::BundleTooLarge { size: data.len(), limit: u16::MAX as usize })?;
+
+    let mut buf = BytesMut::with_capacity(2 + data.len());
+    buf.extend_from_slice(&len.to_be_bytes());
+    buf.extend_from_slice(data);
+    writer.write_all(&buf).await?;
+    // A no-op on TCP; TLS may hold the last record back until flushed
+    writer.flush().await?;
+    Ok(buf.len())
+}
+
+impl PlayerConnection {
+    /// Check if a bundle write is in progress
+    ///
+    /// Flushes triggered by other tasks (relays sending to this player) are
+    /// skipped while a write is blocked; the packets stay queued, where the
+    /// backpressure limits apply.
+    fn socket_busy(&self) -> bool {
+        self.socket.try_lock().is_err()
+    }
+
+    /// Add to the session recording, if there is one; a failed write ends it
+    fn record(&self, write: impl FnOnce(&mut Recording) -> std::io::Result<()>) {
+        let mut recording = self.recording.lock();
+        let Some(active) = recording.as_mut() else { return };
+        if let Err(e) = write(active) {
+            tracing::warn!("Connection {} stopped recording {}: {}", self.player_id.get(), active.path().display(), e);
+            *recording = None;
+        }
+    }
+
+    /// Read and process all packets in a bundle
+    ///
+    /// # Bundle Format
+    /// ```text
+    /// {u16 big-endian length}{bundle_data: possibly compressed}
+    /// {packets separated by \n}
+    /// {each packet: [GCHAR packet_type][packet_data]}
+    /// ```
+    ///
+    /// # Compression
+    /// - **GEN_2/3**: Always zlib compressed
+    /// - **GEN_4**: Always bzip2 compressed
+    /// - **GEN_5**: Compression type byte (0x02=none, 0x04=zlib, 0x06=bz2)
+    /// - **GEN_6/1**: No compression
+    ///
+    /// # Returns
+    /// - `Ok(true)` - Bundle processed successfully
+    /// - `Ok(false)` - Connection closed
+    /// - `Err(e)` - Read error
+    pub(super) async fn read_and_process_bundle(&self) -> Result<bool> {
+        let (limit, stall) = {
+            let config = self.context.config().read();
+            let limit = bundle_limit(self.codec.lock().generation(), self.is_rc(), &config);
+            let stall = (config.bundle_read_timeout > 0).then(|| Duration::from_secs(config.bundle_read_timeout));
+            (limit, stall)
+        };
+        let bundle_data = {
+            let mut socket = self.socket.lock().await;
+            let mut reader = self.bundle_reader.lock().await;
+            match reader.read(&mut *socket, limit, stall).await? {
+                Some(data) => data,
+                None => return Ok(false), // Connection closed
+            }
+        };
+        let bundle_len = bundle_data.len();
+
+        // Update stats
+        *self.bytes_received.lock() += (2 + bundle_len) as u64;
+        self.record(|recording| recording.client_bundle(&bundle_data));
+
+        // Log raw bundle data for debugging
+        tracing::debug!("Connection {} raw bundle ({} bytes): {:02x?}",
+            self.player_id.get(), bundle_len, &bundle_data[..bundle_len.min(32)]);
+
+        // === CRITICAL FIX ===
+        // Check if this is the FIRST bundle (login packet) BEFORE decompressing
+        // The login packet is handled differently: zlib compressed, NO encryption
+        let current_state = self.state();
+
+        tracing::info!("Connection {} state: {:?}, encryption_gen: {}, processing bundle of {} bytes",
+            self.player_id.get(), current_state, self.codec.lock().generation(), bundle_len);
+
+        let bundle_data = if current_state == ConnectionState::Connected {
+            // Login packet: decompress as zlib (no encryption)
+            // The login packet is zlib compressed but NOT encrypted
+            // Use unconditional decompression to match C++ behavior
+            crypto::decompress_zlib_unconditional(&bundle_data)
+        } else {
+            // Subsequent packets: handle based on encryption generation
+            // This includes decryption for GEN_4/5
+            self.codec.lock().decode(&bundle_data)?
+        };
+
+        tracing::debug!("Connection {} decompressed bundle ({} bytes): {:02x?}",
+            self.player_id.get(), bundle_data.len(), &bundle_data[..bundle_data.len().min(32)]);
+
+        if current_state == ConnectionState::Connected {
+            // First bundle is the login packet - ENTIRE bundle is ONE packet
+            tracing::info!("Connection {} handling login bundle ({} bytes)",
+                self.player_id.get(), bundle_data.len());
+
+            // Update activity
+            self.update_activity();
+            *self.packets_received.lock() += 1;
+
+            // Handle login packet (entire bundle)
+            if let Err(e) = self.handle_login_packet(&bundle_data).await {
+                let message = e.downcast_ref::<LoginError>().map_or_else(|| "Login failed.".to_string(), LoginError::client_message);
+                if self.report_error(e, ErrorContext::default()) {
+                    self.disconnect(&message).await?;
+                }
+            }
+
+            return Ok(true);
+        }
+
+        // Process ALL packets in the bundle (newline-separated)
+        let mut pos = 0;
+        while pos < bundle_data.len() {
+            // Find next newline
+            let newline_pos = match bundle_data[pos..].iter().position(|&b| b == 0x0A) {
+                Some(nl) => pos + nl,
+                None => bundle_data.len(), // No more newlines, use rest of bundle
+            };
+
+            let offset = pos;
+            let packet_bytes = &bundle_data[pos..newline_pos];
+            pos = newline_pos + 1; // Skip the newline for next iteration
+
+            if packet_bytes.is_empty() {
+                continue; // Skip empty packets
+            }
+
+            // Parse packet type (first byte is GChar-encoded)
+            let encoded_type = packet_bytes[0];
+            let packet_type_byte = encoded_type.saturating_sub(32);
+            let packet_data = &packet_bytes[1..];
+
+            // Log packet
+            tracing::info!("Received packet: encoded_type={} decoded_type={} data={:02x?}",
+                          encoded_type, packet_type_byte, packet_data);
+
+            // Create a PacketIn with the parsed data
+            use gserver_protocol::{PacketIn, PacketTypeIn};
+            let packet_type = PacketTypeIn::from_u8(packet_type_byte).unwrap_or(PacketTypeIn::LevelWarp);
+            let packet = PacketIn::new(packet_type, packet_data.to_vec());
+
+            // Update activity and packet count
+            self.update_activity();
+            *self.packets_received.lock() += 1;
+            self.packet_counter.lock().record();
+            self.context.traffic().record_packet(packet_type_byte);
+
+            // Handle packet; a bad packet is skipped unless its error drops the client
+            let started = Instant::now();
+            let handled = match self.handle_plugin_packet(packet_type_byte, &packet.packet_data).await {
+                Some(handled) => handled,
+                None => self.handle_packet(packet).await,
+            };
+            let elapsed = started.elapsed();
+            let slow_after = match self.context.config().read().slow_handler_ms {
+                0 => None,
+                ms => Some(Duration::from_millis(ms)),
+            };
+            if self.context.handler_timings().record(packet_type_byte, elapsed, slow_after) {
+                tracing::warn!("Connection {} took {:?} to handle {:?}", self.player_id.get(), elapsed, packet_type);
+            }
+            if let Err(e) = handled {
+                self.report_error(e, ErrorContext::default().packet(packet_type_byte, offset));
+            }
+
+            // Stop processing once a handler or an error has dropped the client
+            if self.disconnect_reason.lock().is_some() {
+                break;
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Send a packet to the client
+    ///
+    /// # Arguments
+    /// * `packet` - Packet to send
+    ///
+    /// # Process (C++ CFileQueue equivalent)
+    /// 1. Serialize packet to bytes (with newline)
+    /// 2. Apply the backpressure limits (see [`crate::backpressure`])
+    /// 3. Add to outbound queue (don't send immediately!)
+    /// 4. Flush if queue is full (>= 48KB or >= 4 send cycles)
+    ///
+    /// A packet dropped for backpressure still returns `Ok`. So does an
+    /// overflow: it's reported on this connection, whichever task sent the
+    /// packet, and the main loop disconnects the client.
+    ///
+    /// # C++ Equivalence
+    /// Matches `Player::sendPacket()` → `CFileQueue::addPacket()` → `sendCompress()`
+    pub async fn send_packet(&self, packet: PacketOut) -> Result<()> {
+        // Serialize packet to bytes (includes newline now)
+        let mut packet_data = BytesMut::new();
+        packet.serialize(&mut packet_data);
+        self.send_serialized(packet.packet_type, packet_data).await
+    }
+
+    /// Queue already serialized packets of one type, as [`Self::send_packet`] does
+    ///
+    /// For packets built once and shared between connections, like the
+    /// weapon bundles of [`crate::weapondelivery`].
+    pub(super) async fn send_serialized(&self, packet_type: PacketTypeOut, packet_data: BytesMut) -> Result<()> {
+        if self.outbound_metrics.lock().disconnecting {
+            return Ok(());
+        }
+
+        let policy = BackpressurePolicy::from_config(&self.context.config().read());
+        let mut queue = self.outbound_queue.lock().await;
+        match policy.admit(queue.queued_bytes(), packet_data.len(), packet_type) {
+            Admission::Queue => {}
+            Admission::Drop => {
+                self.outbound_metrics.lock().dropped_packets += 1;
+                return Ok(());
+            }
+            Admission::Overflow(error) => {
+                drop(queue);
+                self.give_up_sending(error).await;
+                return Ok(());
+            }
+        }
+
+        // Add to queue (CRITICAL: don't send immediately!)
+        queue.add_packet(packet_data, false); // false = not a file packet
+        self.outbound_metrics.lock().set_queued(queue.queued_bytes());
+        let should_flush = queue.should_flush();
+        queue.increment_send_cycles();
+        drop(queue);
+        self.schedule_flush();
+
+        // Flush if we should (48KB reached or 4 send cycles)
+        if should_flush && !self.socket_busy() {
+            self.process_outbound_queue().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Queue serialized file transfer packets
+    ///
+    /// File packets bypass normal batching: [`OutboundQueue`] sends them in
+    /// order, one or two per bundle, so they don't starve other packets.
+    pub(super) async fn send_file_packet(&self, packet: BytesMut) -> Result<()> {
+        let mut queue = self.outbound_queue.lock().await;
+        queue.add_packet(packet, true);
+        let should_flush = queue.should_flush();
+        queue.increment_send_cycles();
+        drop(queue);
+        self.schedule_flush();
+
+        if should_flush && !self.socket_busy() {
+            self.process_outbound_queue().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Wake the main loop to flush the queue
+    ///
+    /// Flushes are coalesced: one wakeup covers every packet queued until
+    /// the flush, at most one per [`FLUSH_INTERVAL`](super::queue::FLUSH_INTERVAL).
+    pub(super) fn schedule_flush(&self) {
+        self.flush_notify.notify_one();
+    }
+
+    /// Send the next batch of queued packets
+    ///
+    /// # C++ Equivalence
+    /// Corresponds to `CFileQueue::sendCompress()`, see [`OutboundQueue::take_batch`]
+    pub(super) async fn process_outbound_queue(&self) -> Result<()> {
+        if self.outbound_metrics.lock().disconnecting {
+            return Ok(());
+        }
+        let limits = BandwidthLimits::from_config(&self.context.config().read());
+        let (batch, packet_count) = {
+            let mut queue = self.outbound_queue.lock().await;
+            let batch = match limits.is_unlimited() {
+                true => queue.take_batch(),
+                false => self.take_shaped_batch(&mut queue, limits),
+            };
+            let Some(batch) = batch else {
+                return Ok(());
+            };
+            self.outbound_metrics.lock().set_queued(queue.queued_bytes());
+            batch
+        };
+
+        tracing::debug!("Connection {}: Sending batched {} packets, {} bytes: {:02x?}",
+            self.player_id.get(), packet_count, batch.len(), &batch[..batch.len().min(64)]);
+        self.record(|recording| recording.server_output(&batch));
+        self.send_batch(batch, packet_count).await
+    }
+
+    /// Take the next batch within the connection's and the server's bandwidth
+    fn take_shaped_batch(&self, queue: &mut OutboundQueue, limits: BandwidthLimits) -> Option<(BytesMut, usize)> {
+        let now = Instant::now();
+        let own = self.bandwidth.available(limits.connection, now);
+        let server = self.context.bandwidth().available(limits.server, now);
+        let budget = Budget { normal: own.0, files: own.1 }.min(Budget { normal: server.0, files: server.1 });
+
+        let batch = queue.take_shaped_batch(budget)?;
+        let gameplay = batch.data.len() - batch.file_bytes;
+        self.bandwidth.consume(gameplay, batch.file_bytes);
+        self.context.bandwidth().consume(gameplay, batch.file_bytes);
+        Some((batch.data, batch.packet_count))
+    }
+
+    /// Send a batch of packets as one compressed bundle
+    ///
+    /// # Arguments
+    /// * `batch` - Multiple serialized packets (with newlines) concatenated
+    /// * `packet_count` - Number of packets in the batch (for stats)
+    ///
+    /// # Process
+    /// 1. Compress and encrypt according to the connection's generation
+    /// 2. Write the length-prefixed bundle to the socket
+    ///
+    /// A write that blocks for longer than the stall timeout (waiting for
+    /// another writer included) gives up on the client, see
+    /// [`Self::give_up_sending`].
+    async fn send_batch(&self, batch: BytesMut, packet_count: usize) -> Result<()> {
+        let raw_len = batch.len();
+        let encoded = self.codec.lock().encode(batch)?;
+        self.context.traffic().record_bundle(raw_len, encoded.len(), packet_count);
+
+        let write = async {
+            let mut socket = self.socket.lock().await;
+            write_bundle(&mut *socket, &encoded).await
+        };
+        let stall_timeout = BackpressurePolicy::from_config(&self.context.config().read()).stall_timeout;
+        let written = match stall_timeout {
+            Some(after) => match tokio::time::timeout(after, write).await {
+                Ok(written) => written?,
+                Err(_) => {
+                    self.give_up_sending(SendError::Stalled { after }).await;
+                    return Ok(());
+                }
+            },
+            None => write.await?,
+        };
+
+        // Update stats
+        *self.bytes_sent.lock() += written as u64;
+        *self.packets_sent.lock() += packet_count as u64;
+        self.keepalive.lock().data_sent(Instant::now());
+
+        Ok(())
+    }
+
+    /// Stop sending to a client that doesn't read
+    ///
+    /// Frees the queued packets and reports the error on this connection,
+    /// which sets the disconnect reason. Later packets are dropped and
+    /// nothing more is written; the main loop wakes and ends.
+    async fn give_up_sending(&self, error: SendError) {
+        self.outbound_metrics.lock().disconnecting = true;
+        self.outbound_queue.lock().await.clear_normal();
+        self.outbound_metrics.lock().set_queued(0);
+        self.report_error(error.into(), ErrorContext::default());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_bundle_framing_roundtrip() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+
+        assert_eq!(write_bundle(&mut client, b"\x22abc\n").await.unwrap(), 7);
+        write_bundle(&mut client, b"").await.unwrap();
+        drop(client);
+
+        assert_eq!(read_bundle(&mut server).await.unwrap(), Some(b"\x22abc\n".to_vec()));
+        assert_eq!(read_bundle(&mut server).await.unwrap(), Some(Vec::new()));
+        assert_eq!(read_bundle(&mut server).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_backpressure_drops_then_disconnects() {
+        use gserver_config::ServerConfig as GameConfig;
+        use gserver_protocol::PacketTypeOut;
+
+        let config = GameConfig { outbound_soft_limit: 1000, outbound_hard_limit: 4000, ..Default::default() };
+        let fixture = super::super::testutil::test_connection(config).await;
+        let conn = &fixture.conn;
+
+        // A write is stuck, so sends only queue (each packet is 101 bytes)
+        let _writer = conn.socket.lock().await;
+        for _ in 0..20 {
+            conn.send_packet(PacketOut::new(PacketTypeOut::NpcMoved, vec![b'x'; 99])).await.unwrap();
+        }
+        let metrics = conn.outbound_metrics();
+        assert_eq!((metrics.queued_bytes, metrics.dropped_packets), (1010, 10));
+
+        for _ in 0..30 {
+            conn.send_packet(PacketOut::new(PacketTypeOut::PlayerWarp, vec![b'x'; 99])).await.unwrap();
+        }
+        let metrics = conn.outbound_metrics();
+        assert!(metrics.disconnecting);
+        assert_eq!((metrics.queued_bytes, metrics.peak_bytes), (0, 3939));
+        assert!(conn.disconnect_reason.lock().as_deref().is_some_and(|r| r.starts_with("Outbound queue overflow")));
+    }
+
+    #[tokio::test]
+    async fn test_truncated_bundle_is_an_error() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        client.write_all(&[0x00, 0x05, b'a']).await.unwrap();
+        drop(client);
+
+        assert!(read_bundle(&mut server).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bundle_limits() {
+        let config = gserver_config::ServerConfig { max_bundle_rc: 4096, ..Default::default() };
+        assert_eq!((bundle_limit(4, false, &config), bundle_limit(5, false, &config)), (65535, 65532));
+        assert_eq!(bundle_limit(5, true, &config), 4096);
+
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        client.write_all(&[0x10, 0x01]).await.unwrap();
+        let error = BundleReader::default().read(&mut server, 4096, None).await.unwrap_err();
+        assert_eq!(error.downcast_ref::<ProtocolError>(), Some(&ProtocolError::BundleTooLarge { size: 4097, limit: 4096 }));
+    }
+
+    #[tokio::test]
+    async fn test_partial_bundle_survives_cancel_and_stalls() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        let mut reader = BundleReader::default();
+
+        // Half a header, then the read is cancelled
+        client.write_all(&[0x00]).await.unwrap();
+        let cancelled = tokio::time::timeout(Duration::from_millis(20), reader.read(&mut server, MAX_BUNDLE_LEN, None)).await;
+        assert!(cancelled.is_err());
+        client.write_all(&[0x03, b'a', b'b', b'c', 0x00]).await.unwrap();
+        assert_eq!(reader.read(&mut server, MAX_BUNDLE_LEN, None).await.unwrap(), Some(b"abc".to_vec()));
+
+        // The next header started and stopped
+        let error = reader.read(&mut server, MAX_BUNDLE_LEN, Some(Duration::from_millis(20))).await.unwrap_err();
+        assert_eq!(error.downcast_ref::<ProtocolError>(),
+            Some(&ProtocolError::BundleStalled { received: 1, expected: 2, after: Duration::from_millis(20) }));
+        assert!(error.disconnects());
+    }
+}
@@ -0,0 +1,707 @@
+//! # Login
+//!
+//! This module handles the first bundle of a connection: the login packet,
+//! which selects the encryption generation and loads the account, and the
+//! packets sent back once the account is loaded.
+//!
+//! # C++ Equivalence
+//!
+//! Matches `PlayerClient::msgPLI_LOGIN` and `PlayerClient::sendLoginClient`
+//! in PlayerClient.cpp.
+
+use super::crypto;
+use super::{ConnectionState, PlayerConnection};
+use crate::error::LoginError;
+use crate::loginpolicy::LoginVerdict;
+use crate::recorder::{Recording, SessionHeader};
+use bytes::BytesMut;
+use gserver_accounts::{Account, AccountLoader};
+use gserver_core::Result;
+use gserver_game::properties::{
+    encode_props, ClientVersion, PlayerProperties, PropertyGaniOrBowGif, PropertyHeadGif, PropertyShieldPower,
+    PropertySprite, PropertySwordPower, LOGIN_PROPS,
+};
+use gserver_game::GameEvent;
+
+impl PlayerConnection {
+    /// Read a GChar-encoded byte from a byte slice
+    ///
+    /// GChar encoding: value = raw_byte - 32
+    /// This is used throughout the Graal protocol for compact integer encoding
+    fn read_gchar(data: &[u8], pos: usize) -> Result<u8> {
+        if pos >= data.len() {
+            return Err(LoginError::Truncated { field: "GChar", offset: pos }.into());
+        }
+        // GChar subtracts 32 from the raw byte
+        Ok(data[pos].wrapping_sub(32))
+    }
+
+    /// Read a GUChar-encoded byte (unsigned GChar)
+    ///
+    /// GUChar is just GChar cast to unsigned: (unsigned char)(readGChar())
+    /// Used for string lengths in the Graal protocol
+    fn read_guchar(data: &[u8], pos: usize) -> Result<usize> {
+        Ok(Self::read_gchar(data, pos)? as usize)
+    }
+
+    /// Handle the initial login packet
+    ///
+    /// # Login Packet Format
+    /// ```text
+    /// [GCHAR: player_type]     // Determines CLIENT, RC, or NC
+    /// [GCHAR: encryption_key]  // For newer clients
+    /// [STRING: client_version] // 8 chars (e.g., "G3D0511C")
+    /// [GUCHAR: account_len]    // Length of account name
+    /// [STRING: account_name]   // Account name
+    /// [GUCHAR: password_len]   // Length of password
+    /// [STRING: password]       // Password
+    /// [STRING: identity]       // Null-terminated identity string
+    /// ```
+    ///
+    /// # Returns
+    /// Ok(()) if login successful, Err if login failed
+    ///
+    /// # Errors
+    /// [`LoginError`] for malformed packets, unknown player types, RC logins
+    /// without staff rights, wrong passwords (`localauth`), accounts already in use (see [`crate::duplicate`])
+    /// and accounts that fail to load; every one of them drops the connection.
+    pub(super) async fn handle_login_packet(&self, packet_bytes: &[u8]) -> Result<()> {
+        let mut pos = 0;
+
+        // Read player type (1 byte, GChar-encoded)
+        if pos >= packet_bytes.len() {
+            return Err(LoginError::Truncated { field: "player type", offset: pos }.into());
+        }
+        let player_type_raw = packet_bytes[pos];
+        let player_type_shift = Self::read_gchar(packet_bytes, pos)? as u32;
+        let player_type = 1u32 << player_type_shift;
+        pos += 1;
+
+        tracing::info!("Connection {} player type: raw={}, shift={}, type=0x{:x}",
+            self.player_id.get(), player_type_raw, player_type_shift, player_type);
+
+        // Determine if this is RC, NC, or regular client
+        let is_rc = (player_type & 0x02) != 0 || (player_type & 0x40) != 0; // PLTYPE_RC or PLTYPE_RC2
+        let _is_nc = (player_type & 0x08) != 0; // PLTYPE_NC
+        let is_client = (player_type & 0x01) != 0 || (player_type & 0x10) != 0 || (player_type & 0x20) != 0;
+
+        // Determine encryption key presence based on player type (deterministic)
+        // Encryption key is present for: NC (shift=3), CLIENT2 (shift=4), CLIENT3 (shift=5), RC2 (shift=6)
+        // NOT present for: CLIENT (shift=0), RC (shift=1)
+        let has_encryption_key = match player_type_shift {
+            0 => false,  // PLTYPE_CLIENT (old client, no key)
+            1 => false,  // PLTYPE_RC (old RC, no key)
+            3 => true,   // PLTYPE_NC (has key)
+            4 => true,   // PLTYPE_CLIENT2 (has key)
+            5 => true,   // PLTYPE_CLIENT3 (has key)
+            6 => true,   // PLTYPE_RC2 (has key)
+            _ => {
+                return Err(LoginError::UnknownPlayerType(player_type_shift).into());
+            }
+        };
+
+        // Set encryption generation based on player type (matches C++ PlayerClient.cpp:312-325 and PlayerRC.cpp)
+        let encryption_gen = match player_type_shift {
+            0 => 2,  // PLTYPE_CLIENT: GEN_2
+            1 => 2,  // PLTYPE_RC: GEN_2
+            3 => 2,  // PLTYPE_NC: GEN_2
+            4 => 4,  // PLTYPE_CLIENT2: GEN_4
+            5 => 5,  // PLTYPE_CLIENT3: GEN_5
+            6 => 5,  // PLTYPE_RC2: GEN_5 (New RC 2.22+ uses GEN_5!)
+            _ => {
+                return Err(LoginError::UnknownPlayerType(player_type_shift).into());
+            }
+        };
+
+        // CLIENT2/CLIENT3 and RC2 are the 2.19+ clients that know props 75-82
+        let prop_version = match player_type_shift {
+            4..=6 => ClientVersion::V2_19,
+            _ => ClientVersion::V2,
+        };
+
+        // Read encryption key if present (1 byte, GChar-encoded)
+        let encryption_key = if has_encryption_key {
+            if pos >= packet_bytes.len() {
+                return Err(LoginError::Truncated { field: "encryption key", offset: pos }.into());
+            }
+            let key = Self::read_gchar(packet_bytes, pos)?;
+            pos += 1;
+
+            tracing::debug!("Connection {} encryption key: {} (raw byte: {})",
+                self.player_id.get(), key, packet_bytes[pos - 1]);
+            key
+        } else {
+            0
+        };
+
+        // Select the bundle codec for the generation
+        *self.codec.lock() = crypto::codec_for(encryption_gen, encryption_key,
+            &self.context.compressor().for_connection(self.compression_stats.clone()));
+
+        tracing::info!("Connection {} set encryption_gen={}, key={}",
+            self.player_id.get(), encryption_gen, encryption_key);
+
+        // Read client version (8 chars)
+        let client_version = if pos + 8 <= packet_bytes.len() {
+            let version = String::from_utf8_lossy(&packet_bytes[pos..pos + 8]).to_string();
+            pos += 8;
+            version
+        } else {
+            "UNKNOWN".to_string()
+        };
+
+        tracing::info!("Connection {} client version: {}", self.player_id.get(), client_version);
+
+        // Read account name length (1 byte, GUChar-encoded)
+        if pos >= packet_bytes.len() {
+            return Err(LoginError::Truncated { field: "account name length", offset: pos }.into());
+        }
+        // CRITICAL FIX: Use GUChar decoding (subtract 32) instead of raw byte
+        let account_len = Self::read_guchar(packet_bytes, pos)?;
+        pos += 1;
+
+        tracing::debug!("Connection {} account name length: {} (raw byte: {})",
+            self.player_id.get(), account_len, packet_bytes[pos - 1]);
+
+        // Read account name
+        if pos + account_len > packet_bytes.len() {
+            return Err(LoginError::Truncated { field: "account name", offset: pos }.into());
+        }
+        let account_name = String::from_utf8_lossy(&packet_bytes[pos..pos + account_len]).to_string();
+        pos += account_len;
+
+        tracing::info!("Connection {} account name: {}", self.player_id.get(), account_name);
+
+        // Read password length (1 byte, GUChar-encoded)
+        if pos >= packet_bytes.len() {
+            return Err(LoginError::Truncated { field: "password length", offset: pos }.into());
+        }
+        // CRITICAL FIX: Use GUChar decoding (subtract 32) instead of raw byte
+        let password_at = pos;
+        let password_len = Self::read_guchar(packet_bytes, pos)?;
+        pos += 1;
+
+        tracing::debug!("Connection {} password length: {} (raw byte: {})",
+            self.player_id.get(), password_len, packet_bytes[pos - 1]);
+
+        // Read password
+        if pos + password_len > packet_bytes.len() {
+            return Err(LoginError::Truncated { field: "password", offset: pos }.into());
+        }
+        let password = String::from_utf8_lossy(&packet_bytes[pos..pos + password_len]).into_owned();
+        pos += password_len;
+
+        // A recorded session starts with this packet, its password cut out
+        if crate::recorder::is_recorded(&self.context.config().read(), &account_name) {
+            let login = [&packet_bytes[..password_at], &[32], &packet_bytes[pos..]].concat();
+            let header = SessionHeader { account: &account_name, client_version: &client_version, gen: encryption_gen, key: encryption_key };
+            let dir = self.context.server_dir().join(crate::recorder::RECORDINGS_DIR);
+            match Recording::create(&dir, &header, &login, gserver_game::moderation::unix_now()) {
+                Ok(recording) => {
+                    tracing::info!("Connection {} ({}) recording to {}", self.player_id.get(), account_name, recording.path().display());
+                    *self.recording.lock() = Some(recording);
+                }
+                Err(e) => tracing::warn!("Connection {} ({}) can't be recorded: {}", self.player_id.get(), account_name, e),
+            }
+        }
+
+        // Read identity string (null-terminated)
+        let identity_end = packet_bytes[pos..].iter().position(|&b| b == 0);
+        let identity = if let Some(end) = identity_end {
+            String::from_utf8_lossy(&packet_bytes[pos..pos + end]).to_string()
+        } else {
+            String::from_utf8_lossy(&packet_bytes[pos..]).to_string()
+        };
+
+        tracing::info!("Connection {} identity: {}", self.player_id.get(), identity);
+
+        // Before anything else, so a wrong password can't kick the player who is on
+        let rehashed = if self.context.config().read().local_auth {
+            self.check_password(&account_name, password).await?
+        } else {
+            None
+        };
+        let country = if is_rc { None } else { self.check_country()? };
+        if !is_rc {
+            let mut events = self.context.events().subscribe();
+            let asked = self.request_verification(&account_name)?;
+            // Only a login the listserver accepted may kick the player who is on
+            let players = self.context.players();
+            if asked && !crate::duplicate::others_on_account(players, &account_name, self.player_id).is_empty() {
+                let wait = crate::verification::VERIFY_WAIT;
+                if crate::verification::wait_for(&mut events, self.player_id, wait).await != Some(true) {
+                    tracing::warn!("Connection {} wasn't verified on {}, which is in use",
+                        self.player_id.get(), account_name);
+                    *self.state.lock() = ConnectionState::Disconnecting;
+                    return Err(LoginError::Unverified { account: account_name.to_string() }.into());
+                }
+            }
+            self.resolve_duplicate_login(&account_name).await?;
+        }
+
+        // Load account (also when resuming, so changes made meanwhile are kept)
+        self.context.account_saves().wait(&account_name).await;
+        let loader = AccountLoader::new(self.context.server_dir());
+        let resumed = if is_rc {
+            None
+        } else {
+            self.context.sessions().take(&account_name, self.peer_addr.ip(), &identity, std::time::Instant::now())
+        };
+
+        match loader.load(&account_name) {
+            Ok(mut account) => {
+                tracing::info!("Connection {} loaded account: {} (nick: {}, staff: {})",
+                    self.player_id.get(), account.name, account.nick, account.is_staff());
+
+                // Check staff rights for RC
+                let staff_status = crate::staffrights::StaffStatus::of(&account, &self.context.config().read());
+                if is_rc && !staff_status.rc {
+                    // Send error packet to RC
+                    use gserver_protocol::{PacketOut, PacketTypeOut};
+                    let error_msg = format!("Error: You don't have staff rights.");
+                    let error_packet = PacketOut::new(PacketTypeOut::ServerText, error_msg.into_bytes());
+                    let _ = self.send_packet(error_packet).await;
+
+                    return Err(LoginError::NoRcRights { account: account.name.clone() }.into());
+                }
+
+                // Account bans keep out everyone but staff; banned ids go
+                // through the login policies
+                if account.is_banned_at(gserver_game::moderation::unix_now()) && !staff_status.staff {
+                    return Err(LoginError::Banned { account: account.name.clone(), reason: account.ban_reason.clone() }.into());
+                }
+                let parsed_identity = crate::identity::Identity::parse(&identity);
+
+                // Anomalies, now that the credentials are known to be good
+                self.check_login_policies(&account, &identity, is_rc, staff_status.staff).await?;
+
+                // Jailed players start in jail wherever they logged off
+                if !is_rc && account.is_jailed_at(gserver_game::moderation::unix_now()) {
+                    let config = self.context.config().read();
+                    if let Some(jail) = crate::moderation::Destination::jail(&config) {
+                        account.level = jail.level;
+                        account.x = jail.x;
+                        account.y = jail.y;
+                    }
+                }
+
+                let identity_changed = !is_rc && parsed_identity.store(&mut account);
+                let rehash = rehashed.is_some();
+                if let Some(hash) = rehashed {
+                    tracing::info!("Connection {} replaced the plaintext password of {} with a hash",
+                        self.player_id.get(), account.name);
+                    account.password = hash;
+                }
+
+                // Store account
+                *self.account.lock() = Some(account.clone());
+                *self.staff_status.lock() = staff_status;
+                if rehash || identity_changed {
+                    self.mark_account_dirty();
+                }
+                *self.is_rc.lock() = is_rc;
+                *self.client_version.lock() = prop_version;
+                if account.language.is_empty() {
+                    self.set_language(self.language());
+                } else {
+                    self.set_language(account.language.clone());
+                }
+
+                // Update state
+                *self.state.lock() = ConnectionState::LoggingIn;
+                self.register_player(&account, is_rc);
+                if let Some(country) = &country {
+                    self.context.geoip().record(self.player_id, country);
+                }
+                if !is_rc {
+                    self.record_login_ip(&account);
+                }
+
+                // Send login response packets
+                self.send_login_response(&account).await?;
+                if is_rc {
+                    self.send_rc_max_upload_size().await?;
+                    if account.has_permission(gserver_accounts::PLPERM_NPCCONTROL) {
+                        // Opens the RC's NPC-Server panel
+                        self.send_packet(gserver_protocol::PacketOut::new(gserver_protocol::PacketTypeOut::HasNpcServer, Vec::new())).await?;
+                    }
+                    self.send_rc_chat_history().await?;
+                } else {
+                    self.send_server_message(&account).await?;
+                    if let Some(country) = &country {
+                        let flag = format!("{}={}", crate::geoip::COUNTRY_FLAG, country);
+                        self.send_packet(gserver_protocol::PacketOut::new(gserver_protocol::PacketTypeOut::FlagSet, flag.into_bytes())).await?;
+                    }
+                    if let Some(packet) = self.start_session() {
+                        self.send_packet(packet).await?;
+                    }
+                    if let Some(session) = resumed {
+                        self.resume_session(session);
+                    }
+                }
+                *self.state.lock() = ConnectionState::Authenticated;
+
+                tracing::info!("Connection {} login successful, sent login response packets",
+                    self.player_id.get());
+
+                self.context.list_player(self.player_id, crate::listserver::ListedPlayer {
+                    account: account.name.clone(),
+                    nick: account.nick.clone(),
+                    level: account.level.clone(),
+                    rc: self.is_rc(),
+                });
+                self.context.events().publish(GameEvent::PlayerJoined {
+                    id: self.player_id,
+                    account: account.name.clone(),
+                    level: account.level.clone(),
+                });
+
+                Ok(())
+            }
+            Err(e) => {
+                *self.state.lock() = ConnectionState::Disconnecting;
+                Err(LoginError::AccountLoad { account: account_name, reason: e.to_string() }.into())
+            }
+        }
+    }
+
+    /// Check a login password against the account file (`localauth`)
+    ///
+    /// Hashing is slow on purpose, so it runs on the blocking pool.
+    ///
+    /// # Returns
+    /// A hash to store in place of a plaintext password that matched
+    ///
+    /// # Errors
+    /// [`LoginError::WrongPassword`] unless the password matches, and
+    /// [`LoginError::AccountLoad`] if the account file can't be read
+    async fn check_password(&self, account_name: &str, password: String) -> Result<Option<String>> {
+        use gserver_accounts::{check_password, hash_password, PasswordCheck};
+
+        let server_dir = self.context.server_dir().to_path_buf();
+        let name = account_name.to_string();
+        let checked = tokio::task::spawn_blocking(move || {
+            let stored = AccountLoader::new(&server_dir).load(&name)
+                .map_err(|e| LoginError::AccountLoad { account: name.clone(), reason: e.to_string() })?
+                .password;
+            match check_password(&stored, &password) {
+                PasswordCheck::Valid => Ok(None),
+                PasswordCheck::Legacy => Ok(hash_password(&password)
+                    .inspect_err(|e| tracing::warn!("Failed to rehash the password of {}: {}", name, e))
+                    .ok()),
+                PasswordCheck::Invalid => Err(LoginError::WrongPassword { account: name }),
+            }
+        }).await;
+
+        let result = checked.unwrap_or_else(|e| Err(LoginError::AccountLoad {
+            account: account_name.to_string(),
+            reason: e.to_string(),
+        }));
+        if result.is_err() {
+            *self.state.lock() = ConnectionState::Disconnecting;
+        }
+        result.map_err(Into::into)
+    }
+
+    /// Make room for a login on an account another player is using
+    ///
+    /// See [`crate::duplicate`].
+    ///
+    /// # Errors
+    /// [`LoginError::AlreadyOnline`] if `duplicatelogin` is `rejectnew`, or
+    /// the old player didn't leave in time
+    async fn resolve_duplicate_login(&self, account_name: &str) -> Result<()> {
+        use crate::duplicate::{others_on_account, replace, KICK_WAIT};
+        use gserver_config::DuplicateLogin;
+
+        let players = self.context.players();
+        let old = others_on_account(players, account_name, self.player_id);
+        if old.is_empty() {
+            return Ok(());
+        }
+
+        let policy = self.context.config().read().duplicate_login;
+        tracing::info!("Connection {} logging in on {}, already used by {:?} ({:?})",
+            self.player_id.get(), account_name, old, policy);
+        let replaced = match policy {
+            DuplicateLogin::KickOld => replace(players, self.context.commands(), &old, KICK_WAIT).await,
+            DuplicateLogin::RejectNew => false,
+        };
+        if !replaced {
+            *self.state.lock() = ConnectionState::Disconnecting;
+            return Err(LoginError::AlreadyOnline { account: account_name.to_string() }.into());
+        }
+        Ok(())
+    }
+
+    /// Have the listserver verify the login, if `verifylogins` is on
+    ///
+    /// See [`crate::verification`].
+    ///
+    /// # Returns
+    /// `true` if the listserver was asked now, `false` if verification is
+    /// off or the request waits for the listserver to come back
+    ///
+    /// # Errors
+    /// [`LoginError::Unverified`] if the listserver is unreachable and the
+    /// account wasn't verified within `verifycachehours`
+    fn request_verification(&self, account_name: &str) -> Result<bool> {
+        let (verify, max_age) = {
+            let config = self.context.config().read();
+            (config.verify_logins, config.verify_cache_hours * 3600)
+        };
+        if !verify {
+            return Ok(false);
+        }
+
+        let listserver = self.context.listserver();
+        if !listserver.is_connected() {
+            let now = gserver_game::moderation::unix_now();
+            if max_age == 0 || !self.context.verifications().is_recent(account_name, now, max_age) {
+                *self.state.lock() = ConnectionState::Disconnecting;
+                return Err(LoginError::Unverified { account: account_name.to_string() }.into());
+            }
+            tracing::info!("Connection {} logging in on {} unverified, the listserver is unreachable",
+                self.player_id.get(), account_name);
+        }
+        let connected = listserver.is_connected();
+        listserver.request_verification(self.player_id, account_name);
+        Ok(connected)
+    }
+
+    /// Look up the client's country and apply the login filters
+    ///
+    /// # Returns
+    /// The country, if GeoIP knows the address
+    ///
+    /// # Errors
+    /// [`LoginError::CountryBlocked`] if `geoipallow` or `geoipdeny` filter it
+    fn check_country(&self) -> Result<Option<String>> {
+        let country = self.context.geoip().lookup(self.peer_addr.ip());
+        if !crate::geoip::allows(&self.context.config().read(), country.as_deref()) {
+            *self.state.lock() = ConnectionState::Disconnecting;
+            return Err(LoginError::CountryBlocked { country: country.unwrap_or_default() }.into());
+        }
+        Ok(country)
+    }
+
+    /// Ask the login policies about this login, holding it for staff approval if one says so
+    ///
+    /// # Errors
+    /// [`LoginError::PolicyDenied`] if a policy refuses the login, or
+    /// [`LoginError::NotApproved`] if no RC approves it in time
+    async fn check_login_policies(&self, account: &Account, identity: &str, is_rc: bool, staff: bool) -> Result<()> {
+        let now = gserver_game::moderation::unix_now();
+        let (mut signals, timeout) = {
+            let config = self.context.config().read();
+            // Staff may log in from PCs banned with /banpc, not from `bannedhardware`
+            let mut banned_hardware = config.banned_hardware.clone();
+            if !staff {
+                banned_hardware.extend(self.context.bans().banned_ids(now));
+            }
+            let signals = crate::loginpolicy::LoginSignals::gather(&account.name, self.peer_addr.ip(), identity,
+                self.context.ip_history(), &banned_hardware, now, |ip| self.context.geoip().lookup(ip));
+            (signals, std::time::Duration::from_secs(config.login_approval_timeout))
+        };
+        signals.rc = is_rc;
+        signals.staff = staff;
+
+        let (verdict, policy) = self.context.login_policies().evaluate(&signals);
+        let policy = policy.unwrap_or_default();
+        match verdict {
+            LoginVerdict::Allow => Ok(()),
+            LoginVerdict::Deny => {
+                let message = format!("Login of {} refused by {} ({})", account.name, policy, signals.summary());
+                tracing::warn!("{}", message);
+                self.context.events().publish(GameEvent::StaffNotice { message });
+                Err(LoginError::PolicyDenied { account: account.name.clone(), policy }.into())
+            }
+            LoginVerdict::RequireApproval => {
+                let approvals = self.context.login_approvals();
+                let pending = approvals.request(&account.name);
+                let message = format!("Login of {} needs approval ({}): /approve {} or /deny {}",
+                    account.name, signals.summary(), account.name, account.name);
+                tracing::info!("{}", message);
+                self.context.events().publish(GameEvent::StaffNotice { message });
+                match tokio::time::timeout(timeout, pending).await {
+                    Ok(Ok(true)) => Ok(()),
+                    result => {
+                        if result.is_err() {
+                            approvals.cancel(&account.name);
+                        }
+                        Err(LoginError::NotApproved { account: account.name.clone() }.into())
+                    }
+                }
+            }
+        }
+    }
+
+    /// Record the login in the IP history and tell staff if a banned alt used the IP
+    fn record_login_ip(&self, account: &Account) {
+        let now = gserver_game::moderation::unix_now();
+        let retention_days = self.context.config().read().ip_history_days;
+        let history = self.context.ip_history();
+        history.record(&account.name, self.peer_addr.ip(), now, retention_days);
+        let banned = crate::alts::banned_alts(history, self.context.server_dir(), &account.name, now);
+        if !banned.is_empty() {
+            let message = format!("{} logged in from an IP used by banned {}", account.name, banned.join(", "));
+            tracing::info!("{}", message);
+            self.context.events().publish(GameEvent::StaffNotice { message });
+        }
+    }
+
+    /// Register the player in the context's player manager, by account and level
+    fn register_player(&self, account: &Account, is_rc: bool) {
+        let player = gserver_game::Player::new(self.player_id,
+            if is_rc { gserver_game::PlayerType::Rc } else { gserver_game::PlayerType::Player });
+        {
+            let mut props = player.properties.lock();
+            props.account_name = account.name.clone();
+            props.nickname = account.nick.clone();
+            props.cur_level = account.level.clone();
+        }
+        self.context.players().add_player(std::sync::Arc::new(player));
+    }
+
+    /// Send the rendered servermessage.html to a player after login
+    ///
+    /// Nothing is sent when no server message is configured.
+    ///
+    /// # C++ Equivalence
+    /// Matches the PLO_STARTMESSAGE sent in `PlayerClient::sendLoginClient`
+    async fn send_server_message(&self, account: &Account) -> Result<()> {
+        let (template, generation) = {
+            let config = self.context.config().read();
+            let template = config.translations.translate(&self.language(), &config.server_message).to_string();
+            (template, config.generation)
+        };
+        if template.trim().is_empty() {
+            return Ok(());
+        }
+
+        let vars = crate::motd::MotdVars {
+            player_name: account.nick.clone(),
+            online: self.context.online_count(),
+            uptime: self.context.uptime(),
+        };
+        let message = crate::motd::render(&template, &vars);
+        self.send_packet(crate::motd::motd_packet(generation, &message)).await
+    }
+
+    /// Tell an RC how large a file it may upload (PLO_RC_MAXUPLOADFILESIZE)
+    ///
+    /// Advertises `rcmaxuploadsize` lowered to what fits in one RC bundle.
+    ///
+    /// # C++ Equivalence
+    /// Matches the PLO_RC_MAXUPLOADFILESIZE sent on RC login in PlayerRC.cpp:250
+    async fn send_rc_max_upload_size(&self) -> Result<()> {
+        use gserver_protocol::{codecs::write_gint4, PacketOut, PacketTypeOut};
+
+        let mut data = BytesMut::new();
+        write_gint4(&mut data, self.rc_upload_limit() as i32);
+        self.send_packet(PacketOut::new(PacketTypeOut::RcMaxUploadFileSize, data.to_vec())).await
+    }
+
+    /// Send the recent staff chat lines to an RC that just logged in
+    async fn send_rc_chat_history(&self) -> Result<()> {
+        use gserver_protocol::{PacketOut, PacketTypeOut};
+
+        for line in self.context.rc_chat().lines() {
+            self.send_packet(PacketOut::new(PacketTypeOut::RcChat, line.into_bytes())).await?;
+        }
+        Ok(())
+    }
+
+    /// Build the player's properties from their account, as sent on login
+    pub(super) fn login_properties(&self, account: &Account) -> PlayerProperties {
+        let mut props = PlayerProperties::new();
+        props.nickname = account.nick.clone();
+        props.max_power = account.max_hp as u8;
+        props.cur_power = (account.hp * 2.0) as u8;
+        props.rupees_count = account.gralats;
+        props.arrows_count = account.arrows as u8;
+        props.bombs_count = account.bombs as u8;
+        props.glove_power = account.glove_power as u8;
+        props.bomb_power = account.bomb_power as u8;
+        props.sword_power = PropertySwordPower { image: account.sword.clone(), power: Some(account.sword_power as i8) };
+        props.shield_power = PropertyShieldPower { image: account.shield.clone(), power: Some(account.shield_power as u8) };
+        props.gani = PropertyGaniOrBowGif::Gani(account.ani.clone());
+        props.head_gif = PropertyHeadGif::Image(account.head.clone());
+        for (color, value) in props.colors.iter_mut().zip(account.colors.split(',')) {
+            *color = value.trim().parse().unwrap_or(0);
+        }
+        props.id = self.player_id.get();
+
+        let (x, y) = account.get_tile_pos();
+        props.set_x_pixels(x.to_pixels());
+        props.set_y_pixels(y.to_pixels());
+        props.sprite = PropertySprite { sprite: (account.sprite / 4) as u8, direction: (account.sprite % 4) as u8 };
+        props.cur_level = account.level.clone();
+        // The client's address, which is the proxy's unless a PROXY header said otherwise
+        props.ip_addr = match self.peer_addr.ip() {
+            std::net::IpAddr::V4(ip) => u32::from(ip) as i64,
+            std::net::IpAddr::V6(_) => 0,
+        };
+        props.account_name = account.name.clone();
+        props.body_img = account.body.clone();
+        props
+    }
+
+    /// Send all login response packets to the client
+    ///
+    /// # Arguments
+    /// * `account` - The loaded account data
+    ///
+    /// # Packets Sent
+    /// 1. PLO_PLAYERPROPS - Player properties (nickname, power, position, etc.)
+    /// 2. PLO_CLEARWEAPONS - Clear weapon list
+    /// 3. PLO_NPCWEAPONADD - The account's weapons, in shared bundles
+    ///    (see [`crate::weapondelivery`])
+    /// 4. PLO_PLAYERWARP - Warp player to starting location (triggers PLI_LEVELWARP)
+    ///
+    /// # C++ Equivalence
+    /// This matches the C++ login flow in PlayerClient.cpp:
+    /// - Line 1170: sendPacket(PLO_PLAYERWARP) - triggers PLI_LEVELWARP response
+    /// - PLO_SIGNATURE and PLO_UNKNOWN168 are NOT sent by C++
+    async fn send_login_response(&self, account: &Account) -> Result<()> {
+        use gserver_protocol::{PacketOut, PacketTypeOut};
+
+        // 1. Send PLO_PLAYERPROPS with the login properties
+        let mut props_data = BytesMut::new();
+        encode_props(&self.login_properties(account), LOGIN_PROPS, self.client_version(), &mut props_data);
+
+        let props_packet = PacketOut::new(PacketTypeOut::PlayerProps, props_data.to_vec());
+        self.send_packet(props_packet).await?;
+        tracing::debug!("Connection {} sent PLO_PLAYERPROPS", self.player_id.get());
+
+        // 3. Send the weapons, after PLO_CLEARWEAPONS unless the client has
+        // some from its last session
+        self.send_account_weapons().await?;
+
+        // 4. Send PLO_PLAYERWARP - This is CRITICAL!
+        // This packet tells the client to warp to the starting location,
+        // which triggers the client to send PLI_LEVELWARP in response.
+        // Without this, the client will hang and never send PLI_LEVELWARP.
+        //
+        // C++: PlayerClient.cpp:1170
+        // sendPacket(CString() >> (char)PLO_PLAYERWARP
+        //     << getProp<PlayerProp::X>().serialize()
+        //     << getProp<PlayerProp::Y>().serialize()
+        //     << levelName);
+        let (x, y) = account.get_tile_pos();
+        self.send_player_warp(&account.level, x, y).await?;
+
+        tracing::info!("Connection {} sent PLO_PLAYERWARP to {} at ({}, {}) - type=14, encoded=46",
+            self.player_id.get(), account.level, x, y);
+
+        // NOTE: PLO_SETACTIVELEVEL and PLO_LEVELNAME are NOT sent here!
+        // They are sent in response to PLI_LEVELWARP (see handle_level_warp)
+        // The C++ server sends these AFTER receiving the level warp request from the client
+
+        // CRITICAL: Flush packets immediately after login
+        // The client won't send PLI_LEVELWARP until it receives these packets
+        self.process_outbound_queue().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,1194 @@
+//! # Player Connection Management
+//!
+//! This module handles individual TCP connections from Graal clients.
+//!
+//! # Architecture
+//!
+//! Each connection runs in its own Tokio task and manages:
+//! - TCP socket I/O
+//! - Packet buffering and parsing
+//! - Compression/decompression
+//! - Timeout handling
+//! - Connection state tracking
+//!
+//! # Lifecycle
+//!
+//! ```text
+//! Connected → LoggingIn → Authenticated → Disconnecting → Disconnected
+//!             ↓                ↓
+//!           Failed           Timeout
+//! ```
+//!
+//! # Memory Usage
+//!
+//! Each connection uses approximately:
+//! - Read buffer: 8KB (configurable)
+//! - Write buffer: 8KB (configurable)
+//! - State tracking: ~1KB
+//! - **Total per connection**: ~17KB (vs 50KB in C++)
+//!
+//! # Thread Safety
+//!
+//! Connection state is protected by a `Mutex` but is typically only accessed
+//! from within the connection's own task. External access goes through
+//! connection handles that clone the `Arc`.
+//!
+//! # Modules
+//!
+//! - [`io`] - Bundle framing and the read/send paths
+//! - [`crypto`] - Per-generation compression and encryption
+//! - [`queue`] - Outbound packet batching (CFileQueue)
+//! - [`login`] - Login packet and login response
+//! - [`files`] - File downloads (PLO_FILE)
+//! - [`filebrowser`] - RC file browser, checked against folder rights
+//! - [`handlers`] - Handlers for packets received after login
+//! - [`chatcommands`] - Player chat commands (`/who`, `/pm`, ...)
+//! - [`respawn`] - Death drops and respawning
+//! - [`shops`] - Shop and dialog trigger actions
+//! - [`bans`] - RC comments and ban dialogs
+//! - [`weapons`] - Weapon scripts sent once per checksum
+//! - [`interest`] - Positions and props for the players in range
+//! - [`scripts`] - NC weapon and class edits and their version history
+//! - `replay` (tests only) - Replays the recorded sessions in `fixtures/sessions`
+
+mod bans;
+mod chatcommands;
+mod crypto;
+mod filebrowser;
+mod files;
+mod handlers;
+mod interest;
+mod io;
+mod login;
+mod queue;
+mod respawn;
+mod scripts;
+mod shops;
+mod weapons;
+#[cfg(test)]
+mod replay;
+#[cfg(test)]
+mod testutil;
+
+// Bundle framing and codecs are shared with client-side tools (gserver-loadtest)
+pub use crypto::{codec_for, GraalCodec};
+pub use io::{read_bundle, write_bundle, BundleReader};
+
+use crate::backpressure::QueueMetrics;
+use crate::recorder::Recording;
+use crate::weapondelivery::WeaponDelivery;
+use crate::bandwidth::Shaper;
+use crate::compression::CompressionStats;
+use crate::context::ServerContext;
+use crate::error::log_error;
+use crate::idle::{IdleAction, IdlePolicy, IdleTracker, PLSTATUS_PAUSED};
+use crate::respawn::RespawnTracker;
+use crate::staffrights::StaffStatus;
+use crate::integrity::PacketCounter;
+use crate::keepalive::Keepalive;
+use crate::tls::ClientStream;
+use bytes::BytesMut;
+use gserver_accounts::{Account, AccountLoader};
+use gserver_config::translations::DEFAULT_LANGUAGE;
+use gserver_core::{ErrorContext, GServerError, PlayerID, Result, Severity, TileCoord};
+use gserver_game::properties::ClientVersion;
+use gserver_game::GameEvent;
+use parking_lot::Mutex;
+use queue::OutboundQueue;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex as TokioMutex;
+use tokio::time::interval;
+
+/// State of a player connection
+///
+/// # Purpose
+/// Tracks where the connection is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// Connection just established, waiting for login
+    Connected,
+
+    /// Login packet received, validating credentials
+    LoggingIn,
+
+    /// Authentication successful, player is logged in
+    Authenticated,
+
+    /// Connection is being closed (cleanup in progress)
+    Disconnecting,
+
+    /// Connection closed
+    Disconnected,
+}
+
+/// Individual player connection
+///
+/// # Purpose
+/// Manages a single TCP connection from a Graal client.
+///
+/// # Architecture
+/// Each connection:
+/// 1. Runs in its own Tokio task
+/// 2. Has its own read/write buffers
+/// 3. Handles packet parsing and validation
+/// 4. Manages compression/decompression
+/// 5. Tracks timeouts and activity
+///
+/// # Thread Safety
+/// Internally synchronized with `Mutex`, allowing safe sharing across tasks.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use gserver_network::PlayerConnection;
+/// use tokio::net::TcpStream;
+///
+/// let stream = TcpStream::connect("127.0.0.1:14902").await?;
+/// let conn = PlayerConnection::new(player_id, stream, peer_addr);
+/// conn.run().await?;
+/// ```
+pub struct PlayerConnection {
+    /// Unique identifier for this player
+    pub player_id: PlayerID,
+
+    /// Peer address (IP:port)
+    pub peer_addr: SocketAddr,
+
+    /// Connection state
+    state: Arc<Mutex<ConnectionState>>,
+
+    /// Client socket, plain or TLS (protected by Tokio mutex for Send safety)
+    socket: Arc<TokioMutex<ClientStream>>,
+
+    /// Outbound packet queue for batching (CFileQueue equivalent)
+    outbound_queue: Arc<TokioMutex<OutboundQueue>>,
+
+    /// Outbound queue depth and drops (readable without the queue lock)
+    outbound_metrics: Arc<Mutex<QueueMetrics>>,
+
+    /// Gameplay and file bandwidth buckets (see [`crate::bandwidth`])
+    bandwidth: Arc<Shaper>,
+
+    /// Bundle codec for the encryption generation (selected by the login packet)
+    codec: Arc<Mutex<Box<dyn GraalCodec>>>,
+
+    /// Protocol and gameplay activity (for timeouts and away status)
+    idle: Arc<Mutex<IdleTracker>>,
+
+    /// Keepalive schedule and round-trip time
+    keepalive: Arc<Mutex<Keepalive>>,
+
+    /// Bundles this connection's codec compressed (see [`crate::compression`])
+    compression_stats: Arc<CompressionStats>,
+
+    /// Staff status as last evaluated (see [`crate::staffrights`])
+    staff_status: Arc<Mutex<StaffStatus>>,
+
+    /// Death state and where the player entered their level
+    respawn: Arc<Mutex<RespawnTracker>>,
+
+    /// Connection established timestamp
+    connected_at: Instant,
+
+    /// Total bytes received
+    bytes_received: Arc<Mutex<u64>>,
+
+    /// Total bytes sent
+    bytes_sent: Arc<Mutex<u64>>,
+
+    /// Total packets received
+    packets_received: Arc<Mutex<u64>>,
+
+    /// Total packets sent
+    packets_sent: Arc<Mutex<u64>>,
+
+    /// Shared server state (players, levels, weapons, scripts, config)
+    context: Arc<ServerContext>,
+
+    /// Account data (loaded after login)
+    account: Arc<Mutex<Option<Account>>>,
+
+    /// Account changed since it was loaded or last saved
+    account_dirty: Arc<Mutex<bool>>,
+
+    /// Packets received since the last PLI_PACKETCOUNT
+    packet_counter: Arc<Mutex<PacketCounter>>,
+
+    /// Set when the server decides to drop this connection
+    disconnect_reason: Arc<Mutex<Option<String>>>,
+
+    /// Connection is a Remote Control client (PLTYPE_RC / PLTYPE_RC2)
+    is_rc: Arc<Mutex<bool>>,
+
+    /// Client generation from the login packet, for property availability
+    client_version: Arc<Mutex<ClientVersion>>,
+
+    /// Word filter points collected this session (for ESCALATE)
+    filter_points: Arc<Mutex<u32>>,
+
+    /// Chat is blocked until this time (word filter mute)
+    muted_until: Arc<Mutex<Option<Instant>>>,
+
+    /// Client language (from PLI_LANGUAGE, or the account until it's sent)
+    language: Arc<Mutex<String>>,
+
+    /// CarrySprite and CarryNPC props as accepted by the server
+    carrying: Arc<Mutex<(u8, u32)>>,
+
+    /// Token a reconnect can resume this session with (see [`crate::session`])
+    session_token: Arc<Mutex<String>>,
+
+    /// Recording of the session, if its account is recorded (see [`crate::recorder`])
+    recording: Arc<Mutex<Option<Recording>>>,
+
+    /// Weapon checksums the client has (see [`crate::weapondelivery`])
+    weapon_delivery: Arc<Mutex<WeaponDelivery>>,
+
+    /// Wakes the main loop to flush the outbound queue (see [`queue::FlushSchedule`])
+    flush_notify: Arc<tokio::sync::Notify>,
+
+    /// The partly read client bundle (see [`io::BundleReader`])
+    bundle_reader: TokioMutex<io::BundleReader>,
+}
+
+impl PlayerConnection {
+    /// Create a new player connection
+    ///
+    /// # Arguments
+    /// * `player_id` - Unique player identifier
+    /// * `socket` - TCP socket, or a [`ClientStream`] after a TLS handshake
+    /// * `peer_addr` - Remote address (IP:port)
+    /// * `context` - Shared server context (server directory, levels, weapons, etc.)
+    ///
+    /// # Returns
+    /// A new connection ready to be started
+    #[inline]
+    pub fn new(player_id: PlayerID, socket: impl Into<ClientStream>, peer_addr: SocketAddr, context: Arc<ServerContext>) -> Self {
+        tracing::debug!("New connection {}: {}", player_id.get(), peer_addr);
+
+        Self {
+            player_id,
+            peer_addr,
+            state: Arc::new(Mutex::new(ConnectionState::Connected)),
+            socket: Arc::new(TokioMutex::new(socket.into())),
+            outbound_queue: Arc::new(TokioMutex::new(OutboundQueue::new())),
+            outbound_metrics: Arc::new(Mutex::new(QueueMetrics::default())),
+            bandwidth: Arc::new(Shaper::new()),
+            codec: Arc::new(Mutex::new(Box::new(crypto::Gen1Codec))), // GEN_1 until the login packet sets it
+            idle: Arc::new(Mutex::new(IdleTracker::new(Instant::now()))),
+            keepalive: Arc::new(Mutex::new(Keepalive::new())),
+            compression_stats: Arc::new(CompressionStats::new()),
+            staff_status: Arc::new(Mutex::new(StaffStatus::default())),
+            respawn: Arc::new(Mutex::new(RespawnTracker::new())),
+            connected_at: Instant::now(),
+            bytes_received: Arc::new(Mutex::new(0)),
+            bytes_sent: Arc::new(Mutex::new(0)),
+            packets_received: Arc::new(Mutex::new(0)),
+            packets_sent: Arc::new(Mutex::new(0)),
+            context,
+            account: Arc::new(Mutex::new(None)),
+            account_dirty: Arc::new(Mutex::new(false)),
+            packet_counter: Arc::new(Mutex::new(PacketCounter::new())),
+            disconnect_reason: Arc::new(Mutex::new(None)),
+            is_rc: Arc::new(Mutex::new(false)),
+            client_version: Arc::new(Mutex::new(ClientVersion::default())),
+            filter_points: Arc::new(Mutex::new(0)),
+            muted_until: Arc::new(Mutex::new(None)),
+            language: Arc::new(Mutex::new(DEFAULT_LANGUAGE.to_string())),
+            carrying: Arc::new(Mutex::new((gserver_game::carry::CARRY_NONE, 0))),
+            session_token: Arc::new(Mutex::new(String::new())),
+            recording: Arc::new(Mutex::new(None)),
+            weapon_delivery: Arc::new(Mutex::new(WeaponDelivery::default())),
+            flush_notify: Arc::new(tokio::sync::Notify::new()),
+            bundle_reader: TokioMutex::new(io::BundleReader::default()),
+        }
+    }
+
+    /// Run the connection main loop
+    ///
+    /// # Purpose
+    /// This is the main entry point for a connection task.
+    /// It reads packets, dispatches them, and handles I/O.
+    ///
+    /// # Lifecycle
+    /// ```text
+    /// 1. Receive packets in loop
+    /// 2. Parse packet type
+    /// 3. Handle packet
+    /// 4. Send responses
+    /// 5. Check for timeout
+    /// 6. Repeat until disconnect/error
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - Socket read/write fails
+    /// - Packet parsing fails
+    /// - Connection times out
+    pub async fn run(&self) -> Result<()> {
+        tracing::info!("Connection {} starting main loop", self.player_id.get());
+
+        // Set socket options
+        {
+            let socket = self.socket.lock().await;
+            socket.set_nodelay(true)?; // Disable Nagle's algorithm for low latency
+        }
+
+        let mut timeout_check = interval(Duration::from_secs(10));
+        // Idle connections have no flush timer; queued packets arm one
+        let mut flush = queue::FlushSchedule::default();
+        let flush_timer = tokio::time::sleep(Duration::ZERO);
+        tokio::pin!(flush_timer);
+        // Drops the server didn't decide on keep the session for a reconnect
+        let mut dropped = false;
+
+        loop {
+            let respawn_at = self.respawn.lock().due_at(Duration::from_secs(self.context.config().read().respawn.delay));
+            tokio::select! {
+                // Read and process all packets in a bundle
+                result = self.read_and_process_bundle() => {
+                    match result {
+                        Ok(true) => {
+                            // Bundle processed successfully, continue unless a handler dropped us
+                            if let Some(reason) = self.disconnect_reason.lock().clone() {
+                                tracing::info!("Connection {} disconnected: {}", self.player_id.get(), reason);
+                                break;
+                            }
+                        }
+                        Ok(false) => {
+                            // Connection closed by client
+                            tracing::info!("Connection {} closed by client", self.player_id.get());
+                            dropped = true;
+                            break;
+                        }
+                        Err(e) => {
+                            dropped = matches!(e, GServerError::Io(_));
+                            let oversized = matches!(e.downcast_ref::<gserver_protocol::ProtocolError>(),
+                                Some(gserver_protocol::ProtocolError::BundleTooLarge { .. }));
+                            self.report_error(e, ErrorContext::default());
+                            if oversized {
+                                // The stream can't be read past it, but the client can still be told
+                                let _ = self.send_disconnect_message("Your client sent too much data.").await;
+                            }
+                            break;
+                        }
+                    }
+                }
+
+                // Packets were queued: flush once the schedule allows
+                _ = self.flush_notify.notified(), if flush.due().is_none() => {
+                    let due = flush.arm(std::time::Instant::now());
+                    flush_timer.as_mut().reset(due.into());
+                }
+
+                // Send the queued packets (low-traffic scenario)
+                _ = &mut flush_timer, if flush.due().is_some() => {
+                    flush.flushed(std::time::Instant::now());
+                    // Other tasks can drop us too (e.g. an outbound queue overflow)
+                    if let Some(reason) = self.disconnect_reason.lock().clone() {
+                        tracing::info!("Connection {} disconnected: {}", self.player_id.get(), reason);
+                        break;
+                    }
+
+                    if self.outbound_queue.lock().await.has_data() {
+                        if let Err(e) = self.process_outbound_queue().await {
+                            self.report_error(e, ErrorContext::default());
+                            break;
+                        }
+                    }
+                    // Bandwidth limits and full batches leave packets for the next flush
+                    if self.outbound_queue.lock().await.has_data() {
+                        self.schedule_flush();
+                    }
+                }
+
+                // Respawn a dead player once the delay is over
+                _ = async {
+                    match respawn_at {
+                        Some(at) => tokio::time::sleep_until(at.into()).await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    if let Err(e) = self.check_respawn().await {
+                        self.report_error(e, ErrorContext::default());
+                        break;
+                    }
+                }
+
+                // Keep NAT mappings open, check timeout and gameplay inactivity
+                _ = timeout_check.tick() => {
+                    if let Some(reason) = self.disconnect_reason.lock().clone() {
+                        tracing::info!("Connection {} disconnected: {}", self.player_id.get(), reason);
+                        break;
+                    }
+                    if let Err(e) = self.send_keepalive().await {
+                        self.report_error(e, ErrorContext::default());
+                        break;
+                    }
+                    match self.check_idle().await {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            dropped = self.disconnect_reason.lock().is_none();
+                            break;
+                        }
+                        Err(e) => {
+                            self.report_error(e, ErrorContext::default());
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        // Cleanup
+        self.cleanup(dropped).await;
+        Ok(())
+    }
+
+    /// Clean up after the main loop panicked
+    ///
+    /// Logs the panic with the player it hit, then saves the account and
+    /// removes the player from the managers like a logout, so no half-connected
+    /// player is left behind. With `panicnotifyrc` RCs are told too. The
+    /// session isn't held for a reconnect, since its state can't be trusted.
+    pub async fn recover_from_panic(&self, message: &str) {
+        let account = self.get_account_name();
+        tracing::error!("Connection {} ({}, {:?}, level {}) panicked: {}",
+            self.player_id.get(), account, self.state(), self.get_level(), message);
+        if self.context.config().read().panic_notify_rc {
+            let message = format!("Connection {} ({}) crashed: {}", self.player_id.get(), account, message);
+            self.context.events().publish(GameEvent::StaffNotice { message });
+        }
+        self.cleanup(false).await;
+    }
+
+    /// Log an error with this connection's context and apply its disconnect
+    /// policy
+    ///
+    /// Severity and whether to disconnect come from the error itself
+    /// ([`GServerError::severity`], [`GServerError::disconnects`]). A
+    /// disconnecting error ends the main loop once the current bundle has been
+    /// processed. Error-severity failures are also posted to RC staff chat.
+    ///
+    /// # Returns
+    /// `true` if the error drops the connection
+    pub(super) fn report_error(&self, error: GServerError, context: ErrorContext) -> bool {
+        let error = error.with_context(ErrorContext { player_id: Some(self.player_id), ..context });
+        log_error(&error);
+        if error.severity() == Severity::Error {
+            self.context.events().publish(GameEvent::StaffNotice { message: error.to_string() });
+        }
+
+        let disconnects = error.disconnects();
+        if disconnects {
+            self.disconnect_reason.lock().get_or_insert_with(|| error.root().to_string());
+            // Wake the main loop to notice
+            self.schedule_flush();
+        }
+        disconnects
+    }
+
+    /// Disconnect this client
+    ///
+    /// Sends PLO_DISCMESSAGE with the reason (translated to the client's
+    /// language) and ends the main loop once the current bundle has been processed.
+    pub async fn disconnect(&self, reason: &str) -> Result<()> {
+        *self.disconnect_reason.lock() = Some(reason.to_string());
+        self.send_disconnect_message(reason).await
+    }
+
+    /// Send PLO_DISCMESSAGE (translated to the client's language) right away
+    async fn send_disconnect_message(&self, reason: &str) -> Result<()> {
+        use gserver_protocol::{PacketOut, PacketTypeOut};
+
+        let message = self.translate(reason);
+        self.send_packet(PacketOut::new(PacketTypeOut::DiscMessage, message.into_bytes())).await?;
+        self.process_outbound_queue().await
+    }
+
+    /// Check if the word filter or staff (see [`crate::moderation`]) muted this player
+    pub fn is_muted(&self) -> bool {
+        let now = gserver_game::moderation::unix_now();
+        if self.account.lock().as_ref().is_some_and(|a| a.is_muted_at(now)) {
+            return true;
+        }
+        let mut muted_until = self.muted_until.lock();
+        match *muted_until {
+            Some(until) if Instant::now() < until => true,
+            Some(_) => {
+                *muted_until = None;
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Get the client's language
+    pub fn language(&self) -> String {
+        self.language.lock().clone()
+    }
+
+    /// Set the client's language, for translations and the player's script runs
+    pub(super) fn set_language(&self, language: String) {
+        self.context.scripts().set_player_language(self.player_id, &language);
+        *self.language.lock() = language;
+    }
+
+    /// Translate a built-in server text to the client's language
+    ///
+    /// Falls back to the English text if there's no translation.
+    pub fn translate(&self, text: &str) -> String {
+        let language = self.language();
+        self.context.config().read().translations.translate(&language, text).to_string()
+    }
+
+    /// Check if this is a Remote Control connection
+    pub fn is_rc(&self) -> bool {
+        *self.is_rc.lock()
+    }
+
+    /// Get the client generation sent in the login packet
+    pub fn client_version(&self) -> ClientVersion {
+        *self.client_version.lock()
+    }
+
+    /// Get the outbound queue depth and drop counts
+    pub fn outbound_metrics(&self) -> QueueMetrics {
+        *self.outbound_metrics.lock()
+    }
+
+    /// Get the compression counters of the bundles sent to this client
+    #[inline]
+    pub fn compression_stats(&self) -> &CompressionStats {
+        &self.compression_stats
+    }
+
+    /// Get the number of packet count desyncs detected on this connection
+    pub fn packet_desyncs(&self) -> u32 {
+        self.packet_counter.lock().desyncs()
+    }
+
+    /// Update last activity timestamp
+    fn update_activity(&self) {
+        self.idle.lock().data(Instant::now());
+    }
+
+    /// Record that the player moved, chatted or warped
+    ///
+    /// Clears the away status if the player had it.
+    pub(super) async fn record_gameplay(&self) -> Result<()> {
+        let back = self.idle.lock().gameplay(Instant::now());
+        if back {
+            self.set_away(false).await?;
+        }
+        Ok(())
+    }
+
+    /// Apply the idle policy
+    ///
+    /// # Returns
+    /// `false` if the connection should be closed
+    async fn check_idle(&self) -> Result<bool> {
+        let policy = IdlePolicy::from_config(&self.context.config().read());
+        let exempt = self.is_staff();
+        let action = self.idle.lock().check(&policy, exempt, Instant::now());
+
+        match action {
+            IdleAction::None => {}
+            IdleAction::Away => self.set_away(true).await?,
+            IdleAction::Disconnect => {
+                tracing::info!("Connection {} idle for {:?}, disconnecting",
+                    self.player_id.get(), self.idle.lock().gameplay_idle(Instant::now()));
+                self.disconnect("You have been disconnected for inactivity.").await?;
+                return Ok(false);
+            }
+            IdleAction::TimedOut => {
+                tracing::warn!("Connection {} timed out", self.player_id.get());
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Set or clear the away status and tell the client
+    ///
+    /// The status bit isn't saved with the account.
+    async fn set_away(&self, away: bool) -> Result<()> {
+        use gserver_game::properties::{encode_prop, PlayerProp, PropValue};
+        use gserver_protocol::{PacketOut, PacketTypeOut};
+
+        let status = {
+            let mut account = self.account.lock();
+            let Some(account) = account.as_mut() else { return Ok(()) };
+            if away {
+                account.status |= PLSTATUS_PAUSED;
+            } else {
+                account.status &= !PLSTATUS_PAUSED;
+            }
+            account.status
+        };
+        tracing::debug!("Connection {} {}", self.player_id.get(), if away { "is away" } else { "is back" });
+
+        let mut data = BytesMut::new();
+        encode_prop(PlayerProp::Status, &PropValue::Int(status.into()), self.client_version(), &mut data);
+        self.send_packet(PacketOut::new(PacketTypeOut::PlayerProps, data.to_vec())).await
+    }
+
+    /// Send a keepalive if nothing went out for the keepalive interval
+    ///
+    /// # Packet Format
+    /// ```text
+    /// {PLO_NEWWORLDTIME}{GINT4 time}
+    /// ```
+    async fn send_keepalive(&self) -> Result<()> {
+        use gserver_protocol::codecs::write_gint4;
+        use gserver_protocol::{PacketOut, PacketTypeOut};
+
+        let interval = Duration::from_secs(self.context.config().read().keepalive_interval);
+        let now = Instant::now();
+        if !self.is_authenticated() || !self.keepalive.lock().due(interval, now) {
+            return Ok(());
+        }
+
+        let mut data = BytesMut::new();
+        write_gint4(&mut data, self.context.world_time() as i32);
+        self.send_packet(PacketOut::new(PacketTypeOut::NewWorldTime, data.to_vec())).await?;
+        self.process_outbound_queue().await?;
+        self.keepalive.lock().keepalive_sent(now);
+        Ok(())
+    }
+
+    /// Record the client's ping and update its round-trip time
+    pub(super) fn handle_ping(&self) {
+        let Some(sample) = self.keepalive.lock().ping_received(Instant::now()) else {
+            return;
+        };
+        let rtt = self.rtt().unwrap_or(sample);
+        tracing::trace!("Connection {} RTT {:?} (smoothed {:?})", self.player_id.get(), sample, rtt);
+        self.context.latency().update(self.player_id, &self.get_account_name(), rtt);
+    }
+
+    /// Get the smoothed round-trip time, once the client answered a keepalive
+    pub fn rtt(&self) -> Option<Duration> {
+        self.keepalive.lock().rtt()
+    }
+
+    /// Check if the player is staff (account rights or the staff list)
+    pub fn is_staff(&self) -> bool {
+        let Some((name, is_staff)) = self.account.lock().as_ref().map(|a| (a.name.clone(), a.is_staff())) else {
+            return false;
+        };
+        is_staff || self.context.config().read().staff_accounts.iter().any(|s| s.eq_ignore_ascii_case(&name))
+    }
+
+    /// Check if the player is marked away for inactivity
+    pub fn is_away(&self) -> bool {
+        self.idle.lock().is_away()
+    }
+
+    /// Mark the account as changed so the next save writes it
+    #[inline]
+    pub fn mark_account_dirty(&self) {
+        *self.account_dirty.lock() = true;
+    }
+
+    /// Append a change of the account's gralats to the economy ledger
+    pub(super) fn audit_gralats(&self, before: u32, after: u32, source: crate::economy::GralatSource) {
+        crate::economy::audit(&self.context, &self.get_account_name(), before, after, &source);
+    }
+
+    /// Save the account if it changed since the last save
+    ///
+    /// # Returns
+    /// `true` if the account was written
+    ///
+    /// # C++ Equivalence
+    /// Matches `Account::saveAccount()`; logout and the periodic autosave use
+    /// [`Self::queue_account_save`] instead
+    pub fn save_account(&self) -> Result<bool> {
+        // Snapshot under the locks, write to disk without holding them
+        let Some(account) = self.take_dirty_account() else {
+            return Ok(false);
+        };
+
+        let loader = AccountLoader::new(self.context.server_dir());
+        if let Err(e) = loader.save(&account) {
+            // Keep it dirty so the next autosave retries
+            self.mark_account_dirty();
+            return Err(gserver_core::GServerError::InvalidData(
+                format!("Failed to save account {}: {}", account.name, e)
+            ));
+        }
+
+        tracing::debug!("Connection {} saved account {}", self.player_id.get(), account.name);
+        Ok(true)
+    }
+
+    /// Queue the account on the save queue if it changed since the last save
+    /// (see [`crate::savequeue`])
+    ///
+    /// # Returns
+    /// `true` if the account was queued
+    pub fn queue_account_save(&self) -> bool {
+        let Some(account) = self.take_dirty_account() else {
+            return false;
+        };
+        self.context.account_saves().submit(account);
+        true
+    }
+
+    /// Snapshot the account if it changed since the last save, marking it saved
+    fn take_dirty_account(&self) -> Option<Account> {
+        let mut dirty = self.account_dirty.lock();
+        if !*dirty {
+            return None;
+        }
+        let account = self.account.lock().clone()?;
+        *dirty = false;
+        Some(account)
+    }
+
+    /// Cleanup connection resources
+    async fn cleanup(&self, dropped: bool) {
+        tracing::info!("Connection {} cleaning up", self.player_id.get());
+        let compression = self.compression_stats.total();
+        if let Some(ratio) = compression.ratio() {
+            tracing::debug!("Connection {} compression: {} -> {} bytes ({:.0}%) in {:?}", self.player_id.get(),
+                compression.bytes_in, compression.bytes_out, ratio * 100.0, compression.time);
+        }
+
+        // Update state
+        let was_authenticated = self.is_authenticated();
+        *self.state.lock() = ConnectionState::Disconnected;
+
+        // Away is a session status, don't save it
+        if let Some(account) = self.account.lock().as_mut() {
+            account.status &= !PLSTATUS_PAUSED;
+        }
+
+        // Save the account on logout, off this task
+        // C++: PlayerClient::~PlayerClient calls saveAccount()
+        self.queue_account_save();
+        // The next session gets only the weapons this client lacks
+        if was_authenticated {
+            if let Some(account) = self.account.lock().as_ref() {
+                self.context.weapon_deliveries().keep(&account.name, self.weapon_delivery.lock().clone());
+            }
+        }
+        // Only now, so a login replacing this one waits for the save just queued
+        self.context.players().remove_player(self.player_id);
+
+        // Only players that finished logging in were announced as joined
+        let account_name = self.account.lock().as_ref().map(|a| a.name.clone());
+        if let Some(account) = account_name {
+            self.context.unlist_player(self.player_id);
+            self.context.events().publish(GameEvent::PlayerLeft { id: self.player_id, account });
+        }
+
+        if dropped && was_authenticated && !self.is_rc() {
+            self.hold_session();
+        }
+
+        self.context.latency().remove(self.player_id);
+        self.context.geoip().remove(self.player_id);
+        self.context.chat_commands().forget(self.player_id);
+        self.context.irc().leave_all(self.player_id);
+        self.context.groups().lock().forget_player(self.player_id);
+        self.context.carry().lock().release(self.player_id);
+        self.context.control().forget_player(self.player_id);
+        self.context.scripts().forget_player(self.player_id);
+
+        // Close socket - scope the lock to avoid holding it across await
+        {
+            let mut socket = self.socket.lock().await;
+            let _ = socket.shutdown().await;
+        }
+
+        // Log stats
+        let duration = self.connected_at.elapsed();
+        let bytes_rx = *self.bytes_received.lock();
+        let bytes_tx = *self.bytes_sent.lock();
+        let packets_rx = *self.packets_received.lock();
+        let packets_tx = *self.packets_sent.lock();
+
+        let outbound = self.outbound_metrics();
+
+        tracing::info!(
+            "Connection {} stats - Duration: {:?}, RX: {} bytes / {} packets, TX: {} bytes / {} packets, \
+             outbound queue peak {} bytes, {} dropped",
+            self.player_id.get(),
+            duration,
+            bytes_rx, packets_rx,
+            bytes_tx, packets_tx,
+            outbound.peak_bytes, outbound.dropped_packets
+        );
+    }
+
+    // === Getters for connection statistics ===
+
+    /// Get current connection state
+    pub fn state(&self) -> ConnectionState {
+        *self.state.lock()
+    }
+
+    /// Get time since last activity
+    pub fn idle_time(&self) -> Duration {
+        self.idle.lock().protocol_idle(Instant::now())
+    }
+
+    /// Get time since the player last moved, chatted or warped
+    pub fn gameplay_idle_time(&self) -> Duration {
+        self.idle.lock().gameplay_idle(Instant::now())
+    }
+
+    /// Get total bytes received
+    pub fn bytes_received(&self) -> u64 {
+        *self.bytes_received.lock()
+    }
+
+    /// Get total bytes sent
+    pub fn bytes_sent(&self) -> u64 {
+        *self.bytes_sent.lock()
+    }
+
+    /// Get total packets received
+    pub fn packets_received(&self) -> u64 {
+        *self.packets_received.lock()
+    }
+
+    /// Get total packets sent
+    pub fn packets_sent(&self) -> u64 {
+        *self.packets_sent.lock()
+    }
+
+    /// Get connection uptime
+    pub fn uptime(&self) -> Duration {
+        self.connected_at.elapsed()
+    }
+
+    // === Player state helpers for broadcasting ===
+
+    /// Get the player's current level name
+    ///
+    /// # C++ Equivalence
+    /// Matches `Player::getLevel()` in Player.cpp
+    pub fn get_level(&self) -> String {
+        let account = self.account.lock();
+        account.as_ref()
+            .map(|a| a.level.clone())
+            .unwrap_or_else(|| String::from("onlinestartlocal.nw"))
+    }
+
+    /// Get the player's current position
+    ///
+    /// # Returns
+    /// (x, y) position in tiles
+    pub fn get_position(&self) -> (TileCoord, TileCoord) {
+        let account = self.account.lock();
+        account.as_ref()
+            .map(|a| a.get_tile_pos())
+            .unwrap_or((TileCoord(30.0), TileCoord(30.0)))
+    }
+
+    /// Get the player's current position in pixels
+    pub fn get_pixel_position(&self) -> (i32, i32) {
+        let (x, y) = self.get_position();
+        (x.to_pixels().0, y.to_pixels().0)
+    }
+
+    /// Check if this player is visible to another player
+    ///
+    /// # C++ Equivalence
+    /// Matches visibility checks in TPlayer::isVisibleTo()
+    ///
+    /// # Arguments
+    /// * `other_id` - The player ID to check visibility against
+    ///
+    /// # Returns
+    /// true if this player should be visible to the other player
+    pub fn is_visible_to(&self, other_id: PlayerID) -> bool {
+        // Always visible to self
+        if self.player_id == other_id {
+            return true;
+        }
+
+        let account = self.account.lock();
+        if let Some(acc) = account.as_ref() {
+            // Check if player has invisible permission
+            // C++: playerobj->getHidden() || hasPermission(PLPERM_INVISIBLE)
+            if acc.has_permission(gserver_accounts::PLPERM_INVISIBLE) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Check if this player is authenticated
+    pub fn is_authenticated(&self) -> bool {
+        matches!(self.state(), ConnectionState::Authenticated)
+    }
+
+    /// Get the player's account nickname
+    pub fn get_nickname(&self) -> String {
+        let account = self.account.lock();
+        account.as_ref()
+            .map(|a| a.nick.clone())
+            .unwrap_or_else(|| String::from("Player"))
+    }
+
+    /// Get the player's account name
+    pub fn get_account_name(&self) -> String {
+        let account = self.account.lock();
+        account.as_ref()
+            .map(|a| a.name.clone())
+            .unwrap_or_else(|| String::new())
+    }
+
+    /// Check if the player's account has a weapon
+    pub fn has_weapon(&self, name: &str) -> bool {
+        let account = self.account.lock();
+        account.as_ref().is_some_and(|a| a.has_weapon(name))
+    }
+
+    /// Check if the player takes PMs from an account (see [`crate::social`])
+    pub fn accepts_pm_from(&self, sender: &str) -> bool {
+        let account = self.account.lock();
+        account.as_ref().is_some_and(|a| a.accepts_pm_from(sender))
+    }
+
+    /// Change the player's friend or ignore list
+    ///
+    /// The lists aren't sent to the client (see [`gserver_game::social`]).
+    ///
+    /// # Returns
+    /// `false` if the list already was that way
+    pub fn apply_social_edit(&self, edit: &gserver_game::SocialEdit) -> bool {
+        let changed = self.account.lock().as_mut().is_some_and(|account| crate::social::apply(account, edit));
+        if changed {
+            self.mark_account_dirty();
+        }
+        changed
+    }
+
+    /// Keep this player's state for a reconnect (see [`crate::session`])
+    fn hold_session(&self) {
+        let grace = self.context.config().read().reconnect_grace;
+        if grace == 0 {
+            return;
+        }
+        let Some(account) = self.account.lock().as_ref().map(|a| a.name.clone()) else {
+            return;
+        };
+
+        let now = Instant::now();
+        let session = crate::session::HeldSession {
+            token: self.session_token.lock().clone(),
+            ip: self.peer_addr.ip(),
+            account,
+            group: self.context.groups().lock().group(self.player_id).map(str::to_string),
+            irc_channels: self.context.irc().channels_of(self.player_id),
+            frozen: self.context.control().frozen_action(self.player_id),
+            filter_points: *self.filter_points.lock(),
+            mute_left: self.muted_until.lock().map(|until| until.saturating_duration_since(now)),
+        };
+        tracing::info!("Connection {} dropped, holding the session of {} for {}s",
+            self.player_id.get(), session.account, grace);
+        self.context.sessions().hold(session, Duration::from_secs(grace), now);
+    }
+
+    /// Start a new session token and, if sessions are held, tell the client
+    ///
+    /// # Returns
+    /// The PLO_FLAGSET packet with the token, if reconnects are enabled
+    pub(super) fn start_session(&self) -> Option<gserver_protocol::PacketOut> {
+        use gserver_protocol::{PacketOut, PacketTypeOut};
+
+        let token = crate::session::new_token();
+        *self.session_token.lock() = token.clone();
+        (self.context.config().read().reconnect_grace > 0)
+            .then(|| PacketOut::new(PacketTypeOut::FlagSet, format!("{}={}", crate::session::TOKEN_FLAG, token).into_bytes()))
+    }
+
+    /// Give a resumed session's state back to this connection
+    pub(super) fn resume_session(&self, session: crate::session::HeldSession) {
+        if let Some(group) = &session.group {
+            self.context.groups().lock().set_group(self.player_id, group);
+        }
+        for channel in &session.irc_channels {
+            self.context.irc().join(self.player_id, &session.account, channel);
+        }
+        if let Some(action) = session.frozen {
+            self.context.control().apply(self.player_id, action);
+        }
+        *self.filter_points.lock() = session.filter_points;
+        *self.muted_until.lock() = session.mute_left.map(|left| Instant::now() + left);
+        tracing::info!("Connection {} resumed the session of {}", self.player_id.get(), session.account);
+    }
+
+    /// Get the jail if the player is jailed
+    pub fn jail(&self) -> Option<crate::moderation::Destination> {
+        let now = gserver_game::moderation::unix_now();
+        if !self.account.lock().as_ref().is_some_and(|a| a.is_jailed_at(now)) {
+            return None;
+        }
+        crate::moderation::Destination::jail(&self.context.config().read())
+    }
+
+    /// Apply a sanction, warp the player if it moves them and tell them
+    pub async fn apply_sanction(&self, sanction: gserver_game::Sanction) -> Result<()> {
+        use gserver_protocol::{PacketOut, PacketTypeOut};
+
+        let destination = {
+            let config = self.context.config().read();
+            let mut account = self.account.lock();
+            let Some(account) = account.as_mut() else {
+                return Ok(());
+            };
+            crate::moderation::apply(account, sanction, &config, gserver_game::moderation::unix_now())?
+        };
+        self.mark_account_dirty();
+        tracing::info!("Connection {} sanctioned: {:?}", self.player_id.get(), sanction);
+
+        if let Some(to) = destination {
+            self.send_player_warp(&to.level, TileCoord(to.x), TileCoord(to.y)).await?;
+        }
+        let notice = crate::announce::admin_message_data(crate::announce::SERVER_SENDER, &self.translate(&sanction.notice()));
+        self.send_packet(PacketOut::new(PacketTypeOut::RcAdminMessage, notice)).await
+    }
+
+    /// Change the account's comments, ban record or gralats, and disconnect the player if banned
+    ///
+    /// The account is saved right away so RC dialogs, which read the file, see the change.
+    pub async fn apply_record_edit(&self, edit: &gserver_game::RecordEdit) -> Result<()> {
+        let (message, gralats) = {
+            let mut account = self.account.lock();
+            let Some(account) = account.as_mut() else {
+                return Ok(());
+            };
+            let before = account.gralats;
+            crate::bans::apply(account, edit);
+            let message = account.is_banned_at(gserver_game::moderation::unix_now()).then(|| crate::bans::ban_message(&account.ban_reason));
+            (message, (before, account.gralats))
+        };
+        self.mark_account_dirty();
+        self.save_account()?;
+        if let gserver_game::RecordEdit::Gralats { issuer, .. } = edit {
+            self.audit_gralats(gralats.0, gralats.1, crate::economy::GralatSource::Rc(issuer.clone()));
+            self.send_counts().await?;
+        }
+        match message {
+            Some(message) if !self.is_staff() => self.disconnect(&message).await,
+            _ => Ok(()),
+        }
+    }
+
+    /// Set the account's rights, if given, and apply what changed about its staff status
+    ///
+    /// An RC that lost its rights is disconnected; a player is told it became
+    /// or stopped being staff. Changes go to staff chat.
+    pub async fn reevaluate_staff(&self, rights: Option<u32>) -> Result<()> {
+        use gserver_protocol::{PacketOut, PacketTypeOut};
+
+        let (name, old, new, old_rights) = {
+            let config = self.context.config().read();
+            let mut account = self.account.lock();
+            let Some(account) = account.as_mut() else {
+                return Ok(());
+            };
+            let old_rights = account.local_rights;
+            if let Some(rights) = rights {
+                account.local_rights = rights;
+            }
+            let new = StaffStatus::of(account, &config);
+            (account.name.clone(), std::mem::replace(&mut *self.staff_status.lock(), new), new, old_rights)
+        };
+        if rights.is_some() {
+            self.mark_account_dirty();
+        }
+
+        if let Some(notice) = old.change_notice(&name, &new) {
+            tracing::info!("Connection {}: {}", self.player_id.get(), notice);
+            self.context.events().publish(GameEvent::StaffNotice { message: notice });
+        }
+        if self.is_rc() {
+            if !new.rc {
+                return self.disconnect(crate::staffrights::REVOKED_MESSAGE).await;
+            }
+            let Some(rights) = rights else {
+                return Ok(());
+            };
+            let npc_control = gserver_accounts::PLPERM_NPCCONTROL;
+            if rights & npc_control != 0 && old_rights & npc_control == 0 {
+                self.send_packet(PacketOut::new(PacketTypeOut::HasNpcServer, Vec::new())).await?;
+            }
+            let text = format!("Your rights were changed to {:#x}", rights);
+            return self.send_packet(PacketOut::new(PacketTypeOut::ServerText, text.into_bytes())).await;
+        }
+        if old.staff == new.staff {
+            return Ok(());
+        }
+        let text = if new.staff { "You are now staff." } else { "You are no longer staff." };
+        let notice = crate::announce::admin_message_data(crate::announce::SERVER_SENDER, &self.translate(text));
+        self.send_packet(PacketOut::new(PacketTypeOut::RcAdminMessage, notice)).await
+    }
+
+    /// Send PLO_PLAYERWARP, which makes the client warp there with PLI_LEVELWARP
+    ///
+    /// # C++ Equivalence
+    /// Matches the PLO_PLAYERWARP sent by `PlayerClient::warp`
+    pub async fn send_player_warp(&self, level: &str, x: TileCoord, y: TileCoord) -> Result<()> {
+        use gserver_protocol::packet_builder::build_player_warp;
+
+        let mut warp_data = BytesMut::new();
+        build_player_warp(&mut warp_data, x.to_half_tiles(), y.to_half_tiles(), level);
+
+        // Add directly to queue (build_player_warp already includes packet type and newline)
+        let mut queue = self.outbound_queue.lock().await;
+        queue.add_packet(warp_data, false); // false = not a file packet
+        let should_flush = queue.should_flush();
+        queue.increment_send_cycles();
+        drop(queue);
+        self.schedule_flush();
+
+        // Flush if we should (48KB reached or 4 send cycles)
+        if should_flush {
+            self.process_outbound_queue().await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection_state() {
+        let state = ConnectionState::Connected;
+        assert_eq!(state, ConnectionState::Connected);
+    }
+
+    #[tokio::test]
+    async fn test_recover_from_panic_cleans_up() {
+        let fixture = testutil::test_connection(Default::default()).await;
+        let (context, conn) = (&fixture.context, &fixture.conn);
+        *conn.account.lock() = Some(Account { name: "Crasher".into(), ..Default::default() });
+        conn.mark_account_dirty();
+        context.players().add_player(Arc::new(gserver_game::Player::new(PlayerID(1), gserver_game::PlayerType::Player)));
+        let mut events = context.events().subscribe();
+
+        conn.recover_from_panic("index out of bounds").await;
+        assert!(context.players().get_player(PlayerID(1)).is_none());
+        context.account_saves().wait("crasher").await;
+        assert!(gserver_accounts::AccountLoader::new(fixture.dir.path()).exists("Crasher"));
+        let notice = std::iter::from_fn(|| events.try_recv().ok())
+            .find_map(|event| match event {
+                GameEvent::StaffNotice { message } => Some(message),
+                _ => None,
+            });
+        assert_eq!(notice.as_deref(), Some("Connection 1 (Crasher) crashed: index out of bounds"));
+    }
+}
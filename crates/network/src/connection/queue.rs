@@ -0,0 +1,390 @@
+//! # Outbound Packet Queue
+//!
+//! This module batches outgoing packets into bundles, like the C++ server's
+//! `CFileQueue`.
+//!
+//! # Architecture
+//! - Normal packets: Batched up to 48KB, then compressed and sent
+//! - File packets: Sent in order, interleaved with normal packets
+//!
+//! The queue only decides what goes into the next bundle; compression and the
+//! socket write happen in [`super::io`]. When the next bundle goes out is up
+//! to the [`FlushSchedule`]: nothing wakes an idle connection, and flushes
+//! are at least [`FLUSH_INTERVAL`] apart, so packets queued under load share
+//! a bundle. With bandwidth limits (see
+//! [`crate::bandwidth`]) a [`Budget`] caps the normal and file bytes of a
+//! bundle; a packet is added while its side has budget left, so one packet
+//! may overshoot and the limiter catches up on later bundles.
+//!
+//! # C++ Equivalence
+//! Matches `CFileQueue` in gs2lib/src/CFileQueue.cpp
+
+use bytes::BytesMut;
+use std::time::{Duration, Instant};
+
+/// Shortest time between two flushes of a connection's queue
+pub(crate) const FLUSH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Packets larger than this are sent on their own (60KB)
+const MAX_BUNDLE: usize = 0xF000;
+
+/// Normal packets are batched up to this size (48KB)
+const BATCH_LIMIT: usize = 0xC000;
+
+/// A file packet is added to bundles smaller than this (16KB)
+const FILE_FILL_LIMIT: usize = 0x4000;
+
+/// A file packet is forced after this many bytes without one (32KB)
+const FILE_STARVATION_LIMIT: u32 = 0x7FFF;
+
+/// Bytes the next bundle may carry, by kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Budget {
+    pub(crate) normal: usize,
+    pub(crate) files: usize,
+}
+
+impl Budget {
+    /// No limits
+    pub(crate) const UNLIMITED: Self = Self { normal: usize::MAX, files: usize::MAX };
+
+    /// Take the smaller allowance of each kind
+    pub(crate) fn min(self, other: Self) -> Self {
+        Self { normal: self.normal.min(other.normal), files: self.files.min(other.files) }
+    }
+}
+
+/// The packets of one bundle
+#[derive(Debug)]
+pub(crate) struct Batch {
+    pub(crate) data: BytesMut,
+    /// Number of normal packets
+    pub(crate) packet_count: usize,
+    /// Bytes of file packets in `data`
+    pub(crate) file_bytes: usize,
+}
+
+/// Outbound packet queue for batching
+#[derive(Debug, Default)]
+pub(crate) struct OutboundQueue {
+    /// Normal packets (can be batched)
+    normal_buffer: Vec<BytesMut>,
+
+    /// File packets (must send in order)
+    file_buffer: Vec<BytesMut>,
+
+    /// Total bytes in normal buffer
+    normal_bytes: usize,
+
+    /// Number of send cycles without flushing (for GEN_5 compatibility)
+    send_cycles_without_flush: u32,
+
+    /// Bytes sent without a file packet (for periodic file sending)
+    /// C++: bytesSentWithoutFile in CFileQueue
+    bytes_sent_without_file: u32,
+
+    /// Consecutive batch attempts with no data
+    /// C++: sendCallsWithoutData in CFileQueue
+    send_calls_without_data: u32,
+}
+
+impl OutboundQueue {
+    /// Create a new empty outbound queue
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a packet to the appropriate buffer
+    ///
+    /// # Arguments
+    /// * `packet` - Serialized packet data (including newline)
+    /// * `is_file_packet` - If true, goes to file buffer (must send in order)
+    pub(crate) fn add_packet(&mut self, packet: BytesMut, is_file_packet: bool) {
+        if is_file_packet {
+            self.file_buffer.push(packet);
+        } else {
+            self.normal_bytes += packet.len();
+            self.normal_buffer.push(packet);
+        }
+    }
+
+    /// Check if there are packets waiting
+    #[inline]
+    pub(crate) fn has_data(&self) -> bool {
+        self.normal_bytes > 0 || !self.file_buffer.is_empty()
+    }
+
+    /// Get the bytes of normal packets waiting (what backpressure limits)
+    #[inline]
+    pub(crate) fn queued_bytes(&self) -> usize {
+        self.normal_bytes
+    }
+
+    /// Drop every queued normal packet
+    pub(crate) fn clear_normal(&mut self) {
+        self.normal_buffer.clear();
+        self.normal_bytes = 0;
+    }
+
+    /// Check if we should flush (C++ logic: >= 48KB or >= 4 send cycles)
+    #[inline]
+    pub(crate) fn should_flush(&self) -> bool {
+        self.normal_bytes >= BATCH_LIMIT || self.send_cycles_without_flush >= 4
+    }
+
+    /// Increment send cycle counter
+    #[inline]
+    pub(crate) fn increment_send_cycles(&mut self) {
+        self.send_cycles_without_flush += 1;
+    }
+
+    /// Take the packets for the next bundle
+    ///
+    /// # Purpose
+    /// Matches C++ CFileQueue::sendCompress() behavior EXACTLY:
+    /// - Handles huge packets (>60KB) immediately
+    /// - Forces file packets if not sent in 32KB
+    /// - Batches normal packets up to 48KB
+    /// - Tries to add file packet if under 16KB
+    /// - Tracks bytes sent without file
+    /// - Tracks empty send calls
+    ///
+    /// # Returns
+    /// The concatenated packets and the number of normal packets in them, or
+    /// `None` if there is nothing to send
+    ///
+    /// # C++ Equivalence
+    /// Corresponds to `CFileQueue::sendCompress()` in CFileQueue.cpp lines 103-263
+    pub(crate) fn take_batch(&mut self) -> Option<(BytesMut, usize)> {
+        self.take_shaped_batch(Budget::UNLIMITED).map(|batch| (batch.data, batch.packet_count))
+    }
+
+    /// Take the packets for the next bundle within a bandwidth budget
+    ///
+    /// Same as [`Self::take_batch`], except a kind of packet is only added
+    /// while its budget isn't used up. Held packets stay queued in order.
+    pub(crate) fn take_shaped_batch(&mut self, budget: Budget) -> Option<Batch> {
+        let mut batch = BytesMut::new();
+        let mut packet_count = 0;
+        let mut file_bytes = 0;
+
+        // C++: "If the next normal packet is huge, lets 'try' to send it."
+        // C++: "Everything else should skip because this may throw is way over the limit."
+        if budget.normal > 0 && self.normal_buffer.first().is_some_and(|p| p.len() > MAX_BUNDLE) {
+            let packet = self.normal_buffer.remove(0);
+            batch.extend_from_slice(&packet);
+            packet_count += 1;
+        }
+
+        // C++: "If we haven't sent a file in a while, forcibly send one now."
+        // C++: if (pSend.length() == 0 && (bytesSentWithoutFile > 0x7FFF || forceSendFiles || sendCallsWithoutData >= 4) && !fileBuffer.empty())
+        if batch.is_empty()
+            && budget.files > 0
+            && (self.bytes_sent_without_file > FILE_STARVATION_LIMIT || !self.file_buffer.is_empty())
+            && self.file_buffer.first().is_some_and(|p| p.len() <= MAX_BUNDLE)
+        {
+            self.bytes_sent_without_file = 0;
+            let file_packet = self.file_buffer.remove(0);
+            file_bytes += file_packet.len();
+            batch.extend_from_slice(&file_packet);
+        }
+
+        // C++: "Keep adding packets from normalBuffer until we hit 48KB"
+        // C++: while (pSend.length() < 0xC000 && !normalBuffer.empty())
+        while !self.normal_buffer.is_empty() && batch.len() < BATCH_LIMIT && batch.len() - file_bytes < budget.normal {
+            // C++: "If the next packet sticks us over 60KB, don't add it."
+            // C++: if (pSend.length() + normalBuffer.front().length() > 0xF000) break;
+            if batch.len() + self.normal_buffer[0].len() > MAX_BUNDLE {
+                break;
+            }
+            let packet = self.normal_buffer.remove(0);
+            batch.extend_from_slice(&packet);
+            packet_count += 1;
+        }
+
+        // C++: bytesSentWithoutFile += pSend.length();
+        self.bytes_sent_without_file += batch.len() as u32;
+
+        // C++: "If we have less than 16KB of data, try to add a file."
+        // C++: if (pSend.length() < 0x4000 && !fileBuffer.empty())
+        if batch.len() < FILE_FILL_LIMIT
+            && file_bytes < budget.files
+            && self.file_buffer.first().is_some_and(|p| batch.len() + p.len() <= MAX_BUNDLE)
+        {
+            self.bytes_sent_without_file = 0;
+            let file_packet = self.file_buffer.remove(0);
+            file_bytes += file_packet.len();
+            batch.extend_from_slice(&file_packet);
+            tracing::debug!("Included file packet in batch");
+        }
+
+        // C++: "Reset this if we have no files to send."
+        // C++: if (fileBuffer.empty()) bytesSentWithoutFile = 0;
+        if self.file_buffer.is_empty() {
+            self.bytes_sent_without_file = 0;
+        }
+
+        self.normal_bytes = self.normal_buffer.iter().map(|p| p.len()).sum();
+        self.send_cycles_without_flush = 0;
+
+        // C++: "If we have no data, just return."
+        // C++: if (pSend.length() == 0) { if (sendCallsWithoutData < 5) { sendCallsWithoutData++; } return; }
+        if batch.is_empty() {
+            if self.send_calls_without_data < 5 {
+                self.send_calls_without_data += 1;
+            }
+            return None;
+        }
+        self.send_calls_without_data = 0;
+
+        Some(Batch { data: batch, packet_count, file_bytes })
+    }
+}
+
+/// When a connection's queue is flushed next
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct FlushSchedule {
+    last: Option<Instant>,
+    due: Option<Instant>,
+}
+
+impl FlushSchedule {
+    /// Schedule a flush for queued packets, [`FLUSH_INTERVAL`] after the last
+    /// one or right away
+    ///
+    /// # Returns
+    /// When the flush is due; a flush already scheduled keeps its time
+    pub(crate) fn arm(&mut self, now: Instant) -> Instant {
+        *self.due.get_or_insert_with(|| self.last.map_or(now, |last| (last + FLUSH_INTERVAL).max(now)))
+    }
+
+    /// Get when the scheduled flush is due, if there is one
+    pub(crate) fn due(&self) -> Option<Instant> {
+        self.due
+    }
+
+    /// Record a flush
+    pub(crate) fn flushed(&mut self, now: Instant) {
+        self.last = Some(now);
+        self.due = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(len: usize) -> BytesMut {
+        BytesMut::from(&vec![b'x'; len][..])
+    }
+
+    #[test]
+    fn test_flush_schedule_coalesces() {
+        let start = Instant::now();
+        let mut schedule = FlushSchedule::default();
+        assert_eq!(schedule.due(), None);
+        assert_eq!(schedule.arm(start), start);
+        schedule.flushed(start);
+
+        let soon = start + Duration::from_millis(10);
+        assert_eq!(schedule.arm(soon), start + FLUSH_INTERVAL);
+        assert_eq!(schedule.arm(soon + Duration::from_millis(5)), start + FLUSH_INTERVAL);
+        schedule.flushed(start + FLUSH_INTERVAL);
+
+        let later = start + Duration::from_secs(5);
+        assert_eq!(schedule.arm(later), later);
+    }
+
+    #[test]
+    fn test_batches_normal_packets_up_to_limit() {
+        let mut queue = OutboundQueue::new();
+        for _ in 0..4 {
+            queue.add_packet(packet(0x4000), false);
+        }
+        assert!(queue.should_flush());
+
+        // 3 x 16KB reaches the 48KB batch limit
+        let (batch, count) = queue.take_batch().unwrap();
+        assert_eq!((batch.len(), count), (0xC000, 3));
+        assert!(queue.has_data());
+
+        let (batch, count) = queue.take_batch().unwrap();
+        assert_eq!((batch.len(), count), (0x4000, 1));
+        assert!(!queue.has_data());
+        assert!(queue.take_batch().is_none());
+    }
+
+    #[test]
+    fn test_send_cycles_trigger_flush() {
+        let mut queue = OutboundQueue::new();
+        queue.add_packet(packet(10), false);
+        for _ in 0..4 {
+            assert!(!queue.should_flush());
+            queue.increment_send_cycles();
+        }
+        assert!(queue.should_flush());
+
+        queue.take_batch().unwrap();
+        assert!(!queue.should_flush());
+    }
+
+    #[test]
+    fn test_file_packets_fill_small_batches() {
+        let mut queue = OutboundQueue::new();
+        queue.add_packet(packet(100), false);
+        queue.add_packet(packet(200), true);
+        queue.add_packet(packet(300), true);
+        assert!(queue.has_data());
+
+        // The first file packet is sent first, then normal packets, then
+        // another file packet fills the small bundle
+        let (batch, count) = queue.take_batch().unwrap();
+        assert_eq!((batch.len(), count), (600, 1));
+        assert!(!queue.has_data());
+        assert!(queue.take_batch().is_none());
+    }
+
+    #[test]
+    fn test_queued_bytes_count_normal_packets_only() {
+        let mut queue = OutboundQueue::new();
+        queue.add_packet(packet(100), false);
+        queue.add_packet(packet(5000), true);
+        queue.add_packet(packet(50), false);
+        assert_eq!(queue.queued_bytes(), 150);
+
+        queue.clear_normal();
+        assert_eq!(queue.queued_bytes(), 0);
+        assert!(queue.has_data());
+        let (batch, count) = queue.take_batch().unwrap();
+        assert_eq!((batch.len(), count), (5000, 0));
+    }
+
+    #[test]
+    fn test_budget_holds_packets() {
+        let mut queue = OutboundQueue::new();
+        queue.add_packet(packet(100), false);
+        queue.add_packet(packet(100), false);
+        queue.add_packet(packet(500), true);
+
+        // Files held, one normal packet overshoots a 50 byte budget
+        let batch = queue.take_shaped_batch(Budget { normal: 50, files: 0 }).unwrap();
+        assert_eq!((batch.data.len(), batch.packet_count, batch.file_bytes), (100, 1, 0));
+        assert!(queue.take_shaped_batch(Budget { normal: 0, files: 0 }).is_none());
+
+        let batch = queue.take_shaped_batch(Budget { normal: 0, files: 1 }).unwrap();
+        assert_eq!((batch.data.len(), batch.packet_count, batch.file_bytes), (500, 0, 500));
+        assert_eq!(queue.queued_bytes(), 100);
+    }
+
+    #[test]
+    fn test_huge_packet_sent_alone() {
+        let mut queue = OutboundQueue::new();
+        queue.add_packet(packet(0xF001), false);
+        queue.add_packet(packet(10), false);
+
+        let (batch, count) = queue.take_batch().unwrap();
+        assert_eq!((batch.len(), count), (0xF001, 1));
+        let (batch, count) = queue.take_batch().unwrap();
+        assert_eq!((batch.len(), count), (10, 1));
+    }
+}
@@ -0,0 +1,247 @@
+//! # Session Replay
+//!
+//! Replays the client side of recorded sessions (`fixtures/sessions`)
+//! against a real connection over loopback and compares what the server
+//! sends back with the recorded server side.
+//!
+//! # Session Format
+//!
+//! ```text
+//! # comment
+//! gen 5                       encryption generation of the session
+//! key 73                      encryption key from the login packet
+//! > 78 9c 4b ...              one client bundle, as sent (after the length prefix)
+//! < 2a 20 21 ?? 0a ...        server packets expected after it, after decryption
+//! ```
+//!
+//! Consecutive `<` lines are one expected stream. The server's output is
+//! compared after decoding, not as raw bundles: how packets are grouped into
+//! bundles depends on flush timing, and compressed bytes depend on the zlib
+//! build. `??` matches any byte (world time, file modification times).
+//!
+//! Every session runs on a fresh copy of `fixtures/server`.
+//!
+//! The checked-in sessions were recorded from this server, so passing them
+//! shows no regression, not parity with the C++ server (see
+//! `fixtures/README.md`).
+
+use super::crypto::{codec_for, GraalCodec};
+use super::io::{read_bundle, write_bundle};
+use super::PlayerConnection;
+use crate::compression::Compressor;
+use crate::context::ServerContext;
+use gserver_config::{ServerConfig as GameConfig, WordFilter};
+use gserver_core::PlayerID;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+
+/// Longest wait for the expected server output of one client bundle
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// One client bundle and the server output it must produce
+#[derive(Debug)]
+struct Exchange {
+    /// Line of the `>` in the session file
+    line: usize,
+    client: Vec<u8>,
+    /// Expected decoded server output, `None` for `??`
+    server: Vec<Option<u8>>,
+}
+
+/// A parsed session file
+#[derive(Debug)]
+struct Session {
+    name: String,
+    gen: u8,
+    key: u8,
+    exchanges: Vec<Exchange>,
+}
+
+fn parse_hex(text: &str, line: usize) -> Vec<Option<u8>> {
+    text.split_whitespace()
+        .map(|byte| match byte {
+            "??" => None,
+            hex => Some(u8::from_str_radix(hex, 16)
+                .unwrap_or_else(|_| panic!("line {}: bad hex byte {:?}", line, hex))),
+        })
+        .collect()
+}
+
+fn parse_session(name: &str, text: &str) -> Session {
+    let mut session = Session { name: name.to_string(), gen: 1, key: 0, exchanges: Vec::new() };
+    for (i, raw) in text.lines().enumerate() {
+        let line = i + 1;
+        let raw = raw.trim();
+        if raw.is_empty() || raw.starts_with('#') {
+            continue;
+        }
+        let (tag, rest) = raw.split_once(char::is_whitespace).unwrap_or((raw, ""));
+        match tag {
+            "gen" => session.gen = rest.trim().parse().unwrap_or_else(|_| panic!("line {}: bad gen", line)),
+            "key" => session.key = rest.trim().parse().unwrap_or_else(|_| panic!("line {}: bad key", line)),
+            ">" => {
+                let client = parse_hex(rest, line).into_iter()
+                    .map(|b| b.unwrap_or_else(|| panic!("line {}: wildcard in a client bundle", line)))
+                    .collect();
+                session.exchanges.push(Exchange { line, client, server: Vec::new() });
+            }
+            "<" => session.exchanges.last_mut()
+                .unwrap_or_else(|| panic!("line {}: server bytes before any client bundle", line))
+                .server.extend(parse_hex(rest, line)),
+            other => panic!("line {}: unknown tag {:?}", line, other),
+        }
+    }
+    session
+}
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures")
+}
+
+fn copy_dir(from: &Path, to: &Path) {
+    std::fs::create_dir_all(to).unwrap();
+    for entry in std::fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        let target = to.join(entry.file_name());
+        if entry.file_type().unwrap().is_dir() {
+            copy_dir(&entry.path(), &target);
+        } else {
+            std::fs::copy(entry.path(), target).unwrap();
+        }
+    }
+}
+
+/// Load the parts of the server's config the sessions depend on
+fn server_config(server_dir: &Path) -> GameConfig {
+    let mut config = GameConfig::default();
+    if let Ok(rules) = std::fs::read_to_string(server_dir.join("config/rules.txt")) {
+        config.word_filter = WordFilter::parse(&rules);
+    }
+    config
+}
+
+/// Find the first byte that differs from the expected output
+fn first_mismatch(expected: &[Option<u8>], actual: &[u8]) -> Option<usize> {
+    (0..expected.len().max(actual.len())).find(|&i| match (expected.get(i), actual.get(i)) {
+        (Some(None), Some(_)) => false,
+        (Some(Some(e)), Some(a)) => e != a,
+        _ => true,
+    })
+}
+
+fn show(expected: &[Option<u8>], actual: &[u8], at: usize) -> String {
+    let from = at.saturating_sub(8);
+    let expected = expected.iter().skip(from).take(24)
+        .map(|b| b.map_or("??".to_string(), |b| format!("{:02x}", b)))
+        .collect::<Vec<_>>().join(" ");
+    let actual = actual.iter().skip(from).take(24).map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" ");
+    format!("at byte {} (showing from {}):\n  expected {}\n  actual   {}", at, from, expected, actual)
+}
+
+/// Replay a session on a fresh fixture server and panic on the first difference
+async fn replay(session: &Session) {
+    let server_dir = tempfile::tempdir().unwrap();
+    copy_dir(&fixtures_dir().join("server"), server_dir.path());
+    replay_on(session, server_dir.path(), server_config(server_dir.path())).await;
+}
+
+/// Replay a session on a server directory
+async fn replay_on(session: &Session, server_dir: &Path, config: GameConfig) {
+    let context = Arc::new(ServerContext::new(server_dir, config));
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let mut client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+    let (socket, peer_addr) = listener.accept().await.unwrap();
+    let connection = Arc::new(PlayerConnection::new(PlayerID(1), socket, peer_addr, context));
+    let task = tokio::spawn({
+        let connection = connection.clone();
+        async move { connection.run().await }
+    });
+
+    let mut codec: Box<dyn GraalCodec> = codec_for(session.gen, session.key, &Compressor::default());
+    for exchange in &session.exchanges {
+        write_bundle(&mut client, &exchange.client).await.unwrap();
+
+        let mut received = Vec::new();
+        while received.len() < exchange.server.len() {
+            let bundle = tokio::time::timeout(REPLY_TIMEOUT, read_bundle(&mut client)).await
+                .unwrap_or_else(|_| panic!("{}:{}: timed out after {} of {} bytes",
+                    session.name, exchange.line, received.len(), exchange.server.len()))
+                .unwrap()
+                .unwrap_or_else(|| panic!("{}:{}: server closed the connection", session.name, exchange.line));
+            received.extend(codec.decode(&bundle).unwrap());
+        }
+
+        if let Some(at) = first_mismatch(&exchange.server, &received) {
+            panic!("{}:{}: server output differs {}", session.name, exchange.line, show(&exchange.server, &received, at));
+        }
+    }
+
+    drop(client);
+    task.await.unwrap().unwrap();
+}
+
+fn load_session(file: &str) -> Session {
+    let path = fixtures_dir().join("sessions").join(file);
+    let text = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+    parse_session(file, &text)
+}
+
+async fn replay_file(file: &str) {
+    replay(&load_session(file)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_session() {
+        let session = parse_session("inline", "gen 5\nkey 73\n# login\n> 01 02\n< 2a ??\n< 0a\n> 03\n");
+        assert_eq!((session.gen, session.key), (5, 73));
+        assert_eq!(session.exchanges.len(), 2);
+        assert_eq!(session.exchanges[0].client, [1, 2]);
+        assert_eq!(session.exchanges[0].server, [Some(0x2a), None, Some(0x0a)]);
+        assert!(session.exchanges[1].server.is_empty());
+
+        assert_eq!(first_mismatch(&session.exchanges[0].server, &[0x2a, 0x99, 0x0a]), None);
+        assert_eq!(first_mismatch(&session.exchanges[0].server, &[0x2a, 0x99, 0x0b]), Some(2));
+        assert_eq!(first_mismatch(&session.exchanges[0].server, &[0x2a, 0x99]), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_replay_login() {
+        replay_file("login.stream").await;
+    }
+
+    #[tokio::test]
+    async fn test_replay_levelwarp() {
+        replay_file("levelwarp.stream").await;
+    }
+
+    #[tokio::test]
+    async fn test_replay_chat() {
+        replay_file("chat.stream").await;
+    }
+
+    /// A session recorded by [`crate::recorder`] replays like a fixture
+    #[tokio::test]
+    async fn test_replay_recording() {
+        let server_dir = tempfile::tempdir().unwrap();
+        copy_dir(&fixtures_dir().join("server"), server_dir.path());
+        let config = GameConfig { record_sessions: vec!["fixture".into()], ..server_config(server_dir.path()) };
+        replay_on(&load_session("chat.stream"), server_dir.path(), config).await;
+
+        let recordings: Vec<_> = std::fs::read_dir(server_dir.path().join(crate::recorder::RECORDINGS_DIR)).unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(recordings.len(), 1);
+        let session = parse_session("recording", &std::fs::read_to_string(&recordings[0]).unwrap());
+        assert_eq!((session.gen, session.key, session.exchanges.len()), (5, 73, 3));
+        let login = super::super::crypto::decompress_zlib(&session.exchanges[0].client).unwrap();
+        assert!(!login.windows(6).any(|w| w == b"secret"));
+        replay(&session).await;
+    }
+}
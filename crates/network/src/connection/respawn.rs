@@ -0,0 +1,116 @@
+//! # Death and Respawn
+//!
+//! This module drops the items of a player whose hearts reached 0 and
+//! respawns them once the delay is over (see [`crate::respawn`]). Deaths and
+//! kills are counted in [`crate::playerstats`].
+
+use super::PlayerConnection;
+use crate::respawn::{Drops, HOME_FLAG};
+use bytes::BytesMut;
+use gserver_core::{Result, TileCoord};
+use gserver_game::properties::{encode_prop, PlayerProp, PropValue};
+use gserver_protocol::codecs::write_gchar;
+use gserver_protocol::{PacketOut, PacketTypeOut};
+use std::time::{Duration, Instant};
+
+impl PlayerConnection {
+    /// Remember the player's hearts (in half hearts), which other players' hits check
+    pub(super) fn record_power(&self, power: u8) {
+        if let Some(player) = self.context.players().get_player(self.player_id) {
+            player.properties.lock().cur_power = power;
+        }
+    }
+
+    /// Handle hearts reaching 0: count the death and drop items
+    pub(super) async fn handle_death(&self) -> Result<()> {
+        let config = self.context.config().read().respawn;
+        if !config.enabled || !self.respawn.lock().died(Instant::now()) {
+            return Ok(());
+        }
+        let sparring = self.context.levels().get_level(&self.get_level()).await?.flags.sparring;
+
+        let (drops, before) = {
+            let mut account = self.account.lock();
+            let Some(account) = account.as_mut() else { return Ok(()) };
+            let drops = match sparring {
+                true => Drops::default(),
+                false => Drops::compute(&config, account.gralats, account.arrows, account.bombs),
+            };
+            let before = account.gralats;
+            account.gralats -= drops.gralats;
+            account.arrows -= drops.arrows;
+            account.bombs -= drops.bombs;
+            account.deaths += 1;
+            (drops, before)
+        };
+        self.mark_account_dirty();
+        self.audit_gralats(before, before - drops.gralats, crate::economy::GralatSource::Death);
+        let killer = self.context.player_stats().died(self.player_id, &self.get_account_name(), Instant::now());
+        tracing::info!("Connection {} died (killed by {}), dropping {} items",
+            self.player_id.get(), killer.as_deref().unwrap_or("nobody"), drops.items.len());
+        if drops.items.is_empty() {
+            return Ok(());
+        }
+
+        self.send_counts().await?;
+        let (x, y) = self.get_position();
+        let (x, y) = ((x.0 * 2.0).clamp(0.0, 127.0) as u8, (y.0 * 2.0).clamp(0.0, 127.0) as u8);
+        let instance = self.instance_key();
+        for (x, y, item) in drops.placed(x, y) {
+            self.context.groups().lock().instance_mut(instance.clone()).add_item(x, y, item);
+            let mut data = BytesMut::new();
+            for value in [x, y, item] {
+                write_gchar(&mut data, value as i8);
+            }
+            self.publish_to_instance(instance.clone(), PacketTypeOut::ItemAdd, &data);
+            self.send_packet(PacketOut::new(PacketTypeOut::ItemAdd, data.to_vec())).await?;
+        }
+        Ok(())
+    }
+
+    /// Respawn a dead player whose delay is over
+    ///
+    /// Called from the connection's main loop once the respawn delay is over.
+    pub(super) async fn check_respawn(&self) -> Result<()> {
+        let delay = Duration::from_secs(self.context.config().read().respawn.delay);
+        if !self.respawn.lock().is_due(Instant::now(), delay) {
+            return Ok(());
+        }
+
+        let to = match self.jail() {
+            Some(jail) => jail,
+            None => {
+                let home = self.account.lock().as_ref()
+                    .and_then(|a| a.get_flag(HOME_FLAG).map(|flag| flag.as_str().into_owned()));
+                self.respawn.lock().respawn_point(home.as_deref(), &self.context.config().read())
+            }
+        };
+        let hearts = {
+            let mut account = self.account.lock();
+            let Some(account) = account.as_mut() else { return Ok(()) };
+            account.hp = account.max_hp;
+            account.hp
+        };
+        self.respawn.lock().respawned();
+        self.record_power((hearts * 2.0) as u8);
+        self.mark_account_dirty();
+        tracing::info!("Connection {} respawning at {} ({}, {})", self.player_id.get(), to.level, to.x, to.y);
+
+        let mut data = BytesMut::new();
+        encode_prop(PlayerProp::CurPower, &PropValue::Int((hearts * 2.0) as i64), self.client_version(), &mut data);
+        self.send_packet(PacketOut::new(PacketTypeOut::PlayerProps, data.to_vec())).await?;
+        self.send_player_warp(&to.level, TileCoord(to.x), TileCoord(to.y)).await
+    }
+
+    /// Send the player's gralat, arrow and bomb counts
+    pub(super) async fn send_counts(&self) -> Result<()> {
+        let Some((gralats, arrows, bombs)) = self.account.lock().as_ref().map(|a| (a.gralats, a.arrows, a.bombs)) else {
+            return Ok(());
+        };
+        let mut data = BytesMut::new();
+        for (prop, value) in [(PlayerProp::RupeesCount, gralats), (PlayerProp::ArrowsCount, arrows), (PlayerProp::BombsCount, bombs)] {
+            encode_prop(prop, &PropValue::Int(value.into()), self.client_version(), &mut data);
+        }
+        self.send_packet(PacketOut::new(PacketTypeOut::PlayerProps, data.to_vec())).await
+    }
+}
@@ -0,0 +1,130 @@
+//! # Script Edits
+//!
+//! This module handles weapon and class edits sent from NC and RC, which
+//! need PLPERM_NPCCONTROL, and the RC commands over their version history
+//! (see [`crate::scripthistory`]).
+//!
+//! # Packet Formats
+//! ```text
+//! PLI_NC_WEAPONADD: {GSTRING name}{GSTRING image}{script}
+//! PLI_NC_CLASSEDIT, PLI_NC_CLASSADD: {GSTRING name}{script}
+//! ```
+//! Script lines are separated by `0xA7`, like in PLO_NPCWEAPONADD.
+
+use super::PlayerConnection;
+use crate::scripthistory::{self, ScriptKind, DIFF_LINES};
+use bytes::BytesMut;
+use gserver_accounts::PLPERM_NPCCONTROL;
+use gserver_core::Result;
+use gserver_game::Weapon;
+use gserver_protocol::codecs::read_gstring;
+use gserver_protocol::{PacketOut, PacketTypeOut};
+
+/// Turn the `0xA7` line separators of a script back into newlines
+fn script_text(data: &[u8]) -> String {
+    let lines: Vec<u8> = data.iter().map(|&b| if b == 0xA7 { b'\n' } else { b }).collect();
+    String::from_utf8_lossy(&lines).into_owned()
+}
+
+impl PlayerConnection {
+    /// Handle PLI_NC_WEAPONADD (117): add or replace a weapon
+    pub(super) async fn handle_nc_weapon_add(&self, packet_data: &[u8]) -> Result<()> {
+        if !self.has_rc_right(Some(PLPERM_NPCCONTROL)) {
+            tracing::warn!("Connection {} sent a weapon without NPC control rights", self.player_id.get());
+            return Ok(());
+        }
+        let mut buf = BytesMut::from(packet_data);
+        let name = read_gstring(&mut buf)?;
+        let image = read_gstring(&mut buf)?;
+        if name.is_empty() {
+            return Ok(());
+        }
+
+        tracing::info!("{} edited weapon {}", self.get_account_name(), name);
+        let reply = match crate::weaponsync::update_weapon(&self.context, Weapon::new(&name, image, script_text(&buf))) {
+            Ok(_) => format!("Weapon {} updated", name),
+            Err(e) => format!("Weapon {} updated but not saved: {}", name, e),
+        };
+        self.send_packet(PacketOut::new(PacketTypeOut::ServerText, reply.into_bytes())).await
+    }
+
+    /// Handle PLI_NC_CLASSEDIT (112) and PLI_NC_CLASSADD (113): add or replace a class
+    pub(super) async fn handle_nc_class_edit(&self, packet_data: &[u8]) -> Result<()> {
+        if !self.has_rc_right(Some(PLPERM_NPCCONTROL)) {
+            tracing::warn!("Connection {} sent a class without NPC control rights", self.player_id.get());
+            return Ok(());
+        }
+        let mut buf = BytesMut::from(packet_data);
+        let name = read_gstring(&mut buf)?;
+        if name.is_empty() {
+            return Ok(());
+        }
+
+        tracing::info!("{} edited class {}", self.get_account_name(), name);
+        let reply = match crate::weaponsync::update_class(&self.context, &name, &script_text(&buf)) {
+            Ok(_) => format!("Class {} updated", name),
+            Err(e) => format!("Class {} updated but not saved: {}", name, e),
+        };
+        self.send_packet(PacketOut::new(PacketTypeOut::ServerText, reply.into_bytes())).await
+    }
+
+    /// Run RC `/scripthistory`, `/scriptdiff` or `/scriptrevert`
+    ///
+    /// # Returns
+    /// The reply lines
+    pub(super) fn rc_script_history(&self, command: &str, args: &[&str]) -> Vec<String> {
+        let usage = || vec![format!("Usage: {} <weapon|class> <name>{}", command,
+            if command == "/scripthistory" { "" } else { " <version>" })];
+        let (kind, name, version) = match args {
+            [kind, name] => (ScriptKind::parse(kind), *name, None),
+            [kind, name, version] => match version.parse::<u32>() {
+                Ok(version) => (ScriptKind::parse(kind), *name, Some(version)),
+                Err(_) => return usage(),
+            },
+            _ => return usage(),
+        };
+        let Some(kind) = kind else { return usage() };
+        // Old versions are script source, so reading them needs NC rights too
+        if !self.has_rc_right(Some(PLPERM_NPCCONTROL)) {
+            return vec!["You don't have the right to edit scripts".to_string()];
+        }
+        let history = self.context.script_history();
+
+        match (command, version) {
+            ("/scripthistory", None) => {
+                let versions = history.versions(kind, name);
+                if versions.is_empty() {
+                    return vec![format!("No previous versions of {} {}", kind, name)];
+                }
+                let listed: Vec<String> = versions.iter().map(|v| format!("{} ({})", v.number, v.stamp)).collect();
+                vec![format!("Versions of {} {}: {}", kind, name, listed.join(", "))]
+            }
+            ("/scriptdiff", Some(version)) => {
+                let Some(old) = history.load(kind, name, version) else {
+                    return vec![format!("No version {} of {} {}", version, kind, name)];
+                };
+                let current = scripthistory::current_source(&self.context, kind, name).unwrap_or_default();
+                let lines = scripthistory::diff(&old, &current);
+                let mut reply = vec![format!("{} {}: {} lines changed since version {}", kind, name, lines.len(), version)];
+                let shown = lines.len().min(DIFF_LINES);
+                reply.extend(lines.into_iter().take(shown));
+                reply
+            }
+            ("/scriptrevert", Some(version)) => {
+                tracing::info!("{} reverted {} {} to version {}", self.get_account_name(), kind, name, version);
+                vec![scripthistory::revert(&self.context, kind, name, version)]
+            }
+            _ => usage(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_text() {
+        assert_eq!(script_text(b"//#CLIENTSIDE\xa7foo();\xa7"), "//#CLIENTSIDE\nfoo();\n");
+    }
+}
@@ -0,0 +1,99 @@
+//! # Shops and Dialogs
+//!
+//! This module answers the shop and dialog trigger actions of
+//! [`crate::shops`] from the variables of the triggering NPC.
+
+use super::PlayerConnection;
+use crate::economy::GralatSource;
+use crate::playerstats::Stat;
+use crate::shops::{buy, sell, say_data, Offer, Refusal, ShopTrigger, Trade};
+use bytes::BytesMut;
+use gserver_core::Result;
+use gserver_game::properties::{encode_prop, PlayerProp, PropValue};
+use gserver_protocol::{PacketOut, PacketTypeOut};
+use gserver_scripting::{npc_script_name, NpcStateHandler};
+
+impl PlayerConnection {
+    /// Carry out a shop or dialog trigger action of an NPC
+    ///
+    /// Ignored unless the NPC is on the player's level.
+    pub(super) async fn handle_shop_trigger(&self, npc: u32, trigger: &ShopTrigger) -> Result<()> {
+        if self.context.levels().get_level(&self.get_level()).await?.get_npc(npc).is_none() {
+            tracing::debug!("Connection {} triggered NPC {}, which isn't on its level", self.player_id.get(), npc);
+            return Ok(());
+        }
+        let value = self.context.npc_saves().get_var(&npc_script_name(npc), &trigger.var_name());
+        let Some(value) = value else {
+            tracing::debug!("NPC {} has no {}", npc, trigger.var_name());
+            return match trigger {
+                ShopTrigger::Buy(_) | ShopTrigger::Sell(_) => self.send_shop_message(Refusal::Unknown.message()).await,
+                ShopTrigger::Dialog(_) | ShopTrigger::Say(_) => Ok(()),
+            };
+        };
+
+        match trigger {
+            ShopTrigger::Dialog(_) => self.send_shop_message(&value).await,
+            ShopTrigger::Say(_) => {
+                let text = self.translate(&value);
+                self.send_packet(PacketOut::new(PacketTypeOut::Say2, say_data(&text))).await
+            }
+            ShopTrigger::Buy(item) | ShopTrigger::Sell(item) => {
+                let Some(offer) = Offer::parse(&value) else {
+                    tracing::warn!("NPC {} has an invalid {}: {}", npc, trigger.var_name(), value);
+                    return self.send_shop_message(Refusal::Unknown.message()).await;
+                };
+                let weapon_exists = self.context.weapons().build(item, self.context.classes()).is_some();
+                let (result, before, after) = {
+                    let mut account = self.account.lock();
+                    let Some(account) = account.as_mut() else { return Ok(()) };
+                    let before = account.gralats;
+                    let result = match trigger {
+                        ShopTrigger::Buy(_) => buy(account, item, offer, weapon_exists),
+                        _ => sell(account, item, offer),
+                    };
+                    (result, before, account.gralats)
+                };
+                match result {
+                    Ok(trade) => {
+                        self.mark_account_dirty();
+                        self.audit_gralats(before, after, GralatSource::Shop(npc));
+                        if let ShopTrigger::Sell(_) = trigger {
+                            self.context.player_stats().add(&self.get_account_name(), Stat::Gralats, offer.price.into());
+                        }
+                        tracing::info!("Connection {} traded {}x {} for {} gralats with NPC {}",
+                            self.player_id.get(), offer.amount, item, offer.price, npc);
+                        self.send_trade(trade).await
+                    }
+                    Err(refusal) => self.send_shop_message(refusal.message()).await,
+                }
+            }
+        }
+    }
+
+    /// Send what a trade changed
+    async fn send_trade(&self, trade: Trade) -> Result<()> {
+        match trade {
+            Trade::Counts => self.send_counts().await,
+            Trade::Hearts => {
+                let Some(hearts) = self.account.lock().as_ref().map(|a| a.hp) else { return Ok(()) };
+                self.record_power((hearts * 2.0) as u8);
+                let mut data = BytesMut::new();
+                encode_prop(PlayerProp::CurPower, &PropValue::Int((hearts * 2.0) as i64), self.client_version(), &mut data);
+                self.send_packet(PacketOut::new(PacketTypeOut::PlayerProps, data.to_vec())).await?;
+                self.send_counts().await
+            }
+            Trade::Weapon(name) => {
+                if let Some(build) = self.context.weapons().build(&name, self.context.classes()) {
+                    self.deliver_weapon(&build).await?;
+                }
+                self.send_counts().await
+            }
+        }
+    }
+
+    /// Show a shop message in a dialog window (PLO_RPGWINDOW)
+    async fn send_shop_message(&self, text: &str) -> Result<()> {
+        let data = crate::motd::rpg_window_data(&self.translate(text));
+        self.send_packet(PacketOut::new(PacketTypeOut::RpgWindow, data)).await
+    }
+}
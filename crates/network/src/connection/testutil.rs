@@ -0,0 +1,63 @@
+//! # Connection Test Fixtures
+//!
+//! One loopback connection on a fresh server directory, shared by the
+//! connection tests.
+
+use super::{ConnectionState, PlayerConnection};
+use crate::context::ServerContext;
+use gserver_accounts::{Account, PlayerPermissions};
+use gserver_config::ServerConfig as GameConfig;
+use gserver_core::PlayerID;
+use std::sync::Arc;
+use tokio::net::{TcpListener, TcpStream};
+
+/// A connection (player 1) and the server it belongs to
+pub(super) struct TestConnection {
+    /// Server directory (removed when the fixture is dropped)
+    pub dir: tempfile::TempDir,
+    /// Server context on `dir`
+    pub context: Arc<ServerContext>,
+    /// The server end of the connection
+    pub conn: PlayerConnection,
+    /// Client end of the socket, kept open for the test
+    _client: TcpStream,
+}
+
+/// Open a connection to a server using `config`
+pub(super) async fn test_connection(config: GameConfig) -> TestConnection {
+    let dir = tempfile::tempdir().unwrap();
+    let context = Arc::new(ServerContext::new(dir.path(), config));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+    let (socket, peer_addr) = listener.accept().await.unwrap();
+    let conn = PlayerConnection::new(PlayerID(1), socket, peer_addr, context.clone());
+    TestConnection { dir, context, conn, _client: client }
+}
+
+impl TestConnection {
+    /// Log the connection in as a player with an account
+    pub fn login(&self, account: Account) {
+        *self.conn.account.lock() = Some(account);
+        *self.conn.state.lock() = ConnectionState::Authenticated;
+    }
+
+    /// Log the connection in as an RC whose account has `rights`
+    pub fn login_rc(&self, name: &str, rights: PlayerPermissions) {
+        *self.conn.is_rc.lock() = true;
+        self.login(Account { name: name.into(), local_rights: rights, ..Default::default() });
+    }
+
+    /// Give the logged in account more rights
+    pub fn grant(&self, rights: PlayerPermissions) {
+        if let Some(account) = self.conn.account.lock().as_mut() {
+            account.local_rights |= rights;
+        }
+    }
+
+    /// Write an account file
+    pub fn save_account(&self, name: &str) {
+        gserver_accounts::AccountLoader::new(self.dir.path())
+            .save(&Account { name: name.into(), ..Default::default() })
+            .unwrap();
+    }
+}
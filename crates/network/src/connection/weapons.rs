@@ -0,0 +1,83 @@
+//! # Weapon Delivery
+//!
+//! This module sends the connection's weapon scripts once per checksum, in
+//! bundles at login, and only those the client lacks from its last session
+//! (see [`crate::weapondelivery`]).
+
+use super::PlayerConnection;
+use gserver_core::Result;
+use gserver_game::WeaponBuild;
+use gserver_protocol::{PacketOut, PacketTypeOut};
+use std::sync::Arc;
+
+impl PlayerConnection {
+    /// Send the account's weapons the client lacks, in the shared bundles of their builds
+    ///
+    /// Picks up the checksums of the account's last session. Without any the
+    /// client's weapons are cleared (PLO_CLEARWEAPONS) and all are sent;
+    /// otherwise weapons the account no longer has are deleted one by one.
+    ///
+    /// # Returns
+    /// The number of weapons sent
+    pub(super) async fn send_account_weapons(&self) -> Result<usize> {
+        let (account, names) = match self.account.lock().as_ref() {
+            Some(account) => (account.name.clone(), account.weapons.clone()),
+            None => return Ok(0),
+        };
+        let kept = self.context.weapon_deliveries().take(&account);
+        if kept.is_empty() {
+            self.send_packet(PacketOut::new(PacketTypeOut::ClearWeapons, vec![])).await?;
+        } else {
+            let owned: Vec<String> = names.iter().map(|name| name.to_lowercase()).collect();
+            for name in kept.names().into_iter().filter(|name| !owned.contains(name)) {
+                self.send_packet(PacketOut::new(PacketTypeOut::NpcWeaponDel, name.into_bytes())).await?;
+            }
+        }
+
+        let (builds, unchanged): (Vec<Arc<WeaponBuild>>, Vec<_>) = names.iter()
+            .filter_map(|name| self.context.weapons().build(name, self.context.classes()))
+            .partition(|build| kept.needs(build));
+        {
+            let mut delivery = self.weapon_delivery.lock();
+            *delivery = Default::default();
+            for build in builds.iter().chain(&unchanged) {
+                delivery.sent(build);
+            }
+        }
+        if builds.is_empty() {
+            return Ok(0);
+        }
+
+        let bundles = self.context.weapon_bundles().get(&builds);
+        for bundle in bundles.iter() {
+            self.send_serialized(PacketTypeOut::NpcWeaponAdd, bundle.clone()).await?;
+        }
+        tracing::debug!("Connection {} sent {} weapons in {} bundles", self.player_id.get(), builds.len(), bundles.len());
+        Ok(builds.len())
+    }
+
+    /// Send a weapon build unless the client already has it
+    ///
+    /// A client with an older build of the weapon gets PLO_NPCWEAPONDEL first,
+    /// since it keeps the old script otherwise.
+    ///
+    /// # Returns
+    /// `true` if the weapon was sent
+    pub async fn deliver_weapon(&self, build: &WeaponBuild) -> Result<bool> {
+        let replaced = {
+            let mut delivery = self.weapon_delivery.lock();
+            if !delivery.needs(build) {
+                return Ok(false);
+            }
+            let replaced = delivery.contains(&build.name);
+            delivery.sent(build);
+            replaced
+        };
+        if replaced {
+            self.send_packet(PacketOut::new(PacketTypeOut::NpcWeaponDel, build.name.as_bytes().to_vec())).await?;
+        }
+        let data = crate::weaponsync::npc_weapon_add_data(build);
+        self.send_packet(PacketOut::new(PacketTypeOut::NpcWeaponAdd, data)).await?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,481 @@
+//! # Operator Console
+//!
+//! An interactive console on the running server for debugging live content.
+//! Lines are GS2 snippets run against a chosen scope, or `:` commands that
+//! inspect the server and call admin functions. The console reads stdin when
+//! the server runs with `--console`, and accepts sessions on the unix socket
+//! `consolesocket` (e.g. `socat - UNIX-CONNECT:servers/default/console.sock`);
+//! the socket is only accessible to the server's user. Snippets run on the
+//! blocking thread pool, and GS2 within `gs2instructionlimit`.
+//!
+//! # Scopes
+//! `:use server|level <name>|player <account>` picks what snippets see. The
+//! global `server` (name, players, uptime, worldtime) is always set; a level
+//! scope adds `level` (name, players, npcs), and a player scope adds `player`
+//! (id, account, nick, level, x, y, hearts, rupees, darts, bombs, ap) and the
+//! player's `level`. They are read again before every snippet.
+//!
+//! # Input
+//! - `<gs2>` - Run GS2; a line without a trailing `;` or `}` is an expression
+//!   whose value is printed. Variables assigned are kept for the session
+//! - `:gs1 <gs1>` - Run GS1 as a `created` event in the scope and print the
+//!   variables it set
+//! - `:vars` - Show the session variables and the scope
+//! - `:players` - List the players online
+//! - `:stats`, `:scriptstats [name]`, `:npcserver` - Like the RC commands
+//! - `:announce <text>` - Admin message to every player
+//! - `:mute`, `:unmute`, `:jail`, `:unjail`, `:warphome`, `:freeze`, `:unfreeze`
+//!   `<account> [minutes]` - Sanction an account like scripts do
+//! - `:help`, `:quit`
+
+use crate::context::ServerContext;
+use gserver_game::Player;
+use gserver_scripting::gs2::bytecode::{Class, Instance, Value};
+use gserver_scripting::{GS1Interpreter, GS1Script, GS2Compiler, GS2Parser};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::watch;
+
+/// Name console snippets run under (in `/scriptstats`)
+pub const CONSOLE_SCRIPT: &str = "-console";
+
+/// Globals set from the scope, not kept as session variables
+const SCOPE_GLOBALS: [&str; 3] = ["server", "level", "player"];
+
+const HELP: &str = "\
+<gs2>                      Run GS2 (an expression without ';' prints its value)
+:gs1 <gs1>                 Run GS1 as a created event
+:use server|level <name>|player <account>
+                           Pick what snippets see as server, level and player
+:vars                      Show the session variables and the scope
+:players                   List the players online
+:stats                     Show server statistics
+:scriptstats [name]        Show script run time and memory
+:npcserver                 Show NPC-Server status
+:announce <text>           Send an admin message to every player
+:mute|unmute|jail|unjail|warphome|freeze|unfreeze <account> [minutes]
+                           Sanction an account
+:quit                      End the session";
+
+/// What console snippets run against
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Scope {
+    /// Only `server`
+    #[default]
+    Server,
+    /// A loaded level
+    Level(String),
+    /// An online account
+    Player(String),
+}
+
+impl std::fmt::Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Scope::Server => f.write_str("server"),
+            Scope::Level(name) => write!(f, "level {}", name),
+            Scope::Player(account) => write!(f, "player {}", account),
+        }
+    }
+}
+
+/// One operator's console: the scope and the variables kept between lines
+pub struct ConsoleSession {
+    context: Arc<ServerContext>,
+    scope: Scope,
+    variables: HashMap<String, Value>,
+}
+
+impl ConsoleSession {
+    /// Start a session in the server scope
+    pub fn new(context: Arc<ServerContext>) -> Self {
+        Self { context, scope: Scope::Server, variables: HashMap::new() }
+    }
+
+    /// Get the current scope
+    pub fn scope(&self) -> &Scope {
+        &self.scope
+    }
+
+    /// Get the prompt, showing the scope
+    pub fn prompt(&self) -> String {
+        format!("{}> ", self.scope)
+    }
+
+    /// Run one line of input
+    ///
+    /// # Returns
+    /// The output (maybe empty), or `None` once the session should end
+    pub fn eval(&mut self, line: &str) -> Option<String> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Some(String::new());
+        }
+        let Some(command) = line.strip_prefix(':') else {
+            return Some(self.eval_gs2(line));
+        };
+        let (name, args) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+        let args = args.trim();
+        Some(match name {
+            "quit" | "exit" => return None,
+            "help" => HELP.to_string(),
+            "use" => self.use_scope(args),
+            "gs1" => self.eval_gs1(args),
+            "vars" => self.describe_vars(),
+            "players" => self.describe_players(),
+            "stats" => self.context.stats().summary(),
+            "scriptstats" => crate::levelstats::describe_scripts(&self.context.scripts().stats(), Some(args).filter(|a| !a.is_empty())),
+            "npcserver" => crate::npcstatus::NpcServerStatus::gather(&self.context).lines().join("\n"),
+            "announce" if args.is_empty() => "Usage: :announce <text>".to_string(),
+            "announce" => {
+                self.context.broadcast_admin(args);
+                format!("Announced: {}", args)
+            }
+            "mute" | "unmute" | "jail" | "unjail" | "warphome" | "freeze" | "unfreeze" => self.moderate(name, args),
+            _ => format!("Unknown command :{} (:help lists them)", name),
+        })
+    }
+
+    /// Compile and run a GS2 snippet with the scope and session variables
+    fn eval_gs2(&mut self, line: &str) -> String {
+        let expression = !line.ends_with(';') && !line.ends_with('}');
+        let source = if expression { format!("return {};", line) } else { line.to_string() };
+        let chunk = match GS2Parser::new(&source).parse().and_then(|script| GS2Compiler::new().compile(&script)) {
+            Ok(chunk) => chunk,
+            Err(e) => return format!("Error: {}", e),
+        };
+        let mut globals = match self.scope_globals() {
+            Ok(globals) => globals,
+            Err(e) => return e,
+        };
+        globals.extend(self.variables.iter().map(|(name, value)| (name.clone(), value.clone())));
+
+        match self.context.scripts().eval_gs2(CONSOLE_SCRIPT, chunk, globals) {
+            Ok((value, mut globals)) => {
+                globals.retain(|name, _| !SCOPE_GLOBALS.contains(&name.as_str()));
+                self.variables = globals;
+                match value {
+                    Value::Null if !expression => String::new(),
+                    value => value.to_string(),
+                }
+            }
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
+    /// Run a GS1 snippet as a `created` event for the scope's player and level
+    fn eval_gs1(&self, source: &str) -> String {
+        if source.is_empty() {
+            return "Usage: :gs1 <gs1>".to_string();
+        }
+        let script = match GS1Script::parse(CONSOLE_SCRIPT.to_string(), source) {
+            Ok(script) => script,
+            Err(e) => return format!("Error: {}", e),
+        };
+        let mut context = self.context.scripts().context().clone();
+        match &self.scope {
+            Scope::Server => {}
+            Scope::Level(name) => context.set_level(name.clone()),
+            Scope::Player(account) => match self.player(account) {
+                Ok(player) => {
+                    context.set_player(player.id);
+                    context.set_level(player.properties.lock().cur_level.clone());
+                }
+                Err(e) => return e,
+            },
+        }
+
+        let mut interpreter = GS1Interpreter::new(context);
+        if let Err(e) = interpreter.execute(&script, "created") {
+            return format!("Error: {}", e);
+        }
+        let mut variables: Vec<String> = interpreter.variables().iter()
+            .map(|(name, value)| format!("{} = {}", name, value))
+            .collect();
+        variables.sort();
+        match variables.is_empty() {
+            true => "Ok".to_string(),
+            false => variables.join("\n"),
+        }
+    }
+
+    /// `:use server|level <name>|player <account>`
+    fn use_scope(&mut self, args: &str) -> String {
+        let (kind, name) = args.split_once(char::is_whitespace).map_or((args, ""), |(k, n)| (k, n.trim()));
+        let scope = match (kind, name) {
+            ("server", _) => Scope::Server,
+            ("level", name) if !name.is_empty() => match self.context.levels().loaded_level(name) {
+                Some(level) => Scope::Level(level.name.clone()),
+                None => return format!("Level {} isn't loaded", name),
+            },
+            ("player", account) if !account.is_empty() => match self.player(account) {
+                Ok(player) => Scope::Player(player.properties.lock().account_name.clone()),
+                Err(e) => return e,
+            },
+            _ => return "Usage: :use server|level <name>|player <account>".to_string(),
+        };
+        self.scope = scope;
+        format!("Using {}", self.scope)
+    }
+
+    /// `:vars`
+    fn describe_vars(&self) -> String {
+        let mut lines = Vec::new();
+        match self.scope_globals() {
+            Ok(globals) => {
+                for name in SCOPE_GLOBALS {
+                    if let Some(Value::Instance(instance)) = globals.get(name) {
+                        let mut fields: Vec<String> = instance.fields.iter()
+                            .map(|(field, value)| format!("{}={}", field, value))
+                            .collect();
+                        fields.sort();
+                        lines.push(format!("{}: {}", name, fields.join(", ")));
+                    }
+                }
+            }
+            Err(e) => lines.push(e),
+        }
+        let mut variables: Vec<String> = self.variables.iter()
+            .map(|(name, value)| format!("{} = {}", name, value))
+            .collect();
+        variables.sort();
+        lines.extend(variables);
+        lines.join("\n")
+    }
+
+    /// `:players`
+    fn describe_players(&self) -> String {
+        let snapshot = self.context.players().snapshot();
+        let mut lines: Vec<String> = snapshot.iter()
+            .map(|player| {
+                let props = player.properties.lock();
+                format!("{} {} ({}) on {}", player.id.get(), props.account_name, props.nickname, props.cur_level)
+            })
+            .collect();
+        lines.sort();
+        match lines.is_empty() {
+            true => "No players online".to_string(),
+            false => lines.join("\n"),
+        }
+    }
+
+    /// `:mute <account> [minutes]` and the other sanctions
+    fn moderate(&self, action: &str, args: &str) -> String {
+        let mut args = args.split_whitespace();
+        let Some(account) = args.next() else {
+            return format!("Usage: :{} <account> [minutes]", action);
+        };
+        let minutes = args.next().and_then(|m| m.parse().ok());
+        let Some(moderation) = self.context.scripts().context().moderation() else {
+            return "Moderation isn't available".to_string();
+        };
+        match moderation.moderate(account, action, minutes) {
+            Ok(()) => format!("{}: {}", action, account),
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
+    /// Find an online player by account
+    fn player(&self, account: &str) -> Result<Arc<Player>, String> {
+        self.context.players().get_by_account(account).into_iter().next()
+            .ok_or_else(|| format!("{} isn't online", account))
+    }
+
+    /// Build the `server`, `level` and `player` globals of the scope
+    fn scope_globals(&self) -> Result<HashMap<String, Value>, String> {
+        let mut globals = HashMap::new();
+        let config = self.context.config().read();
+        globals.insert("server".to_string(), object("server", [
+            ("name", Value::String(config.name.clone())),
+            ("players", Value::Number(self.context.online_count() as f64)),
+            ("uptime", Value::Number(self.context.uptime().as_secs() as f64)),
+            ("worldtime", Value::Number(self.context.world_time() as f64)),
+        ]));
+        drop(config);
+
+        let level = match &self.scope {
+            Scope::Server => None,
+            Scope::Level(name) => Some(name.clone()),
+            Scope::Player(account) => {
+                let player = self.player(account)?;
+                let props = player.properties.lock();
+                globals.insert("player".to_string(), object("player", [
+                    ("id", Value::Number(player.id.get() as f64)),
+                    ("account", Value::String(props.account_name.clone())),
+                    ("nick", Value::String(props.nickname.clone())),
+                    ("level", Value::String(props.cur_level.clone())),
+                    ("x", Value::Number(props.x2 as f64 / 16.0)),
+                    ("y", Value::Number(props.y2 as f64 / 16.0)),
+                    ("hearts", Value::Number(props.cur_power as f64 / 2.0)),
+                    ("rupees", Value::Number(props.rupees_count as f64)),
+                    ("darts", Value::Number(props.arrows_count as f64)),
+                    ("bombs", Value::Number(props.bombs_count as f64)),
+                    ("ap", Value::Number(props.alignment as f64)),
+                ]));
+                Some(props.cur_level.clone())
+            }
+        };
+        if let Some(name) = level {
+            let npcs = self.context.levels().loaded_level(&name).map_or(0, |level| level.npcs.read().len());
+            globals.insert("level".to_string(), object("level", [
+                ("name", Value::String(name.clone())),
+                ("players", Value::Number(self.context.players().players_on_level(&name).len() as f64)),
+                ("npcs", Value::Number(npcs as f64)),
+            ]));
+        }
+        Ok(globals)
+    }
+}
+
+impl std::fmt::Debug for ConsoleSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConsoleSession").field("scope", &self.scope).field("variables", &self.variables).finish()
+    }
+}
+
+/// Build a read-only GS2 object
+fn object<const N: usize>(class: &str, fields: [(&str, Value); N]) -> Value {
+    Value::Instance(Instance {
+        class: Class { name: class.to_string(), superclass: None, methods: Vec::new() },
+        fields: fields.into_iter().map(|(name, value)| (name.to_string(), value)).collect(),
+    })
+}
+
+/// Run a session over a reader and writer until it quits or the input ends
+pub async fn serve<R, W>(context: Arc<ServerContext>, reader: R, mut writer: W) -> std::io::Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut session = ConsoleSession::new(context);
+    let mut lines = reader.lines();
+    writer.write_all(session.prompt().as_bytes()).await?;
+    writer.flush().await?;
+    while let Some(line) = lines.next_line().await? {
+        let (returned, output) = tokio::task::spawn_blocking(move || {
+            let output = session.eval(&line);
+            (session, output)
+        }).await.map_err(std::io::Error::other)?;
+        session = returned;
+        let Some(output) = output else { break };
+        if !output.is_empty() {
+            writer.write_all(format!("{}\n", output).as_bytes()).await?;
+        }
+        writer.write_all(session.prompt().as_bytes()).await?;
+        writer.flush().await?;
+    }
+    Ok(())
+}
+
+/// Run a session on stdin and stdout until it quits or shutdown
+pub async fn run_stdin(context: Arc<ServerContext>, mut shutdown: watch::Receiver<bool>) {
+    tokio::select! {
+        result = serve(context, BufReader::new(tokio::io::stdin()), tokio::io::stdout()) => {
+            if let Err(e) = result {
+                tracing::warn!("Console: {}", e);
+            }
+        }
+        _ = shutdown.changed() => {}
+    }
+}
+
+/// Accept sessions on a unix socket until shutdown
+///
+/// A stale socket file is replaced, and the socket is removed on shutdown.
+#[cfg(unix)]
+pub async fn run_socket(context: Arc<ServerContext>, path: PathBuf, mut shutdown: watch::Receiver<bool>) -> std::io::Result<()> {
+    let _ = std::fs::remove_file(&path);
+    let listener = bind_private(&path)?;
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    tracing::info!("Console session opened on {}", path.display());
+                    let (reader, writer) = stream.into_split();
+                    let context = context.clone();
+                    crate::tasks::spawn_named("console session", async move {
+                        if let Err(e) = serve(context, BufReader::new(reader), writer).await {
+                            tracing::warn!("Console session: {}", e);
+                        }
+                    });
+                }
+                Err(e) => tracing::warn!("Console socket: {}", e),
+            },
+            _ = shutdown.changed() => break,
+        }
+    }
+    let _ = std::fs::remove_file(&path);
+    Ok(())
+}
+
+/// Bind a unix socket only the server's user can connect to
+///
+/// The socket is bound in a new 0700 directory and moved into place once it's
+/// 0600, so it's never reachable with the permissions of the umask.
+#[cfg(unix)]
+fn bind_private(path: &std::path::Path) -> std::io::Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(std::path::Path::new("."));
+    let staging = parent.join(format!(".console-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&staging);
+    std::fs::DirBuilder::new().mode(0o700).create(&staging)?;
+    let staged = staging.join("console.sock");
+    let bound = tokio::net::UnixListener::bind(&staged).and_then(|listener| {
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))?;
+        std::fs::rename(&staged, path)?;
+        Ok(listener)
+    });
+    let _ = std::fs::remove_dir_all(&staging);
+    bound
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eval_gs2_with_session_variables() {
+        let context = Arc::new(ServerContext::new("servers/test", Default::default()));
+        let mut session = ConsoleSession::new(context);
+
+        assert_eq!(session.eval("1 + 2").as_deref(), Some("3"));
+        assert_eq!(session.eval("hits = 5;").as_deref(), Some(""));
+        assert_eq!(session.eval("hits * 2").as_deref(), Some("10"));
+        assert_eq!(session.eval("server.players").as_deref(), Some("0"));
+        assert!(session.eval(":vars").unwrap().ends_with("\nhits = 5"));
+        assert!(session.eval("1 +").unwrap().starts_with("Error: "));
+        assert_eq!(session.eval(":quit"), None);
+    }
+
+    #[test]
+    fn test_scopes_and_commands() {
+        let context = Arc::new(ServerContext::new("servers/test", Default::default()));
+        let mut session = ConsoleSession::new(context);
+
+        assert_eq!(session.eval(":use player Nobody").as_deref(), Some("Nobody isn't online"));
+        assert_eq!(session.eval(":use level nowhere.nw").as_deref(), Some("Level nowhere.nw isn't loaded"));
+        assert_eq!(session.scope(), &Scope::Server);
+        assert_eq!(session.prompt(), "server> ");
+        assert_eq!(session.eval(":gs1 color = red").as_deref(), Some("color = red"));
+        assert_eq!(session.eval(":players").as_deref(), Some("No players online"));
+        assert!(session.eval(":bogus").unwrap().starts_with("Unknown command :bogus"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_private_socket() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("console.sock");
+        let listener = bind_private(&path).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let _client = tokio::net::UnixStream::connect(&path).await.unwrap();
+        listener.accept().await.unwrap();
+    }
+}
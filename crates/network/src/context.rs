@@ -0,0 +1,817 @@
+//! # Shared Server Context
+//!
+//! This module provides the state shared by every connection and packet handler.
+//!
+//! # Purpose
+//!
+//! Packet handlers need more than their own connection: they look up other players,
+//! load levels, send weapons and run scripts. Rather than giving each connection a
+//! path and letting it reload everything from disk, the server builds one
+//! `ServerContext` at startup and hands an `Arc` of it to every connection.
+//!
+//! # C++ Equivalence
+//!
+//! Matches the `Server* m_server` back-pointer held by `PlayerClient` in C++,
+//! which handlers use for `getPlayerList()`, `getLevel()`, `getWeapon()`, etc.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use gserver_network::ServerContext;
+//! use std::sync::Arc;
+//!
+//! let context = Arc::new(ServerContext::new("servers/default", Default::default()));
+//! let weapons = context.weapons().load_all();
+//! ```
+
+use crate::ambience::AmbienceService;
+use crate::announce::{Announcements, SERVER_SENDER};
+use crate::compression::Compressor;
+use crate::control::PlayerControl;
+use crate::files::FileIndex;
+use crate::geoip::GeoIp;
+use crate::integrity::IntegrityPolicies;
+use crate::irc::IrcBridge;
+use crate::keepalive::LatencyTable;
+use crate::listserver::{ListedPlayer, Listing, ListServerStatus};
+use crate::moderation::Moderation;
+use crate::npcmovement::NpcMovements;
+use crate::npcsaves::NpcSaves;
+use crate::rcchat::RcChatHistory;
+use crate::server::ServerStats;
+use crate::session::SessionStore;
+use crate::timings::HandlerTimings;
+use crate::traffic::TrafficStats;
+use crate::levelstats::LevelStats;
+use crate::bandwidth::Shaper;
+use crate::verification::VerificationCache;
+use crate::chatcommands::ChatCommandRegistry;
+use crate::clienttext::ClientTextRegistry;
+use crate::alts::IpHistory;
+use crate::chatlog::ChatLog;
+use crate::economy::EconomyLedger;
+use crate::savequeue::AccountSaveQueue;
+use crate::interest::PlayerInterest;
+use crate::scriptlog::ScriptErrorLog;
+use crate::scripthistory::ScriptHistory;
+use crate::playerstats::PlayerStats;
+use crate::weapondelivery::{BundleCache, DeliveryStore};
+use crate::watchdog::Watchdog;
+#[cfg(feature = "plugins")]
+use crate::plugins::{Plugin, Plugins};
+use crate::bans::BanManager;
+use crate::loginpolicy::{BannedHardwarePolicy, LoginApprovals, LoginPolicies};
+use gserver_config::ServerConfig as GameConfig;
+use gserver_core::PlayerID;
+use gserver_game::{CarryTracker, ClassManager, CommandQueue, EventBus, Groups, PlayerManager, TickStats, WeaponManager};
+use gserver_levels::LevelManager;
+use gserver_scripting::ScriptHost;
+use gserver_storage::{BackupConfig, BackupManager};
+use parking_lot::{Mutex, RwLock};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Snapshot function of the running server's connections
+pub type StatsSource = Box<dyn Fn(&ServerContext) -> ServerStats + Send + Sync>;
+
+/// State shared between all connections
+///
+/// # Thread Safety
+///
+/// Every member is internally synchronized, so the context is shared as a
+/// plain `Arc<ServerContext>` without an outer lock.
+pub struct ServerContext {
+    /// Server directory (contains accounts/, world/, weapons/, etc.)
+    server_dir: PathBuf,
+
+    /// Game configuration (serveroptions.txt and friends)
+    ///
+    /// Behind a lock so it can be reloaded while the server is running.
+    config: Arc<RwLock<GameConfig>>,
+
+    /// All players in the game
+    players: PlayerManager,
+
+    /// Level cache
+    levels: LevelManager,
+
+    /// Server weapons
+    weapons: WeaponManager,
+
+    /// Login bundles of weapon sets
+    weapon_bundles: BundleCache,
+
+    /// Weapon checksums of accounts' last sessions
+    weapon_deliveries: DeliveryStore,
+
+    /// Script classes joined by weapons
+    classes: ClassManager,
+
+    /// Script host (compiled scripts and global script state)
+    scripts: ScriptHost,
+
+    /// Event bus (player joins/leaves, chat, level and flag changes)
+    events: EventBus,
+
+    /// Requests for the server's command relay (sanctions, record edits, disconnects)
+    commands: CommandQueue,
+
+    /// Graal world time, advanced by the tick loop
+    world_time: AtomicU32,
+
+    /// Tick loop timing statistics
+    tick_stats: Arc<Mutex<TickStats>>,
+
+    /// Server folder snapshots (backups/)
+    backups: Arc<BackupManager>,
+
+    /// Operator integrity policies (consulted on PLI_TAMPERCHECK)
+    integrity: IntegrityPolicies,
+
+    /// Bundle compressor (levels and encoder pool from startup config)
+    compressor: Compressor,
+
+    /// Downloadable files in world/ (from foldersconfig.txt)
+    files: FileIndex,
+
+    /// Round-trip times from keepalives (RC /ping, server stats)
+    latency: LatencyTable,
+
+    /// Country database and player countries (login filter, RC, stats)
+    geoip: GeoIp,
+
+    /// Packet and bundle counters of all connections
+    traffic: TrafficStats,
+
+    /// Broadcast counters of each level
+    level_stats: LevelStats,
+
+    /// Server-wide gameplay and file bandwidth buckets
+    bandwidth: Shaper,
+
+    /// Packet handler time histograms
+    handler_timings: HandlerTimings,
+
+    /// Snapshot of the running server's connections, installed by the server
+    stats_source: RwLock<Option<StatsSource>>,
+
+    /// Recent staff chat lines, sent to RCs when they log in
+    rc_chat: RcChatHistory,
+
+    /// Listserver IRC channels (players and scripts)
+    irc: Arc<IrcBridge>,
+
+    /// Weather and tint settings (RC, scripts, schedule)
+    ambience: Arc<AmbienceService>,
+
+    /// Push-away and other movement control (scripts, blocking NPCs)
+    control: Arc<PlayerControl>,
+
+    /// Saved NPC state (npcs/), also backing `this.*` in NPC scripts
+    npc_saves: Arc<NpcSaves>,
+
+    /// Scripted NPC movement (`move`, `setdir`, `shoot`)
+    npc_movements: Arc<NpcMovements>,
+
+    /// Admin message popups, now and scheduled
+    announcements: Arc<Announcements>,
+
+    /// Sessions of dropped players waiting for a reconnect
+    sessions: SessionStore,
+
+    /// Player groups and their level instances
+    groups: Mutex<Groups>,
+
+    /// NPCs being carried by players
+    carry: Mutex<CarryTracker>,
+
+    /// When the server started
+    started: Instant,
+
+    /// Connected players, kept up to date by the server
+    online: AtomicUsize,
+
+    /// The accept loop is running
+    accepting: AtomicBool,
+
+    /// Connection state of each listserver, `listip` first
+    listservers: Vec<Arc<ListServerStatus>>,
+
+    /// Accounts the listserver verified, for logins during outages
+    verifications: VerificationCache,
+
+    /// Player chat commands
+    chat_commands: ChatCommandRegistry,
+
+    /// PLI_REQUESTTEXT / PLI_SENDTEXT commands
+    client_texts: ClientTextRegistry,
+
+    /// Accounts and IPs of past logins
+    ip_history: IpHistory,
+
+    /// Chat log files
+    chat_log: ChatLog,
+
+    /// Per-account statistics and leaderboards
+    player_stats: PlayerStats,
+
+    /// Gralat change ledger
+    economy: EconomyLedger,
+
+    /// Accounts of disconnected players waiting to be written
+    account_saves: AccountSaveQueue,
+
+    /// Which players get each other's props
+    interest: PlayerInterest,
+
+    /// Previous versions of edited weapons and classes
+    script_history: ScriptHistory,
+
+    /// Operator login policies (consulted after the credentials check out)
+    login_policies: LoginPolicies,
+
+    /// Logins held for staff approval
+    login_approvals: LoginApprovals,
+
+    /// Bans on PC and hard disk ids
+    bans: BanManager,
+
+    /// Heartbeats of the long-running loops
+    watchdog: Watchdog,
+
+    /// Installed plugins
+    #[cfg(feature = "plugins")]
+    plugins: Plugins,
+}
+
+impl ServerContext {
+    /// Create a new server context
+    ///
+    /// # Arguments
+    /// * `server_dir` - Server directory path
+    /// * `config` - Loaded game configuration
+    ///
+    /// # Notes
+    /// Levels are loaded lazily from `world/`, but its downloadable files are
+    /// indexed right away. Weapons, classes and NPC saves are not loaded
+    /// until `weapons().load_all()`, `classes().load_all()` and
+    /// `npc_saves().load_all()` are called.
+    pub fn new<P: Into<PathBuf>>(server_dir: P, config: GameConfig) -> Self {
+        let server_dir = server_dir.into();
+        let backup_config = BackupConfig::new(&server_dir).with_retention(config.backup_retention);
+        let events = EventBus::new();
+        let commands = CommandQueue::new();
+        let irc = Arc::new(IrcBridge::new(events.clone(), config.name.clone()));
+        let scripts = ScriptHost::new();
+        scripts.set_memory_limit(config.script_memory_limit * 1024);
+        scripts.set_gs2_instruction_limit(config.gs2_instruction_limit);
+        #[cfg(feature = "lua")]
+        scripts.set_instruction_limit(config.lua_instruction_limit);
+        scripts.context().set_irc_handler(irc.clone());
+        let ambience = Arc::new(AmbienceService::new(events.clone(), server_dir.join("world")));
+        scripts.context().set_ambience_handler(ambience.clone());
+        let control = Arc::new(PlayerControl::new(events.clone()));
+        scripts.context().set_control_handler(control.clone());
+        let npc_saves = Arc::new(NpcSaves::new(server_dir.join("npcs")));
+        scripts.context().set_npc_state_handler(npc_saves.clone());
+        let npc_movements = Arc::new(NpcMovements::new(events.clone(), &server_dir));
+        scripts.context().set_npc_control_handler(npc_movements.clone());
+        let announcements = Arc::new(Announcements::new(events.clone()));
+        scripts.context().set_admin_message_handler(announcements.clone());
+        scripts.context().set_moderation_handler(Arc::new(Moderation::new(commands.clone())));
+        let script_log = ScriptErrorLog::new(server_dir.join(crate::scriptlog::SCRIPT_LOG_FILE), events.clone());
+        scripts.context().set_script_error_handler(Arc::new(script_log));
+        let login_policies = LoginPolicies::new();
+        login_policies.register(Arc::new(BannedHardwarePolicy));
+
+        Self {
+            backups: Arc::new(BackupManager::new(server_dir.clone(), backup_config)),
+            levels: LevelManager::new(server_dir.join("world")),
+            weapons: WeaponManager::new(server_dir.join("weapons")),
+            weapon_bundles: BundleCache::new(),
+            weapon_deliveries: DeliveryStore::new(),
+            classes: ClassManager::new(server_dir.join("scripts")),
+            players: PlayerManager::new(),
+            scripts,
+            events,
+            commands,
+            world_time: AtomicU32::new(gserver_game::tick::world_time()),
+            tick_stats: Arc::new(Mutex::new(TickStats::default())),
+            integrity: IntegrityPolicies::new(),
+            compressor: Compressor::new(config.compression),
+            files: FileIndex::new(server_dir.join("world"), &config.folder_config),
+            latency: LatencyTable::new(),
+            geoip: GeoIp::from_config(&server_dir, &config),
+            traffic: TrafficStats::new(),
+            level_stats: LevelStats::new(),
+            bandwidth: Shaper::new(),
+            handler_timings: HandlerTimings::new(),
+            stats_source: RwLock::new(None),
+            rc_chat: RcChatHistory::default(),
+            irc,
+            ambience,
+            control,
+            npc_saves,
+            npc_movements,
+            announcements,
+            sessions: SessionStore::new(),
+            groups: Mutex::new(Groups::new()),
+            carry: Mutex::new(CarryTracker::new()),
+            started: Instant::now(),
+            online: AtomicUsize::new(0),
+            accepting: AtomicBool::new(false),
+            listservers: config.listserver_addresses().into_iter()
+                .map(|(host, port)| Arc::new(ListServerStatus::new(host, port)))
+                .collect(),
+            verifications: VerificationCache::load(&server_dir.join(crate::verification::CACHE_FILE)),
+            chat_commands: ChatCommandRegistry::new(),
+            client_texts: ClientTextRegistry::new(),
+            ip_history: IpHistory::load(&server_dir.join(crate::alts::HISTORY_FILE)),
+            chat_log: ChatLog::new(server_dir.join(crate::chatlog::CHAT_LOG_DIR)),
+            player_stats: match config.stats_interval {
+                0 => PlayerStats::disabled(),
+                _ => PlayerStats::load(&server_dir.join(crate::playerstats::STATS_FILE)),
+            },
+            economy: EconomyLedger::new(server_dir.join(crate::economy::LEDGER_FILE)),
+            account_saves: AccountSaveQueue::new(server_dir.clone()),
+            interest: PlayerInterest::new(&server_dir.join("world")),
+            script_history: ScriptHistory::new(server_dir.join(crate::scripthistory::HISTORY_DIR)),
+            login_policies,
+            login_approvals: LoginApprovals::new(),
+            bans: BanManager::load(&server_dir.join(crate::bans::IDENTITY_BANS_FILE)),
+            watchdog: Watchdog::new(),
+            #[cfg(feature = "plugins")]
+            plugins: Plugins::new(),
+            config: Arc::new(RwLock::new(config)),
+            server_dir,
+        }
+    }
+
+    /// Get the server directory
+    #[inline]
+    pub fn server_dir(&self) -> &Path {
+        &self.server_dir
+    }
+
+    /// Get the shared configuration handle
+    #[inline]
+    pub fn config(&self) -> &Arc<RwLock<GameConfig>> {
+        &self.config
+    }
+
+    /// Get the player manager
+    #[inline]
+    pub fn players(&self) -> &PlayerManager {
+        &self.players
+    }
+
+    /// Get the level manager
+    #[inline]
+    pub fn levels(&self) -> &LevelManager {
+        &self.levels
+    }
+
+    /// Get the weapon manager
+    #[inline]
+    pub fn weapons(&self) -> &WeaponManager {
+        &self.weapons
+    }
+
+    /// Get the login bundles of weapon sets
+    #[inline]
+    pub fn weapon_bundles(&self) -> &BundleCache {
+        &self.weapon_bundles
+    }
+
+    /// Get the weapon checksums kept between sessions
+    #[inline]
+    pub fn weapon_deliveries(&self) -> &DeliveryStore {
+        &self.weapon_deliveries
+    }
+
+    /// Get the class manager
+    #[inline]
+    pub fn classes(&self) -> &ClassManager {
+        &self.classes
+    }
+
+    /// Get the script host
+    #[inline]
+    pub fn scripts(&self) -> &ScriptHost {
+        &self.scripts
+    }
+
+    /// Get the event bus
+    ///
+    /// Subsystems call `events().subscribe()` to be notified of game events.
+    #[inline]
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// Get the command queue
+    ///
+    /// The server's command relay takes its receiver (see [`crate::server`]).
+    #[inline]
+    pub fn commands(&self) -> &CommandQueue {
+        &self.commands
+    }
+
+    /// Get the current Graal world time (sent in PLO_NEWWORLDTIME)
+    #[inline]
+    pub fn world_time(&self) -> u32 {
+        self.world_time.load(Ordering::Relaxed)
+    }
+
+    /// Set the current Graal world time
+    #[inline]
+    pub fn set_world_time(&self, time: u32) {
+        self.world_time.store(time, Ordering::Relaxed);
+    }
+
+    /// Get the tick loop statistics handle
+    #[inline]
+    pub fn tick_stats(&self) -> &Arc<Mutex<TickStats>> {
+        &self.tick_stats
+    }
+
+    /// Get the backup manager
+    #[inline]
+    pub fn backups(&self) -> &Arc<BackupManager> {
+        &self.backups
+    }
+
+    /// Get the integrity policies
+    ///
+    /// Operators call `integrity().register()` to add custom policies.
+    #[inline]
+    pub fn integrity(&self) -> &IntegrityPolicies {
+        &self.integrity
+    }
+
+    /// Get the bundle compressor
+    #[inline]
+    pub fn compressor(&self) -> &Compressor {
+        &self.compressor
+    }
+
+    /// Get the downloadable file index
+    #[inline]
+    pub fn files(&self) -> &FileIndex {
+        &self.files
+    }
+
+    /// Get the players' round-trip times
+    #[inline]
+    pub fn latency(&self) -> &LatencyTable {
+        &self.latency
+    }
+
+    /// Get the GeoIP lookup
+    #[inline]
+    pub fn geoip(&self) -> &GeoIp {
+        &self.geoip
+    }
+
+    /// Get the packet and bundle counters
+    #[inline]
+    pub fn traffic(&self) -> &TrafficStats {
+        &self.traffic
+    }
+
+    /// Get the broadcast counters of each level
+    #[inline]
+    pub fn level_stats(&self) -> &LevelStats {
+        &self.level_stats
+    }
+
+    /// Get the server-wide bandwidth buckets
+    #[inline]
+    pub fn bandwidth(&self) -> &Shaper {
+        &self.bandwidth
+    }
+
+    /// Get the packet handler time histograms
+    #[inline]
+    pub fn handler_timings(&self) -> &HandlerTimings {
+        &self.handler_timings
+    }
+
+    /// Install what [`Self::stats`] snapshots (done by `GServer`)
+    pub fn set_stats_source(&self, source: StatsSource) {
+        *self.stats_source.write() = Some(source);
+    }
+
+    /// Take a server statistics snapshot (RC `/stats`, health service `/stats`)
+    ///
+    /// Without a running server there are no connections to count, only the
+    /// context's own counters.
+    pub fn stats(&self) -> ServerStats {
+        match &*self.stats_source.read() {
+            Some(source) => source(self),
+            None => crate::server::collect_stats(&dashmap::DashMap::new(), self),
+        }
+    }
+
+    /// Get the staff chat history
+    #[inline]
+    pub fn rc_chat(&self) -> &RcChatHistory {
+        &self.rc_chat
+    }
+
+    /// Get the listserver IRC bridge
+    #[inline]
+    pub fn irc(&self) -> &Arc<IrcBridge> {
+        &self.irc
+    }
+
+    /// Get the weather and tint control
+    #[inline]
+    pub fn ambience(&self) -> &Arc<AmbienceService> {
+        &self.ambience
+    }
+
+    /// Get the player movement control
+    #[inline]
+    pub fn control(&self) -> &Arc<PlayerControl> {
+        &self.control
+    }
+
+    /// Get the saved NPC state
+    #[inline]
+    pub fn npc_saves(&self) -> &NpcSaves {
+        &self.npc_saves
+    }
+
+    /// Get the scripted NPC movement
+    #[inline]
+    pub fn npc_movements(&self) -> &NpcMovements {
+        &self.npc_movements
+    }
+
+    /// Get the admin message service
+    #[inline]
+    pub fn announcements(&self) -> &Arc<Announcements> {
+        &self.announcements
+    }
+
+    /// Get the sessions held for reconnecting players
+    #[inline]
+    pub fn sessions(&self) -> &SessionStore {
+        &self.sessions
+    }
+
+    /// Show an admin message popup from the server to every player
+    pub fn broadcast_admin(&self, message: &str) {
+        self.announcements.broadcast(SERVER_SENDER, message);
+    }
+
+    /// Get the player groups and level instances
+    #[inline]
+    pub fn groups(&self) -> &Mutex<Groups> {
+        &self.groups
+    }
+
+    /// Get who carries which NPC
+    #[inline]
+    pub fn carry(&self) -> &Mutex<CarryTracker> {
+        &self.carry
+    }
+
+    /// Get the time since the server started
+    #[inline]
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Get the number of connected players
+    #[inline]
+    pub fn online_count(&self) -> usize {
+        self.online.load(Ordering::Relaxed)
+    }
+
+    /// Set the number of connected players
+    #[inline]
+    pub fn set_online_count(&self, count: usize) {
+        self.online.store(count, Ordering::Relaxed);
+    }
+
+    /// Check if the server is accepting connections
+    #[inline]
+    pub fn is_accepting(&self) -> bool {
+        self.accepting.load(Ordering::Relaxed)
+    }
+
+    /// Record whether the accept loop is running
+    #[inline]
+    pub fn set_accepting(&self, accepting: bool) {
+        self.accepting.store(accepting, Ordering::Relaxed);
+    }
+
+    /// Get the connection state of the first listserver, which verifies
+    /// logins and answers player requests
+    #[inline]
+    pub fn listserver(&self) -> &Arc<ListServerStatus> {
+        &self.listservers[0]
+    }
+
+    /// Get the connection state of every listserver, `listip` first
+    #[inline]
+    pub fn listservers(&self) -> &[Arc<ListServerStatus>] {
+        &self.listservers
+    }
+
+    /// Change the listing extras on every listserver
+    ///
+    /// # Returns
+    /// `true` if anything changed
+    pub fn set_listing(&self, listing: Listing) -> bool {
+        self.listservers.iter().fold(false, |changed, status| status.set_listing(listing.clone()) | changed)
+    }
+
+    /// List a player that logged in on every listserver
+    pub fn list_player(&self, id: PlayerID, player: ListedPlayer) {
+        for status in &self.listservers {
+            status.add_player(id, player.clone());
+        }
+    }
+
+    /// Unlist a player that logged out from every listserver
+    pub fn unlist_player(&self, id: PlayerID) {
+        for status in &self.listservers {
+            status.remove_player(id);
+        }
+    }
+
+    /// Get the accounts the listserver verified
+    #[inline]
+    pub fn verifications(&self) -> &VerificationCache {
+        &self.verifications
+    }
+
+    /// Get the accounts and IPs of past logins
+    #[inline]
+    pub fn ip_history(&self) -> &IpHistory {
+        &self.ip_history
+    }
+
+    /// Get the chat log files
+    #[inline]
+    pub fn chat_log(&self) -> &ChatLog {
+        &self.chat_log
+    }
+
+    /// Get the player statistics
+    #[inline]
+    pub fn player_stats(&self) -> &PlayerStats {
+        &self.player_stats
+    }
+
+    /// Get the gralat ledger
+    #[inline]
+    pub fn economy(&self) -> &EconomyLedger {
+        &self.economy
+    }
+
+    /// Get the queue of account saves
+    #[inline]
+    pub fn account_saves(&self) -> &AccountSaveQueue {
+        &self.account_saves
+    }
+
+    /// Get the player interest tracker
+    #[inline]
+    pub fn interest(&self) -> &PlayerInterest {
+        &self.interest
+    }
+
+    /// Get the weapon and class version history
+    #[inline]
+    pub fn script_history(&self) -> &ScriptHistory {
+        &self.script_history
+    }
+
+    /// Get the login policies
+    ///
+    /// Operators call `login_policies().register()` to add custom policies.
+    #[inline]
+    pub fn login_policies(&self) -> &LoginPolicies {
+        &self.login_policies
+    }
+
+    /// Get the logins held for staff approval
+    #[inline]
+    pub fn login_approvals(&self) -> &LoginApprovals {
+        &self.login_approvals
+    }
+
+    /// Get the bans on PC and hard disk ids
+    #[inline]
+    pub fn bans(&self) -> &BanManager {
+        &self.bans
+    }
+
+    /// Get the heartbeats of the long-running loops
+    #[inline]
+    pub fn watchdog(&self) -> &Watchdog {
+        &self.watchdog
+    }
+
+    /// Get the player chat commands
+    #[inline]
+    pub fn chat_commands(&self) -> &ChatCommandRegistry {
+        &self.chat_commands
+    }
+
+    /// Get the PLI_REQUESTTEXT / PLI_SENDTEXT commands
+    #[inline]
+    pub fn client_texts(&self) -> &ClientTextRegistry {
+        &self.client_texts
+    }
+
+    /// Get the installed plugins
+    #[cfg(feature = "plugins")]
+    #[inline]
+    pub fn plugins(&self) -> &Plugins {
+        &self.plugins
+    }
+
+    /// Install a plugin, registering its handlers
+    #[cfg(feature = "plugins")]
+    pub fn install_plugin(&self, plugin: &dyn Plugin) {
+        self.plugins.install(self, plugin);
+    }
+
+    /// Load the Lua scripts in `lua/` and run their `created` events
+    ///
+    /// `lua/<name>.lua` is loaded as script `<name>`, so `npc12.lua` gets the
+    /// events of level NPC 12 and `-System.lua` those of weapon `-System`.
+    ///
+    /// # Returns
+    /// The number of scripts loaded. A missing directory loads nothing.
+    #[cfg(feature = "lua")]
+    pub fn load_lua_scripts(&self) -> usize {
+        let Ok(entries) = std::fs::read_dir(self.server_dir.join("lua")) else { return 0 };
+        let mut loaded = 0;
+        for path in entries.flatten().map(|e| e.path()).filter(|p| p.extension().is_some_and(|e| e == "lua")) {
+            let Some(name) = path.file_stem().and_then(|n| n.to_str()) else { continue };
+            let source = match std::fs::read(&path) {
+                Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+                Err(e) => {
+                    tracing::warn!("Can't read {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            match self.scripts.load_lua(name, &source) {
+                Ok(script) if script.handles("created") => {
+                    if let Err(e) = self.scripts.trigger_event(name, "created") {
+                        tracing::warn!("Lua script {} failed in created: {}", name, e);
+                    }
+                    loaded += 1;
+                }
+                Ok(_) => loaded += 1,
+                Err(e) => tracing::warn!("Lua script {} not loaded: {}", name, e),
+            }
+        }
+        loaded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_paths() {
+        let context = ServerContext::new("servers/test", GameConfig::default());
+
+        assert_eq!(context.server_dir(), Path::new("servers/test"));
+        assert_eq!(context.levels().levels_dir(), &PathBuf::from("servers/test/world"));
+        assert_eq!(context.weapons().weapons_dir(), Path::new("servers/test/weapons"));
+        assert_eq!(context.classes().classes_dir(), Path::new("servers/test/scripts"));
+        assert_eq!(context.players().player_count(), 0);
+    }
+
+    #[test]
+    fn test_config_reload() {
+        let context = ServerContext::new("servers/test", GameConfig::default());
+        context.config().write().name = "Reloaded".to_string();
+
+        assert_eq!(context.config().read().name, "Reloaded");
+    }
+
+    #[test]
+    fn test_listing_on_every_listserver() {
+        let config = GameConfig { list_servers: vec!["other.example.net:14901".into()], ..GameConfig::default() };
+        let context = ServerContext::new("servers/test", config);
+        assert_eq!(context.listservers().len(), 2);
+        assert_eq!(context.listservers()[1].address(), ("other.example.net", 14901));
+
+        let listing = Listing { tags: vec!["pvp".into()], ..Default::default() };
+        assert!(context.set_listing(listing.clone()));
+        assert!(!context.set_listing(listing.clone()));
+        assert!(context.listservers().iter().all(|status| status.listing() == listing));
+    }
+}
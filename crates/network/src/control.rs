@@ -0,0 +1,139 @@
+//! # Player Control
+//!
+//! Server-initiated nudges of a player's movement, sent as one-player
+//! packets ([`GameEvent::PlayerPacket`]) through the server's player relay:
+//!
+//! - PLO_PUSHAWAY `{GCHAR dx}{GCHAR dy}` moves the player by a pixel offset,
+//!   for blocking NPC shapes ([`gserver_game::physics`]) and `pushaway`.
+//! - PLO_FREEZEPLAYER2, PLO_FULLSTOP and PLO_UNFREEZEPLAYER (no body) for
+//!   the freeze API ([`gserver_game::freeze`]). The frozen state is kept here
+//!   so the connection can ignore movement from frozen players.
+//!
+//! [`GameEvent::PlayerPacket`]: gserver_game::GameEvent::PlayerPacket
+
+use bytes::BytesMut;
+use gserver_core::PlayerID;
+use gserver_game::physics::MAX_PUSH_PIXELS;
+use gserver_game::{ControlAction, EventBus, FrozenPlayers, GameEvent};
+use gserver_protocol::codecs::write_gchar;
+use gserver_protocol::PacketTypeOut;
+use gserver_scripting::PlayerControlHandler;
+use parking_lot::Mutex;
+
+/// Encode a PLO_PUSHAWAY body (offsets are capped at [`MAX_PUSH_PIXELS`])
+pub fn push_away_data(dx: i32, dy: i32) -> Vec<u8> {
+    let mut data = BytesMut::new();
+    write_gchar(&mut data, dx.clamp(-MAX_PUSH_PIXELS, MAX_PUSH_PIXELS) as i8);
+    write_gchar(&mut data, dy.clamp(-MAX_PUSH_PIXELS, MAX_PUSH_PIXELS) as i8);
+    data.to_vec()
+}
+
+/// Sends movement control packets to players
+#[derive(Debug)]
+pub struct PlayerControl {
+    events: EventBus,
+    frozen: Mutex<FrozenPlayers>,
+}
+
+impl PlayerControl {
+    /// Create the service
+    ///
+    /// # Arguments
+    /// * `events` - Event bus for [`GameEvent::PlayerPacket`]
+    pub fn new(events: EventBus) -> Self {
+        Self { events, frozen: Mutex::new(FrozenPlayers::new()) }
+    }
+
+    /// Push a player by a pixel offset
+    pub fn push_away(&self, player: PlayerID, dx: i32, dy: i32) {
+        self.send(player, PacketTypeOut::PushAway, push_away_data(dx, dy));
+    }
+
+    /// Freeze, fullstop or release a player
+    ///
+    /// # Returns
+    /// `false` if nothing changed (and nothing was sent)
+    pub fn apply(&self, player: PlayerID, action: ControlAction) -> bool {
+        if !self.frozen.lock().apply(player, action) {
+            return false;
+        }
+        self.send(player, action.packet_type(), Vec::new());
+        true
+    }
+
+    /// Check if a player's movement is ignored
+    pub fn is_frozen(&self, player: PlayerID) -> bool {
+        self.frozen.lock().is_frozen(player)
+    }
+
+    /// Get how a player is frozen
+    pub fn frozen_action(&self, player: PlayerID) -> Option<ControlAction> {
+        self.frozen.lock().action(player)
+    }
+
+    /// Remove a player (on disconnect)
+    pub fn forget_player(&self, player: PlayerID) {
+        self.frozen.lock().forget_player(player);
+    }
+
+    fn send(&self, player: PlayerID, packet_type: PacketTypeOut, data: Vec<u8>) {
+        self.events.publish(GameEvent::PlayerPacket { player, packet_type, data });
+    }
+}
+
+impl PlayerControlHandler for PlayerControl {
+    fn push_away(&self, player: PlayerID, dx: i32, dy: i32) {
+        PlayerControl::push_away(self, player, dx, dy);
+    }
+
+    fn freeze(&self, player: PlayerID) {
+        self.apply(player, ControlAction::Freeze);
+    }
+
+    fn full_stop(&self, player: PlayerID) {
+        self.apply(player, ControlAction::FullStop);
+    }
+
+    fn unfreeze(&self, player: PlayerID) {
+        self.apply(player, ControlAction::Unfreeze);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_away_packet() {
+        let events = EventBus::new();
+        let mut rx = events.subscribe();
+        let control = PlayerControl::new(events);
+
+        control.push_away(PlayerID(2), -8, 200);
+        match rx.try_recv() {
+            Ok(GameEvent::PlayerPacket { player, packet_type, data }) => {
+                assert_eq!(player, PlayerID(2));
+                assert_eq!(packet_type, PacketTypeOut::PushAway);
+                assert_eq!(data, [32 - 8, 32 + MAX_PUSH_PIXELS as u8]);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_freeze_is_tracked() {
+        let events = EventBus::new();
+        let mut rx = events.subscribe();
+        let control = PlayerControl::new(events);
+
+        PlayerControlHandler::freeze(&control, PlayerID(5));
+        assert!(control.is_frozen(PlayerID(5)));
+        assert!(!control.apply(PlayerID(5), ControlAction::Freeze));
+        assert!(matches!(rx.try_recv(),
+            Ok(GameEvent::PlayerPacket { packet_type: PacketTypeOut::FreezePlayer2, .. })));
+        assert!(rx.try_recv().is_err());
+
+        control.forget_player(PlayerID(5));
+        assert!(!control.is_frozen(PlayerID(5)));
+    }
+}
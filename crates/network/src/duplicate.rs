@@ -0,0 +1,104 @@
+//! # Duplicate Logins
+//!
+//! One account, one player. Logged-in connections are registered in the
+//! context's [`PlayerManager`] under their account, and a second login on an
+//! account that is in use is handled by the `duplicatelogin` option:
+//!
+//! - `kickold` - The player already online is disconnected
+//!   ([`GameCommand::DisconnectRequested`]) and the new login waits until it
+//!   has left before loading the account
+//! - `rejectnew` - The new login is refused
+//!
+//! With `verifylogins` on, the old player is only kicked once the listserver
+//! accepted the new login (see [`crate::verification`]).
+//!
+//! # Save Ordering
+//! A connection leaves the manager only after its account was saved on
+//! logout, so waiting for the old player to leave means the new login loads
+//! what the old one wrote, and nothing the old one does afterwards reaches
+//! the file. A player that doesn't leave within [`KICK_WAIT`] makes the new
+//! login fail instead of loading stale data.
+//!
+//! # C++ Equivalence
+//! `PlayerClient::msgPLI_LOGIN` disconnects the existing player with
+//! "Someone else has logged into your account."
+
+use gserver_core::PlayerID;
+use gserver_game::{CommandQueue, GameCommand, PlayerManager, PlayerType};
+use std::time::Duration;
+
+/// Message for the player whose login was taken over
+pub const REPLACED_MESSAGE: &str = "Someone else has logged into your account.";
+
+/// Longest a new login waits for the player it replaces to leave
+pub const KICK_WAIT: Duration = Duration::from_secs(5);
+
+/// How often the leaving player is checked for
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Get the players (not RCs) logged in on an account, other than `id`
+pub fn others_on_account(players: &PlayerManager, account: &str, id: PlayerID) -> Vec<PlayerID> {
+    players.get_by_account(account).iter()
+        .filter(|player| player.player_type == PlayerType::Player && player.id != id)
+        .map(|player| player.id)
+        .collect()
+}
+
+/// Disconnect players and wait for them to leave the manager
+///
+/// # Returns
+/// `true` once all of them are gone, `false` if some are still there after `wait`
+pub async fn replace(players: &PlayerManager, commands: &CommandQueue, old: &[PlayerID], wait: Duration) -> bool {
+    for &player in old {
+        commands.send(GameCommand::DisconnectRequested { player, reason: REPLACED_MESSAGE.to_string() });
+    }
+
+    let gone = || old.iter().all(|&id| players.get_player(id).is_none());
+    let deadline = tokio::time::Instant::now() + wait;
+    while !gone() {
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gserver_game::Player;
+    use std::sync::Arc;
+
+    fn register(players: &PlayerManager, id: u16, kind: PlayerType, account: &str) {
+        let player = Player::new(PlayerID::new(id), kind);
+        player.properties.lock().account_name = account.to_string();
+        players.add_player(Arc::new(player));
+    }
+
+    #[tokio::test]
+    async fn test_replace_old_login() {
+        let players = Arc::new(PlayerManager::new());
+        let commands = CommandQueue::new();
+        let mut received = commands.take_receiver().unwrap();
+        register(&players, 1, PlayerType::Player, "Bob");
+        register(&players, 2, PlayerType::Rc, "Bob");
+
+        assert_eq!(others_on_account(&players, "bob", PlayerID::new(3)), [PlayerID::new(1)]);
+        assert!(others_on_account(&players, "Bob", PlayerID::new(1)).is_empty());
+
+        // Nobody leaves: the new login gives up
+        assert!(!replace(&players, &commands, &[PlayerID::new(1)], Duration::from_millis(120)).await);
+        assert_eq!(received.try_recv().unwrap(), GameCommand::DisconnectRequested {
+            player: PlayerID::new(1),
+            reason: REPLACED_MESSAGE.into(),
+        });
+
+        let leaving = players.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(60)).await;
+            leaving.remove_player(PlayerID::new(1));
+        });
+        assert!(replace(&players, &commands, &[PlayerID::new(1)], KICK_WAIT).await);
+    }
+}
@@ -0,0 +1,229 @@
+//! # Economy Ledger
+//!
+//! Every change to an account's gralats is appended to `logs/economy.log`,
+//! with where it came from:
+//!
+//! - `client` - RupeesCount from the client: items picked up, client scripts
+//! - `shop <npc>` - Buying from or selling to an NPC (see [`crate::shops`])
+//! - `death` - Gralats dropped on death (see [`crate::respawn`])
+//! - `rc <account>` - RC `/setgralats <account> <amount>`
+//!
+//! An account gaining `economyalertgain` gralats or more within
+//! `economyalertwindow` seconds is reported in staff chat, so item dupes and
+//! script bugs are caught while they happen. RC `/economy <account> [span]`
+//! lists an account's changes.
+//!
+//! # File Format
+//! ```text
+//! {YYYYMMDD-HHMMSS UTC}\t{account}\t{+/-change}\t{balance}\t{source}
+//! ```
+
+use crate::context::ServerContext;
+use gserver_game::GameEvent;
+use gserver_storage::format_timestamp;
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// Ledger file in the server folder
+pub const LEDGER_FILE: &str = "logs/economy.log";
+
+/// Most entries a search returns (the newest ones)
+pub const SEARCH_LIMIT: usize = 50;
+
+/// What changed an account's gralats
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GralatSource {
+    /// RupeesCount sent by the client
+    Client,
+    /// A trade with an NPC
+    Shop(u32),
+    /// Dropped on death
+    Death,
+    /// Set from RC by a staff account
+    Rc(String),
+}
+
+impl fmt::Display for GralatSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Client => write!(f, "client"),
+            Self::Shop(npc) => write!(f, "shop {}", npc),
+            Self::Death => write!(f, "death"),
+            Self::Rc(issuer) => write!(f, "rc {}", issuer),
+        }
+    }
+}
+
+/// One ledger line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LedgerEntry {
+    /// `YYYYMMDD-HHMMSS` UTC
+    pub stamp: String,
+
+    pub account: String,
+
+    pub change: i64,
+
+    /// Gralats after the change
+    pub balance: u32,
+
+    pub source: String,
+}
+
+/// When gains raise an alert
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlertPolicy {
+    /// Gralats gained within `window`, 0 for no alerts
+    pub gain: u64,
+
+    /// Seconds
+    pub window: u64,
+}
+
+/// The gralat ledger file and the recent gains of each account
+#[derive(Debug)]
+pub struct EconomyLedger {
+    path: PathBuf,
+    file: Mutex<Option<File>>,
+
+    /// Unix time and amount of each gain inside the alert window
+    gains: Mutex<HashMap<String, VecDeque<(u64, u64)>>>,
+}
+
+impl EconomyLedger {
+    /// Create a ledger writing `path`, opened on the first change
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), file: Mutex::new(None), gains: Mutex::new(HashMap::new()) }
+    }
+
+    /// Append a change of an account's gralats
+    ///
+    /// # Returns
+    /// The gralats gained inside the alert window, if they reached the alert
+    /// threshold; the window starts over after an alert
+    pub fn record(&self, account: &str, before: u32, after: u32, source: &GralatSource, policy: AlertPolicy, now: u64)
+        -> io::Result<Option<u64>> {
+        if before == after {
+            return Ok(None);
+        }
+        let change = i64::from(after) - i64::from(before);
+        self.write(&format!("{}\t{}\t{:+}\t{}\t{}\n", format_timestamp(now), account, change, after, source))?;
+
+        if change <= 0 || policy.gain == 0 {
+            return Ok(None);
+        }
+        let mut gains = self.gains.lock();
+        let recent = gains.entry(account.to_lowercase()).or_default();
+        recent.push_back((now, change as u64));
+        while recent.front().is_some_and(|&(at, _)| at + policy.window < now) {
+            recent.pop_front();
+        }
+        let gained: u64 = recent.iter().map(|&(_, gain)| gain).sum();
+        if gained < policy.gain {
+            return Ok(None);
+        }
+        recent.clear();
+        Ok(Some(gained))
+    }
+
+    fn write(&self, line: &str) -> io::Result<()> {
+        let mut file = self.file.lock();
+        if file.is_none() {
+            if let Some(dir) = self.path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            *file = Some(OpenOptions::new().create(true).append(true).open(&self.path)?);
+        }
+        let Some(out) = file.as_mut() else { return Ok(()) };
+        out.write_all(line.as_bytes())
+    }
+
+    /// Get an account's entries between two unix times, the newest
+    /// [`SEARCH_LIMIT`] in order
+    pub fn search(&self, account: &str, since: u64, until: u64) -> Vec<LedgerEntry> {
+        let (since, until) = (format_timestamp(since), format_timestamp(until));
+        let Ok(content) = std::fs::read_to_string(&self.path) else {
+            return Vec::new();
+        };
+        let entries: Vec<LedgerEntry> = content.lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.splitn(5, '\t').collect();
+                let [stamp, name, change, balance, source] = fields[..] else { return None };
+                if !name.eq_ignore_ascii_case(account) || stamp < since.as_str() || stamp > until.as_str() {
+                    return None;
+                }
+                Some(LedgerEntry {
+                    stamp: stamp.to_string(),
+                    account: name.to_string(),
+                    change: change.parse().ok()?,
+                    balance: balance.parse().ok()?,
+                    source: source.to_string(),
+                })
+            })
+            .collect();
+        let skip = entries.len().saturating_sub(SEARCH_LIMIT);
+        entries.into_iter().skip(skip).collect()
+    }
+}
+
+/// Record a change of an account's gralats, alerting staff chat of large gains
+///
+/// Write failures are logged; the change itself has already happened.
+pub fn audit(context: &ServerContext, account: &str, before: u32, after: u32, source: &GralatSource) {
+    let policy = {
+        let config = context.config().read();
+        AlertPolicy { gain: config.economy_alert_gain, window: config.economy_alert_window }
+    };
+    match context.economy().record(account, before, after, source, policy, gserver_game::moderation::unix_now()) {
+        Ok(Some(gained)) => {
+            let message = format!("{} gained {} gralats within {}s (last: {:+} from {})",
+                account, gained, policy.window, i64::from(after) - i64::from(before), source);
+            tracing::warn!("Economy alert: {}", message);
+            context.events().publish(GameEvent::StaffNotice { message });
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Economy ledger write failed: {}", e),
+    }
+}
+
+/// Describe search results for RC
+pub fn describe(account: &str, span: &str, entries: &[LedgerEntry]) -> String {
+    if entries.is_empty() {
+        return format!("No gralat changes for {} in the last {}", account, span);
+    }
+    let net: i64 = entries.iter().map(|e| e.change).sum();
+    let shown: Vec<String> = entries.iter()
+        .map(|e| format!("[{}] {:+} = {} ({})", e.stamp, e.change, e.balance, e.source))
+        .collect();
+    format!("Gralats of {} in the last {} ({} changes, net {:+}): {}", account, span, entries.len(), net, shown.join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_760_400_000;
+
+    #[test]
+    fn test_record_and_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let ledger = EconomyLedger::new(dir.path().join(LEDGER_FILE));
+        let policy = AlertPolicy { gain: 1000, window: 60 };
+
+        assert_eq!(ledger.record("Bob", 0, 600, &GralatSource::Client, policy, NOW - 100).unwrap(), None);
+        assert_eq!(ledger.record("Bob", 600, 600, &GralatSource::Client, policy, NOW - 90).unwrap(), None);
+        assert_eq!(ledger.record("Bob", 600, 1100, &GralatSource::Shop(5), policy, NOW - 20).unwrap(), None);
+        assert_eq!(ledger.record("Bob", 1100, 1600, &GralatSource::Rc("Admin".into()), policy, NOW).unwrap(), Some(1000));
+        assert_eq!(ledger.record("Bob", 1600, 1400, &GralatSource::Death, policy, NOW).unwrap(), None);
+
+        let entries = ledger.search("bob", NOW - 60, NOW);
+        assert_eq!(entries.iter().map(|e| (e.change, e.source.as_str())).collect::<Vec<_>>(),
+            [(500, "shop 5"), (500, "rc Admin"), (-200, "death")]);
+        assert_eq!(entries[2].balance, 1400);
+        assert!(describe("Bob", "1m", &entries).starts_with("Gralats of Bob in the last 1m (3 changes, net +800): "));
+    }
+}
@@ -0,0 +1,201 @@
+//! # Connection Errors
+//!
+//! Login, file serving and send failures, and [`log_error`], which logs any
+//! [`GServerError`] at the severity it carries. Whether an error drops the
+//! connection is decided by [`GServerError::disconnects`] in
+//! `PlayerConnection::report_error`; handlers just return the error.
+//!
+//! | Error | Severity | Disconnects |
+//! |-------|----------|-------------|
+//! | [`LoginError`] | warn (account load: error) | yes |
+//! | [`FileServeError::NotFound`] | debug | no |
+//! | [`FileServeError::Unreadable`] | warn | no |
+//! | [`SendError`] | warn | yes |
+
+use gserver_core::{GServerError, Severity, SubsystemError};
+
+/// Failure while handling the login packet
+///
+/// # C++ Equivalence
+/// The C++ server answers these with PLO_DISCMESSAGE and drops the socket
+/// (`PlayerClient::msgPLI_LOGIN`).
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum LoginError {
+    /// The login packet ended before a field
+    #[error("Login packet truncated: missing {field} at offset {offset}")]
+    Truncated { field: &'static str, offset: usize },
+
+    /// The player type byte isn't a known PLTYPE
+    #[error("Unknown player type shift: {0}")]
+    UnknownPlayerType(u32),
+
+    /// An RC login for an account without staff rights
+    #[error("RC access denied for {account}: no staff rights")]
+    NoRcRights { account: String },
+
+    /// The account couldn't be loaded
+    #[error("Failed to load account {account}: {reason}")]
+    AccountLoad { account: String, reason: String },
+
+    /// With `localauth`, the password didn't match the account's
+    #[error("Wrong password for {account}")]
+    WrongPassword { account: String },
+
+    /// The account is banned (see [`crate::bans`])
+    #[error("Account {account} is banned: {reason}")]
+    Banned { account: String, reason: String },
+
+    /// The account is in use and `duplicatelogin` refuses a second login
+    #[error("Account {account} is already logged in")]
+    AlreadyOnline { account: String },
+
+    /// The client's country is filtered by `geoipallow` or `geoipdeny`
+    #[error("Logins from {country} are not allowed")]
+    CountryBlocked { country: String },
+
+    /// With `verifylogins`, the listserver is unreachable and the account
+    /// wasn't verified recently enough to log in without it
+    #[error("Can't verify {account}: listserver unreachable")]
+    Unverified { account: String },
+
+    /// A [`LoginPolicy`](crate::loginpolicy::LoginPolicy) refused the login
+    #[error("Login of {account} denied by policy '{policy}'")]
+    PolicyDenied { account: String, policy: String },
+
+    /// A policy held the login for staff approval and no RC approved it in time
+    #[error("Login of {account} not approved by staff")]
+    NotApproved { account: String },
+}
+
+impl LoginError {
+    /// Get the PLO_DISCMESSAGE text for the client
+    ///
+    /// Kept generic so a failed login doesn't reveal why an account failed
+    /// to load. Banned players are told the reason.
+    pub fn client_message(&self) -> String {
+        let message = match self {
+            Self::Truncated { .. } | Self::UnknownPlayerType(_) => "Invalid login packet.",
+            Self::NoRcRights { .. } => "You don't have staff rights.",
+            Self::AccountLoad { .. } => "Your account could not be loaded.",
+            Self::WrongPassword { .. } => "Invalid account name or password.",
+            Self::AlreadyOnline { .. } => "This account is already in use.",
+            Self::CountryBlocked { .. } => "Connections from your country are not allowed.",
+            Self::Unverified { .. } => "Your login can't be verified right now, try again later.",
+            Self::PolicyDenied { .. } => "Your login was refused.",
+            Self::NotApproved { .. } => "Your login was not approved by staff.",
+            Self::Banned { reason, .. } => return crate::bans::ban_message(reason),
+        };
+        message.to_string()
+    }
+}
+
+impl SubsystemError for LoginError {
+    fn severity(&self) -> Severity {
+        match self {
+            Self::AccountLoad { .. } => Severity::Error,
+            _ => Severity::Warn,
+        }
+    }
+
+    fn disconnects(&self) -> bool {
+        true
+    }
+}
+
+impl From<LoginError> for GServerError {
+    fn from(error: LoginError) -> Self {
+        GServerError::subsystem(error)
+    }
+}
+
+/// Failure while serving a file to a client
+///
+/// The client gets PLO_FILESENDFAILED and carries on.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum FileServeError {
+    /// No file with that name is in the file index
+    #[error("Requested unknown file: {name}")]
+    NotFound { name: String },
+
+    /// The file exists but couldn't be read
+    #[error("Failed to read {name}: {reason}")]
+    Unreadable { name: String, reason: String },
+}
+
+impl SubsystemError for FileServeError {
+    fn severity(&self) -> Severity {
+        match self {
+            Self::NotFound { .. } => Severity::Debug,
+            Self::Unreadable { .. } => Severity::Warn,
+        }
+    }
+
+    fn disconnects(&self) -> bool {
+        false
+    }
+}
+
+impl From<FileServeError> for GServerError {
+    fn from(error: FileServeError) -> Self {
+        GServerError::subsystem(error)
+    }
+}
+
+/// A client that doesn't take its packets off the server
+///
+/// See [`crate::backpressure`].
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum SendError {
+    /// The outbound queue reached the hard limit
+    #[error("Outbound queue overflow: {queued} bytes queued, limit {limit}")]
+    QueueOverflow { queued: usize, limit: usize },
+
+    /// A bundle write blocked for too long
+    #[error("Client stopped reading: bundle write blocked for {after:?}")]
+    Stalled { after: std::time::Duration },
+}
+
+impl SubsystemError for SendError {
+    fn severity(&self) -> Severity {
+        Severity::Warn
+    }
+
+    fn disconnects(&self) -> bool {
+        true
+    }
+}
+
+impl From<SendError> for GServerError {
+    fn from(error: SendError) -> Self {
+        GServerError::subsystem(error)
+    }
+}
+
+/// Log an error at its severity
+pub fn log_error(error: &GServerError) {
+    match error.severity() {
+        Severity::Debug => tracing::debug!("{}", error),
+        Severity::Info => tracing::info!("{}", error),
+        Severity::Warn => tracing::warn!("{}", error),
+        Severity::Error => tracing::error!("{}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gserver_core::{ErrorContext, PlayerID};
+
+    #[test]
+    fn test_login_errors_disconnect_file_errors_dont() {
+        let error = GServerError::from(LoginError::UnknownPlayerType(9))
+            .with_context(ErrorContext::player(PlayerID(4)));
+        assert!(error.disconnects());
+        assert_eq!(error.to_string(), "Unknown player type shift: 9 (player 4)");
+        assert_eq!(error.downcast_ref::<LoginError>(), Some(&LoginError::UnknownPlayerType(9)));
+
+        let error = GServerError::from(FileServeError::NotFound { name: "head0.png".into() });
+        assert!(!error.disconnects());
+        assert_eq!(error.severity(), Severity::Debug);
+    }
+}
@@ -0,0 +1,197 @@
+//! # RC File Browser
+//!
+//! This module builds the RC file browser views of the server directory. What
+//! an RC sees is scoped by its account's folder rights
+//! ([`gserver_accounts::FolderRights`]): only folders with a FOLDERRIGHT line
+//! are listed, and only files one of those lines covers are shown.
+//!
+//! The handlers in `connection/filebrowser.rs` check the rights before every
+//! download, upload, delete, rename or move. Client-sent names go through
+//! [`is_plain_file_name`] and folders must come from the account's folder
+//! list, so no request can reach outside the folders it was granted.
+//!
+//! # Packet Format
+//! ```text
+//! {PLO_RC_FILEBROWSER_DIRLIST}[{GCHAR len}{rights} {folder}/{wildcard}]...
+//! {PLO_RC_FILEBROWSER_DIR}{GCHAR len}{folder}[ {GCHAR len}{file entry}]...
+//!   file entry: {GCHAR len}{name}{GCHAR len}{rights}{GINT5 size}{GINT5 modtime}
+//! {PLO_RC_FILEBROWSER_MESSAGE}{text}
+//! ```
+//!
+//! # C++ Equivalence
+//!
+//! Matches `PlayerRC::msgPLI_RC_FILEBROWSER_*` in PlayerRCPackets.cpp
+
+use crate::files::file_mod_time;
+use bytes::{BufMut, BytesMut};
+use gserver_accounts::{FolderAccess, FolderRights};
+use gserver_protocol::codecs::{write_gchar, write_gstring, write_guint5};
+use gserver_protocol::{PacketOut, PacketTypeOut};
+use std::fs;
+use std::path::Path;
+
+/// A file shown in the browser
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrowserFile {
+    /// File name
+    pub name: String,
+
+    /// Access the account has to the file
+    pub access: FolderAccess,
+
+    /// Size in bytes
+    pub size: u64,
+
+    /// Modification time (Unix seconds)
+    pub mod_time: u64,
+}
+
+/// Normalize a folder sent by the RC ("levels/*", "levels\\", "/levels")
+/// to the form folder rights use ("levels")
+pub fn normalize_folder(folder: &str) -> String {
+    let folder = folder.trim().replace('\\', "/");
+    let folder = match folder.rfind('/') {
+        // The folder list sends "folder/wildcard"; the wildcard isn't part of it
+        Some(pos) if folder[pos + 1..].contains(['*', '?']) => &folder[..pos],
+        _ if folder.contains(['*', '?']) => "",
+        _ => folder.as_str(),
+    };
+    folder.trim_matches('/').to_string()
+}
+
+/// Check that a client-sent file name names a file, not a path
+pub fn is_plain_file_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\', ':', '\0'])
+}
+
+/// Join a folder (as normalized by [`normalize_folder`]) and a file name into
+/// the path folder rights are checked against
+pub fn rights_path(folder: &str, name: &str) -> String {
+    if folder.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", folder, name)
+    }
+}
+
+/// List the files of a folder the account may see, sorted by name
+///
+/// # Arguments
+/// * `server_dir` - Server directory folder rights are relative to
+/// * `folder` - Normalized folder
+/// * `rights` - Folder rights of the account
+pub fn list_folder(server_dir: &Path, folder: &str, rights: &FolderRights) -> Vec<BrowserFile> {
+    let Ok(entries) = fs::read_dir(server_dir.join(folder)) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<BrowserFile> = entries.flatten()
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            let access = rights.permission(&rights_path(folder, &name))?;
+            let path = entry.path();
+            Some(BrowserFile {
+                access,
+                size: fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+                mod_time: file_mod_time(&path).unwrap_or(0),
+                name,
+            })
+        })
+        .collect();
+    files.sort_by_key(|file| file.name.to_lowercase());
+    files
+}
+
+/// Build PLO_RC_FILEBROWSER_DIRLIST from the account's folder rights
+pub fn dir_list_packet(rights: &FolderRights) -> PacketOut {
+    let mut buf = BytesMut::new();
+    for right in rights.iter() {
+        write_gstring(&mut buf, &right.to_string());
+    }
+    PacketOut::new(PacketTypeOut::RcFileBrowserDirList, buf.to_vec())
+}
+
+/// Build PLO_RC_FILEBROWSER_DIR for a folder and its files
+pub fn dir_packet(folder: &str, files: &[BrowserFile]) -> PacketOut {
+    let mut buf = BytesMut::new();
+    write_gstring(&mut buf, &format!("{}/", folder));
+
+    for file in files {
+        let mut entry = BytesMut::new();
+        write_gstring(&mut entry, &file.name);
+        write_gstring(&mut entry, &file.access.to_string());
+        write_guint5(&mut entry, file.size.min(u32::MAX as u64) as u32);
+        write_guint5(&mut entry, file.mod_time as u32);
+
+        buf.put_u8(b' ');
+        write_gchar(&mut buf, entry.len().min(i8::MAX as usize) as i8);
+        buf.put_slice(&entry);
+    }
+    PacketOut::new(PacketTypeOut::RcFileBrowserDir, buf.to_vec())
+}
+
+/// Build PLO_RC_FILEBROWSER_MESSAGE
+pub fn message_packet(text: &str) -> PacketOut {
+    PacketOut::new(PacketTypeOut::RcFileBrowserMessage, text.as_bytes().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_folder() {
+        assert_eq!(normalize_folder("levels/*"), "levels");
+        assert_eq!(normalize_folder("world\\images\\"), "world/images");
+        assert_eq!(normalize_folder("/levels/*.nw"), "levels");
+        assert_eq!(normalize_folder("*"), "");
+    }
+
+    #[test]
+    fn test_plain_file_names() {
+        assert!(is_plain_file_name("start.nw"));
+        assert!(is_plain_file_name("..start.nw"));
+        assert!(!is_plain_file_name(".."));
+        assert!(!is_plain_file_name("../accounts/admin.txt"));
+        assert!(!is_plain_file_name("levels\\start.nw"));
+        assert!(!is_plain_file_name(""));
+    }
+
+    #[test]
+    fn test_list_folder_shows_covered_files_only() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("levels")).unwrap();
+        fs::create_dir(dir.path().join("levels/old")).unwrap();
+        fs::write(dir.path().join("levels/start.nw"), b"GLEVNW01").unwrap();
+        fs::write(dir.path().join("levels/notes.txt"), b"notes").unwrap();
+        fs::write(dir.path().join("levels/Cave.nw"), b"").unwrap();
+
+        let rights = FolderRights::parse(&["rw levels/*.nw"]);
+        let files = list_folder(dir.path(), "levels", &rights);
+        let names: Vec<_> = files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["Cave.nw", "start.nw"]);
+        assert_eq!(files[1].size, 8);
+        assert!(files[1].access.write);
+
+        assert!(list_folder(dir.path(), "config", &rights).is_empty());
+    }
+
+    #[test]
+    fn test_browser_packets() {
+        let rights = FolderRights::parse(&["rw levels/*", "r images/*.png"]);
+        let packet = dir_list_packet(&rights);
+        assert_eq!(packet.packet_type, PacketTypeOut::RcFileBrowserDirList);
+        assert_eq!(packet.packet_data, b"\x2Brw levels/*\x2Er images/*.png");
+
+        let file = BrowserFile { name: "a.nw".into(), access: FolderAccess::parse("rw"), size: 1, mod_time: 0 };
+        let packet = dir_packet("levels", &[file]);
+        // {GCHAR 7}levels/ {GCHAR 18}{GCHAR 4}a.nw{GCHAR 2}rw{GINT5 1}{GINT5 0}
+        let mut expected = b"\x27levels/ \x32\x24a.nw\x22rw".to_vec();
+        expected.extend_from_slice(&[32, 32, 32, 32, 33, 32, 32, 32, 32, 32]);
+        assert_eq!(packet.packet_data, expected);
+    }
+}
@@ -0,0 +1,300 @@
+//! # File Serving
+//!
+//! This module finds the files clients request (PLI_WANTFILE, PLI_UPDATEFILE)
+//! and encodes them as PLO_FILE transfers.
+//!
+//! # File Index
+//!
+//! [`FileIndex`] scans `world/` once using the patterns from
+//! foldersconfig.txt (`file images/*.png`, `head heads/*`, ...) and maps each
+//! file name to its path. Clients only ever send a bare file name, so lookups
+//! never touch paths from the network. Names are matched case-insensitively
+//! since Graal clients treat them that way.
+//!
+//! Pattern folders are found ignoring case too (`images/` finds `Images/`),
+//! and each folder is listed once per scan however many patterns share it,
+//! so requests are hash lookups that never read a directory.
+//!
+//! # Transfer Format
+//! ```text
+//! [{PLO_LARGEFILESTART}{name}]                 // files over 32000 bytes
+//! [{PLO_LARGEFILESIZE}{GINT5 size}]
+//! {PLO_RAWDATA}{GINT3 length}                  // one pair per 32000 byte chunk
+//! {PLO_FILE}{GINT5 modtime}{GCHAR name length}{name}{chunk}\n
+//! [{PLO_LARGEFILEEND}{name}]
+//! ```
+//!
+//! # C++ Equivalence
+//!
+//! Matches `FileSystem` (FileSystem.cpp) and `PlayerClient::sendFile` in
+//! PlayerClient.cpp. Clients older than 2.1 (no modtime field) and 2.14 (no
+//! large files) aren't supported by this server, so their variants are
+//! omitted.
+
+use bytes::{BufMut, BytesMut};
+use gserver_config::FolderConfig;
+use gserver_core::{resolve_ignore_case, wildcard_match};
+use gserver_protocol::codecs::{write_gint, write_gstring, write_guint5};
+use gserver_protocol::PacketTypeOut;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Files larger than this are split into chunks wrapped in LARGEFILESTART/END
+///
+/// # C++ Equivalence
+/// Matches the 32000 byte chunk size in `PlayerClient::sendFile`
+pub const FILE_CHUNK_SIZE: usize = 32000;
+
+/// Index of the files clients may download
+#[derive(Debug)]
+pub struct FileIndex {
+    /// World directory the folder patterns are relative to
+    world_dir: PathBuf,
+
+    /// Folder patterns from foldersconfig.txt
+    patterns: Vec<String>,
+
+    /// Lowercase file name -> path
+    files: RwLock<HashMap<String, PathBuf>>,
+}
+
+impl FileIndex {
+    /// Create an index and scan the world directory
+    ///
+    /// # Arguments
+    /// * `world_dir` - Server world directory
+    /// * `folders` - Folder patterns from foldersconfig.txt
+    pub fn new<P: Into<PathBuf>>(world_dir: P, folders: &FolderConfig) -> Self {
+        let index = Self {
+            world_dir: world_dir.into(),
+            patterns: folders.entries.iter().map(|(_, pattern)| pattern.clone()).collect(),
+            files: RwLock::new(HashMap::new()),
+        };
+        index.rescan();
+        index
+    }
+
+    /// Rescan the world directory (e.g. after files were uploaded)
+    ///
+    /// # Returns
+    /// Number of indexed files
+    pub fn rescan(&self) -> usize {
+        let mut files = HashMap::new();
+        let mut listings: HashMap<PathBuf, Vec<(String, PathBuf)>> = HashMap::new();
+
+        for pattern in &self.patterns {
+            let (dir, wildcard) = match pattern.rfind('/') {
+                Some(pos) => (&pattern[..pos], &pattern[pos + 1..]),
+                None => ("", pattern.as_str()),
+            };
+            let Some(dir) = resolve_ignore_case(&self.world_dir, dir) else {
+                continue;
+            };
+            let listing = listings.entry(dir).or_insert_with_key(|dir| list_files(dir));
+
+            for (name, path) in listing.iter() {
+                if wildcard_match(wildcard, name) {
+                    // First matching pattern wins, like the C++ FileSystem
+                    files.entry(name.to_lowercase()).or_insert_with(|| path.clone());
+                }
+            }
+        }
+
+        let count = files.len();
+        *self.files.write() = files;
+        tracing::debug!("Indexed {} files in {:?}", count, self.world_dir);
+        count
+    }
+
+    /// Find the path of a file by name
+    pub fn find(&self, name: &str) -> Option<PathBuf> {
+        self.files.read().get(&name.to_lowercase()).cloned()
+    }
+
+    /// Get the modification time of a file by name (Unix seconds)
+    ///
+    /// # Returns
+    /// `None` if the file isn't indexed or can't be read
+    ///
+    /// # C++ Equivalence
+    /// Matches `FileSystem::getModTime()`
+    pub fn mod_time(&self, name: &str) -> Option<u64> {
+        self.find(name).and_then(|path| file_mod_time(&path))
+    }
+
+    /// Get the number of indexed files
+    pub fn len(&self) -> usize {
+        self.files.read().len()
+    }
+
+    /// Check if no files are indexed
+    pub fn is_empty(&self) -> bool {
+        self.files.read().is_empty()
+    }
+
+    /// Get the indexed file names (lowercase), sorted
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.files.read().keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+/// List the files of a directory, `(name, path)`
+fn list_files(dir: &Path) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries.flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter_map(|path| Some((path.file_name()?.to_str()?.to_string(), path)))
+        .collect()
+}
+
+/// Get the modification time of a file (Unix seconds)
+pub fn file_mod_time(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    modified.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs())
+}
+
+/// Encode a file as the packets that transfer it, in send order
+///
+/// Every entry goes to the file queue as a whole; the PLO_RAWDATA header and
+/// its PLO_FILE packet share an entry so nothing can be sent between them.
+///
+/// # Arguments
+/// * `name` - File name as requested by the client
+/// * `mod_time` - Modification time (Unix seconds), cached by the client
+/// * `data` - File contents
+pub fn encode_file(name: &str, mod_time: u64, data: &[u8]) -> Vec<BytesMut> {
+    let mut packets = Vec::new();
+    let is_large = data.len() > FILE_CHUNK_SIZE;
+
+    if is_large {
+        let mut start = BytesMut::new();
+        start.put_u8(PacketTypeOut::LargeFileStart.as_u8() + 32);
+        start.put_slice(name.as_bytes());
+        start.put_u8(b'\n');
+        packets.push(start);
+
+        // C++: CString() >> (long long)fileData.length()
+        let mut size = BytesMut::new();
+        size.put_u8(PacketTypeOut::LargeFileSize.as_u8() + 32);
+        write_guint5(&mut size, data.len() as u32);
+        size.put_u8(b'\n');
+        packets.push(size);
+    }
+
+    // An empty file is still sent once so the client stops waiting for it
+    let chunks: Vec<&[u8]> = if data.is_empty() { vec![data] } else { data.chunks(FILE_CHUNK_SIZE).collect() };
+    for chunk in chunks {
+        let mut file = BytesMut::with_capacity(chunk.len() + name.len() + 8);
+        file.put_u8(PacketTypeOut::File.as_u8() + 32);
+        write_guint5(&mut file, mod_time as u32);
+        write_gstring(&mut file, name);
+        file.put_slice(chunk);
+        file.put_u8(b'\n');
+
+        // C++: CString() >> (char)PLO_RAWDATA >> (int)(packetLength + sendSize)
+        let mut packet = BytesMut::with_capacity(file.len() + 5);
+        packet.put_u8(PacketTypeOut::RawData.as_u8() + 32);
+        write_gint(&mut packet, file.len() as i32);
+        packet.put_u8(b'\n');
+        packet.extend_from_slice(&file);
+        packets.push(packet);
+    }
+
+    if is_large {
+        let mut end = BytesMut::new();
+        end.put_u8(PacketTypeOut::LargeFileEnd.as_u8() + 32);
+        end.put_slice(name.as_bytes());
+        end.put_u8(b'\n');
+        packets.push(end);
+    }
+
+    packets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gserver_config::FolderType;
+
+    #[test]
+    fn test_index_uses_folder_patterns() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("images")).unwrap();
+        fs::write(dir.path().join("images/Logo.png"), b"png").unwrap();
+        fs::write(dir.path().join("images/notes.txt"), b"txt").unwrap();
+        fs::write(dir.path().join("idle.gani"), b"gani").unwrap();
+
+        let folders = FolderConfig {
+            entries: vec![
+                (FolderType::File, "images/*.png".into()),
+                (FolderType::File, "*.gani".into()),
+            ],
+        };
+        let index = FileIndex::new(dir.path(), &folders);
+
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.find("logo.png"), Some(dir.path().join("images/Logo.png")));
+        assert!(index.find("notes.txt").is_none());
+        assert!(index.find("../idle.gani").is_none());
+        assert!(index.mod_time("idle.gani").is_some());
+
+        fs::write(dir.path().join("walk.gani"), b"gani").unwrap();
+        assert!(index.find("walk.gani").is_none());
+        assert_eq!(index.rescan(), 3);
+        assert!(index.find("walk.gani").is_some());
+    }
+
+    #[test]
+    fn test_index_resolves_folders_ignoring_case() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("Levels/Heads")).unwrap();
+        fs::write(dir.path().join("Levels/Heads/Head0.PNG"), b"png").unwrap();
+        fs::write(dir.path().join("Levels/Heads/head1.gif"), b"gif").unwrap();
+
+        let folders = FolderConfig {
+            entries: vec![
+                (FolderType::Head, "levels/heads/*.png".into()),
+                (FolderType::File, "levels/heads/*".into()),
+            ],
+        };
+        let index = FileIndex::new(dir.path(), &folders);
+
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.find("HEAD0.png"), Some(dir.path().join("Levels/Heads/Head0.PNG")));
+        assert!(index.find("head1.GIF").is_some());
+    }
+
+    #[test]
+    fn test_encode_small_file() {
+        let packets = encode_file("a.txt", 1000, b"hi");
+
+        // {FILE}{GINT5 1000}{GCHAR 5}a.txt hi \n
+        let file = [&[102 + 32, 32, 32, 32, 32 + 7, 32 + 104, 32 + 5][..], b"a.txthi\n"].concat();
+        let mut expected = vec![100 + 32, 32, 32, 32 + file.len() as u8, b'\n'];
+        expected.extend_from_slice(&file);
+
+        assert_eq!(packets.len(), 1);
+        assert_eq!(&packets[0][..], &expected[..]);
+    }
+
+    #[test]
+    fn test_encode_large_file() {
+        let data = vec![b'x'; FILE_CHUNK_SIZE * 2 + 1];
+        let packets = encode_file("big.png", 0, &data);
+
+        // Start, size, 3 chunks, end
+        assert_eq!(packets.len(), 6);
+        assert_eq!(&packets[0][..], b"\x64big.png\n");
+        assert_eq!(packets[1][0], 84 + 32);
+        assert_eq!(packets[4].len(), 5 + 1 + 5 + 1 + 7 + 1 + 1);
+        assert_eq!(&packets[5][..], b"\x65big.png\n");
+    }
+}
@@ -0,0 +1,155 @@
+//! # GeoIP
+//!
+//! Optional country lookup of client addresses in a MaxMind database
+//! (`geoipdb`, e.g. a GeoLite2-Country.mmdb in the server folder). The
+//! country of each logged-in player, as an ISO code like `DE`, is:
+//!
+//! - Sent to the player as the `clientr.country` flag, for scripted
+//!   player and status lists
+//! - Shown by RC `/country`
+//! - Counted per country in [`ServerStats`](crate::server::ServerStats)
+//!
+//! `geoipallow` and `geoipdeny` filter player logins by country. Addresses
+//! without one (LAN, loopback, or missing from the database) are always let
+//! in, so a server can't lock out its own network.
+
+use dashmap::DashMap;
+use gserver_config::ServerConfig as GameConfig;
+use gserver_core::{GServerError, PlayerID, Result};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::path::Path;
+
+/// Flag that tells the client its country
+pub const COUNTRY_FLAG: &str = "clientr.country";
+
+/// Check if the login filters let a country in
+///
+/// The deny list wins; a non-empty allow list lets in only its countries.
+pub fn allows(config: &GameConfig, country: Option<&str>) -> bool {
+    let Some(country) = country else {
+        return true;
+    };
+    let listed = |list: &[String]| list.iter().any(|c| c.eq_ignore_ascii_case(country));
+    !listed(&config.geoip_deny) && (config.geoip_allow.is_empty() || listed(&config.geoip_allow))
+}
+
+/// Country database and the countries of logged-in players
+#[derive(Default)]
+pub struct GeoIp {
+    reader: Option<maxminddb::Reader<Vec<u8>>>,
+    players: DashMap<PlayerID, String>,
+}
+
+impl std::fmt::Debug for GeoIp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GeoIp")
+            .field("enabled", &self.is_enabled())
+            .field("players", &self.players.len())
+            .finish()
+    }
+}
+
+impl GeoIp {
+    /// Create a lookup without a database, which knows no countries
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Open a MaxMind database
+    ///
+    /// # Errors
+    /// `GServerError::Config` if the file can't be read or isn't a database
+    pub fn open(path: &Path) -> Result<Self> {
+        let reader = maxminddb::Reader::open_readfile(path)
+            .map_err(|e| GServerError::Config(format!("Failed to open GeoIP database {}: {}", path.display(), e)))?;
+        Ok(Self { reader: Some(reader), players: DashMap::new() })
+    }
+
+    /// Open the database `geoipdb` names, relative to the server folder
+    ///
+    /// A database that fails to open is logged and GeoIP stays off.
+    pub fn from_config(server_dir: &Path, config: &GameConfig) -> Self {
+        if config.geoip_db.is_empty() {
+            return Self::disabled();
+        }
+        Self::open(&server_dir.join(&config.geoip_db)).unwrap_or_else(|e| {
+            tracing::error!("{}", e);
+            Self::disabled()
+        })
+    }
+
+    /// Check if a database is loaded
+    pub fn is_enabled(&self) -> bool {
+        self.reader.is_some()
+    }
+
+    /// Look up the country of an address
+    pub fn lookup(&self, ip: IpAddr) -> Option<String> {
+        let reader = self.reader.as_ref()?;
+        match reader.lookup::<maxminddb::geoip2::Country>(ip) {
+            Ok(found) => found?.country?.iso_code.map(str::to_string),
+            Err(e) => {
+                tracing::debug!("GeoIP lookup of {} failed: {}", ip, e);
+                None
+            }
+        }
+    }
+
+    /// Remember a player's country
+    pub fn record(&self, id: PlayerID, country: &str) {
+        self.players.insert(id, country.to_string());
+    }
+
+    /// Forget a player
+    pub fn remove(&self, id: PlayerID) {
+        self.players.remove(&id);
+    }
+
+    /// Get a player's country
+    pub fn country(&self, id: PlayerID) -> Option<String> {
+        self.players.get(&id).map(|entry| entry.value().clone())
+    }
+
+    /// Count the players of each country
+    pub fn counts(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for entry in self.players.iter() {
+            *counts.entry(entry.value().clone()).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_country_filters() {
+        let mut config = GameConfig::default();
+        assert!(allows(&config, Some("DE")) && allows(&config, None));
+
+        config.geoip_deny = vec!["RU".into()];
+        assert!(!allows(&config, Some("ru")) && allows(&config, Some("DE")));
+
+        config.geoip_allow = vec!["DE".into(), "RU".into()];
+        assert!(allows(&config, Some("DE")) && !allows(&config, Some("US")) && !allows(&config, Some("RU")));
+        assert!(allows(&config, None));
+    }
+
+    #[test]
+    fn test_player_countries() {
+        let geoip = GeoIp::disabled();
+        assert!(!geoip.is_enabled());
+        assert_eq!(geoip.lookup("8.8.8.8".parse().unwrap()), None);
+        assert!(GeoIp::open(Path::new("missing.mmdb")).is_err());
+
+        geoip.record(PlayerID::new(1), "DE");
+        geoip.record(PlayerID::new(2), "DE");
+        geoip.record(PlayerID::new(3), "US");
+        geoip.remove(PlayerID::new(3));
+        assert_eq!(geoip.country(PlayerID::new(1)).as_deref(), Some("DE"));
+        assert_eq!(geoip.counts(), BTreeMap::from([("DE".to_string(), 2)]));
+    }
+}
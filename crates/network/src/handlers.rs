@@ -30,12 +30,13 @@
 //! let mut registry = HandlerRegistry::new();
 //!
 //! // Register a handler function
-//! registry.register_function(PacketTypeIn::ToAll, |packet| async move {
+//! registry.register_function(PacketTypeIn::ToAll, |ctx, packet| async move {
 //!     println!("ToAll packet received");
 //!     Ok(())
 //! });
 //! ```
 
+use crate::context::ServerContext;
 use gserver_core::Result;
 use gserver_protocol::{PacketIn, PacketTypeIn};
 use std::collections::HashMap;
@@ -47,12 +48,13 @@ use std::future::Future;
 ///
 /// # Purpose
 /// Async function that handles a packet and returns a Result.
+/// Every handler receives the shared [`ServerContext`] alongside the packet.
 ///
 /// # Type Parameters
 /// - `'static` - The future cannot borrow any data from its environment
 /// - `Send` - The future must be safe to send between threads
 /// - `Result<()>` - Returns success or error
-pub type HandlerFunction = Arc<dyn Fn(&PacketIn) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> + Send + Sync>;
+pub type HandlerFunction = Arc<dyn Fn(Arc<ServerContext>, &PacketIn) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> + Send + Sync>;
 
 /// Registry of packet handlers
 ///
@@ -72,7 +74,7 @@ pub type HandlerFunction = Arc<dyn Fn(&PacketIn) -> Pin<Box<dyn Future<Output =
 /// let mut registry = HandlerRegistry::new();
 ///
 /// // Register a handler function
-/// registry.register_function(PacketTypeIn::LevelWarp, |packet| async move {
+/// registry.register_function(PacketTypeIn::LevelWarp, |ctx, packet| async move {
 ///     println!("Handling level warp");
 ///     Ok(())
 /// });
@@ -103,18 +105,18 @@ impl HandlerRegistry {
     /// # Example
     ///
     /// ```no_run
-    /// registry.register_function(PacketTypeIn::LevelWarp, |packet| async move {
+    /// registry.register_function(PacketTypeIn::LevelWarp, |ctx, packet| async move {
     ///     println!("Level warp packet!");
     ///     Ok(())
     /// });
     /// ```
     pub fn register_function<F, Fut>(&mut self, packet_type: PacketTypeIn, handler: F)
     where
-        F: Fn(&PacketIn) -> Fut + Send + Sync + 'static,
+        F: Fn(Arc<ServerContext>, &PacketIn) -> Fut + Send + Sync + 'static,
         Fut: Future<Output = Result<()>> + Send + 'static,
     {
-        let handler = Arc::new(move |packet: &PacketIn| -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
-            Box::pin(handler(packet))
+        let handler = Arc::new(move |ctx: Arc<ServerContext>, packet: &PacketIn| -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+            Box::pin(handler(ctx, packet))
         });
 
         tracing::debug!("Registered handler for packet type: {:?}", packet_type);
@@ -124,6 +126,7 @@ impl HandlerRegistry {
     /// Dispatch a packet to its registered handler
     ///
     /// # Arguments
+    /// * `ctx` - Shared server context
     /// * `packet` - The packet to dispatch
     ///
     /// # Returns
@@ -134,7 +137,7 @@ impl HandlerRegistry {
     /// Returns an error if:
     /// - No handler is registered for this packet type
     /// - The handler itself returns an error
-    pub async fn dispatch(&self, packet: &PacketIn) -> Result<()> {
+    pub async fn dispatch(&self, ctx: &Arc<ServerContext>, packet: &PacketIn) -> Result<()> {
         let handler = self.handlers.get(&packet.packet_type)
             .ok_or_else(|| {
                 gserver_core::GServerError::Protocol(format!(
@@ -143,7 +146,7 @@ impl HandlerRegistry {
                 ))
             })?;
 
-        handler(packet).await
+        handler(ctx.clone(), packet).await
     }
 
     /// Check if a handler is registered for a packet type
@@ -173,11 +176,15 @@ impl Default for HandlerRegistry {
 mod tests {
     use super::*;
 
+    fn test_context() -> Arc<ServerContext> {
+        Arc::new(ServerContext::new("servers/test", Default::default()))
+    }
+
     #[tokio::test]
     async fn test_registry_register() {
         let mut registry = HandlerRegistry::new();
 
-        registry.register_function(PacketTypeIn::ToAll, |packet| async move {
+        registry.register_function(PacketTypeIn::ToAll, |_ctx, _packet| async move {
             println!("ToAll packet received");
             Ok(())
         });
@@ -190,13 +197,13 @@ mod tests {
     async fn test_registry_dispatch() {
         let mut registry = HandlerRegistry::new();
 
-        registry.register_function(PacketTypeIn::ToAll, |packet| async move {
+        registry.register_function(PacketTypeIn::ToAll, |_ctx, _packet| async move {
             println!("Handling packet");
             Ok(())
         });
 
         let packet = PacketIn::new(PacketTypeIn::ToAll, vec![]);
-        let result = registry.dispatch(&packet).await;
+        let result = registry.dispatch(&test_context(), &packet).await;
         assert!(result.is_ok());
     }
 
@@ -205,7 +212,7 @@ mod tests {
         let registry = HandlerRegistry::new();
         let packet = PacketIn::new(PacketTypeIn::ToAll, vec![]);
 
-        let result = registry.dispatch(&packet).await;
+        let result = registry.dispatch(&test_context(), &packet).await;
         assert!(result.is_err());
     }
 }
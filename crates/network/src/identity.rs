@@ -0,0 +1,143 @@
+//! # Login Identity
+//!
+//! Clients end the login packet with an identity string describing the
+//! machine they run on. Its fields are comma separated, and text fields may
+//! be quoted:
+//!
+//! ```text
+//! {platform},{"id"},{pc id},{hdd id},{"os version"}
+//! win,"",02e2465a2bf38f8a115f6208df8d2b8b,54e8d93b3dea0b6e6e74cbd3b6e19417,"6.1 7601 "
+//! ```
+//!
+//! Older clients send fewer fields; missing ones are empty. The platform and
+//! ids are stored with the account (PLATFORM, PCID, HDDID) and can be banned
+//! with [`BanManager`](crate::bans::BanManager). Ids with whitespace or control
+//! characters are dropped, since they would break the account and ban files.
+//!
+//! # C++ Equivalence
+//! Like the identity handling of `PlayerClient::msgPLI_LOGIN`, which keeps the
+//! PC id for PC-id bans.
+
+use gserver_accounts::Account;
+
+/// The kinds of identifiers a ban can be keyed on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IdentifierKind {
+    /// PC id
+    PcId,
+    /// Hard disk id
+    HddId,
+}
+
+impl IdentifierKind {
+    /// Parse a ban file kind (`pcid`, `hddid`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "pcid" => Some(IdentifierKind::PcId),
+            "hddid" => Some(IdentifierKind::HddId),
+            _ => None,
+        }
+    }
+
+    /// Get the ban file kind name
+    pub fn name(&self) -> &'static str {
+        match self {
+            IdentifierKind::PcId => "pcid",
+            IdentifierKind::HddId => "hddid",
+        }
+    }
+}
+
+/// Check if an identifier can be stored in the account and ban files
+///
+/// Ids are hex digests; anything with whitespace or control characters is
+/// refused.
+pub fn is_valid_id(id: &str) -> bool {
+    id.chars().all(|c| c.is_ascii_graphic())
+}
+
+/// The parsed identity string of a login
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Identity {
+    /// Client platform (`win`, `mac`, `linux`, `android`, ...)
+    pub platform: String,
+    /// Client-chosen id, usually empty
+    pub id: String,
+    /// PC id
+    pub pc_id: String,
+    /// Hard disk id
+    pub hdd_id: String,
+    /// Operating system version
+    pub os: String,
+}
+
+impl Identity {
+    /// Parse an identity string
+    pub fn parse(identity: &str) -> Self {
+        let mut fields = identity.split(',').map(|field| field.trim().trim_matches('"').to_string());
+        let mut next = || fields.next().unwrap_or_default();
+        let mut next_id = || Some(next()).filter(|id| is_valid_id(id)).unwrap_or_default();
+        Self { platform: next_id(), id: next_id(), pc_id: next_id(), hdd_id: next_id(), os: next() }
+    }
+
+    /// Get the identifiers a ban can match, skipping empty ones
+    pub fn identifiers(&self) -> Vec<(IdentifierKind, &str)> {
+        [(IdentifierKind::PcId, self.pc_id.as_str()), (IdentifierKind::HddId, self.hdd_id.as_str())]
+            .into_iter()
+            .filter(|(_, value)| !value.is_empty())
+            .collect()
+    }
+
+    /// Read the identity stored with an account
+    pub fn of_account(account: &Account) -> Self {
+        Self {
+            platform: account.platform.clone(),
+            pc_id: account.pc_id.clone(),
+            hdd_id: account.hdd_id.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Store the identity with an account
+    ///
+    /// # Returns
+    /// `true` if the account changed
+    pub fn store(&self, account: &mut Account) -> bool {
+        let changed = account.platform != self.platform || account.pc_id != self.pc_id || account.hdd_id != self.hdd_id;
+        account.platform.clone_from(&self.platform);
+        account.pc_id.clone_from(&self.pc_id);
+        account.hdd_id.clone_from(&self.hdd_id);
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_identity() {
+        let identity = Identity::parse("win,\"\",02e2465a,54e8d93b,\"6.1 7601 \"");
+        assert_eq!(identity, Identity {
+            platform: "win".into(),
+            id: String::new(),
+            pc_id: "02e2465a".into(),
+            hdd_id: "54e8d93b".into(),
+            os: "6.1 7601 ".into(),
+        });
+        assert_eq!(identity.identifiers(), [(IdentifierKind::PcId, "02e2465a"), (IdentifierKind::HddId, "54e8d93b")]);
+
+        let old = Identity::parse("linux,bot7,loadtest");
+        assert_eq!((old.pc_id.as_str(), old.hdd_id.as_str()), ("loadtest", ""));
+        assert_eq!(old.identifiers().len(), 1);
+
+        let mut account = Account::default();
+        assert!(old.store(&mut account));
+        assert!(!old.store(&mut account));
+        assert_eq!(Identity::of_account(&account).identifiers(), old.identifiers());
+
+        let injected = Identity::parse("win,\"\",02e2465a\npcid other 0,54e8 d93b");
+        assert!(injected.identifiers().is_empty());
+        assert_eq!(injected.platform, "win");
+    }
+}
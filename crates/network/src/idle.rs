@@ -0,0 +1,203 @@
+//! # Idle Detection
+//!
+//! Two kinds of inactivity are tracked per connection:
+//!
+//! - **Protocol**: nothing at all arrived. The socket is probably dead, so the
+//!   connection is dropped after `protocoltimeout` seconds.
+//! - **Gameplay**: packets still arrive but the player doesn't move, chat or
+//!   warp. After `afkminutes` the player is marked away (
+//!   [`PLSTATUS_PAUSED`], the client's away icon); after
+//!   `idledisconnectminutes` they're disconnected. Staff are never
+//!   disconnected for gameplay inactivity.
+//!
+//! # C++ Equivalence
+//! The C++ server drops clients after 5 minutes without data and, with
+//! `disconnectifnotmoved`, after `maxnomovement` seconds without moving
+//! (`PlayerClient::doTimedEvents`).
+
+use gserver_config::ServerConfig;
+use gserver_game::properties::PlayerProp;
+use std::time::{Duration, Instant};
+
+/// Player status bit for a paused / away player
+pub const PLSTATUS_PAUSED: u32 = 0x01;
+
+/// Props whose change counts as gameplay activity in PLI_PLAYERPROPS
+pub const GAMEPLAY_PROPS: &[PlayerProp] = &[
+    PlayerProp::X,
+    PlayerProp::Y,
+    PlayerProp::Z,
+    PlayerProp::X2,
+    PlayerProp::Y2,
+    PlayerProp::Z2,
+    PlayerProp::Sprite,
+    PlayerProp::Gani,
+    PlayerProp::CurChat,
+];
+
+/// Idle limits from the server options
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdlePolicy {
+    /// Time without any data before the connection is dropped
+    pub protocol_timeout: Duration,
+
+    /// Gameplay inactivity before the player is marked away
+    pub afk_after: Option<Duration>,
+
+    /// Gameplay inactivity before a non-staff player is disconnected
+    pub disconnect_after: Option<Duration>,
+}
+
+impl IdlePolicy {
+    /// Build the policy from the server options (0 minutes disables a limit)
+    pub fn from_config(config: &ServerConfig) -> Self {
+        let minutes = |m: u64| (m > 0).then(|| Duration::from_secs(m * 60));
+        Self {
+            protocol_timeout: Duration::from_secs(config.protocol_timeout),
+            afk_after: minutes(config.afk_minutes),
+            disconnect_after: minutes(config.idle_disconnect_minutes),
+        }
+    }
+}
+
+/// What to do about an idle connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleAction {
+    /// Nothing to do
+    None,
+
+    /// Mark the player away
+    Away,
+
+    /// Disconnect the player for gameplay inactivity
+    Disconnect,
+
+    /// Drop the connection, nothing has arrived in too long
+    TimedOut,
+}
+
+/// Activity timestamps of one connection
+#[derive(Debug, Clone)]
+pub struct IdleTracker {
+    last_data: Instant,
+    last_gameplay: Instant,
+    away: bool,
+}
+
+impl IdleTracker {
+    /// Create a tracker for a connection that just became active
+    pub fn new(now: Instant) -> Self {
+        Self { last_data: now, last_gameplay: now, away: false }
+    }
+
+    /// Record that a packet arrived
+    #[inline]
+    pub fn data(&mut self, now: Instant) {
+        self.last_data = now;
+    }
+
+    /// Record gameplay activity
+    ///
+    /// # Returns
+    /// `true` if the player was away and is now back
+    pub fn gameplay(&mut self, now: Instant) -> bool {
+        self.last_data = now;
+        self.last_gameplay = now;
+        std::mem::replace(&mut self.away, false)
+    }
+
+    /// Check the connection against the idle limits
+    ///
+    /// Returns [`IdleAction::Away`] only once per away period.
+    ///
+    /// # Arguments
+    /// * `policy` - Idle limits
+    /// * `exempt` - Player is staff and can't be disconnected for gameplay inactivity
+    /// * `now` - Current time
+    pub fn check(&mut self, policy: &IdlePolicy, exempt: bool, now: Instant) -> IdleAction {
+        if now.saturating_duration_since(self.last_data) >= policy.protocol_timeout {
+            return IdleAction::TimedOut;
+        }
+
+        let idle = now.saturating_duration_since(self.last_gameplay);
+        if !exempt && policy.disconnect_after.is_some_and(|limit| idle >= limit) {
+            return IdleAction::Disconnect;
+        }
+        if !self.away && policy.afk_after.is_some_and(|limit| idle >= limit) {
+            self.away = true;
+            return IdleAction::Away;
+        }
+        IdleAction::None
+    }
+
+    /// Get the time since any packet arrived
+    pub fn protocol_idle(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.last_data)
+    }
+
+    /// Get the time since the last gameplay activity
+    pub fn gameplay_idle(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.last_gameplay)
+    }
+
+    /// Check if the player is marked away
+    #[inline]
+    pub fn is_away(&self) -> bool {
+        self.away
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> IdlePolicy {
+        IdlePolicy::from_config(&ServerConfig {
+            protocol_timeout: 300,
+            afk_minutes: 5,
+            idle_disconnect_minutes: 20,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_away_then_back_then_disconnect() {
+        let (policy, start) = (policy(), Instant::now());
+        let mut idle = IdleTracker::new(start);
+        let at = |mins: u64| start + Duration::from_secs(mins * 60);
+
+        // Packets arriving without gameplay don't reset gameplay idleness
+        idle.data(at(4));
+        assert_eq!(idle.check(&policy, false, at(4)), IdleAction::None);
+        idle.data(at(5));
+        assert_eq!(idle.check(&policy, false, at(5)), IdleAction::Away);
+        assert_eq!(idle.check(&policy, false, at(5)), IdleAction::None);
+        assert!(idle.is_away());
+
+        assert!(idle.gameplay(at(6)));
+        assert!(!idle.gameplay(at(6)));
+
+        idle.data(at(26));
+        assert_eq!(idle.check(&policy, false, at(26)), IdleAction::Disconnect);
+    }
+
+    #[test]
+    fn test_staff_exempt_from_idle_disconnect_not_timeout() {
+        let (policy, start) = (policy(), Instant::now());
+        let mut idle = IdleTracker::new(start);
+
+        idle.data(start + Duration::from_secs(30 * 60));
+        assert_eq!(idle.check(&policy, true, start + Duration::from_secs(30 * 60)), IdleAction::Away);
+        assert_eq!(idle.check(&policy, true, start + Duration::from_secs(35 * 60)), IdleAction::TimedOut);
+    }
+
+    #[test]
+    fn test_zero_minutes_disables_limits() {
+        let policy = IdlePolicy::from_config(&ServerConfig {
+            afk_minutes: 0,
+            idle_disconnect_minutes: 0,
+            ..Default::default()
+        });
+        assert_eq!((policy.afk_after, policy.disconnect_after), (None, None));
+    }
+}
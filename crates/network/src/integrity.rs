@@ -0,0 +1,283 @@
+//! # Client Integrity Checks
+//!
+//! This module detects clients whose packet stream doesn't match what they
+//! report about it, which usually means a modified client or a proxy
+//! injecting packets.
+//!
+//! # Packet Counts
+//!
+//! Clients periodically send PLI_PACKETCOUNT with the number of packets they
+//! sent since the previous report. The server counts the packets it actually
+//! received and compares the two.
+//!
+//! # Tamper Checks
+//!
+//! Clients send PLI_TAMPERCHECK when their own integrity check fails. The
+//! server answers with a [`TamperAction`]: by default the one configured with
+//! `tamperaction` in serveroptions.txt, but operators can register an
+//! [`IntegrityPolicy`] to decide per report (e.g. allow a known tool, or
+//! disconnect repeat offenders) without patching the connection code.
+//!
+//! # C++ Equivalence
+//!
+//! Matches `PlayerClient::msgPLI_PACKETCOUNT` in PlayerClientPackets.cpp, which
+//! logs "had an invalid packet count" when the counts differ or exceed 10000.
+//! The C++ server ignores PLI_TAMPERCHECK; [`TamperAction::Log`] is the default.
+
+use gserver_core::PlayerID;
+use parking_lot::RwLock;
+use std::sync::Arc;
+
+/// Counts above this are always treated as a desync
+///
+/// # C++ Equivalence
+/// Matches the `packetCount > 10000` check in `msgPLI_PACKETCOUNT`
+pub const MAX_PACKET_COUNT: u32 = 10000;
+
+/// Result of comparing a reported packet count
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PacketCountResult {
+    /// Counts match
+    Match,
+
+    /// Counts differ (or exceed [`MAX_PACKET_COUNT`])
+    Desync {
+        /// Packets the server received
+        counted: u32,
+        /// Packets the client claims to have sent
+        reported: u16,
+    },
+}
+
+/// Per-connection packet counter
+#[derive(Debug, Default)]
+pub struct PacketCounter {
+    /// Packets received since the last PLI_PACKETCOUNT
+    counted: u32,
+
+    /// Desyncs detected on this connection
+    desyncs: u32,
+}
+
+impl PacketCounter {
+    /// Create a new counter
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a received packet
+    ///
+    /// Called for every packet, including PLI_PACKETCOUNT itself.
+    #[inline]
+    pub fn record(&mut self) {
+        self.counted = self.counted.saturating_add(1);
+    }
+
+    /// Compare the client's count with ours and start a new period
+    pub fn verify(&mut self, reported: u16) -> PacketCountResult {
+        let counted = std::mem::take(&mut self.counted);
+
+        if counted != reported as u32 || counted > MAX_PACKET_COUNT {
+            self.desyncs += 1;
+            PacketCountResult::Desync { counted, reported }
+        } else {
+            PacketCountResult::Match
+        }
+    }
+
+    /// Get the number of packets counted since the last report
+    #[inline]
+    pub fn counted(&self) -> u32 {
+        self.counted
+    }
+
+    /// Get the number of desyncs detected on this connection
+    #[inline]
+    pub fn desyncs(&self) -> u32 {
+        self.desyncs
+    }
+}
+
+/// How the server responds to an integrity violation
+///
+/// Ordered from mildest to strictest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum TamperAction {
+    /// Do nothing
+    Ignore,
+    /// Write a warning to the server log
+    #[default]
+    Log,
+    /// Log and notify connected RCs
+    WarnRc,
+    /// Log, notify RCs and disconnect the client
+    Disconnect,
+}
+
+impl TamperAction {
+    /// Parse a `tamperaction` option value (`ignore`, `log`, `warnrc`, `disconnect`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "ignore" => Some(TamperAction::Ignore),
+            "log" => Some(TamperAction::Log),
+            "warnrc" => Some(TamperAction::WarnRc),
+            "disconnect" => Some(TamperAction::Disconnect),
+            _ => None,
+        }
+    }
+
+    /// Get the option value name
+    pub fn name(&self) -> &'static str {
+        match self {
+            TamperAction::Ignore => "ignore",
+            TamperAction::Log => "log",
+            TamperAction::WarnRc => "warnrc",
+            TamperAction::Disconnect => "disconnect",
+        }
+    }
+}
+
+/// A PLI_TAMPERCHECK received from a client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TamperReport {
+    /// Reporting player
+    pub id: PlayerID,
+
+    /// Account name
+    pub account: String,
+
+    /// Raw packet payload
+    pub data: Vec<u8>,
+}
+
+/// Operator-supplied integrity policy
+///
+/// # Example
+///
+/// ```rust
+/// use gserver_network::integrity::{IntegrityPolicy, TamperAction, TamperReport};
+///
+/// /// Never act on reports from the test account
+/// struct TrustTester;
+///
+/// impl IntegrityPolicy for TrustTester {
+///     fn name(&self) -> &str {
+///         "trust_tester"
+///     }
+///
+///     fn evaluate(&self, report: &TamperReport) -> Option<TamperAction> {
+///         (report.account == "tester").then_some(TamperAction::Ignore)
+///     }
+/// }
+/// ```
+pub trait IntegrityPolicy: Send + Sync {
+    /// Policy name (for logging)
+    fn name(&self) -> &str;
+
+    /// Decide how to respond to a report
+    ///
+    /// # Returns
+    /// `None` to leave the decision to other policies or the configured default
+    fn evaluate(&self, report: &TamperReport) -> Option<TamperAction>;
+}
+
+/// Registered integrity policies
+#[derive(Default)]
+pub struct IntegrityPolicies {
+    policies: RwLock<Vec<Arc<dyn IntegrityPolicy>>>,
+}
+
+impl IntegrityPolicies {
+    /// Create an empty policy set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a policy
+    pub fn register(&self, policy: Arc<dyn IntegrityPolicy>) {
+        tracing::info!("Registered integrity policy '{}'", policy.name());
+        self.policies.write().push(policy);
+    }
+
+    /// Get the number of registered policies
+    pub fn len(&self) -> usize {
+        self.policies.read().len()
+    }
+
+    /// Check if no policies are registered
+    pub fn is_empty(&self) -> bool {
+        self.policies.read().is_empty()
+    }
+
+    /// Decide the response to a report
+    ///
+    /// If any policy returns a verdict the strictest one wins, so a policy
+    /// that ignores a report can't override one that disconnects for it.
+    /// Otherwise `default` is used.
+    pub fn evaluate(&self, report: &TamperReport, default: TamperAction) -> TamperAction {
+        self.policies.read()
+            .iter()
+            .filter_map(|policy| {
+                let verdict = policy.evaluate(report);
+                if let Some(action) = verdict {
+                    tracing::debug!("Integrity policy '{}' chose {:?}", policy.name(), action);
+                }
+                verdict
+            })
+            .max()
+            .unwrap_or(default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packet_count_match_and_desync() {
+        let mut counter = PacketCounter::new();
+        for _ in 0..5 {
+            counter.record();
+        }
+        assert_eq!(counter.verify(5), PacketCountResult::Match);
+        assert_eq!(counter.counted(), 0);
+
+        counter.record();
+        assert_eq!(counter.verify(3), PacketCountResult::Desync { counted: 1, reported: 3 });
+        assert_eq!(counter.desyncs(), 1);
+    }
+
+    struct Fixed(Option<TamperAction>);
+
+    impl IntegrityPolicy for Fixed {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        fn evaluate(&self, _report: &TamperReport) -> Option<TamperAction> {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_policy_evaluation() {
+        let report = TamperReport { id: PlayerID::new(1), account: "Bob".into(), data: vec![] };
+        let policies = IntegrityPolicies::new();
+        assert_eq!(policies.evaluate(&report, TamperAction::WarnRc), TamperAction::WarnRc);
+
+        policies.register(Arc::new(Fixed(None)));
+        policies.register(Arc::new(Fixed(Some(TamperAction::Ignore))));
+        assert_eq!(policies.evaluate(&report, TamperAction::WarnRc), TamperAction::Ignore);
+
+        policies.register(Arc::new(Fixed(Some(TamperAction::Disconnect))));
+        assert_eq!(policies.evaluate(&report, TamperAction::Log), TamperAction::Disconnect);
+    }
+
+    #[test]
+    fn test_tamper_action_names() {
+        for action in [TamperAction::Ignore, TamperAction::Log, TamperAction::WarnRc, TamperAction::Disconnect] {
+            assert_eq!(TamperAction::from_name(action.name()), Some(action));
+        }
+        assert_eq!(TamperAction::from_name("kick"), None);
+    }
+}
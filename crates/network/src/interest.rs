@@ -0,0 +1,135 @@
+//! # Player Interest
+//!
+//! Player props go only to the players in range (see
+//! [`gserver_game::interest`]), so a full gmap doesn't send every step to
+//! everyone on it:
+//!
+//! - Positions on a level of a `world/*.gmap` count from the gmap's top
+//!   left, so players see each other across level borders; other levels are
+//!   spaces of their own. Groups (see [`gserver_game::groups`]) get their own
+//!   spaces
+//! - A player coming into range gets the other's full PLO_OTHERPLPROPS with
+//!   JOINLEAVELVL=1, one going out of range JOINLEAVELVL=0
+//! - PLI_PLAYERPROPS updates go to the players still in range
+//!
+//! The ranges come from `interestrange` and `interesthysteresis`.
+
+use gserver_config::ServerConfig;
+use gserver_core::PlayerID;
+use gserver_game::properties::PlayerProp;
+use gserver_game::{InstanceKey, InterestChange, InterestMap, InterestRange, WorldPos};
+use gserver_levels::map::MapLoader;
+use gserver_protocol::codecs::write_gshort;
+use bytes::{BufMut, BytesMut};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Width and height of a level, in tiles
+const LEVEL_TILES: i32 = 64;
+
+/// Where the levels of the gmaps are
+#[derive(Debug, Default)]
+pub struct GmapLayout {
+    /// Lowercase level name to gmap and position in levels
+    levels: HashMap<String, (String, u16, u16)>,
+}
+
+impl GmapLayout {
+    /// Load every `.gmap` in a folder (unreadable ones are skipped)
+    pub fn load(world_dir: &Path) -> Self {
+        let mut levels = HashMap::new();
+        let Ok(entries) = std::fs::read_dir(world_dir) else {
+            return Self { levels };
+        };
+        for path in entries.flatten().map(|e| e.path()) {
+            if path.extension().and_then(|e| e.to_str()) != Some("gmap") {
+                continue;
+            }
+            let Ok(map) = MapLoader::load_gmap(&path) else {
+                tracing::warn!("Failed to load gmap {}", path.display());
+                continue;
+            };
+            for ((x, y), level) in map.levels {
+                levels.insert(level.to_lowercase(), (map.name.clone(), x, y));
+            }
+        }
+        Self { levels }
+    }
+
+    /// Get the position of a player in its level instance
+    ///
+    /// # Arguments
+    /// * `x`, `y` - Position on the level, in tiles
+    pub fn position(&self, instance: &InstanceKey, x: f32, y: f32) -> WorldPos {
+        let (space, x, y) = match self.levels.get(&instance.level.to_lowercase()) {
+            Some((gmap, mx, my)) => (gmap.clone(), i32::from(*mx) * LEVEL_TILES + x as i32, i32::from(*my) * LEVEL_TILES + y as i32),
+            None => (instance.level.clone(), x as i32, y as i32),
+        };
+        let space = match &instance.group {
+            Some(group) => format!("{}#{}", space, group),
+            None => space,
+        };
+        WorldPos::new(space, x, y)
+    }
+}
+
+/// The gmap layout and who is in range of whom
+#[derive(Debug)]
+pub struct PlayerInterest {
+    layout: GmapLayout,
+    map: Mutex<InterestMap>,
+}
+
+impl PlayerInterest {
+    /// Create the interest tracker for a world folder
+    pub fn new(world_dir: &Path) -> Self {
+        Self { layout: GmapLayout::load(world_dir), map: Mutex::new(InterestMap::new()) }
+    }
+
+    /// Get the gmap layout
+    pub fn layout(&self) -> &GmapLayout {
+        &self.layout
+    }
+
+    /// Move a player, see [`InterestMap::update`]
+    pub fn update(&self, id: PlayerID, pos: WorldPos, config: &ServerConfig) -> InterestChange {
+        let range = InterestRange {
+            range: config.interest_range as i32,
+            hysteresis: config.interest_hysteresis as i32,
+        };
+        self.map.lock().update(id, pos, range)
+    }
+
+    /// Take a player out, returning the players it was in range of
+    pub fn remove(&self, id: PlayerID) -> Vec<PlayerID> {
+        self.map.lock().remove(id)
+    }
+}
+
+/// Build the PLO_OTHERPLPROPS body telling a client a player went out of range
+pub fn leave_data(id: PlayerID) -> Vec<u8> {
+    let mut buf = BytesMut::new();
+    write_gshort(&mut buf, id.get() as i16);
+    buf.put_u8(PlayerProp::JoinLeaveLvl as u8 + 32);
+    buf.put_u8(32);
+    buf.to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gmap_positions() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("world.gmap"),
+            "WIDTH 2\nHEIGHT 1\n0,0 a.nw\n1,0 b.nw\n").unwrap();
+        let layout = GmapLayout::load(dir.path());
+
+        let b = InstanceKey { level: "B.nw".into(), group: None };
+        assert_eq!(layout.position(&b, 10.5, 3.0), WorldPos::new("world.gmap", 74, 3));
+        let grouped = InstanceKey { level: "house.nw".into(), group: Some("red".into()) };
+        assert_eq!(layout.position(&grouped, 1.0, 2.0), WorldPos::new("house.nw#red", 1, 2));
+    }
+}
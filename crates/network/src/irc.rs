@@ -0,0 +1,382 @@
+//! # Listserver IRC
+//!
+//! The listserver runs IRC-style chat channels shared by every server. Clients
+//! talk to them with PLI_SENDTEXT and hear back through PLO_SERVERTEXT; the
+//! server relays both ways over SVO_SENDTEXT / SVI_SENDTEXT:
+//!
+//! ```text
+//! client -> server:     GraalEngine,irc,join,<channel>
+//!                       GraalEngine,irc,part,<channel>
+//!                       GraalEngine,irc,privmsg,<channel>,<message>
+//! server -> listserver: GraalEngine,irc,<join|part>,<account>,<channel>
+//!                       GraalEngine,irc,privmsg,<account>,<channel>,<message>
+//! listserver -> server: GraalEngine,irc,privmsg,<from>,<channel>,<message>
+//! server -> client:     GraalEngine,irc,privmsg,<from>,<channel>,<message>
+//! ```
+//!
+//! [`IrcBridge`] keeps which players are in which channel. Lines said on this
+//! server are relayed to its own channel members right away and published as
+//! [`GameEvent::IrcMessage`]; the server's IRC relay delivers those to the
+//! members' clients. Scripts use the same channels through `irc.join`,
+//! `irc.part` and `irc.say`, speaking as the server.
+//!
+//! # C++ Equivalence
+//! Matches the `GraalEngine,irc` handling of `ServerList::msgSVI_SENDTEXT`
+//! and `Player::msgPLI_SENDTEXT`. Fields are comma tokens as produced by
+//! `gCommaStrTokens`.
+//!
+//! [`GameEvent::IrcMessage`]: gserver_game::GameEvent::IrcMessage
+
+use dashmap::DashMap;
+use gserver_core::PlayerID;
+use gserver_game::{EventBus, GameEvent};
+use gserver_scripting::IrcHandler;
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet};
+use tokio::sync::mpsc;
+
+/// Split a comma token string (`gCommaStrTokens`)
+///
+/// Tokens may be quoted; `""` inside quotes is a literal quote.
+pub fn split_tokens(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    loop {
+        let mut token = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        token.push('"');
+                    }
+                    '"' => break,
+                    c => token.push(c),
+                }
+            }
+            // Anything between the closing quote and the comma is dropped
+            for c in chars.by_ref() {
+                if c == ',' {
+                    break;
+                }
+            }
+            tokens.push(token);
+            if chars.peek().is_none() && !text.ends_with(',') {
+                break;
+            }
+            continue;
+        }
+
+        let mut ended = true;
+        for c in chars.by_ref() {
+            if c == ',' {
+                ended = false;
+                break;
+            }
+            token.push(c);
+        }
+        tokens.push(token);
+        if ended {
+            break;
+        }
+    }
+    tokens
+}
+
+/// Join tokens into a comma token string, quoting where needed
+pub fn join_tokens(tokens: &[&str]) -> String {
+    tokens.iter()
+        .map(|token| {
+            if token.is_empty() || token.contains([',', '"', ' ']) {
+                format!("\"{}\"", token.replace('"', "\"\""))
+            } else {
+                token.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// One channel on this server
+#[derive(Debug, Default)]
+struct IrcChannel {
+    /// Channel name as first joined
+    name: String,
+
+    /// Members and their account names
+    members: HashMap<PlayerID, String>,
+}
+
+/// Channel membership and relaying between players, scripts and the
+/// listserver IRC
+#[derive(Debug)]
+pub struct IrcBridge {
+    /// Channels by lowercase name
+    channels: DashMap<String, IrcChannel>,
+
+    /// Channels joined by scripts
+    script_channels: Mutex<HashSet<String>>,
+
+    /// Name scripts speak as
+    script_nick: String,
+
+    /// Lines for the listserver (SVO_SENDTEXT)
+    outbound: mpsc::UnboundedSender<String>,
+
+    /// Receiving end of `outbound`, until the listserver client takes it
+    outbound_rx: Mutex<Option<mpsc::UnboundedReceiver<String>>>,
+
+    /// Where local channel lines are published
+    events: EventBus,
+}
+
+impl IrcBridge {
+    /// Create a bridge
+    ///
+    /// # Arguments
+    /// * `events` - Event bus for [`GameEvent::IrcMessage`]
+    /// * `script_nick` - Name scripts speak as (usually the server name)
+    pub fn new(events: EventBus, script_nick: impl Into<String>) -> Self {
+        let (outbound, outbound_rx) = mpsc::unbounded_channel();
+        Self {
+            channels: DashMap::new(),
+            script_channels: Mutex::new(HashSet::new()),
+            script_nick: script_nick.into(),
+            outbound,
+            outbound_rx: Mutex::new(Some(outbound_rx)),
+            events,
+        }
+    }
+
+    /// Take the receiver of lines for the listserver
+    ///
+    /// # Returns
+    /// `None` if it was already taken
+    pub fn take_outbound(&self) -> Option<mpsc::UnboundedReceiver<String>> {
+        self.outbound_rx.lock().take()
+    }
+
+    /// Queue a line for the listserver
+    fn send(&self, tokens: &[&str]) {
+        let mut line = vec!["GraalEngine", "irc"];
+        line.extend_from_slice(tokens);
+        // Unsent lines are dropped once the listserver client is gone
+        let _ = self.outbound.send(join_tokens(&line));
+    }
+
+    /// Add a player to a channel
+    ///
+    /// # Returns
+    /// `false` if the player was already in it
+    pub fn join(&self, id: PlayerID, account: &str, channel: &str) -> bool {
+        let mut entry = self.channels.entry(channel.to_lowercase()).or_insert_with(|| IrcChannel {
+            name: channel.to_string(),
+            members: HashMap::new(),
+        });
+        if entry.members.insert(id, account.to_string()).is_some() {
+            return false;
+        }
+        drop(entry);
+        self.send(&["join", account, channel]);
+        true
+    }
+
+    /// Remove a player from a channel
+    ///
+    /// # Returns
+    /// `false` if the player wasn't in it
+    pub fn part(&self, id: PlayerID, channel: &str) -> bool {
+        let key = channel.to_lowercase();
+        let Some(account) = self.channels.get_mut(&key).and_then(|mut c| c.members.remove(&id)) else {
+            return false;
+        };
+        self.channels.remove_if(&key, |_, c| c.members.is_empty());
+        self.send(&["part", &account, channel]);
+        true
+    }
+
+    /// Get the channels a player is in
+    pub fn channels_of(&self, id: PlayerID) -> Vec<String> {
+        self.channels.iter()
+            .filter(|c| c.members.contains_key(&id))
+            .map(|c| c.name.clone())
+            .collect()
+    }
+
+    /// Remove a player from every channel (on disconnect)
+    pub fn leave_all(&self, id: PlayerID) {
+        for channel in self.channels_of(id) {
+            self.part(id, &channel);
+        }
+    }
+
+    /// Say something in a channel
+    ///
+    /// The line goes to the listserver and to this server's channel members
+    /// except the sender.
+    ///
+    /// # Arguments
+    /// * `sender` - Speaking player, `None` for scripts
+    /// * `from` - Name shown as the sender
+    pub fn say(&self, sender: Option<PlayerID>, from: &str, channel: &str, message: &str) {
+        self.send(&["privmsg", from, channel, message]);
+        self.publish(sender, from, channel, message);
+    }
+
+    fn publish(&self, sender: Option<PlayerID>, from: &str, channel: &str, message: &str) {
+        self.events.publish(GameEvent::IrcMessage {
+            channel: channel.to_string(),
+            from: from.to_string(),
+            message: message.to_string(),
+            sender,
+        });
+    }
+
+    /// Get the players in a channel
+    pub fn members(&self, channel: &str) -> Vec<PlayerID> {
+        let mut members: Vec<PlayerID> = self.channels.get(&channel.to_lowercase())
+            .map(|c| c.members.keys().copied().collect())
+            .unwrap_or_default();
+        members.sort_by_key(|id| id.get());
+        members
+    }
+
+    /// Get the join lines that restore every membership after the listserver
+    /// reconnects
+    pub fn rejoin_lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self.channels.iter()
+            .flat_map(|c| {
+                c.members.values()
+                    .map(|account| join_tokens(&["GraalEngine", "irc", "join", account, &c.name]))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        for channel in self.script_channels.lock().iter() {
+            lines.push(join_tokens(&["GraalEngine", "irc", "join", &self.script_nick, channel]));
+        }
+        lines
+    }
+
+    /// Handle a `GraalEngine,irc,...` line from the listserver
+    ///
+    /// # Arguments
+    /// * `tokens` - Tokens after `GraalEngine,irc`
+    pub fn handle_listserver(&self, tokens: &[String]) {
+        match tokens {
+            [command, from, channel, message, ..] if command == "privmsg" => {
+                self.publish(None, from, channel, message);
+            }
+            _ => tracing::debug!("Unhandled listserver IRC message: {:?}", tokens),
+        }
+    }
+
+    /// Handle a `GraalEngine,irc,...` PLI_SENDTEXT from a player
+    ///
+    /// # Arguments
+    /// * `tokens` - Tokens after `GraalEngine,irc`
+    ///
+    /// # Returns
+    /// `false` if the command wasn't understood
+    pub fn handle_client(&self, id: PlayerID, account: &str, tokens: &[String]) -> bool {
+        match tokens {
+            [command, channel, ..] if command == "join" => {
+                self.join(id, account, channel);
+            }
+            [command, channel, ..] if command == "part" => {
+                self.part(id, channel);
+            }
+            [command, channel, message, ..] if command == "privmsg" => {
+                self.say(Some(id), account, channel, message);
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl IrcHandler for IrcBridge {
+    fn join(&self, channel: &str) {
+        if self.script_channels.lock().insert(channel.to_string()) {
+            self.send(&["join", &self.script_nick, channel]);
+        }
+    }
+
+    fn part(&self, channel: &str) {
+        if self.script_channels.lock().remove(channel) {
+            self.send(&["part", &self.script_nick, channel]);
+        }
+    }
+
+    fn say(&self, channel: &str, message: &str) {
+        IrcBridge::say(self, None, &self.script_nick, channel, message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(text: &str) -> Vec<String> {
+        split_tokens(text)
+    }
+
+    #[test]
+    fn test_comma_tokens_round_trip() {
+        assert_eq!(tokens("GraalEngine,irc,privmsg,Bob,#graal,\"hi, \"\"all\"\"\""),
+            ["GraalEngine", "irc", "privmsg", "Bob", "#graal", "hi, \"all\""]);
+        assert_eq!(tokens("a,,b,"), ["a", "", "b", ""]);
+
+        let joined = join_tokens(&["privmsg", "Bob", "#graal", "hi, \"all\""]);
+        assert_eq!(joined, "privmsg,Bob,#graal,\"hi, \"\"all\"\"\"");
+        assert_eq!(tokens(&joined)[3], "hi, \"all\"");
+    }
+
+    #[test]
+    fn test_player_channels_relay_to_listserver() {
+        let events = EventBus::new();
+        let mut local = events.subscribe();
+        let bridge = IrcBridge::new(events, "My Server");
+        let mut outbound = bridge.take_outbound().unwrap();
+
+        assert!(bridge.handle_client(PlayerID(2), "Alice", &tokens("join,#Graal")));
+        assert!(!bridge.join(PlayerID(2), "Alice", "#graal"));
+        bridge.join(PlayerID(3), "Bob", "#graal");
+        assert_eq!(bridge.members("#GRAAL"), [PlayerID(2), PlayerID(3)]);
+
+        bridge.handle_client(PlayerID(2), "Alice", &tokens("privmsg,#graal,hello there"));
+        bridge.leave_all(PlayerID(2));
+        assert_eq!(bridge.members("#graal"), [PlayerID(3)]);
+
+        let sent: Vec<String> = std::iter::from_fn(|| outbound.try_recv().ok()).collect();
+        assert_eq!(sent, [
+            "GraalEngine,irc,join,Alice,#Graal",
+            "GraalEngine,irc,join,Bob,#graal",
+            "GraalEngine,irc,privmsg,Alice,#graal,\"hello there\"",
+            "GraalEngine,irc,part,Alice,#Graal",
+        ]);
+        assert!(matches!(local.try_recv(),
+            Ok(GameEvent::IrcMessage { sender: Some(PlayerID(2)), ref message, .. }) if message == "hello there"));
+    }
+
+    #[test]
+    fn test_scripts_and_listserver_lines() {
+        let events = EventBus::new();
+        let mut local = events.subscribe();
+        let bridge = IrcBridge::new(events, "My Server");
+        let mut outbound = bridge.take_outbound().unwrap();
+
+        IrcHandler::join(&bridge, "#staff");
+        IrcHandler::say(&bridge, "#staff", "restart in 5");
+        assert_eq!(outbound.try_recv().unwrap(), "GraalEngine,irc,join,\"My Server\",#staff");
+        assert_eq!(bridge.rejoin_lines(), ["GraalEngine,irc,join,\"My Server\",#staff"]);
+
+        bridge.handle_listserver(&tokens("privmsg,Carol,#staff,ok"));
+        let from: Vec<String> = std::iter::from_fn(|| match local.try_recv() {
+            Ok(GameEvent::IrcMessage { from, .. }) => Some(from),
+            _ => None,
+        }).collect();
+        assert_eq!(from, ["My Server", "Carol"]);
+    }
+}
@@ -0,0 +1,206 @@
+//! # Keepalive and Round-Trip Time
+//!
+//! Some NAT routers forget a TCP mapping after a minute or two without
+//! traffic. The server sends every client a keepalive (PLO_NEWWORLDTIME with
+//! the current world time, harmless to resend) every `keepaliveinterval`
+//! seconds when nothing else went out, so a quiet player's mapping stays
+//! open.
+//!
+//! Clients answer with PLI_PING (`PLI_UNKNOWN46` in the C++ server). The time
+//! from the keepalive to the first ping after it is one RTT sample;
+//! [`Keepalive`] smooths samples the way TCP does (SRTT, 1/8 gain).
+//! [`LatencyTable`] keeps each player's RTT where RC commands and metrics can
+//! read it.
+
+use dashmap::DashMap;
+use gserver_core::PlayerID;
+use std::time::{Duration, Instant};
+
+/// Keepalive and RTT state of one connection
+#[derive(Debug, Clone, Default)]
+pub struct Keepalive {
+    /// When the unanswered keepalive was sent
+    outstanding: Option<Instant>,
+
+    /// When anything was last sent to the client
+    last_sent: Option<Instant>,
+
+    /// Most recent RTT sample
+    last_rtt: Option<Duration>,
+
+    /// Smoothed RTT
+    rtt: Option<Duration>,
+}
+
+impl Keepalive {
+    /// Create keepalive state with no samples
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that data went out to the client
+    #[inline]
+    pub fn data_sent(&mut self, now: Instant) {
+        self.last_sent = Some(now);
+    }
+
+    /// Check if a keepalive should be sent
+    ///
+    /// # Arguments
+    /// * `interval` - Keepalive interval (zero disables keepalives)
+    /// * `now` - Current time
+    pub fn due(&self, interval: Duration, now: Instant) -> bool {
+        !interval.is_zero()
+            && self.last_sent.is_none_or(|sent| now.saturating_duration_since(sent) >= interval)
+    }
+
+    /// Record that a keepalive was sent
+    ///
+    /// An unanswered keepalive keeps its send time, so a lost reply shows up
+    /// as a long sample instead of being forgotten.
+    pub fn keepalive_sent(&mut self, now: Instant) {
+        self.outstanding.get_or_insert(now);
+        self.last_sent = Some(now);
+    }
+
+    /// Record the client's ping
+    ///
+    /// # Returns
+    /// The RTT sample, or `None` if no keepalive was waiting for an answer
+    pub fn ping_received(&mut self, now: Instant) -> Option<Duration> {
+        let sample = now.saturating_duration_since(self.outstanding.take()?);
+        self.last_rtt = Some(sample);
+        self.rtt = Some(match self.rtt {
+            Some(rtt) => (rtt * 7 + sample) / 8,
+            None => sample,
+        });
+        Some(sample)
+    }
+
+    /// Get the smoothed RTT
+    #[inline]
+    pub fn rtt(&self) -> Option<Duration> {
+        self.rtt
+    }
+
+    /// Get the most recent RTT sample
+    #[inline]
+    pub fn last_rtt(&self) -> Option<Duration> {
+        self.last_rtt
+    }
+}
+
+/// One player's entry in the [`LatencyTable`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerLatency {
+    /// Account name
+    pub account: String,
+
+    /// Smoothed RTT
+    pub rtt: Duration,
+}
+
+/// Smoothed RTT of every logged-in player that answered a keepalive
+#[derive(Debug, Default)]
+pub struct LatencyTable {
+    entries: DashMap<PlayerID, PlayerLatency>,
+}
+
+impl LatencyTable {
+    /// Create an empty table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a player's RTT
+    pub fn update(&self, id: PlayerID, account: &str, rtt: Duration) {
+        self.entries.insert(id, PlayerLatency { account: account.to_string(), rtt });
+    }
+
+    /// Remove a player
+    pub fn remove(&self, id: PlayerID) {
+        self.entries.remove(&id);
+    }
+
+    /// Get a player's RTT
+    pub fn get(&self, id: PlayerID) -> Option<Duration> {
+        self.entries.get(&id).map(|entry| entry.rtt)
+    }
+
+    /// Get the RTT of every connection of an account (case-insensitive)
+    pub fn find_account(&self, account: &str) -> Vec<(PlayerID, Duration)> {
+        let mut found: Vec<_> = self.entries.iter()
+            .filter(|entry| entry.account.eq_ignore_ascii_case(account))
+            .map(|entry| (*entry.key(), entry.rtt))
+            .collect();
+        found.sort_by_key(|&(id, _)| id.get());
+        found
+    }
+
+    /// Get the average RTT over all players
+    pub fn average(&self) -> Option<Duration> {
+        let count = self.entries.len() as u32;
+        let total: Duration = self.entries.iter().map(|entry| entry.rtt).sum();
+        (count > 0).then(|| total / count)
+    }
+
+    /// Get the number of players with an RTT
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if no player has an RTT
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keepalive_only_when_quiet() {
+        let (interval, start) = (Duration::from_secs(30), Instant::now());
+        let mut keepalive = Keepalive::new();
+        assert!(keepalive.due(interval, start));
+        assert!(!keepalive.due(Duration::ZERO, start));
+
+        keepalive.data_sent(start);
+        assert!(!keepalive.due(interval, start + Duration::from_secs(29)));
+        assert!(keepalive.due(interval, start + Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn test_rtt_is_smoothed() {
+        let start = Instant::now();
+        let mut keepalive = Keepalive::new();
+        assert_eq!(keepalive.ping_received(start), None);
+
+        keepalive.keepalive_sent(start);
+        assert_eq!(keepalive.ping_received(start + Duration::from_millis(80)), Some(Duration::from_millis(80)));
+        assert_eq!(keepalive.ping_received(start + Duration::from_millis(90)), None);
+
+        // A keepalive resent before the reply keeps the first send time
+        keepalive.keepalive_sent(start + Duration::from_secs(1));
+        keepalive.keepalive_sent(start + Duration::from_secs(2));
+        keepalive.ping_received(start + Duration::from_millis(1160));
+        assert_eq!(keepalive.last_rtt(), Some(Duration::from_millis(160)));
+        assert_eq!(keepalive.rtt(), Some(Duration::from_millis(90)));
+    }
+
+    #[test]
+    fn test_latency_table() {
+        let table = LatencyTable::new();
+        table.update(PlayerID(2), "Alice", Duration::from_millis(40));
+        table.update(PlayerID(5), "alice", Duration::from_millis(60));
+        table.update(PlayerID(3), "Bob", Duration::from_millis(20));
+
+        assert_eq!(table.find_account("ALICE"), [(PlayerID(2), Duration::from_millis(40)), (PlayerID(5), Duration::from_millis(60))]);
+        assert_eq!(table.average(), Some(Duration::from_millis(40)));
+
+        table.remove(PlayerID(3));
+        assert_eq!(table.get(PlayerID(3)), None);
+        assert_eq!(table.len(), 2);
+    }
+}
@@ -0,0 +1,127 @@
+//! # Level Hot Reload
+//!
+//! Loaded levels whose `.nw` file changed are reparsed every
+//! `levelcheckinterval` seconds, and on RC request (PLI_RC_UPDATELEVELS or
+//! `/updatelevel <level>`). Players on a reloaded level get the differences:
+//!
+//! - PLO_BOARDMODIFY with the rectangle of base layer tiles that changed
+//! - A warp to where they stand when links, signs, chests or baddies changed
+//!   ([`GameEvent::LevelReloaded`]), so the client fetches the level again
+//!
+//! NPCs added at runtime and the level's players and state stay; board
+//! changes the server hadn't saved yet are replaced by the file.
+
+use crate::ServerContext;
+use bytes::BytesMut;
+use gserver_game::GameEvent;
+use gserver_levels::Level;
+use gserver_protocol::codecs::{write_gchar, write_gshort};
+use gserver_protocol::PacketTypeOut;
+use std::sync::Arc;
+
+/// Encode a PLO_BOARDMODIFY body with a level's tiles in a rectangle
+///
+/// `{GCHAR x}{GCHAR y}{GCHAR width}{GCHAR height}{GSHORT tile}*`, tiles by row
+pub fn board_modify_data(level: &Level, (x, y, width, height): (u8, u8, u8, u8)) -> Vec<u8> {
+    let tiles = level.tiles.read();
+    let mut data = BytesMut::new();
+    for value in [x, y, width, height] {
+        write_gchar(&mut data, value as i8);
+    }
+    for ty in y..y + height {
+        for tx in x..x + width {
+            write_gshort(&mut data, tiles.get_tile(tx, ty, 0) as i16);
+        }
+    }
+    data.to_vec()
+}
+
+/// Send the differences of a reloaded level to its players
+///
+/// # Returns
+/// `false` if nothing players see changed
+pub fn publish_reload(context: &ServerContext, old: &Level, new: &Level) -> bool {
+    let board = new.tiles.read().changed_rect(&old.tiles.read(), 0);
+    if let Some(rect) = board {
+        context.events().publish(GameEvent::LevelPacket {
+            level: new.name.clone(),
+            packet_type: PacketTypeOut::BoardModify,
+            data: board_modify_data(new, rect),
+        });
+    }
+    let objects = new.objects_differ(old);
+    if objects {
+        context.events().publish(GameEvent::LevelReloaded { level: new.name.clone() });
+    }
+    tracing::info!("Reloaded level {} (board {}, objects {})", new.name,
+        board.map_or("unchanged".to_string(), |(x, y, w, h)| format!("{}x{} at {},{}", w, h, x, y)),
+        if objects { "changed" } else { "unchanged" });
+    board.is_some() || objects
+}
+
+/// Reload the loaded levels edited on disk
+///
+/// # Returns
+/// Names of the levels reloaded
+pub fn check_level_files(context: &ServerContext) -> Vec<String> {
+    publish_all(context, context.levels().reload_changed())
+}
+
+/// Reload levels from disk, changed or not (RC request)
+///
+/// # Returns
+/// Names of the levels reloaded; levels that aren't loaded are skipped
+pub fn reload_levels(context: &ServerContext, names: &[String]) -> Vec<String> {
+    let reloaded = names.iter().filter_map(|name| context.levels().reload_level(name)).collect();
+    publish_all(context, reloaded)
+}
+
+fn publish_all(context: &ServerContext, reloaded: Vec<(Arc<Level>, Arc<Level>)>) -> Vec<String> {
+    reloaded.into_iter()
+        .map(|(old, new)| {
+            publish_reload(context, &old, &new);
+            new.name.clone()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_board_modify_data() {
+        let level = Level::create_default("test.nw".into());
+        level.tiles.write().set_tile(3, 4, 0, 511);
+        level.tiles.write().set_tile(4, 4, 0, 2);
+        let data = board_modify_data(&level, (3, 4, 2, 1));
+        assert_eq!(&data[..4], &[32 + 3, 32 + 4, 32 + 2, 32 + 1]);
+        assert_eq!(data.len(), 4 + 2 * 2);
+
+        let mut tiles = BytesMut::new();
+        write_gshort(&mut tiles, 511);
+        write_gshort(&mut tiles, 2);
+        assert_eq!(&data[4..], &tiles[..]);
+    }
+
+    #[test]
+    fn test_publish_reload() {
+        let context = ServerContext::new("servers/test", Default::default());
+        let mut events = context.events().subscribe();
+        let old = Level::create_default("test.nw".into());
+        let new = Level::create_default("test.nw".into());
+        assert!(!publish_reload(&context, &old, &new));
+        assert!(events.try_recv().is_err());
+
+        new.tiles.write().set_tile(10, 20, 0, 7);
+        assert!(publish_reload(&context, &old, &new));
+        match events.try_recv().unwrap() {
+            GameEvent::LevelPacket { level, packet_type, data } => {
+                assert_eq!((level.as_str(), packet_type), ("test.nw", PacketTypeOut::BoardModify));
+                assert_eq!(&data[..4], &[32 + 10, 32 + 20, 33, 33]);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        assert!(events.try_recv().is_err());
+    }
+}
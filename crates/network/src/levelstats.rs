@@ -0,0 +1,201 @@
+//! # Level Profiling
+//!
+//! Per-level counters for finding expensive levels and scripts, shown by the
+//! RC command `/levelstats <level>`:
+//!
+//! - Players on the level and NPCs on it
+//! - Packets and bytes broadcast to its players, per second over the last
+//!   [`RATE_WINDOW`], and in total since startup
+//! - Time spent running its NPC scripts since startup
+//!
+//! `/scriptstats [name]` shows the run time of each script (weapons, classes,
+//! NPCs) from the script host, and the memory of those with a heap: the
+//! serverside GS2 runs and Lua scripts.
+//!
+//! Broadcasts are counted by the server relays once per player sent to, so a
+//! packet to a level of 20 players counts 20 times, like the bandwidth it
+//! costs: level and instance packets, hits, player props and leaves, and
+//! ambience overlays.
+
+use crate::ServerContext;
+use dashmap::DashMap;
+use gserver_game::PlayerType;
+use gserver_scripting::{npc_script_name, ScriptStats};
+use std::time::{Duration, Instant};
+
+/// Window the broadcast rates are measured over
+pub const RATE_WINDOW: Duration = Duration::from_secs(10);
+
+/// Broadcast counters of one level
+#[derive(Debug, Clone, Copy)]
+struct LevelTraffic {
+    window_start: Instant,
+    window: (u64, u64),
+    last_window: (u64, u64),
+    total: (u64, u64),
+}
+
+impl LevelTraffic {
+    fn new(now: Instant) -> Self {
+        Self { window_start: now, window: (0, 0), last_window: (0, 0), total: (0, 0) }
+    }
+
+    /// Move on to the window `now` is in
+    fn roll(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed < RATE_WINDOW {
+            return;
+        }
+        self.last_window = if elapsed < RATE_WINDOW * 2 { self.window } else { (0, 0) };
+        self.window = (0, 0);
+        self.window_start = now;
+    }
+}
+
+/// Broadcast counters of every level
+#[derive(Debug, Default)]
+pub struct LevelStats {
+    /// Lowercase level name → counters
+    levels: DashMap<String, LevelTraffic>,
+}
+
+/// What `/levelstats` shows for one level
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LevelReport {
+    pub level: String,
+    pub players: usize,
+    pub npcs: usize,
+    pub packets_per_sec: f64,
+    pub bytes_per_sec: f64,
+    pub total_packets: u64,
+    pub total_bytes: u64,
+    pub script_time: Duration,
+}
+
+impl LevelStats {
+    /// Create empty counters
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a packet broadcast to a player on a level
+    pub fn record_broadcast(&self, level: &str, bytes: usize, now: Instant) {
+        let mut traffic = self.levels.entry(level.to_lowercase()).or_insert_with(|| LevelTraffic::new(now));
+        traffic.roll(now);
+        traffic.window.0 += 1;
+        traffic.window.1 += bytes as u64;
+        traffic.total.0 += 1;
+        traffic.total.1 += bytes as u64;
+    }
+
+    /// Get a level's broadcast rates over the last full window
+    ///
+    /// # Returns
+    /// `(packets, bytes)` per second
+    pub fn rates(&self, level: &str, now: Instant) -> (f64, f64) {
+        let Some(mut traffic) = self.levels.get(&level.to_lowercase()).map(|t| *t) else {
+            return (0.0, 0.0);
+        };
+        traffic.roll(now);
+        let seconds = RATE_WINDOW.as_secs_f64();
+        (traffic.last_window.0 as f64 / seconds, traffic.last_window.1 as f64 / seconds)
+    }
+
+    /// Get a level's broadcast totals since startup, `(packets, bytes)`
+    pub fn totals(&self, level: &str) -> (u64, u64) {
+        self.levels.get(&level.to_lowercase()).map(|t| t.total).unwrap_or_default()
+    }
+
+    /// Gather everything shown for a level
+    pub fn report(&self, level: &str, context: &ServerContext, now: Instant) -> LevelReport {
+        let players = context.players().snapshot().iter()
+            .filter(|p| p.player_type == PlayerType::Player)
+            .filter(|p| p.properties.lock().cur_level.eq_ignore_ascii_case(level))
+            .count();
+        let npc_ids: Vec<u32> = context.levels().loaded_level(level)
+            .map(|l| l.npcs.read().iter().map(|npc| npc.id).collect())
+            .unwrap_or_default();
+        let script_time = npc_ids.iter()
+            .map(|&id| context.scripts().run_time(&npc_script_name(id)))
+            .sum();
+        let (packets_per_sec, bytes_per_sec) = self.rates(level, now);
+        let (total_packets, total_bytes) = self.totals(level);
+        LevelReport {
+            level: level.to_string(),
+            players,
+            npcs: npc_ids.len(),
+            packets_per_sec,
+            bytes_per_sec,
+            total_packets,
+            total_bytes,
+            script_time,
+        }
+    }
+}
+
+impl LevelReport {
+    /// Format the report as one RC line
+    pub fn describe(&self) -> String {
+        format!(
+            "{}: {} players, {} NPCs, {:.1} packets/s, {:.1} KB/s ({} packets, {} KB total), scripts {:.1}ms",
+            self.level, self.players, self.npcs, self.packets_per_sec, self.bytes_per_sec / 1024.0,
+            self.total_packets, self.total_bytes / 1024, self.script_time.as_secs_f64() * 1000.0,
+        )
+    }
+}
+
+/// Format script stats for RC `/scriptstats`, optionally only names containing `filter`
+pub fn describe_scripts(stats: &[ScriptStats], filter: Option<&str>) -> String {
+    let filter = filter.map(str::to_lowercase);
+    let lines: Vec<String> = stats.iter()
+        .filter(|s| filter.as_ref().is_none_or(|f| s.name.to_lowercase().contains(f)))
+        .map(|s| format!("{}: {:.1}ms, {} KB live, {} KB peak, {} collections, {} errors", s.name,
+            s.run_time.as_secs_f64() * 1000.0, s.heap.live_bytes / 1024, s.heap.peak_bytes / 1024, s.heap.collections, s.errors))
+        .collect();
+    if lines.is_empty() {
+        return "No script has run".to_string();
+    }
+    lines.join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_broadcast_rates() {
+        let stats = LevelStats::new();
+        let start = Instant::now();
+        for _ in 0..20 {
+            stats.record_broadcast("Town.nw", 512, start);
+        }
+        assert_eq!(stats.rates("town.nw", start), (0.0, 0.0));
+        assert_eq!(stats.rates("town.nw", start + RATE_WINDOW), (2.0, 1024.0));
+        assert_eq!(stats.rates("town.nw", start + RATE_WINDOW * 2), (0.0, 0.0));
+
+        stats.record_broadcast("town.nw", 100, start + RATE_WINDOW);
+        assert_eq!(stats.rates("town.nw", start + RATE_WINDOW), (2.0, 1024.0));
+        assert_eq!(stats.totals("TOWN.NW"), (21, 10340));
+        assert_eq!(stats.totals("cave.nw"), (0, 0));
+    }
+
+    #[test]
+    fn test_report() {
+        let context = ServerContext::new("servers/test", Default::default());
+        let now = Instant::now();
+        context.level_stats().record_broadcast("town.nw", 2048, now);
+        let report = context.level_stats().report("town.nw", &context, now + RATE_WINDOW);
+        assert_eq!((report.players, report.npcs, report.total_bytes), (0, 0, 2048));
+        assert_eq!(report.describe(),
+            "town.nw: 0 players, 0 NPCs, 0.1 packets/s, 0.2 KB/s (1 packets, 2 KB total), scripts 0.0ms");
+
+        let stats = [ScriptStats {
+            name: "-System".into(),
+            run_time: Duration::from_millis(3),
+            heap: gserver_scripting::gs2::HeapStats { live_bytes: 2048, peak_bytes: 8192, collections: 4, freed_bytes: 0 },
+            errors: 2,
+        }];
+        assert_eq!(describe_scripts(&stats, Some("system")), "-System: 3.0ms, 2 KB live, 8 KB peak, 4 collections, 2 errors");
+        assert_eq!(describe_scripts(&stats, Some("npc")), "No script has run");
+    }
+}
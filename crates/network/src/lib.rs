@@ -5,20 +5,112 @@
 //! ## Modules
 //!
 //! - [`config`] - Server configuration options
+//! - [`context`] - Shared server state passed to handlers
 //! - [`connection`] - Individual connection management
 //! - [`handlers`] - Packet handler registry
 //! - [`server`] - Main server implementation
 //! - [`listserver`] - ListServer client implementation
+//! - [`autosave`] - Autosave targets for accounts, server flags and levels
+//! - [`integrity`] - Client integrity checks (packet counts, tamper checks)
+//! - [`nickname`] - Nickname sanitization for PLI_PLAYERPROPS
+//! - [`motd`] - servermessage.html templating and delivery
+//! - [`compression`] - Bundle compression levels and encoder pool
+//! - [`files`] - File index and PLO_FILE transfers
+//! - [`filebrowser`] - RC file browser views scoped by folder rights
+//! - [`packages`] - Update packages (PLI_UPDATEPACKAGEREQUESTFILE)
+//! - [`error`] - Login and file serving errors, logged by severity
+//! - [`idle`] - Protocol timeouts, away status and idle disconnects
+//! - [`keepalive`] - NAT keepalives and round-trip time
+//! - [`rcchat`] - Staff chat channel between RCs
+//! - [`webhook`] - Optional event posts to a Discord-compatible webhook
+//! - [`irc`] - Listserver IRC channels for players and scripts
+//! - [`ambience`] - Weather and tint control from RC, scripts and the schedule
+//! - [`control`] - Server-driven player movement (push-away)
+//! - [`backpressure`] - Outbound queue limits for clients that stop reading
+//! - [`logging`] - Log filters, rotated log files and JSON output
+//! - [`service`] - PID file, health check and signals for service managers
+//! - [`weaponsync`] - Rebuilt weapons pushed to players when classes change
+//! - [`npcsaves`] - NPC state kept across restarts in `npcs/`
+//! - [`social`] - PM delivery and friend/ignore lists
+//! - [`announce`] - Admin message popups, scheduled announcements and shutdown warnings
+//! - [`moderation`] - Mutes, jail and warp home from RC and scripts
+//! - [`session`] - Sessions held for players that reconnect after a drop
 
 pub mod config;
 pub mod connection;
+pub mod context;
 pub mod handlers;
 pub mod server;
 pub mod listserver;
+pub mod listlatency;
+pub mod autosave;
+pub mod integrity;
+pub mod nickname;
+pub mod motd;
+pub mod compression;
+pub mod files;
+pub mod filebrowser;
+pub mod packages;
+pub mod error;
+pub mod idle;
+pub mod keepalive;
+pub mod rcchat;
+pub mod webhook;
+pub mod irc;
+pub mod ambience;
+pub mod control;
+pub mod backpressure;
+pub mod logging;
+pub mod service;
+pub mod weaponsync;
+pub mod npcsaves;
+pub mod social;
+pub mod announce;
+pub mod moderation;
+pub mod session;
+pub mod duplicate;
+pub mod tls;
+pub mod proxy;
+pub mod geoip;
+pub mod requesttext;
+pub mod verification;
+pub mod traffic;
+pub mod timings;
+pub mod tasks;
+pub mod chatcommands;
+pub mod respawn;
+pub mod npcmovement;
+pub mod shops;
+pub mod levelstats;
+pub mod levelreload;
+pub mod bandwidth;
+pub mod staffrights;
+pub mod bans;
+pub mod alts;
+pub mod loginpolicy;
+pub mod identity;
+pub mod simclient;
+pub mod watchdog;
+pub mod clienttext;
+pub mod console;
+pub mod recorder;
+pub mod chatlog;
+pub mod playerstats;
+pub mod economy;
+pub mod weapondelivery;
+pub mod savequeue;
+pub mod interest;
+pub mod npcstatus;
+pub mod scriptlog;
+pub mod scripthistory;
+#[cfg(feature = "plugins")]
+pub mod plugins;
 
 // Re-export commonly used items
 pub use config::ServerConfig;
 pub use connection::{PlayerConnection, ConnectionState};
+pub use context::ServerContext;
 pub use handlers::HandlerRegistry;
 pub use server::GServer;
-pub use listserver::{ListServerClient, ListServerConfig, spawn_listserver_client};
+pub use listserver::{ListedPlayer, Listing, ListServerClient, ListServerConfig, ListServerStatus, spawn_listserver_client};
+pub use autosave::{AccountAutosave, LevelAutosave, ServerFlagsAutosave};
@@ -0,0 +1,187 @@
+//! # Listserver Registration Health
+//!
+//! Registrations that fail (no connection, or the listserver closing it
+//! within seconds) are counted until the listserver pings again. Its pings
+//! (SVI_PING) are always answered with SVO_PING.
+//!
+//! The round trip isn't measured: SVI_PING carries nothing, and the
+//! listserver doesn't echo an SVO_PING of ours, so its own pings can't be
+//! told from an answer.
+//!
+//! The failures are shown in RC `/status` and the `/stats` metrics (of the
+//! first listserver). [`run`] checks every listserver's every second and
+//! tells staff chat once when `listfailurealert` registrations in a row
+//! fail, and again when that's over.
+
+use crate::context::ServerContext;
+use gserver_game::GameEvent;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Time between alert checks
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Default)]
+struct LatencyState {
+    /// Failed registrations since the listserver last pinged
+    failures: u32,
+
+    /// Failed registrations since startup
+    total_failures: u64,
+
+    /// Why the last registration failed
+    last_failure: Option<String>,
+}
+
+/// Failed registrations of the listserver client
+#[derive(Debug, Default)]
+pub struct ListServerLatency {
+    state: Mutex<LatencyState>,
+}
+
+impl ListServerLatency {
+    /// Record an SVI_PING, which shows the registration went through
+    pub fn ping_received(&self) {
+        self.state.lock().failures = 0;
+    }
+
+    /// Record a failed registration
+    pub fn registration_failed(&self, reason: impl Into<String>) {
+        let mut state = self.state.lock();
+        state.failures += 1;
+        state.total_failures += 1;
+        state.last_failure = Some(reason.into());
+    }
+
+    /// Take the current numbers
+    pub fn report(&self, connected: bool) -> LatencyReport {
+        let state = self.state.lock();
+        LatencyReport {
+            connected,
+            failures: state.failures,
+            total_failures: state.total_failures,
+            last_failure: state.last_failure.clone(),
+        }
+    }
+}
+
+/// Snapshot of the listserver client's registration health
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LatencyReport {
+    /// The client is registered and connected
+    pub connected: bool,
+
+    /// Failed registrations in a row
+    pub failures: u32,
+
+    /// Failed registrations since startup
+    pub total_failures: u64,
+
+    /// Why the last registration failed
+    pub last_failure: Option<String>,
+}
+
+impl LatencyReport {
+    /// Describe the report in one line for RC `/status`
+    pub fn describe(&self) -> String {
+        let mut line = format!("Listserver {}, {} failed registrations in a row ({} total)",
+            if self.connected { "connected" } else { "disconnected" }, self.failures, self.total_failures);
+        if let Some(reason) = &self.last_failure {
+            line.push_str(&format!(", last: {}", reason));
+        }
+        line
+    }
+}
+
+/// Threshold from `listfailurealert`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlertThresholds {
+    /// Failures in a row at which to alert, 0 for no alerts
+    pub failures: u32,
+}
+
+/// Which alerts are raised
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LatencyAlerts {
+    failing: bool,
+}
+
+impl LatencyAlerts {
+    /// Compare a report with the thresholds
+    ///
+    /// # Returns
+    /// The staff chat lines for alerts raised or cleared since the last check
+    pub fn check(&mut self, report: &LatencyReport, thresholds: AlertThresholds) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        let failing = thresholds.failures > 0 && report.failures >= thresholds.failures;
+        if failing != self.failing {
+            self.failing = failing;
+            lines.push(match failing {
+                true => format!("Listserver registration alert: {} failures in a row (last: {})",
+                    report.failures, report.last_failure.as_deref().unwrap_or("unknown")),
+                false => "Listserver registration is working again".to_string(),
+            });
+        }
+        lines
+    }
+}
+
+/// Check the listservers' registrations until shutdown, alerting staff chat
+pub async fn run(context: Arc<ServerContext>, mut shutdown: watch::Receiver<bool>) {
+    let mut check = tokio::time::interval(CHECK_INTERVAL);
+    let mut alerts = vec![LatencyAlerts::default(); context.listservers().len()];
+    loop {
+        tokio::select! {
+            _ = check.tick() => {}
+            _ = shutdown.changed() => return,
+        }
+        let thresholds = AlertThresholds { failures: context.config().read().list_failure_alert };
+        for (status, alerts) in context.listservers().iter().zip(&mut alerts) {
+            let (host, port) = status.address();
+            for line in alerts.check(&status.latency_report(), thresholds) {
+                let message = format!("{} ({}:{})", line, host, port);
+                tracing::warn!("{}", message);
+                context.events().publish(GameEvent::StaffNotice { message });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failures() {
+        let latency = ListServerLatency::default();
+        latency.registration_failed("connection refused");
+        latency.registration_failed("closed after 1s");
+        let report = latency.report(false);
+        assert_eq!((report.failures, report.total_failures), (2, 2));
+        assert_eq!(report.describe(), "Listserver disconnected, \
+            2 failed registrations in a row (2 total), last: closed after 1s");
+
+        latency.ping_received();
+        let report = latency.report(true);
+        assert_eq!((report.failures, report.total_failures), (0, 2));
+    }
+
+    #[test]
+    fn test_alerts_once_per_crossing() {
+        let thresholds = AlertThresholds { failures: 3 };
+        let mut alerts = LatencyAlerts::default();
+        let mut report = LatencyReport { failures: 2, ..Default::default() };
+        assert!(alerts.check(&report, thresholds).is_empty());
+
+        report.failures = 3;
+        report.last_failure = Some("connection refused".into());
+        assert_eq!(alerts.check(&report, thresholds),
+            ["Listserver registration alert: 3 failures in a row (last: connection refused)"]);
+        assert!(alerts.check(&report, thresholds).is_empty());
+        let off = AlertThresholds { failures: 0 };
+        assert_eq!(alerts.check(&report, off), ["Listserver registration is working again"]);
+    }
+}
@@ -12,6 +12,32 @@
 //! 4. Handles incoming SVI_* packets
 //! 5. Auto-reconnects on disconnect with exponential backoff
 //!
+//! # Listing Extras
+//!
+//! Beyond the SVO_NEWSERVER fields, hosts can give their listing an icon, a
+//! banner and category tags (`list_icon`, `list_banner`, `list_tags` in
+//! adminconfig.txt). They go out as `Listserver,settings,...` text lines
+//! after registration, and again whenever [`ListServerStatus::set_listing`]
+//! changes them (config reload, RC `/listing`).
+//!
+//! # Several Listservers
+//!
+//! Besides `listip:listport`, the server can register with the listservers
+//! of `listservers`, so it shows up in more than one server list. Each one
+//! gets its own client task and [`ListServerStatus`], registering and
+//! sending its player list on its own: the full list (SVO_SETPLYR) after each
+//! registration, then each login and logout (SVO_PLYRADD, SVO_PLYRREM) as
+//! [`ServerContext::list_player`](crate::context::ServerContext::list_player)
+//! reports them. Login verification, player text
+//! requests and the IRC relay go through the first one
+//! ([`ServerContext::listserver`](crate::context::ServerContext::listserver));
+//! listing extras go to all of them.
+//!
+//! # Registration Health
+//!
+//! The client answers the listserver's pings and counts failed
+//! registrations, see [`crate::listlatency`].
+//!
 //! # Protocol
 //!
 //! - **Compression**: zlib compression on all packets
@@ -24,13 +50,18 @@
 //! - C++: `/home/versa/Desktop/GServer-v2/server/include/ServerList.h`
 
 use crate::config::ServerConfig;
-use gserver_core::{Result, GServerError};
+use bytes::{BufMut, BytesMut};
+use crate::irc::{join_tokens, split_tokens, IrcBridge};
+use crate::listlatency::{LatencyReport, ListServerLatency};
+use crate::requesttext::{RequestTextRegistry, TextRequest};
+use crate::watchdog::{self, Heartbeat, HEARTBEAT_INTERVAL};
+use gserver_core::{PlayerID, Result, GServerError};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::net::TcpStream;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::sync::{mpsc, Mutex};
-use tokio::time::sleep;
+use tokio::sync::{mpsc, Notify};
 use tracing::{info, warn, error, debug, trace};
 use rand::Rng;
 
@@ -72,6 +103,7 @@ pub struct ListServerConfig {
 
     /// Only staff mode
     pub only_staff: bool,
+
 }
 
 impl Default for ListServerConfig {
@@ -93,6 +125,283 @@ impl Default for ListServerConfig {
     }
 }
 
+/// Listing extras shown by server browsers
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Listing {
+    /// Server icon image URL
+    pub icon: String,
+
+    /// Banner image URL
+    pub banner: String,
+
+    /// Category tags, like "pvp" or "roleplay"
+    pub tags: Vec<String>,
+}
+
+impl Listing {
+    /// Take the listing extras from adminconfig.txt
+    pub fn from_config(config: &gserver_config::ServerConfig) -> Self {
+        Self {
+            icon: config.list_icon.clone(),
+            banner: config.list_banner.clone(),
+            tags: config.list_tags.clone(),
+        }
+    }
+
+    /// Build the SVO_SENDTEXT lines for the listserver
+    ///
+    /// Every field is sent, so one that was cleared is cleared on the listing too.
+    pub fn settings_lines(&self) -> Vec<String> {
+        let tags: Vec<&str> = self.tags.iter().map(String::as_str).collect();
+        vec![
+            join_tokens(&["Listserver", "settings", "icon", &self.icon]),
+            join_tokens(&["Listserver", "settings", "banner", &self.banner]),
+            format!("Listserver,settings,tags{}{}", if tags.is_empty() { "" } else { "," }, join_tokens(&tags)),
+        ]
+    }
+}
+
+/// A player as the listserver lists it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListedPlayer {
+    pub account: String,
+    pub nick: String,
+    pub level: String,
+    /// Listed as an RC rather than a client
+    pub rc: bool,
+}
+
+impl ListedPlayer {
+    /// Encode the props the listserver shows: account, nickname and level
+    ///
+    /// Each value is cut to 64 bytes so the whole fits SVO_SETPLYR's GCHAR length.
+    fn props(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        for (prop, value) in [(PLPROP_ACCOUNTNAME, &self.account), (PLPROP_NICKNAME, &self.nick), (PLPROP_CURLEVEL, &self.level)] {
+            let value = &value.as_bytes()[..value.len().min(64)];
+            data.push(prop + 32);
+            data.push(value.len() as u8 + 32);
+            data.extend_from_slice(value);
+        }
+        data
+    }
+
+    /// Build SVO_PLYRADD: `[GSHORT id][GCHAR type][props]`
+    fn add_packet(&self, id: PlayerID) -> Vec<u8> {
+        let mut packet = BytesMut::new();
+        packet.put_u8(5 + 32); // SVO_PLYRADD encoded (5 + 32 = 37)
+        gserver_protocol::codecs::write_gshort(&mut packet, id.get() as i16);
+        packet.put_u8(u8::from(self.rc) + 32);
+        packet.put_slice(&self.props());
+        packet.to_vec()
+    }
+}
+
+/// Build SVO_PLYRREM: `[GSHORT id]`
+fn remove_packet(id: PlayerID) -> Vec<u8> {
+    let mut packet = BytesMut::new();
+    packet.put_u8(6 + 32); // SVO_PLYRREM encoded (6 + 32 = 38)
+    gserver_protocol::codecs::write_gshort(&mut packet, id.get() as i16);
+    packet.to_vec()
+}
+
+/// Build SVO_SETPLYR, replacing the whole list: `[GCHAR count]{[GCHAR len][props]}`
+fn set_players_packet(players: &[(PlayerID, ListedPlayer)]) -> Vec<u8> {
+    let players = &players[..players.len().min(223)];
+    let mut packet = vec![7 + 32, players.len() as u8 + 32]; // SVO_SETPLYR encoded (7 + 32 = 39)
+    for (_, player) in players {
+        let props = player.props();
+        packet.push(props.len() as u8 + 32);
+        packet.extend_from_slice(&props);
+    }
+    packet
+}
+
+const PLPROP_NICKNAME: u8 = 0;
+const PLPROP_CURLEVEL: u8 = 20;
+const PLPROP_ACCOUNTNAME: u8 = 34;
+
+/// A login or logout to send the listserver
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PlayerChange {
+    Added(PlayerID, ListedPlayer),
+    Removed(PlayerID),
+}
+
+/// The players a listserver lists, and the changes it hasn't been sent yet
+#[derive(Debug, Default)]
+struct PlayerList {
+    players: Vec<(PlayerID, ListedPlayer)>,
+    changes: Vec<PlayerChange>,
+    /// Whether the listserver got the full list since it last (re)connected;
+    /// until then changes aren't queued, the full list covers them
+    synced: bool,
+}
+
+/// Listserver connection state, shared with the health endpoint
+#[derive(Debug, Default)]
+pub struct ListServerStatus {
+    /// Host and port of the listserver
+    address: (String, u16),
+
+    connected: AtomicBool,
+
+    /// Listing extras the client sends
+    listing: parking_lot::Mutex<Listing>,
+
+    /// Woken when the listing changes
+    listing_changed: Notify,
+
+    /// Logins waiting to be verified (see [`crate::verification`])
+    verifications: parking_lot::Mutex<Vec<(PlayerID, String)>>,
+
+    /// Woken when a login is queued for verification
+    verification_requested: Notify,
+
+    /// Player text requests waiting to be asked (see [`crate::clienttext`])
+    player_requests: parking_lot::Mutex<Vec<(PlayerID, String)>>,
+
+    /// Woken when a player text request is queued
+    player_request_queued: Notify,
+
+    /// Players online, sent after registration and at each login and logout
+    players: parking_lot::Mutex<PlayerList>,
+
+    /// Woken when a player logs in or out while the list is synced
+    players_changed: Notify,
+
+    /// Round-trip time and failed registrations
+    latency: ListServerLatency,
+}
+
+impl ListServerStatus {
+    /// Create the state of the client registering with a listserver
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        Self { address: (host.into(), port), ..Default::default() }
+    }
+
+    /// Get the host and port of the listserver
+    pub fn address(&self) -> (&str, u16) {
+        (&self.address.0, self.address.1)
+    }
+
+    /// Check if the listserver client is registered and connected
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    fn set_connected(&self, connected: bool) {
+        self.connected.store(connected, Ordering::Relaxed);
+        if !connected {
+            let mut list = self.players.lock();
+            list.synced = false;
+            list.changes.clear();
+        }
+    }
+
+    /// Get the failed registrations
+    pub fn latency(&self) -> &ListServerLatency {
+        &self.latency
+    }
+
+    /// Take the failed registrations along with the connection state
+    pub fn latency_report(&self) -> LatencyReport {
+        self.latency.report(self.is_connected())
+    }
+
+    /// Get the listing extras
+    pub fn listing(&self) -> Listing {
+        self.listing.lock().clone()
+    }
+
+    /// Change the listing extras, updating the listserver if they differ
+    ///
+    /// # Returns
+    /// `true` if anything changed
+    pub fn set_listing(&self, listing: Listing) -> bool {
+        let mut current = self.listing.lock();
+        if *current == listing {
+            return false;
+        }
+        *current = listing;
+        self.listing_changed.notify_one();
+        true
+    }
+
+    /// Queue a login for the listserver to verify
+    ///
+    /// The request waits here while the listserver is unreachable.
+    pub fn request_verification(&self, player: PlayerID, account: &str) {
+        self.verifications.lock().push((player, account.to_string()));
+        self.verification_requested.notify_one();
+    }
+
+    /// Take the logins waiting to be verified
+    fn take_verifications(&self) -> Vec<(PlayerID, String)> {
+        std::mem::take(&mut *self.verifications.lock())
+    }
+
+    /// Ask the listserver a player's text request
+    ///
+    /// Unlike verifications, requests aren't kept for an outage.
+    ///
+    /// # Returns
+    /// `false` if the listserver isn't connected
+    pub fn request_for_player(&self, player: PlayerID, text: &str) -> bool {
+        if !self.is_connected() {
+            return false;
+        }
+        self.player_requests.lock().push((player, text.to_string()));
+        self.player_request_queued.notify_one();
+        true
+    }
+
+    /// Take the player text requests waiting to be asked
+    fn take_player_requests(&self) -> Vec<(PlayerID, String)> {
+        std::mem::take(&mut *self.player_requests.lock())
+    }
+
+    /// List a player that logged in, replacing its entry if it was listed
+    pub fn add_player(&self, id: PlayerID, player: ListedPlayer) {
+        let mut list = self.players.lock();
+        list.players.retain(|(listed, _)| *listed != id);
+        list.players.push((id, player.clone()));
+        if list.synced {
+            list.changes.push(PlayerChange::Added(id, player));
+            self.players_changed.notify_one();
+        }
+    }
+
+    /// Unlist a player that logged out
+    pub fn remove_player(&self, id: PlayerID) {
+        let mut list = self.players.lock();
+        let count = list.players.len();
+        list.players.retain(|(listed, _)| *listed != id);
+        if list.synced && list.players.len() != count {
+            list.changes.push(PlayerChange::Removed(id));
+            self.players_changed.notify_one();
+        }
+    }
+
+    /// Get the listed players, in login order
+    pub fn players(&self) -> Vec<(PlayerID, ListedPlayer)> {
+        self.players.lock().players.clone()
+    }
+
+    /// Take the full list for a new registration; changes are queued from now on
+    fn sync_players(&self) -> Vec<(PlayerID, ListedPlayer)> {
+        let mut list = self.players.lock();
+        list.synced = true;
+        list.changes.clear();
+        list.players.clone()
+    }
+
+    /// Take the logins and logouts waiting to be sent
+    fn take_player_changes(&self) -> Vec<PlayerChange> {
+        std::mem::take(&mut self.players.lock().changes)
+    }
+}
+
 /// ListServer client state
 pub struct ListServerClient {
     /// Configuration
@@ -125,6 +434,18 @@ pub struct ListServerClient {
 
     /// Count of rapid disconnections (connection closed within 5 seconds)
     rapid_disconnection_count: u32,
+
+    /// IRC bridge for `GraalEngine,irc` messages
+    irc: Option<Arc<IrcBridge>>,
+
+    /// Listing extras sent after registration
+    listing: Listing,
+
+    /// Handlers of SVI_REQUESTTEXT commands
+    requests: Arc<RequestTextRegistry>,
+
+    /// Shared state the pings and failed registrations are recorded in
+    status: Arc<ListServerStatus>,
 }
 
 impl ListServerClient {
@@ -142,9 +463,100 @@ impl ListServerClient {
             last_timer: None,
             last_connect_time: None,
             rapid_disconnection_count: 0,
+            irc: None,
+            listing: Listing::default(),
+            requests: Arc::new(RequestTextRegistry::new(gserver_game::EventBus::new(), gserver_game::CommandQueue::new())),
+            status: Arc::default(),
         }
     }
 
+    /// Record pings and failed registrations in this state
+    pub fn with_status(mut self, status: Arc<ListServerStatus>) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Relay `GraalEngine,irc` messages to and from an IRC bridge
+    pub fn with_irc(mut self, irc: Arc<IrcBridge>) -> Self {
+        self.irc = Some(irc);
+        self
+    }
+
+    /// Handle SVI_REQUESTTEXT with these commands instead of the built-ins
+    /// without a game to reach
+    pub fn with_requests(mut self, requests: Arc<RequestTextRegistry>) -> Self {
+        self.requests = requests;
+        self
+    }
+
+    /// Wait until the listserver socket has data to read
+    ///
+    /// Doesn't consume anything, so it can be raced against outbound lines
+    /// without losing data.
+    async fn readable(&self) -> Result<()> {
+        let socket = self.socket.as_ref().ok_or_else(|| {
+            GServerError::Network("No socket".to_string())
+        })?;
+        socket.readable().await.map_err(|e| GServerError::Network(format!("Read error: {}", e)))
+    }
+
+    /// Send the listing extras (SVO_SENDTEXT)
+    async fn send_listing(&mut self) -> Result<()> {
+        for line in self.listing.settings_lines() {
+            self.send_text(&line).await?;
+        }
+        debug!("Sent listing extras to listserver: {:?}", self.listing);
+        Ok(())
+    }
+
+    /// Replace the listing extras and send them if connected
+    pub async fn update_listing(&mut self, listing: Listing) -> Result<()> {
+        self.listing = listing;
+        if !self.connected {
+            return Ok(());
+        }
+        self.send_listing().await?;
+        self.flush_packets().await
+    }
+
+    /// Ask the listserver to verify logins (SVO_SENDTEXT)
+    ///
+    /// The answers come back as `Listserver,TClientLogin` text requests.
+    async fn send_verifications(&mut self, logins: &[(PlayerID, String)]) -> Result<()> {
+        for (player, account) in logins {
+            let player = player.get().to_string();
+            self.send_text(&join_tokens(&["Listserver", "TClientLogin", account, &player])).await?;
+        }
+        if !logins.is_empty() {
+            debug!("Sent {} login verification requests to listserver", logins.len());
+            self.flush_packets().await?;
+        }
+        Ok(())
+    }
+
+    /// Ask text requests on behalf of players (SVO_REQUESTLIST)
+    ///
+    /// The answers come back as text requests for the same players.
+    async fn send_player_requests(&mut self, requests: &[(PlayerID, String)]) -> Result<()> {
+        for (player, text) in requests {
+            let mut packet = BytesMut::new();
+            packet.put_u8(26 + 32); // SVO_REQUESTLIST encoded (26 + 32 = 58)
+            gserver_protocol::codecs::write_gshort(&mut packet, player.get() as i16);
+            packet.put_slice(text.as_bytes());
+            self.send_packet(&packet).await?;
+        }
+        if !requests.is_empty() {
+            self.flush_packets().await?;
+        }
+        Ok(())
+    }
+
+    /// Send a line queued by the IRC bridge (SVO_SENDTEXT)
+    async fn send_irc(&mut self, line: &str) -> Result<()> {
+        self.send_text(line).await?;
+        self.flush_packets().await
+    }
+
     /// Check if connected to listserver
     pub fn is_connected(&self) -> bool {
         self.connected
@@ -184,6 +596,7 @@ impl ListServerClient {
             }
             Err(e) => {
                 error!("Failed to connect to listserver: {}", e);
+                self.status.latency().registration_failed(format!("connect failed: {}", e));
 
                 // Exponential backoff
                 if self.connection_attempts < 8 {
@@ -284,6 +697,7 @@ impl ListServerClient {
 
         // Send version configuration
         self.send_version_config().await?;
+        self.send_listing().await?;
 
         // Send initial player list (clear + add players)
         self.send_players().await?;
@@ -318,20 +732,30 @@ impl ListServerClient {
         Ok(())
     }
 
-    /// Send player list to listserver
+    /// Send the full player list to the listserver (SVO_SETPLYR)
     async fn send_players(&mut self) -> Result<()> {
         if !self.connected {
             return Ok(());
         }
 
-        // Send SVO_SETPLYR to clear the player list
-        let mut packet = Vec::new();
-        packet.push(0x07 + 32); // SVO_SETPLYR encoded (7 + 32 = 39)
-        self.send_packet(&packet).await?;
+        let players = self.status.sync_players();
+        self.send_packet(&set_players_packet(&players)).await?;
+        debug!("Sent player list to listserver ({} players)", players.len());
+        Ok(())
+    }
 
-        // TODO: Add actual players when player system is implemented
-        // For now, just send the clear packet (no players online yet)
-        debug!("Sent player list update to listserver (0 players)");
+    /// Send logins and logouts since the full list (SVO_PLYRADD, SVO_PLYRREM)
+    async fn send_player_changes(&mut self, changes: &[PlayerChange]) -> Result<()> {
+        for change in changes {
+            let packet = match change {
+                PlayerChange::Added(id, player) => player.add_packet(*id),
+                PlayerChange::Removed(id) => remove_packet(*id),
+            };
+            self.send_packet(&packet).await?;
+        }
+        if !changes.is_empty() {
+            self.flush_packets().await?;
+        }
         Ok(())
     }
 
@@ -503,6 +927,8 @@ impl ListServerClient {
                 if connection_duration < Duration::from_secs(5) {
                     self.rapid_disconnection_count += 1;
                     info!("Rapid disconnection detected (count: {})", self.rapid_disconnection_count);
+                    self.status.latency().registration_failed(
+                        format!("closed after {:.1}s", connection_duration.as_secs_f64()));
                 } else {
                     // Connection lasted a while, reset the counter
                     self.rapid_disconnection_count = 0;
@@ -625,6 +1051,8 @@ impl ListServerClient {
 
     /// SVI_PING - Respond to ping with pong
     async fn handle_ping(&mut self, _data: &[u8]) -> Result<()> {
+        self.status.latency().ping_received();
+
         // Send PONG (same packet), encoded with GChar
         let packet = vec![0x10 + 32]; // SVO_PING (16) encoded
         self.send_packet(&packet).await?;
@@ -694,21 +1122,15 @@ impl ListServerClient {
 
         info!("SVI_REQUESTTEXT: player_id={}, message={}", player_id, msg);
 
-        // Parse the message - format is "Listserver,command,options"
-        let parts: Vec<&str> = msg.split(',').collect();
-
-        if parts.len() >= 1 && parts[0] == "Listserver" {
-            if parts.len() >= 2 {
-                match parts[1] {
-                    "SetRemote" => {
-                        info!("Listserver confirmed: Remote mode enabled");
-                        // Acknowledge by doing nothing - the connection staying open is our acknowledgment
-                    }
-                    _ => {
-                        debug!("Unhandled listserver command: {}", parts[1]);
-                    }
+        // Format is "Service,command,options", routed by service and command
+        let request = TextRequest::new(PlayerID::new(player_id as u16), msg);
+        match self.requests.dispatch(&request) {
+            Some(replies) => {
+                for reply in replies {
+                    self.send_text(&reply).await?;
                 }
             }
+            None => debug!("Unhandled listserver request: {}", msg),
         }
 
         Ok(())
@@ -721,9 +1143,8 @@ impl ListServerClient {
 
         info!("SVI_SENDTEXT: {}", msg);
 
-        // Parse comma-separated message (but need to handle quoted strings)
-        // The C++ code uses gCommaStrTokens() which handles tokenization properly
-        // For now, use simple split and improve if needed
+        // Parse comma-separated message (Listserver lines are still split
+        // naively, IRC lines use the quoting-aware gCommaStrTokens rules)
         let parts: Vec<&str> = msg.split(',').collect();
 
         if parts.len() >= 1 {
@@ -731,9 +1152,11 @@ impl ListServerClient {
                 "GraalEngine" => {
                     // Handle IRC-style messages from listserver
                     if parts.len() >= 2 && parts[1] == "irc" {
-                        // IRC protocol message
-                        debug!("Listserver IRC message: {:?}", &parts[2..]);
-                        // TODO: Implement IRC handling if needed
+                        let tokens = split_tokens(&msg);
+                        debug!("Listserver IRC message: {:?}", &tokens[2..]);
+                        if let Some(irc) = &self.irc {
+                            irc.handle_listserver(&tokens[2..]);
+                        }
                     }
                 }
                 "Listserver" => {
@@ -825,22 +1248,40 @@ impl ListServerClient {
 }
 
 /// Spawn the listserver client task
+///
+/// # Arguments
+/// * `config` - Listserver configuration
+/// * `irc` - IRC bridge whose lines are relayed to the listserver
+/// * `requests` - Handlers of SVI_REQUESTTEXT commands
+/// * `status` - Updated as the client connects and disconnects
+/// * `heartbeat` - Beaten while the client runs (see [`crate::watchdog`])
 pub fn spawn_listserver_client(
     config: ListServerConfig,
+    irc: Option<Arc<IrcBridge>>,
+    requests: Arc<RequestTextRegistry>,
+    status: Arc<ListServerStatus>,
+    heartbeat: Arc<Heartbeat>,
 ) -> tokio::task::JoinHandle<()> {
-    tokio::spawn(async move {
-        let mut client = ListServerClient::new(config);
+    crate::tasks::spawn_named("listserver client", async move {
+        let mut outbound = irc.as_ref().and_then(|irc| irc.take_outbound());
+        let mut client = ListServerClient::new(config).with_requests(requests).with_status(status.clone());
+        if let Some(irc) = irc {
+            client = client.with_irc(irc);
+        }
 
+        let mut beat = tokio::time::interval(HEARTBEAT_INTERVAL);
         loop {
             // Try to connect
+            heartbeat.beat();
+            client.listing = status.listing();
             if let Err(e) = client.connect().await {
                 error!("Connection failed: {:?}", e);
                 // Wait before retrying
                 if let Some(next_attempt) = client.next_connection_attempt {
                     let delay = next_attempt.saturating_duration_since(Instant::now());
-                    sleep(delay).await;
+                    watchdog::sleep(&heartbeat, delay).await;
                 } else {
-                    sleep(Duration::from_secs(5)).await;
+                    watchdog::sleep(&heartbeat, Duration::from_secs(5)).await;
                 }
                 continue;
             }
@@ -849,9 +1290,60 @@ pub fn spawn_listserver_client(
             // This will block until the connection closes
             // The connection should stay persistent indefinitely
             info!("Listserver connection established, waiting for packets...");
+            status.set_connected(true);
+
+            // Logins let in during an outage are verified now
+            if let Err(e) = client.send_verifications(&status.take_verifications()).await {
+                warn!("Failed to send login verifications: {:?}", e);
+            }
+
+            if let Some(irc) = client.irc.clone() {
+                for line in irc.rejoin_lines() {
+                    if let Err(e) = client.send_irc(&line).await {
+                        warn!("Failed to rejoin IRC channel: {:?}", e);
+                    }
+                }
+            }
+
+            enum Wake {
+                Readable(Result<()>),
+                Irc(Option<String>),
+                Listing,
+                Verify,
+                PlayerRequests,
+                Players,
+                Beat,
+            }
 
             loop {
-                match client.process().await {
+                let wake = tokio::select! {
+                    readable = client.readable() => Wake::Readable(readable),
+                    line = next_irc_line(&mut outbound) => Wake::Irc(line),
+                    _ = status.listing_changed.notified() => Wake::Listing,
+                    _ = status.verification_requested.notified() => Wake::Verify,
+                    _ = status.player_request_queued.notified() => Wake::PlayerRequests,
+                    _ = status.players_changed.notified() => Wake::Players,
+                    _ = beat.tick() => Wake::Beat,
+                };
+                let result = match wake {
+                    Wake::Readable(Ok(())) => client.process().await,
+                    Wake::Readable(Err(e)) => Err(e),
+                    Wake::Irc(Some(line)) => client.send_irc(&line).await.map(|()| true),
+                    Wake::Listing => client.update_listing(status.listing()).await.map(|()| true),
+                    Wake::Verify => client.send_verifications(&status.take_verifications()).await.map(|()| true),
+                    Wake::PlayerRequests => client.send_player_requests(&status.take_player_requests()).await.map(|()| true),
+                    Wake::Players => client.send_player_changes(&status.take_player_changes()).await.map(|()| true),
+                    Wake::Beat => {
+                        heartbeat.beat();
+                        continue;
+                    }
+                    Wake::Irc(None) => {
+                        // Bridge dropped, nothing more will be queued
+                        outbound = None;
+                        continue;
+                    }
+                };
+                match result {
                     Ok(true) => {
                         // Connection still open, continue processing
                         continue;
@@ -869,6 +1361,7 @@ pub fn spawn_listserver_client(
             }
 
             // Connection closed, wait before reconnecting
+            status.set_connected(false);
             info!("Connection closed loop: checking backoff...");
             // Check if there's a backoff timer set
             if let Some(next_attempt) = client.next_connection_attempt {
@@ -876,15 +1369,100 @@ pub fn spawn_listserver_client(
                 info!("Backoff timer set: delay={:?}", delay);
                 if delay > Duration::ZERO {
                     info!("Waiting {:?} before reconnecting (backoff)", delay);
-                    sleep(delay).await;
+                    watchdog::sleep(&heartbeat, delay).await;
                 } else {
                     info!("Backoff delay is zero, reconnecting immediately");
                 }
             } else {
                 // No backoff set, wait a bit before reconnecting
                 info!("No backoff timer set, waiting 5 seconds before reconnecting");
-                sleep(Duration::from_secs(5)).await;
+                watchdog::sleep(&heartbeat, Duration::from_secs(5)).await;
             }
         }
     })
 }
+
+/// Wait for the next line queued by the IRC bridge (forever without one)
+async fn next_irc_line(outbound: &mut Option<mpsc::UnboundedReceiver<String>>) -> Option<String> {
+    match outbound {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listing_updates() {
+        let status = ListServerStatus::default();
+        assert_eq!(status.listing().settings_lines(), [
+            "Listserver,settings,icon,\"\"",
+            "Listserver,settings,banner,\"\"",
+            "Listserver,settings,tags",
+        ]);
+
+        let listing = Listing {
+            icon: "https://example.com/icon.png".into(),
+            banner: String::new(),
+            tags: vec!["pvp".into(), "role play".into()],
+        };
+        assert!(status.set_listing(listing.clone()));
+        assert!(!status.set_listing(listing));
+        assert_eq!(status.listing().settings_lines(), [
+            "Listserver,settings,icon,https://example.com/icon.png",
+            "Listserver,settings,banner,\"\"",
+            "Listserver,settings,tags,pvp,\"role play\"",
+        ]);
+    }
+
+    #[test]
+    fn test_player_requests_need_connection() {
+        let status = ListServerStatus::default();
+        let player = PlayerID::new(2);
+        assert!(!status.request_for_player(player, "GraalEngine,pmservers"));
+        status.set_connected(true);
+        assert!(status.request_for_player(player, "GraalEngine,pmservers"));
+        assert_eq!(status.take_player_requests(), [(player, "GraalEngine,pmservers".to_string())]);
+    }
+
+    #[test]
+    fn test_player_list_sync() {
+        let status = ListServerStatus::default();
+        let bob = ListedPlayer { account: "Bob".into(), nick: "bob".into(), level: "start.nw".into(), rc: false };
+        let alice = ListedPlayer { account: "Alice".into(), rc: true, ..bob.clone() };
+
+        // Before registration only the list changes, the full list covers it
+        status.add_player(PlayerID::new(1), bob.clone());
+        assert!(status.take_player_changes().is_empty());
+        let players = status.sync_players();
+        let mut props = b"B#Bob ".to_vec();
+        props.extend_from_slice(b"#bob4(start.nw");
+        // [SETPLYR][count 1][len][props]
+        let mut expected = vec![39, 33, props.len() as u8 + 32];
+        expected.extend_from_slice(&props);
+        assert_eq!(set_players_packet(&players), expected);
+
+        status.add_player(PlayerID::new(2), alice.clone());
+        status.remove_player(PlayerID::new(1));
+        status.remove_player(PlayerID::new(9));
+        let changes = status.take_player_changes();
+        assert_eq!(changes, [PlayerChange::Added(PlayerID::new(2), alice.clone()), PlayerChange::Removed(PlayerID::new(1))]);
+        assert_eq!(status.players(), [(PlayerID::new(2), alice.clone())]);
+
+        let mut id = BytesMut::new();
+        gserver_protocol::codecs::write_gshort(&mut id, 2);
+        let add = alice.add_packet(PlayerID::new(2));
+        assert_eq!(add[0], 37);
+        assert_eq!(&add[1..3], &id[..]);
+        assert_eq!(add[3], 33);
+        assert_eq!(remove_packet(PlayerID::new(2))[0], 38);
+
+        // A reconnect needs the full list again
+        status.set_connected(false);
+        status.add_player(PlayerID::new(3), bob);
+        assert!(status.take_player_changes().is_empty());
+        assert_eq!(status.sync_players().len(), 2);
+    }
+}
@@ -0,0 +1,476 @@
+//! # Logging
+//!
+//! Installs the tracing subscriber from the `log*` server options:
+//!
+//! - A default level plus per-module levels (`logfilters = network=debug`).
+//!   Module names are the crate names without the `gserver_` prefix; a path
+//!   inside a crate (`network::connection`) narrows the filter.
+//! - Output to stdout and, unless `logfile = false`, to `logs/` in the server
+//!   folder, one file per day or hour (`logrotation`) of which the newest
+//!   `logretention` are kept.
+//! - Text or, with `logjson = true`, one JSON object per line.
+//!
+//! Levels can be changed while the server runs with [`set_level`] (the RC
+//! command `/loglevel network debug`).
+//!
+//! With the `console` feature the tokio-console layer is installed too,
+//! beside the filtered outputs so the levels don't hide runtime events from
+//! it (see [`crate::tasks`]).
+
+use gserver_config::{LogRotation, LoggingConfig};
+use gserver_core::{GServerError, Result};
+use parking_lot::Mutex;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, MakeWriter};
+use tracing_subscriber::layer::{Layered, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Layer, Registry};
+
+/// Workspace crates a module name may refer to
+const CRATES: &[&str] = &[
+    "core", "protocol", "levels", "network", "accounts", "scripting", "game", "config", "resources", "storage",
+];
+
+/// Accepted level names
+const LEVELS: &[&str] = &["trace", "debug", "info", "warn", "error", "off"];
+
+/// The tokio-console layer, `None` without the `console` feature
+type ConsoleLayer = Option<Box<dyn Layer<Registry> + Send + Sync>>;
+
+/// Subscriber the filtered outputs are stacked on
+type Base = Layered<ConsoleLayer, Registry>;
+
+/// The installed filter, kept so RC can change it
+static RELOAD: OnceLock<(reload::Handle<EnvFilter, Base>, Mutex<LogFilter>)> = OnceLock::new();
+
+/// Default level and per-module overrides
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFilter {
+    default: String,
+    /// Tracing target → level
+    targets: BTreeMap<String, String>,
+}
+
+impl LogFilter {
+    /// Build the filter from the server options
+    ///
+    /// # Returns
+    /// The filter and the options that were ignored (unknown levels)
+    pub fn from_config(config: &LoggingConfig) -> (Self, Vec<String>) {
+        let mut ignored = Vec::new();
+        let mut filter = Self { default: "info".into(), targets: BTreeMap::new() };
+        if let Err(e) = filter.set(None, &config.level) {
+            ignored.push(e.to_string());
+        }
+        for (module, level) in &config.filters {
+            if let Err(e) = filter.set(Some(module), level) {
+                ignored.push(e.to_string());
+            }
+        }
+        (filter, ignored)
+    }
+
+    /// Change the default level, or one module's level
+    ///
+    /// # Arguments
+    /// * `module` - Module name (`network`, `network::connection`), `None` for the default
+    /// * `level` - Level name, or `default` to drop a module's override
+    pub fn set(&mut self, module: Option<&str>, level: &str) -> Result<()> {
+        let level = level.to_lowercase();
+        match module {
+            Some(module) if level == "default" => {
+                self.targets.remove(&target_for(module));
+            }
+            _ if !LEVELS.contains(&level.as_str()) => {
+                return Err(GServerError::Config(format!("Unknown log level '{}' (use {})", level, LEVELS.join(", "))));
+            }
+            Some(module) => {
+                self.targets.insert(target_for(module), level);
+            }
+            None => self.default = level,
+        }
+        Ok(())
+    }
+
+    /// `EnvFilter` directives, e.g. `info,gserver_network=debug`
+    pub fn directives(&self) -> String {
+        std::iter::once(self.default.clone())
+            .chain(self.targets.iter().map(|(target, level)| format!("{}={}", target, level)))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    fn env_filter(&self) -> EnvFilter {
+        EnvFilter::new(self.directives())
+    }
+}
+
+impl fmt::Display for LogFilter {
+    /// `info, network=debug` with the crate prefix stripped again
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.default)?;
+        for (target, level) in &self.targets {
+            write!(f, ", {}={}", target.strip_prefix("gserver_").unwrap_or(target), level)?;
+        }
+        Ok(())
+    }
+}
+
+/// Map a module name to its tracing target (`network` → `gserver_network`)
+fn target_for(module: &str) -> String {
+    let module = module.trim().replace('-', "_");
+    let name = module.split("::").next().unwrap_or_default();
+    if CRATES.contains(&name) {
+        format!("gserver_{}", module)
+    } else {
+        module
+    }
+}
+
+/// Install the global subscriber
+///
+/// # Arguments
+/// * `config` - Logging options
+/// * `server_dir` - Server folder; log files go to its `logs/` folder
+pub fn init(config: &LoggingConfig, server_dir: impl AsRef<Path>) -> Result<()> {
+    let (filter, ignored) = LogFilter::from_config(config);
+    let (filter_layer, handle) = reload::Layer::new(filter.env_filter());
+
+    let file = match config.file {
+        true => Some(RollingFile::new(server_dir.as_ref().join("logs"), config.rotation, config.retention)?),
+        false => None,
+    };
+    let stdout = output(config.json, io::stdout, true);
+    let file = file.map(|file| output(config.json, file, false));
+
+    tracing_subscriber::registry()
+        .with(console_layer())
+        .with(stdout.and_then(file).with_filter(filter_layer))
+        .try_init()
+        .map_err(|e| GServerError::Config(format!("Logging already initialized: {}", e)))?;
+    let _ = RELOAD.set((handle, Mutex::new(filter)));
+
+    for message in ignored {
+        tracing::warn!("Ignored logging option: {}", message);
+    }
+    Ok(())
+}
+
+/// Build the tokio-console layer
+#[cfg(feature = "console")]
+fn console_layer() -> ConsoleLayer {
+    Some(console_subscriber::ConsoleLayer::builder().with_default_env().spawn().boxed())
+}
+
+/// Build the tokio-console layer
+#[cfg(not(feature = "console"))]
+fn console_layer() -> ConsoleLayer {
+    None
+}
+
+/// Text or JSON output to one writer
+fn output<S, W>(json: bool, writer: W, ansi: bool) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer().with_writer(writer).with_ansi(ansi);
+    if json {
+        layer.event_format(JsonFormat).boxed()
+    } else {
+        layer.boxed()
+    }
+}
+
+/// Change a level at runtime
+///
+/// # Arguments
+/// * `module` - Module name, `None` for the default level
+/// * `level` - Level name, or `default` to drop a module's override
+///
+/// # Returns
+/// The filter now in effect
+pub fn set_level(module: Option<&str>, level: &str) -> Result<String> {
+    update(|filter| filter.set(module, level))
+}
+
+/// Replace all levels with the ones from reloaded options
+///
+/// Log files and format stay as they were at startup.
+///
+/// # Returns
+/// The filter now in effect and the options that were ignored
+pub fn reconfigure(config: &LoggingConfig) -> Result<(String, Vec<String>)> {
+    let (replacement, ignored) = LogFilter::from_config(config);
+    let filter = update(|filter| {
+        *filter = replacement;
+        Ok(())
+    })?;
+    Ok((filter, ignored))
+}
+
+/// Apply a change to the installed filter
+fn update(change: impl FnOnce(&mut LogFilter) -> Result<()>) -> Result<String> {
+    let (handle, filter) = RELOAD.get()
+        .ok_or_else(|| GServerError::Config("Logging is not initialized".to_string()))?;
+    let mut filter = filter.lock();
+    let mut updated = filter.clone();
+    change(&mut updated)?;
+    handle.reload(updated.env_filter())
+        .map_err(|e| GServerError::Config(format!("Log filter not changed: {}", e)))?;
+    *filter = updated;
+    Ok(filter.to_string())
+}
+
+/// The filter in effect, `None` before [`init`]
+pub fn current_filter() -> Option<String> {
+    RELOAD.get().map(|(_, filter)| filter.lock().to_string())
+}
+
+/// Log files in one folder, rotated by period
+///
+/// Files are named `gserver-YYYYMMDD.log` (daily), `gserver-YYYYMMDD-HH.log`
+/// (hourly) or `gserver.log` (never) after the UTC time of their first line.
+#[derive(Debug)]
+pub struct RollingFile {
+    dir: PathBuf,
+    rotation: LogRotation,
+    /// Files to keep, 0 = all
+    retention: usize,
+    /// Name and handle of the open file
+    current: Mutex<Option<(String, File)>>,
+}
+
+impl RollingFile {
+    /// Create the folder; the first file is opened by the first write
+    pub fn new(dir: impl Into<PathBuf>, rotation: LogRotation, retention: usize) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir, rotation, retention, current: Mutex::new(None) })
+    }
+
+    /// Name of the file for a time
+    ///
+    /// # Arguments
+    /// * `secs` - Unix seconds
+    fn file_name(&self, secs: u64) -> String {
+        let stamp = gserver_storage::format_timestamp(secs);
+        match self.rotation {
+            LogRotation::Daily => format!("gserver-{}.log", &stamp[..8]),
+            LogRotation::Hourly => format!("gserver-{}.log", &stamp[..11]),
+            LogRotation::Never => "gserver.log".to_string(),
+        }
+    }
+
+    /// Write to the file for `secs`, rotating first if the period changed
+    fn write_at(&self, buf: &[u8], secs: u64) -> io::Result<usize> {
+        let name = self.file_name(secs);
+        let mut current = self.current.lock();
+        let file = match current.as_mut() {
+            Some((open, file)) if *open == name => file,
+            _ => {
+                let file = OpenOptions::new().create(true).append(true).open(self.dir.join(&name))?;
+                self.prune(&name);
+                &mut current.insert((name, file)).1
+            }
+        };
+        file.write(buf)
+    }
+
+    /// Delete the oldest rotated files beyond the retention
+    fn prune(&self, keep: &str) {
+        if self.retention == 0 || self.rotation == LogRotation::Never {
+            return;
+        }
+        let Ok(entries) = fs::read_dir(&self.dir) else { return };
+        let mut files: Vec<String> = entries
+            .filter_map(|e| e.ok()?.file_name().into_string().ok())
+            .filter(|name| name.starts_with("gserver-") && name.ends_with(".log"))
+            .filter(|name| name.as_str() != keep)
+            .collect();
+        // The stamps sort by time; the new file counts towards the retention
+        files.sort();
+        let excess = (files.len() + 1).saturating_sub(self.retention);
+        for name in files.into_iter().take(excess) {
+            let _ = fs::remove_file(self.dir.join(name));
+        }
+    }
+}
+
+impl Write for &RollingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_at(buf, now_secs())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.current.lock().as_mut() {
+            Some((_, file)) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> MakeWriter<'a> for RollingFile {
+    type Writer = &'a RollingFile;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self
+    }
+}
+
+/// One JSON object per event
+///
+/// ```text
+/// {"timestamp":"2026-10-14T09:30:00.125Z","level":"INFO","target":"gserver_network::server",
+///  "message":"...","fields":{"id":3},"spans":["connection"]}
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonFormat;
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        let mut fields = JsonFields::default();
+        event.record(&mut fields);
+        let message = fields.0.remove("message").unwrap_or(Value::Null);
+        let spans: Vec<&str> = ctx.event_scope()
+            .map(|scope| scope.from_root().map(|span| span.name()).collect())
+            .unwrap_or_default();
+
+        let metadata = event.metadata();
+        let line = json!({
+            "timestamp": rfc3339_now(),
+            "level": metadata.level().as_str(),
+            "target": metadata.target(),
+            "message": message,
+            "fields": fields.0,
+            "spans": spans,
+        });
+        writeln!(writer, "{}", line)
+    }
+}
+
+/// Collects event fields into a JSON object
+#[derive(Default)]
+struct JsonFields(Map<String, Value>);
+
+impl Visit for JsonFields {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().to_string(), format!("{:?}", value).into());
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// The current UTC time as `YYYY-MM-DDTHH:MM:SS.mmmZ`
+fn rfc3339_now() -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let s = gserver_storage::format_timestamp(now.as_secs());
+    format!("{}-{}-{}T{}:{}:{}.{:03}Z",
+        &s[0..4], &s[4..6], &s[6..8], &s[9..11], &s[11..13], &s[13..15], now.subsec_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_directives() {
+        let config = LoggingConfig {
+            level: "warn".into(),
+            filters: vec![
+                ("network".into(), "debug".into()),
+                ("scripting::vm".into(), "trace".into()),
+                ("hyper".into(), "error".into()),
+                ("game".into(), "loud".into()),
+            ],
+            ..Default::default()
+        };
+        let (mut filter, ignored) = LogFilter::from_config(&config);
+        assert_eq!(ignored.len(), 1);
+        assert_eq!(filter.directives(), "warn,gserver_network=debug,gserver_scripting::vm=trace,hyper=error");
+
+        filter.set(Some("network"), "default").unwrap();
+        filter.set(None, "INFO").unwrap();
+        assert!(filter.set(Some("core"), "verbose").is_err());
+        assert_eq!(filter.to_string(), "info, scripting::vm=trace, hyper=error");
+        assert!(EnvFilter::try_new(filter.directives()).is_ok());
+    }
+
+    #[test]
+    fn test_rolling_file_rotates_and_prunes() {
+        let dir = tempfile::tempdir().unwrap();
+        let daily = RollingFile::new(dir.path(), LogRotation::Daily, 2).unwrap();
+        assert_eq!(daily.file_name(1_760_400_000), "gserver-20251014.log");
+
+        // Three days, retention 2: the first day's file goes
+        for day in 0..3 {
+            daily.write_at(format!("day {}\n", day).as_bytes(), 1_760_400_000 + day * 86_400).unwrap();
+        }
+        daily.write_at(b"still day 2\n", 1_760_400_000 + 2 * 86_400 + 60).unwrap();
+        let mut names: Vec<String> = fs::read_dir(dir.path()).unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["gserver-20251015.log", "gserver-20251016.log"]);
+        assert_eq!(fs::read_to_string(dir.path().join("gserver-20251016.log")).unwrap(), "day 2\nstill day 2\n");
+
+        let hourly = RollingFile::new(dir.path(), LogRotation::Hourly, 0).unwrap();
+        assert_eq!(hourly.file_name(1_760_400_000 + 3_600), "gserver-20251014-01.log");
+        let never = RollingFile::new(dir.path(), LogRotation::Never, 2).unwrap();
+        assert_eq!(never.file_name(1_760_400_000), "gserver.log");
+    }
+
+    #[test]
+    fn test_json_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = RollingFile::new(dir.path(), LogRotation::Never, 0).unwrap();
+        let subscriber = tracing_subscriber::fmt().event_format(JsonFormat).with_writer(file).finish();
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("connection", id = 3);
+            let _entered = span.enter();
+            tracing::warn!(bytes = 512, slow = true, "Queue backing up for {}", "Alice");
+        });
+
+        let text = fs::read_to_string(dir.path().join("gserver.log")).unwrap();
+        let line: Value = serde_json::from_str(text.trim()).unwrap();
+        assert_eq!(line["level"], "WARN");
+        assert_eq!(line["target"], "gserver_network::logging::tests");
+        assert_eq!(line["message"], "Queue backing up for Alice");
+        assert_eq!(line["fields"], json!({ "bytes": 512, "slow": true }));
+        assert_eq!(line["spans"], json!(["connection"]));
+        assert!(line["timestamp"].as_str().unwrap().ends_with('Z'));
+    }
+}
@@ -0,0 +1,334 @@
+//! # Login Policies
+//!
+//! Once a login's credentials check out (and the account isn't banned), the
+//! registered [`LoginPolicy`]s look at what's unusual about it and may let it
+//! in, hold it for staff approval, or refuse it:
+//!
+//! - New IP - The account has logged in before, but never from this IP
+//! - Impossible travel - The last login came from another country less than
+//!   [`TRAVEL_WINDOW`] seconds ago
+//! - Accounts on the IP - How many other accounts logged in from this IP
+//! - Banned hardware - A hardware id of the login identity is in `bannedhardware`
+//!
+//! The signals come from the IP history ([`crate::alts`]) and GeoIP. The
+//! strictest verdict wins. [`BannedHardwarePolicy`] is always registered;
+//! without other policies every other login is let in.
+//!
+//! A login held for approval waits up to `loginapprovaltimeout` seconds while
+//! RCs are asked in staff chat to `/approve <account>` or `/deny <account>`.
+
+use crate::alts::IpHistory;
+use parking_lot::{Mutex, RwLock};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use tokio::sync::oneshot;
+
+/// Seconds within which a login from another country counts as impossible travel
+pub const TRAVEL_WINDOW: u64 = 2 * 3600;
+
+/// A change of country between two logins
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Travel {
+    /// Country of the previous login
+    pub from: String,
+    /// Country of this login
+    pub to: String,
+    /// Seconds since the previous login
+    pub seconds: u64,
+}
+
+/// What's known about a login when the policies are asked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginSignals {
+    /// Account name
+    pub account: String,
+    /// Client IP
+    pub ip: IpAddr,
+    /// Identity string of the login packet
+    pub identity: String,
+    /// Logging in as RC
+    pub rc: bool,
+    /// The account is staff
+    pub staff: bool,
+    /// The account logged in before, but not from this IP
+    pub new_ip: bool,
+    /// The last login came from another country within [`TRAVEL_WINDOW`]
+    pub impossible_travel: Option<Travel>,
+    /// Other accounts that logged in from this IP
+    pub accounts_on_ip: usize,
+    /// Hardware id of the identity found in `bannedhardware`
+    pub banned_hardware: Option<String>,
+}
+
+impl LoginSignals {
+    /// Gather the signals of a login from the IP history
+    ///
+    /// Called before the login is recorded in the history.
+    ///
+    /// # Arguments
+    /// * `banned_hardware` - `bannedhardware`, matched against the comma-separated
+    ///   fields of the identity
+    /// * `country_of` - GeoIP lookup
+    pub fn gather(account: &str, ip: IpAddr, identity: &str, history: &IpHistory, banned_hardware: &[String],
+        now: u64, country_of: impl Fn(IpAddr) -> Option<String>) -> Self {
+        let ips = history.ips_of(account);
+        let impossible_travel = ips.iter()
+            .find(|(previous, _)| *previous != ip)
+            .filter(|(_, at)| now.saturating_sub(*at) < TRAVEL_WINDOW)
+            .and_then(|(previous, at)| {
+                let (from, to) = (country_of(*previous)?, country_of(ip)?);
+                (from != to).then(|| Travel { from, to, seconds: now.saturating_sub(*at) })
+            });
+        let banned_hardware = identity.split(',')
+            .map(str::trim)
+            .find(|id| !id.is_empty() && banned_hardware.iter().any(|b| b.eq_ignore_ascii_case(id)))
+            .map(str::to_string);
+        Self {
+            account: account.to_string(),
+            ip,
+            identity: identity.to_string(),
+            rc: false,
+            staff: false,
+            new_ip: !ips.is_empty() && ips.iter().all(|(previous, _)| *previous != ip),
+            impossible_travel,
+            accounts_on_ip: history.accounts_on(ip).iter().filter(|a| !a.eq_ignore_ascii_case(account)).count(),
+            banned_hardware,
+        }
+    }
+
+    /// Describe the raised signals for staff chat
+    pub fn summary(&self) -> String {
+        let mut raised = Vec::new();
+        if self.new_ip {
+            raised.push(format!("new IP {}", self.ip));
+        }
+        if let Some(travel) = &self.impossible_travel {
+            raised.push(format!("{} to {} in {} minutes", travel.from, travel.to, travel.seconds / 60));
+        }
+        if self.accounts_on_ip > 0 {
+            raised.push(format!("{} other accounts on the IP", self.accounts_on_ip));
+        }
+        if let Some(id) = &self.banned_hardware {
+            raised.push(format!("banned hardware {}", id));
+        }
+        if raised.is_empty() {
+            "no signals".to_string()
+        } else {
+            raised.join(", ")
+        }
+    }
+}
+
+/// What happens to a login
+///
+/// Ordered from mildest to strictest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum LoginVerdict {
+    /// Let the player in
+    #[default]
+    Allow,
+    /// Hold the login until an RC approves it
+    RequireApproval,
+    /// Refuse the login
+    Deny,
+}
+
+/// Operator-supplied login policy
+///
+/// # Example
+///
+/// ```rust
+/// use gserver_network::loginpolicy::{LoginPolicy, LoginSignals, LoginVerdict};
+///
+/// /// Have staff look at logins that hop countries
+/// struct HoldTravellers;
+///
+/// impl LoginPolicy for HoldTravellers {
+///     fn name(&self) -> &str {
+///         "hold_travellers"
+///     }
+///
+///     fn evaluate(&self, signals: &LoginSignals) -> Option<LoginVerdict> {
+///         signals.impossible_travel.as_ref().map(|_| LoginVerdict::RequireApproval)
+///     }
+/// }
+/// ```
+pub trait LoginPolicy: Send + Sync {
+    /// Policy name (for logging)
+    fn name(&self) -> &str;
+
+    /// Decide what happens to a login
+    ///
+    /// # Returns
+    /// `None` to leave the decision to other policies
+    fn evaluate(&self, signals: &LoginSignals) -> Option<LoginVerdict>;
+}
+
+/// Refuses identities with a hardware id in `bannedhardware`
+#[derive(Debug, Default)]
+pub struct BannedHardwarePolicy;
+
+impl LoginPolicy for BannedHardwarePolicy {
+    fn name(&self) -> &str {
+        "banned_hardware"
+    }
+
+    fn evaluate(&self, signals: &LoginSignals) -> Option<LoginVerdict> {
+        signals.banned_hardware.as_ref().map(|_| LoginVerdict::Deny)
+    }
+}
+
+/// Registered login policies
+#[derive(Default)]
+pub struct LoginPolicies {
+    policies: RwLock<Vec<Arc<dyn LoginPolicy>>>,
+}
+
+impl LoginPolicies {
+    /// Create an empty policy set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a policy
+    pub fn register(&self, policy: Arc<dyn LoginPolicy>) {
+        tracing::info!("Registered login policy '{}'", policy.name());
+        self.policies.write().push(policy);
+    }
+
+    /// Get the number of registered policies
+    pub fn len(&self) -> usize {
+        self.policies.read().len()
+    }
+
+    /// Check if no policies are registered
+    pub fn is_empty(&self) -> bool {
+        self.policies.read().is_empty()
+    }
+
+    /// Decide what happens to a login
+    ///
+    /// # Returns
+    /// The strictest verdict and the policy that gave it, or
+    /// [`LoginVerdict::Allow`] and `None` if no policy had one
+    pub fn evaluate(&self, signals: &LoginSignals) -> (LoginVerdict, Option<String>) {
+        self.policies.read()
+            .iter()
+            .filter_map(|policy| policy.evaluate(signals).map(|verdict| (verdict, policy.name().to_string())))
+            .max_by_key(|(verdict, _)| *verdict)
+            .map_or((LoginVerdict::Allow, None), |(verdict, name)| (verdict, Some(name)))
+    }
+}
+
+/// Logins waiting for staff approval, by lowercase account
+#[derive(Debug, Default)]
+pub struct LoginApprovals {
+    pending: Mutex<HashMap<String, oneshot::Sender<bool>>>,
+}
+
+impl LoginApprovals {
+    /// Create an empty set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hold a login for approval
+    ///
+    /// A second login of the account replaces the first, whose receiver then
+    /// errors.
+    ///
+    /// # Returns
+    /// `true` once an RC approves, `false` once one denies
+    pub fn request(&self, account: &str) -> oneshot::Receiver<bool> {
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().insert(account.to_lowercase(), tx);
+        rx
+    }
+
+    /// Approve or deny a held login
+    ///
+    /// # Returns
+    /// `false` if no login of the account is waiting
+    pub fn resolve(&self, account: &str, approved: bool) -> bool {
+        match self.pending.lock().remove(&account.to_lowercase()) {
+            Some(tx) => tx.send(approved).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Forget a held login that gave up waiting
+    pub fn cancel(&self, account: &str) {
+        self.pending.lock().remove(&account.to_lowercase());
+    }
+
+    /// Get the accounts whose logins are waiting, sorted
+    pub fn pending(&self) -> Vec<String> {
+        let mut accounts: Vec<String> = self.pending.lock().keys().cloned().collect();
+        accounts.sort();
+        accounts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(last: u8) -> IpAddr {
+        IpAddr::from([10, 0, 0, last])
+    }
+
+    fn country_of(ip: IpAddr) -> Option<String> {
+        Some(if ip == self::ip(1) { "DE" } else { "US" }.to_string())
+    }
+
+    #[test]
+    fn test_gather_signals() {
+        let history = IpHistory::new();
+        let first = LoginSignals::gather("Bob", ip(1), "win,abc", &history, &[], 1_000, country_of);
+        assert!(!first.new_ip && first.impossible_travel.is_none());
+        assert_eq!(first.summary(), "no signals");
+
+        history.record("Bob", ip(1), 1_000, 90);
+        history.record("Alt", ip(2), 1_000, 90);
+        let banned = ["ABC".to_string()];
+        let signals = LoginSignals::gather("Bob", ip(2), "win,abc", &history, &banned, 1_000 + 600, country_of);
+        assert!(signals.new_ip);
+        assert_eq!(signals.impossible_travel, Some(Travel { from: "DE".into(), to: "US".into(), seconds: 600 }));
+        assert_eq!(signals.accounts_on_ip, 1);
+        assert_eq!(signals.summary(), "new IP 10.0.0.2, DE to US in 10 minutes, 1 other accounts on the IP, banned hardware abc");
+
+        let later = LoginSignals::gather("Bob", ip(2), "win", &history, &banned, 1_000 + TRAVEL_WINDOW, country_of);
+        assert!(later.impossible_travel.is_none() && later.banned_hardware.is_none());
+    }
+
+    struct Fixed(Option<LoginVerdict>);
+
+    impl LoginPolicy for Fixed {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        fn evaluate(&self, _signals: &LoginSignals) -> Option<LoginVerdict> {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_policies_and_approvals() {
+        let signals = LoginSignals::gather("Bob", ip(1), "win,abc", &IpHistory::new(), &["abc".into()], 0, country_of);
+        let policies = LoginPolicies::new();
+        assert_eq!(policies.evaluate(&signals), (LoginVerdict::Allow, None));
+
+        policies.register(Arc::new(Fixed(Some(LoginVerdict::RequireApproval))));
+        policies.register(Arc::new(BannedHardwarePolicy));
+        assert_eq!(policies.evaluate(&signals), (LoginVerdict::Deny, Some("banned_hardware".to_string())));
+
+        let approvals = LoginApprovals::new();
+        let mut rx = approvals.request("Bob");
+        assert_eq!(approvals.pending(), ["bob"]);
+        assert!(approvals.resolve("BOB", true));
+        assert_eq!(rx.try_recv(), Ok(true));
+        assert!(!approvals.resolve("bob", false));
+    }
+}
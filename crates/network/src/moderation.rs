@@ -0,0 +1,173 @@
+//! # Moderation
+//!
+//! Carries out the sanctions of [`gserver_game::moderation`]. RC commands
+//! and scripts send [`GameCommand::SanctionRequested`]; the server's command
+//! relay applies it to the account's connections, or to the account file if
+//! nobody is online on it, so the sanction is waiting at the next login.
+//!
+//! Jailing and warping home move the player with PLO_PLAYERWARP, to which
+//! the client answers with a normal level warp. The player is told about
+//! each sanction with an admin message popup.
+
+use gserver_accounts::{Account, AccountLoader};
+use gserver_config::ServerConfig as GameConfig;
+use gserver_core::{GServerError, Result};
+use gserver_game::{CommandQueue, ControlAction, GameCommand, Sanction};
+use gserver_game::moderation::unix_now;
+use gserver_scripting::ModerationHandler;
+use std::path::Path;
+
+/// Where a sanction moves a player
+#[derive(Debug, Clone, PartialEq)]
+pub struct Destination {
+    /// Level name
+    pub level: String,
+    /// X position (tiles)
+    pub x: f32,
+    /// Y position (tiles)
+    pub y: f32,
+}
+
+impl Destination {
+    /// Get the start location of new accounts
+    pub fn home(config: &GameConfig) -> Self {
+        let start = &config.default_account;
+        Self { level: start.level.clone(), x: start.x as f32, y: start.y as f32 }
+    }
+
+    /// Get the jail, if `jaillevel` is set
+    pub fn jail(config: &GameConfig) -> Option<Self> {
+        (!config.jail_level.is_empty()).then(|| Self { level: config.jail_level.clone(), x: 30.0, y: 30.0 })
+    }
+}
+
+/// Apply a sanction to an account
+///
+/// # Returns
+/// Where the player has to be warped, if anywhere
+///
+/// # Errors
+/// Jailing while no `jaillevel` is set
+pub fn apply(account: &mut Account, sanction: Sanction, config: &GameConfig, now: u64) -> Result<Option<Destination>> {
+    let destination = match sanction {
+        Sanction::Mute(minutes) => {
+            account.muted_until = Sanction::expiry(minutes, now);
+            None
+        }
+        Sanction::Unmute => {
+            account.muted_until = 0;
+            None
+        }
+        Sanction::Jail(minutes) => {
+            let jail = Destination::jail(config)
+                .ok_or_else(|| GServerError::InvalidData("No jail level set (jaillevel)".into()))?;
+            account.jailed_until = Sanction::expiry(minutes, now);
+            Some(jail)
+        }
+        Sanction::Unjail => {
+            account.jailed_until = 0;
+            // Released players only leave if they're still in jail
+            Destination::jail(config)
+                .filter(|jail| jail.level.eq_ignore_ascii_case(&account.level))
+                .map(|_| Destination::home(config))
+        }
+        Sanction::WarpHome => {
+            if account.is_jailed_at(now) {
+                return Err(GServerError::InvalidData(format!("{} is in jail", account.name)));
+            }
+            Some(Destination::home(config))
+        }
+    };
+
+    if let Some(to) = &destination {
+        account.level = to.level.clone();
+        account.x = to.x;
+        account.y = to.y;
+    }
+    Ok(destination)
+}
+
+/// Apply a sanction to the file of an account that isn't online
+pub fn edit_account_file(server_dir: &Path, account: &str, sanction: Sanction, config: &GameConfig) -> Result<()> {
+    let loader = AccountLoader::new(server_dir);
+    if !loader.exists(account) {
+        return Err(GServerError::NotFound(format!("account {}", account)));
+    }
+    let mut loaded = loader.load(account)
+        .map_err(|e| GServerError::InvalidData(format!("Failed to load account {}: {}", account, e)))?;
+    apply(&mut loaded, sanction, config, unix_now())?;
+    loader.save(&loaded)
+        .map_err(|e| GServerError::InvalidData(format!("Failed to save account {}: {}", account, e)))
+}
+
+/// Sanctions requested by scripts
+#[derive(Debug)]
+pub struct Moderation {
+    commands: CommandQueue,
+}
+
+impl Moderation {
+    /// Create the service
+    ///
+    /// # Arguments
+    /// * `commands` - Command queue for [`GameCommand::SanctionRequested`]
+    pub fn new(commands: CommandQueue) -> Self {
+        Self { commands }
+    }
+}
+
+impl ModerationHandler for Moderation {
+    fn moderate(&self, account: &str, action: &str, minutes: Option<u64>) -> std::result::Result<(), String> {
+        let account = account.to_string();
+        let command = match action {
+            "freeze" => GameCommand::ControlRequested { account, action: ControlAction::Freeze },
+            "unfreeze" => GameCommand::ControlRequested { account, action: ControlAction::Unfreeze },
+            _ => {
+                let minutes = minutes.map(|m| m.to_string());
+                GameCommand::SanctionRequested { account, sanction: Sanction::parse(action, minutes.as_deref())? }
+            }
+        };
+        self.commands.send(command);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_sanctions() {
+        let mut config = GameConfig::default();
+        let mut account = Account { name: "Bob".into(), level: "town.nw".into(), ..Default::default() };
+        assert!(apply(&mut account, Sanction::Jail(Some(5)), &config, 100).is_err());
+
+        config.jail_level = "jail.nw".into();
+        let to = apply(&mut account, Sanction::Jail(Some(5)), &config, 100).unwrap();
+        assert_eq!(to.map(|d| d.level).as_deref(), Some("jail.nw"));
+        assert!(account.is_jailed_at(399) && !account.is_jailed_at(400));
+        assert!(apply(&mut account, Sanction::WarpHome, &config, 200).is_err());
+
+        let to = apply(&mut account, Sanction::Unjail, &config, 200).unwrap();
+        assert_eq!(to, Some(Destination::home(&config)));
+        assert_eq!(account.level, config.default_account.level);
+        assert_eq!(apply(&mut account, Sanction::Unjail, &config, 200).unwrap(), None);
+
+        apply(&mut account, Sanction::Mute(None), &config, 200).unwrap();
+        assert!(account.is_muted_at(u64::MAX - 1));
+        apply(&mut account, Sanction::Unmute, &config, 200).unwrap();
+        assert!(!account.is_muted_at(0));
+    }
+
+    #[test]
+    fn test_script_sanctions() {
+        let commands = CommandQueue::new();
+        let mut received = commands.take_receiver().unwrap();
+        let moderation = Moderation::new(commands);
+        moderation.moderate("Bob", "mute", Some(3)).unwrap();
+        moderation.moderate("Bob", "freeze", None).unwrap();
+        assert!(moderation.moderate("Bob", "ban", None).is_err());
+        assert_eq!(received.try_recv().unwrap(), GameCommand::SanctionRequested { account: "Bob".into(), sanction: Sanction::Mute(Some(3)) });
+        assert_eq!(received.try_recv().unwrap(), GameCommand::ControlRequested { account: "Bob".into(), action: ControlAction::Freeze });
+    }
+}
@@ -0,0 +1,145 @@
+//! # Server Message (MOTD)
+//!
+//! This module renders `config/servermessage.html` and builds the packet that
+//! shows it to players after login.
+//!
+//! # Template Variables
+//!
+//! - `{playername}` - The player's nickname
+//! - `{online}` - Number of connected players
+//! - `{uptime}` - Time since the server started (e.g. `2d 3h 15m`)
+//!
+//! Unknown `{...}` sequences are left as they are.
+//!
+//! # C++ Equivalence
+//!
+//! The C++ server sends the message unchanged in PLO_STARTMESSAGE from
+//! `PlayerClient::sendLoginClient`. Clients from the newmain generation on
+//! no longer show the start message window, so they get it in PLO_RPGWINDOW.
+
+use gserver_config::ServerGeneration;
+use gserver_protocol::{PacketOut, PacketTypeOut};
+use std::time::Duration;
+
+/// Values substituted into the server message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MotdVars {
+    /// Player nickname
+    pub player_name: String,
+
+    /// Connected players
+    pub online: usize,
+
+    /// Server uptime
+    pub uptime: Duration,
+}
+
+/// Substitute template variables in a server message
+pub fn render(template: &str, vars: &MotdVars) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find('}') else { break };
+        let value = match &rest[1..end] {
+            "playername" => Some(vars.player_name.clone()),
+            "online" => Some(vars.online.to_string()),
+            "uptime" => Some(format_uptime(vars.uptime)),
+            _ => None,
+        };
+
+        match value {
+            Some(value) => {
+                result.push_str(&value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Format an uptime as `Xd Xh Xm`, omitting leading zero units
+pub fn format_uptime(uptime: Duration) -> String {
+    let minutes = uptime.as_secs() / 60;
+    let (days, hours, minutes) = (minutes / 1440, minutes / 60 % 24, minutes % 60);
+
+    if days > 0 {
+        format!("{}d {}h {}m", days, hours, minutes)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// Build the packet that shows a rendered message to a client
+///
+/// # Packet Format
+/// ```text
+/// Classic and older: {PLO_STARTMESSAGE}{message}
+/// NewMain and later: {PLO_RPGWINDOW}{GSTRING "message"}
+/// ```
+///
+/// Line breaks are sent as `<br>` since a packet ends at the first newline.
+pub fn motd_packet(generation: ServerGeneration, message: &str) -> PacketOut {
+    let message = message.replace("\r\n", "<br>").replace('\n', "<br>");
+
+    match generation {
+        ServerGeneration::Original | ServerGeneration::Classic => {
+            PacketOut::new(PacketTypeOut::StartMessage, message.into_bytes())
+        }
+        ServerGeneration::NewMain | ServerGeneration::Modern => {
+            PacketOut::new(PacketTypeOut::RpgWindow, rpg_window_data(&message))
+        }
+    }
+}
+
+/// Encode a PLO_RPGWINDOW body, `{GSTRING "message"}` with `"` doubled
+pub fn rpg_window_data(message: &str) -> Vec<u8> {
+    format!("\"{}\"", message.replace('"', "\"\"")).into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_variables() {
+        let vars = MotdVars {
+            player_name: "Bob".into(),
+            online: 12,
+            uptime: Duration::from_secs(26 * 3600 + 5 * 60),
+        };
+
+        assert_eq!(
+            render("Welcome {playername}! {online} online, up {uptime}. {unknown} {", &vars),
+            "Welcome Bob! 12 online, up 1d 2h 5m. {unknown} {"
+        );
+    }
+
+    #[test]
+    fn test_format_uptime() {
+        assert_eq!(format_uptime(Duration::from_secs(59)), "0m");
+        assert_eq!(format_uptime(Duration::from_secs(3 * 3600 + 60)), "3h 1m");
+    }
+
+    #[test]
+    fn test_packet_by_generation() {
+        let packet = motd_packet(ServerGeneration::Classic, "Hi\r\nthere");
+        assert_eq!(packet.packet_type, PacketTypeOut::StartMessage);
+        assert_eq!(packet.packet_data, b"Hi<br>there");
+
+        let packet = motd_packet(ServerGeneration::Modern, "Say \"hi\"");
+        assert_eq!(packet.packet_type, PacketTypeOut::RpgWindow);
+        assert_eq!(packet.packet_data, b"\"Say \"\"hi\"\"\"");
+    }
+}
@@ -0,0 +1,126 @@
+//! # Nickname Sanitization
+//!
+//! This module validates nicknames set through PLI_PLAYERPROPS before they
+//! are stored on the account and shown to other players.
+//!
+//! # Rules
+//!
+//! - Control characters are stripped and surrounding whitespace trimmed
+//! - The name part is capped at `maxnicklength` characters
+//! - Non-staff players can't use the nickname of a staff account
+//! - Guild tags listed in `staffguilds` are reserved for staff
+//! - An empty name falls back to `unknown`
+//!
+//! The word filter runs afterwards on the sanitized nick (see
+//! `PlayerConnection::apply_word_filter`).
+//!
+//! # C++ Equivalence
+//!
+//! Extends `PlayerClient::setNick` in PlayerClient.cpp, which trims the nick,
+//! replaces an empty name with "unknown" and strips unauthorized guild tags.
+
+use gserver_config::wordfilter::split_guild;
+use gserver_config::ServerConfig;
+
+/// Name used when a nickname is empty after sanitizing
+///
+/// # C++ Equivalence
+/// Matches the `"unknown"` fallback in `PlayerClient::setNick`
+pub const EMPTY_NICK: &str = "unknown";
+
+/// Sanitize a nickname requested by a player
+///
+/// # Arguments
+/// * `raw` - Nickname as sent by the client, optionally with a `(guild)` tag
+/// * `account` - The player's account name
+/// * `is_staff` - Whether the player is staff (exempt from impersonation and tag checks)
+/// * `config` - Server configuration (`staff`, `staffguilds`, `maxnicklength`)
+///
+/// # Returns
+/// The nickname to store and echo back to the client
+pub fn sanitize_nickname(raw: &str, account: &str, is_staff: bool, config: &ServerConfig) -> String {
+    let cleaned: String = raw.chars().filter(|c| !c.is_control()).collect();
+    let (name, guild) = match split_guild(&cleaned) {
+        Some((name, guild)) => (name.trim(), Some(guild.trim())),
+        None => (cleaned.trim(), None),
+    };
+
+    let mut name: String = name.chars().take(config.max_nick_length).collect();
+    let name_trimmed = name.trim_end().len();
+    name.truncate(name_trimmed);
+    if name.is_empty() {
+        name = EMPTY_NICK.to_string();
+    }
+
+    if !is_staff && impersonates_staff(&name, account, config) {
+        tracing::warn!("Account {} tried to use staff nickname '{}'", account, name);
+        name = account.to_string();
+    }
+
+    match guild {
+        Some(guild) if !guild.is_empty() => {
+            let reserved = config.staff_guilds.iter().any(|g| g.trim().eq_ignore_ascii_case(guild));
+            if reserved && !is_staff {
+                tracing::warn!("Account {} tried to use staff guild tag '{}'", account, guild);
+                name
+            } else {
+                format!("{} ({})", name, guild)
+            }
+        }
+        _ => name,
+    }
+}
+
+/// Check if a name looks like a staff account name other than the player's own
+///
+/// Comparison ignores case and whitespace, so "M a n a g e r" still matches.
+fn impersonates_staff(name: &str, account: &str, config: &ServerConfig) -> bool {
+    let normalize = |s: &str| -> String {
+        s.chars().filter(|c| !c.is_whitespace()).flat_map(char::to_lowercase).collect()
+    };
+
+    let name = normalize(name);
+    let own = normalize(account);
+    config.staff_accounts.iter()
+        .map(|staff| normalize(staff))
+        .any(|staff| !staff.is_empty() && staff == name && staff != own)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ServerConfig {
+        ServerConfig {
+            staff_accounts: vec!["Manager".into()],
+            staff_guilds: vec!["Staff".into()],
+            max_nick_length: 10,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_strips_control_characters_and_caps_length() {
+        let config = config();
+        assert_eq!(sanitize_nickname("  Bo\x07b\t ", "bob", false, &config), "Bob");
+        assert_eq!(sanitize_nickname("Bartholomew the Great (Heroes)", "bob", false, &config),
+            "Bartholome (Heroes)");
+        assert_eq!(sanitize_nickname(" \x01 ", "bob", false, &config), EMPTY_NICK);
+    }
+
+    #[test]
+    fn test_staff_impersonation() {
+        let config = config();
+        assert_eq!(sanitize_nickname("MANA GER", "bob", false, &config), "bob");
+        assert_eq!(sanitize_nickname("Manager", "manager", false, &config), "Manager");
+        assert_eq!(sanitize_nickname("Manager", "admin", true, &config), "Manager");
+    }
+
+    #[test]
+    fn test_reserved_guild_tags() {
+        let config = config();
+        assert_eq!(sanitize_nickname("Bob (staff)", "bob", false, &config), "Bob");
+        assert_eq!(sanitize_nickname("Bob (Staff)", "bob", true, &config), "Bob (Staff)");
+        assert_eq!(sanitize_nickname("Bob ()", "bob", false, &config), "Bob");
+    }
+}
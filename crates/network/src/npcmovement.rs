@@ -0,0 +1,608 @@
+//! # Serverside NPC Movement
+//!
+//! The `putnpc2`, `move`, `setdir` and `shoot` script functions of
+//! serverside NPCs ([`NpcControlHandler`]). Scripts only queue them; the
+//! tick loop carries them out ([`NpcMovements::tick`]) so the players on the
+//! NPC's level are told from one place:
+//!
+//! - `putnpc2 x, y, script` puts a new NPC on the script's level (ids from
+//!   [`FIRST_NPC_ID`] up), loads its script and runs `created`, then sends
+//!   its position to the level.
+//! - `move dx, dy, seconds, options` glides the NPC from where it is by
+//!   `dx, dy` tiles. Each tick its position is interpolated, and sent to
+//!   the level as PLO_NPCPROPS X/Y/X2/Y2 at most `npcmoverate` times a
+//!   second, and once more where it stops.
+//! - `setdir dir` sends the SPRITE prop at once.
+//! - `shoot x, y, z, angle, zangle, power, gani` sends PLO_SHOOT2 from the
+//!   NPC (shooter 0).
+//! - `canbecarried` / `cannotbecarried` set whether players can lift the
+//!   NPC (checked when a player picks it up).
+//! - `setshape type, width, height` gives the NPC a blocking shape (type 1)
+//!   or clears it.
+//!
+//! # Move Options
+//! - `2` - Turn the NPC the way it moves
+//! - `8` - Stop at the first wall of the level board. Walls are the tiles
+//!   typed blocking or throw-through in `tiletypes.dat` of the server
+//!   folder; without it only the board edges stop NPCs.
+//! - `16` - Run the script's `movementfinished` event when it stops
+//!
+//! The NPC collides with its blocking shape (`setshape`), or two by two
+//! tiles without one. Scripted NPCs can patrol without a client this way.
+//!
+//! Where an NPC is put and where each move ends are recorded in its save
+//! ([`crate::npcsaves`]); `putnpc2` of an NPC with a save puts it back there.
+//!
+//! # C++ Equivalence
+//! Matches the `move` and `shoot` commands of serverside NPCs in
+//! `NPC::moveNPC` and `NPC::shoot` (GS1 `move` interpolation runs on the
+//! client there; here the server sends the steps).
+
+use crate::ServerContext;
+use bytes::{BufMut, BytesMut};
+use gserver_game::{EventBus, GameEvent};
+use gserver_levels::{Level, LevelTiles, TileTypes};
+use gserver_protocol::codecs::{write_gchar, write_gint, write_gshort};
+use gserver_protocol::PacketTypeOut;
+use gserver_scripting::{npc_script_name, NpcControlHandler, Projectile};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// `move` option: turn the NPC the way it moves
+pub const MOVE_TURN: u32 = 2;
+
+/// `move` option: stop at walls
+pub const MOVE_BLOCK_CHECK: u32 = 8;
+
+/// `move` option: run `movementfinished` when done
+pub const MOVE_EVENT_WHEN_DONE: u32 = 16;
+
+/// Id of the first NPC put by `putnpc2`
+pub const FIRST_NPC_ID: u32 = 10000;
+
+/// Tile types file in the server folder
+pub const TILE_TYPES_FILE: &str = "tiletypes.dat";
+
+/// Script event run when a `move` with [`MOVE_EVENT_WHEN_DONE`] stops
+const MOVEMENT_FINISHED: &str = "movementfinished";
+
+/// Script event run when `putnpc2` puts an NPC
+const CREATED: &str = "created";
+
+/// NPC prop IDs (C++ `NPCPROP_*`)
+const NPCPROP_X: u8 = 2;
+const NPCPROP_Y: u8 = 3;
+const NPCPROP_SPRITE: u8 = 18;
+const NPCPROP_X2: u8 = 75;
+const NPCPROP_Y2: u8 = 76;
+
+/// Collision size of an NPC without a shape (pixels)
+const DEFAULT_SIZE: (f32, f32) = (32.0, 32.0);
+
+/// Distance between the wall checks along a path (pixels)
+const BLOCK_CHECK_STEP: f32 = 4.0;
+
+/// Projectile gravity sent in PLO_SHOOT2
+const SHOT_GRAVITY: u8 = 8;
+
+/// An NPC function call waiting for the next tick
+#[derive(Debug, Clone, PartialEq)]
+enum Command {
+    Put { npc: u32, level: String, x: f64, y: f64, script: String },
+    Move { npc: u32, dx: f64, dy: f64, seconds: f64, options: u32 },
+    SetDir { npc: u32, dir: u8 },
+    Shoot { npc: u32, projectile: Projectile },
+    SetCarryable { npc: u32, carryable: bool },
+    SetShape { npc: u32, shape: Option<(u16, u16)> },
+}
+
+impl Command {
+    fn npc(&self) -> u32 {
+        match self {
+            Command::Put { npc, .. } | Command::Move { npc, .. } | Command::SetDir { npc, .. }
+            | Command::Shoot { npc, .. } | Command::SetCarryable { npc, .. } | Command::SetShape { npc, .. } => *npc,
+        }
+    }
+}
+
+/// One NPC gliding from one spot to another
+#[derive(Debug, Clone)]
+pub struct Movement {
+    /// Level the NPC is on
+    pub level: Arc<Level>,
+
+    /// Start and end position (pixels)
+    pub from: (f32, f32),
+    pub to: (f32, f32),
+
+    pub started: Instant,
+    pub duration: Duration,
+
+    /// Run `movementfinished` when done
+    pub notify: bool,
+
+    /// When the position was last sent
+    last_sent: Option<Instant>,
+}
+
+impl Movement {
+    /// Get the position at a time
+    pub fn position(&self, now: Instant) -> (f32, f32) {
+        if self.is_done(now) {
+            return self.to;
+        }
+        let t = now.duration_since(self.started).as_secs_f32() / self.duration.as_secs_f32();
+        (self.from.0 + (self.to.0 - self.from.0) * t, self.from.1 + (self.to.1 - self.from.1) * t)
+    }
+
+    /// Check if the NPC has arrived
+    pub fn is_done(&self, now: Instant) -> bool {
+        now.duration_since(self.started) >= self.duration
+    }
+}
+
+/// Cut a path short where the NPC would first touch a wall
+///
+/// # Arguments
+/// * `from`, `to` - Top-left corner at the start and end (pixels)
+/// * `size` - Collision size of the NPC
+///
+/// # Returns
+/// The furthest free position along the path (`from` if even the first
+/// step is blocked)
+pub fn clip_path(tiles: &LevelTiles, types: &TileTypes, from: (f32, f32), to: (f32, f32), size: (f32, f32)) -> (f32, f32) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let steps = (dx.abs().max(dy.abs()) / BLOCK_CHECK_STEP).ceil() as u32;
+    let mut free = from;
+    for step in 1..=steps {
+        let t = step as f32 / steps as f32;
+        let at = (from.0 + dx * t, from.1 + dy * t);
+        if tiles.is_blocked(types, at.0, at.1, size.0, size.1) {
+            break;
+        }
+        free = at;
+    }
+    free
+}
+
+/// Get the direction of a movement (0 up, 1 left, 2 down, 3 right)
+pub fn direction_of(dx: f64, dy: f64) -> u8 {
+    match dx.abs() > dy.abs() {
+        true if dx > 0.0 => 3,
+        true => 1,
+        false if dy < 0.0 => 0,
+        false => 2,
+    }
+}
+
+/// Encode a PLO_NPCPROPS body with an NPC's position
+///
+/// The X/Y props (half tiles) are for old clients; X2/Y2 (pixels) follow
+/// and win on newer ones.
+pub fn position_props_data(npc: u32, x: f32, y: f32) -> Vec<u8> {
+    let mut data = BytesMut::new();
+    write_gint(&mut data, npc as i32);
+    write_gchar(&mut data, NPCPROP_X as i8);
+    write_gchar(&mut data, (x / 8.0).round().clamp(0.0, 191.0) as u8 as i8);
+    write_gchar(&mut data, NPCPROP_Y as i8);
+    write_gchar(&mut data, (y / 8.0).round().clamp(0.0, 191.0) as u8 as i8);
+    for (prop, pixels) in [(NPCPROP_X2, x), (NPCPROP_Y2, y)] {
+        let pixels = pixels.round() as i32;
+        write_gchar(&mut data, prop as i8);
+        write_gshort(&mut data, (((pixels.unsigned_abs() as u16) << 1) | (pixels < 0) as u16) as i16);
+    }
+    data.to_vec()
+}
+
+/// Encode a PLO_NPCPROPS body with an NPC's direction
+pub fn direction_props_data(npc: u32, dir: u8) -> Vec<u8> {
+    let mut data = BytesMut::new();
+    write_gint(&mut data, npc as i32);
+    write_gchar(&mut data, NPCPROP_SPRITE as i8);
+    write_gchar(&mut data, (dir % 4) as i8);
+    data.to_vec()
+}
+
+/// Encode a PLO_SHOOT2 body for a projectile of an NPC
+///
+/// ```text
+/// {GSHORT shooter}{GSHORT x}{GSHORT y}{GSHORT z}{GCHAR offset x}{GCHAR offset y}
+/// {GCHAR angle}{GCHAR z angle}{GCHAR speed}{GCHAR gravity}
+/// {GSHORT gani length}{gani}{GCHAR params length}{params}
+/// ```
+/// Positions are pixels, angles 0-220 for a full turn and the speed is the
+/// power (0-220). NPCs shoot as player 0.
+pub fn shoot_data(projectile: &Projectile) -> Vec<u8> {
+    let angle = |radians: f64| (radians.rem_euclid(std::f64::consts::TAU) / std::f64::consts::TAU * 220.0) as u8 as i8;
+    let pixels = |tiles: f64| (tiles * 16.0).max(0.0) as i16;
+    let gani = &projectile.gani.as_bytes()[..projectile.gani.len().min(i16::MAX as usize)];
+    let params = &projectile.params.as_bytes()[..projectile.params.len().min(191)];
+
+    let mut data = BytesMut::new();
+    write_gshort(&mut data, 0);
+    write_gshort(&mut data, pixels(projectile.x));
+    write_gshort(&mut data, pixels(projectile.y));
+    write_gshort(&mut data, pixels(projectile.z));
+    write_gchar(&mut data, 0);
+    write_gchar(&mut data, 0);
+    write_gchar(&mut data, angle(projectile.angle));
+    write_gchar(&mut data, angle(projectile.z_angle));
+    write_gchar(&mut data, projectile.power.clamp(0.0, 220.0) as u8 as i8);
+    write_gchar(&mut data, SHOT_GRAVITY as i8);
+    write_gshort(&mut data, gani.len() as i16);
+    data.put_slice(gani);
+    write_gchar(&mut data, params.len() as i8);
+    data.put_slice(params);
+    data.to_vec()
+}
+
+/// Record an NPC's image, level and position (tiles) in its save
+fn record(context: &ServerContext, level: &Level, npc: u32) {
+    if let Some(npc_ref) = level.get_npc(npc) {
+        context.npc_saves().update(&npc_script_name(npc), |save| {
+            save.image = npc_ref.image;
+            save.level = level.name.clone();
+            save.x = npc_ref.x / 16.0;
+            save.y = npc_ref.y / 16.0;
+        });
+    }
+}
+
+/// Moves serverside NPCs for their scripts
+#[derive(Debug)]
+pub struct NpcMovements {
+    events: EventBus,
+    tile_types: TileTypes,
+
+    /// Script calls since the last tick
+    commands: Mutex<Vec<Command>>,
+
+    /// Movements in progress by NPC
+    moving: Mutex<HashMap<u32, Movement>>,
+
+    /// Id of the next NPC put by a script
+    next_id: AtomicU32,
+}
+
+impl NpcMovements {
+    /// Create the service
+    ///
+    /// # Arguments
+    /// * `events` - Event bus for [`GameEvent::LevelPacket`]
+    /// * `server_dir` - Server folder holding [`TILE_TYPES_FILE`]
+    pub fn new(events: EventBus, server_dir: &Path) -> Self {
+        Self::with_tile_types(events, TileTypes::load(&server_dir.join(TILE_TYPES_FILE)))
+    }
+
+    /// Create the service with a tile type table
+    pub fn with_tile_types(events: EventBus, tile_types: TileTypes) -> Self {
+        Self {
+            events,
+            tile_types,
+            commands: Mutex::new(Vec::new()),
+            moving: Mutex::new(HashMap::new()),
+            next_id: AtomicU32::new(FIRST_NPC_ID),
+        }
+    }
+
+    /// Check if an NPC is moving
+    pub fn is_moving(&self, npc: u32) -> bool {
+        self.moving.lock().contains_key(&npc)
+    }
+
+    /// Carry out the queued script calls and advance the movements
+    ///
+    /// Called by the tick loop. NPCs that aren't on a loaded level are
+    /// skipped.
+    pub fn tick(&self, context: &ServerContext, now: Instant) {
+        let commands = std::mem::take(&mut *self.commands.lock());
+        for command in commands {
+            self.run(context, command, now);
+        }
+
+        let rate = context.config().read().npc_move_rate.max(1);
+        let interval = Duration::from_secs(1) / rate;
+        let mut finished = Vec::new();
+        {
+            let mut moving = self.moving.lock();
+            for (&npc, movement) in moving.iter_mut() {
+                let done = movement.is_done(now);
+                if !done && movement.last_sent.is_some_and(|sent| now.duration_since(sent) < interval) {
+                    continue;
+                }
+                let (x, y) = movement.position(now);
+                movement.level.move_npc(npc, x, y);
+                self.send(&movement.level, position_props_data(npc, x, y));
+                movement.last_sent = Some(now);
+                if done {
+                    record(context, &movement.level, npc);
+                    finished.push((npc, movement.notify));
+                }
+            }
+            for (npc, _) in &finished {
+                moving.remove(npc);
+            }
+        }
+
+        // Outside the lock: the event may start the next movement
+        let scripts = context.scripts();
+        for (npc, _) in finished.into_iter().filter(|&(_, notify)| notify) {
+            let name = npc_script_name(npc);
+            if scripts.has_event(&name, MOVEMENT_FINISHED) {
+                if let Err(e) = scripts.trigger_event(&name, MOVEMENT_FINISHED) {
+                    tracing::warn!("NPC {} movementfinished script failed: {}", npc, e);
+                }
+            }
+        }
+    }
+
+    fn run(&self, context: &ServerContext, command: Command, now: Instant) {
+        let level = match &command {
+            // Back on the level it was saved on, if that one is loaded
+            Command::Put { npc, level, .. } => context.npc_saves().get(&npc_script_name(*npc))
+                .and_then(|save| context.levels().loaded_level(&save.level))
+                .or_else(|| context.levels().loaded_level(level)),
+            _ => context.levels().find_npc(command.npc()),
+        };
+        let Some(level) = level else {
+            tracing::debug!("NPC {} isn't on a loaded level", command.npc());
+            return;
+        };
+
+        match command {
+            Command::Put { npc, x, y, script, .. } => {
+                let name = npc_script_name(npc);
+                let (image, x, y) = match context.npc_saves().get(&name).filter(|save| save.level.eq_ignore_ascii_case(&level.name)) {
+                    Some(save) => (save.image, save.x * 16.0, save.y * 16.0),
+                    None => (String::new(), (x * 16.0) as f32, (y * 16.0) as f32),
+                };
+                level.add_npc(npc, image, x, y);
+                record(context, &level, npc);
+                let scripts = context.scripts();
+                match scripts.load_script(&name, &script) {
+                    Ok(_) if scripts.has_event(&name, CREATED) => {
+                        if let Err(e) = scripts.trigger_event(&name, CREATED) {
+                            tracing::warn!("NPC {} created script failed: {}", npc, e);
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("NPC {} script doesn't compile: {}", npc, e),
+                }
+                self.send(&level, position_props_data(npc, x, y));
+            }
+            Command::Move { npc, dx, dy, seconds, options } => {
+                let Some(npc_ref) = level.get_npc(npc) else { return };
+                let from = self.moving.lock().get(&npc).map_or((npc_ref.x, npc_ref.y), |m| m.position(now));
+                let mut to = (from.0 + (dx * 16.0) as f32, from.1 + (dy * 16.0) as f32);
+                if options & MOVE_BLOCK_CHECK != 0 {
+                    let size = npc_ref.shape.map_or(DEFAULT_SIZE, |(w, h)| (w.into(), h.into()));
+                    to = clip_path(&level.tiles.read(), &self.tile_types, from, to, size);
+                }
+                if options & MOVE_TURN != 0 && (dx != 0.0 || dy != 0.0) {
+                    self.send(&level, direction_props_data(npc, direction_of(dx, dy)));
+                }
+                let movement = Movement {
+                    level,
+                    from,
+                    to,
+                    started: now,
+                    duration: Duration::try_from_secs_f64(seconds).unwrap_or(Duration::ZERO),
+                    notify: options & MOVE_EVENT_WHEN_DONE != 0,
+                    last_sent: None,
+                };
+                self.moving.lock().insert(npc, movement);
+            }
+            Command::SetDir { npc, dir } => self.send(&level, direction_props_data(npc, dir)),
+            Command::Shoot { projectile, .. } => {
+                self.events.publish(GameEvent::LevelPacket {
+                    level: level.name.clone(),
+                    packet_type: PacketTypeOut::Shoot2,
+                    data: shoot_data(&projectile),
+                });
+            }
+            Command::SetCarryable { npc, carryable } => {
+                level.set_npc_carryable(npc, carryable);
+            }
+            Command::SetShape { npc, shape } => {
+                level.set_npc_shape(npc, shape);
+            }
+        }
+    }
+
+    fn send(&self, level: &Level, data: Vec<u8>) {
+        self.events.publish(GameEvent::LevelPacket { level: level.name.clone(), packet_type: PacketTypeOut::NpcProps, data });
+    }
+
+    fn queue(&self, command: Command) {
+        self.commands.lock().push(command);
+    }
+}
+
+impl NpcControlHandler for NpcMovements {
+    fn put_npc(&self, level: &str, x: f64, y: f64, script: &str) -> u32 {
+        let npc = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.queue(Command::Put { npc, level: level.to_string(), x, y, script: script.to_string() });
+        npc
+    }
+
+    fn move_by(&self, npc: u32, dx: f64, dy: f64, seconds: f64, options: u32) {
+        self.queue(Command::Move { npc, dx, dy, seconds, options });
+    }
+
+    fn set_dir(&self, npc: u32, dir: u8) {
+        self.queue(Command::SetDir { npc, dir });
+    }
+
+    fn shoot(&self, npc: u32, projectile: Projectile) {
+        self.queue(Command::Shoot { npc, projectile });
+    }
+
+    fn set_carryable(&self, npc: u32, carryable: bool) {
+        self.queue(Command::SetCarryable { npc, carryable });
+    }
+
+    fn set_shape(&self, npc: u32, shape: Option<(u16, u16)>) {
+        self.queue(Command::SetShape { npc, shape });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gserver_levels::tiles::{BASE_LAYER, TILE_TYPE_BLOCKING};
+
+    #[test]
+    fn test_path_and_interpolation() {
+        let mut tiles = LevelTiles::with_base_fill(0);
+        for y in 0..64 {
+            tiles.set_tile(20, y, BASE_LAYER, 1);
+        }
+        let types = TileTypes::from_bytes(&[0, TILE_TYPE_BLOCKING]);
+        assert_eq!(clip_path(&tiles, &types, (160.0, 160.0), (480.0, 160.0), (32.0, 32.0)), (288.0, 160.0));
+        assert_eq!(clip_path(&tiles, &types, (160.0, 160.0), (160.0, 40.0), (32.0, 32.0)), (160.0, 40.0));
+        assert_eq!(clip_path(&tiles, &types, (160.0, 8.0), (160.0, -40.0), (32.0, 32.0)), (160.0, 0.0));
+        assert_eq!((direction_of(1.0, -0.5), direction_of(-0.5, 2.0), direction_of(0.0, -1.0)), (3, 2, 0));
+
+        let now = Instant::now();
+        let movement = Movement {
+            level: Arc::new(Level::create_default("test.nw".into())),
+            from: (0.0, 100.0),
+            to: (64.0, 100.0),
+            started: now,
+            duration: Duration::from_secs(2),
+            notify: false,
+            last_sent: None,
+        };
+        assert_eq!(movement.position(now + Duration::from_millis(500)), (16.0, 100.0));
+        assert!(!movement.is_done(now + Duration::from_secs(1)));
+        assert_eq!(movement.position(now + Duration::from_secs(3)), (64.0, 100.0));
+
+        assert_eq!(position_props_data(3, 20.0, 33.0)[3..],
+            [32 + NPCPROP_X, 32 + 3, 32 + NPCPROP_Y, 32 + 4, 32 + NPCPROP_X2, 32, 32 + 40, 32 + NPCPROP_Y2, 32, 32 + 66]);
+    }
+
+    #[tokio::test]
+    async fn test_scripted_move_is_rate_capped() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("world")).unwrap();
+        std::fs::write(dir.path().join("world/patrol.nw"), "GLEVNW01\n").unwrap();
+        let config = gserver_config::ServerConfig { npc_move_rate: 5, ..Default::default() };
+        let context = ServerContext::new(dir.path(), config);
+        let level = context.levels().get_level("patrol.nw").await.unwrap();
+        level.add_npc(9, "guard.png".into(), 160.0, 160.0);
+
+        let mut events = context.events().subscribe();
+        let movements = context.npc_movements();
+        movements.move_by(9, 4.0, 0.0, 1.0, MOVE_TURN | MOVE_EVENT_WHEN_DONE);
+        movements.shoot(9, Projectile { x: 10.0, y: 10.0, z: 0.0, angle: 0.0, z_angle: 0.0, power: 1.0, gani: "arrow".into(), params: String::new() });
+
+        let start = Instant::now();
+        for ms in [0, 100, 200, 600, 1000] {
+            movements.tick(&context, start + Duration::from_millis(ms));
+        }
+        let mut sent = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let GameEvent::LevelPacket { level, packet_type, data } = event {
+                assert_eq!(level, "patrol.nw");
+                sent.push((packet_type, data));
+            }
+        }
+        let types: Vec<_> = sent.iter().map(|(t, _)| *t).collect();
+        assert_eq!(types, [PacketTypeOut::NpcProps, PacketTypeOut::Shoot2, PacketTypeOut::NpcProps,
+            PacketTypeOut::NpcProps, PacketTypeOut::NpcProps, PacketTypeOut::NpcProps]);
+        assert_eq!(sent[0].1, direction_props_data(9, 3));
+        assert_eq!(sent[2].1, position_props_data(9, 160.0, 160.0));
+        assert_eq!(sent[4].1, position_props_data(9, 198.4, 160.0));
+        assert_eq!(sent[5].1, position_props_data(9, 224.0, 160.0));
+        assert_eq!(level.get_npc(9).map(|npc| (npc.x, npc.y)), Some((224.0, 160.0)));
+        assert!(!movements.is_moving(9));
+    }
+
+    #[tokio::test]
+    async fn test_put_npc_and_move() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("world")).unwrap();
+        std::fs::write(dir.path().join("world/yard.nw"), "GLEVNW01\n").unwrap();
+        let context = ServerContext::new(dir.path(), gserver_config::ServerConfig::default());
+        let level = context.levels().get_level("yard.nw").await.unwrap();
+
+        let movements = context.npc_movements();
+        let npc = movements.put_npc("yard.nw", 2.0, 3.0, "this.ready = 1");
+        assert_eq!(npc, FIRST_NPC_ID);
+        assert_eq!(movements.put_npc("nowhere.nw", 0.0, 0.0, ""), FIRST_NPC_ID + 1);
+        movements.tick(&context, Instant::now());
+        assert_eq!(level.get_npc(npc).map(|npc| (npc.x, npc.y)), Some((32.0, 48.0)));
+        assert!(context.scripts().get_script(&npc_script_name(npc)).is_some());
+        assert!(context.levels().find_npc(FIRST_NPC_ID + 1).is_none());
+
+        // An endless move is a jump, not a panic
+        movements.move_by(npc, 1.0, 0.0, f64::INFINITY, 0);
+        movements.tick(&context, Instant::now());
+        assert_eq!(level.get_npc(npc).map(|npc| npc.x), Some(48.0));
+    }
+
+    #[tokio::test]
+    async fn test_saved_npc_restored() {
+        use gserver_scripting::{GS1Interpreter, GS1Script};
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("world")).unwrap();
+        std::fs::write(dir.path().join("world/yard.nw"), "GLEVNW01\n").unwrap();
+        {
+            let context = ServerContext::new(dir.path(), gserver_config::ServerConfig::default());
+            context.levels().get_level("yard.nw").await.unwrap();
+            let movements = context.npc_movements();
+            let npc = movements.put_npc("yard.nw", 2.0, 3.0, "this.ready = 1");
+            movements.tick(&context, Instant::now());
+            movements.move_by(npc, 1.0, 0.0, 0.0, 0);
+            movements.tick(&context, Instant::now());
+            let save = context.npc_saves().get(&npc_script_name(npc)).unwrap();
+            assert_eq!((save.level.as_str(), save.x, save.y), ("yard.nw", 3.0, 3.0));
+            context.npc_saves().save_dirty().unwrap();
+        }
+
+        // After a restart the NPC is put back where it stopped, with its variables
+        let context = ServerContext::new(dir.path(), gserver_config::ServerConfig::default());
+        assert_eq!(context.npc_saves().load_all(), 1);
+        let level = context.levels().get_level("yard.nw").await.unwrap();
+        let movements = context.npc_movements();
+        let npc = movements.put_npc("yard.nw", 2.0, 3.0, "this.ready = 1");
+        movements.tick(&context, Instant::now());
+        assert_eq!(level.get_npc(npc).map(|npc| (npc.x, npc.y)), Some((48.0, 48.0)));
+
+        let script = GS1Script::parse(npc_script_name(npc), "this.seen = 1").unwrap();
+        let mut interpreter = GS1Interpreter::new(context.scripts().context().clone());
+        interpreter.execute(&script, "created").unwrap();
+        assert_eq!(interpreter.variables().get("this.ready").map(String::as_str), Some("1"));
+    }
+
+    #[tokio::test]
+    async fn test_scripted_carry_flag_and_shape() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("world")).unwrap();
+        std::fs::write(dir.path().join("world/yard.nw"), "GLEVNW01\n").unwrap();
+        let context = ServerContext::new(dir.path(), gserver_config::ServerConfig::default());
+        let level = context.levels().get_level("yard.nw").await.unwrap();
+        level.add_npc(5, "pot.png".into(), 64.0, 64.0);
+
+        let movements = context.npc_movements();
+        movements.set_carryable(5, true);
+        movements.tick(&context, Instant::now());
+        assert!(level.get_npc(5).unwrap().carryable);
+
+        movements.set_carryable(5, false);
+        movements.set_shape(5, Some((48, 16)));
+        movements.tick(&context, Instant::now());
+        let npc = level.get_npc(5).unwrap();
+        assert!(!npc.carryable);
+        assert_eq!(npc.shape, Some((48, 16)));
+
+        movements.set_shape(5, None);
+        movements.tick(&context, Instant::now());
+        assert_eq!(level.get_npc(5).unwrap().shape, None);
+    }
+}
@@ -0,0 +1,334 @@
+//! # NPC Saves
+//!
+//! Serverside NPC state that survives restarts: the NPC's `this.*`
+//! variables, image, level and position, one file per NPC in `npcs/`.
+//!
+//! # Saving
+//!
+//! Changes only mark the NPC dirty. [`NpcSaveAutosave`](crate::autosave::NpcSaveAutosave)
+//! writes the dirty NPCs on each autosave and once more at shutdown, so a
+//! script that sets a variable every tick costs one write per autosave.
+//!
+//! RC can inspect a save with `/npcsave <name>` and delete it with
+//! `/npcreset <name>`; the NPC starts fresh the next time its script runs.
+//!
+//! # Restoring
+//!
+//! Script runs start with the stored `this.*` variables. A serverside NPC
+//! records its image, level and position when it's put and when a move
+//! ends ([`crate::npcmovement`]), and is put back there when its script puts
+//! it again after a restart.
+//!
+//! # File Format
+//! ```text
+//! GRNPC001
+//! NAME npc3
+//! IMAGE door.png
+//! LEVEL house.nw
+//! X 30.5
+//! Y 12
+//! FLAG opened=1
+//! ```
+//!
+//! # C++ Equivalence
+//! Uses the layout of the `npcs/npc<name>.txt` files written by
+//! `TNPC::saveNPC`. Scripts aren't stored since they live with their owner.
+
+use gserver_core::{GServerError, Result};
+use gserver_scripting::NpcStateHandler;
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// First line of an NPC save file
+const HEADER: &str = "GRNPC001";
+
+/// Saved state of one NPC
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NpcSave {
+    /// NPC name (the name its script is registered under)
+    pub name: String,
+
+    /// NPC image
+    pub image: String,
+
+    /// Level the NPC is on
+    pub level: String,
+
+    /// X position (tiles)
+    pub x: f32,
+
+    /// Y position (tiles)
+    pub y: f32,
+
+    /// `this.*` variables (names without `this.`)
+    pub vars: BTreeMap<String, String>,
+}
+
+impl NpcSave {
+    /// Create an empty save for an NPC
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Parse a save file
+    ///
+    /// # Returns
+    /// `None` if the header or the name is missing
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut lines = text.lines();
+        if lines.next()?.trim() != HEADER {
+            return None;
+        }
+
+        let mut save = Self::default();
+        for line in lines.map(|l| l.trim_end_matches('\r')) {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            match key {
+                "NAME" => save.name = value.to_string(),
+                "IMAGE" => save.image = value.to_string(),
+                "LEVEL" => save.level = value.to_string(),
+                "X" => save.x = value.parse().unwrap_or(0.0),
+                "Y" => save.y = value.parse().unwrap_or(0.0),
+                "FLAG" => {
+                    let (name, value) = value.split_once('=').unwrap_or((value, ""));
+                    save.vars.insert(name.to_string(), value.to_string());
+                }
+                _ => {}
+            }
+        }
+        (!save.name.is_empty()).then_some(save)
+    }
+
+    /// Format as a save file
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\r\nNAME {}\r\nIMAGE {}\r\nLEVEL {}\r\nX {}\r\nY {}\r\n",
+            HEADER, self.name, self.image, self.level, self.x, self.y);
+        for (name, value) in &self.vars {
+            text.push_str(&format!("FLAG {}={}\r\n", name, value.replace(['\r', '\n'], " ")));
+        }
+        text
+    }
+}
+
+/// All NPC saves, with the ones changed since the last write
+///
+/// # Thread Safety
+/// Scripts change saves from any task while autosave writes them, so both
+/// sets are behind locks. Names are case-insensitive.
+#[derive(Debug)]
+pub struct NpcSaves {
+    /// Directory holding `npc<name>.txt` files
+    dir: PathBuf,
+
+    /// Saves by lowercase name
+    saves: Mutex<BTreeMap<String, NpcSave>>,
+
+    /// Lowercase names changed since they were last written
+    dirty: Mutex<BTreeSet<String>>,
+}
+
+impl NpcSaves {
+    /// Create an empty store writing to `dir`
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self {
+            dir: dir.into(),
+            saves: Mutex::new(BTreeMap::new()),
+            dirty: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// Load every save file in the directory
+    ///
+    /// # Returns
+    /// The number of NPCs loaded. A missing directory loads nothing.
+    pub fn load_all(&self) -> usize {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return 0;
+        };
+
+        let mut saves = self.saves.lock();
+        let mut loaded = 0;
+        for path in entries.flatten().map(|e| e.path()).filter(|p| p.extension().is_some_and(|ext| ext == "txt")) {
+            let parsed = fs::read(&path).ok().and_then(|bytes| NpcSave::parse(&String::from_utf8_lossy(&bytes)));
+            match parsed {
+                Some(save) => {
+                    saves.insert(save.name.to_lowercase(), save);
+                    loaded += 1;
+                }
+                None => tracing::warn!("Ignoring malformed NPC save {:?}", path),
+            }
+        }
+        loaded
+    }
+
+    /// Get the save of an NPC
+    pub fn get(&self, name: &str) -> Option<NpcSave> {
+        self.saves.lock().get(&name.to_lowercase()).cloned()
+    }
+
+    /// Get the names of all saved NPCs
+    pub fn names(&self) -> Vec<String> {
+        self.saves.lock().values().map(|s| s.name.clone()).collect()
+    }
+
+    /// Change the save of an NPC, creating it if needed
+    ///
+    /// # Returns
+    /// `true` if anything changed (the NPC is then written on the next save)
+    pub fn update<F: FnOnce(&mut NpcSave)>(&self, name: &str, change: F) -> bool {
+        let key = name.to_lowercase();
+        let mut saves = self.saves.lock();
+        let save = saves.entry(key.clone()).or_insert_with(|| NpcSave::new(name));
+        let before = save.clone();
+        change(save);
+        let changed = *save != before;
+        if changed {
+            self.dirty.lock().insert(key);
+        }
+        changed
+    }
+
+    /// Delete the save of an NPC, in memory and on disk
+    ///
+    /// # Returns
+    /// `false` if the NPC had no save
+    pub fn reset(&self, name: &str) -> io::Result<bool> {
+        let key = name.to_lowercase();
+        let Some(save) = self.saves.lock().remove(&key) else {
+            return Ok(false);
+        };
+        self.dirty.lock().remove(&key);
+
+        match fs::remove_file(self.file_path(&save.name)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(true),
+        }
+    }
+
+    /// Get the number of NPCs waiting to be written
+    pub fn dirty_count(&self) -> usize {
+        self.dirty.lock().len()
+    }
+
+    /// Write the NPCs that changed since the last save
+    ///
+    /// An NPC that fails to write stays dirty and is retried next time.
+    ///
+    /// # Returns
+    /// The number of NPCs written
+    pub fn save_dirty(&self) -> Result<usize> {
+        let dirty = std::mem::take(&mut *self.dirty.lock());
+        if dirty.is_empty() {
+            return Ok(0);
+        }
+        // Copy out so scripts aren't blocked during file I/O
+        let pending: Vec<NpcSave> = {
+            let saves = self.saves.lock();
+            dirty.iter().filter_map(|key| saves.get(key).cloned()).collect()
+        };
+
+        if let Err(e) = fs::create_dir_all(&self.dir) {
+            self.dirty.lock().extend(dirty);
+            return Err(e.into());
+        }
+        let mut saved = 0;
+        let mut failed = None;
+        for save in pending {
+            let path = self.file_path(&save.name);
+            let temp_path = path.with_extension("txt.tmp");
+            match fs::write(&temp_path, save.to_text()).and_then(|_| fs::rename(&temp_path, &path)) {
+                Ok(()) => saved += 1,
+                Err(e) => {
+                    self.dirty.lock().insert(save.name.to_lowercase());
+                    failed = Some(GServerError::Io(io::Error::new(e.kind(), format!("Failed to write {:?}: {}", path, e))));
+                }
+            }
+        }
+        match failed {
+            Some(e) => Err(e),
+            None => Ok(saved),
+        }
+    }
+
+    /// Get the directory the saves are written to
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn file_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("npc{}.txt", name))
+    }
+}
+
+impl NpcStateHandler for NpcSaves {
+    fn get_var(&self, npc: &str, name: &str) -> Option<String> {
+        self.saves.lock().get(&npc.to_lowercase())?.vars.get(name).cloned()
+    }
+
+    fn set_var(&self, npc: &str, name: &str, value: &str) {
+        self.update(npc, |save| {
+            if value.is_empty() {
+                save.vars.remove(name);
+            } else {
+                save.vars.insert(name.to_string(), value.to_string());
+            }
+        });
+    }
+
+    fn vars(&self, npc: &str) -> Vec<(String, String)> {
+        self.saves.lock().get(&npc.to_lowercase())
+            .map(|save| save.vars.iter().map(|(name, value)| (name.clone(), value.clone())).collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_saved_in_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        let saves = NpcSaves::new(dir.path().join("npcs"));
+        saves.set_var("npc3", "hits", "1");
+        saves.set_var("npc3", "hits", "2");
+        saves.update("npc3", |save| {
+            save.image = "door.png".into();
+            save.x = 30.5;
+        });
+        assert!(!saves.update("NPC3", |save| save.x = 30.5));
+        assert_eq!(saves.dirty_count(), 1);
+        assert_eq!(saves.save_dirty().unwrap(), 1);
+        assert_eq!(saves.save_dirty().unwrap(), 0);
+
+        let text = fs::read_to_string(dir.path().join("npcs/npcnpc3.txt")).unwrap();
+        assert_eq!(text, "GRNPC001\r\nNAME npc3\r\nIMAGE door.png\r\nLEVEL \r\nX 30.5\r\nY 0\r\nFLAG hits=2\r\n");
+
+        let reloaded = NpcSaves::new(dir.path().join("npcs"));
+        assert_eq!(reloaded.load_all(), 1);
+        assert_eq!(reloaded.get("npc3"), saves.get("npc3"));
+        assert_eq!(reloaded.get_var("Npc3", "hits").as_deref(), Some("2"));
+
+        assert!(reloaded.reset("npc3").unwrap());
+        assert!(!reloaded.reset("npc3").unwrap());
+        assert!(!dir.path().join("npcs/npcnpc3.txt").exists());
+    }
+
+    #[test]
+    fn test_script_variables_stored() {
+        let dir = tempfile::tempdir().unwrap();
+        let context = crate::ServerContext::new(dir.path(), Default::default());
+        let scripts = context.scripts();
+        scripts.load_script("npc3", "this.opened = 1").unwrap();
+        scripts.trigger_event("npc3", "created").unwrap();
+        assert_eq!(context.npc_saves().get_var("npc3", "opened").as_deref(), Some("1"));
+        assert_eq!(context.npc_saves().save_dirty().unwrap(), 1);
+        assert!(dir.path().join("npcs/npcnpc3.txt").exists());
+    }
+}
@@ -0,0 +1,115 @@
+//! # NPC-Server Status
+//!
+//! The serverside engine's health the original npcserver showed RCs: uptime,
+//! loaded levels and their NPCs, saved NPCs, and the script host's scripts,
+//! run time, memory and errors.
+//!
+//! RCs get it with the RC command `/npcserver`. PLI_NPCSERVERQUERY instead
+//! asks where the NPC-Server is, and is answered with PLO_NPCSERVERADDR (see
+//! [`server_addr_data`]).
+
+use crate::ServerContext;
+use bytes::BytesMut;
+use gserver_config::ServerConfig;
+use gserver_protocol::write_gshort;
+use std::time::Duration;
+
+/// Build the PLO_NPCSERVERADDR body: `{GSHORT 0}{ip},{port}`
+///
+/// The NPC-Server runs inside this server, so NCs connect to the main port;
+/// the address is `ns_ip`, or `serverip` while `ns_ip` is "AUTO".
+pub fn server_addr_data(config: &ServerConfig) -> Vec<u8> {
+    let ip = [&config.ns_ip, &config.server_ip]
+        .into_iter()
+        .find(|ip| !ip.is_empty() && !ip.eq_ignore_ascii_case("AUTO"))
+        .map(String::as_str)
+        .unwrap_or("127.0.0.1");
+    let mut buf = BytesMut::new();
+    write_gshort(&mut buf, 0);
+    buf.extend_from_slice(format!("{},{}", ip, config.server_port).as_bytes());
+    buf.to_vec()
+}
+
+/// A snapshot of the serverside engine
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NpcServerStatus {
+    pub uptime: Duration,
+    pub levels: usize,
+    pub npcs: usize,
+    pub saved_npcs: usize,
+    pub scripts: usize,
+    pub script_time: Duration,
+    pub script_errors: u64,
+
+    /// Bytes held by the scripts after their last runs
+    pub live_bytes: usize,
+
+    /// Sum of the scripts' peaks
+    pub peak_bytes: usize,
+}
+
+impl NpcServerStatus {
+    /// Take a snapshot of the server's engine
+    pub fn gather(context: &ServerContext) -> Self {
+        let levels = context.levels().loaded_levels();
+        let stats = context.scripts().stats();
+        Self {
+            uptime: context.uptime(),
+            levels: levels.len(),
+            npcs: levels.iter().map(|level| level.npcs.read().len()).sum(),
+            saved_npcs: context.npc_saves().names().len(),
+            scripts: context.scripts().script_count(),
+            script_time: stats.iter().map(|s| s.run_time).sum(),
+            script_errors: context.scripts().total_errors(),
+            live_bytes: stats.iter().map(|s| s.heap.live_bytes).sum(),
+            peak_bytes: stats.iter().map(|s| s.heap.peak_bytes).sum(),
+        }
+    }
+
+    /// Format the status as the lines of the RC's NPC-Server panel
+    pub fn lines(&self) -> Vec<String> {
+        let secs = self.uptime.as_secs();
+        vec![
+            format!("Uptime: {}d {:02}:{:02}:{:02}", secs / 86400, secs / 3600 % 24, secs / 60 % 60, secs % 60),
+            format!("NPCs: {} on {} loaded levels, {} saved", self.npcs, self.levels, self.saved_npcs),
+            format!("Scripts: {} loaded, {:.1}ms run, {} errors", self.scripts,
+                self.script_time.as_secs_f64() * 1000.0, self.script_errors),
+            format!("Script memory: {} KB live, {} KB peak", self.live_bytes / 1024, self.peak_bytes / 1024),
+        ]
+    }
+
+    /// Format the status as one RC line
+    pub fn describe(&self) -> String {
+        format!("NPC-Server: {}", self.lines().join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let context = ServerContext::new(dir.path(), Default::default());
+        let _ = context.scripts().load_script("-broken", "if (");
+        let status = NpcServerStatus { uptime: Duration::from_secs(90_061), ..NpcServerStatus::gather(&context) };
+        assert_eq!(status.script_errors, context.scripts().total_errors());
+        assert_eq!(status.lines()[0], "Uptime: 1d 01:01:01");
+        assert!(status.describe().starts_with("NPC-Server: Uptime: 1d 01:01:01, NPCs: 0 on 0 loaded levels"));
+    }
+
+    #[test]
+    fn test_server_addr_data() {
+        let mut config = ServerConfig { server_ip: "AUTO".into(), server_port: 14900, ..Default::default() };
+        let mut expected = BytesMut::new();
+        write_gshort(&mut expected, 0);
+        expected.extend_from_slice(b"127.0.0.1,14900");
+        assert_eq!(server_addr_data(&config), expected.to_vec());
+
+        config.server_ip = "game.example.com".into();
+        assert!(server_addr_data(&config).ends_with(b"game.example.com,14900"));
+        config.ns_ip = "10.0.0.5".into();
+        assert!(server_addr_data(&config).ends_with(b"10.0.0.5,14900"));
+    }
+}
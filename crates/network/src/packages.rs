@@ -0,0 +1,152 @@
+//! # Update Packages
+//!
+//! This module implements the update package flow Modern clients use to
+//! download a set of files at once. Packages are defined in
+//! `config/updatepackages.txt` (see [`gserver_config::UpdatePackage`]); their
+//! files are served from the [`FileIndex`] like PLI_WANTFILE requests.
+//!
+//! # Flow
+//! 1. Client queries the package version (and download size)
+//! 2. If its installed version is older, it asks for the update and gets
+//!    PLO_UPDATEPACKAGESIZE, every file (PLO_FILE), then PLO_UPDATEPACKAGEDONE
+//! 3. Single files that failed can be requested again by name
+//!
+//! # Packet Format
+//! ```text
+//! {PLI_UPDATEPACKAGEREQUESTFILE}{GSTRING package}{GCHAR request}...
+//!   request 0 (version):  nothing more
+//!   request 1 (update):   {GUINT5 installed version}
+//!   request 2 (file):     {file name}
+//!
+//! {PLO_UPDATEPACKAGESIZE}{GSTRING package}{GUINT5 version}{GUINT5 download size}
+//! {PLO_UPDATEPACKAGEDONE}{package}
+//! ```
+//!
+//! Unknown packages and files are answered with PLO_FILESENDFAILED.
+
+use crate::files::FileIndex;
+use bytes::{Buf, BytesMut};
+use gserver_config::UpdatePackage;
+use gserver_core::Result;
+use gserver_protocol::codecs::{read_gchar, read_gstring, read_guint5, write_gstring, write_guint5};
+use gserver_protocol::{PacketOut, PacketTypeOut, ProtocolError};
+use std::fs;
+
+/// What a PLI_UPDATEPACKAGEREQUESTFILE asks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PackageRequest {
+    /// Version and download size only
+    Version,
+
+    /// Every file, unless the installed version is current
+    Update { installed_version: u32 },
+
+    /// One file of the package
+    File(String),
+}
+
+/// Parse a PLI_UPDATEPACKAGEREQUESTFILE packet
+///
+/// # Returns
+/// The package name and the request
+pub fn parse_request(packet_data: &[u8]) -> Result<(String, PackageRequest)> {
+    let mut buf = BytesMut::from(packet_data);
+    let package = read_gstring(&mut buf)?;
+
+    let request = match read_gchar(&mut buf)? {
+        0 => PackageRequest::Version,
+        1 => PackageRequest::Update { installed_version: read_guint5(&mut buf)? },
+        2 => {
+            let file = String::from_utf8_lossy(buf.chunk()).trim().to_string();
+            PackageRequest::File(file)
+        }
+        other => {
+            return Err(ProtocolError::Malformed {
+                packet: "UpdatePackageRequestFile",
+                reason: format!("unknown request type {}", other),
+            }.into());
+        }
+    };
+
+    Ok((package, request))
+}
+
+/// Get the total size of a package's files in bytes
+///
+/// Files that aren't in the index count as 0; they fail individually when sent.
+pub fn package_size(package: &UpdatePackage, files: &FileIndex) -> u64 {
+    package.files.iter()
+        .filter_map(|name| files.find(name))
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|meta| meta.len())
+        .sum()
+}
+
+/// Build PLO_UPDATEPACKAGESIZE
+pub fn size_packet(package: &UpdatePackage, size: u64) -> PacketOut {
+    let mut buf = BytesMut::new();
+    write_gstring(&mut buf, &package.name);
+    write_guint5(&mut buf, package.version);
+    write_guint5(&mut buf, size.min(u32::MAX as u64) as u32);
+    PacketOut::new(PacketTypeOut::UpdatePackageSize, buf.to_vec())
+}
+
+/// Build PLO_UPDATEPACKAGEDONE
+pub fn done_packet(package: &UpdatePackage) -> PacketOut {
+    PacketOut::new(PacketTypeOut::UpdatePackageDone, package.name.as_bytes().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gserver_config::{FolderConfig, FolderType};
+
+    fn request(package: &str, kind: i8, rest: &[u8]) -> Vec<u8> {
+        let mut buf = BytesMut::new();
+        write_gstring(&mut buf, package);
+        gserver_protocol::codecs::write_gchar(&mut buf, kind);
+        buf.extend_from_slice(rest);
+        buf.to_vec()
+    }
+
+    #[test]
+    fn test_parse_requests() {
+        assert_eq!(parse_request(&request("base", 0, b"")).unwrap(),
+            ("base".to_string(), PackageRequest::Version));
+
+        let mut version = BytesMut::new();
+        write_guint5(&mut version, 7);
+        assert_eq!(parse_request(&request("base", 1, &version)).unwrap().1,
+            PackageRequest::Update { installed_version: 7 });
+
+        assert_eq!(parse_request(&request("base", 2, b"logo.png")).unwrap().1,
+            PackageRequest::File("logo.png".into()));
+        assert!(parse_request(&request("base", 9, b"")).is_err());
+    }
+
+    #[test]
+    fn test_package_size_and_packets() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("logo.png"), [0u8; 300]).unwrap();
+        fs::write(dir.path().join("idle.gani"), [0u8; 20]).unwrap();
+        let folders = FolderConfig { entries: vec![(FolderType::File, "*".into())] };
+        let files = FileIndex::new(dir.path(), &folders);
+
+        let package = UpdatePackage {
+            name: "base".into(),
+            version: 3,
+            files: vec!["logo.png".into(), "idle.gani".into(), "missing.wav".into()],
+        };
+        assert_eq!(package_size(&package, &files), 320);
+
+        let packet = size_packet(&package, 320);
+        assert_eq!(packet.packet_type, PacketTypeOut::UpdatePackageSize);
+        let mut expected = BytesMut::new();
+        write_gstring(&mut expected, "base");
+        write_guint5(&mut expected, 3);
+        write_guint5(&mut expected, 320);
+        assert_eq!(packet.packet_data, expected.to_vec());
+
+        assert_eq!(done_packet(&package).packet_data, b"base");
+    }
+}
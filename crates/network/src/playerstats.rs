@@ -0,0 +1,428 @@
+//! # Player Statistics
+//!
+//! Counts, per account, what players did on the server:
+//!
+//! - `playtime` - Seconds logged in
+//! - `kills` / `deaths` - A death is a kill for whoever hurt the player last,
+//!   within [`KILL_WINDOW`]
+//! - `gralats` - Gralats picked up or earned by selling to shops
+//! - `levels` - Different levels visited
+//!
+//! Every `statsinterval` seconds [`run`] saves the statistics to
+//! `playerstats.txt` and ranks them into leaderboards, which queries read as
+//! they are, so asking for them costs nothing. With `statsinterval = 0`
+//! nothing is counted ([`PlayerStats::disabled`]).
+//!
+//!
+//! ```text
+//! triggeraction 0, 0, gr.leaderboard, kills   // -> PLO_TRIGGERACTION gr.leaderboard,kills,Bob,12,Alice,7
+//! GET /leaderboard                            // health address, JSON of every leaderboard
+//! ```
+//!
+//! # File Format
+//! ```text
+//! {account}\t{playtime}\t{kills}\t{deaths}\t{gralats}\t{levels, comma-separated}
+//! ```
+
+use crate::context::ServerContext;
+use bytes::BytesMut;
+use gserver_core::PlayerID;
+use gserver_game::GameEvent;
+use gserver_protocol::codecs::{write_gchar, write_gint, write_gshort};
+use parking_lot::{Mutex, RwLock};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::watch;
+
+/// Statistics file in the server folder
+pub const STATS_FILE: &str = "playerstats.txt";
+
+/// Accounts on each leaderboard
+pub const LEADERBOARD_SIZE: usize = 10;
+
+/// How long after a hit a death still counts as a kill for the attacker
+pub const KILL_WINDOW: Duration = Duration::from_secs(10);
+
+/// A counted statistic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stat {
+    Playtime,
+    Kills,
+    Deaths,
+    Gralats,
+    Levels,
+}
+
+impl Stat {
+    /// Every statistic, in leaderboard order
+    pub const ALL: [Stat; 5] = [Stat::Playtime, Stat::Kills, Stat::Deaths, Stat::Gralats, Stat::Levels];
+
+    /// Parse a statistic name (case-insensitive)
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|stat| stat.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Get the name used by triggeractions and the HTTP API
+    pub fn name(self) -> &'static str {
+        match self {
+            Stat::Playtime => "playtime",
+            Stat::Kills => "kills",
+            Stat::Deaths => "deaths",
+            Stat::Gralats => "gralats",
+            Stat::Levels => "levels",
+        }
+    }
+}
+
+/// Statistics of one account
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountStats {
+    /// Seconds logged in
+    pub playtime: u64,
+
+    pub kills: u64,
+
+    pub deaths: u64,
+
+    /// Gralats earned
+    pub gralats: u64,
+
+    /// Lowercase names of the levels visited
+    pub levels: BTreeSet<String>,
+}
+
+impl AccountStats {
+    /// Get the value of a statistic
+    pub fn get(&self, stat: Stat) -> u64 {
+        match stat {
+            Stat::Playtime => self.playtime,
+            Stat::Kills => self.kills,
+            Stat::Deaths => self.deaths,
+            Stat::Gralats => self.gralats,
+            Stat::Levels => self.levels.len() as u64,
+        }
+    }
+}
+
+/// Per-account statistics and the leaderboards last aggregated from them
+#[derive(Debug, Default)]
+pub struct PlayerStats {
+    /// Where the statistics are saved, `None` to keep them in memory
+    path: Option<PathBuf>,
+    accounts: Mutex<HashMap<String, AccountStats>>,
+
+    /// Account of each online player and when its playtime was last counted
+    sessions: Mutex<HashMap<PlayerID, (String, Instant)>>,
+
+    /// Last attacker of each player, and when they hit
+    hits: Mutex<HashMap<PlayerID, (String, Instant)>>,
+
+    leaderboards: RwLock<HashMap<Stat, Vec<(String, u64)>>>,
+
+    /// Count nothing (`statsinterval = 0`)
+    disabled: bool,
+}
+
+impl PlayerStats {
+    /// Create empty statistics that aren't saved
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create statistics that count nothing and aren't saved
+    pub fn disabled() -> Self {
+        Self { disabled: true, ..Default::default() }
+    }
+
+    /// Load the statistics file, starting empty if there is none
+    pub fn load(path: &Path) -> Self {
+        let mut accounts = HashMap::new();
+        if let Ok(content) = std::fs::read_to_string(path) {
+            for line in content.lines() {
+                let fields: Vec<&str> = line.split('\t').collect();
+                let [account, playtime, kills, deaths, gralats, levels] = fields[..] else { continue };
+                let (Ok(playtime), Ok(kills), Ok(deaths), Ok(gralats)) = (playtime.parse(), kills.parse(), deaths.parse(), gralats.parse()) else {
+                    continue;
+                };
+                let levels = levels.split(',').filter(|l| !l.is_empty()).map(str::to_string).collect();
+                accounts.insert(account.to_string(), AccountStats { playtime, kills, deaths, gralats, levels });
+            }
+        }
+        let stats = Self { path: Some(path.to_path_buf()), accounts: Mutex::new(accounts), ..Default::default() };
+        stats.rank();
+        stats
+    }
+
+    /// Add to a counted statistic of an account
+    ///
+    /// `levels` is counted by [`visited`](Self::visited) instead.
+    pub fn add(&self, account: &str, stat: Stat, amount: u64) {
+        if self.disabled {
+            return;
+        }
+        let mut accounts = self.accounts.lock();
+        let stats = accounts.entry(account.to_string()).or_default();
+        match stat {
+            Stat::Playtime => stats.playtime += amount,
+            Stat::Kills => stats.kills += amount,
+            Stat::Deaths => stats.deaths += amount,
+            Stat::Gralats => stats.gralats += amount,
+            Stat::Levels => {}
+        }
+    }
+
+    /// Count a level visit
+    pub fn visited(&self, account: &str, level: &str) {
+        if self.disabled {
+            return;
+        }
+        self.accounts.lock().entry(account.to_string()).or_default().levels.insert(level.to_lowercase());
+    }
+
+    /// Remember who hurt a player last
+    pub fn hit(&self, victim: PlayerID, attacker: &str, now: Instant) {
+        if self.disabled {
+            return;
+        }
+        self.hits.lock().insert(victim, (attacker.to_string(), now));
+    }
+
+    /// Count a death, and a kill for the last attacker if they hit in time
+    ///
+    /// # Returns
+    /// The killer's account
+    pub fn died(&self, id: PlayerID, account: &str, now: Instant) -> Option<String> {
+        if self.disabled {
+            return None;
+        }
+        self.add(account, Stat::Deaths, 1);
+        let (killer, at) = self.hits.lock().remove(&id)?;
+        if now.duration_since(at) > KILL_WINDOW || killer == account {
+            return None;
+        }
+        self.add(&killer, Stat::Kills, 1);
+        Some(killer)
+    }
+
+    /// Start counting a player's playtime
+    pub fn joined(&self, id: PlayerID, account: &str, now: Instant) {
+        if self.disabled {
+            return;
+        }
+        self.sessions.lock().insert(id, (account.to_string(), now));
+    }
+
+    /// Stop counting a player's playtime
+    pub fn left(&self, id: PlayerID, now: Instant) {
+        self.hits.lock().remove(&id);
+        if let Some((account, since)) = self.sessions.lock().remove(&id) {
+            self.add(&account, Stat::Playtime, now.duration_since(since).as_secs());
+        }
+    }
+
+    /// Get an account's statistics, with playtime up to the last aggregation
+    pub fn get(&self, account: &str) -> AccountStats {
+        self.accounts.lock().get(account).cloned().unwrap_or_default()
+    }
+
+    /// Get a leaderboard as of the last aggregation, best first
+    pub fn leaderboard(&self, stat: Stat) -> Vec<(String, u64)> {
+        self.leaderboards.read().get(&stat).cloned().unwrap_or_default()
+    }
+
+    /// Count the playtime of online players, rank the leaderboards and save
+    ///
+    /// Failures to save are logged; the statistics keep working in memory.
+    pub fn aggregate(&self, now: Instant) {
+        let online: Vec<(String, u64)> = self.sessions.lock().values_mut()
+            .map(|(account, since)| {
+                // Whole seconds only, so the remainder counts next time
+                let secs = now.duration_since(*since).as_secs();
+                *since += Duration::from_secs(secs);
+                (account.clone(), secs)
+            })
+            .collect();
+        for (account, secs) in online {
+            self.add(&account, Stat::Playtime, secs);
+        }
+        self.rank();
+        self.save();
+    }
+
+    /// Rebuild the leaderboards
+    fn rank(&self) {
+        let accounts = self.accounts.lock();
+        let leaderboards = Stat::ALL.into_iter()
+            .map(|stat| {
+                let mut board: Vec<(String, u64)> = accounts.iter()
+                    .map(|(account, stats)| (account.clone(), stats.get(stat)))
+                    .filter(|(_, value)| *value > 0)
+                    .collect();
+                board.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                board.truncate(LEADERBOARD_SIZE);
+                (stat, board)
+            })
+            .collect();
+        *self.leaderboards.write() = leaderboards;
+    }
+
+    /// Write the statistics file
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let mut lines: Vec<String> = self.accounts.lock().iter()
+            .map(|(account, s)| {
+                let levels: Vec<&str> = s.levels.iter().map(String::as_str).collect();
+                format!("{}\t{}\t{}\t{}\t{}\t{}\n", account, s.playtime, s.kills, s.deaths, s.gralats, levels.join(","))
+            })
+            .collect();
+        lines.sort();
+        // Written aside and renamed over, so a crash mid-write keeps the last file
+        let temp = path.with_extension("txt.tmp");
+        if let Err(e) = std::fs::write(&temp, lines.concat()).and_then(|()| std::fs::rename(&temp, path)) {
+            tracing::warn!("Can't save {}: {}", path.display(), e);
+        }
+    }
+
+    /// Get every leaderboard as JSON (`GET /leaderboard`)
+    pub fn to_json(&self) -> serde_json::Value {
+        let leaderboards = self.leaderboards.read();
+        let boards: serde_json::Map<String, serde_json::Value> = Stat::ALL.into_iter()
+            .map(|stat| {
+                let board = leaderboards.get(&stat).map(Vec::as_slice).unwrap_or_default().iter()
+                    .map(|(account, value)| serde_json::json!({ "account": account, "value": value }))
+                    .collect();
+                (stat.name().to_string(), serde_json::Value::Array(board))
+            })
+            .collect();
+        serde_json::Value::Object(boards)
+    }
+}
+
+/// Parse a `gr.leaderboard,<stat>` trigger action
+pub fn parse_trigger(action: &str) -> Option<Stat> {
+    let (command, stat) = action.split_once(',')?;
+    match command.trim() {
+        "gr.leaderboard" => Stat::parse(stat),
+        _ => None,
+    }
+}
+
+/// Build the PLO_TRIGGERACTION answering a leaderboard trigger
+///
+/// # Format
+/// ```text
+/// {GSHORT 0}{GINT npc}{GCHAR x}{GCHAR y}gr.leaderboard,{stat}[,{account},{value}]...
+/// ```
+pub fn leaderboard_reply(npc: u32, x: u8, y: u8, stat: Stat, board: &[(String, u64)]) -> Vec<u8> {
+    let mut data = BytesMut::new();
+    write_gshort(&mut data, 0);
+    write_gint(&mut data, npc as i32);
+    write_gchar(&mut data, x as i8);
+    write_gchar(&mut data, y as i8);
+    let mut action = format!("gr.leaderboard,{}", stat.name());
+    for (account, value) in board {
+        action.push_str(&format!(",{},{}", account, value));
+    }
+    data.extend_from_slice(action.as_bytes());
+    data.to_vec()
+}
+
+/// Count playtime from joins and leaves, and aggregate every `statsinterval`
+///
+/// Returns at once when `statsinterval` is 0.
+pub async fn run(context: Arc<ServerContext>, mut shutdown: watch::Receiver<bool>) {
+    let interval = context.config().read().stats_interval;
+    if interval == 0 {
+        return;
+    }
+    let mut events = context.events().subscribe();
+    let mut ticker = tokio::time::interval(Duration::from_secs(interval));
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let stats = context.player_stats();
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(GameEvent::PlayerJoined { id, account, .. }) => stats.joined(id, &account, Instant::now()),
+                Ok(GameEvent::PlayerLeft { id, .. }) => stats.left(id, Instant::now()),
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => tracing::warn!("Player statistics skipped {} events", skipped),
+                Err(RecvError::Closed) => break,
+            },
+            _ = ticker.tick() => stats.aggregate(Instant::now()),
+            _ = shutdown.changed() => {
+                if *shutdown.borrow() {
+                    break;
+                }
+            }
+        }
+    }
+    stats.aggregate(Instant::now());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kills_and_leaderboards() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(STATS_FILE);
+        let stats = PlayerStats::load(&path);
+        let start = Instant::now();
+
+        stats.joined(PlayerID(1), "Bob", start);
+        stats.hit(PlayerID(2), "Bob", start);
+        assert_eq!(stats.died(PlayerID(2), "Alice", start + Duration::from_secs(2)).as_deref(), Some("Bob"));
+        stats.hit(PlayerID(2), "Bob", start);
+        assert_eq!(stats.died(PlayerID(2), "Alice", start + KILL_WINDOW * 2), None);
+        stats.add("Alice", Stat::Gralats, 30);
+        stats.visited("Alice", "Town.nw");
+        stats.visited("Alice", "town.nw");
+
+        // Leaderboards only change on aggregation
+        assert!(stats.leaderboard(Stat::Kills).is_empty());
+        stats.aggregate(start + Duration::from_millis(90_500));
+        assert_eq!(stats.leaderboard(Stat::Kills), [("Bob".to_string(), 1)]);
+        assert_eq!(stats.leaderboard(Stat::Deaths), [("Alice".to_string(), 2)]);
+        assert_eq!(stats.leaderboard(Stat::Playtime), [("Bob".to_string(), 90)]);
+        stats.left(PlayerID(1), start + Duration::from_secs(100));
+        assert_eq!(stats.get("Bob").playtime, 100);
+
+        let loaded = PlayerStats::load(&path);
+        assert_eq!(loaded.get("Alice"), AccountStats {
+            deaths: 2,
+            gralats: 30,
+            levels: ["town.nw".to_string()].into(),
+            ..Default::default()
+        });
+        assert_eq!(loaded.to_json()["gralats"], serde_json::json!([{ "account": "Alice", "value": 30 }]));
+    }
+
+    #[test]
+    fn test_disabled_counts_nothing() {
+        let stats = PlayerStats::disabled();
+        let start = Instant::now();
+        stats.joined(PlayerID(1), "Bob", start);
+        stats.hit(PlayerID(2), "Bob", start);
+        assert_eq!(stats.died(PlayerID(2), "Alice", start), None);
+        stats.add("Alice", Stat::Gralats, 30);
+        stats.visited("Alice", "town.nw");
+        stats.left(PlayerID(1), start + Duration::from_secs(100));
+        assert_eq!(stats.get("Alice"), AccountStats::default());
+        assert_eq!(stats.get("Bob"), AccountStats::default());
+    }
+
+    #[test]
+    fn test_parse_trigger() {
+        assert_eq!(parse_trigger("gr.leaderboard, Kills"), Some(Stat::Kills));
+        assert_eq!(parse_trigger("gr.leaderboard,score"), None);
+        assert_eq!(parse_trigger("gr.leaderboard"), None);
+        let reply = leaderboard_reply(5, 30, 31, Stat::Kills, &[("Bob".into(), 12)]);
+        assert!(reply.ends_with(b"gr.leaderboard,kills,Bob,12"));
+    }
+}
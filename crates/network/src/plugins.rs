@@ -0,0 +1,242 @@
+//! # Plugins
+//!
+//! Built with the `plugins` feature, downstream crates extend the server
+//! without forking it: a [`Plugin`] is a trait object installed on the
+//! [`ServerContext`] at startup, before `GServer::with_context` and the tick
+//! loop start. Its [`Plugin::register`] adds, through a [`PluginRegistrar`]:
+//!
+//! - Packet handlers by packet id, consulted before the built-in handlers; a
+//!   handler returns [`PacketVerdict::Continue`] to leave a packet to them,
+//!   and may handle ids the server doesn't know
+//! - Player chat commands ([`crate::chatcommands`])
+//! - PLI_REQUESTTEXT / PLI_SENDTEXT commands ([`crate::clienttext`])
+//! - Tick hooks, run by the tick loop at their interval once
+//!   [`Plugins::attach_tick_hooks`] is called
+//!
+//! ```rust,ignore
+//! struct Greeter;
+//!
+//! impl Plugin for Greeter {
+//!     fn name(&self) -> &str { "greeter" }
+//!
+//!     fn register(&self, registrar: &mut PluginRegistrar<'_>) {
+//!         registrar.chat_command("hello", |command, _| vec![ChatAction::Reply(format!("Hi {}", command.account))]);
+//!         registrar.tick_hook("census", Duration::from_secs(60), |context| {
+//!             tracing::info!("{} players", context.online_count());
+//!         });
+//!     }
+//! }
+//!
+//! context.install_plugin(&Greeter);
+//! ```
+
+use crate::chatcommands::{ChatAction, ChatCommand};
+use crate::clienttext::{ClientText, TextAction};
+use crate::context::ServerContext;
+use gserver_core::PlayerID;
+use gserver_game::TickLoop;
+use gserver_protocol::PacketOut;
+use parking_lot::{Mutex, RwLock};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A server extension
+pub trait Plugin: Send + Sync {
+    /// Name in logs and tick timer names
+    fn name(&self) -> &str;
+
+    /// Register the plugin's handlers, once when it is installed
+    fn register(&self, registrar: &mut PluginRegistrar<'_>);
+}
+
+/// A packet from a logged-in client, offered to plugins
+#[derive(Debug, Clone, Copy)]
+pub struct PluginPacket<'a> {
+    /// Player who sent it
+    pub player: PlayerID,
+
+    /// Their account
+    pub account: &'a str,
+
+    /// Packet id (PLI_*)
+    pub packet_id: u8,
+
+    /// Packet body
+    pub data: &'a [u8],
+}
+
+/// What a plugin packet handler did with a packet
+#[derive(Debug, Clone)]
+pub enum PacketVerdict {
+    /// Not handled; the next plugin or the built-in handler gets it
+    Continue,
+
+    /// Handled; the packets are sent to the player and the built-in handler is skipped
+    Handled(Vec<PacketOut>),
+}
+
+/// Handler of one packet id
+pub type PluginPacketHandler = Arc<dyn Fn(&PluginPacket<'_>, &ServerContext) -> PacketVerdict + Send + Sync>;
+
+/// Hook run by the tick loop
+type TickHook = Box<dyn FnMut(&ServerContext) + Send>;
+
+/// Installed plugins and the handlers they registered
+#[derive(Default)]
+pub struct Plugins {
+    names: RwLock<Vec<String>>,
+
+    /// Packet id → handlers in install order
+    packet_handlers: RwLock<HashMap<u8, Vec<PluginPacketHandler>>>,
+
+    /// Tick hooks not yet attached to a tick loop
+    tick_hooks: Mutex<Vec<(String, Duration, TickHook)>>,
+}
+
+impl Plugins {
+    /// Create an empty plugin list
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Install a plugin, registering its handlers
+    pub fn install(&self, context: &ServerContext, plugin: &dyn Plugin) {
+        let mut registrar = PluginRegistrar { plugin: plugin.name().to_string(), context, plugins: self };
+        plugin.register(&mut registrar);
+        tracing::info!("Installed plugin {}", plugin.name());
+        self.names.write().push(plugin.name().to_string());
+    }
+
+    /// Get the installed plugins' names, in install order
+    pub fn names(&self) -> Vec<String> {
+        self.names.read().clone()
+    }
+
+    /// Check if a plugin handles a packet id
+    pub fn has_packet_handler(&self, packet_id: u8) -> bool {
+        self.packet_handlers.read().contains_key(&packet_id)
+    }
+
+    /// Offer a packet to the plugins that handle its id, in install order
+    pub fn handle_packet(&self, packet: &PluginPacket<'_>, context: &ServerContext) -> PacketVerdict {
+        let handlers = self.packet_handlers.read().get(&packet.packet_id).cloned().unwrap_or_default();
+        handlers.iter()
+            .map(|handler| handler(packet, context))
+            .find(|verdict| matches!(verdict, PacketVerdict::Handled(_)))
+            .unwrap_or(PacketVerdict::Continue)
+    }
+
+    /// Add the registered tick hooks to a tick loop
+    ///
+    /// # Returns
+    /// How many were added (hooks registered later need another call)
+    pub fn attach_tick_hooks(&self, tick_loop: &mut TickLoop, context: Arc<ServerContext>) -> usize {
+        let hooks = std::mem::take(&mut *self.tick_hooks.lock());
+        let count = hooks.len();
+        for (name, interval, mut hook) in hooks {
+            let context = context.clone();
+            tick_loop.add_timer(&name, interval, move |_| hook(&context));
+        }
+        count
+    }
+}
+
+impl std::fmt::Debug for Plugins {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut packets: Vec<u8> = self.packet_handlers.read().keys().copied().collect();
+        packets.sort();
+        f.debug_struct("Plugins").field("names", &self.names()).field("packets", &packets).finish()
+    }
+}
+
+/// What a plugin registers its handlers through
+pub struct PluginRegistrar<'a> {
+    plugin: String,
+    context: &'a ServerContext,
+    plugins: &'a Plugins,
+}
+
+impl PluginRegistrar<'_> {
+    /// Get the server context, e.g. for the config or to register more services
+    pub fn context(&self) -> &ServerContext {
+        self.context
+    }
+
+    /// Handle packets with an id before the built-in handler
+    pub fn packet_handler<F>(&mut self, packet_id: u8, handler: F)
+    where
+        F: Fn(&PluginPacket<'_>, &ServerContext) -> PacketVerdict + Send + Sync + 'static,
+    {
+        self.plugins.packet_handlers.write().entry(packet_id).or_default().push(Arc::new(handler));
+    }
+
+    /// Add or replace a player chat command
+    pub fn chat_command<F>(&mut self, name: &str, handler: F)
+    where
+        F: Fn(&ChatCommand, &ServerContext) -> Vec<ChatAction> + Send + Sync + 'static,
+    {
+        self.context.chat_commands().register(name, handler);
+    }
+
+    /// Add or replace a client text command
+    pub fn client_text<F>(&mut self, kind: &str, option: Option<&str>, handler: F)
+    where
+        F: Fn(&ClientText, &ServerContext) -> Vec<TextAction> + Send + Sync + 'static,
+    {
+        self.context.client_texts().register(kind, option, handler);
+    }
+
+    /// Run a hook every `interval` on the tick loop (timer `plugin <name>: <hook>`)
+    pub fn tick_hook<F>(&mut self, name: &str, interval: Duration, hook: F)
+    where
+        F: FnMut(&ServerContext) + Send + 'static,
+    {
+        let name = format!("plugin {}: {}", self.plugin, name);
+        self.plugins.tick_hooks.lock().push((name, interval, Box::new(hook)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gserver_protocol::PacketTypeOut;
+
+    struct Echo;
+
+    impl Plugin for Echo {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        fn register(&self, registrar: &mut PluginRegistrar<'_>) {
+            registrar.packet_handler(200, |packet, _| {
+                PacketVerdict::Handled(vec![PacketOut::new(PacketTypeOut::ServerText, packet.data.to_vec())])
+            });
+            registrar.packet_handler(6, |_, _| PacketVerdict::Continue);
+            registrar.chat_command("echo", |command, _| vec![ChatAction::Reply(command.args.join(" "))]);
+            registrar.tick_hook("count", Duration::from_secs(1), |_| {});
+        }
+    }
+
+    #[test]
+    fn test_install_plugin() {
+        let context = Arc::new(ServerContext::new("servers/test", Default::default()));
+        context.install_plugin(&Echo);
+        let plugins = context.plugins();
+        assert_eq!(plugins.names(), ["echo"]);
+
+        let packet = |packet_id| PluginPacket { player: PlayerID::new(1), account: "Bob", packet_id, data: b"hi" };
+        match plugins.handle_packet(&packet(200), &context) {
+            PacketVerdict::Handled(replies) => assert_eq!(replies[0].packet_type, PacketTypeOut::ServerText),
+            PacketVerdict::Continue => panic!("packet 200 wasn't handled"),
+        }
+        assert!(matches!(plugins.handle_packet(&packet(6), &context), PacketVerdict::Continue));
+        assert!(!plugins.has_packet_handler(7));
+        assert!(context.chat_commands().has_command("echo"));
+
+        let mut tick_loop = TickLoop::new(20);
+        assert_eq!(plugins.attach_tick_hooks(&mut tick_loop, context.clone()), 1);
+        assert_eq!(plugins.attach_tick_hooks(&mut tick_loop, context.clone()), 0);
+    }
+}
@@ -0,0 +1,164 @@
+//! # PROXY Protocol
+//!
+//! Servers behind a load balancer or an anti-DDoS proxy only see the
+//! proxy's address. With `proxyprotocol` on, the proxy puts a PROXY v2
+//! header (HAProxy `send-proxy-v2`) in front of each connection, and the
+//! address in it becomes the connection's peer address: the one logged,
+//! matched by reconnect sessions, and sent as the IPADDR prop.
+//!
+//! Only connections from `proxytrusted` addresses are expected to send the
+//! header (none if the list is empty); others are taken as direct clients,
+//! so nobody can claim an address by sending a header themselves.
+//! On the TLS port the header comes before the handshake.
+//!
+//! # Header Format (v2)
+//! ```text
+//! {12 bytes signature}{ver_cmd}{family}{u16 length}{addresses, length bytes}
+//! TCP over IPv4: {src ip: 4}{dst ip: 4}{src port: 2}{dst port: 2}
+//! TCP over IPv6: {src ip: 16}{dst ip: 16}{src port: 2}{dst port: 2}
+//! ```
+//! A `LOCAL` command (the proxy's own health checks) keeps the proxy's
+//! address. The text format of PROXY v1 isn't supported.
+
+use gserver_config::ServerConfig as GameConfig;
+use gserver_core::{GServerError, Result};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::net::TcpStream;
+
+/// First 12 bytes of every v2 header
+const SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+
+/// Longest a proxy may take to send the header
+pub const HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Check if a connection from `peer` starts with a PROXY header
+pub fn expects_header(config: &GameConfig, peer: IpAddr) -> bool {
+    config.proxy_protocol && config.proxy_trusted.contains(&peer)
+}
+
+/// Parse the address block of a v2 header
+///
+/// # Arguments
+/// * `fixed` - The first 16 bytes (signature, version/command, family, length)
+/// * `addresses` - The `length` bytes after them
+///
+/// # Returns
+/// The client's address, or `None` for `LOCAL` headers and families other
+/// than TCP over IPv4/IPv6
+pub fn parse_v2(fixed: &[u8; 16], addresses: &[u8]) -> Result<Option<SocketAddr>> {
+    if fixed[..12] != SIGNATURE {
+        return Err(GServerError::InvalidData("Missing PROXY v2 signature".into()));
+    }
+    let (version, command) = (fixed[12] >> 4, fixed[12] & 0x0F);
+    if version != 2 {
+        return Err(GServerError::InvalidData(format!("Unsupported PROXY version {}", version)));
+    }
+    match command {
+        0 => return Ok(None),
+        1 => {}
+        _ => return Err(GServerError::InvalidData(format!("Unknown PROXY command {}", command))),
+    }
+
+    let port = |at: usize| u16::from_be_bytes([addresses[at], addresses[at + 1]]);
+    let source = match fixed[13] {
+        // TCP over IPv4
+        0x11 if addresses.len() >= 12 => {
+            let ip: [u8; 4] = addresses[..4].try_into().unwrap_or_default();
+            SocketAddr::new(Ipv4Addr::from(ip).into(), port(8))
+        }
+        // TCP over IPv6
+        0x21 if addresses.len() >= 36 => {
+            let ip: [u8; 16] = addresses[..16].try_into().unwrap_or_default();
+            SocketAddr::new(Ipv6Addr::from(ip).into(), port(32))
+        }
+        0x11 | 0x21 => return Err(GServerError::InvalidData("PROXY address block too short".into())),
+        _ => return Ok(None),
+    };
+    Ok(Some(source))
+}
+
+/// Read a v2 header off a connection, leaving the stream right after it
+///
+/// # Returns
+/// The client's address, if the header carries one
+pub async fn read_header<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<SocketAddr>> {
+    let mut fixed = [0u8; 16];
+    reader.read_exact(&mut fixed).await?;
+    let length = u16::from_be_bytes([fixed[14], fixed[15]]) as usize;
+    let mut addresses = vec![0u8; length];
+    reader.read_exact(&mut addresses).await?;
+    parse_v2(&fixed, &addresses)
+}
+
+/// Find the real address of a connection that may come through a proxy
+///
+/// # Returns
+/// The address to use for the connection, or `None` if its header was
+/// missing or broken and it has to be dropped
+pub async fn resolve(socket: &mut TcpStream, peer: SocketAddr, config: &parking_lot::RwLock<GameConfig>) -> Option<SocketAddr> {
+    if !expects_header(&config.read(), peer.ip()) {
+        return Some(peer);
+    }
+    match tokio::time::timeout(HEADER_TIMEOUT, read_header(socket)).await {
+        Ok(Ok(client)) => {
+            let client = client.unwrap_or(peer);
+            tracing::debug!("Connection from {} forwarded for {}", peer, client);
+            Some(client)
+        }
+        Ok(Err(e)) => {
+            tracing::warn!("Dropped connection from {}: bad PROXY header: {}", peer, e);
+            None
+        }
+        Err(_) => {
+            tracing::warn!("Dropped connection from {}: no PROXY header", peer);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(command: u8, family: u8, addresses: &[u8]) -> Vec<u8> {
+        let mut data = SIGNATURE.to_vec();
+        data.extend([0x20 | command, family]);
+        data.extend((addresses.len() as u16).to_be_bytes());
+        data.extend_from_slice(addresses);
+        data
+    }
+
+    #[tokio::test]
+    async fn test_read_v2_header() {
+        // 203.0.113.7:40000 -> 10.0.0.1:14802, then the first client bytes
+        let mut data = header(1, 0x11, &[203, 0, 113, 7, 10, 0, 0, 1, 0x9c, 0x40, 0x39, 0xd2]);
+        data.extend_from_slice(b"login");
+        let mut reader = &data[..];
+        assert_eq!(read_header(&mut reader).await.unwrap(), Some("203.0.113.7:40000".parse().unwrap()));
+        assert_eq!(reader, b"login");
+
+        let mut v6 = [0u8; 36];
+        v6[15] = 1;
+        v6[32..34].copy_from_slice(&1234u16.to_be_bytes());
+        assert_eq!(read_header(&mut &header(1, 0x21, &v6)[..]).await.unwrap(), Some("[::1]:1234".parse().unwrap()));
+
+        assert_eq!(read_header(&mut &header(0, 0x00, &[])[..]).await.unwrap(), None);
+        assert!(read_header(&mut &header(1, 0x11, &[1, 2, 3])[..]).await.is_err());
+        assert!(read_header(&mut &b"GET / HTTP/1.1\r\n\r\n"[..]).await.is_err());
+    }
+
+    #[test]
+    fn test_expects_header() {
+        let mut config = GameConfig::default();
+        let proxy: IpAddr = "10.0.0.5".parse().unwrap();
+        assert!(!expects_header(&config, proxy));
+        config.proxy_protocol = true;
+        assert!(!expects_header(&config, proxy));
+        config.proxy_trusted = vec!["10.0.0.6".parse().unwrap()];
+        assert!(!expects_header(&config, proxy));
+        config.proxy_trusted.push(proxy);
+        assert!(expects_header(&config, proxy));
+    }
+}
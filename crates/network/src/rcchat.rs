@@ -0,0 +1,112 @@
+//! # RC Staff Chat
+//!
+//! Every connected RC is in one staff chat channel. An RC's PLI_RC_CHAT line
+//! without a leading `/` is published as [`GameEvent::RcChat`] and relayed to
+//! every RC by the server's RC notifier, together with server notices
+//! (integrity violations, word filter matches, [`GameEvent::StaffNotice`]).
+//! `/msg <account> <text>` reaches only that account's RCs.
+//!
+//! The last channel lines are kept in [`RcChatHistory`] and sent to an RC
+//! when it logs in, so staff joining late see what was said.
+//!
+//! # C++ Equivalence
+//! Lines are formatted like `PlayerRC::msgPLI_RC_CHAT`: `account: message`.
+//!
+//! [`GameEvent::RcChat`]: gserver_game::GameEvent::RcChat
+//! [`GameEvent::StaffNotice`]: gserver_game::GameEvent::StaffNotice
+
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+
+/// Default number of channel lines kept for RCs that join later
+pub const DEFAULT_RC_HISTORY: usize = 50;
+
+/// Recent lines of the staff chat channel
+#[derive(Debug)]
+pub struct RcChatHistory {
+    lines: Mutex<VecDeque<String>>,
+    capacity: usize,
+}
+
+impl RcChatHistory {
+    /// Create a history keeping up to `capacity` lines
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    /// Add a line, dropping the oldest when full
+    pub fn record(&self, line: String) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut lines = self.lines.lock();
+        if lines.len() == self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    /// Get the kept lines, oldest first
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.lock().iter().cloned().collect()
+    }
+}
+
+impl Default for RcChatHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_RC_HISTORY)
+    }
+}
+
+/// Format a channel line
+pub fn format_line(account: &str, message: &str) -> String {
+    format!("{}: {}", account, message)
+}
+
+/// Format a `/msg` line as its sender and target see it
+pub fn format_private(from: &str, to: &str, message: &str) -> String {
+    format!("(to {}) {}: {}", to, from, message)
+}
+
+/// Split `/msg <account> <text>` into account and text
+///
+/// # Returns
+/// `None` if the line isn't a `/msg` or has no text
+pub fn parse_msg(text: &str) -> Option<(&str, &str)> {
+    let rest = text.strip_prefix("/msg")?;
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let (target, message) = rest.trim_start().split_once(char::is_whitespace)?;
+    let message = message.trim();
+    (!message.is_empty()).then_some((target, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_keeps_latest_lines() {
+        let history = RcChatHistory::new(2);
+        for line in ["a: 1", "b: 2", "c: 3"] {
+            history.record(line.to_string());
+        }
+        assert_eq!(history.lines(), ["b: 2", "c: 3"]);
+
+        let disabled = RcChatHistory::new(0);
+        disabled.record("a: 1".into());
+        assert!(disabled.lines().is_empty());
+    }
+
+    #[test]
+    fn test_parse_msg() {
+        assert_eq!(parse_msg("/msg Manager  ban him?"), Some(("Manager", "ban him?")));
+        assert_eq!(parse_msg("/msg Manager"), None);
+        assert_eq!(parse_msg("/msgManager hi"), None);
+        assert_eq!(parse_msg("hello"), None);
+    }
+}
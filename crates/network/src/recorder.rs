@@ -0,0 +1,160 @@
+//! # Session Recorder
+//!
+//! Records the sessions of the accounts in `recordsessions` to
+//! `recordings/<account>-<unix time>.stream`, in the session format the
+//! regression tests in `src/connection/replay.rs` replay (see
+//! `fixtures/README.md`): each client bundle as it arrived, then the decoded
+//! server output that followed it, with the time of each as a comment. A
+//! protocol bug a player reports can be recorded on the live server and
+//! checked in as a fixture that reproduces it.
+//!
+//! Recordings are made to be shared:
+//!
+//! - The password is removed from the login bundle, which is compressed again
+//! - World time (PLO_NEWWORLDTIME) is masked with `??`
+//!
+//! Packets caused by other players are in the server output too; cut them
+//! before replaying against the empty fixture server.
+
+use gserver_config::ServerConfig as GameConfig;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Folder of the server directory recordings are written to
+pub const RECORDINGS_DIR: &str = "recordings";
+
+/// PLO_NEWWORLDTIME, GCHAR encoded
+const WORLD_TIME_PACKET: u8 = 42 + 32;
+
+/// Bytes per hex line
+const LINE_BYTES: usize = 32;
+
+/// Check if an account's sessions are recorded
+pub fn is_recorded(config: &GameConfig, account: &str) -> bool {
+    config.record_sessions.iter().any(|a| a.eq_ignore_ascii_case(account))
+}
+
+/// What a recording starts with
+#[derive(Debug, Clone, Copy)]
+pub struct SessionHeader<'a> {
+    /// Account from the login packet
+    pub account: &'a str,
+
+    /// Client version from the login packet
+    pub client_version: &'a str,
+
+    /// Encryption generation
+    pub gen: u8,
+
+    /// Encryption key from the login packet
+    pub key: u8,
+}
+
+/// A session being recorded
+#[derive(Debug)]
+pub struct Recording {
+    path: PathBuf,
+    started: Instant,
+    out: BufWriter<File>,
+}
+
+impl Recording {
+    /// Start a recording in `dir`
+    ///
+    /// # Arguments
+    /// * `login` - Decompressed login packet, password already removed
+    pub fn create(dir: &Path, header: &SessionHeader<'_>, login: &[u8], now: u64) -> io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let account: String = header.account.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        let path = dir.join(format!("{}-{}.stream", account, now));
+        let mut recording = Self { out: BufWriter::new(File::create(&path)?), path, started: Instant::now() };
+
+        let mut compressed = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        compressed.write_all(login)?;
+        let login = compressed.finish()?;
+
+        writeln!(recording.out, "# Recorded session of {} ({}), started at unix time {}", header.account, header.client_version, now)?;
+        writeln!(recording.out, "# The login bundle's password was removed")?;
+        writeln!(recording.out)?;
+        writeln!(recording.out, "gen {}", header.gen)?;
+        writeln!(recording.out, "key {}", header.key)?;
+        writeln!(recording.out)?;
+        writeln!(recording.out, "# +0.000s PLI_LOGIN")?;
+        let login: Vec<Option<u8>> = login.into_iter().map(Some).collect();
+        recording.out.write_all(hex_lines(">", &login, usize::MAX).as_bytes())?;
+        recording.out.flush()?;
+        Ok(recording)
+    }
+
+    /// Get the file being written
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Record a bundle from the client, as it arrived
+    pub fn client_bundle(&mut self, bundle: &[u8]) -> io::Result<()> {
+        let bundle: Vec<Option<u8>> = bundle.iter().copied().map(Some).collect();
+        let lines = hex_lines(">", &bundle, usize::MAX);
+        self.write(&format!("\n# +{:.3}s client\n{}", self.started.elapsed().as_secs_f64(), lines))
+    }
+
+    /// Record decoded packets sent to the client
+    pub fn server_output(&mut self, packets: &[u8]) -> io::Result<()> {
+        let lines = hex_lines("<", &mask_world_time(packets), LINE_BYTES);
+        self.write(&format!("# +{:.3}s server\n{}", self.started.elapsed().as_secs_f64(), lines))
+    }
+
+    fn write(&mut self, text: &str) -> io::Result<()> {
+        self.out.write_all(text.as_bytes())?;
+        self.out.flush()
+    }
+}
+
+/// Mask the time in world time packets, which differs on every run
+fn mask_world_time(packets: &[u8]) -> Vec<Option<u8>> {
+    let mut masked: Vec<Option<u8>> = packets.iter().copied().map(Some).collect();
+    let mut start = 0;
+    for end in packets.iter().enumerate().filter(|&(_, &b)| b == b'\n').map(|(i, _)| i) {
+        if end - start == 5 && packets[start] == WORLD_TIME_PACKET {
+            masked[start + 1..end].fill(None);
+        }
+        start = end + 1;
+    }
+    masked
+}
+
+/// Format bytes as `tag xx xx ...` lines of at most `per_line` bytes
+fn hex_lines(tag: &str, bytes: &[Option<u8>], per_line: usize) -> String {
+    bytes.chunks(per_line.max(1))
+        .map(|chunk| {
+            let hex: Vec<String> = chunk.iter().map(|b| b.map_or("??".to_string(), |b| format!("{:02x}", b))).collect();
+            format!("{} {}\n", tag, hex.join(" "))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recording_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let header = SessionHeader { account: "Bob/1", client_version: "G3D0311C", gen: 5, key: 73 };
+        let mut recording = Recording::create(dir.path(), &header, b"login", 1700000000).unwrap();
+        assert_eq!(recording.path(), dir.path().join("Bob_1-1700000000.stream"));
+
+        recording.client_bundle(&[0x02, 0x47]).unwrap();
+        recording.server_output(&[0x4a, 0x20, 0x21, 0x22, 0x23, 0x0a, 0x2a, 0x0a]).unwrap();
+        let text = std::fs::read_to_string(recording.path()).unwrap();
+        assert!(text.contains("gen 5\nkey 73\n") && text.contains("\n> 78 9c"), "{}", text);
+        assert!(text.contains("> 02 47\n# +") && text.ends_with("< 4a ?? ?? ?? ?? 0a 2a 0a\n"), "{}", text);
+
+        let config = GameConfig { record_sessions: vec!["bob/1".into()], ..Default::default() };
+        assert!(is_recorded(&config, "Bob/1") && !is_recorded(&config, "Bob"));
+    }
+}
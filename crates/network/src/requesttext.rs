@@ -0,0 +1,190 @@
+//! # Listserver Text Requests
+//!
+//! SVI_REQUESTTEXT lines are comma token strings like
+//! `GraalEngine,lister,serverlist,...`, each for one player. The first two
+//! tokens pick a handler from a [`RequestTextRegistry`], so a new command is
+//! one [`RequestTextRegistry::register`] call instead of another parser arm.
+//!
+//! # Built-in Commands
+//! - `Listserver,SetRemote` - The listserver accepted remote mode
+//! - `Listserver,TClientLogin,<account>,<1|0>` - Verification of a player's
+//!   login, sent as [`GameCommand::LoginVerified`] and published as
+//!   [`GameEvent::LoginVerified`] for logins waiting on it (see [`crate::verification`])
+//! - `GraalEngine,lister,...`, `GraalEngine,profile,...`,
+//!   `GraalEngine,pmservers,...`, `GraalEngine,pmguilds,...`,
+//!   `GraalEngine,pmserverplayers,...` (and the same for `-Serverlist`) -
+//!   Answers to the player's serverlist, profile get/set and PM server
+//!   queries (see [`crate::clienttext`]), forwarded as PLO_SERVERTEXT
+//!
+//! # C++ Equivalence
+//! `ServerList::msgSVI_REQUESTTEXT` forwards the text to the player.
+
+use crate::irc::split_tokens;
+use gserver_core::PlayerID;
+use gserver_game::{CommandQueue, EventBus, GameCommand, GameEvent};
+use gserver_protocol::PacketTypeOut;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// One SVI_REQUESTTEXT line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextRequest {
+    /// Player the request is about
+    pub player: PlayerID,
+
+    /// The raw line
+    pub text: String,
+
+    /// The line split into comma tokens
+    pub tokens: Vec<String>,
+}
+
+impl TextRequest {
+    /// Parse a request line
+    pub fn new(player: PlayerID, text: &str) -> Self {
+        Self { player, text: text.to_string(), tokens: split_tokens(text) }
+    }
+
+    /// Get the tokens after the service and command
+    pub fn args(&self) -> &[String] {
+        self.tokens.get(2..).unwrap_or_default()
+    }
+}
+
+/// Handler for one command
+///
+/// Returns the lines to send back to the listserver (SVO_SENDTEXT).
+pub type TextHandler = Arc<dyn Fn(&TextRequest) -> Vec<String> + Send + Sync>;
+
+/// Handlers of SVI_REQUESTTEXT commands, by service and command
+pub struct RequestTextRegistry {
+    handlers: HashMap<(String, String), TextHandler>,
+}
+
+impl RequestTextRegistry {
+    /// Create a registry with the built-in commands
+    ///
+    /// # Arguments
+    /// * `events` - Where forwarded text and verification results are published
+    /// * `commands` - Where verification results are sent to be acted on
+    pub fn new(events: EventBus, commands: CommandQueue) -> Self {
+        let mut registry = Self::empty();
+        registry.register("Listserver", "SetRemote", |_| {
+            tracing::info!("Listserver confirmed: Remote mode enabled");
+            Vec::new()
+        });
+
+        let verify_events = events.clone();
+        registry.register("Listserver", "TClientLogin", move |request| {
+            let (account, verified) = match request.args() {
+                [account, result, ..] => (account.as_str(), result == "1"),
+                _ => return Vec::new(),
+            };
+            tracing::info!("Listserver {} the login of {} (player {})",
+                if verified { "verified" } else { "rejected" }, account, request.player.get());
+            commands.send(GameCommand::LoginVerified { player: request.player, account: account.to_string(), verified });
+            verify_events.publish(GameEvent::LoginVerified {
+                player: request.player,
+                account: account.to_string(),
+                verified,
+            });
+            Vec::new()
+        });
+
+        for service in ["GraalEngine", "-Serverlist"] {
+            for command in ["lister", "profile", "pmservers", "pmguilds", "pmserverplayers"] {
+                let events = events.clone();
+                registry.register(service, command, move |request| {
+                    events.publish(GameEvent::PlayerPacket {
+                        player: request.player,
+                        packet_type: PacketTypeOut::ServerText,
+                        data: request.text.clone().into_bytes(),
+                    });
+                    Vec::new()
+                });
+            }
+        }
+        registry
+    }
+
+    /// Create a registry without any commands
+    pub fn empty() -> Self {
+        Self { handlers: HashMap::new() }
+    }
+
+    /// Add or replace the handler of a command (names are case-insensitive)
+    pub fn register<F>(&mut self, service: &str, command: &str, handler: F)
+    where
+        F: Fn(&TextRequest) -> Vec<String> + Send + Sync + 'static,
+    {
+        self.handlers.insert(Self::key(service, command), Arc::new(handler));
+    }
+
+    /// Check if a command has a handler
+    pub fn has_handler(&self, service: &str, command: &str) -> bool {
+        self.handlers.contains_key(&Self::key(service, command))
+    }
+
+    /// Run the handler of a request
+    ///
+    /// # Returns
+    /// The lines for the listserver, or `None` if no handler knows the command
+    pub fn dispatch(&self, request: &TextRequest) -> Option<Vec<String>> {
+        let [service, command, ..] = request.tokens.as_slice() else {
+            return None;
+        };
+        let handler = self.handlers.get(&Self::key(service, command))?;
+        Some(handler(request))
+    }
+
+    fn key(service: &str, command: &str) -> (String, String) {
+        (service.to_ascii_lowercase(), command.to_ascii_lowercase())
+    }
+}
+
+impl std::fmt::Debug for RequestTextRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RequestTextRegistry")
+            .field("handlers", &self.handlers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dispatch_requests() {
+        let events = EventBus::new();
+        let commands = CommandQueue::new();
+        let mut received = events.subscribe();
+        let mut verifications = commands.take_receiver().unwrap();
+        let mut registry = RequestTextRegistry::new(events, commands);
+        let player = PlayerID::new(4);
+
+        let text = "GraalEngine,lister,simpleserverlist,\"Classic Server\",12";
+        assert_eq!(registry.dispatch(&TextRequest::new(player, text)), Some(vec![]));
+        assert_eq!(received.try_recv().unwrap(), GameEvent::PlayerPacket {
+            player,
+            packet_type: PacketTypeOut::ServerText,
+            data: text.as_bytes().to_vec(),
+        });
+
+        registry.dispatch(&TextRequest::new(player, "Listserver,TClientLogin,Bob,0"));
+        assert_eq!(verifications.try_recv().unwrap(), GameCommand::LoginVerified {
+            player,
+            account: "Bob".into(),
+            verified: false,
+        });
+        assert!(matches!(received.try_recv().unwrap(), GameEvent::LoginVerified { verified: false, .. }));
+        registry.dispatch(&TextRequest::new(player, "Listserver,TClientLogin"));
+        assert!(received.try_recv().is_err() && verifications.try_recv().is_err());
+
+        assert_eq!(registry.dispatch(&TextRequest::new(player, "Listserver,getglobalitems")), None);
+        registry.register("listserver", "GetGlobalItems", |request| vec![format!("Listserver,items,{}", request.player.get())]);
+        assert!(registry.has_handler("Listserver", "getglobalitems"));
+        assert_eq!(registry.dispatch(&TextRequest::new(player, "Listserver,getglobalitems")),
+            Some(vec!["Listserver,items,4".to_string()]));
+    }
+}
@@ -0,0 +1,204 @@
+//! # Death and Respawn
+//!
+//! With `respawn = true`, a player whose hearts (CurPower) reach 0 is dead
+//! until the server respawns them:
+//!
+//! 1. What `deathdrops` says is taken from the account and dropped as level
+//!    items around the spot they died on (nothing in `sparringzone` levels)
+//! 2. After `respawndelay` seconds they're healed and warped to their home
+//!    (the `gserver.home` flag, `level,x,y`, set with `/sethome`), or else
+//!    where they entered the level they died on, or else the start
+//!    location. Jailed players respawn in jail.
+//!
+//! Drops come in whole items: gralats as red (30), blue (5) and green (1)
+//! rupees, arrows and bombs in fives; at most [`MAX_DROPPED_ITEMS`] items,
+//! and only what is dropped is taken.
+
+use crate::moderation::Destination;
+use gserver_config::{RespawnConfig, ServerConfig as GameConfig};
+use std::time::{Duration, Instant};
+
+/// Account flag holding a player's home, `level,x,y`
+pub const HOME_FLAG: &str = "gserver.home";
+
+/// Most items one death drops
+pub const MAX_DROPPED_ITEMS: usize = 8;
+
+/// Level item IDs in PLI_ITEMADD (C++ `LevelItemType`)
+const ITEM_GREEN_RUPEE: u8 = 0;
+const ITEM_BLUE_RUPEE: u8 = 1;
+const ITEM_RED_RUPEE: u8 = 2;
+const ITEM_BOMBS: u8 = 3;
+const ITEM_DARTS: u8 = 4;
+
+/// Arrows or bombs in one item
+const AMMO_PER_ITEM: u32 = 5;
+
+/// Item spots around the death spot, in half tiles
+const SPOTS: [(i16, i16); MAX_DROPPED_ITEMS] = [(0, 0), (2, 0), (0, 2), (2, 2), (-2, 0), (0, -2), (-2, -2), (2, -2)];
+
+/// What a death takes from a player
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Drops {
+    pub gralats: u32,
+    pub arrows: u32,
+    pub bombs: u32,
+
+    /// Level item IDs to place
+    pub items: Vec<u8>,
+}
+
+impl Drops {
+    /// Work out the drops of a player holding `gralats`, `arrows` and `bombs`
+    pub fn compute(config: &RespawnConfig, gralats: u32, arrows: u32, bombs: u32) -> Self {
+        let mut drops = Self::default();
+        let mut gralats = config.gralats.of(gralats);
+        for (item, value) in [(ITEM_RED_RUPEE, 30), (ITEM_BLUE_RUPEE, 5), (ITEM_GREEN_RUPEE, 1)] {
+            while gralats >= value && drops.items.len() < MAX_DROPPED_ITEMS {
+                drops.items.push(item);
+                drops.gralats += value;
+                gralats -= value;
+            }
+        }
+        for (item, amount, taken) in [
+            (ITEM_DARTS, config.arrows.of(arrows), &mut drops.arrows),
+            (ITEM_BOMBS, config.bombs.of(bombs), &mut drops.bombs),
+        ] {
+            for _ in 0..amount / AMMO_PER_ITEM {
+                if drops.items.len() == MAX_DROPPED_ITEMS {
+                    break;
+                }
+                drops.items.push(item);
+                *taken += AMMO_PER_ITEM;
+            }
+        }
+        drops
+    }
+
+    /// Place the items around a spot
+    ///
+    /// # Arguments
+    /// * `x`, `y` - Death spot in half tiles
+    ///
+    /// # Returns
+    /// `(x, y, item)` per item, in half tiles
+    pub fn placed(&self, x: u8, y: u8) -> Vec<(u8, u8, u8)> {
+        self.items.iter().zip(SPOTS).map(|(&item, (dx, dy))| {
+            let at = |pos: u8, d: i16| (i16::from(pos) + d).clamp(0, 127) as u8;
+            (at(x, dx), at(y, dy), item)
+        }).collect()
+    }
+}
+
+/// Death state of one player
+#[derive(Debug, Clone, Default)]
+pub struct RespawnTracker {
+    /// Where the player entered their current level
+    entry: Option<Destination>,
+    died_at: Option<Instant>,
+}
+
+impl RespawnTracker {
+    /// Create the state of a living player
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record where the player entered a level
+    pub fn entered(&mut self, level: &str, x: f32, y: f32) {
+        self.entry = Some(Destination { level: level.to_string(), x, y });
+    }
+
+    /// Record a death
+    ///
+    /// # Returns
+    /// `false` if the player was dead already
+    pub fn died(&mut self, now: Instant) -> bool {
+        if self.died_at.is_some() {
+            return false;
+        }
+        self.died_at = Some(now);
+        true
+    }
+
+    /// Check if the player is dead
+    pub fn is_dead(&self) -> bool {
+        self.died_at.is_some()
+    }
+
+    /// Check if a dead player is due to respawn
+    pub fn is_due(&self, now: Instant, delay: Duration) -> bool {
+        self.died_at.is_some_and(|at| now.duration_since(at) >= delay)
+    }
+
+    /// Get when a dead player respawns
+    pub fn due_at(&self, delay: Duration) -> Option<Instant> {
+        self.died_at.map(|at| at + delay)
+    }
+
+    /// Record the respawn
+    pub fn respawned(&mut self) {
+        self.died_at = None;
+    }
+
+    /// Pick where the player respawns (see the module docs; jail is the caller's)
+    ///
+    /// # Arguments
+    /// * `home` - The player's `gserver.home` flag
+    pub fn respawn_point(&self, home: Option<&str>, config: &GameConfig) -> Destination {
+        home.and_then(parse_home)
+            .or_else(|| self.entry.clone())
+            .unwrap_or_else(|| Destination::home(config))
+    }
+}
+
+/// Parse a `level,x,y` home
+pub fn parse_home(value: &str) -> Option<Destination> {
+    let mut parts = value.split(',').map(str::trim);
+    let level = parts.next().filter(|l| !l.is_empty())?;
+    let x = parts.next()?.parse().ok()?;
+    let y = parts.next()?.parse().ok()?;
+    Some(Destination { level: level.to_string(), x, y })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gserver_config::DropAmount;
+
+    #[test]
+    fn test_death_drops() {
+        let config = RespawnConfig {
+            gralats: DropAmount::Percent(50),
+            arrows: DropAmount::Count(12),
+            bombs: DropAmount::Count(5),
+            ..Default::default()
+        };
+        let drops = Drops::compute(&config, 72, 30, 2);
+        assert_eq!(drops.items, [ITEM_RED_RUPEE, ITEM_BLUE_RUPEE, ITEM_GREEN_RUPEE, ITEM_DARTS, ITEM_DARTS]);
+        assert_eq!((drops.gralats, drops.arrows, drops.bombs), (36, 10, 0));
+        assert_eq!(&drops.placed(1, 60)[..3], [(1, 60, ITEM_RED_RUPEE), (3, 60, ITEM_BLUE_RUPEE), (1, 62, ITEM_GREEN_RUPEE)]);
+        assert_eq!(drops.placed(0, 0)[4], (0, 0, ITEM_DARTS));
+
+        let capped = Drops::compute(&RespawnConfig { gralats: DropAmount::Count(1000), ..config }, 1000, 0, 0);
+        assert_eq!((capped.items.len(), capped.gralats), (MAX_DROPPED_ITEMS, 240));
+    }
+
+    #[test]
+    fn test_respawn_point_and_delay() {
+        let config = GameConfig::default();
+        let mut tracker = RespawnTracker::new();
+        assert_eq!(tracker.respawn_point(None, &config), Destination::home(&config));
+        tracker.entered("cave.nw", 12.0, 40.5);
+        assert_eq!(tracker.respawn_point(Some("broken"), &config).level, "cave.nw");
+        assert_eq!(tracker.respawn_point(Some("house.nw, 3, 4.5"), &config),
+            Destination { level: "house.nw".into(), x: 3.0, y: 4.5 });
+
+        let now = Instant::now();
+        assert!(tracker.died(now) && !tracker.died(now));
+        assert!(!tracker.is_due(now + Duration::from_secs(4), Duration::from_secs(5)));
+        assert!(tracker.is_due(now + Duration::from_secs(5), Duration::from_secs(5)));
+        tracker.respawned();
+        assert!(!tracker.is_dead());
+    }
+}
@@ -0,0 +1,197 @@
+//! # Account Save Queue
+//!
+//! Accounts of disconnecting players are written by detached workers, not
+//! by the connection tasks, so a disconnect storm (a proxy restarting drops
+//! all of its players at once) doesn't hold hundreds of tasks on disk I/O.
+//!
+//! - Accounts are spread over [`SAVE_WORKERS`] queues by name; each queue
+//!   writes its accounts in the order they came in, on a blocking thread
+//!   that runs only while the queue has work
+//! - A newer snapshot of an account that is still waiting replaces the
+//!   older one
+//! - Before an account file is read back (a login, an offline RC edit),
+//!   [`AccountSaveQueue::wait`] waits for the account's pending save
+//! - On shutdown [`AccountSaveQueue::drain`] waits for all of them
+
+use gserver_accounts::{Account, AccountLoader};
+use parking_lot::Mutex;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+/// Queues accounts are spread over
+pub const SAVE_WORKERS: usize = 4;
+
+/// The accounts of one queue
+#[derive(Debug, Default)]
+struct QueueState {
+    /// Lowercase names in the order they were queued
+    order: VecDeque<String>,
+
+    /// Newest snapshot of each queued account
+    pending: HashMap<String, Account>,
+
+    /// The account being written
+    writing: Option<String>,
+
+    /// Whether a worker is draining the queue
+    running: bool,
+}
+
+impl QueueState {
+    fn has(&self, key: &str) -> bool {
+        self.pending.contains_key(key) || self.writing.as_deref() == Some(key)
+    }
+
+    fn is_idle(&self) -> bool {
+        !self.running && self.order.is_empty()
+    }
+}
+
+#[derive(Debug, Default)]
+struct SaveQueue {
+    state: Mutex<QueueState>,
+
+    /// Woken after every write
+    written: Notify,
+}
+
+/// Account saves waiting for disk I/O
+#[derive(Debug)]
+pub struct AccountSaveQueue {
+    server_dir: PathBuf,
+    queues: Vec<Arc<SaveQueue>>,
+}
+
+impl AccountSaveQueue {
+    /// Create a queue writing to the server folder's `accounts/`
+    pub fn new(server_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            server_dir: server_dir.into(),
+            queues: (0..SAVE_WORKERS).map(|_| Arc::default()).collect(),
+        }
+    }
+
+    fn queue_of(&self, key: &str) -> &Arc<SaveQueue> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        &self.queues[hasher.finish() as usize % self.queues.len()]
+    }
+
+    /// Queue an account to be written, returning right away
+    ///
+    /// Must be called inside the tokio runtime.
+    pub fn submit(&self, account: Account) {
+        let key = account.name.to_lowercase();
+        let queue = self.queue_of(&key).clone();
+        let start = {
+            let mut state = queue.state.lock();
+            if state.pending.insert(key.clone(), account).is_none() {
+                state.order.push_back(key);
+            }
+            !std::mem::replace(&mut state.running, true)
+        };
+        if start {
+            let loader = AccountLoader::new(&self.server_dir);
+            tokio::task::spawn_blocking(move || drain_queue(&queue, &loader));
+        }
+    }
+
+    /// Get the number of accounts waiting or being written
+    pub fn len(&self) -> usize {
+        self.queues.iter()
+            .map(|queue| {
+                let state = queue.state.lock();
+                state.order.len() + usize::from(state.writing.is_some())
+            })
+            .sum()
+    }
+
+    /// Check if no account is waiting
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Wait until an account has no pending save
+    pub async fn wait(&self, account: &str) {
+        let key = account.to_lowercase();
+        let queue = self.queue_of(&key);
+        loop {
+            let written = queue.written.notified();
+            tokio::pin!(written);
+            written.as_mut().enable();
+            if !queue.state.lock().has(&key) {
+                return;
+            }
+            written.await;
+        }
+    }
+
+    /// Wait until every queued account is written
+    pub async fn drain(&self) {
+        for queue in &self.queues {
+            loop {
+                let written = queue.written.notified();
+                tokio::pin!(written);
+                written.as_mut().enable();
+                if queue.state.lock().is_idle() {
+                    break;
+                }
+                written.await;
+            }
+        }
+    }
+}
+
+/// Write a queue's accounts until it's empty
+fn drain_queue(queue: &SaveQueue, loader: &AccountLoader) {
+    loop {
+        let account = {
+            let mut state = queue.state.lock();
+            let next = state.order.pop_front().and_then(|key| {
+                let account = state.pending.remove(&key)?;
+                state.writing = Some(key);
+                Some(account)
+            });
+            if next.is_none() {
+                state.running = false;
+            }
+            next
+        };
+        let Some(account) = account else {
+            queue.written.notify_waiters();
+            return;
+        };
+
+        match loader.save(&account) {
+            Ok(()) => tracing::debug!("Saved account {}", account.name),
+            Err(e) => tracing::error!("Failed to save account {}: {}", account.name, e),
+        }
+        queue.state.lock().writing = None;
+        queue.written.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_newest_snapshot_is_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let saves = AccountSaveQueue::new(dir.path());
+
+        for gralats in [10, 20, 30] {
+            saves.submit(Account { name: "Saver".into(), gralats, ..Default::default() });
+        }
+        saves.wait("SAVER").await;
+        assert!(saves.is_empty());
+        saves.drain().await;
+
+        let loaded = AccountLoader::new(dir.path()).load("Saver").unwrap();
+        assert_eq!(loaded.gralats, 30);
+    }
+}
@@ -0,0 +1,276 @@
+//! # Script History
+//!
+//! Weapons and classes edited from NC or RC keep their previous versions on
+//! disk, so an accidental overwrite can be undone:
+//!
+//! - Before an edit replaces a script, the old source (the whole weapon
+//!   file for weapons) is written to
+//!   `history/{weapons|classes}/{name}/{version}-{YYYYMMDD-HHMMSS}.txt`;
+//!   only the newest `scripthistory` versions are kept
+//! - RC `/scripthistory <weapon|class> <name>` lists them,
+//!   `/scriptdiff <weapon|class> <name> <version>` shows what changed since
+//!   a version and `/scriptrevert <weapon|class> <name> <version>` puts it
+//!   back. A revert is an edit too, so it can be reverted in turn
+
+use crate::context::ServerContext;
+use gserver_game::Weapon;
+use gserver_storage::format_timestamp;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// History folder in the server folder
+pub const HISTORY_DIR: &str = "history";
+
+/// Most diff lines shown to RC
+pub const DIFF_LINES: usize = 40;
+
+/// Line pairs compared before a diff gives up on finding common lines
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// What kind of script a history is of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptKind {
+    Weapon,
+    Class,
+}
+
+impl ScriptKind {
+    /// Parse `weapon` or `class`
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.to_ascii_lowercase().as_str() {
+            "weapon" => Some(Self::Weapon),
+            "class" => Some(Self::Class),
+            _ => None,
+        }
+    }
+
+    fn folder(self) -> &'static str {
+        match self {
+            Self::Weapon => "weapons",
+            Self::Class => "classes",
+        }
+    }
+}
+
+impl fmt::Display for ScriptKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Weapon => write!(f, "weapon"),
+            Self::Class => write!(f, "class"),
+        }
+    }
+}
+
+/// A kept version of a script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptVersion {
+    /// Counts up from 1 per script
+    pub number: u32,
+
+    /// When it was replaced, `YYYYMMDD-HHMMSS` UTC
+    pub stamp: String,
+}
+
+/// Previous versions of the weapons and classes
+#[derive(Debug)]
+pub struct ScriptHistory {
+    dir: PathBuf,
+}
+
+impl ScriptHistory {
+    /// Create a history kept in `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Get the folder of a script's versions
+    ///
+    /// Names keep their case, like the weapon files; `.`, `..` and empty
+    /// names have none.
+    fn folder(&self, kind: ScriptKind, name: &str) -> Option<PathBuf> {
+        let name: String = name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || "-_.@ ".contains(c) { c } else { '_' })
+            .collect();
+        if matches!(name.as_str(), "" | "." | "..") {
+            return None;
+        }
+        Some(self.dir.join(kind.folder()).join(name))
+    }
+
+    /// Keep a script's source before it is replaced
+    ///
+    /// A source equal to the newest kept version isn't kept twice.
+    ///
+    /// # Arguments
+    /// * `keep` - Versions kept of the script, older ones are deleted (0 keeps none)
+    /// * `now` - Unix time
+    ///
+    /// # Returns
+    /// The number of the new version
+    pub fn record(&self, kind: ScriptKind, name: &str, source: &str, keep: usize, now: u64) -> io::Result<Option<u32>> {
+        if keep == 0 {
+            return Ok(None);
+        }
+        let versions = self.versions(kind, name);
+        if let Some(newest) = versions.first() {
+            if self.load(kind, name, newest.number).as_deref() == Some(source) {
+                return Ok(None);
+            }
+        }
+
+        let folder = self.folder(kind, name)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("invalid script name '{}'", name)))?;
+        std::fs::create_dir_all(&folder)?;
+        let number = versions.first().map_or(1, |v| v.number + 1);
+        std::fs::write(folder.join(format!("{}-{}.txt", number, format_timestamp(now))), source)?;
+        for old in versions.iter().skip(keep.saturating_sub(1)) {
+            std::fs::remove_file(folder.join(format!("{}-{}.txt", old.number, old.stamp)))?;
+        }
+        Ok(Some(number))
+    }
+
+    /// Get the kept versions of a script, newest first
+    pub fn versions(&self, kind: ScriptKind, name: &str) -> Vec<ScriptVersion> {
+        let Some(Ok(entries)) = self.folder(kind, name).map(std::fs::read_dir) else {
+            return Vec::new();
+        };
+        let mut versions: Vec<ScriptVersion> = entries.flatten()
+            .filter_map(|entry| {
+                let file = entry.file_name().into_string().ok()?;
+                let (number, stamp) = file.strip_suffix(".txt")?.split_once('-')?;
+                Some(ScriptVersion { number: number.parse().ok()?, stamp: stamp.to_string() })
+            })
+            .collect();
+        versions.sort_by_key(|v| std::cmp::Reverse(v.number));
+        versions
+    }
+
+    /// Get the source of a kept version
+    pub fn load(&self, kind: ScriptKind, name: &str, number: u32) -> Option<String> {
+        let version = self.versions(kind, name).into_iter().find(|v| v.number == number)?;
+        std::fs::read_to_string(self.folder(kind, name)?.join(format!("{}-{}.txt", version.number, version.stamp))).ok()
+    }
+}
+
+/// Get the current source of a script, as its versions are kept
+pub fn current_source(context: &ServerContext, kind: ScriptKind, name: &str) -> Option<String> {
+    match kind {
+        ScriptKind::Weapon => context.weapons().get_weapon(name).map(|w| w.to_text()),
+        ScriptKind::Class => context.classes().get_class(name).map(|c| c.script.clone()),
+    }
+}
+
+/// Keep the current source of a script before an edit replaces it
+///
+/// Write failures are logged; the edit goes ahead.
+pub fn keep_current(context: &ServerContext, kind: ScriptKind, name: &str) {
+    let Some(source) = current_source(context, kind, name) else { return };
+    let keep = context.config().read().script_history;
+    let now = gserver_game::moderation::unix_now();
+    match context.script_history().record(kind, name, &source, keep, now) {
+        Ok(Some(number)) => tracing::debug!("Kept version {} of {} {}", number, kind, name),
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Failed to keep the previous version of {} {}: {}", kind, name, e),
+    }
+}
+
+/// Put a kept version of a script back
+///
+/// # Returns
+/// The RC reply
+pub fn revert(context: &ServerContext, kind: ScriptKind, name: &str, number: u32) -> String {
+    let Some(source) = context.script_history().load(kind, name, number) else {
+        return format!("No version {} of {} {}", number, kind, name);
+    };
+    let saved = match kind {
+        ScriptKind::Weapon => match Weapon::parse(&source) {
+            Some(weapon) => crate::weaponsync::update_weapon(context, weapon).map(|_| ()),
+            None => return format!("Version {} of weapon {} can't be read", number, name),
+        },
+        ScriptKind::Class => crate::weaponsync::update_class(context, name, &source).map(|_| ()),
+    };
+    match saved {
+        Ok(()) => format!("Reverted {} {} to version {}", kind, name, number),
+        Err(e) => format!("Reverted {} {} to version {}, but it wasn't saved: {}", kind, name, number, e),
+    }
+}
+
+/// Compare two sources line by line
+///
+/// # Returns
+/// The removed lines as `- line` and the added lines as `+ line`, in order
+pub fn diff(old: &str, new: &str) -> Vec<String> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let (old, new) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+
+    let removed = |line: &&str| format!("- {}", line);
+    let added = |line: &&str| format!("+ {}", line);
+    if old.len() * new.len() > MAX_DIFF_CELLS {
+        return old.iter().map(removed).chain(new.iter().map(added)).collect();
+    }
+
+    // common[i][j]: longest common subsequence of old[i..] and new[j..]
+    let width = new.len() + 1;
+    let mut common = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i * width + j] = match old[i] == new[j] {
+                true => common[(i + 1) * width + j + 1] + 1,
+                false => common[(i + 1) * width + j].max(common[i * width + j + 1]),
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if common[(i + 1) * width + j] >= common[i * width + j + 1] {
+            lines.push(removed(&old[i]));
+            i += 1;
+        } else {
+            lines.push(added(&new[j]));
+            j += 1;
+        }
+    }
+    lines.extend(old[i..].iter().map(removed));
+    lines.extend(new[j..].iter().map(added));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_prune() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = ScriptHistory::new(dir.path());
+        for (i, source) in ["a", "b", "b", "c", "d"].iter().enumerate() {
+            history.record(ScriptKind::Class, "Shop", source, 3, 1_700_000_000 + i as u64).unwrap();
+        }
+        let numbers: Vec<u32> = history.versions(ScriptKind::Class, "Shop").iter().map(|v| v.number).collect();
+        assert_eq!(numbers, [4, 3, 2]);
+        assert_eq!(history.load(ScriptKind::Class, "Shop", 2).as_deref(), Some("b"));
+        assert!(history.load(ScriptKind::Class, "Shop", 1).is_none());
+        assert!(history.versions(ScriptKind::Weapon, "Shop").is_empty());
+        assert_eq!(history.record(ScriptKind::Class, "Shop", "e", 0, 0).unwrap(), None);
+        assert!(dir.path().join("classes").join("Shop").is_dir());
+
+        assert!(history.record(ScriptKind::Class, "..", "up", 3, 0).is_err());
+        assert!(history.versions(ScriptKind::Class, ".").is_empty());
+    }
+
+    #[test]
+    fn test_diff() {
+        assert_eq!(diff("a\nb\nc\nd\n", "a\nc\nx\nd\n"), ["- b", "+ x"]);
+        assert_eq!(diff("same", "same"), Vec::<String>::new());
+        assert_eq!(diff("", "new"), ["+ new"]);
+    }
+}
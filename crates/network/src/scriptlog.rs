@@ -0,0 +1,106 @@
+//! # Script Error Log
+//!
+//! Compile and runtime errors of serverside scripts (GS1, GS2 and Lua) are
+//! reported by the script host with the script name, the line and the player
+//! the script ran for. Each one is:
+//!
+//! - Appended to `logs/scripts.log`
+//! - Published as [`GameEvent::ScriptError`], which the RC notifier sends to
+//!   every RC as a "Script compiler output" line
+//!
+//! # File Format
+//! ```text
+//! {YYYYMMDD-HHMMSS UTC}\t{script}\t{line or -}\t{player id or -}\t{message}
+//! ```
+
+use gserver_game::{EventBus, GameEvent};
+use gserver_scripting::{ScriptErrorHandler, ScriptErrorReport};
+use gserver_storage::format_timestamp;
+use parking_lot::Mutex;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// Script error log in the server folder
+pub const SCRIPT_LOG_FILE: &str = "logs/scripts.log";
+
+/// The script error file and the staff relay
+#[derive(Debug)]
+pub struct ScriptErrorLog {
+    path: PathBuf,
+    file: Mutex<Option<File>>,
+    events: EventBus,
+}
+
+impl ScriptErrorLog {
+    /// Create a log writing `path`, opened on the first error
+    ///
+    /// # Arguments
+    /// * `events` - Event bus for [`GameEvent::ScriptError`]
+    pub fn new(path: impl Into<PathBuf>, events: EventBus) -> Self {
+        Self { path: path.into(), file: Mutex::new(None), events }
+    }
+
+    fn write(&self, line: &str) -> io::Result<()> {
+        let mut file = self.file.lock();
+        if file.is_none() {
+            if let Some(dir) = self.path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            *file = Some(OpenOptions::new().create(true).append(true).open(&self.path)?);
+        }
+        let Some(out) = file.as_mut() else { return Ok(()) };
+        out.write_all(line.as_bytes())
+    }
+}
+
+impl ScriptErrorHandler for ScriptErrorLog {
+    fn report(&self, error: &ScriptErrorReport) {
+        let dash = || "-".to_string();
+        let line = format!("{}\t{}\t{}\t{}\t{}\n", format_timestamp(gserver_game::moderation::unix_now()), error.script,
+            error.line.map_or_else(dash, |l| l.to_string()), error.player.map_or_else(dash, |p| p.get().to_string()),
+            error.message.replace('\n', " "));
+        if let Err(e) = self.write(&line) {
+            tracing::warn!("Script log write failed: {}", e);
+        }
+        self.events.publish(GameEvent::ScriptError {
+            script: error.script.clone(),
+            line: error.line,
+            player: error.player,
+            message: error.message.clone(),
+        });
+    }
+}
+
+/// Format a script error as the RC's "Script compiler output" line
+pub fn compiler_output(script: &str, line: Option<usize>, message: &str) -> String {
+    match line {
+        Some(line) => format!("Script compiler output for {}: line {}: {}", script, line, message),
+        None => format!("Script compiler output for {}: {}", script, message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gserver_core::PlayerID;
+
+    #[test]
+    fn test_report_is_logged_and_published() {
+        let dir = tempfile::tempdir().unwrap();
+        let events = EventBus::new();
+        let mut rx = events.subscribe();
+        let log = ScriptErrorLog::new(dir.path().join(SCRIPT_LOG_FILE), events);
+
+        log.report(&ScriptErrorReport {
+            script: "-Shop".into(),
+            line: Some(3),
+            player: Some(PlayerID(7)),
+            message: "Runtime error: bad\nvalue".into(),
+        });
+        let content = std::fs::read_to_string(dir.path().join(SCRIPT_LOG_FILE)).unwrap();
+        assert!(content.ends_with("\t-Shop\t3\t7\tRuntime error: bad value\n"));
+        assert!(matches!(rx.try_recv(), Ok(GameEvent::ScriptError { line: Some(3), .. })));
+        assert_eq!(compiler_output("-Shop", None, "x"), "Script compiler output for -Shop: x");
+    }
+}
@@ -8,10 +8,11 @@
 //!
 //! ## Components
 //!
-//! 1. **TCP Listener** - Accepts incoming connections
+//! 1. **TCP Listener** - Accepts incoming connections (plus TLS ones, see [`crate::tls`])
 //! 2. **Connection Map** - Tracks all active players (DashMap for concurrent access)
 //! 3. **Handler Registry** - Routes packets to appropriate handlers
 //! 4. **ID Generator** - Assigns unique player IDs
+//! 5. **Server Context** - Shared game state (players, levels, weapons, scripts)
 //!
 //! # Thread Safety
 //!
@@ -47,11 +48,19 @@
 //! }
 //! ```
 
-use crate::{config::ServerConfig, connection::PlayerConnection, handlers::HandlerRegistry};
+use crate::{config::ServerConfig, connection::PlayerConnection, context::ServerContext, handlers::HandlerRegistry};
+use crate::tls::ClientStream;
 use gserver_core::{PlayerID, Result};
+use crate::timings::HandlerTiming;
+use crate::compression::{CompressionMethod, MethodStats};
+use crate::listlatency::LatencyReport;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::oneshot;
 use tokio::io::AsyncWriteExt;
+use tokio_rustls::TlsAcceptor;
 
 /// Main GServer instance
 ///
@@ -93,6 +102,12 @@ pub struct GServer {
     /// ID generator for players
     id_generator: Arc<parking_lot::Mutex<gserver_core::IdGenerator<u16>>>,
 
+    /// Shared server state handed to every connection
+    context: Arc<ServerContext>,
+
+    /// TLS listener and its handshake acceptor, if enabled
+    tls: Option<(Arc<tokio::net::TcpListener>, TlsAcceptor)>,
+
     /// Shutdown signal sender
     shutdown_tx: Option<oneshot::Sender<()>>,
 }
@@ -117,6 +132,19 @@ impl GServer {
     /// let server = GServer::new(config).await?;
     /// ```
     pub async fn new(config: ServerConfig) -> Result<Self> {
+        let context = Arc::new(ServerContext::new(&config.server_dir, Default::default()));
+        Self::with_context(config, context).await
+    }
+
+    /// Create a new server instance with an existing server context
+    ///
+    /// # Arguments
+    /// * `config` - Server configuration options
+    /// * `context` - Shared server state (loaded game config, levels, weapons, etc.)
+    ///
+    /// # Errors
+    /// Same as [`GServer::new`]
+    pub async fn with_context(config: ServerConfig, context: Arc<ServerContext>) -> Result<Self> {
         // Validate configuration
         config.validate().map_err(|e| {
             gserver_core::GServerError::Config(format!("Invalid configuration: {}", e))
@@ -133,17 +161,39 @@ impl GServer {
             })?;
 
         tracing::info!("GServer listening on {}", config.bind_address);
+
+        let tls = match &config.tls {
+            Some(tls_config) => {
+                let acceptor = crate::tls::load_acceptor(tls_config)?;
+                let listener = tokio::net::TcpListener::bind(&tls_config.bind_address).await.map_err(|e| {
+                    gserver_core::GServerError::Io(std::io::Error::new(
+                        std::io::ErrorKind::AddrInUse,
+                        format!("Failed to bind TLS to {}: {}", tls_config.bind_address, e)
+                    ))
+                })?;
+                tracing::info!("GServer listening for TLS on {}", tls_config.bind_address);
+                Some((Arc::new(listener), acceptor))
+            }
+            None => None,
+        };
         tracing::info!("Configuration: max_connections={}, compression={}",
             config.max_connections, config.enable_compression);
 
         let (shutdown_tx, _) = oneshot::channel();
 
+        // RC /stats and the health service only have the context
+        let connections: Arc<dashmap::DashMap<PlayerID, Arc<PlayerConnection>>> = Arc::new(dashmap::DashMap::new());
+        let stats_connections = connections.clone();
+        context.set_stats_source(Box::new(move |context| collect_stats(&stats_connections, context)));
+
         Ok(Self {
             config,
             listener: Arc::new(listener),
-            connections: Arc::new(dashmap::DashMap::new()),
+            connections,
             handlers: Arc::new(parking_lot::Mutex::new(HandlerRegistry::new())),
-            id_generator: Arc::new(parking_lot::Mutex::new(gserver_core::IdGenerator::new())),
+            id_generator: Arc::new(parking_lot::Mutex::new(gserver_core::IdGenerator::for_players())),
+            context,
+            tls,
             shutdown_tx: Some(shutdown_tx),
         })
     }
@@ -180,78 +230,92 @@ impl GServer {
     pub async fn run(&self) -> Result<()> {
         tracing::info!("GServer starting main loop");
 
+        let rc_notifier = self.spawn_rc_notifier();
+        let irc_relay = self.spawn_irc_relay();
+        let ambience_relay = self.spawn_ambience_relay();
+        let instance_relay = self.spawn_instance_relay();
+        let player_relay = self.spawn_player_relay();
+        let command_relay = self.spawn_command_relay();
+        let class_watcher = self.spawn_class_watcher();
+        let level_watcher = self.spawn_level_watcher();
+        let announcer = self.spawn_announcer();
+        let handler_summary = self.spawn_handler_summary();
+
+        // Connections that first need a TLS handshake or a PROXY header
+        // (see crate::proxy) join the accept loop once they are done
+        let (ready_tx, mut ready) = tokio::sync::mpsc::channel(64);
+        let tls_listener = self.tls.clone().map(|(listener, acceptor)| {
+            tokio::spawn(crate::tls::accept_loop(listener, acceptor, self.context.config().clone(), ready_tx.clone()))
+        });
+
+        let shutdown = crate::service::shutdown_signal();
+        tokio::pin!(shutdown);
+        self.context.set_accepting(true);
+        let heartbeat = self.context.watchdog().register("accept loop");
+        let mut beat = tokio::time::interval(crate::watchdog::HEARTBEAT_INTERVAL);
+
         // Accept connections loop
         loop {
             tokio::select! {
                 // Accept new connection
                 result = self.listener.accept() => {
                     match result {
-                        Ok((mut socket, addr)) => {
-                            // Check connection limit
-                            if self.connections.len() >= self.config.max_connections {
-                                tracing::warn!("Connection rejected: server full ({} connections)",
-                                    self.connections.len());
-                                let _ = socket.shutdown().await;
-                                continue;
-                            }
-
-                            tracing::debug!("New connection from {}", addr);
-
-                            // Generate player ID
-                            let player_id = {
-                                let gen = self.id_generator.lock();
-                                PlayerID::new(gen.get_available_id())
-                            };
-
-                            // Create connection
-                            let conn = Arc::new(PlayerConnection::new(
-                                player_id,
-                                socket,
-                                addr,
-                                self.config.server_dir.clone()
-                            ));
-
-                            // Store in connection map
-                            self.connections.insert(player_id, conn.clone());
-
-                            // Spawn connection task
-                            let connections_clone = self.connections.clone();
-                            let _handlers = self.handlers.clone();
-
+                        Ok((mut socket, addr)) if crate::proxy::expects_header(&self.context.config().read(), addr.ip()) => {
+                            let config = self.context.config().clone();
+                            let ready_tx = ready_tx.clone();
                             tokio::spawn(async move {
-                                tracing::info!("Connection {} task started", player_id.get());
-
-                                // Run connection loop
-                                let result = conn.run().await;
-
-                                // Remove from connection map
-                                connections_clone.remove(&player_id);
-
-                                match result {
-                                    Ok(()) => {
-                                        tracing::info!("Connection {} task completed", player_id.get());
-                                    }
-                                    Err(e) => {
-                                        tracing::error!("Connection {} task failed: {:?}", player_id.get(), e);
-                                    }
+                                if let Some(client) = crate::proxy::resolve(&mut socket, addr, &config).await {
+                                    let _ = ready_tx.send((socket.into(), client)).await;
                                 }
                             });
                         }
+                        Ok((socket, addr)) => self.admit(socket.into(), addr).await,
                         Err(e) => {
                             tracing::error!("Error accepting connection: {:?}", e);
                         }
                     }
                 }
 
+                Some((socket, addr)) = ready.recv() => self.admit(socket, addr).await,
+
+                _ = beat.tick() => heartbeat.beat(),
+
                 // Wait for shutdown signal
-                _ = tokio::signal::ctrl_c() => {
-                    tracing::info!("Ctrl-C received, initiating shutdown");
+                signal = &mut shutdown => {
+                    tracing::info!("{} received, initiating shutdown", signal);
+                    break;
+                }
+
+                _ = self.context.watchdog().restart_requested() => {
+                    tracing::info!("Watchdog restart, initiating shutdown");
                     break;
                 }
             }
         }
+        heartbeat.stop();
+        self.context.set_accepting(false);
+
+        // Players are warned while the relays can still deliver
+        let countdown = self.context.config().read().shutdown_countdown;
+        if countdown > 0 {
+            tracing::info!("Shutting down in {}s", countdown);
+            self.context.announcements().shutdown_countdown(countdown).await;
+        }
 
         tracing::info!("GServer main loop ended");
+        rc_notifier.abort();
+        irc_relay.abort();
+        ambience_relay.abort();
+        instance_relay.abort();
+        player_relay.abort();
+        command_relay.abort();
+        class_watcher.abort();
+        level_watcher.abort();
+        announcer.abort();
+        handler_summary.abort();
+        if let Some(tls_listener) = tls_listener {
+            tls_listener.abort();
+        }
 
         // Wait for all connection tasks to complete
         tracing::info!("Waiting for {} connection tasks to finish", self.connections.len());
@@ -269,6 +333,775 @@ impl GServer {
         Ok(())
     }
 
+    /// Admit an accepted connection and spawn its task
+    ///
+    /// Plain connections come here straight from the accept loop, TLS ones
+    /// once their handshake is done; from here on both are handled the same.
+    /// `addr` is the client's address, from the PROXY header if there was one.
+    async fn admit(&self, mut socket: ClientStream, addr: SocketAddr) {
+        // Check connection limit
+        if self.connections.len() >= self.config.max_connections {
+            tracing::warn!("Connection rejected: server full ({} connections)",
+                self.connections.len());
+            let _ = socket.shutdown().await;
+            return;
+        }
+
+        tracing::debug!("New {} connection from {}", if socket.is_tls() { "TLS" } else { "TCP" }, addr);
+
+        // Generate player ID (released IDs come back after quarantine)
+        let allocated = self.id_generator.lock().get_available_id();
+        let player_id = match allocated {
+            Ok(id) => PlayerID::new(id),
+            Err(e) => {
+                tracing::warn!("Connection from {} rejected: {}", addr, e);
+                let _ = socket.shutdown().await;
+                return;
+            }
+        };
+
+        // Create connection
+        let conn = Arc::new(PlayerConnection::new(
+            player_id,
+            socket,
+            addr,
+            self.context.clone()
+        ));
+
+        // Store in connection map
+        self.connections.insert(player_id, conn.clone());
+        self.context.set_online_count(self.connections.len());
+
+        // Spawn connection task
+        let connections_clone = self.connections.clone();
+        let id_generator = self.id_generator.clone();
+        let context = self.context.clone();
+        let _handlers = self.handlers.clone();
+
+        crate::tasks::spawn_named(&format!("connection {}", player_id.get()), async move {
+            tracing::info!("Connection {} task started", player_id.get());
+
+            // Run connection loop in a task of its own, so a panicking handler
+            // is caught here and the player is still cleaned up
+            let running = conn.clone();
+            let run = crate::tasks::spawn_named(&format!("connection {} loop", player_id.get()),
+                async move { running.run().await });
+            let result = match run.await {
+                Ok(result) => result,
+                Err(e) => {
+                    let message = if e.is_panic() {
+                        crate::tasks::panic_message(&*e.into_panic())
+                    } else {
+                        e.to_string()
+                    };
+                    conn.recover_from_panic(&message).await;
+                    Err(gserver_core::GServerError::Network(format!("connection loop aborted: {}", message)))
+                }
+            };
+
+            // Remove from connection map
+            connections_clone.remove(&player_id);
+            context.set_online_count(connections_clone.len());
+            id_generator.lock().release(player_id.get());
+
+            match result {
+                Ok(()) => {
+                    tracing::info!("Connection {} task completed", player_id.get());
+                }
+                Err(e) => {
+                    tracing::error!("Connection {} task failed: {:?}", player_id.get(), e);
+                }
+            }
+        });
+    }
+
+    /// Relay the staff chat channel and forward events that staff should see
+    /// to RC connections
+    ///
+    /// # Events
+    /// - `RcChat` - Sent to every RC, or with `/msg` to the target's RCs and the sender
+    /// - `IntegrityViolation` - Sent as an RC chat line
+    /// - `WordFilterMatch` - Sent as an RC chat line
+    /// - `StaffNotice` - Sent as an RC chat line
+    /// - `ScriptError` - Sent as a "Script compiler output" line (see [`crate::scriptlog`])
+    ///
+    /// Lines sent to every RC are kept in the context's RC chat history.
+    fn spawn_rc_notifier(&self) -> tokio::task::JoinHandle<()> {
+        use crate::rcchat::{format_line, format_private};
+        use gserver_game::GameEvent;
+        use gserver_protocol::{PacketOut, PacketTypeOut};
+        use tokio::sync::broadcast::error::RecvError;
+
+        let mut events = self.context.events().subscribe();
+        let connections = self.connections.clone();
+        let context = self.context.clone();
+
+        tokio::spawn(async move {
+            loop {
+                // (line, Some((sender, target account)) for a /msg)
+                let (message, private) = match events.recv().await {
+                    Ok(GameEvent::RcChat { id, account, message, to: Some(to) }) => {
+                        (format_private(&account, &to, &message), Some((id, to)))
+                    }
+                    Ok(GameEvent::RcChat { account, message, to: None, .. }) => {
+                        (format_line(&account, &message), None)
+                    }
+                    Ok(GameEvent::IntegrityViolation { id, account, reason }) => {
+                        (format!("Server: {} (id {}) {}", account, id.get(), reason), None)
+                    }
+                    Ok(GameEvent::WordFilterMatch { id, account, check, words }) if words.is_empty() => {
+                        (format!("Word filter: {} (id {}) triggered a rule in {}", account, id.get(), check), None)
+                    }
+                    Ok(GameEvent::WordFilterMatch { id, account, check, words }) => {
+                        (format!("Word filter: {} (id {}) used {} in {}", account, id.get(), words.join(", "), check), None)
+                    }
+                    Ok(GameEvent::StaffNotice { message }) => (format!("Server: {}", message), None),
+                    Ok(GameEvent::ScriptError { script, line, message, .. }) => {
+                        (crate::scriptlog::compiler_output(&script, line, &message), None)
+                    }
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("RC notifier skipped {} events", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                let mut rcs: Vec<_> = connections.iter()
+                    .filter(|e| e.value().is_rc() && e.value().is_authenticated())
+                    .map(|e| e.value().clone())
+                    .collect();
+                let mut message = message;
+                match &private {
+                    Some((sender, to)) => {
+                        let found = rcs.iter().any(|rc| rc.get_account_name().eq_ignore_ascii_case(to));
+                        rcs.retain(|rc| rc.player_id == *sender || rc.get_account_name().eq_ignore_ascii_case(to));
+                        if !found {
+                            message = format!("Server: {} isn't on RC", to);
+                        }
+                    }
+                    None => context.rc_chat().record(message.clone()),
+                }
+
+                for rc in rcs {
+                    let packet = PacketOut::new(PacketTypeOut::RcChat, message.clone().into_bytes());
+                    if let Err(e) = rc.send_packet(packet).await {
+                        tracing::debug!("Failed to notify RC {}: {}", rc.player_id.get(), e);
+                    }
+                }
+            }
+        })
+    }
+
+    /// Deliver IRC channel lines to the channel members on this server
+    ///
+    /// # Events
+    /// - `IrcMessage` - Sent as PLO_SERVERTEXT to every member except the sender
+    fn spawn_irc_relay(&self) -> tokio::task::JoinHandle<()> {
+        use crate::irc::join_tokens;
+        use gserver_game::GameEvent;
+        use gserver_protocol::{PacketOut, PacketTypeOut};
+        use tokio::sync::broadcast::error::RecvError;
+
+        let mut events = self.context.events().subscribe();
+        let connections = self.connections.clone();
+        let context = self.context.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let (channel, from, message, sender) = match events.recv().await {
+                    Ok(GameEvent::IrcMessage { channel, from, message, sender }) => (channel, from, message, sender),
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("IRC relay skipped {} events", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                let text = join_tokens(&["GraalEngine", "irc", "privmsg", &from, &channel, &message]);
+                for id in context.irc().members(&channel) {
+                    if Some(id) == sender {
+                        continue;
+                    }
+                    let Some(conn) = connections.get(&id).map(|e| e.value().clone()) else {
+                        continue;
+                    };
+                    let packet = PacketOut::new(PacketTypeOut::ServerText, text.clone().into_bytes());
+                    if let Err(e) = conn.send_packet(packet).await {
+                        tracing::debug!("Failed to relay IRC line to {}: {}", id.get(), e);
+                    }
+                }
+            }
+        })
+    }
+
+    /// Run scheduled ambience changes and send changed overlays to the
+    /// players on affected levels
+    ///
+    /// # Events
+    /// - `AmbienceChanged` - Sent as PLO_SHOWIMG with the level's effective overlay
+    fn spawn_ambience_relay(&self) -> tokio::task::JoinHandle<()> {
+        use gserver_game::GameEvent;
+        use gserver_protocol::{PacketOut, PacketTypeOut};
+        use tokio::sync::broadcast::error::RecvError;
+
+        let mut events = self.context.events().subscribe();
+        let connections = self.connections.clone();
+        let context = self.context.clone();
+
+        tokio::spawn(async move {
+            let mut schedule = tokio::time::interval(std::time::Duration::from_secs(1));
+            loop {
+                let (scope, effect) = tokio::select! {
+                    _ = schedule.tick() => {
+                        // Changes come back around as AmbienceChanged events
+                        context.ambience().run_due(std::time::Instant::now());
+                        continue;
+                    }
+                    event = events.recv() => match event {
+                        Ok(GameEvent::AmbienceChanged { scope, effect }) => (scope, effect),
+                        Ok(_) => continue,
+                        Err(RecvError::Lagged(skipped)) => {
+                            tracing::warn!("Ambience relay skipped {} events", skipped);
+                            continue;
+                        }
+                        Err(RecvError::Closed) => break,
+                    },
+                };
+
+                let players: Vec<_> = connections.iter()
+                    .filter(|e| e.value().is_authenticated() && !e.value().is_rc())
+                    .map(|e| e.value().clone())
+                    .collect();
+                for conn in players {
+                    let level = conn.get_level();
+                    if !context.ambience().covers(&scope, &level) {
+                        continue;
+                    }
+                    let image = context.ambience().image(&level, effect);
+                    let packet = PacketOut::new(PacketTypeOut::ShowImg, image.to_packet_text().into_bytes());
+                    context.level_stats().record_broadcast(&level, packet.packet_data.len(), std::time::Instant::now());
+                    if let Err(e) = conn.send_packet(packet).await {
+                        tracing::debug!("Failed to send ambience to {}: {}", conn.player_id.get(), e);
+                    }
+                }
+            }
+        })
+    }
+
+    /// Route level instance packets and level group changes
+    ///
+    /// # Events
+    /// - `InstancePacket` - Sent to the players in that level instance
+    /// - `LevelPacket` - Sent to the players on that level
+    /// - `LevelReloaded` - Warps the players on the level to where they stand
+    /// - `LevelGroupSet` - Puts every player on the level in the group
+    /// - `ObjectThrown` - Hurts the players the object lands on
+    /// - `AreaHit` - Sends PLO_HITOBJECTS to the players inside the area
+    /// - `PlayerPropsChanged` - Sent to the players in range, who also get
+    ///   each other's full props or leaves as they come into or go out of
+    ///   range (see [`crate::interest`])
+    /// - `PlayerLeft` - Sent as a leave to the players that had it in range
+    ///
+    /// Every packet sent to a player here, and the ambience relay's
+    /// overlays, count as broadcasts on the receiver's level in
+    /// [`LevelStats`](crate::levelstats::LevelStats).
+    fn spawn_instance_relay(&self) -> tokio::task::JoinHandle<()> {
+        use gserver_core::PixelCoord;
+        use gserver_game::GameEvent;
+        use gserver_protocol::{codecs::*, PacketOut, PacketTypeOut};
+        use tokio::sync::broadcast::error::RecvError;
+
+        let mut events = self.context.events().subscribe();
+        let connections = self.connections.clone();
+        let context = self.context.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Instance relay skipped {} events", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                match event {
+                    GameEvent::InstancePacket { instance, except, packet_type, data } => {
+                        let players: Vec<_> = {
+                            let groups = context.groups().lock();
+                            connections.iter()
+                                .filter(|e| Some(*e.key()) != except && e.value().is_authenticated())
+                                .filter(|e| groups.instance_key(*e.key(), &e.value().get_level()) == instance)
+                                .map(|e| e.value().clone())
+                                .collect()
+                        };
+                        let now = std::time::Instant::now();
+                        for conn in players {
+                            context.level_stats().record_broadcast(&instance.level, data.len(), now);
+                            if let Err(e) = conn.send_packet(PacketOut::new(packet_type, data.clone())).await {
+                                tracing::debug!("Failed to send instance packet to {}: {}", conn.player_id.get(), e);
+                            }
+                        }
+                    }
+                    GameEvent::LevelPacket { level, packet_type, data } => {
+                        let players: Vec<_> = connections.iter()
+                            .filter(|e| e.value().is_authenticated() && e.value().get_level().eq_ignore_ascii_case(&level))
+                            .map(|e| e.value().clone())
+                            .collect();
+                        let now = std::time::Instant::now();
+                        for conn in players {
+                            context.level_stats().record_broadcast(&level, data.len(), now);
+                            if let Err(e) = conn.send_packet(PacketOut::new(packet_type, data.clone())).await {
+                                tracing::debug!("Failed to send level packet to {}: {}", conn.player_id.get(), e);
+                            }
+                        }
+                    }
+                    GameEvent::ObjectThrown { thrower, instance, landing, power } if power > 0 => {
+                        let victims: Vec<_> = {
+                            let groups = context.groups().lock();
+                            connections.iter()
+                                .filter(|e| *e.key() != thrower && e.value().is_authenticated())
+                                .filter(|e| gserver_game::carry::hits(landing, e.value().get_pixel_position()))
+                                .filter(|e| groups.instance_key(*e.key(), &e.value().get_level()) == instance)
+                                .map(|e| e.value().clone())
+                                .collect()
+                        };
+                        let now = std::time::Instant::now();
+                        for conn in victims {
+                            // {GSHORT attacker}{GCHAR dx}{GCHAR dy}{GCHAR power}{GINT npc}
+                            let (x, y) = conn.get_pixel_position();
+                            let mut data = bytes::BytesMut::new();
+                            write_gshort(&mut data, thrower.get() as i16);
+                            write_gchar(&mut data, (x - landing.0).signum() as i8);
+                            write_gchar(&mut data, (y - landing.1).signum() as i8);
+                            write_gchar(&mut data, power as i8);
+                            write_gint(&mut data, 0);
+                            context.level_stats().record_broadcast(&instance.level, data.len(), now);
+                            if let Err(e) = conn.send_packet(PacketOut::new(PacketTypeOut::HurtPlayer, data.to_vec())).await {
+                                tracing::debug!("Failed to send thrown object hit to {}: {}", conn.player_id.get(), e);
+                            }
+                        }
+                    }
+                    GameEvent::AreaHit { attacker, instance, area, power } => {
+                        let victims: Vec<_> = {
+                            let groups = context.groups().lock();
+                            connections.iter()
+                                .filter(|e| *e.key() != attacker && e.value().is_authenticated())
+                                .filter(|e| area.touches(e.value().get_pixel_position()))
+                                .filter(|e| groups.instance_key(*e.key(), &e.value().get_level()) == instance)
+                                .map(|e| e.value().clone())
+                                .collect()
+                        };
+                        let now = std::time::Instant::now();
+                        for conn in victims {
+                            // {GSHORT attacker}{GCHAR power}{GCHAR x}{GCHAR y}, x/y in half tiles
+                            let mut data = bytes::BytesMut::new();
+                            write_gshort(&mut data, attacker.get() as i16);
+                            write_gchar(&mut data, power as i8);
+                            write_gchar(&mut data, PixelCoord(area.x).to_half_tiles().0 as i8);
+                            write_gchar(&mut data, PixelCoord(area.y).to_half_tiles().0 as i8);
+                            context.level_stats().record_broadcast(&instance.level, data.len(), now);
+                            if let Err(e) = conn.send_packet(PacketOut::new(PacketTypeOut::HitObjects, data.to_vec())).await {
+                                tracing::debug!("Failed to send hit to {}: {}", conn.player_id.get(), e);
+                            }
+                        }
+                    }
+                    GameEvent::LevelReloaded { level } => {
+                        let players: Vec<_> = connections.iter()
+                            .filter(|e| e.value().is_authenticated() && e.value().get_level().eq_ignore_ascii_case(&level))
+                            .map(|e| e.value().clone())
+                            .collect();
+                        for conn in players {
+                            let (x, y) = conn.get_position();
+                            if let Err(e) = conn.send_player_warp(&conn.get_level(), x, y).await {
+                                tracing::debug!("Failed to resend level {} to {}: {}", level, conn.player_id.get(), e);
+                            }
+                        }
+                    }
+                    GameEvent::LevelGroupSet { level, group } => {
+                        let mut groups = context.groups().lock();
+                        for entry in connections.iter() {
+                            if entry.value().get_level().eq_ignore_ascii_case(&level) {
+                                groups.set_group(*entry.key(), &group);
+                            }
+                        }
+                    }
+                    GameEvent::PlayerPropsChanged { player, data } => {
+                        let Some(conn) = connections.get(&player).map(|e| e.value().clone()) else {
+                            continue;
+                        };
+                        if conn.is_rc() || !conn.is_authenticated() {
+                            continue;
+                        }
+                        let change = context.interest().update(player, conn.world_position(), &context.config().read());
+                        let others = |ids: Vec<PlayerID>| -> Vec<_> {
+                            ids.into_iter().filter_map(|id| connections.get(&id).map(|e| e.value().clone())).collect()
+                        };
+
+                        let mut packets = Vec::new();
+                        for other in others(change.entered) {
+                            packets.push((other.clone(), conn.other_props_data(other.client_version())));
+                            packets.push((conn.clone(), other.other_props_data(conn.client_version())));
+                        }
+                        for other in others(change.left) {
+                            packets.push((other.clone(), crate::interest::leave_data(player)));
+                            packets.push((conn.clone(), crate::interest::leave_data(other.player_id)));
+                        }
+                        if !data.is_empty() {
+                            // {GSHORT id}{props}
+                            let mut update = bytes::BytesMut::new();
+                            write_gshort(&mut update, player.get() as i16);
+                            update.extend_from_slice(&data);
+                            for other in others(change.viewers) {
+                                packets.push((other, update.to_vec()));
+                            }
+                        }
+                        let now = std::time::Instant::now();
+                        for (to, data) in packets {
+                            context.level_stats().record_broadcast(&to.get_level(), data.len(), now);
+                            if let Err(e) = to.send_packet(PacketOut::new(PacketTypeOut::OtherPlayerProps, data)).await {
+                                tracing::debug!("Failed to send player props to {}: {}", to.player_id.get(), e);
+                            }
+                        }
+                    }
+                    GameEvent::PlayerLeft { id, .. } => {
+                        let now = std::time::Instant::now();
+                        for other in context.interest().remove(id) {
+                            let Some(conn) = connections.get(&other).map(|e| e.value().clone()) else {
+                                continue;
+                            };
+                            context.level_stats().record_broadcast(&conn.get_level(), crate::interest::leave_data(id).len(), now);
+                            if let Err(e) = conn.send_packet(PacketOut::new(PacketTypeOut::OtherPlayerProps, crate::interest::leave_data(id))).await {
+                                tracing::debug!("Failed to send player leave to {}: {}", other.get(), e);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        })
+    }
+
+    /// Send one-player packets
+    ///
+    /// # Events
+    /// - `PlayerPacket` - Sent to that player
+    /// - `WeaponChanged` - Resent to every player who has the weapon
+    /// - `PrivateMessage` - Sent to each recipient that doesn't ignore the sender
+    /// - `AdminMessage` - Sent to the target, or to every player (not RCs)
+    fn spawn_player_relay(&self) -> tokio::task::JoinHandle<()> {
+        use gserver_game::GameEvent;
+        use gserver_protocol::{PacketOut, PacketTypeOut};
+        use tokio::sync::broadcast::error::RecvError;
+
+        let mut events = self.context.events().subscribe();
+        let connections = self.connections.clone();
+        let context = self.context.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Player relay skipped {} events", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                match event {
+                    GameEvent::PlayerPacket { player, packet_type, data } => {
+                        let Some(conn) = connections.get(&player).map(|e| e.value().clone()) else {
+                            continue;
+                        };
+                        if let Err(e) = conn.send_packet(PacketOut::new(packet_type, data)).await {
+                            tracing::debug!("Failed to send packet to {}: {}", player.get(), e);
+                        }
+                    }
+                    GameEvent::WeaponChanged { name, .. } => {
+                        let Some(build) = context.weapons().build(&name, context.classes()) else {
+                            continue;
+                        };
+                        let players: Vec<_> = connections.iter()
+                            .filter(|e| e.value().is_authenticated() && e.value().has_weapon(&name))
+                            .map(|e| e.value().clone())
+                            .collect();
+                        for conn in players {
+                            if let Err(e) = conn.deliver_weapon(&build).await {
+                                tracing::debug!("Failed to resend weapon {} to {}: {}", name, conn.player_id.get(), e);
+                            }
+                        }
+                    }
+                    GameEvent::PrivateMessage { from, account, to, data } => {
+                        for id in to {
+                            let Some(conn) = connections.get(&id).map(|e| e.value().clone()) else {
+                                continue;
+                            };
+                            // Ignored senders aren't told, so the PM just doesn't arrive
+                            if !conn.accepts_pm_from(&account) {
+                                tracing::debug!("PM from {} to {} blocked", from.get(), id.get());
+                                continue;
+                            }
+                            if let Err(e) = conn.send_packet(PacketOut::new(PacketTypeOut::PrivateMessage, data.clone())).await {
+                                tracing::debug!("Failed to send PM to {}: {}", id.get(), e);
+                            }
+                        }
+                    }
+                    GameEvent::AdminMessage { to, from, message } => {
+                        let data = crate::announce::admin_message_data(&from, &message);
+                        let players: Vec<_> = match to {
+                            Some(id) => connections.get(&id).map(|e| e.value().clone()).into_iter().collect(),
+                            None => connections.iter()
+                                .filter(|e| e.value().is_authenticated() && !e.value().is_rc())
+                                .map(|e| e.value().clone())
+                                .collect(),
+                        };
+                        for conn in players {
+                            if let Err(e) = conn.send_packet(PacketOut::new(PacketTypeOut::RcAdminMessage, data.clone())).await {
+                                tracing::debug!("Failed to send admin message to {}: {}", conn.player_id.get(), e);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        })
+    }
+
+    /// Carry out the commands of the context's [`CommandQueue`](gserver_game::CommandQueue)
+    ///
+    /// # Commands
+    /// - `ControlRequested` - Applied to every player of the account
+    /// - `SocialEdited` - Applied to the account's connections, or its file if offline
+    /// - `RecordEdited` - Applied to the account's connections, or its file if offline
+    /// - `SanctionRequested` - Applied to the account's players, or its file if offline
+    /// - `LoginVerified` - Recorded in the verification cache; a rejected player is disconnected
+    /// - `StaffRightsChanged` - Re-evaluated by the account's connections (everyone's if no
+    ///   account), or set in its file if offline
+    /// - `DisconnectRequested` - That player is disconnected with the reason
+    ///
+    /// Offline account files are edited on their own tasks (see [`OfflineEdits`]),
+    /// so a save the edit waits for doesn't hold up the commands behind it.
+    fn spawn_command_relay(&self) -> tokio::task::JoinHandle<()> {
+        use gserver_game::GameCommand;
+
+        let receiver = self.context.commands().take_receiver();
+        let connections = self.connections.clone();
+        let context = self.context.clone();
+
+        tokio::spawn(async move {
+            let Some(mut commands) = receiver else {
+                tracing::error!("Command queue has another relay, commands are not carried out");
+                return;
+            };
+            let mut offline = OfflineEdits::default();
+            loop {
+                let Some(command) = commands.recv().await else {
+                    break;
+                };
+
+                match command {
+                    GameCommand::ControlRequested { account, action } => {
+                        let players: Vec<_> = connections.iter()
+                            .filter(|e| e.value().get_account_name().eq_ignore_ascii_case(&account))
+                            .map(|e| *e.key())
+                            .collect();
+                        for id in players {
+                            context.control().apply(id, action);
+                        }
+                    }
+                    GameCommand::SocialEdited { account, edit } => {
+                        let players: Vec<_> = connections.iter()
+                            .filter(|e| e.value().get_account_name().eq_ignore_ascii_case(&account))
+                            .map(|e| e.value().clone())
+                            .collect();
+                        if players.is_empty() {
+                            offline.spawn(&context, account, move |context, account| {
+                                if let Err(e) = crate::social::edit_account_file(context.server_dir(), account, &edit) {
+                                    tracing::warn!("Failed to change the lists of {}: {}", account, e);
+                                }
+                            });
+                            continue;
+                        }
+                        for conn in players {
+                            conn.apply_social_edit(&edit);
+                        }
+                    }
+                    GameCommand::RecordEdited { account, edit } => {
+                        let players: Vec<_> = connections.iter()
+                            .filter(|e| e.value().get_account_name().eq_ignore_ascii_case(&account))
+                            .map(|e| e.value().clone())
+                            .collect();
+                        if players.is_empty() {
+                            offline.spawn(&context, account, move |context, account| {
+                                match crate::bans::edit_account_file(context.server_dir(), account, &edit) {
+                                    Ok(before) => if let gserver_game::RecordEdit::Gralats { amount, issuer } = &edit {
+                                        let source = crate::economy::GralatSource::Rc(issuer.clone());
+                                        crate::economy::audit(context, &before.name, before.gralats, *amount, &source);
+                                    },
+                                    Err(e) => tracing::warn!("Failed to change the record of {}: {}", account, e),
+                                }
+                            });
+                            continue;
+                        }
+                        for conn in players {
+                            if let Err(e) = conn.apply_record_edit(&edit).await {
+                                tracing::warn!("Failed to change the record of {} ({}): {}", account, conn.player_id.get(), e);
+                            }
+                        }
+                    }
+                    GameCommand::SanctionRequested { account, sanction } => {
+                        let players: Vec<_> = connections.iter()
+                            .filter(|e| !e.value().is_rc() && e.value().get_account_name().eq_ignore_ascii_case(&account))
+                            .map(|e| e.value().clone())
+                            .collect();
+                        if players.is_empty() {
+                            offline.spawn(&context, account, move |context, account| {
+                                let config = context.config().read();
+                                if let Err(e) = crate::moderation::edit_account_file(context.server_dir(), account, sanction, &config) {
+                                    tracing::warn!("Failed to sanction {}: {}", account, e);
+                                }
+                            });
+                            continue;
+                        }
+                        for conn in players {
+                            if let Err(e) = conn.apply_sanction(sanction).await {
+                                tracing::warn!("Failed to sanction {} ({}): {}", account, conn.player_id.get(), e);
+                            }
+                        }
+                    }
+                    GameCommand::LoginVerified { player, account, verified } => {
+                        if verified {
+                            context.verifications().record(&account, gserver_game::moderation::unix_now());
+                            continue;
+                        }
+                        context.verifications().forget(&account);
+                        let Some(conn) = connections.get(&player).map(|e| e.value().clone()) else {
+                            continue;
+                        };
+                        // The ID may belong to someone else by now
+                        if conn.get_account_name().eq_ignore_ascii_case(&account) {
+                            if let Err(e) = conn.disconnect(crate::verification::REJECTED_MESSAGE).await {
+                                tracing::debug!("Failed to disconnect {}: {}", player.get(), e);
+                            }
+                        }
+                    }
+                    GameCommand::StaffRightsChanged { account, rights } => {
+                        let players: Vec<_> = connections.iter()
+                            .filter(|e| e.value().is_authenticated())
+                            .filter(|e| account.as_ref().is_none_or(|a| e.value().get_account_name().eq_ignore_ascii_case(a)))
+                            .map(|e| e.value().clone())
+                            .collect();
+                        if let (Some(account), Some(rights), true) = (account, rights, players.is_empty()) {
+                            offline.spawn(&context, account, move |context, account| {
+                                if let Err(e) = crate::staffrights::edit_account_file(context.server_dir(), account, rights) {
+                                    tracing::warn!("Failed to set the rights of {}: {}", account, e);
+                                }
+                            });
+                            continue;
+                        }
+                        for conn in players {
+                            if let Err(e) = conn.reevaluate_staff(rights).await {
+                                tracing::debug!("Failed to update the rights of {}: {}", conn.player_id.get(), e);
+                            }
+                        }
+                    }
+                    GameCommand::DisconnectRequested { player, reason } => {
+                        let Some(conn) = connections.get(&player).map(|e| e.value().clone()) else {
+                            continue;
+                        };
+                        if let Err(e) = conn.disconnect(&reason).await {
+                            tracing::debug!("Failed to disconnect {}: {}", player.get(), e);
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Reload class files edited on disk every `classcheckinterval` seconds
+    ///
+    /// Weapons that join a changed class are resent through `WeaponChanged`
+    /// events (see [`crate::weaponsync`]). An interval of 0 turns it off.
+    fn spawn_class_watcher(&self) -> tokio::task::JoinHandle<()> {
+        let context = self.context.clone();
+        let interval = context.config().read().class_check_interval;
+
+        tokio::spawn(async move {
+            if interval == 0 {
+                return;
+            }
+            let mut check = tokio::time::interval(std::time::Duration::from_secs(interval));
+            // The first tick completes immediately, right after loading
+            check.tick().await;
+            loop {
+                check.tick().await;
+                crate::weaponsync::check_class_files(&context);
+            }
+        })
+    }
+
+    /// Reload levels edited on disk every `levelcheckinterval` seconds
+    ///
+    /// See [`crate::levelreload`]. An interval of 0 turns it off.
+    fn spawn_level_watcher(&self) -> tokio::task::JoinHandle<()> {
+        let context = self.context.clone();
+        let interval = context.config().read().level_check_interval;
+
+        tokio::spawn(async move {
+            if interval == 0 {
+                return;
+            }
+            let mut check = tokio::time::interval(std::time::Duration::from_secs(interval));
+            check.tick().await;
+            loop {
+                check.tick().await;
+                crate::levelreload::check_level_files(&context);
+            }
+        })
+    }
+
+    /// Log the busiest packet handlers every `handlersummaryinterval` seconds
+    ///
+    /// See [`crate::timings`]. An interval of 0 turns it off.
+    fn spawn_handler_summary(&self) -> tokio::task::JoinHandle<()> {
+        let context = self.context.clone();
+        let interval = context.config().read().handler_summary_interval;
+
+        tokio::spawn(async move {
+            if interval == 0 {
+                return;
+            }
+            let mut summary = tokio::time::interval(std::time::Duration::from_secs(interval));
+            summary.tick().await;
+            loop {
+                summary.tick().await;
+                let busiest = context.handler_timings().busiest(BUSIEST_HANDLERS);
+                if !busiest.is_empty() {
+                    let lines: Vec<String> = busiest.iter().map(HandlerTiming::describe).collect();
+                    tracing::info!("Busiest packet handlers: {}", lines.join("; "));
+                }
+            }
+        })
+    }
+
+    /// Send scheduled announcements (RC `/announce ... in <seconds>`) when due
+    fn spawn_announcer(&self) -> tokio::task::JoinHandle<()> {
+        let context = self.context.clone();
+
+        tokio::spawn(async move {
+            let mut schedule = tokio::time::interval(std::time::Duration::from_secs(1));
+            loop {
+                schedule.tick().await;
+                // Messages go out as AdminMessage events
+                context.announcements().run_due(std::time::Instant::now());
+            }
+        })
+    }
+
     /// Register a packet handler function
     ///
     /// # Arguments
@@ -281,14 +1114,14 @@ impl GServer {
     /// # Example
     ///
     /// ```rust,no_run
-    /// server.register_handler_function(PacketTypeIn::LevelWarp, |packet| async move {
+    /// server.register_handler_function(PacketTypeIn::LevelWarp, |ctx, packet| async move {
     ///     println!("Handling level warp");
     ///     Ok(())
     /// });
     /// ```
     pub fn register_handler_function<F, Fut>(&self, packet_type: gserver_protocol::PacketTypeIn, handler: F)
     where
-        F: Fn(&gserver_protocol::PacketIn) -> Fut + Send + Sync + 'static,
+        F: Fn(Arc<ServerContext>, &gserver_protocol::PacketIn) -> Fut + Send + Sync + 'static,
         Fut: std::future::Future<Output = Result<()>> + Send + 'static,
     {
         let mut handlers = self.handlers.lock();
@@ -296,6 +1129,36 @@ impl GServer {
         tracing::debug!("Registered handler for packet type: {:?}", packet_type);
     }
 
+    /// Build the autosave service for this server
+    ///
+    /// # Purpose
+    /// Registers the account, server flag, level, NPC and IP history targets. The caller
+    /// spawns `run()` on the returned service.
+    pub fn autosave_service(&self, config: gserver_game::AutosaveConfig) -> gserver_game::AutosaveService {
+        use crate::autosave::{AccountAutosave, IpHistoryAutosave, LevelAutosave, NpcSaveAutosave, ServerFlagsAutosave};
+
+        let mut service = gserver_game::AutosaveService::new(config);
+        service.add_target(Arc::new(AccountAutosave::new(self.connections.clone())));
+        service.add_target(Arc::new(ServerFlagsAutosave::new(self.context.clone())));
+        service.add_target(Arc::new(LevelAutosave::new(self.context.clone())));
+        service.add_target(Arc::new(NpcSaveAutosave::new(self.context.clone())));
+        service.add_target(Arc::new(IpHistoryAutosave::new(self.context.clone())));
+        service
+    }
+
+    /// Get the shared server context
+    #[inline]
+    pub fn context(&self) -> &Arc<ServerContext> {
+        &self.context
+    }
+
+    /// Get the address the game listener is bound to
+    ///
+    /// Tells the actual port when the server was bound to port 0.
+    pub fn local_addr(&self) -> Result<std::net::SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
     /// Get the number of active connections
     ///
     /// # Returns
@@ -491,55 +1354,6 @@ impl GServer {
         sent_count
     }
 
-    /// Send a packet to players within a certain range on the same level
-    ///
-    /// # C++ Equivalence
-    /// Matches `sendPacketToNearbyPlayers()` in Player.cpp
-    ///
-    /// # Arguments
-    /// * `packet` - The packet to send
-    /// * `level_name` - The level name to filter by
-    /// * `x` - Center X position (in pixels)
-    /// * `y` - Center Y position (in pixels)
-    /// * `range` - Range in pixels (default ~64 for visible area)
-    ///
-    /// # Returns
-    /// The number of players the packet was sent to
-    pub async fn send_to_nearby_players(&self, packet: gserver_protocol::PacketOut, level_name: &str, x: f32, y: f32, range: f32) -> usize {
-        let mut sent_count = 0;
-        let range_sq = range * range; // Use squared distance for comparison
-
-        for entry in self.connections.iter() {
-            let conn = entry.value();
-            if !conn.is_authenticated() {
-                continue;
-            }
-
-            // Check if player is on this level
-            if conn.get_level() != level_name {
-                continue;
-            }
-
-            // Check distance
-            let (px, py) = conn.get_position();
-            let dx = px - x;
-            let dy = py - y;
-            if dx * dx + dy * dy > range_sq {
-                continue;
-            }
-
-            if let Err(e) = conn.send_packet(packet.clone()).await {
-                tracing::warn!("Failed to send to connection {}: {:?}",
-                    entry.key().get(), e);
-            } else {
-                sent_count += 1;
-            }
-        }
-
-        tracing::debug!("Sent packet to {} nearby players on level {}", sent_count, level_name);
-        sent_count
-    }
-
     /// Send a packet to a specific player by ID
     ///
     /// # Arguments
@@ -599,26 +1413,104 @@ impl GServer {
     /// # Returns
     /// A snapshot of current server statistics
     pub fn stats(&self) -> ServerStats {
-        let mut total_bytes_rx = 0;
-        let mut total_bytes_tx = 0;
-        let mut total_packets_rx = 0;
-        let mut total_packets_tx = 0;
+        collect_stats(&self.connections, &self.context)
+    }
+}
 
-        for entry in self.connections.iter() {
-            let conn = entry.value();
-            total_bytes_rx += conn.bytes_received();
-            total_bytes_tx += conn.bytes_sent();
-            total_packets_rx += conn.packets_received();
-            total_packets_tx += conn.packets_sent();
-        }
+/// Received packet types listed in [`ServerStats::top_packets`]
+const TOP_PACKETS: usize = 5;
 
-        ServerStats {
-            connections: self.connections.len(),
-            total_bytes_received: total_bytes_rx,
-            total_bytes_sent: total_bytes_tx,
-            total_packets_received: total_packets_rx,
-            total_packets_sent: total_packets_tx,
+/// Packet handlers listed in [`ServerStats::handlers`] and the periodic summary
+const BUSIEST_HANDLERS: usize = 5;
+
+/// Take a statistics snapshot of a set of connections
+///
+/// Traffic counters and countries come from the context, the rest from the
+/// Edits of offline account files, spawned by the command relay
+///
+/// Each edit runs once the account's pending save is written (see
+/// [`crate::savequeue`]), on a blocking thread. The edits of one account run
+/// in the order they were spawned, each after the one before.
+#[derive(Default)]
+struct OfflineEdits {
+    /// Last edit of each account (lowercase name)
+    tasks: std::collections::HashMap<String, tokio::task::JoinHandle<()>>,
+}
+
+impl OfflineEdits {
+    /// Edit an account's file after its earlier edits and its pending save
+    fn spawn(&mut self, context: &Arc<ServerContext>, account: String,
+        edit: impl FnOnce(&ServerContext, &str) + Send + 'static) {
+        self.tasks.retain(|_, task| !task.is_finished());
+        let key = account.to_lowercase();
+        let previous = self.tasks.remove(&key);
+        let context = context.clone();
+        let task = tokio::spawn(async move {
+            if let Some(previous) = previous {
+                let _ = previous.await;
+            }
+            context.account_saves().wait(&account).await;
+            if let Err(e) = tokio::task::spawn_blocking(move || edit(&context, &account)).await {
+                tracing::error!("Offline account edit failed: {}", e);
+            }
+        });
+        self.tasks.insert(key, task);
+    }
+}
+
+/// connections.
+pub fn collect_stats(connections: &dashmap::DashMap<PlayerID, Arc<PlayerConnection>>, context: &ServerContext) -> ServerStats {
+    let mut total_bytes_rx = 0;
+    let mut total_bytes_tx = 0;
+    let mut total_packets_rx = 0;
+    let mut total_packets_tx = 0;
+    let mut rtts = Vec::new();
+    let mut max_outbound_queue = 0;
+    let mut dropped_packets = 0;
+    let mut generations = BTreeMap::new();
+    let mut worst_compression_ratio: Option<f64> = None;
+
+    for entry in connections.iter() {
+        let conn = entry.value();
+        total_bytes_rx += conn.bytes_received();
+        total_bytes_tx += conn.bytes_sent();
+        total_packets_rx += conn.packets_received();
+        total_packets_tx += conn.packets_sent();
+        rtts.extend(conn.rtt());
+        let outbound = conn.outbound_metrics();
+        max_outbound_queue = max_outbound_queue.max(outbound.queued_bytes);
+        dropped_packets += outbound.dropped_packets;
+        if let Some(ratio) = conn.compression_stats().total().ratio() {
+            worst_compression_ratio = Some(worst_compression_ratio.map_or(ratio, |worst| worst.max(ratio)));
         }
+        if conn.is_authenticated() {
+            let generation = if conn.is_rc() { "RC".to_string() } else { format!("{:?}", conn.client_version()) };
+            *generations.entry(generation).or_insert(0) += 1;
+        }
+    }
+
+    let traffic = context.traffic();
+    ServerStats {
+        connections: connections.len(),
+        total_bytes_received: total_bytes_rx,
+        total_bytes_sent: total_bytes_tx,
+        total_packets_received: total_packets_rx,
+        total_packets_sent: total_packets_tx,
+        average_rtt: (!rtts.is_empty()).then(|| rtts.iter().sum::<Duration>() / rtts.len() as u32),
+        max_rtt: rtts.into_iter().max(),
+        max_outbound_queue,
+        dropped_packets,
+        countries: context.geoip().counts(),
+        generations,
+        top_packets: traffic.top_packets(TOP_PACKETS).into_iter()
+            .map(|(id, count)| (crate::timings::packet_name(id), count))
+            .collect(),
+        compression_ratio: traffic.compression_ratio(),
+        compression_methods: context.compressor().stats().used_methods(),
+        worst_compression_ratio,
+        batching_saved_bytes: traffic.batching_saved_bytes(),
+        handlers: context.handler_timings().busiest(BUSIEST_HANDLERS),
+        listserver: context.listserver().latency_report(),
     }
 }
 
@@ -642,6 +1534,115 @@ pub struct ServerStats {
 
     /// Total packets sent to all connections
     pub total_packets_sent: u64,
+
+    /// Average smoothed round-trip time of connections that answered a keepalive
+    pub average_rtt: Option<Duration>,
+
+    /// Largest smoothed round-trip time
+    pub max_rtt: Option<Duration>,
+
+    /// Deepest outbound queue (normal bytes waiting) of any connection
+    pub max_outbound_queue: usize,
+
+    /// Packets dropped for backpressure by the current connections
+    pub dropped_packets: u64,
+
+    /// Logged-in players of each country, empty without GeoIP
+    pub countries: BTreeMap<String, usize>,
+
+    /// Logged-in clients of each generation (`V6`, `RC`, ...)
+    pub generations: BTreeMap<String, usize>,
+
+    /// Most received packet types since startup, with their counts
+    pub top_packets: Vec<(String, u64)>,
+
+    /// Sent bytes after compression per byte before, since startup
+    pub compression_ratio: Option<f64>,
+
+    /// Bundles, bytes before and after, and time of each compression method used since startup
+    pub compression_methods: Vec<(CompressionMethod, MethodStats)>,
+
+    /// Highest compression ratio of the current connections
+    pub worst_compression_ratio: Option<f64>,
+
+    /// Bundle length prefixes saved by batching packets, since startup
+    pub batching_saved_bytes: u64,
+
+    /// Packet handlers that took the most time since startup (see [`crate::timings`])
+    pub handlers: Vec<HandlerTiming>,
+
+    /// Listserver failed registrations (see [`crate::listlatency`])
+    pub listserver: LatencyReport,
+}
+
+impl ServerStats {
+    /// Summarize the stats in one line for RC `/stats`
+    pub fn summary(&self) -> String {
+        let list = |entries: Vec<String>| if entries.is_empty() { "none".to_string() } else { entries.join(", ") };
+        format!("{} connections ({}); in {} packets/{} bytes, out {} packets/{} bytes; \
+            top packets: {}; compression {} ({}); batching saved {} bytes; ping {}; busiest handlers: {}",
+            self.connections,
+            list(self.generations.iter().map(|(generation, count)| format!("{} {}", generation, count)).collect()),
+            self.total_packets_received, self.total_bytes_received,
+            self.total_packets_sent, self.total_bytes_sent,
+            list(self.top_packets.iter().map(|(name, count)| format!("{} {}", name, count)).collect()),
+            self.compression_ratio.map_or_else(|| "n/a".to_string(), |ratio| format!("{:.0}%", ratio * 100.0)),
+            list(self.compression_methods.iter()
+                .map(|(method, stats)| format!("{} {}: {} -> {} bytes in {:.1}ms", method.name(), stats.bundles,
+                    stats.bytes_in, stats.bytes_out, stats.time.as_secs_f64() * 1000.0))
+                .collect()),
+            self.batching_saved_bytes,
+            self.average_rtt.map_or_else(|| "n/a".to_string(), |rtt| format!("{} ms", rtt.as_millis())),
+            list(self.handlers.iter().map(HandlerTiming::describe).collect()))
+    }
+
+    /// JSON object for the health service's `/stats`
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "connections": self.connections,
+            "generations": self.generations,
+            "bytes_received": self.total_bytes_received,
+            "bytes_sent": self.total_bytes_sent,
+            "packets_received": self.total_packets_received,
+            "packets_sent": self.total_packets_sent,
+            "average_rtt_ms": self.average_rtt.map(|rtt| rtt.as_millis() as u64),
+            "max_rtt_ms": self.max_rtt.map(|rtt| rtt.as_millis() as u64),
+            "max_outbound_queue": self.max_outbound_queue,
+            "dropped_packets": self.dropped_packets,
+            "countries": self.countries,
+            "top_packets": self.top_packets.iter()
+                .map(|(name, count)| serde_json::json!({ "type": name, "count": count }))
+                .collect::<Vec<_>>(),
+            "compression_ratio": self.compression_ratio,
+            "compression": self.compression_methods.iter()
+                .map(|(method, stats)| (method.name().to_string(), serde_json::json!({
+                    "bundles": stats.bundles,
+                    "bytes_in": stats.bytes_in,
+                    "bytes_out": stats.bytes_out,
+                    "time_us": stats.time.as_micros() as u64,
+                })))
+                .collect::<serde_json::Map<_, _>>(),
+            "worst_connection_compression_ratio": self.worst_compression_ratio,
+            "batching_saved_bytes": self.batching_saved_bytes,
+            "listserver": {
+                "connected": self.listserver.connected,
+                "failures": self.listserver.failures,
+                "total_failures": self.listserver.total_failures,
+            },
+            "handlers": self.handlers.iter()
+                .map(|timing| serde_json::json!({
+                    "type": timing.name,
+                    "count": timing.count,
+                    "total_us": timing.total.as_micros() as u64,
+                    "mean_us": timing.mean().as_micros() as u64,
+                    "p99_us": timing.percentile(0.99).as_micros() as u64,
+                    "max_us": timing.max.as_micros() as u64,
+                    "slow": timing.slow,
+                    "buckets": timing.buckets,
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -656,9 +1657,51 @@ mod tests {
             total_bytes_sent: 2048,
             total_packets_received: 100,
             total_packets_sent: 50,
+            average_rtt: None,
+            max_rtt: None,
+            max_outbound_queue: 0,
+            dropped_packets: 0,
+            countries: Default::default(),
+            generations: BTreeMap::from([("V6".to_string(), 8), ("RC".to_string(), 2)]),
+            top_packets: vec![("PlayerProps".to_string(), 60)],
+            compression_ratio: Some(0.25),
+            compression_methods: vec![(CompressionMethod::Zlib, MethodStats {
+                bundles: 4,
+                bytes_in: 2000,
+                bytes_out: 500,
+                time: Duration::from_micros(1500),
+            })],
+            worst_compression_ratio: Some(0.5),
+            batching_saved_bytes: 90,
+            handlers: Vec::new(),
+            listserver: LatencyReport { total_failures: 1, ..Default::default() },
         };
 
         assert_eq!(stats.connections, 10);
         assert_eq!(stats.total_bytes_received, 1024);
+        assert_eq!(stats.summary(), "10 connections (RC 2, V6 8); in 100 packets/1024 bytes, out 50 packets/2048 bytes; \
+            top packets: PlayerProps 60; compression 25% (zlib 4: 2000 -> 500 bytes in 1.5ms); batching saved 90 bytes; ping n/a; busiest handlers: none");
+        let json = stats.to_json();
+        assert_eq!(json["generations"]["V6"], 8);
+        assert_eq!(json["top_packets"][0]["type"], "PlayerProps");
+        assert_eq!(json["compression"]["zlib"]["bytes_out"], 500);
+        assert!(json["average_rtt_ms"].is_null());
+        assert_eq!(json["listserver"]["total_failures"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_offline_edits_run_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let context = Arc::new(ServerContext::new(dir.path(), Default::default()));
+        let order = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let mut offline = OfflineEdits::default();
+        for i in 0..20 {
+            let order = order.clone();
+            offline.spawn(&context, "Bob".into(), move |_, account| order.lock().push((account.to_string(), i)));
+        }
+        for task in offline.tasks.into_values() {
+            task.await.unwrap();
+        }
+        assert_eq!(*order.lock(), (0..20).map(|i| ("Bob".to_string(), i)).collect::<Vec<_>>());
     }
 }
@@ -0,0 +1,379 @@
+//! # Service Integration
+//!
+//! Support for running the server under systemd or in a container:
+//!
+//! - [`PidFile`] - The process ID in the `pidfile` file, removed on exit
+//! - [`HealthService`] - An HTTP health check on `healthaddress`
+//! - [`shutdown_signal`] - Ctrl-C and SIGTERM both stop the server
+//! - [`reload_on_hangup`] - SIGHUP reloads the config files
+//!
+//! The server stays in the foreground; the service manager runs it in the
+//! background (systemd `Type=simple`, a container's main process).
+//!
+//! # Health Check
+//!
+//! Every request, whatever its path (but see `/stats` and `/leaderboard`
+//! below), is answered with the server state:
+//!
+//! ```text
+//! HTTP/1.1 200 OK
+//! Content-Type: application/json
+//!
+//! {"accepting":true,"listserver":"connected","players":12,"status":"ok","uptime":3600}
+//! ```
+//!
+//! The status is 503 while the server is not accepting connections (starting
+//! up or shutting down). The listserver state is reported but doesn't affect
+//! the status, since players can still connect directly.
+//!
+//! `GET /stats` is answered with [`ServerStats::to_json`](crate::server::ServerStats::to_json)
+//! instead, always with 200, and `GET /leaderboard` with the leaderboards of
+//! [`PlayerStats::to_json`](crate::playerstats::PlayerStats::to_json).
+//!
+//! # Reload
+//!
+//! SIGHUP reloads serveroptions.txt and the other config files, reapplies the
+//! log levels and reloads the weapons. Options that are read once at startup
+//! (ports, compression, folders, log files) keep their values until restart.
+
+use crate::context::ServerContext;
+use gserver_config::ServerConfig as GameConfig;
+use gserver_core::{GServerError, Result};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+/// Time a health check client has to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Longest request head read before answering
+const MAX_REQUEST: usize = 1024;
+
+/// PID file that is removed when dropped
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// Write the current process ID to `path`
+    ///
+    /// # Errors
+    /// Fails if the file names a different process that is still running
+    /// (checked through `/proc`, so only on Linux), or can't be written.
+    pub fn create(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let pid = std::process::id();
+        if let Some(running) = Self::read(&path).filter(|&p| p != pid && process_alive(p)) {
+            return Err(GServerError::Config(format!(
+                "{} belongs to running process {}; is the server already running?", path.display(), running)));
+        }
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, format!("{}\n", pid))?;
+        Ok(Self { path })
+    }
+
+    /// Get the path of the file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read the process ID in a PID file
+    fn read(path: &Path) -> Option<u32> {
+        std::fs::read_to_string(path).ok()?.trim().parse().ok()
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // Leave the file alone if another process has taken it over
+        if Self::read(&self.path) == Some(std::process::id()) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn process_alive(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+/// Server state reported by the health check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthReport {
+    /// The accept loop is running
+    pub accepting: bool,
+    /// The listserver client is connected
+    pub listserver: bool,
+    /// Connected players
+    pub players: usize,
+    /// Time since the server started
+    pub uptime: Duration,
+}
+
+impl HealthReport {
+    /// Take the current state of the server
+    pub fn from_context(context: &ServerContext) -> Self {
+        Self {
+            accepting: context.is_accepting(),
+            listserver: context.listserver().is_connected(),
+            players: context.online_count(),
+            uptime: context.uptime(),
+        }
+    }
+
+    /// JSON body of the response
+    pub fn body(&self) -> String {
+        json!({
+            "status": if self.accepting { "ok" } else { "unavailable" },
+            "accepting": self.accepting,
+            "listserver": if self.listserver { "connected" } else { "disconnected" },
+            "players": self.players,
+            "uptime": self.uptime.as_secs(),
+        }).to_string()
+    }
+
+    /// Full HTTP response, 200 while accepting and 503 otherwise
+    pub fn http_response(&self) -> String {
+        let status = if self.accepting { "200 OK" } else { "503 Service Unavailable" };
+        json_response(status, &self.body())
+    }
+}
+
+/// Build an HTTP response with a JSON body
+fn json_response(status: &str, body: &str) -> String {
+    format!("HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, body.len(), body)
+}
+
+/// HTTP health check endpoint
+pub struct HealthService {
+    listener: TcpListener,
+    context: Arc<ServerContext>,
+}
+
+impl HealthService {
+    /// Listen on an address
+    ///
+    /// # Arguments
+    /// * `address` - `host:port` from `healthaddress`
+    /// * `context` - Server whose state is reported
+    pub async fn bind(address: &str, context: Arc<ServerContext>) -> Result<Self> {
+        let listener = TcpListener::bind(address).await
+            .map_err(|e| GServerError::Network(format!("Health check can't listen on {}: {}", address, e)))?;
+        Ok(Self { listener, context })
+    }
+
+    /// Get the address the endpoint listens on
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Answer health checks until `shutdown` turns true
+    pub async fn run(self, mut shutdown: watch::Receiver<bool>) {
+        loop {
+            tokio::select! {
+                accepted = self.listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        tokio::spawn(answer(stream, self.context.clone()));
+                    }
+                    Err(e) => tracing::warn!("Health check accept failed: {}", e),
+                },
+                _ = shutdown.changed() => {
+                    if *shutdown.borrow() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Read the request head, then send the stats or the health report
+///
+/// Only the path of the request is looked at, but reading all of it first
+/// keeps the client from seeing a reset when the connection is closed with
+/// unread data.
+async fn answer(mut stream: TcpStream, context: Arc<ServerContext>) {
+    let mut request = Vec::with_capacity(256);
+    let _ = tokio::time::timeout(REQUEST_TIMEOUT, async {
+        let mut buf = [0u8; 256];
+        while request.len() < MAX_REQUEST && !request.windows(4).any(|w| w == b"\r\n\r\n") {
+            match stream.read(&mut buf).await {
+                Ok(0) | Err(_) => break,
+                Ok(n) => request.extend_from_slice(&buf[..n]),
+            }
+        }
+    }).await;
+
+    let response = if request.starts_with(b"GET /stats ") {
+        json_response("200 OK", &context.stats().to_json().to_string())
+    } else if request.starts_with(b"GET /leaderboard ") {
+        json_response("200 OK", &context.player_stats().to_json().to_string())
+    } else {
+        HealthReport::from_context(&context).http_response()
+    };
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Wait for Ctrl-C or, on Unix, SIGTERM
+///
+/// # Returns
+/// The name of the signal
+pub async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => tokio::select! {
+                _ = tokio::signal::ctrl_c() => "Ctrl-C",
+                _ = terminate.recv() => "SIGTERM",
+            },
+            Err(e) => {
+                tracing::warn!("Can't listen for SIGTERM: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                "Ctrl-C"
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "Ctrl-C"
+    }
+}
+
+/// Reload the config files on every SIGHUP (Unix only)
+pub async fn reload_on_hangup(context: Arc<ServerContext>) {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(e) => {
+                tracing::warn!("Can't listen for SIGHUP, reload disabled: {}", e);
+                return;
+            }
+        };
+        while hangup.recv().await.is_some() {
+            tracing::info!("SIGHUP received, reloading configuration");
+            let loaded = GameConfig::load_default().map_err(|e| e.to_string());
+            match loaded {
+                Ok(config) => tracing::info!("✓ {}", reload(&context, config)),
+                Err(e) => tracing::warn!("Reload failed, keeping the current configuration: {}", e),
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = context;
+}
+
+/// Apply reloaded config files
+///
+/// # Returns
+/// A summary for the log
+pub fn reload(context: &ServerContext, config: GameConfig) -> String {
+    let logging = config.logging.clone();
+    context.set_listing(crate::listserver::Listing::from_config(&config));
+    context.scripts().set_memory_limit(config.script_memory_limit * 1024);
+    context.scripts().set_gs2_instruction_limit(config.gs2_instruction_limit);
+    #[cfg(feature = "lua")]
+    context.scripts().set_instruction_limit(config.lua_instruction_limit);
+    *context.config().write() = config;
+    // The staff list may have changed
+    context.commands().send(gserver_game::GameCommand::StaffRightsChanged { account: None, rights: None });
+
+    let levels = match crate::logging::reconfigure(&logging) {
+        Ok((filter, ignored)) => {
+            for message in ignored {
+                tracing::warn!("Ignored logging option: {}", message);
+            }
+            filter
+        }
+        Err(e) => e.to_string(),
+    };
+    let weapons = context.weapons().load_all();
+    let classes = crate::weaponsync::check_class_files(context);
+    format!("Configuration reloaded: {} weapons, {} weapons resent, log levels {}", weapons, classes.len(), levels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pid_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run/gserver.pid");
+
+        let pid_file = PidFile::create(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), format!("{}\n", std::process::id()));
+        drop(pid_file);
+        assert!(!path.exists());
+
+        // A PID file left by a process that is gone is replaced
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "4294967\n").unwrap();
+        let _pid_file = PidFile::create(&path).unwrap();
+        assert_eq!(PidFile::read(&path), Some(std::process::id()));
+    }
+
+    #[tokio::test]
+    async fn test_health_endpoint() {
+        let context = Arc::new(ServerContext::new("servers/test", GameConfig::default()));
+        let service = HealthService::bind("127.0.0.1:0", context.clone()).await.unwrap();
+        let address = service.local_addr().unwrap();
+        let (_shutdown_tx, shutdown_rx) = watch::channel(false);
+        tokio::spawn(service.run(shutdown_rx));
+
+        let request = |path: &'static str| async move {
+            let mut stream = TcpStream::connect(address).await.unwrap();
+            stream.write_all(format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        let response = request("/health").await;
+        assert!(response.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+        assert!(response.contains(r#""status":"unavailable""#));
+
+        context.set_accepting(true);
+        context.set_online_count(3);
+        let response = request("/health").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        let body: serde_json::Value = serde_json::from_str(response.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(body["status"], "ok");
+        assert_eq!(body["accepting"], true);
+        assert_eq!(body["listserver"], "disconnected");
+        assert_eq!(body["players"], 3);
+
+        context.traffic().record_packet(2);
+        let response = request("/stats").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        let body: serde_json::Value = serde_json::from_str(response.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(body["connections"], 0);
+        assert_eq!(body["top_packets"][0]["count"], 1);
+    }
+
+    #[test]
+    fn test_reload_replaces_config() {
+        let context = ServerContext::new("servers/test", GameConfig::default());
+        let config = GameConfig { name: "Reloaded".into(), ..Default::default() };
+
+        let summary = reload(&context, config);
+        assert_eq!(context.config().read().name, "Reloaded");
+        assert!(summary.starts_with("Configuration reloaded: 0 weapons"));
+    }
+}
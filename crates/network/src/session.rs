@@ -0,0 +1,169 @@
+//! # Reconnect Sessions
+//!
+//! Flaky connections shouldn't cost a player their place. When a player's
+//! connection drops without the server closing it (socket error, protocol
+//! timeout, or the client just going away), the session is held for
+//! `reconnectgrace` seconds. A login to the same account in that time
+//! resumes it instead of starting fresh:
+//!
+//! - The player's group, IRC channels and freeze
+//! - Word filter points and mutes, so reconnecting doesn't clear them
+//!
+//! # Tokens
+//! Each login gets a random session token, sent as the `clientr.sessiontoken`
+//! flag. A reconnect resumes if it comes from the same IP address, or if it
+//! sends the token as its login identity (for clients whose address changed).
+//!
+//! The account itself isn't held: it is saved on disconnect and loaded from
+//! storage again on resume, so what RC changed in the meantime is kept.
+//!
+//! Kicks, bans and other disconnects by the server aren't held.
+
+use gserver_game::ControlAction;
+use parking_lot::Mutex;
+use rand::Rng;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+/// Flag that tells the client its session token
+pub const TOKEN_FLAG: &str = "clientr.sessiontoken";
+
+/// Characters in a session token
+const TOKEN_LENGTH: usize = 24;
+
+/// Generate a session token
+pub fn new_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(TOKEN_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+/// State of a dropped player kept for a reconnect
+#[derive(Debug, Clone)]
+pub struct HeldSession {
+    /// Session token of the dropped connection
+    pub token: String,
+
+    /// Address the player was connected from
+    pub ip: IpAddr,
+
+    /// Account name
+    pub account: String,
+
+    /// Player group (see [`gserver_game::groups`])
+    pub group: Option<String>,
+
+    /// IRC channels the player was in
+    pub irc_channels: Vec<String>,
+
+    /// Freeze or fullstop in effect
+    pub frozen: Option<ControlAction>,
+
+    /// Word filter points
+    pub filter_points: u32,
+
+    /// Time left on a word filter mute
+    pub mute_left: Option<Duration>,
+}
+
+impl HeldSession {
+    /// Check if a login may resume this session
+    ///
+    /// # Arguments
+    /// * `ip` - Address of the new connection
+    /// * `identity` - Identity sent with the login
+    pub fn matches(&self, ip: IpAddr, identity: &str) -> bool {
+        self.ip == ip || identity == self.token
+    }
+}
+
+/// Sessions of dropped players, by account
+#[derive(Debug, Default)]
+pub struct SessionStore {
+    /// Held session and when it expires, by lowercase account name
+    held: Mutex<HashMap<String, (HeldSession, Instant)>>,
+}
+
+impl SessionStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hold a session until `grace` has passed
+    ///
+    /// A session already held for the account is replaced.
+    pub fn hold(&self, session: HeldSession, grace: Duration, now: Instant) {
+        let key = session.account.to_lowercase();
+        let mut held = self.held.lock();
+        held.retain(|_, (_, expires)| *expires > now);
+        held.insert(key, (session, now + grace));
+    }
+
+    /// Take the session of an account for a login that may resume it
+    ///
+    /// # Returns
+    /// `None` if nothing is held, it expired, or the login doesn't match it
+    /// (the session is then left for the right client)
+    pub fn take(&self, account: &str, ip: IpAddr, identity: &str, now: Instant) -> Option<HeldSession> {
+        let mut held = self.held.lock();
+        held.retain(|_, (_, expires)| *expires > now);
+
+        let key = account.to_lowercase();
+        if !held.get(&key).is_some_and(|(session, _)| session.matches(ip, identity)) {
+            return None;
+        }
+        held.remove(&key).map(|(session, _)| session)
+    }
+
+    /// Get the number of held sessions (including expired ones not yet dropped)
+    pub fn len(&self) -> usize {
+        self.held.lock().len()
+    }
+
+    /// Check if no sessions are held
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(name: &str, ip: [u8; 4]) -> HeldSession {
+        HeldSession {
+            token: new_token(),
+            ip: IpAddr::from(ip),
+            account: name.into(),
+            group: Some("red".into()),
+            irc_channels: Vec::new(),
+            frozen: None,
+            filter_points: 3,
+            mute_left: None,
+        }
+    }
+
+    #[test]
+    fn test_resume_within_grace() {
+        let store = SessionStore::new();
+        let now = Instant::now();
+        let held = session("Bob", [10, 0, 0, 1]);
+        let token = held.token.clone();
+        assert_eq!(token.len(), TOKEN_LENGTH);
+        store.hold(held, Duration::from_secs(30), now);
+
+        // Another address without the token doesn't get it, and it stays held
+        assert!(store.take("bob", IpAddr::from([10, 0, 0, 2]), "", now).is_none());
+        let resumed = store.take("BOB", IpAddr::from([10, 0, 0, 2]), &token, now).unwrap();
+        assert_eq!((resumed.account.as_str(), resumed.group.as_deref()), ("Bob", Some("red")));
+        assert!(store.is_empty());
+
+        store.hold(session("Bob", [10, 0, 0, 1]), Duration::from_secs(30), now);
+        assert!(store.take("Bob", IpAddr::from([10, 0, 0, 1]), "", now + Duration::from_secs(30)).is_none());
+        assert!(store.is_empty());
+    }
+}
@@ -0,0 +1,226 @@
+//! # Shop and Dialog Trigger Actions
+//!
+//! Built-in trigger actions for shops and talking NPCs, so basic ones need
+//! no script. What an NPC sells and says lives in its saved variables
+//! (`FLAG` lines of `npcs/npc<id>.txt`, or `this.*` in its script); the
+//! client only names the NPC and the action:
+//!
+//! ```text
+//! triggeraction x, y, gr.buyitem, arrows     // buy.arrows = 10,30 -> 30 arrows for 10 gralats
+//! triggeraction x, y, gr.sellitem, bombs     // sell.bombs = 2,5   -> 5 bombs for 2 gralats
+//! triggeraction x, y, gr.dialog              // dialog = Welcome!  -> PLO_RPGWINDOW
+//! triggeraction x, y, gr.say, prices         // say.prices = Arrows: 10|Bombs: 5 -> PLO_SAY2
+//! ```
+//!
+//! Items are `arrows`, `bombs`, `hearts` (healing, never over the maximum)
+//! and weapons by name (bought once, and only if the server has them; the
+//! name keeps its case, the variable is lowercase). Only NPCs on the
+//! player's level trade, and nothing the NPC doesn't list can be bought or
+//! sold, whatever the client sends. The `|` of a saying starts a new line.
+
+use gserver_accounts::Account;
+
+/// Most arrows or bombs a player can carry
+pub const MAX_AMMO: u32 = 99;
+
+/// Most gralats a player can carry
+pub const MAX_GRALATS: u32 = 9_999_999;
+
+/// A shop or dialog trigger action
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShopTrigger {
+    /// `gr.buyitem,<item>`
+    Buy(String),
+    /// `gr.sellitem,<item>`
+    Sell(String),
+    /// `gr.dialog[,<name>]`
+    Dialog(String),
+    /// `gr.say[,<name>]`
+    Say(String),
+}
+
+impl ShopTrigger {
+    /// Parse a trigger action string
+    ///
+    /// # Returns
+    /// `None` if the action isn't a shop trigger
+    pub fn parse(action: &str) -> Option<Self> {
+        let (command, arg) = action.split_once(',').unwrap_or((action, ""));
+        let arg = arg.trim().to_string();
+        match command.trim() {
+            "gr.buyitem" if !arg.is_empty() => Some(Self::Buy(arg)),
+            "gr.sellitem" if !arg.is_empty() => Some(Self::Sell(arg)),
+            "gr.dialog" => Some(Self::Dialog(arg)),
+            "gr.say" => Some(Self::Say(arg)),
+            _ => None,
+        }
+    }
+
+    /// Get the NPC variable the action reads (lowercase)
+    pub fn var_name(&self) -> String {
+        let (prefix, name) = match self {
+            Self::Buy(item) => ("buy", item),
+            Self::Sell(item) => ("sell", item),
+            Self::Dialog(name) => ("dialog", name),
+            Self::Say(name) => ("say", name),
+        };
+        match name.is_empty() {
+            true => prefix.to_string(),
+            false => format!("{}.{}", prefix, name.to_lowercase()),
+        }
+    }
+}
+
+/// A price listed by an NPC, `<gralats>[,<amount>]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Offer {
+    pub price: u32,
+    pub amount: u32,
+}
+
+impl Offer {
+    /// Parse an offer (the amount defaults to 1)
+    pub fn parse(value: &str) -> Option<Self> {
+        let (price, amount) = value.split_once(',').unwrap_or((value, "1"));
+        let amount = amount.trim().parse().ok().filter(|&a| a > 0)?;
+        Some(Self { price: price.trim().parse().ok()?, amount })
+    }
+}
+
+/// Why a trade didn't happen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Refusal {
+    TooPoor,
+    Full,
+    NotEnough,
+    AlreadyOwned,
+    Unknown,
+}
+
+impl Refusal {
+    /// Get the message shown to the player (before translation)
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::TooPoor => "You don't have enough gralats.",
+            Self::Full => "You can't carry any more.",
+            Self::NotEnough => "You don't have that many.",
+            Self::AlreadyOwned => "You already have this.",
+            Self::Unknown => "That isn't for sale.",
+        }
+    }
+}
+
+/// What a trade changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Trade {
+    /// Gralats, arrows or bombs changed
+    Counts,
+    /// Hearts changed
+    Hearts,
+    /// A weapon was added
+    Weapon(String),
+}
+
+/// Buy an item for an account
+///
+/// # Arguments
+/// * `weapon_exists` - Whether a non-ammo item is a weapon of the server
+pub fn buy(account: &mut Account, item: &str, offer: Offer, weapon_exists: bool) -> Result<Trade, Refusal> {
+    if account.gralats < offer.price {
+        return Err(Refusal::TooPoor);
+    }
+    let trade = match item.to_lowercase().as_str() {
+        kind @ ("arrows" | "bombs") => {
+            let held = if kind == "arrows" { &mut account.arrows } else { &mut account.bombs };
+            if held.saturating_add(offer.amount) > MAX_AMMO {
+                return Err(Refusal::Full);
+            }
+            *held += offer.amount;
+            Trade::Counts
+        }
+        "hearts" => {
+            if account.hp >= account.max_hp {
+                return Err(Refusal::Full);
+            }
+            account.hp = (account.hp + offer.amount as f32).min(account.max_hp);
+            Trade::Hearts
+        }
+        _ if weapon_exists => {
+            if account.has_weapon(item) {
+                return Err(Refusal::AlreadyOwned);
+            }
+            account.add_weapon(item.to_string());
+            Trade::Weapon(item.to_string())
+        }
+        _ => return Err(Refusal::Unknown),
+    };
+    account.gralats -= offer.price;
+    Ok(trade)
+}
+
+/// Sell arrows or bombs of an account
+pub fn sell(account: &mut Account, item: &str, offer: Offer) -> Result<Trade, Refusal> {
+    let held = match item.to_lowercase().as_str() {
+        "arrows" => &mut account.arrows,
+        "bombs" => &mut account.bombs,
+        _ => return Err(Refusal::Unknown),
+    };
+    if *held < offer.amount {
+        return Err(Refusal::NotEnough);
+    }
+    if account.gralats.saturating_add(offer.price) > MAX_GRALATS {
+        return Err(Refusal::Full);
+    }
+    *held -= offer.amount;
+    account.gralats += offer.price;
+    Ok(Trade::Counts)
+}
+
+/// Encode a PLO_SAY2 body (`|` becomes the sign line break `#b`)
+pub fn say_data(text: &str) -> Vec<u8> {
+    text.replace('|', "#b").replace(['\r', '\n'], "").into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_triggers_and_offers() {
+        assert_eq!(ShopTrigger::parse("gr.buyitem, Arrows"), Some(ShopTrigger::Buy("Arrows".into())));
+        assert_eq!(ShopTrigger::parse("gr.buyitem,-Bow").unwrap().var_name(), "buy.-bow");
+        assert_eq!(ShopTrigger::parse("gr.buyitem"), None);
+        assert_eq!(ShopTrigger::parse("gr.setgroup,red"), None);
+        assert_eq!(ShopTrigger::parse("gr.dialog").unwrap().var_name(), "dialog");
+        assert_eq!(ShopTrigger::parse("gr.say,prices").unwrap().var_name(), "say.prices");
+        assert_eq!(ShopTrigger::parse("gr.sellitem,bombs").unwrap().var_name(), "sell.bombs");
+
+        assert_eq!(Offer::parse("10, 30"), Some(Offer { price: 10, amount: 30 }));
+        assert_eq!(Offer::parse("25"), Some(Offer { price: 25, amount: 1 }));
+        assert_eq!(Offer::parse("free"), None);
+        assert_eq!(Offer::parse("5,0"), None);
+        assert_eq!(say_data("Arrows: 10|Bombs: 5"), b"Arrows: 10#bBombs: 5");
+    }
+
+    #[test]
+    fn test_buy_and_sell() {
+        let mut account = Account { gralats: 30, arrows: 80, bombs: 5, hp: 1.5, max_hp: 3.0, ..Default::default() };
+        let offer = Offer { price: 10, amount: 10 };
+
+        assert_eq!(buy(&mut account, "Arrows", offer, false), Ok(Trade::Counts));
+        assert_eq!((account.gralats, account.arrows), (20, 90));
+        assert_eq!(buy(&mut account, "arrows", Offer { price: 1, amount: 10 }, false), Err(Refusal::Full));
+        assert_eq!(buy(&mut account, "hearts", offer, false), Ok(Trade::Hearts));
+        assert_eq!(account.hp, 3.0);
+        assert_eq!(buy(&mut account, "-bow", offer, false), Err(Refusal::Unknown));
+        assert_eq!(buy(&mut account, "-Bow", offer, true), Ok(Trade::Weapon("-Bow".into())));
+        assert_eq!(buy(&mut account, "-Bow", Offer { price: 0, amount: 1 }, true), Err(Refusal::AlreadyOwned));
+        assert_eq!(buy(&mut account, "bombs", offer, false), Err(Refusal::TooPoor));
+        assert_eq!(account.gralats, 0);
+
+        assert_eq!(sell(&mut account, "bombs", Offer { price: 2, amount: 6 }), Err(Refusal::NotEnough));
+        assert_eq!(sell(&mut account, "bombs", Offer { price: 2, amount: 5 }), Ok(Trade::Counts));
+        assert_eq!((account.gralats, account.bombs), (2, 0));
+        assert_eq!(sell(&mut account, "-bow", offer), Err(Refusal::Unknown));
+    }
+}
@@ -0,0 +1,264 @@
+//! # Simulated Client
+//!
+//! A headless client that logs in like a 2.x client (PLTYPE_CLIENT3, GEN_5),
+//! answers the login warp, and then walks, chats, warps, downloads files and
+//! logs out when told to. `gserver-loadtest` runs many of them against a
+//! server; `gserver --selftest` runs one through every step.
+//!
+//! # Reading
+//!
+//! A reader task decodes every bundle the server sends, so broadcasts from
+//! the other clients never back up the socket. It forwards the type of the
+//! packets latencies are measured against ([`WATCHED`]) to the client.
+//! Packets are told apart by the first byte of each line, which is not exact
+//! once board data follows, but the watched packets of a warp always come
+//! before its board.
+
+use bytes::{BufMut, BytesMut};
+use gserver_core::{GServerError, Result};
+use gserver_game::properties::PlayerProp;
+use crate::compression::Compressor;
+use crate::connection::{codec_for, read_bundle, write_bundle, GraalCodec};
+use gserver_protocol::{write_gshort, write_gstring, write_guint5, PacketTypeIn, PacketTypeOut};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+
+/// Login packet player type (as a shift): PLTYPE_CLIENT3, which selects GEN_5
+const PLTYPE_CLIENT3: u8 = 5;
+
+/// Client version sent in the login packet
+const CLIENT_VERSION: &str = "G3D0311C";
+
+/// Encryption generation of a PLTYPE_CLIENT3 login
+const GENERATION: u8 = 5;
+
+/// Longest wait for a login or warp reply
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Packets the reader forwards to the client
+const WATCHED: [PacketTypeOut; 5] = [
+    PacketTypeOut::PlayerWarp,
+    PacketTypeOut::LevelName,
+    PacketTypeOut::WarpFailed,
+    PacketTypeOut::File,
+    PacketTypeOut::FileSendFailed,
+];
+
+/// Largest position in half-tiles (a 64x64 level)
+const MAX_HALF_TILE: u8 = 126;
+
+/// A logged-in simulated client
+#[derive(Debug)]
+pub struct SimClient {
+    writer: OwnedWriteHalf,
+    /// Send direction only; the reader task has its own codec
+    codec: Box<dyn GraalCodec>,
+    replies: mpsc::UnboundedReceiver<u8>,
+    received: Arc<AtomicU64>,
+    sent: u64,
+    x: u8,
+    y: u8,
+}
+
+impl SimClient {
+    /// Connect, log in and wait for the login warp
+    ///
+    /// # Arguments
+    /// * `target` - Server address (`host:port`)
+    /// * `account` - Account name; the server falls back to its default account
+    /// * `key` - Encryption key sent in the login packet
+    ///
+    /// # Returns
+    /// The client and the time from connecting to PLO_PLAYERWARP
+    pub async fn connect(target: &str, account: &str, key: u8) -> Result<(Self, Duration)> {
+        let started = Instant::now();
+        let stream = TcpStream::connect(target).await?;
+        stream.set_nodelay(true)?;
+        let (reader, mut writer) = stream.into_split();
+
+        // The login bundle is always zlib and never encrypted
+        let login = Compressor::default().zlib(&login_packet(account, key))?;
+        let sent = write_bundle(&mut writer, &login).await? as u64;
+
+        let compressor = Compressor::default();
+        let (tx, replies) = mpsc::unbounded_channel();
+        let received = Arc::new(AtomicU64::new(0));
+        tokio::spawn(read_loop(reader, codec_for(GENERATION, key, &compressor), tx, received.clone()));
+
+        let mut client = Self {
+            writer,
+            codec: codec_for(GENERATION, key, &compressor),
+            replies,
+            received,
+            sent,
+            x: 60,
+            y: 60,
+        };
+        client.wait_for(&[PacketTypeOut::PlayerWarp]).await?;
+        Ok((client, started.elapsed()))
+    }
+
+    /// Move half a tile in a random direction (PLI_PLAYERPROPS X/Y)
+    pub async fn walk(&mut self, direction: u8) -> Result<()> {
+        match direction % 4 {
+            0 => self.y = self.y.saturating_sub(1),
+            1 => self.x = self.x.saturating_sub(1),
+            2 => self.y = (self.y + 1).min(MAX_HALF_TILE),
+            _ => self.x = (self.x + 1).min(MAX_HALF_TILE),
+        }
+
+        let mut packet = packet(PacketTypeIn::PlayerProps);
+        packet.put_u8(gchar(PlayerProp::X as u8));
+        packet.put_u8(gchar(self.x));
+        packet.put_u8(gchar(PlayerProp::Y as u8));
+        packet.put_u8(gchar(self.y));
+        self.send(packet).await
+    }
+
+    /// Send a message to every player (PLI_TOALL)
+    pub async fn chat(&mut self, message: &str) -> Result<()> {
+        let mut packet = packet(PacketTypeIn::ToAll);
+        write_gstring(&mut packet, message);
+        self.send(packet).await
+    }
+
+    /// Warp to a level at the current position (PLI_LEVELWARP)
+    ///
+    /// # Returns
+    /// The time until the server answers with PLO_LEVELNAME or PLO_WARPFAILED
+    pub async fn warp(&mut self, level: &str) -> Result<Duration> {
+        self.warp_to(level).await.map(|(elapsed, _)| elapsed)
+    }
+
+    /// Warp to a level like [`SimClient::warp`], telling whether it worked
+    ///
+    /// # Returns
+    /// The time until the server answered, and `false` for PLO_WARPFAILED
+    pub async fn warp_to(&mut self, level: &str) -> Result<(Duration, bool)> {
+        // Drop replies to warps the server started on its own
+        while self.replies.try_recv().is_ok() {}
+
+        let mut packet = packet(PacketTypeIn::LevelWarp);
+        write_guint5(&mut packet, 0);
+        write_gshort(&mut packet, self.x as i16);
+        write_gshort(&mut packet, self.y as i16);
+        write_gstring(&mut packet, level);
+
+        let started = Instant::now();
+        self.send(packet).await?;
+        let reply = self.wait_for(&[PacketTypeOut::LevelName, PacketTypeOut::WarpFailed]).await?;
+        Ok((started.elapsed(), reply == PacketTypeOut::LevelName))
+    }
+
+    /// Download a file (PLI_WANTFILE)
+    ///
+    /// # Returns
+    /// `true` once PLO_FILE arrives, `false` for PLO_FILESENDFAILED
+    pub async fn download(&mut self, name: &str) -> Result<bool> {
+        let mut packet = packet(PacketTypeIn::WantFile);
+        packet.put_slice(name.as_bytes());
+        self.send(packet).await?;
+        let reply = self.wait_for(&[PacketTypeOut::File, PacketTypeOut::FileSendFailed]).await?;
+        Ok(reply == PacketTypeOut::File)
+    }
+
+    /// Close the connection like a client quitting
+    pub async fn logout(mut self) -> Result<()> {
+        self.writer.shutdown().await?;
+        Ok(())
+    }
+
+    /// Bytes written to the socket, length prefixes included
+    pub fn bytes_sent(&self) -> u64 {
+        self.sent
+    }
+
+    /// Bytes read from the socket, length prefixes included
+    pub fn bytes_received(&self) -> u64 {
+        self.received.load(Ordering::Relaxed)
+    }
+
+    async fn send(&mut self, mut packet: BytesMut) -> Result<()> {
+        packet.put_u8(b'\n');
+        let bundle = self.codec.encode(packet)?;
+        self.sent += write_bundle(&mut self.writer, &bundle).await? as u64;
+        Ok(())
+    }
+
+    /// Wait for one of some packets, returning the one that came
+    async fn wait_for(&mut self, types: &[PacketTypeOut]) -> Result<PacketTypeOut> {
+        let deadline = tokio::time::Instant::now() + REPLY_TIMEOUT;
+        loop {
+            match tokio::time::timeout_at(deadline, self.replies.recv()).await {
+                Ok(Some(t)) => match types.iter().find(|w| w.as_u8() == t) {
+                    Some(&packet_type) => return Ok(packet_type),
+                    None => continue,
+                },
+                Ok(None) => return Err(GServerError::Network("server closed the connection".to_string())),
+                Err(_) => return Err(GServerError::Network(format!("no {:?} within {:?}", types, REPLY_TIMEOUT))),
+            }
+        }
+    }
+}
+
+/// Decode server bundles until the connection closes
+async fn read_loop(mut reader: OwnedReadHalf, mut codec: Box<dyn GraalCodec>, replies: mpsc::UnboundedSender<u8>, received: Arc<AtomicU64>) {
+    while let Ok(Some(bundle)) = read_bundle(&mut reader).await {
+        received.fetch_add(2 + bundle.len() as u64, Ordering::Relaxed);
+        let Ok(data) = codec.decode(&bundle) else { break };
+        for line in data.split(|&b| b == b'\n') {
+            let Some(&first) = line.first() else { continue };
+            let packet_type = first.wrapping_sub(32);
+            if WATCHED.iter().any(|w| w.as_u8() == packet_type) && replies.send(packet_type).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Build the login packet (sent without a packet type)
+///
+/// # Packet Format
+/// ```text
+/// {GCHAR player_type}{GCHAR key}{version: 8 chars}{GSTRING account}{GSTRING password}{identity}
+/// ```
+fn login_packet(account: &str, key: u8) -> Vec<u8> {
+    let mut buf = BytesMut::new();
+    buf.put_u8(gchar(PLTYPE_CLIENT3));
+    buf.put_u8(gchar(key));
+    buf.put_slice(CLIENT_VERSION.as_bytes());
+    write_gstring(&mut buf, account);
+    write_gstring(&mut buf, "loadtest");
+    buf.put_slice(format!("linux,{},loadtest", account).as_bytes());
+    buf.to_vec()
+}
+
+/// Start a client packet: its GChar-encoded type
+fn packet(packet_type: PacketTypeIn) -> BytesMut {
+    let mut buf = BytesMut::new();
+    buf.put_u8(gchar(packet_type.as_u8()));
+    buf
+}
+
+fn gchar(value: u8) -> u8 {
+    value.wrapping_add(32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_login_packet() {
+        let packet = login_packet("bot7", 73);
+        assert_eq!(&packet[..10], b"%iG3D0311C");
+        assert_eq!(&packet[10..15], b"$bot7");
+        assert_eq!(&packet[15..24], b"(loadtest");
+        assert_eq!(&packet[24..], b"linux,bot7,loadtest");
+    }
+}
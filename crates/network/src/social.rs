@@ -0,0 +1,127 @@
+//! # PMs, Friends and Ignore Lists
+//!
+//! Delivery rules and packets for private messages and the per-account
+//! lists of [`gserver_game::social`].
+//!
+//! # Packet Format (PLI_PRIVATEMESSAGE)
+//! ```text
+//! {GUSHORT count}{GUSHORT player id}*count{message}
+//! ```
+//!
+//! # Packet Format (PLO_PRIVATEMESSAGE)
+//! ```text
+//! {GSHORT sender id}"","Private message:",{message}
+//! ```
+//! Sent to more than one player it says `"Mass message:"` instead.
+//!
+//! # C++ Equivalence
+//! Matches `PlayerClient::msgPLI_PRIVATEMESSAGE`; the friend and ignore
+//! lists have no C++ counterpart.
+
+use bytes::{BufMut, BytesMut};
+use gserver_accounts::{Account, AccountLoader};
+use gserver_core::{GServerError, PlayerID, Result};
+use gserver_game::SocialEdit;
+use gserver_protocol::codecs::{read_gushort, write_gshort};
+use std::path::Path;
+
+/// Parse a PLI_PRIVATEMESSAGE body
+///
+/// # Returns
+/// The recipients and the message
+pub fn parse_pm(data: &[u8]) -> Result<(Vec<PlayerID>, String)> {
+    let mut buf = BytesMut::from(data);
+    let count = read_gushort(&mut buf)?;
+    let mut to = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        to.push(PlayerID(read_gushort(&mut buf)?));
+    }
+    Ok((to, String::from_utf8_lossy(&buf).into_owned()))
+}
+
+/// Build a PLO_PRIVATEMESSAGE body
+pub fn pm_data(from: PlayerID, recipients: usize, message: &str) -> Vec<u8> {
+    let mut data = BytesMut::new();
+    write_gshort(&mut data, from.get() as i16);
+    let kind = if recipients > 1 { "Mass message:" } else { "Private message:" };
+    data.put_slice(format!("\"\",\"{}\",{}", kind, message).as_bytes());
+    data.to_vec()
+}
+
+/// Get the message of a PLO_PRIVATEMESSAGE body built by [`pm_data`]
+pub fn pm_message(data: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(data.get(2..)?).into_owned();
+    ["\"\",\"Private message:\",", "\"\",\"Mass message:\","].iter()
+        .find_map(|prefix| text.strip_prefix(prefix).map(String::from))
+}
+
+/// Apply a list change to an account
+///
+/// # Returns
+/// `true` if the account changed
+pub fn apply(account: &mut Account, edit: &SocialEdit) -> bool {
+    match edit {
+        SocialEdit::AddFriend(name) => account.add_friend(name),
+        SocialEdit::RemoveFriend(name) => account.remove_friend(name),
+        SocialEdit::Ignore(name) => account.ignore(name),
+        SocialEdit::Unignore(name) => account.unignore(name),
+        SocialEdit::FriendsOnly(on) => std::mem::replace(&mut account.pm_friends_only, *on) != *on,
+    }
+}
+
+/// Apply a list change to the file of an account that isn't online
+///
+/// # Returns
+/// `true` if the account changed and was saved
+pub fn edit_account_file(server_dir: &Path, account: &str, edit: &SocialEdit) -> Result<bool> {
+    let loader = AccountLoader::new(server_dir);
+    if !loader.exists(account) {
+        return Err(GServerError::NotFound(format!("account {}", account)));
+    }
+    let mut loaded = loader.load(account)
+        .map_err(|e| GServerError::InvalidData(format!("Failed to load account {}: {}", account, e)))?;
+    if !apply(&mut loaded, edit) {
+        return Ok(false);
+    }
+    loader.save(&loaded)
+        .map_err(|e| GServerError::InvalidData(format!("Failed to save account {}: {}", account, e)))?;
+    Ok(true)
+}
+
+/// Summarize an account's lists for RC
+pub fn describe(account: &Account) -> String {
+    let list = |names: &[String]| if names.is_empty() { "none".to_string() } else { names.join(", ") };
+    format!("{}: friends {}; ignoring {}; PMs from {}", account.name, list(&account.friends), list(&account.ignored),
+        if account.pm_friends_only { "friends only" } else { "everyone" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pm_packets() {
+        let mut data = BytesMut::new();
+        for value in [2, 3, 7] {
+            write_gshort(&mut data, value);
+        }
+        data.put_slice(b"hi there");
+        assert_eq!(parse_pm(&data).unwrap(), (vec![PlayerID(3), PlayerID(7)], "hi there".to_string()));
+
+        assert_eq!(&pm_data(PlayerID(5), 1, "hi")[2..], b"\"\",\"Private message:\",hi");
+        assert!(String::from_utf8_lossy(&pm_data(PlayerID(5), 2, "hi")).contains("Mass message:"));
+        assert_eq!(pm_message(&pm_data(PlayerID(5), 2, "a,b")).as_deref(), Some("a,b"));
+    }
+
+    #[test]
+    fn test_apply_edits() {
+        let mut account = Account { name: "Alice".into(), ..Default::default() };
+        assert!(apply(&mut account, &SocialEdit::AddFriend("Bob".into())));
+        assert!(!apply(&mut account, &SocialEdit::AddFriend("bob".into())));
+        assert!(apply(&mut account, &SocialEdit::Ignore("Eve".into())));
+        assert!(apply(&mut account, &SocialEdit::FriendsOnly(true)));
+        assert!(!apply(&mut account, &SocialEdit::FriendsOnly(true)));
+        assert!(account.accepts_pm_from("BOB") && !account.accepts_pm_from("Carol") && !account.accepts_pm_from("eve"));
+        assert_eq!(describe(&account), "Alice: friends Bob; ignoring Eve; PMs from friends only");
+    }
+}
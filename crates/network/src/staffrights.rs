@@ -0,0 +1,133 @@
+//! # Staff Rights
+//!
+//! An account is staff when it has rights or is in the `staff` list, and may
+//! use RC when it has rights and, if a `staff` list is set, is in it. Online
+//! players are re-evaluated as soon as either changes, without relogging:
+//!
+//! - A config reload (SIGHUP) re-evaluates everyone
+//! - RC `/rights <account> <rights>` re-evaluates that account's connections,
+//!   or edits its file if it isn't online. An RC can only give or take the
+//!   rights it has itself ([`masked_rights`]), and not edit its own
+//!
+//! RCs that lost their rights are disconnected, RCs that gained NPC control
+//! get the NPC-Server panel (PLO_HASNPCSERVER), players that became (or
+//! stopped being) staff are told, and every RC sees the change in staff chat
+//! ([`GameEvent::StaffNotice`]).
+//!
+//! # C++ Equivalence
+//! Like `Player::isStaff()`, which checks the `staff` option.
+//!
+//! [`GameEvent::StaffNotice`]: gserver_game::GameEvent::StaffNotice
+
+use gserver_accounts::{Account, AccountLoader};
+use gserver_config::ServerConfig as GameConfig;
+use gserver_core::{GServerError, Result};
+use std::path::Path;
+
+/// Message sent to an RC before it's disconnected for losing its rights
+pub const REVOKED_MESSAGE: &str = "Your RC rights were revoked.";
+
+/// What an account may do, as last evaluated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StaffStatus {
+    /// Staff (rights or the staff list)
+    pub staff: bool,
+    /// May log in as RC
+    pub rc: bool,
+}
+
+impl StaffStatus {
+    /// Evaluate an account against the staff list
+    pub fn of(account: &Account, config: &GameConfig) -> Self {
+        let listed = config.staff_accounts.iter().any(|s| s.eq_ignore_ascii_case(&account.name));
+        Self {
+            staff: account.is_staff() || listed,
+            rc: account.can_use_rc() && (config.staff_accounts.is_empty() || listed),
+        }
+    }
+
+    /// Describe a change for staff chat
+    ///
+    /// # Returns
+    /// `None` if nothing changed
+    pub fn change_notice(&self, account: &str, new: &StaffStatus) -> Option<String> {
+        match (self.rc, new.rc, self.staff, new.staff) {
+            (true, false, ..) => Some(format!("{} lost RC rights", account)),
+            (false, true, ..) => Some(format!("{} got RC rights", account)),
+            (.., false, true) => Some(format!("{} is now staff", account)),
+            (.., true, false) => Some(format!("{} is no longer staff", account)),
+            _ => None,
+        }
+    }
+}
+
+/// Parse rights given in RC, hexadecimal with `0x` or decimal
+pub fn parse_rights(value: &str) -> Option<u32> {
+    match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Work out the rights an RC sets on an account
+///
+/// Bits the issuer doesn't have keep their current value.
+///
+/// # Arguments
+/// * `current` - The account's rights now
+/// * `requested` - The rights the issuer asked for
+/// * `issuer` - The issuer's own rights
+pub fn masked_rights(current: u32, requested: u32, issuer: u32) -> u32 {
+    (current & !issuer) | (requested & issuer)
+}
+
+/// Set the rights in the file of an account that isn't online
+pub fn edit_account_file(server_dir: &Path, account: &str, rights: u32) -> Result<()> {
+    let loader = AccountLoader::new(server_dir);
+    if !loader.exists(account) {
+        return Err(GServerError::NotFound(format!("account {}", account)));
+    }
+    let mut loaded = loader.load(account)
+        .map_err(|e| GServerError::InvalidData(format!("Failed to load account {}: {}", account, e)))?;
+    loaded.local_rights = rights;
+    loader.save(&loaded)
+        .map_err(|e| GServerError::InvalidData(format!("Failed to save account {}: {}", account, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_follows_staff_list() {
+        let account = Account { name: "Manager".into(), local_rights: 0x400, ..Default::default() };
+        let mut config = GameConfig::default();
+        assert_eq!(StaffStatus::of(&account, &config), StaffStatus { staff: true, rc: true });
+
+        config.staff_accounts = vec!["Other".into()];
+        let unlisted = StaffStatus::of(&account, &config);
+        assert_eq!(unlisted, StaffStatus { staff: true, rc: false });
+        assert_eq!(StaffStatus { staff: true, rc: true }.change_notice("Manager", &unlisted).as_deref(),
+            Some("Manager lost RC rights"));
+
+        let player = Account { name: "other".into(), ..Default::default() };
+        let promoted = StaffStatus::of(&player, &config);
+        assert_eq!(promoted, StaffStatus { staff: true, rc: false });
+        assert_eq!(StaffStatus::default().change_notice("other", &promoted).as_deref(), Some("other is now staff"));
+        assert_eq!(promoted.change_notice("other", &promoted), None);
+    }
+
+    #[test]
+    fn test_masked_rights() {
+        // An issuer with only SETRIGHTS and BAN can't grant NPC control
+        assert_eq!(masked_rights(0x0001, 0xFFFFFF, 0x0C00), 0x0C01);
+        assert_eq!(masked_rights(0x80801, 0, 0x0C00), 0x80001);
+    }
+
+    #[test]
+    fn test_parse_rights() {
+        assert_eq!(parse_rights("0xFFFFFF"), Some(0xFFFFFF));
+        assert_eq!(parse_rights("1024"), Some(0x400));
+        assert_eq!(parse_rights("all"), None);
+    }
+}
@@ -0,0 +1,102 @@
+//! # Task Names
+//!
+//! Long-running tasks (connections, the tick loop, the listserver client,
+//! file transfers) are spawned through [`spawn_named`] so they can be told
+//! apart in [tokio-console](https://github.com/tokio-rs/console) when
+//! looking for tasks that starve the runtime.
+//!
+//! Without the `console` feature these are plain `tokio::spawn` calls. With
+//! it, [`crate::logging::init`] also installs the console layer, which
+//! listens on `127.0.0.1:6669` (`TOKIO_CONSOLE_BIND` to change it). Tokio
+//! only records tasks and their names when built with `tokio_unstable`:
+//!
+//! ```text
+//! RUSTFLAGS="--cfg tokio_unstable" cargo build --release --features console
+//! tokio-console http://127.0.0.1:6669
+//! ```
+
+use std::any::Any;
+use std::future::Future;
+use tokio::task::JoinHandle;
+
+/// Spawn a task with a name for tokio-console
+///
+/// # Arguments
+/// * `name` - Task name, e.g. `connection 12`
+/// * `future` - The task
+pub fn spawn_named<F>(name: &str, future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    #[cfg(all(feature = "console", tokio_unstable))]
+    {
+        tokio::task::Builder::new()
+            .name(name)
+            .spawn(future)
+            .unwrap_or_else(|e| panic!("Failed to spawn task {}: {}", name, e))
+    }
+    #[cfg(not(all(feature = "console", tokio_unstable)))]
+    {
+        let _ = name;
+        tokio::spawn(future)
+    }
+}
+
+/// Run blocking work on the blocking pool with a name for tokio-console
+///
+/// # Arguments
+/// * `name` - Task name, e.g. `file bomy.gani`
+/// * `work` - The blocking work
+pub fn spawn_blocking_named<F, R>(name: &str, work: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    #[cfg(all(feature = "console", tokio_unstable))]
+    {
+        tokio::task::Builder::new()
+            .name(name)
+            .spawn_blocking(work)
+            .unwrap_or_else(|e| panic!("Failed to spawn task {}: {}", name, e))
+    }
+    #[cfg(not(all(feature = "console", tokio_unstable)))]
+    {
+        let _ = name;
+        tokio::task::spawn_blocking(work)
+    }
+}
+
+/// Get the message of a caught panic
+///
+/// # Arguments
+/// * `payload` - From `JoinError::into_panic` or `catch_unwind`
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_named_tasks_run() {
+        assert_eq!(spawn_named("test task", async { 7 }).await.unwrap(), 7);
+        assert_eq!(spawn_blocking_named("test blocking", || 8).await.unwrap(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_panic_message() {
+        let error = spawn_named("test panic", async { panic!("handler {} broke", 7) }).await.unwrap_err();
+        assert!(error.is_panic());
+        assert_eq!(panic_message(&*error.into_panic()), "handler 7 broke");
+        assert_eq!(panic_message(&"static"), "static");
+        assert_eq!(panic_message(&7), "unknown panic");
+    }
+}
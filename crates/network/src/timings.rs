@@ -0,0 +1,156 @@
+//! # Handler Timings
+//!
+//! How long the packet handlers take, per packet type, to find the hot ones
+//! under load. Every handled packet lands in a histogram of its type; one
+//! slower than `slowhandlerms` is also logged as a warning right away.
+//!
+//! The busiest handlers (by total time) are listed in
+//! [`ServerStats::handlers`](crate::server::ServerStats::handlers) and
+//! logged every `handlersummaryinterval` seconds.
+//!
+//! # Buckets
+//! ```text
+//! <=100µs <=250µs <=500µs <=1ms <=2.5ms <=5ms <=10ms <=50ms >50ms
+//! ```
+//! Percentiles are the upper bound of the bucket they fall in (the largest
+//! time seen for the last one).
+
+use dashmap::DashMap;
+use gserver_protocol::PacketTypeIn;
+use std::time::Duration;
+
+/// Upper bounds of the histogram buckets, in microseconds; one more bucket
+/// holds the rest
+const BUCKET_BOUNDS: [u64; 8] = [100, 250, 500, 1_000, 2_500, 5_000, 10_000, 50_000];
+
+/// Timings of one packet type
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HandlerTiming {
+    /// Packet type name (`PlayerProps`, or `#id` for unknown types)
+    pub name: String,
+
+    /// Packets handled
+    pub count: u64,
+
+    /// Time spent in all of them
+    pub total: Duration,
+
+    /// Longest single packet
+    pub max: Duration,
+
+    /// Packets slower than the warning threshold
+    pub slow: u64,
+
+    /// Packets per bucket (see the module docs)
+    pub buckets: [u64; BUCKET_BOUNDS.len() + 1],
+}
+
+impl HandlerTiming {
+    /// Get the mean time per packet
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            return Duration::ZERO;
+        }
+        self.total / self.count as u32
+    }
+
+    /// Estimate a percentile (0.0 to 1.0) from the buckets
+    pub fn percentile(&self, p: f64) -> Duration {
+        let rank = ((self.count as f64 * p).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, &count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return BUCKET_BOUNDS.get(i).map_or(self.max, |&us| Duration::from_micros(us).min(self.max));
+            }
+        }
+        self.max
+    }
+
+    /// Describe the timing in one line
+    pub fn describe(&self) -> String {
+        format!("{} x{} mean {:?} p99 {:?} max {:?} ({} slow)",
+            self.name, self.count, self.mean(), self.percentile(0.99), self.max, self.slow)
+    }
+
+    fn record(&mut self, elapsed: Duration, slow: bool) {
+        let micros = elapsed.as_micros() as u64;
+        let bucket = BUCKET_BOUNDS.iter().position(|&bound| micros <= bound).unwrap_or(BUCKET_BOUNDS.len());
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+        if slow {
+            self.slow += 1;
+        }
+    }
+}
+
+/// Handler timing histograms of all packet types
+#[derive(Debug, Default)]
+pub struct HandlerTimings {
+    by_type: DashMap<u8, HandlerTiming>,
+}
+
+impl HandlerTimings {
+    /// Create empty histograms
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record how long a packet took to handle
+    ///
+    /// # Arguments
+    /// * `packet_type` - Raw packet type ID
+    /// * `elapsed` - Handler time
+    /// * `slow_after` - Warning threshold, `None` for no warnings
+    ///
+    /// # Returns
+    /// `true` if the packet was slower than the threshold
+    pub fn record(&self, packet_type: u8, elapsed: Duration, slow_after: Option<Duration>) -> bool {
+        let slow = slow_after.is_some_and(|limit| elapsed > limit);
+        self.by_type.entry(packet_type)
+            .or_insert_with(|| HandlerTiming { name: packet_name(packet_type), ..Default::default() })
+            .record(elapsed, slow);
+        slow
+    }
+
+    /// Get the packet types that took the most time in total, busiest first
+    pub fn busiest(&self, count: usize) -> Vec<HandlerTiming> {
+        let mut timings: Vec<HandlerTiming> = self.by_type.iter().map(|e| e.value().clone()).collect();
+        timings.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
+        timings.truncate(count);
+        timings
+    }
+}
+
+/// Name a raw packet type
+pub fn packet_name(packet_type: u8) -> String {
+    PacketTypeIn::from_u8(packet_type).map_or_else(|| format!("#{}", packet_type), |t| format!("{:?}", t))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handler_histograms() {
+        let timings = HandlerTimings::new();
+        let slow_after = Some(Duration::from_millis(5));
+        for micros in [50, 80, 300, 900] {
+            assert!(!timings.record(2, Duration::from_micros(micros), slow_after));
+        }
+        assert!(timings.record(2, Duration::from_millis(12), slow_after));
+        timings.record(250, Duration::from_millis(1), None);
+
+        let busiest = timings.busiest(5);
+        assert_eq!(busiest.len(), 2);
+        let timing = &busiest[0];
+        assert_eq!((timing.count, timing.slow), (5, 1));
+        assert_eq!(timing.buckets, [2, 0, 1, 1, 0, 0, 0, 1, 0]);
+        assert_eq!(timing.max, Duration::from_millis(12));
+        assert_eq!(timing.percentile(0.5), Duration::from_micros(500));
+        assert_eq!(timing.percentile(0.99), Duration::from_millis(12));
+        assert_eq!(busiest[1].name, "#250");
+    }
+}
@@ -0,0 +1,217 @@
+//! # TLS Listener
+//!
+//! Optional encrypted transport on a second port (`tlsport`), for RC
+//! clients and proxies that can speak TLS. The Graal protocol runs unchanged
+//! inside the tunnel: once the handshake is done, the connection goes
+//! through the same admission and [`PlayerConnection`](crate::PlayerConnection)
+//! pipeline as a plain TCP one.
+//!
+//! The certificate chain and private key are PEM files, by default
+//! `tls/cert.pem` and `tls/key.pem` in the server folder (`tlscert`,
+//! `tlskey`). Handshakes run in their own tasks, so slow or broken clients
+//! don't hold up the accept loop; one that doesn't finish within
+//! [`HANDSHAKE_TIMEOUT`] is dropped.
+
+use gserver_config::ServerConfig as GameConfig;
+use gserver_core::{GServerError, Result};
+use parking_lot::RwLock;
+use std::io;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::ServerConfig as RustlsConfig;
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+
+/// Longest a client may take to finish the TLS handshake
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// TLS listener settings
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    /// Address and port of the TLS listener
+    pub bind_address: SocketAddr,
+
+    /// PEM certificate chain, leaf first
+    pub cert_path: PathBuf,
+
+    /// PEM private key (PKCS#8, PKCS#1 or SEC1)
+    pub key_path: PathBuf,
+}
+
+/// Build the handshake acceptor from the certificate and key files
+///
+/// # Errors
+/// `GServerError::Config` if a file can't be read or the key doesn't fit
+/// the certificate
+pub fn load_acceptor(config: &TlsConfig) -> Result<TlsAcceptor> {
+    let file_error = |path: &Path, e: &dyn std::fmt::Display| {
+        GServerError::Config(format!("Failed to read {}: {}", path.display(), e))
+    };
+
+    let certs = CertificateDer::pem_file_iter(&config.cert_path)
+        .and_then(|certs| certs.collect::<std::result::Result<Vec<_>, _>>())
+        .map_err(|e| file_error(&config.cert_path, &e))?;
+    if certs.is_empty() {
+        return Err(GServerError::Config(format!("No certificate in {}", config.cert_path.display())));
+    }
+    let key = PrivateKeyDer::from_pem_file(&config.key_path).map_err(|e| file_error(&config.key_path, &e))?;
+
+    let server_config = RustlsConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| GServerError::Config(format!("Invalid TLS certificate or key: {}", e)))?;
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+/// Accept TLS connections and hand each one over once its handshake is done
+///
+/// # Arguments
+/// * `listener` - Bound TLS port
+/// * `acceptor` - From [`load_acceptor`]
+/// * `config` - Game config, for [`crate::proxy`] headers before the handshake
+/// * `ready` - Where finished connections go to be admitted
+pub async fn accept_loop(
+    listener: Arc<TcpListener>,
+    acceptor: TlsAcceptor,
+    config: Arc<RwLock<GameConfig>>,
+    ready: mpsc::Sender<(ClientStream, SocketAddr)>,
+) {
+    loop {
+        let (mut socket, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                tracing::error!("Error accepting TLS connection: {:?}", e);
+                continue;
+            }
+        };
+
+        let acceptor = acceptor.clone();
+        let config = config.clone();
+        let ready = ready.clone();
+        tokio::spawn(async move {
+            let Some(addr) = crate::proxy::resolve(&mut socket, peer, &config).await else {
+                return;
+            };
+            match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(socket)).await {
+                Ok(Ok(stream)) => {
+                    tracing::debug!("TLS handshake with {} done", addr);
+                    let _ = ready.send((ClientStream::Tls(Box::new(stream)), addr)).await;
+                }
+                Ok(Err(e)) => tracing::debug!("TLS handshake with {} failed: {}", addr, e),
+                Err(_) => tracing::debug!("TLS handshake with {} timed out", addr),
+            }
+        });
+    }
+}
+
+/// A client socket, plain or inside TLS
+pub enum ClientStream {
+    /// Plain TCP
+    Plain(TcpStream),
+    /// TLS over TCP
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+impl ClientStream {
+    /// Check if the connection is encrypted
+    pub fn is_tls(&self) -> bool {
+        matches!(self, Self::Tls(_))
+    }
+
+    /// Set TCP_NODELAY on the underlying socket
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        match self {
+            Self::Plain(stream) => stream.set_nodelay(nodelay),
+            Self::Tls(stream) => stream.get_ref().0.set_nodelay(nodelay),
+        }
+    }
+}
+
+impl From<TcpStream> for ClientStream {
+    fn from(stream: TcpStream) -> Self {
+        Self::Plain(stream)
+    }
+}
+
+impl AsyncRead for ClientStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+            Self::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for ClientStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+            Self::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Plain(stream) => Pin::new(stream).poll_flush(cx),
+            Self::Tls(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Plain(stream) => Pin::new(stream).poll_shutdown(cx),
+            Self::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::{read_bundle, write_bundle};
+    use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+    use tokio_rustls::TlsConnector;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/tls").join(name)
+    }
+
+    #[tokio::test]
+    async fn test_bundles_through_tls() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = TlsConfig {
+            bind_address: listener.local_addr().unwrap(),
+            cert_path: fixture("cert.pem"),
+            key_path: fixture("key.pem"),
+        };
+        let acceptor = load_acceptor(&config).unwrap();
+        let (ready, mut admitted) = mpsc::channel(1);
+        let game_config = Arc::new(RwLock::new(GameConfig::default()));
+        let server = tokio::spawn(accept_loop(Arc::new(listener), acceptor, game_config, ready));
+
+        let mut roots = RootCertStore::empty();
+        roots.add(CertificateDer::from_pem_file(&config.cert_path).unwrap()).unwrap();
+        let connector = TlsConnector::from(Arc::new(
+            ClientConfig::builder().with_root_certificates(roots).with_no_client_auth()));
+        let tcp = TcpStream::connect(config.bind_address).await.unwrap();
+        let mut client = connector.connect("localhost".try_into().unwrap(), tcp).await.unwrap();
+
+        let (mut stream, _) = admitted.recv().await.unwrap();
+        assert!(stream.is_tls());
+        write_bundle(&mut client, b"hello").await.unwrap();
+        assert_eq!(read_bundle(&mut stream).await.unwrap().as_deref(), Some(&b"hello"[..]));
+        server.abort();
+
+        let missing = TlsConfig { key_path: fixture("missing.pem"), ..config };
+        assert!(load_acceptor(&missing).is_err());
+    }
+}
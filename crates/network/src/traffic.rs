@@ -0,0 +1,94 @@
+//! # Traffic Counters
+//!
+//! Server-wide counters since startup, kept by the connections as they read
+//! and write bundles, for [`ServerStats`](crate::server::ServerStats):
+//!
+//! - Received packets by type
+//! - Bundle bytes before and after compression/encryption
+//! - Packets per bundle, for what batching saves
+//!
+//! Each bundle costs a 2-byte length prefix, so sending `n` packets in one
+//! bundle instead of `n` bundles saves `2 * (n - 1)` bytes before counting
+//! the better compression of larger bundles.
+
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Length prefix of each bundle
+const BUNDLE_HEADER: u64 = 2;
+
+/// Packet and bundle counters of all connections
+#[derive(Debug, Default)]
+pub struct TrafficStats {
+    packets_in: DashMap<u8, u64>,
+    bundles_out: AtomicU64,
+    packets_out: AtomicU64,
+    raw_bytes_out: AtomicU64,
+    encoded_bytes_out: AtomicU64,
+}
+
+impl TrafficStats {
+    /// Create empty counters
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a received packet
+    pub fn record_packet(&self, packet_type: u8) {
+        *self.packets_in.entry(packet_type).or_insert(0) += 1;
+    }
+
+    /// Count a sent bundle
+    ///
+    /// # Arguments
+    /// * `raw` - Bytes of the packets in it
+    /// * `encoded` - Bytes after compression and encryption
+    /// * `packets` - Number of packets in it
+    pub fn record_bundle(&self, raw: usize, encoded: usize, packets: usize) {
+        self.bundles_out.fetch_add(1, Ordering::Relaxed);
+        self.packets_out.fetch_add(packets as u64, Ordering::Relaxed);
+        self.raw_bytes_out.fetch_add(raw as u64, Ordering::Relaxed);
+        self.encoded_bytes_out.fetch_add(encoded as u64, Ordering::Relaxed);
+    }
+
+    /// Get the most received packet types, most frequent first
+    pub fn top_packets(&self, count: usize) -> Vec<(u8, u64)> {
+        let mut packets: Vec<(u8, u64)> = self.packets_in.iter().map(|e| (*e.key(), *e.value())).collect();
+        packets.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        packets.truncate(count);
+        packets
+    }
+
+    /// Get encoded bytes per raw byte of the sent bundles, `None` before the first
+    pub fn compression_ratio(&self) -> Option<f64> {
+        let raw = self.raw_bytes_out.load(Ordering::Relaxed);
+        (raw > 0).then(|| self.encoded_bytes_out.load(Ordering::Relaxed) as f64 / raw as f64)
+    }
+
+    /// Get the length prefix bytes saved by sending packets in shared bundles
+    pub fn batching_saved_bytes(&self) -> u64 {
+        let packets = self.packets_out.load(Ordering::Relaxed);
+        packets.saturating_sub(self.bundles_out.load(Ordering::Relaxed)) * BUNDLE_HEADER
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traffic_counters() {
+        let traffic = TrafficStats::new();
+        assert_eq!(traffic.compression_ratio(), None);
+
+        for packet_type in [2, 6, 2, 2, 6, 9] {
+            traffic.record_packet(packet_type);
+        }
+        assert_eq!(traffic.top_packets(2), [(2, 3), (6, 2)]);
+
+        traffic.record_bundle(1000, 250, 10);
+        traffic.record_bundle(200, 150, 1);
+        assert_eq!(traffic.compression_ratio(), Some(400.0 / 1200.0));
+        assert_eq!(traffic.batching_saved_bytes(), 18);
+    }
+}
@@ -0,0 +1,155 @@
+//! # Login Verification
+//!
+//! With `verifylogins` on, every player login is checked by the listserver
+//! (`Listserver,TClientLogin`, see [`crate::requesttext`]). The player plays
+//! while the answer is on its way and is disconnected if it's a rejection.
+//!
+//! Accepted accounts are remembered in `logincache.txt`, so a listserver
+//! outage doesn't empty the server: while the listserver is unreachable,
+//! accounts verified within `verifycachehours` can still log in (degraded
+//! mode), and others are refused. The verification requests of degraded
+//! logins wait in [`ListServerStatus`](crate::ListServerStatus) and go out
+//! once the listserver is back.
+//!
+//! A login on an account that is already in use waits for the answer (up to
+//! [`VERIFY_WAIT`]) before the player who is on is kicked (see
+//! [`crate::duplicate`]), so a forged login can't take over the account; a
+//! rejected or unanswered one is refused and the player stays.
+//!
+//! # File Format
+//! ```text
+//! {lowercase account} {unix seconds of the last verification}
+//! ```
+
+use dashmap::DashMap;
+use gserver_core::PlayerID;
+use gserver_game::GameEvent;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Verification cache file in the server folder
+pub const CACHE_FILE: &str = "logincache.txt";
+
+/// Message for a player whose login the listserver rejected
+pub const REJECTED_MESSAGE: &str = "Your login could not be verified.";
+
+/// Longest a login replacing another player waits for the listserver
+pub const VERIFY_WAIT: Duration = Duration::from_secs(10);
+
+/// Wait for the listserver's answer to a player's login
+///
+/// # Returns
+/// Whether the listserver accepted it, `None` without an answer in `wait`
+pub async fn wait_for(events: &mut broadcast::Receiver<GameEvent>, player: PlayerID, wait: Duration) -> Option<bool> {
+    let answer = async {
+        loop {
+            match events.recv().await {
+                Ok(GameEvent::LoginVerified { player: verified_player, verified, .. }) if verified_player == player => {
+                    return Some(verified);
+                }
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    };
+    tokio::time::timeout(wait, answer).await.ok().flatten()
+}
+
+/// Last time the listserver verified each account
+#[derive(Debug, Default)]
+pub struct VerificationCache {
+    /// Where the cache is saved, `None` to keep it in memory
+    path: Option<PathBuf>,
+    verified: DashMap<String, u64>,
+}
+
+impl VerificationCache {
+    /// Create an empty cache that isn't saved
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the cache file, starting empty if there is none
+    pub fn load(path: &Path) -> Self {
+        let verified = DashMap::new();
+        if let Ok(content) = std::fs::read_to_string(path) {
+            for line in content.lines() {
+                if let Some((account, at)) = line.trim().split_once(' ') {
+                    if let Ok(at) = at.trim().parse() {
+                        verified.insert(account.to_lowercase(), at);
+                    }
+                }
+            }
+        }
+        Self { path: Some(path.to_path_buf()), verified }
+    }
+
+    /// Remember that the listserver accepted an account, and save the cache
+    pub fn record(&self, account: &str, now: u64) {
+        self.verified.insert(account.to_lowercase(), now);
+        self.save();
+    }
+
+    /// Forget an account the listserver rejected
+    pub fn forget(&self, account: &str) {
+        if self.verified.remove(&account.to_lowercase()).is_some() {
+            self.save();
+        }
+    }
+
+    /// Check if an account was verified within `max_age` seconds
+    pub fn is_recent(&self, account: &str, now: u64, max_age: u64) -> bool {
+        self.verified.get(&account.to_lowercase())
+            .is_some_and(|at| now.saturating_sub(*at) <= max_age)
+    }
+
+    /// Write the cache file
+    ///
+    /// Failures are logged; the cache keeps working in memory.
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let mut entries: Vec<(String, u64)> = self.verified.iter().map(|e| (e.key().clone(), *e.value())).collect();
+        entries.sort();
+        let content: String = entries.iter().map(|(account, at)| format!("{} {}\n", account, at)).collect();
+        if let Err(e) = std::fs::write(path, content) {
+            tracing::warn!("Failed to save {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_survives_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CACHE_FILE);
+
+        let cache = VerificationCache::load(&path);
+        cache.record("Bob", 1_000);
+        cache.record("alice", 5_000);
+        cache.forget("ALICE");
+        assert!(cache.is_recent("bob", 4_600, 3_600));
+        assert!(!cache.is_recent("bob", 4_601, 3_600));
+        assert!(!cache.is_recent("alice", 5_000, 3_600));
+
+        let reloaded = VerificationCache::load(&path);
+        assert!(reloaded.is_recent("BOB", 1_000, 0));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "bob 1000\n");
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_answer() {
+        let events = gserver_game::EventBus::new();
+        let mut received = events.subscribe();
+        let answer = |player: u16, verified| GameEvent::LoginVerified { player: PlayerID::new(player), account: "Bob".into(), verified };
+        events.publish(answer(3, true));
+        events.publish(answer(2, false));
+        assert_eq!(wait_for(&mut received, PlayerID::new(2), VERIFY_WAIT).await, Some(false));
+        assert_eq!(wait_for(&mut received, PlayerID::new(2), Duration::from_millis(10)).await, None);
+    }
+}
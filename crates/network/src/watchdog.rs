@@ -0,0 +1,256 @@
+//! # Watchdog
+//!
+//! Long-running loops beat a [`Heartbeat`] while they make progress:
+//!
+//! - `tick loop` - From a tick loop timer, so every tick that runs timers beats
+//! - `listserver client` - From its event loop and while it waits to reconnect
+//! - `accept loop` - Every [`HEARTBEAT_INTERVAL`] while it waits for connections
+//!
+//! The [`run`] task checks them every second. A subsystem that hasn't beaten
+//! for `watchdogtimeout` seconds is reported once per stall, in the log and
+//! in staff chat, with diagnostics (tick stats, players, runtime tasks; built
+//! with the `console` feature, tokio-console has the per-task view).
+//!
+//! With `watchdogaction = restart` a stall also shuts the server down the
+//! normal way, saving everything, and the process exits with
+//! [`RESTART_EXIT_CODE`] for its supervisor (systemd, docker) to start it
+//! again. If the shutdown itself doesn't finish within another timeout, the
+//! process exits right away.
+//!
+//! Heartbeats are only watched after their first beat, so subsystems that
+//! aren't running (no listserver in `--selftest`) are never reported.
+
+use crate::context::ServerContext;
+use parking_lot::Mutex;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, Notify};
+
+/// Time between beats of loops that beat on a timer
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Time between watchdog checks
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Exit status after a watchdog restart (EX_TEMPFAIL)
+pub const RESTART_EXIT_CODE: i32 = 75;
+
+/// What the watchdog does about a stall
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WatchdogAction {
+    /// Report it
+    #[default]
+    Log,
+    /// Report it and restart the server
+    Restart,
+}
+
+impl WatchdogAction {
+    /// Parse a `watchdogaction` option value (`log`, `restart`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "log" => Some(WatchdogAction::Log),
+            "restart" => Some(WatchdogAction::Restart),
+            _ => None,
+        }
+    }
+}
+
+/// Last sign of progress of one subsystem
+#[derive(Debug)]
+pub struct Heartbeat {
+    name: &'static str,
+    /// `None` until the first beat, and again once stopped
+    last: Mutex<Option<Instant>>,
+}
+
+impl Heartbeat {
+    /// Create a heartbeat that isn't watched until it beats
+    pub fn new(name: &'static str) -> Self {
+        Self { name, last: Mutex::new(None) }
+    }
+
+    /// Get the subsystem name
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Record progress
+    #[inline]
+    pub fn beat(&self) {
+        *self.last.lock() = Some(Instant::now());
+    }
+
+    /// Stop watching, e.g. once the subsystem ended on purpose
+    pub fn stop(&self) {
+        *self.last.lock() = None;
+    }
+
+    /// Get the time since the last beat, `None` if not watched
+    pub fn since_last(&self, now: Instant) -> Option<Duration> {
+        self.last.lock().map(|last| now.saturating_duration_since(last))
+    }
+}
+
+/// Sleep while beating a heartbeat, for loops that wait on purpose
+pub async fn sleep(heartbeat: &Heartbeat, duration: Duration) {
+    let end = tokio::time::Instant::now() + duration;
+    loop {
+        heartbeat.beat();
+        let now = tokio::time::Instant::now();
+        if now >= end {
+            return;
+        }
+        tokio::time::sleep((end - now).min(HEARTBEAT_INTERVAL)).await;
+    }
+}
+
+/// The watched heartbeats and the restart request
+#[derive(Debug, Default)]
+pub struct Watchdog {
+    heartbeats: Mutex<Vec<Arc<Heartbeat>>>,
+    restart: Notify,
+    restart_reason: Mutex<Option<String>>,
+}
+
+impl Watchdog {
+    /// Create a watchdog without heartbeats
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create and watch a heartbeat
+    pub fn register(&self, name: &'static str) -> Arc<Heartbeat> {
+        let heartbeat = Arc::new(Heartbeat::new(name));
+        self.watch(heartbeat.clone());
+        heartbeat
+    }
+
+    /// Watch a heartbeat created elsewhere
+    pub fn watch(&self, heartbeat: Arc<Heartbeat>) {
+        self.heartbeats.lock().push(heartbeat);
+    }
+
+    /// Get a watched heartbeat by name
+    pub fn heartbeat(&self, name: &str) -> Option<Arc<Heartbeat>> {
+        self.heartbeats.lock().iter().find(|h| h.name == name).cloned()
+    }
+
+    /// Get the subsystems that haven't beaten for `timeout`, and for how long
+    pub fn stalled(&self, now: Instant, timeout: Duration) -> Vec<(&'static str, Duration)> {
+        self.heartbeats.lock().iter()
+            .filter_map(|h| h.since_last(now).filter(|&since| since >= timeout).map(|since| (h.name, since)))
+            .collect()
+    }
+
+    /// Ask the accept loop to shut the server down for a restart
+    pub fn request_restart(&self, reason: String) {
+        self.restart_reason.lock().get_or_insert(reason);
+        self.restart.notify_one();
+    }
+
+    /// Wait until a restart is requested
+    pub async fn restart_requested(&self) {
+        self.restart.notified().await
+    }
+
+    /// Get why a restart was requested, if one was
+    pub fn restart_reason(&self) -> Option<String> {
+        self.restart_reason.lock().clone()
+    }
+}
+
+/// Describe the server's state for a stall report
+fn diagnostics(context: &ServerContext) -> String {
+    let ticks = context.tick_stats().lock().clone();
+    let metrics = tokio::runtime::Handle::current().metrics();
+    let mut report = format!("{} ticks (last {:?}, max {:?}), {} players, {} workers, {} tasks alive",
+        ticks.ticks, ticks.last_duration, ticks.max_duration, context.online_count(),
+        metrics.num_workers(), metrics.num_alive_tasks());
+    if cfg!(feature = "console") {
+        report.push_str("; see tokio-console for the tasks");
+    }
+    report
+}
+
+/// Watch the heartbeats until shutdown
+pub async fn run(context: Arc<ServerContext>, mut shutdown: watch::Receiver<bool>) {
+    let mut check = tokio::time::interval(CHECK_INTERVAL);
+    let mut reported: BTreeSet<&'static str> = BTreeSet::new();
+    let mut restart_deadline: Option<Instant> = None;
+    loop {
+        tokio::select! {
+            _ = check.tick() => {}
+            _ = shutdown.changed() => return,
+        }
+        let (timeout, action) = {
+            let config = context.config().read();
+            let action = WatchdogAction::from_name(&config.watchdog_action).unwrap_or_else(|| {
+                tracing::warn!("Unknown watchdogaction '{}', using log", config.watchdog_action);
+                WatchdogAction::Log
+            });
+            (Duration::from_secs(config.watchdog_timeout), action)
+        };
+        if timeout.is_zero() {
+            continue;
+        }
+
+        let now = Instant::now();
+        if restart_deadline.is_some_and(|deadline| now >= deadline) {
+            tracing::error!("Watchdog: shutdown didn't finish within {:?}, exiting", timeout);
+            std::process::exit(RESTART_EXIT_CODE);
+        }
+
+        let stalled = context.watchdog().stalled(now, timeout);
+        reported.retain(|name| stalled.iter().any(|(stalled, _)| stalled == name));
+        for (name, since) in stalled {
+            if !reported.insert(name) {
+                continue;
+            }
+            let message = format!("Watchdog: {} stalled for {}s", name, since.as_secs());
+            tracing::error!("{} ({})", message, diagnostics(&context));
+            context.events().publish(gserver_game::GameEvent::StaffNotice { message: message.clone() });
+            if action == WatchdogAction::Restart && restart_deadline.is_none() {
+                tracing::error!("Watchdog: restarting the server");
+                context.watchdog().request_restart(message);
+                restart_deadline = Some(now + timeout);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stalled_heartbeats() {
+        let watchdog = Watchdog::new();
+        let tick = watchdog.register("tick loop");
+        let accept = watchdog.register("accept loop");
+        let now = Instant::now() + Duration::from_secs(60);
+        // Not watched before the first beat
+        assert!(watchdog.stalled(now, Duration::from_secs(30)).is_empty());
+
+        tick.beat();
+        accept.beat();
+        accept.stop();
+        let stalled = watchdog.stalled(now, Duration::from_secs(30));
+        assert_eq!(stalled.iter().map(|(name, _)| *name).collect::<Vec<_>>(), ["tick loop"]);
+        assert!(watchdog.stalled(Instant::now(), Duration::from_secs(30)).is_empty());
+        assert!(watchdog.heartbeat("tick loop").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_restart_request() {
+        let watchdog = Arc::new(Watchdog::new());
+        let waiter = watchdog.clone();
+        let waiting = tokio::spawn(async move { waiter.restart_requested().await });
+        watchdog.request_restart("tick loop stalled".into());
+        watchdog.request_restart("accept loop stalled".into());
+        waiting.await.unwrap();
+        assert_eq!(watchdog.restart_reason().as_deref(), Some("tick loop stalled"));
+        assert_eq!(WatchdogAction::from_name(" Restart"), Some(WatchdogAction::Restart));
+    }
+}
@@ -0,0 +1,212 @@
+//! # Weapon Delivery
+//!
+//! Weapon scripts are sent to a client when it first gets them, and again
+//! only when their checksum changes:
+//!
+//! - At login the account's weapons the client lacks go out in bundles: the PLO_NPCWEAPONADD
+//!   packets of small scripts are joined into chunks of up to
+//!   [`BUNDLE_BYTES`], which the outbound queue takes as one entry each.
+//!   Bundles are built once per set of weapon builds ([`BundleCache`]), so
+//!   players with the same weapons share them
+//! - A weapon added later (bought from a shop) is sent when it's added
+//! - PLI_UPDATESCRIPT asks for a weapon the account has
+//! - Class changes (see [`crate::weaponsync`]) reach only the clients that
+//!   have an older checksum
+//!
+//! Each connection keeps a [`WeaponDelivery`] of the checksums its client
+//! has; PLI_NPCWEAPONDEL from the client forgets one. At logout it's kept in
+//! the [`DeliveryStore`] under the account, so the next session only gets
+//! what changed: weapons the account lost are deleted instead of clearing
+//! them all. A client that lost a weapon anyway asks with PLI_UPDATESCRIPT.
+
+use crate::weaponsync::npc_weapon_add_data;
+use bytes::BytesMut;
+use gserver_game::WeaponBuild;
+use gserver_protocol::{PacketOut, PacketTypeOut};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Largest bundle of weapon packets; bigger scripts go alone
+pub const BUNDLE_BYTES: usize = 8192;
+
+/// Sets of weapons whose bundles are kept
+const MAX_CACHED_SETS: usize = 64;
+
+/// Accounts whose weapon checksums are kept between sessions
+const MAX_KEPT_ACCOUNTS: usize = 4096;
+
+/// Names and checksums of a set of builds, sorted by name
+type WeaponSet = Vec<(String, u32)>;
+
+/// The weapon checksums a client has
+#[derive(Debug, Clone, Default)]
+pub struct WeaponDelivery {
+    /// Lowercase weapon name to checksum
+    sent: HashMap<String, u32>,
+}
+
+impl WeaponDelivery {
+    /// Check if the client lacks a build
+    pub fn needs(&self, build: &WeaponBuild) -> bool {
+        self.sent.get(&build.name.to_lowercase()) != Some(&build.checksum)
+    }
+
+    /// Remember that the client got a build
+    pub fn sent(&mut self, build: &WeaponBuild) {
+        self.sent.insert(build.name.to_lowercase(), build.checksum);
+    }
+
+    /// Check if the client has some build of a weapon
+    pub fn contains(&self, name: &str) -> bool {
+        self.sent.contains_key(&name.to_lowercase())
+    }
+
+    /// Forget a weapon the client deleted
+    pub fn forget(&mut self, name: &str) {
+        self.sent.remove(&name.to_lowercase());
+    }
+
+    /// Get the number of weapons the client has
+    pub fn len(&self) -> usize {
+        self.sent.len()
+    }
+
+    /// Check if the client has no weapons
+    pub fn is_empty(&self) -> bool {
+        self.sent.is_empty()
+    }
+
+    /// Get the lowercase names of the weapons the client has
+    pub fn names(&self) -> Vec<String> {
+        self.sent.keys().cloned().collect()
+    }
+}
+
+/// The weapon checksums of accounts' last sessions
+#[derive(Debug, Default)]
+pub struct DeliveryStore {
+    /// Lowercase account name to checksums
+    kept: Mutex<HashMap<String, WeaponDelivery>>,
+}
+
+impl DeliveryStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep the checksums of a session that ended
+    pub fn keep(&self, account: &str, delivery: WeaponDelivery) {
+        let mut kept = self.kept.lock();
+        if kept.len() >= MAX_KEPT_ACCOUNTS {
+            kept.clear();
+        }
+        kept.insert(account.to_lowercase(), delivery);
+    }
+
+    /// Take the checksums of an account's last session, empty without one
+    pub fn take(&self, account: &str) -> WeaponDelivery {
+        self.kept.lock().remove(&account.to_lowercase()).unwrap_or_default()
+    }
+}
+
+/// Serialize a build's PLO_NPCWEAPONADD, newline included
+pub fn weapon_packet(build: &WeaponBuild) -> BytesMut {
+    let mut packet = BytesMut::new();
+    PacketOut::new(PacketTypeOut::NpcWeaponAdd, npc_weapon_add_data(build)).serialize(&mut packet);
+    packet
+}
+
+/// Join the weapon packets of builds into bundles of up to `limit` bytes
+pub fn bundle(builds: &[Arc<WeaponBuild>], limit: usize) -> Vec<BytesMut> {
+    let mut bundles = Vec::new();
+    let mut current = BytesMut::new();
+    for build in builds {
+        let packet = weapon_packet(build);
+        if !current.is_empty() && current.len() + packet.len() > limit {
+            bundles.push(std::mem::take(&mut current));
+        }
+        current.extend_from_slice(&packet);
+    }
+    if !current.is_empty() {
+        bundles.push(current);
+    }
+    bundles
+}
+
+/// Bundles of the weapon sets logins asked for
+#[derive(Debug, Default)]
+pub struct BundleCache {
+    bundles: Mutex<HashMap<WeaponSet, Arc<Vec<BytesMut>>>>,
+}
+
+impl BundleCache {
+    /// Create an empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the bundles of a set of builds, building them the first time
+    ///
+    /// A changed checksum is a different set, so edited weapons are never
+    /// served from an old bundle.
+    pub fn get(&self, builds: &[Arc<WeaponBuild>]) -> Arc<Vec<BytesMut>> {
+        let mut builds = builds.to_vec();
+        builds.sort_by(|a, b| a.name.cmp(&b.name));
+        let key: WeaponSet = builds.iter().map(|b| (b.name.clone(), b.checksum)).collect();
+
+        let mut bundles = self.bundles.lock();
+        if let Some(cached) = bundles.get(&key) {
+            return cached.clone();
+        }
+        if bundles.len() >= MAX_CACHED_SETS {
+            bundles.clear();
+        }
+        let built = Arc::new(bundle(&builds, BUNDLE_BYTES));
+        bundles.insert(key, built.clone());
+        built
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(name: &str, script_len: usize, checksum: u32) -> Arc<WeaponBuild> {
+        Arc::new(WeaponBuild {
+            name: name.into(),
+            image: String::new(),
+            script: "x".repeat(script_len),
+            classes: Vec::new(),
+            checksum,
+        })
+    }
+
+    #[test]
+    fn test_bundles() {
+        let builds = [build("a", 100, 1), build("b", 100, 2), build("big", BUNDLE_BYTES, 3), build("c", 100, 4)];
+        let bundles = bundle(&builds, BUNDLE_BYTES);
+        assert_eq!(bundles.len(), 3);
+        assert_eq!(bundles[0].iter().filter(|&&b| b == b'\n').count(), 2);
+
+        let cache = BundleCache::new();
+        let first = cache.get(&builds);
+        assert!(Arc::ptr_eq(&first, &cache.get(&[builds[3].clone(), builds[2].clone(), builds[1].clone(), builds[0].clone()])));
+        assert!(!Arc::ptr_eq(&first, &cache.get(&[build("a", 100, 9), builds[1].clone(), builds[2].clone(), builds[3].clone()])));
+
+        let mut delivery = WeaponDelivery::default();
+        assert!(delivery.needs(&builds[0]));
+        delivery.sent(&builds[0]);
+        assert!(!delivery.needs(&builds[0]) && delivery.needs(&build("A", 100, 5)));
+        assert!(delivery.contains("A"));
+        delivery.forget("A");
+        assert!(delivery.is_empty());
+
+        let store = DeliveryStore::new();
+        delivery.sent(&builds[1]);
+        store.keep("Bob", delivery);
+        assert!(store.take("bob").contains("b"));
+        assert!(store.take("bob").is_empty());
+    }
+}
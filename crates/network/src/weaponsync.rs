@@ -0,0 +1,192 @@
+//! # Weapon Updates
+//!
+//! Resends weapons to online players when a class they join changes, so
+//! nobody has to reconnect to get the new code:
+//!
+//! 1. [`update_class`] (NC class edits) or [`check_class_files`] (edits in
+//!    `scripts/`, every `classcheckinterval` seconds) replaces the class
+//! 2. [`class_changed`] drops the builds of the weapons that join it,
+//!    rebuilds them and publishes [`GameEvent::WeaponChanged`] for each
+//! 3. The server's player relay sends PLO_NPCWEAPONDEL and PLO_NPCWEAPONADD
+//!    to every player whose account has the weapon and whose client has an
+//!    older build (see [`crate::weapondelivery`])
+//!
+//! Weapons whose build comes out the same (the class change was serverside
+//! only) keep their checksum and aren't resent. NC weapon edits
+//! ([`update_weapon`]) go the same way for the one weapon. Both kinds of
+//! NC edits keep the previous version (see [`crate::scripthistory`]).
+//!
+//! # Packet Format (PLO_NPCWEAPONADD)
+//! ```text
+//! {GSTRING name}{GCHAR 0}{GSTRING image}{GCHAR 1}{GSHORT length}{script}
+//! ```
+//! Script lines are separated by `0xA7` since packets end at a newline.
+
+use crate::context::ServerContext;
+use crate::scripthistory::ScriptKind;
+use bytes::{BufMut, BytesMut};
+use gserver_game::{GameEvent, Weapon, WeaponBuild};
+use gserver_protocol::codecs::{write_gchar, write_gshort, write_gstring};
+use std::io;
+
+/// Longest script a GSHORT length can describe
+const MAX_SCRIPT_LENGTH: usize = 28767;
+
+/// Line separator in weapon scripts sent to clients
+const LINE_SEPARATOR: u8 = 0xA7;
+
+/// Encode a PLO_NPCWEAPONADD body
+pub fn npc_weapon_add_data(build: &WeaponBuild) -> Vec<u8> {
+    let mut script: Vec<u8> = build.script.bytes()
+        .filter(|&b| b != b'\r')
+        .map(|b| if b == b'\n' { LINE_SEPARATOR } else { b })
+        .collect();
+    if script.len() > MAX_SCRIPT_LENGTH {
+        tracing::warn!("Weapon {} script is {} bytes, only {} are sent", build.name, script.len(), MAX_SCRIPT_LENGTH);
+        script.truncate(MAX_SCRIPT_LENGTH);
+    }
+
+    let mut data = BytesMut::new();
+    write_gstring(&mut data, &build.name);
+    write_gchar(&mut data, 0);
+    write_gstring(&mut data, &build.image);
+    write_gchar(&mut data, 1);
+    write_gshort(&mut data, script.len() as i16);
+    data.put_slice(&script);
+    data.to_vec()
+}
+
+/// Rebuild the weapons joining a class and tell players about the new builds
+///
+/// # Returns
+/// The weapons whose build changed
+pub fn class_changed(context: &ServerContext, class: &str) -> Vec<String> {
+    let weapons = context.weapons();
+    // Weapons that were never built count as changed
+    let old: Vec<(String, Option<u32>)> = weapons.dependents(class).into_iter()
+        .map(|name| {
+            let checksum = weapons.cached_build(&name).map(|b| b.checksum);
+            (name, checksum)
+        })
+        .collect();
+    weapons.invalidate_class(class);
+
+    let mut changed = Vec::new();
+    for (name, old) in old {
+        let Some(build) = weapons.build(&name, context.classes()) else {
+            continue;
+        };
+        if old != Some(build.checksum) {
+            context.events().publish(GameEvent::WeaponChanged { name: name.clone(), checksum: build.checksum });
+            changed.push(name);
+        }
+    }
+
+    if !changed.is_empty() {
+        tracing::info!("Class {} changed, resending weapons: {}", class, changed.join(", "));
+    }
+    changed
+}
+
+/// Replace a class (NC class edit) and resend the weapons that join it
+///
+/// # Returns
+/// The weapons whose build changed
+///
+/// # Errors
+/// Fails if the class file can't be written; the new class is used anyway.
+pub fn update_class(context: &ServerContext, name: &str, script: &str) -> io::Result<Vec<String>> {
+    crate::scripthistory::keep_current(context, ScriptKind::Class, name);
+    let saved = context.classes().set_class(name, script);
+    let changed = class_changed(context, name);
+    saved.map(|_| changed)
+}
+
+/// Replace a weapon (NC weapon edit) and resend it to the players who have it
+///
+/// # Returns
+/// Whether its build changed
+///
+/// # Errors
+/// Fails if the weapon file can't be written; the new weapon is used anyway.
+pub fn update_weapon(context: &ServerContext, weapon: Weapon) -> io::Result<bool> {
+    let name = weapon.name.clone();
+    crate::scripthistory::keep_current(context, ScriptKind::Weapon, &name);
+    let old = context.weapons().cached_build(&name).map(|b| b.checksum);
+    let saved = context.weapons().save_weapon(weapon);
+
+    let build = context.weapons().build(&name, context.classes());
+    let changed = build.as_ref().is_some_and(|b| old != Some(b.checksum));
+    if let Some(build) = build.filter(|_| changed) {
+        tracing::info!("Weapon {} changed, resending it", name);
+        context.events().publish(GameEvent::WeaponChanged { name, checksum: build.checksum });
+    }
+    saved.map(|_| changed)
+}
+
+/// Reload classes edited on disk and resend the weapons that join them
+///
+/// # Returns
+/// The weapons whose build changed
+pub fn check_class_files(context: &ServerContext) -> Vec<String> {
+    let mut changed = Vec::new();
+    for class in context.classes().reload_changed() {
+        tracing::debug!("Class file {} changed on disk", class);
+        for weapon in class_changed(context, &class) {
+            if !changed.contains(&weapon) {
+                changed.push(weapon);
+            }
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gserver_config::ServerConfig as GameConfig;
+    use gserver_game::Weapon;
+
+    #[test]
+    fn test_npc_weapon_add_data() {
+        let build = WeaponBuild {
+            name: "-shop".into(),
+            image: "shop.png".into(),
+            script: "//#CLIENTSIDE\r\nfoo();\n".into(),
+            classes: Vec::new(),
+            checksum: 0,
+        };
+        let data = npc_weapon_add_data(&build);
+        assert_eq!(&data[..6], b"%-shop");
+        assert_eq!(&data[6..16], b" (shop.png");
+        assert_eq!(data[16], 33);
+        assert_eq!(&data[17..19], &[32, 32 + 21]);
+        assert_eq!(&data[19..], b"//#CLIENTSIDE\xa7foo();\xa7");
+    }
+
+    #[test]
+    fn test_class_change_publishes_rebuilt_weapons() {
+        let dir = tempfile::tempdir().unwrap();
+        let context = ServerContext::new(dir.path(), GameConfig::default());
+        let mut events = context.events().subscribe();
+        context.classes().set_class("gui", "//#CLIENTSIDE\nfunction onShow() {}\n").unwrap();
+        context.weapons().add_weapon(Weapon::new("-shop", "", "//#CLIENTSIDE\njoin(\"gui\");\n"));
+        context.weapons().add_weapon(Weapon::new("-other", "", "//#CLIENTSIDE\n"));
+        context.weapons().build("-shop", context.classes()).unwrap();
+
+        // Serverside changes don't reach clients
+        let changed = update_class(&context, "gui", "function onCreated() {}\n//#CLIENTSIDE\nfunction onShow() {}\n").unwrap();
+        assert!(changed.is_empty());
+        assert!(events.try_recv().is_err());
+
+        let changed = update_class(&context, "gui", "//#CLIENTSIDE\nfunction onShow() { show(); }\n").unwrap();
+        assert_eq!(changed, ["-shop"]);
+        let checksum = context.weapons().build("-shop", context.classes()).unwrap().checksum;
+        assert_eq!(events.try_recv().unwrap(), GameEvent::WeaponChanged { name: "-shop".into(), checksum });
+
+        std::fs::write(dir.path().join("scripts/gui.txt"), "//#CLIENTSIDE\n").unwrap();
+        let file = std::fs::File::options().write(true).open(dir.path().join("scripts/gui.txt")).unwrap();
+        file.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(5)).unwrap();
+        assert_eq!(check_class_files(&context), ["-shop"]);
+    }
+}
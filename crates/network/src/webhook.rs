@@ -0,0 +1,445 @@
+//! # Event Webhook
+//!
+//! Posts selected server events to a webhook as Discord-compatible JSON
+//! (`{"username": ..., "content": ...}`). Disabled unless `webhookurl` is set.
+//!
+//! # Events
+//!
+//! | Name | Posted for | Template variables |
+//! |------|------------|--------------------|
+//! | `join` | A player logged in | `{account}`, `{id}`, `{level}` |
+//! | `leave` | A player disconnected | `{account}`, `{id}` |
+//! | `staff` | Staff notices and integrity violations | `{message}` |
+//! | `serverup` | The webhook service started | `{server}` |
+//! | `serverdown` | The server is shutting down | `{server}` |
+//! | `chat` | PLI_TOALL on a `webhookchatlevels` level | `{id}`, `{level}`, `{message}` |
+//!
+//! Every template can also use `{server}`. Templates are overridden with
+//! `webhooktemplate.<event> = ...`. Unknown `{...}` sequences are left as
+//! they are.
+//!
+//! # Transport
+//!
+//! Only plain `http://` URLs are supported (there is no TLS client in the
+//! server); point `webhookurl` at a local relay or HTTPS-terminating proxy to
+//! reach Discord. At most `webhookratelimit` posts go out per minute, the rest
+//! are dropped and counted.
+
+use crate::context::ServerContext;
+use gserver_config::WebhookConfig;
+use gserver_core::{GServerError, Result};
+use gserver_game::GameEvent;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::watch;
+
+/// Time allowed for one webhook request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Kind of event posted to the webhook
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WebhookEvent {
+    Join,
+    Leave,
+    Staff,
+    ServerUp,
+    ServerDown,
+    Chat,
+}
+
+impl WebhookEvent {
+    /// Get the name used in `webhookevents` and `webhooktemplate.<event>`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Join => "join",
+            Self::Leave => "leave",
+            Self::Staff => "staff",
+            Self::ServerUp => "serverup",
+            Self::ServerDown => "serverdown",
+            Self::Chat => "chat",
+        }
+    }
+
+    /// Get the template used when none is configured
+    pub fn default_template(self) -> &'static str {
+        match self {
+            Self::Join => "{account} joined ({level})",
+            Self::Leave => "{account} left",
+            Self::Staff => "[staff] {message}",
+            Self::ServerUp => "{server} is up",
+            Self::ServerDown => "{server} is shutting down",
+            Self::Chat => "[{level}] #{id}: {message}",
+        }
+    }
+}
+
+/// A rendered webhook post
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookMessage {
+    /// Event the message is for
+    pub event: WebhookEvent,
+
+    /// Rendered text
+    pub content: String,
+}
+
+/// Turns events into webhook messages according to the configuration
+#[derive(Debug, Clone)]
+pub struct WebhookFormatter {
+    config: WebhookConfig,
+    server: String,
+}
+
+impl WebhookFormatter {
+    /// Create a formatter
+    ///
+    /// # Arguments
+    /// * `config` - Webhook settings
+    /// * `server` - Server name, for `{server}` and the post's username
+    pub fn new(config: WebhookConfig, server: &str) -> Self {
+        Self { config, server: server.to_string() }
+    }
+
+    /// Check if an event kind is enabled
+    pub fn enabled(&self, event: WebhookEvent) -> bool {
+        self.config.events.iter().any(|name| name == event.name())
+    }
+
+    /// Render a message for an event kind
+    ///
+    /// # Returns
+    /// `None` if the event kind isn't enabled
+    pub fn message(&self, event: WebhookEvent, vars: &[(&str, &str)]) -> Option<WebhookMessage> {
+        if !self.enabled(event) {
+            return None;
+        }
+        let template = self.config.templates.get(event.name()).map_or(event.default_template(), String::as_str);
+        let server = [("server", self.server.as_str())];
+        Some(WebhookMessage { event, content: render(template, &[vars, &server].concat()) })
+    }
+
+    /// Render the message for a game event, if it should be posted
+    pub fn game_event(&self, event: &GameEvent) -> Option<WebhookMessage> {
+        match event {
+            GameEvent::PlayerJoined { id, account, level } => {
+                let id = id.get().to_string();
+                self.message(WebhookEvent::Join, &[("account", account), ("id", &id), ("level", level)])
+            }
+            GameEvent::PlayerLeft { id, account } => {
+                let id = id.get().to_string();
+                self.message(WebhookEvent::Leave, &[("account", account), ("id", &id)])
+            }
+            GameEvent::StaffNotice { message } => self.message(WebhookEvent::Staff, &[("message", message)]),
+            GameEvent::IntegrityViolation { id, account, reason } => {
+                let message = format!("{} (id {}) {}", account, id.get(), reason);
+                self.message(WebhookEvent::Staff, &[("message", &message)])
+            }
+            GameEvent::ChatMessage { id, level, message } => {
+                if !self.config.chat_levels.iter().any(|l| l.eq_ignore_ascii_case(level)) {
+                    return None;
+                }
+                let id = id.get().to_string();
+                self.message(WebhookEvent::Chat, &[("id", &id), ("level", level), ("message", message)])
+            }
+            _ => None,
+        }
+    }
+
+    /// Build the JSON body for a message
+    pub fn body(&self, message: &WebhookMessage) -> String {
+        serde_json::json!({ "username": self.server, "content": message.content }).to_string()
+    }
+}
+
+/// Substitute `{name}` variables in a template
+pub fn render(template: &str, vars: &[(&str, &str)]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find('}') else { break };
+        match vars.iter().find(|(name, _)| *name == &rest[1..end]) {
+            Some((_, value)) => {
+                result.push_str(value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Sliding one-minute window of posts
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_minute: u32,
+    sent: VecDeque<Instant>,
+    dropped: u64,
+}
+
+impl RateLimiter {
+    /// Create a limiter allowing `per_minute` posts per minute (0 = no limit)
+    pub fn new(per_minute: u32) -> Self {
+        Self { per_minute, sent: VecDeque::new(), dropped: 0 }
+    }
+
+    /// Check if a post may go out now, and count it if so
+    pub fn allow(&mut self, now: Instant) -> bool {
+        if self.per_minute == 0 {
+            return true;
+        }
+        while self.sent.front().is_some_and(|&at| now.saturating_duration_since(at) >= Duration::from_secs(60)) {
+            self.sent.pop_front();
+        }
+        if self.sent.len() >= self.per_minute as usize {
+            self.dropped += 1;
+            return false;
+        }
+        self.sent.push_back(now);
+        true
+    }
+
+    /// Get the number of posts dropped by the limit
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+/// Host, port and path of an `http://` URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebhookUrl {
+    pub host: String,
+    pub port: u16,
+    pub path: String,
+}
+
+impl WebhookUrl {
+    /// Parse an `http://host[:port][/path]` URL
+    ///
+    /// # Errors
+    /// `GServerError::Config` for other schemes or a bad port
+    pub fn parse(url: &str) -> Result<Self> {
+        let Some(rest) = url.strip_prefix("http://") else {
+            return Err(GServerError::Config(format!("webhookurl must be an http:// URL: {}", url)));
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(slash) => (&rest[..slash], &rest[slash..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => {
+                let port = port.parse().map_err(|_| GServerError::Config(format!("Bad webhookurl port: {}", port)))?;
+                (host, port)
+            }
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(GServerError::Config(format!("webhookurl has no host: {}", url)));
+        }
+        Ok(Self { host: host.to_string(), port, path: path.to_string() })
+    }
+
+    /// POST a JSON body
+    ///
+    /// # Returns
+    /// The HTTP status code
+    pub async fn post_json(&self, body: &str) -> Result<u16> {
+        let request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.path, self.host, body.len(), body
+        );
+
+        let exchange = async {
+            let mut stream = TcpStream::connect((self.host.as_str(), self.port)).await?;
+            stream.write_all(request.as_bytes()).await?;
+
+            // The status line is all we need
+            let mut response = Vec::new();
+            let mut chunk = [0u8; 256];
+            while !response.contains(&b'\n') {
+                let n = stream.read(&mut chunk).await?;
+                if n == 0 {
+                    break;
+                }
+                response.extend_from_slice(&chunk[..n]);
+            }
+            Ok::<_, std::io::Error>(response)
+        };
+        let response = tokio::time::timeout(REQUEST_TIMEOUT, exchange).await
+            .map_err(|_| GServerError::Network(format!("Webhook {}:{} timed out", self.host, self.port)))??;
+
+        let status_line = String::from_utf8_lossy(&response);
+        status_line.split_whitespace().nth(1).and_then(|code| code.parse().ok())
+            .ok_or_else(|| GServerError::Network(format!("Bad webhook response: {}", status_line.lines().next().unwrap_or(""))))
+    }
+}
+
+/// Background task posting events to the webhook
+pub struct WebhookService {
+    url: WebhookUrl,
+    formatter: WebhookFormatter,
+    limiter: RateLimiter,
+    context: Arc<ServerContext>,
+}
+
+impl WebhookService {
+    /// Create the service from the server options
+    ///
+    /// # Returns
+    /// `None` if no webhook is configured
+    ///
+    /// # Errors
+    /// `GServerError::Config` if `webhookurl` can't be used
+    pub fn from_context(context: Arc<ServerContext>) -> Result<Option<Self>> {
+        let (config, server) = {
+            let config = context.config().read();
+            (config.webhook.clone(), config.name.clone())
+        };
+        if config.url.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            url: WebhookUrl::parse(&config.url)?,
+            limiter: RateLimiter::new(config.rate_limit),
+            formatter: WebhookFormatter::new(config, &server),
+            context,
+        }))
+    }
+
+    /// Post events until `shutdown` turns true, then post `serverdown`
+    pub async fn run(mut self, mut shutdown: watch::Receiver<bool>) {
+        let mut events = self.context.events().subscribe();
+        if let Some(message) = self.formatter.message(WebhookEvent::ServerUp, &[]) {
+            self.post(message).await;
+        }
+
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => {
+                        if let Some(message) = self.formatter.game_event(&event) {
+                            self.post(message).await;
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Webhook skipped {} events", skipped);
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = shutdown.changed() => {
+                    if *shutdown.borrow() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if let Some(message) = self.formatter.message(WebhookEvent::ServerDown, &[]) {
+            self.post(message).await;
+        }
+        if self.limiter.dropped() > 0 {
+            tracing::info!("Webhook dropped {} posts over the rate limit", self.limiter.dropped());
+        }
+    }
+
+    /// Post one message if the rate limit allows
+    ///
+    /// Failures are logged; a down webhook never affects the game.
+    async fn post(&mut self, message: WebhookMessage) {
+        if !self.limiter.allow(Instant::now()) {
+            tracing::debug!("Webhook rate limit reached, dropping {} post", message.event.name());
+            return;
+        }
+
+        match self.url.post_json(&self.formatter.body(&message)).await {
+            Ok(status) if (200..300).contains(&status) => {}
+            Ok(status) => tracing::warn!("Webhook answered {} to {} post", status, message.event.name()),
+            Err(e) => tracing::warn!("Webhook post failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gserver_core::PlayerID;
+
+    fn formatter() -> WebhookFormatter {
+        let mut config = WebhookConfig {
+            events: ["join", "chat", "serverup"].map(String::from).to_vec(),
+            chat_levels: vec!["start.nw".into()],
+            ..Default::default()
+        };
+        config.templates.insert("join".into(), "**{account}** joined {server} {unknown}".into());
+        WebhookFormatter::new(config, "Classic")
+    }
+
+    #[test]
+    fn test_events_are_filtered_and_templated() {
+        let formatter = formatter();
+        let joined = GameEvent::PlayerJoined { id: PlayerID(3), account: "Bob".into(), level: "start.nw".into() };
+        assert_eq!(formatter.game_event(&joined).unwrap().content, "**Bob** joined Classic {unknown}");
+        assert!(formatter.game_event(&GameEvent::PlayerLeft { id: PlayerID(3), account: "Bob".into() }).is_none());
+
+        let chat = |level: &str| GameEvent::ChatMessage { id: PlayerID(3), level: level.into(), message: "hi".into() };
+        assert_eq!(formatter.game_event(&chat("START.nw")).unwrap().content, "[START.nw] #3: hi");
+        assert!(formatter.game_event(&chat("other.nw")).is_none());
+
+        let up = formatter.message(WebhookEvent::ServerUp, &[]).unwrap();
+        assert_eq!(formatter.body(&up), r#"{"content":"Classic is up","username":"Classic"}"#);
+    }
+
+    #[test]
+    fn test_rate_limit_window() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(2);
+        assert!(limiter.allow(start));
+        assert!(limiter.allow(start));
+        assert!(!limiter.allow(start + Duration::from_secs(59)));
+        assert!(limiter.allow(start + Duration::from_secs(60)));
+        assert_eq!(limiter.dropped(), 1);
+    }
+
+    #[test]
+    fn test_parse_url() {
+        assert_eq!(WebhookUrl::parse("http://localhost:8080/hook/1").unwrap(), WebhookUrl {
+            host: "localhost".into(),
+            port: 8080,
+            path: "/hook/1".into(),
+        });
+        assert_eq!(WebhookUrl::parse("http://relay").unwrap().path, "/");
+        assert!(WebhookUrl::parse("https://discord.com/api/webhooks/1").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_post_json() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = vec![0u8; 1024];
+            let n = socket.read(&mut request).await.unwrap();
+            socket.write_all(b"HTTP/1.1 204 No Content\r\n\r\n").await.unwrap();
+            String::from_utf8_lossy(&request[..n]).to_string()
+        });
+
+        let url = WebhookUrl::parse(&format!("http://127.0.0.1:{}/hook", port)).unwrap();
+        assert_eq!(url.post_json(r#"{"content":"hi"}"#).await.unwrap(), 204);
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /hook HTTP/1.1\r\n"));
+        assert!(request.ends_with(r#"{"content":"hi"}"#));
+    }
+}
@@ -0,0 +1,220 @@
+//! Protocol hot paths
+//!
+//! Integer and string codecs, zlib/bzip2 round trips at typical bundle sizes
+//! and the XOR bundle cipher.
+//!
+//! Run with `cargo bench -p gserver-protocol --bench codecs`. After the run
+//! every mean is compared with `benches/baseline.json`, and the bench fails if
+//! one is more than the baseline's tolerance slower. Rewrite the baseline
+//! with `cargo bench -p gserver-protocol --bench codecs --features bless` on
+//! the machine you compare on.
+
+use bytes::BytesMut;
+use criterion::{black_box, BenchmarkId, Criterion, Throughput};
+use gserver_protocol::codecs::*;
+use gserver_protocol::compression::{compress, decompress, CompressionType};
+use gserver_protocol::encryption::Cipher;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Baseline file, next to this bench
+const BASELINE: &str = "benches/baseline.json";
+
+/// Slowdown allowed before a bench counts as a regression
+const DEFAULT_TOLERANCE: f64 = 0.25;
+
+/// Values that take every encoded length of each codec
+const INTEGERS: [u32; 6] = [0, 100, 3000, 100_000, 5_000_000, 400_000_000];
+
+/// Packet-like data: GChar-encoded text lines with some repetition
+fn bundle(len: usize) -> Vec<u8> {
+    let line = b"\x2a\x20\x21\x22Hello from level onlinestartlocal.nw at 30.5,30.5\n";
+    line.iter().copied().cycle()
+        .enumerate()
+        .map(|(i, b)| if i % 97 == 0 { (i % 64) as u8 + 32 } else { b })
+        .take(len)
+        .collect()
+}
+
+fn bench_integers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("integers");
+
+    macro_rules! codec {
+        ($name:literal, $write:ident, $read:ident, $ty:ty) => {
+            let values = INTEGERS.map(|v| v as $ty);
+            group.bench_function(concat!("write_", $name), |b| {
+                let mut buf = BytesMut::with_capacity(64);
+                b.iter(|| {
+                    buf.clear();
+                    for v in values {
+                        $write(&mut buf, black_box(v));
+                    }
+                })
+            });
+
+            let mut encoded = BytesMut::new();
+            for v in values {
+                $write(&mut encoded, v);
+            }
+            group.bench_function(concat!("read_", $name), |b| {
+                b.iter(|| {
+                    let mut buf = encoded.clone();
+                    for _ in values {
+                        black_box($read(&mut buf).unwrap());
+                    }
+                })
+            });
+        };
+    }
+
+    codec!("gchar", write_gchar, read_gchar, i8);
+    codec!("gshort", write_gshort, read_gshort, i16);
+    codec!("gint", write_gint, read_gint, i32);
+    codec!("guint5", write_guint5, read_guint5, u32);
+    group.finish();
+}
+
+fn bench_gstring(c: &mut Criterion) {
+    let mut group = c.benchmark_group("gstring");
+    let text = "onlinestartlocal.nw";
+
+    group.bench_function("write", |b| {
+        let mut buf = BytesMut::with_capacity(64);
+        b.iter(|| {
+            buf.clear();
+            write_gstring(&mut buf, black_box(text));
+        })
+    });
+
+    let mut encoded = BytesMut::new();
+    write_gstring(&mut encoded, text);
+    group.bench_function("read", |b| {
+        b.iter(|| read_gstring(&mut encoded.clone()).unwrap())
+    });
+    group.finish();
+}
+
+fn bench_compression(c: &mut Criterion) {
+    let mut group = c.benchmark_group("roundtrip");
+
+    // Movement and chat bundles, a level load, and a GEN_5 bzip2-sized bundle
+    for (method, name, sizes) in [
+        (CompressionType::Zlib, "zlib", &[256, 4096][..]),
+        (CompressionType::Bzip2, "bz2", &[0x8000][..]),
+    ] {
+        for &len in sizes {
+            let data = bundle(len);
+            group.throughput(Throughput::Bytes(len as u64));
+            group.bench_with_input(BenchmarkId::new(name, len), &data, |b, data| {
+                b.iter(|| decompress(&compress(black_box(data), method).unwrap(), method).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_xor_crypt(c: &mut Criterion) {
+    let mut group = c.benchmark_group("xor_crypt");
+
+    // GEN_5 encrypts 12 words of uncompressed bundles; GEN_4 and unlimited
+    // GEN_5 bundles run over everything
+    for (name, limit) in [("limit12", 12), ("unlimited", -1)] {
+        let mut data = bundle(4096);
+        group.throughput(Throughput::Bytes(data.len() as u64));
+        group.bench_function(BenchmarkId::new(name, data.len()), |b| {
+            let mut cipher = Cipher::new(73);
+            b.iter(|| cipher.apply(black_box(&mut data), limit))
+        });
+    }
+    group.finish();
+}
+
+/// Criterion's output directory (`<target>/criterion`)
+fn criterion_dir() -> PathBuf {
+    let target = std::env::var_os("CARGO_TARGET_DIR").map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_TARGET_TMPDIR")).parent().unwrap().to_path_buf());
+    target.join("criterion")
+}
+
+/// Collect the mean time (ns) of every bench that has results
+///
+/// Keys are the result directories below `criterion/`, e.g.
+/// `integers/write_gchar` or `roundtrip/zlib/256`.
+fn read_means(dir: &Path, prefix: &str, means: &mut BTreeMap<String, f64>) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !path.is_dir() || name == "report" {
+            continue;
+        }
+        let key = format!("{}/{}", prefix, name);
+        match std::fs::read_to_string(path.join("new/estimates.json")) {
+            Ok(text) => {
+                let json: serde_json::Value = serde_json::from_str(&text).unwrap_or_default();
+                if let Some(mean) = json["mean"]["point_estimate"].as_f64() {
+                    means.insert(key, mean);
+                }
+            }
+            Err(_) => read_means(&path, &key, means),
+        }
+    }
+}
+
+/// Compare this run with the baseline, or rewrite it when blessing
+///
+/// # Returns
+/// The benches that got slower than the tolerance allows
+fn check_baseline(groups: &[&str]) -> Vec<String> {
+    let mut means = BTreeMap::new();
+    for group in groups {
+        read_means(&criterion_dir().join(group), group, &mut means);
+    }
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(BASELINE);
+
+    if cfg!(feature = "bless") {
+        let rounded = means.iter().map(|(k, v)| (k.clone(), (v * 100.0).round() / 100.0)).collect::<BTreeMap<_, _>>();
+        let baseline = serde_json::json!({ "tolerance": DEFAULT_TOLERANCE, "mean_ns": rounded });
+        std::fs::write(&path, serde_json::to_string_pretty(&baseline).unwrap() + "\n").unwrap();
+        println!("Wrote {} benches to {}", means.len(), path.display());
+        return Vec::new();
+    }
+
+    let Ok(text) = std::fs::read_to_string(&path) else {
+        println!("No baseline at {}", path.display());
+        return Vec::new();
+    };
+    let baseline: serde_json::Value = serde_json::from_str(&text).expect("baseline.json is not valid JSON");
+    let tolerance = baseline["tolerance"].as_f64().unwrap_or(DEFAULT_TOLERANCE);
+
+    let mut regressions = Vec::new();
+    for (name, mean) in &means {
+        let Some(expected) = baseline["mean_ns"][name].as_f64() else { continue };
+        let change = mean / expected - 1.0;
+        println!("{:<32} {:>12.1} ns  baseline {:>12.1} ns  {:+.1}%", name, mean, expected, change * 100.0);
+        if change > tolerance {
+            regressions.push(format!("{} is {:.0}% slower than the baseline", name, change * 100.0));
+        }
+    }
+    regressions
+}
+
+fn main() {
+    let mut criterion = Criterion::default()
+        .warm_up_time(Duration::from_secs(1))
+        .measurement_time(Duration::from_secs(2))
+        .configure_from_args();
+
+    bench_integers(&mut criterion);
+    bench_gstring(&mut criterion);
+    bench_compression(&mut criterion);
+    bench_xor_crypt(&mut criterion);
+    criterion.final_summary();
+
+    let regressions = check_baseline(&["integers", "gstring", "roundtrip", "xor_crypt"]);
+    if !regressions.is_empty() {
+        eprintln!("\n{}", regressions.join("\n"));
+        std::process::exit(1);
+    }
+}
@@ -4,7 +4,8 @@
 //! All bytes have +32 added (ASCII space offset), and each byte uses 7 bits for data.
 
 use bytes::{Buf, BufMut, BytesMut};
-use gserver_core::{GServerError, Result};
+use crate::error::ProtocolError;
+use gserver_core::Result;
 
 /// Trait for types that can be encoded/decoded using Graal protocol
 pub trait GSerializable: Sized {
@@ -30,7 +31,7 @@ pub fn write_gchar(buf: &mut BytesMut, val: i8) {
 #[inline]
 pub fn read_gchar(buf: &mut BytesMut) -> Result<i8> {
     if buf.remaining() < 1 {
-        return Err(GServerError::InvalidData("Not enough bytes for GChar".into()));
+        return Err(ProtocolError::Truncated { field: "GChar", needed: 1, remaining: buf.remaining() }.into());
     }
     let val = buf.get_u8();
     Ok((val.wrapping_sub(32)) as i8)
@@ -57,7 +58,7 @@ pub fn write_gshort(buf: &mut BytesMut, val: i16) {
 #[inline]
 pub fn read_gshort(buf: &mut BytesMut) -> Result<i16> {
     if buf.remaining() < 2 {
-        return Err(GServerError::InvalidData("Not enough bytes for GShort".into()));
+        return Err(ProtocolError::Truncated { field: "GShort", needed: 2, remaining: buf.remaining() }.into());
     }
 
     let byte0 = buf.get_u8();
@@ -96,7 +97,7 @@ pub fn write_gint(buf: &mut BytesMut, val: i32) {
 #[inline]
 pub fn read_gint(buf: &mut BytesMut) -> Result<i32> {
     if buf.remaining() < 3 {
-        return Err(GServerError::InvalidData("Not enough bytes for GInt".into()));
+        return Err(ProtocolError::Truncated { field: "GInt", needed: 3, remaining: buf.remaining() }.into());
     }
 
     let byte0 = buf.get_u8();
@@ -141,7 +142,7 @@ pub fn write_gint4(buf: &mut BytesMut, val: i32) {
 #[inline]
 pub fn read_gint4(buf: &mut BytesMut) -> Result<i32> {
     if buf.remaining() < 4 {
-        return Err(GServerError::InvalidData("Not enough bytes for GInt4".into()));
+        return Err(ProtocolError::Truncated { field: "GInt4", needed: 4, remaining: buf.remaining() }.into());
     }
 
     let byte0 = buf.get_u8();
@@ -188,7 +189,7 @@ pub fn write_guint5(buf: &mut BytesMut, val: u32) {
 #[inline]
 pub fn read_guint5(buf: &mut BytesMut) -> Result<u32> {
     if buf.remaining() < 5 {
-        return Err(GServerError::InvalidData("Not enough bytes for GUInt5".into()));
+        return Err(ProtocolError::Truncated { field: "GUInt5", needed: 5, remaining: buf.remaining() }.into());
     }
 
     let byte0 = buf.get_u8();
@@ -237,12 +238,12 @@ pub fn read_gstring(buf: &mut BytesMut) -> Result<String> {
     let len = len as usize;
 
     if buf.remaining() < len {
-        return Err(GServerError::InvalidData("Not enough bytes for GString".into()));
+        return Err(ProtocolError::Truncated { field: "GString", needed: len, remaining: buf.remaining() }.into());
     }
 
     let bytes = buf.copy_to_bytes(len);
     String::from_utf8(bytes.to_vec())
-        .map_err(|e| GServerError::InvalidData(format!("Invalid UTF-8: {}", e)))
+        .map_err(|_| ProtocolError::InvalidUtf8 { field: "GString" }.into())
 }
 
 /// Read a GUShort (unsigned GShort)
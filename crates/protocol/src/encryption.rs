@@ -0,0 +1,84 @@
+//! # Bundle Encryption
+//!
+//! The XOR cipher used by GEN_3 to GEN_5 bundles. Each direction of a
+//! connection has its own [`Cipher`]; both start from [`ITERATOR_START`] and
+//! advance once per 4-byte word.
+//!
+//! # C++ Equivalence
+//! Matches `CEncryption` in CEncryption.cpp
+
+/// Initial iterator value for GEN_3 to GEN_5
+///
+/// # C++ Equivalence
+/// Matches `CEncryption::ITERATOR_START`
+pub const ITERATOR_START: u32 = 0x04A80B38;
+
+/// Key and iterator of one direction of a connection
+///
+/// # C++ Equivalence
+/// Matches the `m_key`/`m_iterator` pair of `CEncryption`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cipher {
+    key: u8,
+    iterator: u32,
+}
+
+impl Cipher {
+    /// Create a cipher for a key (the one sent in the login packet)
+    pub fn new(key: u8) -> Self {
+        Self { key, iterator: ITERATOR_START }
+    }
+
+    /// Advance the iterator
+    ///
+    /// C++: m_iterator *= 0x8088405; m_iterator += m_key;
+    pub fn step(&mut self) -> u32 {
+        self.iterator = self.iterator.wrapping_mul(0x8088405).wrapping_add(self.key as u32);
+        self.iterator
+    }
+
+    /// XOR-encrypt/decrypt data for GEN_4/5
+    ///
+    /// # Arguments
+    /// * `data` - Data to encrypt/decrypt (modified in place)
+    /// * `limit` - Number of 4-byte words to encrypt (negative = unlimited)
+    ///
+    /// # C++ Equivalence
+    /// Matches `CEncryption::encrypt()` and `CEncryption::decrypt()` for GEN_4/5
+    pub fn apply(&mut self, data: &mut [u8], limit: i32) {
+        let mut current_limit = limit;
+        let mut word = [0u8; 4];
+        for (i, byte) in data.iter_mut().enumerate() {
+            if i % 4 == 0 {
+                if current_limit == 0 {
+                    break;
+                }
+                // C++ reads the iterator through a byte pointer, which on x86 is
+                // little-endian: [0x38, 0x0B, 0xA8, 0x04] for 0x04A80B38
+                word = self.step().to_le_bytes();
+                if current_limit > 0 {
+                    current_limit -= 1;
+                }
+            }
+            *byte ^= word[i % 4];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_limit_and_roundtrip() {
+        let plain = b"0123456789abcdef".to_vec();
+
+        let mut data = plain.clone();
+        Cipher::new(73).apply(&mut data, 2);
+        assert_ne!(data[..8], plain[..8]);
+        assert_eq!(data[8..], plain[8..]);
+
+        Cipher::new(73).apply(&mut data, 2);
+        assert_eq!(data, plain);
+    }
+}
@@ -0,0 +1,96 @@
+//! Protocol decoding errors
+//!
+//! # Purpose
+//! Failures while decoding packets and bundles. A bad packet only costs that
+//! packet, so most variants log a warning and keep the connection; variants
+//! that leave the bundle stream out of sync disconnect.
+
+use gserver_core::{GServerError, Severity, SubsystemError};
+
+/// Packet or bundle decoding failure
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ProtocolError {
+    /// A field ran past the end of the packet
+    #[error("Not enough bytes for {field}: need {needed}, have {remaining}")]
+    Truncated {
+        field: &'static str,
+        needed: usize,
+        remaining: usize,
+    },
+
+    /// A string field isn't UTF-8
+    #[error("Invalid UTF-8 in {field}")]
+    InvalidUtf8 { field: &'static str },
+
+    /// A packet with no type byte
+    #[error("Empty packet")]
+    EmptyPacket,
+
+    /// A packet type the server doesn't know
+    #[error("Unknown packet type: {0}")]
+    UnknownPacketType(u8),
+
+    /// A packet whose fields decode but make no sense
+    #[error("Malformed {packet} packet: {reason}")]
+    Malformed { packet: &'static str, reason: String },
+
+    /// A bundle over the framing limit
+    #[error("Bundle too large: {size} bytes (max {limit})")]
+    BundleTooLarge { size: usize, limit: usize },
+
+    /// A GEN_5 bundle with an unknown compression type byte
+    #[error("Invalid GEN_5 compression type: 0x{0:02x}")]
+    InvalidCompression(u8),
+
+    /// The client stopped sending in the middle of a bundle
+    #[error("Bundle stalled: {received} of {expected} bytes after {after:?}")]
+    BundleStalled { received: usize, expected: usize, after: std::time::Duration },
+}
+
+impl SubsystemError for ProtocolError {
+    fn severity(&self) -> Severity {
+        match self {
+            Self::EmptyPacket | Self::UnknownPacketType(_) => Severity::Debug,
+            _ => Severity::Warn,
+        }
+    }
+
+    fn disconnects(&self) -> bool {
+        // Past these the next bundle can't be found or decrypted
+        matches!(self, Self::BundleTooLarge { .. } | Self::InvalidCompression(_) | Self::BundleStalled { .. })
+    }
+}
+
+impl From<ProtocolError> for GServerError {
+    fn from(error: ProtocolError) -> Self {
+        GServerError::subsystem(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codecs::{read_gint, read_gstring};
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_codec_errors_are_structured() {
+        let error = read_gint(&mut BytesMut::from(&b"  "[..])).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<ProtocolError>(),
+            Some(&ProtocolError::Truncated { field: "GInt", needed: 3, remaining: 2 })
+        );
+        assert!(!error.disconnects());
+
+        let error = read_gstring(&mut BytesMut::from(&b"\x22\xff\xfe"[..])).unwrap_err();
+        assert_eq!(error.downcast_ref::<ProtocolError>(), Some(&ProtocolError::InvalidUtf8 { field: "GString" }));
+    }
+
+    #[test]
+    fn test_framing_errors_disconnect() {
+        let error: GServerError = ProtocolError::BundleTooLarge { size: 2_000_000, limit: 1_000_000 }.into();
+        assert!(error.disconnects());
+        assert_eq!(error.severity(), Severity::Warn);
+        assert_eq!(GServerError::from(ProtocolError::UnknownPacketType(250)).severity(), Severity::Debug);
+    }
+}
@@ -0,0 +1,214 @@
+//! # Packet Golden Files
+//!
+//! Every [`packet_builder`](crate::packet_builder) function is run with fixed
+//! arguments and its output compared with `golden/<name>.hex`, so a change to
+//! a packet's bytes can't go unnoticed.
+//!
+//! # Blessing Changes
+//!
+//! When a format change is intended, rewrite the files and review the diff:
+//!
+//! ```text
+//! cargo test -p gserver-protocol --features bless golden
+//! git diff crates/protocol/golden
+//! ```
+//!
+//! # File Format
+//!
+//! ```text
+//! # build_signature(buf, 73)
+//! 39 69 0a
+//! ```
+//!
+//! The first line is the call the bytes come from, then the bytes in hex,
+//! 32 per line.
+
+use crate::packet_builder::*;
+use crate::ShowImg;
+use bytes::BytesMut;
+use gserver_core::{HalfTile, TileCoord};
+use std::path::{Path, PathBuf};
+
+/// Bytes per line of a golden file
+const BYTES_PER_LINE: usize = 32;
+
+/// One builder call and the bytes it produced
+struct Case {
+    name: &'static str,
+    call: &'static str,
+    bytes: Vec<u8>,
+}
+
+/// Build a [`Case`] from a closure calling a builder on `buf`
+macro_rules! case {
+    ($name:literal, $build:expr) => {{
+        let build: fn(&mut BytesMut) = $build;
+        let mut buf = BytesMut::new();
+        build(&mut buf);
+        Case { name: $name, call: stringify!($build), bytes: buf.to_vec() }
+    }};
+}
+
+fn strings(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+/// Transparent orange `light.png` at (32, 48.5)
+fn light_overlay() -> ShowImg {
+    ShowImg::new(200, 32.0, 48.5, "light.png".to_string())
+        .with_color(255, 128, 0)
+        .with_transparent(true)
+}
+
+/// Blue parallax overlay at (16, 16), zoomed 200%
+fn tint_overlay() -> ShowImg {
+    ShowImg::new(200, 16.0, 16.0, String::new())
+        .with_color(0, 0, 255)
+        .with_zoom(512)
+        .with_parallax(true)
+}
+
+fn cases() -> Vec<Case> {
+    vec![
+        case!("level_name", |buf| build_level_name(buf, "onlinestartlocal.nw")),
+        case!("raw_data", |buf| build_raw_data(buf, 8, &[0x00, 0x01, 0x02, 0x03, 0x7f, 0x80, 0xfe, 0xff])),
+        case!("level_modtime", |buf| build_level_modtime(buf, 1_700_000_000)),
+        case!("set_active_level", |buf| build_set_active_level(buf, "onlinestartlocal.nw")),
+        case!("new_world_time", |buf| build_new_world_time(buf, 123_456)),
+        case!("ghost_icon", |buf| build_ghost_icon(buf, 0)),
+        case!("is_leader", |buf| build_is_leader(buf)),
+        case!("signature", |buf| build_signature(buf, 73)),
+        case!("warp_failed", |buf| build_warp_failed(buf, "missing.nw")),
+        case!("other_player_props", |buf| build_other_player_props(buf, 300, b"\x20\x25Bob")),
+        case!("add_player", |buf| build_add_player(buf, 2, "bob", b"\x20\x25Bob")),
+        case!("chat", |buf| build_chat(buf, "Hello, world!")),
+        case!("disconnect_message", |buf| build_disconnect_message(buf, "Server is shutting down.")),
+        case!("player_warp", |buf| build_player_warp(buf, HalfTile(60), HalfTile(61), "onlinestartlocal.nw")),
+        case!("clear_weapons", |buf| build_clear_weapons(buf)),
+        case!("serverlist_connected", |buf| build_serverlist_connected(buf)),
+        case!("has_npc_server", |buf| build_has_npc_server(buf)),
+        case!("max_upload_file_size", |buf| build_max_upload_file_size(buf, 20_971_520)),
+        case!("staff_guilds", |buf| build_staff_guilds(buf, &strings(&["Server", "Manager"]))),
+        case!("status_list", |buf| build_status_list(buf, &strings(&["Online", "Away", "DND"]))),
+        case!("rc_chat", |buf| build_rc_chat(buf, "bob: hi staff")),
+        case!("showimg", |buf| build_showimg(buf, &light_overlay())),
+        case!("hideimg", |buf| build_hideimg(buf, 200)),
+        case!("changeimg", |buf| build_changeimg(buf, &tint_overlay())),
+        case!("changeimgvis", |buf| build_changeimgvis(buf, 200, false)),
+        case!("flag_set", |buf| build_flag_set(buf, "quest.stage", "3")),
+        case!("bigmap", |buf| build_bigmap(buf, "world.png")),
+        case!("minimap", |buf| build_minimap(buf, "world_mini.png")),
+        case!("chest", |buf| build_chest(buf, 10, 20, 3, 1)),
+        case!("sign", |buf| build_sign(buf, 30, 12, "Welcome")),
+        case!("board_modify", |buf| build_board_modify(buf, 5, 6, 2, 1, &[0x20, 0x21, 0x22, 0x23])),
+        case!("npc_weapon_add", |buf| build_npc_weapon_add(buf, "-gr_movement")),
+        case!("npc_weapon_del", |buf| build_npc_weapon_del(buf, "bow")),
+        case!("start_message", |buf| build_start_message(buf, "Welcome to the server")),
+        case!("server_text", |buf| build_server_text(buf, "GraalEngine,lister,options")),
+        case!("rpg_window", |buf| build_rpg_window(buf, "You found a key!")),
+        case!("horse_add", |buf| build_horse_add(buf, 1, 30, 31, "horse.png")),
+        case!("horse_del", |buf| build_horse_del(buf, 1)),
+        case!("explosion", |buf| build_explosion(buf, TileCoord(30.5), TileCoord(12.0))),
+        case!("bomb_add", |buf| build_bomb_add(buf, 4, TileCoord(30.0), TileCoord(31.5), 2, 7)),
+        case!("bomb_del", |buf| build_bomb_del(buf, 4)),
+    ]
+}
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("golden")
+}
+
+fn render(case: &Case) -> String {
+    let mut text = format!("# {}\n", case.call.trim_start_matches("|buf|").trim());
+    for line in case.bytes.chunks(BYTES_PER_LINE) {
+        let hex = line.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>();
+        text.push_str(&hex.join(" "));
+        text.push('\n');
+    }
+    text
+}
+
+/// Show the lines that differ between two golden files
+fn diff(expected: &str, actual: &str) -> String {
+    let (expected, actual) = (expected.lines().collect::<Vec<_>>(), actual.lines().collect::<Vec<_>>());
+    let mut out = String::new();
+    for i in 0..expected.len().max(actual.len()) {
+        match (expected.get(i), actual.get(i)) {
+            (Some(e), Some(a)) if e == a => {}
+            (e, a) => {
+                if let Some(e) = e {
+                    out.push_str(&format!("  {:>3} - {}\n", i + 1, e));
+                }
+                if let Some(a) = a {
+                    out.push_str(&format!("  {:>3} + {}\n", i + 1, a));
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packet_golden_files() {
+        let dir = golden_dir();
+        let cases = cases();
+        let bless = cfg!(feature = "bless");
+        let mut failures = Vec::new();
+
+        for case in &cases {
+            let path = dir.join(format!("{}.hex", case.name));
+            let actual = render(case);
+            if bless {
+                std::fs::create_dir_all(&dir).unwrap();
+                std::fs::write(&path, &actual).unwrap();
+                continue;
+            }
+            match std::fs::read_to_string(&path) {
+                Ok(expected) if expected.replace("\r\n", "\n") == actual => {}
+                Ok(expected) => failures.push(format!("{}.hex differs:\n{}", case.name, diff(&expected, &actual))),
+                Err(_) => failures.push(format!("{}.hex is missing", case.name)),
+            }
+        }
+
+        // A golden file without a case is a builder that lost its test
+        for entry in std::fs::read_dir(&dir).into_iter().flatten() {
+            let path = entry.unwrap().path();
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()) else { continue };
+            if path.extension().is_some_and(|e| e == "hex") && !cases.iter().any(|c| c.name == name) {
+                if bless {
+                    std::fs::remove_file(&path).unwrap();
+                } else {
+                    failures.push(format!("{}.hex has no case", name));
+                }
+            }
+        }
+
+        assert!(failures.is_empty(),
+            "{}\n\nIf the change is intended: cargo test -p gserver-protocol --features bless golden",
+            failures.join("\n"));
+    }
+
+    #[test]
+    fn test_case_names_unique() {
+        let cases = cases();
+        for (i, case) in cases.iter().enumerate() {
+            assert!(cases[i + 1..].iter().all(|c| c.name != case.name), "duplicate case {}", case.name);
+        }
+    }
+
+    #[test]
+    fn test_render_and_diff() {
+        let case = Case { name: "x", call: "|buf| build_x(buf)", bytes: (0..40).collect() };
+        let text = render(&case);
+        assert_eq!(text.lines().count(), 3);
+        assert!(text.starts_with("# build_x(buf)\n00 01 02"));
+        assert!(text.ends_with("26 27\n"));
+
+        assert_eq!(diff(&text, &text), "");
+        assert_eq!(diff("# a\n01\n", "# a\n02\n03\n"), "    2 - 01\n    2 + 02\n    3 + 03\n");
+    }
+}
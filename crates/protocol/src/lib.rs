@@ -32,6 +32,13 @@
 //! ### 4. Compression ([`compression`])
 //! Packet compression using zlib or bzip2 algorithms.
 //!
+//! ### 5. Encryption ([`encryption`])
+//! The XOR bundle cipher of GEN_3 to GEN_5.
+//!
+//! ### 6. Errors ([`error`])
+//! [`ProtocolError`] describes decoding failures, with the field or packet that
+//! failed and whether the connection survives it.
+//!
 //! ## Usage Example
 //!
 //! ```rust,no_run
@@ -72,7 +79,9 @@
 //! This library supports all generations through feature flags.
 
 pub mod codecs;
+pub mod error;
 pub mod compression;
+pub mod encryption;
 pub mod packets;
 pub mod packet_types;
 pub mod packet_structures;
@@ -84,8 +93,12 @@ pub mod gs1;
 pub mod rc;
 pub mod nc;
 
+#[cfg(test)]
+mod golden;
+
 // Re-export commonly used items
 pub use codecs::*;
+pub use error::ProtocolError;
 pub use compression::*;
 pub use packets::*;
 pub use packet_types::*;
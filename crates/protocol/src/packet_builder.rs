@@ -18,9 +18,16 @@
 //! let mut buf = BytesMut::new();
 //! build_level_name(&mut buf, "onlinestartlocal.nw");
 //! ```
+//!
+//! ## Golden Files
+//!
+//! The bytes of every builder are pinned in `golden/<name>.hex`. A new
+//! builder needs a case in `src/golden.rs`; a changed format needs the files
+//! blessed (`cargo test -p gserver-protocol --features bless golden`).
 
 use bytes::{BufMut, BytesMut};
-use super::{codecs::*, packets::*};
+use super::{codecs::*, packets::*, showimg::ShowImg};
+use gserver_core::{HalfTile, TileCoord};
 
 /// Build a level name packet (PLO_LEVELNAME = 6)
 ///
@@ -354,8 +361,8 @@ pub fn build_disconnect_message(buf: &mut BytesMut, message: &str) {
 ///
 /// # Arguments
 /// * `buf` - Buffer to write the packet to
-/// * `x` - X coordinate in **pixels** (will be divided by 8 for GChar)
-/// * `y` - Y coordinate in **pixels** (will be divided by 8 for GChar)
+/// * `x` - X coordinate in half-tiles
+/// * `y` - Y coordinate in half-tiles
 /// * `level_name` - Level name to warp to (e.g., "onlinestartlocal.nw")
 ///
 /// # C++ Equivalence
@@ -367,14 +374,10 @@ pub fn build_disconnect_message(buf: &mut BytesMut, message: &str) {
 ///     << levelName);
 /// ```
 /// PropertyTileCoordinate::serialize() writes: `pixelCoordinate / 8` as GChar
-pub fn build_player_warp(buf: &mut BytesMut, x: i32, y: i32, level_name: &str) {
+pub fn build_player_warp(buf: &mut BytesMut, x: HalfTile, y: HalfTile, level_name: &str) {
     buf.put_u8(PacketTypeOut::PlayerWarp.as_u8().wrapping_add(32));
-    // X and Y are stored as pixel coordinates, serialized as GChar = pixel / 8
-    // This represents halftiles (8 pixels = 0.5 tiles)
-    let x_halftile = (x / 8) as i8;
-    let y_halftile = (y / 8) as i8;
-    write_gchar(buf, x_halftile);
-    write_gchar(buf, y_halftile);
+    write_gchar(buf, x.0 as i8);
+    write_gchar(buf, y.0 as i8);
     write_gstring(buf, level_name);
     buf.put_u8(b'\n');
 }
@@ -554,24 +557,13 @@ pub fn build_rc_chat(buf: &mut BytesMut, message: &str) {
 ///
 /// # Arguments
 /// * `buf` - Buffer to write the packet to
-/// * `index` - Image index (0-255)
-/// * `x` - X position (pixels)
-/// * `y` - Y position (pixels)
-/// * `image` - Image filename
-/// * `r` - Red colorization (0-255)
-/// * `g` - Green colorization (0-255)
-/// * `b` - Blue colorization (0-255)
-/// * `zoom` - Zoom level (256 = 100%)
-/// * `mode` - Mode flags (0x01=transparent, 0x02=parallax)
-/// * `params` - Animation parameters
+/// * `image` - The overlay (see [`ShowImg::to_packet_text`])
 ///
 /// # C++ Equivalence
 /// Matches showimg packet in Player.cpp
-pub fn build_showimg(buf: &mut BytesMut, index: u8, x: f32, y: f32, image: &str,
-                    r: u8, g: u8, b: u8, zoom: i16, mode: u8, params: u32) {
+pub fn build_showimg(buf: &mut BytesMut, image: &ShowImg) {
     buf.put_u8(160u8.wrapping_add(32));
-    write_gstring(buf, &format!("{},{},{},{},{},{},{},{},{},{}",
-        index, x, y, image, r, g, b, zoom, mode, params));
+    write_gstring(buf, &image.to_packet_text());
     buf.put_u8(b'\n');
 }
 
@@ -606,11 +598,12 @@ pub fn build_hideimg(buf: &mut BytesMut, index: u8) {
 /// ```text
 /// {162}{index},{x},{y},{r},{g},{b},{zoom},{mode}
 /// ```
-pub fn build_changeimg(buf: &mut BytesMut, index: u8, x: f32, y: f32,
-                       r: u8, g: u8, b: u8, zoom: i16, mode: u8) {
+///
+/// The image name and animation of `image` are not sent.
+pub fn build_changeimg(buf: &mut BytesMut, image: &ShowImg) {
     buf.put_u8(162u8.wrapping_add(32));
     write_gstring(buf, &format!("{},{},{},{},{},{},{},{}",
-        index, x, y, r, g, b, zoom, mode));
+        image.index, image.x, image.y, image.red, image.green, image.blue, image.zoom, image.mode));
     buf.put_u8(b'\n');
 }
 
@@ -852,7 +845,7 @@ pub fn build_horse_del(buf: &mut BytesMut, horse_id: u8) {
 /// ```text
 /// {59}{x},{y}
 /// ```
-pub fn build_explosion(buf: &mut BytesMut, x: f32, y: f32) {
+pub fn build_explosion(buf: &mut BytesMut, x: TileCoord, y: TileCoord) {
     buf.put_u8(59u8.wrapping_add(32));
     write_gstring(buf, &format!("{},{}", x, y));
     buf.put_u8(b'\n');
@@ -867,7 +860,7 @@ pub fn build_explosion(buf: &mut BytesMut, x: f32, y: f32) {
 /// ```text
 /// {50}{bomb_id},{x},{y},{power},{owner_id}
 /// ```
-pub fn build_bomb_add(buf: &mut BytesMut, bomb_id: u8, x: f32, y: f32, power: u8, owner_id: u32) {
+pub fn build_bomb_add(buf: &mut BytesMut, bomb_id: u8, x: TileCoord, y: TileCoord, power: u8, owner_id: u32) {
     buf.put_u8(50u8.wrapping_add(32));
     write_gstring(buf, &format!("{},{},{},{},{}", bomb_id, x, y, power, owner_id));
     buf.put_u8(b'\n');
@@ -940,4 +933,13 @@ mod tests {
         // Should end with newline
         assert_eq!(buf[buf.len() - 1], b'\n');
     }
+
+    #[test]
+    fn test_player_warp_packet() {
+        let mut buf = BytesMut::new();
+        build_player_warp(&mut buf, TileCoord(30.5).to_half_tiles(), TileCoord(30.0).to_half_tiles(), "a.nw");
+
+        // {PLAYERWARP}{GCHAR 61}{GCHAR 60}{GCHAR 4}a.nw\n
+        assert_eq!(&buf[..], &[14 + 32, 61 + 32, 60 + 32, 4 + 32, b'a', b'.', b'n', b'w', b'\n']);
+    }
 }
@@ -12,8 +12,8 @@
 //! - **Testable**: All packets can be tested with round-trip encoding
 
 use bytes::{Buf, BufMut, BytesMut};
-use gserver_core::Result;
-use super::{codecs::*, packets::*};
+use gserver_core::{HalfTile, Result};
+use super::{codecs::*, error::ProtocolError, packets::*};
 
 /// Represents a complete packet from client to server
 ///
@@ -61,17 +61,12 @@ impl PacketIn {
     /// ```
     pub fn deserialize(buf: &mut BytesMut) -> Result<Self> {
         if buf.remaining() < 1 {
-            return Err(gserver_core::GServerError::InvalidData(
-                "Packet buffer is empty".into(),
-            ));
+            return Err(ProtocolError::EmptyPacket.into());
         }
 
         let packet_type_byte = buf.get_u8();
         let packet_type = PacketTypeIn::from_u8(packet_type_byte)
-            .ok_or_else(|| gserver_core::GServerError::Protocol(format!(
-                "Unknown packet type: {}",
-                packet_type_byte
-            )))?;
+            .ok_or(ProtocolError::UnknownPacketType(packet_type_byte))?;
 
         let packet_data = buf.to_vec();
         buf.advance(buf.remaining()); // Consume all remaining data
@@ -126,17 +121,12 @@ impl PacketOut {
     /// Deserialize a packet from a byte buffer
     pub fn deserialize(buf: &mut BytesMut) -> Result<Self> {
         if buf.remaining() < 1 {
-            return Err(gserver_core::GServerError::InvalidData(
-                "Packet buffer is empty".into(),
-            ));
+            return Err(ProtocolError::EmptyPacket.into());
         }
 
         let packet_type_byte = buf.get_u8();
         let packet_type = PacketTypeOut::from_u8(packet_type_byte)
-            .ok_or_else(|| gserver_core::GServerError::Protocol(format!(
-                "Unknown packet type: {}",
-                packet_type_byte
-            )))?;
+            .ok_or(ProtocolError::UnknownPacketType(packet_type_byte))?;
 
         let packet_data = buf.to_vec();
         buf.advance(buf.remaining());
@@ -571,9 +561,10 @@ impl ShowImgPlayerPacket {
         let parts: Vec<&str> = data.split(',').collect();
 
         if parts.len() < 9 {
-            return Err(gserver_core::GServerError::InvalidData(
-                "ShowImgPlayer packet requires 9 parameters".to_string()
-            ));
+            return Err(ProtocolError::Malformed {
+                packet: "ShowImgPlayer",
+                reason: format!("expected 9 parameters, got {}", parts.len()),
+            }.into());
         }
 
         let index = parts[0].parse().unwrap_or(0);
@@ -680,8 +671,8 @@ pub struct ItemAddPacket {
 impl ItemAddPacket {
     /// Deserialize from buffer
     pub fn deserialize(buf: &mut BytesMut) -> Result<Self> {
-        let x = read_gchar(buf)? as f32 / 2.0;
-        let y = read_gchar(buf)? as f32 / 2.0;
+        let x = HalfTile(read_gchar(buf)? as i16).to_tiles().0;
+        let y = HalfTile(read_gchar(buf)? as i16).to_tiles().0;
         let item_type = read_gchar(buf)? as u8;
 
         Ok(Self { x, y, item_type })
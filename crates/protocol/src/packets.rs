@@ -234,6 +234,9 @@ pub enum PacketTypeIn {
     /// Player requests a server warp (different from level warp)
     ServerWarp = 41,
 
+    /// Keepalive reply and ping (C++: PLI_UNKNOWN46)
+    Ping = 46,
+
     //=== Remote Control (RC) Packets ===//
     // These packets are sent by RC (Remote Control) connections
 
@@ -562,6 +565,7 @@ impl PacketTypeIn {
             39 => Some(PacketTypeIn::MapInfo),
             40 => Some(PacketTypeIn::Shoot),
             41 => Some(PacketTypeIn::ServerWarp),
+            46 => Some(PacketTypeIn::Ping),
             //=== RC Packets (51-95) ===//
             51 => Some(PacketTypeIn::RcServerOptionsGet),
             52 => Some(PacketTypeIn::RcServerOptionsSet),
@@ -823,15 +827,39 @@ pub enum PacketTypeOut {
     /// Unknown packet 60
     Unknown60 = 60,
 
+    /// RC: An account's staff comments
+    RcPlayerCommentsGet = 63,
+
+    /// RC: Whether an account is banned, and why
+    RcPlayerBanGet = 64,
+
+    /// RC: File browser folder list (folders the account has rights to)
+    RcFileBrowserDirList = 65,
+
+    /// RC: File browser current folder and its files
+    RcFileBrowserDir = 66,
+
+    /// RC: File browser status message
+    RcFileBrowserMessage = 67,
+
     /// File transfer start
     LargeFileStart = 68,
 
     /// File transfer end
     LargeFileEnd = 69,
 
+    /// RC: Chat line shown in the RC window
+    RcChat = 74,
+
+    /// NPC-Server address (answer to PLI_NPCSERVERQUERY)
+    NpcServerAddr = 79,
+
     /// Server text response
     ServerText = 82,
 
+    /// Total size of a large file transfer
+    LargeFileSize = 84,
+
     /// Raw data
     RawData = 100,
 
@@ -913,6 +941,12 @@ pub enum PacketTypeOut {
 
     /// Shoot (version 2)
     Shoot2 = 191,
+
+    /// Update package version and download size
+    UpdatePackageSize = 196,
+
+    /// Update package download finished
+    UpdatePackageDone = 197,
 }
 
 impl PacketTypeOut {
@@ -989,10 +1023,20 @@ impl PacketTypeOut {
             55 => Some(PacketTypeOut::AddPlayer),
             56 => Some(PacketTypeOut::DelPlayer),
             60 => Some(PacketTypeOut::Unknown60),
+            //=== RC Account Records (63-64) ===//
+            63 => Some(PacketTypeOut::RcPlayerCommentsGet),
+            64 => Some(PacketTypeOut::RcPlayerBanGet),
+            //=== RC File Browser (65-67) ===//
+            65 => Some(PacketTypeOut::RcFileBrowserDirList),
+            66 => Some(PacketTypeOut::RcFileBrowserDir),
+            67 => Some(PacketTypeOut::RcFileBrowserMessage),
             //=== File Transfer (68-69, 100-103) ===//
             68 => Some(PacketTypeOut::LargeFileStart),
             69 => Some(PacketTypeOut::LargeFileEnd),
+            74 => Some(PacketTypeOut::RcChat),
+            79 => Some(PacketTypeOut::NpcServerAddr),
             82 => Some(PacketTypeOut::ServerText),
+            84 => Some(PacketTypeOut::LargeFileSize),
             100 => Some(PacketTypeOut::RawData),
             101 => Some(PacketTypeOut::BoardPacket),
             102 => Some(PacketTypeOut::File),
@@ -1024,6 +1068,9 @@ impl PacketTypeOut {
             189 => Some(PacketTypeOut::Move2),
             191 => Some(PacketTypeOut::Shoot2),
             194 => Some(PacketTypeOut::ClearWeapons),
+            //=== Update Packages (196-197) ===//
+            196 => Some(PacketTypeOut::UpdatePackageSize),
+            197 => Some(PacketTypeOut::UpdatePackageDone),
             //=== Unknown ===//
             _ => None,
         }
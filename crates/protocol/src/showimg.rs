@@ -185,6 +185,15 @@ impl ShowImg {
     pub fn color_rgb(&self) -> (u8, u8, u8) {
         (self.red, self.green, self.blue)
     }
+
+    /// Encode as PLO_SHOWIMG text (sent by `build_showimg`)
+    ///
+    /// An empty image name removes the overlay.
+    pub fn to_packet_text(&self) -> String {
+        format!("{},{},{},{},{},{},{},{},{},{}",
+            self.index, self.x, self.y, self.image, self.red, self.green, self.blue,
+            self.zoom, self.mode, self.params)
+    }
 }
 
 /// Collection of showimg overlays
@@ -3,8 +3,10 @@
 //! Provides 200+ built-in functions for game logic.
 
 use crate::{Result, ScriptError};
-use crate::context::ScriptContext;
+use crate::context::{NpcControlHandler, PlayerControlHandler, Projectile, ScriptContext};
+use gserver_core::PlayerID;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 /// Built-in function registry
 pub struct Builtins {
@@ -25,6 +27,9 @@ impl Builtins {
         register_string_functions(&mut functions);
         register_level_functions(&mut functions);
         register_weapon_functions(&mut functions);
+        register_irc_functions(&mut functions);
+        register_ambience_functions(&mut functions);
+        register_moderation_functions(&mut functions);
         
         Self { functions }
     }
@@ -35,6 +40,11 @@ impl Builtins {
             .ok_or_else(|| ScriptError::InvalidFunctionCall(name.to_string()))?
             (ctx, args)
     }
+
+    /// Get the names of the built-in functions
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.functions.keys().map(String::as_str)
+    }
 }
 
 impl Default for Builtins {
@@ -56,11 +66,19 @@ fn register_player_functions(map: &mut HashMap<String, BuiltinFn>) {
     map.insert("gethp".to_string(), builtin_get_hp);
     map.insert("setap".to_string(), builtin_set_ap);
     map.insert("getap".to_string(), builtin_get_ap);
+    map.insert("playerlanguage".to_string(), builtin_player_language);
+
+    // Player control
+    map.insert("pushaway".to_string(), builtin_push_away);
+    map.insert("freezeplayer2".to_string(), builtin_freeze_player2);
+    map.insert("unfreezeplayer".to_string(), builtin_unfreeze_player);
+    map.insert("fullstop".to_string(), builtin_full_stop);
     
     // Player chat
     map.insert("say".to_string(), builtin_say);
     map.insert("message".to_string(), builtin_message);
     map.insert("pm".to_string(), builtin_pm);
+    map.insert("sendrpgmessage".to_string(), builtin_send_rpg_message);
     
     // Player actions
     map.insert("warp".to_string(), builtin_warp);
@@ -78,6 +96,15 @@ fn register_npc_functions(map: &mut HashMap<String, BuiltinFn>) {
     map.insert("hide".to_string(), builtin_hide);
     map.insert("show".to_string(), builtin_show);
     map.insert("destroy".to_string(), builtin_destroy);
+
+    // NPC movement
+    map.insert("putnpc2".to_string(), builtin_put_npc2);
+    map.insert("move".to_string(), builtin_move);
+    map.insert("setdir".to_string(), builtin_set_dir);
+    map.insert("shoot".to_string(), builtin_shoot);
+    map.insert("canbecarried".to_string(), builtin_can_be_carried);
+    map.insert("cannotbecarried".to_string(), builtin_cannot_be_carried);
+    map.insert("setshape".to_string(), builtin_set_shape);
 }
 
 /// Register math functions
@@ -111,7 +138,6 @@ fn register_level_functions(map: &mut HashMap<String, BuiltinFn>) {
     map.insert("levelwidth".to_string(), builtin_level_width);
     map.insert("levelheight".to_string(), builtin_level_height);
     map.insert("putnpc".to_string(), builtin_put_npc);
-    map.insert("putnpc2".to_string(), builtin_put_npc2);
 }
 
 /// Register weapon functions
@@ -177,6 +203,52 @@ fn builtin_get_ap(_ctx: &ScriptContext, _args: &[String]) -> Result<String> {
     Ok("1".to_string()) // Would return actual player ap
 }
 
+fn builtin_player_language(ctx: &ScriptContext, _args: &[String]) -> Result<String> {
+    Ok(ctx.language().unwrap_or("English").to_string())
+}
+
+/// pushaway(dx, dy) - Push the current player by pixels
+fn builtin_push_away(ctx: &ScriptContext, args: &[String]) -> Result<String> {
+    if args.len() < 2 {
+        return Err(ScriptError::InvalidFunctionCall("pushaway requires dx and dy".into()));
+    }
+    let offset = |arg: &String| arg.parse::<f64>()
+        .map(|v| v as i32)
+        .map_err(|_| ScriptError::InvalidFunctionCall(format!("Invalid offset: {}", arg)));
+    let (dx, dy) = (offset(&args[0])?, offset(&args[1])?);
+
+    let (control, player) = player_control(ctx, "pushaway")?;
+    control.push_away(player, dx, dy);
+    Ok(String::new())
+}
+
+/// freezeplayer2() - Stop the current player until unfrozen
+fn builtin_freeze_player2(ctx: &ScriptContext, _args: &[String]) -> Result<String> {
+    let (control, player) = player_control(ctx, "freezeplayer2")?;
+    control.freeze(player);
+    Ok(String::new())
+}
+
+/// unfreezeplayer() - Release the current player
+fn builtin_unfreeze_player(ctx: &ScriptContext, _args: &[String]) -> Result<String> {
+    let (control, player) = player_control(ctx, "unfreezeplayer")?;
+    control.unfreeze(player);
+    Ok(String::new())
+}
+
+/// fullstop() - Stop the current player's input until unfrozen
+fn builtin_full_stop(ctx: &ScriptContext, _args: &[String]) -> Result<String> {
+    let (control, player) = player_control(ctx, "fullstop")?;
+    control.full_stop(player);
+    Ok(String::new())
+}
+
+fn player_control(ctx: &ScriptContext, function: &str) -> Result<(Arc<dyn PlayerControlHandler>, PlayerID)> {
+    let player = ctx.player().ok_or_else(|| ScriptError::RuntimeError(format!("{} needs a player", function)))?;
+    let control = ctx.control().ok_or_else(|| ScriptError::RuntimeError("Player control is not available".into()))?;
+    Ok((control, player))
+}
+
 fn builtin_say(_ctx: &ScriptContext, args: &[String]) -> Result<String> {
     if args.is_empty() {
         return Ok(String::new());
@@ -198,6 +270,19 @@ fn builtin_pm(_ctx: &ScriptContext, args: &[String]) -> Result<String> {
     Ok(args[1].clone())
 }
 
+/// sendrpgmessage(message, [player id]) - Admin message popup, to everyone
+/// unless a player is given
+fn builtin_send_rpg_message(ctx: &ScriptContext, args: &[String]) -> Result<String> {
+    let message = args.first().ok_or_else(|| ScriptError::InvalidFunctionCall("sendrpgmessage requires a message".into()))?;
+    let player = match args.get(1) {
+        Some(id) => Some(PlayerID(id.parse().map_err(|_| ScriptError::InvalidFunctionCall(format!("Invalid player id: {}", id)))?)),
+        None => None,
+    };
+    let handler = ctx.admin_message().ok_or_else(|| ScriptError::RuntimeError("Admin messages are not available".into()))?;
+    handler.send(player, message);
+    Ok(String::new())
+}
+
 fn builtin_warp(_ctx: &ScriptContext, args: &[String]) -> Result<String> {
     if args.is_empty() {
         return Err(ScriptError::InvalidFunctionCall("warp requires level name".into()));
@@ -213,6 +298,106 @@ fn builtin_show_player(_ctx: &ScriptContext, _args: &[String]) -> Result<String>
     Ok("".to_string())
 }
 
+// ============================================================================
+// IRC FUNCTIONS
+// ============================================================================
+
+/// Register listserver IRC functions
+fn register_irc_functions(map: &mut HashMap<String, BuiltinFn>) {
+    map.insert("irc.join".to_string(), builtin_irc_join);
+    map.insert("irc.part".to_string(), builtin_irc_part);
+    map.insert("irc.say".to_string(), builtin_irc_say);
+}
+
+fn irc_handler(ctx: &ScriptContext) -> Result<std::sync::Arc<dyn crate::context::IrcHandler>> {
+    ctx.irc().ok_or_else(|| ScriptError::RuntimeError("IRC is not available".into()))
+}
+
+fn builtin_irc_join(ctx: &ScriptContext, args: &[String]) -> Result<String> {
+    let channel = args.first().ok_or_else(|| ScriptError::InvalidFunctionCall("irc.join requires a channel".into()))?;
+    irc_handler(ctx)?.join(channel);
+    Ok(String::new())
+}
+
+fn builtin_irc_part(ctx: &ScriptContext, args: &[String]) -> Result<String> {
+    let channel = args.first().ok_or_else(|| ScriptError::InvalidFunctionCall("irc.part requires a channel".into()))?;
+    irc_handler(ctx)?.part(channel);
+    Ok(String::new())
+}
+
+fn builtin_irc_say(ctx: &ScriptContext, args: &[String]) -> Result<String> {
+    if args.len() < 2 {
+        return Err(ScriptError::InvalidFunctionCall("irc.say requires channel and message".into()));
+    }
+    irc_handler(ctx)?.say(&args[0], &args[1]);
+    Ok(String::new())
+}
+
+// ============================================================================
+// AMBIENCE FUNCTIONS
+// ============================================================================
+
+/// Register weather and tint functions
+fn register_ambience_functions(map: &mut HashMap<String, BuiltinFn>) {
+    map.insert("ambience.set".to_string(), builtin_ambience_set);
+    map.insert("ambience.clear".to_string(), builtin_ambience_clear);
+}
+
+fn set_ambience(ctx: &ScriptContext, effect: &str, intensity: Option<u8>, rest: &[String]) -> Result<String> {
+    let handler = ctx.ambience().ok_or_else(|| ScriptError::RuntimeError("Ambience is not available".into()))?;
+    let scope = rest.first().map(String::as_str).unwrap_or("");
+    let delay = rest.get(1).and_then(|d| d.parse::<f64>().ok()).unwrap_or(0.0);
+    handler.set(effect, intensity, scope, delay).map_err(ScriptError::RuntimeError)?;
+    Ok(String::new())
+}
+
+/// ambience.set(effect, intensity, [scope], [delay])
+fn builtin_ambience_set(ctx: &ScriptContext, args: &[String]) -> Result<String> {
+    if args.len() < 2 {
+        return Err(ScriptError::InvalidFunctionCall("ambience.set requires effect and intensity".into()));
+    }
+    let intensity = args[1].parse::<f64>()
+        .map_err(|_| ScriptError::InvalidFunctionCall(format!("Invalid intensity: {}", args[1])))?
+        .clamp(0.0, 100.0) as u8;
+    set_ambience(ctx, &args[0], Some(intensity), &args[2..])
+}
+
+/// ambience.clear(effect, [scope], [delay])
+fn builtin_ambience_clear(ctx: &ScriptContext, args: &[String]) -> Result<String> {
+    let effect = args.first().ok_or_else(|| ScriptError::InvalidFunctionCall("ambience.clear requires an effect".into()))?;
+    set_ambience(ctx, effect, None, &args[1..])
+}
+
+// ============================================================================
+// MODERATION FUNCTIONS
+// ============================================================================
+
+/// Register account sanction functions
+fn register_moderation_functions(map: &mut HashMap<String, BuiltinFn>) {
+    map.insert("moderation.mute".to_string(), |ctx, args| moderate(ctx, "mute", args));
+    map.insert("moderation.unmute".to_string(), |ctx, args| moderate(ctx, "unmute", args));
+    map.insert("moderation.jail".to_string(), |ctx, args| moderate(ctx, "jail", args));
+    map.insert("moderation.unjail".to_string(), |ctx, args| moderate(ctx, "unjail", args));
+    map.insert("moderation.warphome".to_string(), |ctx, args| moderate(ctx, "warphome", args));
+    map.insert("moderation.freeze".to_string(), |ctx, args| moderate(ctx, "freeze", args));
+    map.insert("moderation.unfreeze".to_string(), |ctx, args| moderate(ctx, "unfreeze", args));
+}
+
+/// moderation.<action>(account, [minutes])
+fn moderate(ctx: &ScriptContext, action: &str, args: &[String]) -> Result<String> {
+    let account = args.first()
+        .ok_or_else(|| ScriptError::InvalidFunctionCall(format!("moderation.{} requires an account", action)))?;
+    let minutes = match args.get(1) {
+        Some(m) => Some(m.parse::<f64>()
+            .map_err(|_| ScriptError::InvalidFunctionCall(format!("Invalid minutes: {}", m)))?
+            .max(1.0) as u64),
+        None => None,
+    };
+    let handler = ctx.moderation().ok_or_else(|| ScriptError::RuntimeError("Moderation is not available".into()))?;
+    handler.moderate(account, action, minutes).map_err(ScriptError::RuntimeError)?;
+    Ok(String::new())
+}
+
 // ============================================================================
 // NPC FUNCTIONS
 // ============================================================================
@@ -251,6 +436,108 @@ fn builtin_destroy(_ctx: &ScriptContext, _args: &[String]) -> Result<String> {
     Ok("".to_string())
 }
 
+/// putnpc2(x, y, script) - Put a new NPC on the script's level, returns its id
+fn builtin_put_npc2(ctx: &ScriptContext, args: &[String]) -> Result<String> {
+    if args.len() < 3 {
+        return Err(ScriptError::InvalidFunctionCall("putnpc2 requires x, y, and script".into()));
+    }
+    let (x, y) = (finite(&args[0])?, finite(&args[1])?);
+    let level = ctx.level().ok_or_else(|| ScriptError::RuntimeError("putnpc2 needs a level".into()))?;
+    let control = ctx.npc_control().ok_or_else(|| ScriptError::RuntimeError("NPC movement is not available".into()))?;
+    Ok(control.put_npc(level, x, y, &args[2..].join(",")).to_string())
+}
+
+/// move(dx, dy, [seconds], [options]) - Move the NPC by tiles
+fn builtin_move(ctx: &ScriptContext, args: &[String]) -> Result<String> {
+    if args.len() < 2 {
+        return Err(ScriptError::InvalidFunctionCall("move requires dx and dy".into()));
+    }
+    let (dx, dy) = (finite(&args[0])?, finite(&args[1])?);
+    let seconds = args.get(2).map(|s| finite(s)).transpose()?.unwrap_or(0.0).max(0.0);
+    let options = args.get(3).map(|s| number(s)).transpose()?.unwrap_or(0.0) as u32;
+
+    let (control, npc) = npc_control(ctx, "move")?;
+    control.move_by(npc, dx, dy, seconds, options);
+    Ok(String::new())
+}
+
+/// setdir(dir) - Turn the NPC (0 up, 1 left, 2 down, 3 right)
+fn builtin_set_dir(ctx: &ScriptContext, args: &[String]) -> Result<String> {
+    let dir = args.first()
+        .ok_or_else(|| ScriptError::InvalidFunctionCall("setdir requires a direction".into()))?;
+    let dir = number(dir)? as i64;
+
+    let (control, npc) = npc_control(ctx, "setdir")?;
+    control.set_dir(npc, dir.rem_euclid(4) as u8);
+    Ok(String::new())
+}
+
+/// shoot(x, y, z, angle, zangle, power, gani, [params]) - Fire a projectile
+/// from the NPC
+fn builtin_shoot(ctx: &ScriptContext, args: &[String]) -> Result<String> {
+    if args.len() < 7 {
+        return Err(ScriptError::InvalidFunctionCall("shoot requires x, y, z, angle, zangle, power and gani".into()));
+    }
+    let projectile = Projectile {
+        x: number(&args[0])?,
+        y: number(&args[1])?,
+        z: number(&args[2])?,
+        angle: number(&args[3])?,
+        z_angle: number(&args[4])?,
+        power: number(&args[5])?,
+        gani: args[6].clone(),
+        params: args[7..].join(","),
+    };
+
+    let (control, npc) = npc_control(ctx, "shoot")?;
+    control.shoot(npc, projectile);
+    Ok(String::new())
+}
+
+/// canbecarried - Let players lift the NPC
+fn builtin_can_be_carried(ctx: &ScriptContext, _args: &[String]) -> Result<String> {
+    let (control, npc) = npc_control(ctx, "canbecarried")?;
+    control.set_carryable(npc, true);
+    Ok(String::new())
+}
+
+/// cannotbecarried - Stop players lifting the NPC
+fn builtin_cannot_be_carried(ctx: &ScriptContext, _args: &[String]) -> Result<String> {
+    let (control, npc) = npc_control(ctx, "cannotbecarried")?;
+    control.set_carryable(npc, false);
+    Ok(String::new())
+}
+
+/// setshape(type, width, height) - Set the NPC's shape (type 1 blocks
+/// players, any other type clears it)
+fn builtin_set_shape(ctx: &ScriptContext, args: &[String]) -> Result<String> {
+    if args.len() < 3 {
+        return Err(ScriptError::InvalidFunctionCall("setshape requires type, width and height".into()));
+    }
+    let kind = number(&args[0])? as i64;
+    let size = (number(&args[1])?.clamp(0.0, u16::MAX as f64) as u16, number(&args[2])?.clamp(0.0, u16::MAX as f64) as u16);
+
+    let (control, npc) = npc_control(ctx, "setshape")?;
+    control.set_shape(npc, (kind == 1).then_some(size));
+    Ok(String::new())
+}
+
+fn npc_control(ctx: &ScriptContext, function: &str) -> Result<(Arc<dyn NpcControlHandler>, u32)> {
+    let npc = ctx.npc().ok_or_else(|| ScriptError::RuntimeError(format!("{} needs an NPC", function)))?;
+    let control = ctx.npc_control().ok_or_else(|| ScriptError::RuntimeError("NPC movement is not available".into()))?;
+    Ok((control, npc))
+}
+
+fn number(arg: &str) -> Result<f64> {
+    arg.trim().parse().map_err(|_| ScriptError::InvalidFunctionCall(format!("Invalid number: {}", arg)))
+}
+
+/// Parse a number that isn't infinite or NaN
+fn finite(arg: &str) -> Result<f64> {
+    Some(number(arg)?).filter(|n| n.is_finite())
+        .ok_or_else(|| ScriptError::InvalidFunctionCall(format!("Invalid number: {}", arg)))
+}
+
 // ============================================================================
 // MATH FUNCTIONS
 // ============================================================================
@@ -458,13 +745,6 @@ fn builtin_put_npc(_ctx: &ScriptContext, args: &[String]) -> Result<String> {
     Ok("".to_string())
 }
 
-fn builtin_put_npc2(_ctx: &ScriptContext, args: &[String]) -> Result<String> {
-    if args.len() < 3 {
-        return Err(ScriptError::InvalidFunctionCall("putnpc2 requires x, y, and script".into()));
-    }
-    Ok("".to_string())
-}
-
 // ============================================================================
 // WEAPON FUNCTIONS
 // ============================================================================
@@ -508,4 +788,209 @@ mod tests {
         assert_eq!(builtins.call(&ctx, "strlower", &["HELLO".into()]).unwrap(), "hello");
         assert_eq!(builtins.call(&ctx, "strupper", &["hello".into()]).unwrap(), "HELLO");
     }
+    
+    #[test]
+    fn test_player_language() {
+        let builtins = Builtins::new();
+        let mut ctx = ScriptContext::new();
+        
+        assert_eq!(builtins.call(&ctx, "playerlanguage", &[]).unwrap(), "English");
+        ctx.set_language("Deutsch".into());
+        assert_eq!(builtins.call(&ctx, "playerlanguage", &[]).unwrap(), "Deutsch");
+    }
+    
+    #[derive(Debug, Default)]
+    struct RecordingIrc(std::sync::Mutex<Vec<String>>);
+    
+    impl crate::context::IrcHandler for RecordingIrc {
+        fn join(&self, channel: &str) {
+            self.0.lock().unwrap().push(format!("join {}", channel));
+        }
+        fn part(&self, channel: &str) {
+            self.0.lock().unwrap().push(format!("part {}", channel));
+        }
+        fn say(&self, channel: &str, message: &str) {
+            self.0.lock().unwrap().push(format!("say {} {}", channel, message));
+        }
+    }
+    
+    #[test]
+    fn test_irc_functions() {
+        let builtins = Builtins::new();
+        let ctx = ScriptContext::new();
+        assert!(builtins.call(&ctx, "irc.join", &["#graal".into()]).is_err());
+        
+        let irc = std::sync::Arc::new(RecordingIrc::default());
+        ctx.clone().set_irc_handler(irc.clone());
+        builtins.call(&ctx, "irc.join", &["#graal".into()]).unwrap();
+        builtins.call(&ctx, "irc.say", &["#graal".into(), "hello".into()]).unwrap();
+        builtins.call(&ctx, "irc.part", &["#graal".into()]).unwrap();
+        assert_eq!(*irc.0.lock().unwrap(), ["join #graal", "say #graal hello", "part #graal"]);
+    }
+    
+    #[derive(Debug, Default)]
+    struct RecordingControl(std::sync::Mutex<Vec<String>>);
+    
+    impl crate::context::PlayerControlHandler for RecordingControl {
+        fn push_away(&self, player: gserver_core::PlayerID, dx: i32, dy: i32) {
+            self.0.lock().unwrap().push(format!("push {} {} {}", player.get(), dx, dy));
+        }
+        fn freeze(&self, player: gserver_core::PlayerID) {
+            self.0.lock().unwrap().push(format!("freeze {}", player.get()));
+        }
+        fn full_stop(&self, player: gserver_core::PlayerID) {
+            self.0.lock().unwrap().push(format!("fullstop {}", player.get()));
+        }
+        fn unfreeze(&self, player: gserver_core::PlayerID) {
+            self.0.lock().unwrap().push(format!("unfreeze {}", player.get()));
+        }
+    }
+    
+    #[test]
+    fn test_player_control_functions() {
+        let builtins = Builtins::new();
+        let mut ctx = ScriptContext::new();
+        let control = std::sync::Arc::new(RecordingControl::default());
+        ctx.set_control_handler(control.clone());
+        assert!(builtins.call(&ctx, "pushaway", &["8".into(), "0".into()]).is_err());
+        
+        ctx.set_player(gserver_core::PlayerID(3));
+        builtins.call(&ctx, "pushaway", &["-16".into(), "4.5".into()]).unwrap();
+        builtins.call(&ctx, "freezeplayer2", &[]).unwrap();
+        builtins.call(&ctx, "unfreezeplayer", &[]).unwrap();
+        assert_eq!(*control.0.lock().unwrap(), ["push 3 -16 4", "freeze 3", "unfreeze 3"]);
+    }
+    
+    #[derive(Debug, Default)]
+    struct RecordingNpcControl(std::sync::Mutex<Vec<String>>);
+    
+    impl crate::context::NpcControlHandler for RecordingNpcControl {
+        fn put_npc(&self, level: &str, x: f64, y: f64, script: &str) -> u32 {
+            self.0.lock().unwrap().push(format!("put {} {} {} {}", level, x, y, script));
+            12
+        }
+        fn move_by(&self, npc: u32, dx: f64, dy: f64, seconds: f64, options: u32) {
+            self.0.lock().unwrap().push(format!("move {} {} {} {} {}", npc, dx, dy, seconds, options));
+        }
+        fn set_dir(&self, npc: u32, dir: u8) {
+            self.0.lock().unwrap().push(format!("dir {} {}", npc, dir));
+        }
+        fn shoot(&self, npc: u32, projectile: Projectile) {
+            self.0.lock().unwrap().push(format!("shoot {} {} {} {}", npc, projectile.angle, projectile.gani, projectile.params));
+        }
+        fn set_carryable(&self, npc: u32, carryable: bool) {
+            self.0.lock().unwrap().push(format!("carryable {} {}", npc, carryable));
+        }
+        fn set_shape(&self, npc: u32, shape: Option<(u16, u16)>) {
+            self.0.lock().unwrap().push(format!("shape {} {:?}", npc, shape));
+        }
+    }
+    
+    #[test]
+    fn test_npc_movement_functions() {
+        let builtins = Builtins::new();
+        let mut ctx = ScriptContext::new();
+        let control = std::sync::Arc::new(RecordingNpcControl::default());
+        ctx.set_npc_control_handler(control.clone());
+        assert!(builtins.call(&ctx, "move", &["1".into(), "0".into()]).is_err());
+        
+        ctx.set_npc(7);
+        builtins.call(&ctx, "move", &["2".into(), "-1.5".into(), "0.5".into(), "24".into()]).unwrap();
+        builtins.call(&ctx, "move", &["1".into(), "0".into()]).unwrap();
+        builtins.call(&ctx, "setdir", &["-1".into()]).unwrap();
+        let shot: Vec<String> = ["30", "30", "0", "1.5", "0", "1", "arrow", "a", "b"].map(String::from).into();
+        builtins.call(&ctx, "shoot", &shot).unwrap();
+        assert!(builtins.call(&ctx, "shoot", &shot[..6]).is_err());
+        assert!(builtins.call(&ctx, "setdir", &["up".into()]).is_err());
+        assert!(builtins.call(&ctx, "move", &["1".into(), "0".into(), "inf".into()]).is_err());
+        assert!(builtins.call(&ctx, "move", &["NaN".into(), "0".into()]).is_err());
+        assert_eq!(*control.0.lock().unwrap(), ["move 7 2 -1.5 0.5 24", "move 7 1 0 0 0", "dir 7 3", "shoot 7 1.5 arrow a,b"]);
+    }
+    
+    #[test]
+    fn test_put_npc2() {
+        let builtins = Builtins::new();
+        let mut ctx = ScriptContext::new();
+        let control = std::sync::Arc::new(RecordingNpcControl::default());
+        ctx.set_npc_control_handler(control.clone());
+        assert!(builtins.call(&ctx, "putnpc2", &["1".into(), "2".into(), "x".into()]).is_err());
+        
+        ctx.set_level("yard.nw".into());
+        let id = builtins.call(&ctx, "putnpc2", &["1.5".into(), "2".into(), "this.a = 1".into()]).unwrap();
+        assert_eq!(id, "12");
+        assert_eq!(*control.0.lock().unwrap(), ["put yard.nw 1.5 2 this.a = 1"]);
+    }
+    
+    #[test]
+    fn test_npc_carry_flag_functions() {
+        let builtins = Builtins::new();
+        let mut ctx = ScriptContext::new();
+        let control = std::sync::Arc::new(RecordingNpcControl::default());
+        ctx.set_npc_control_handler(control.clone());
+        assert!(builtins.call(&ctx, "canbecarried", &[]).is_err());
+        
+        ctx.set_npc(4);
+        builtins.call(&ctx, "canbecarried", &[]).unwrap();
+        builtins.call(&ctx, "cannotbecarried", &[]).unwrap();
+        assert_eq!(*control.0.lock().unwrap(), ["carryable 4 true", "carryable 4 false"]);
+    }
+    
+    #[test]
+    fn test_npc_setshape_function() {
+        let builtins = Builtins::new();
+        let mut ctx = ScriptContext::new();
+        let control = std::sync::Arc::new(RecordingNpcControl::default());
+        ctx.set_npc_control_handler(control.clone());
+        ctx.set_npc(4);
+        builtins.call(&ctx, "setshape", &["1".into(), "48".into(), "16".into()]).unwrap();
+        builtins.call(&ctx, "setshape", &["0".into(), "48".into(), "16".into()]).unwrap();
+        assert!(builtins.call(&ctx, "setshape", &["1".into(), "48".into()]).is_err());
+        assert_eq!(*control.0.lock().unwrap(), ["shape 4 Some((48, 16))", "shape 4 None"]);
+    }
+    
+    #[derive(Debug, Default)]
+    struct RecordingAdminMessages(std::sync::Mutex<Vec<String>>);
+    
+    impl crate::context::AdminMessageHandler for RecordingAdminMessages {
+        fn send(&self, player: Option<gserver_core::PlayerID>, message: &str) {
+            self.0.lock().unwrap().push(format!("{:?} {}", player.map(|p| p.get()), message));
+        }
+    }
+    
+    #[test]
+    fn test_send_rpg_message() {
+        let builtins = Builtins::new();
+        let ctx = ScriptContext::new();
+        assert!(builtins.call(&ctx, "sendrpgmessage", &["hi".into()]).is_err());
+        
+        let messages = std::sync::Arc::new(RecordingAdminMessages::default());
+        ctx.set_admin_message_handler(messages.clone());
+        builtins.call(&ctx, "sendrpgmessage", &["Event starts now".into()]).unwrap();
+        builtins.call(&ctx, "sendrpgmessage", &["Welcome".into(), "4".into()]).unwrap();
+        assert!(builtins.call(&ctx, "sendrpgmessage", &["x".into(), "bob".into()]).is_err());
+        assert_eq!(*messages.0.lock().unwrap(), ["None Event starts now", "Some(4) Welcome"]);
+    }
+    
+    #[derive(Debug, Default)]
+    struct RecordingModeration(std::sync::Mutex<Vec<String>>);
+    
+    impl crate::context::ModerationHandler for RecordingModeration {
+        fn moderate(&self, account: &str, action: &str, minutes: Option<u64>) -> std::result::Result<(), String> {
+            self.0.lock().unwrap().push(format!("{} {} {:?}", action, account, minutes));
+            Ok(())
+        }
+    }
+    
+    #[test]
+    fn test_moderation_functions() {
+        let builtins = Builtins::new();
+        let ctx = ScriptContext::new();
+        let moderation = std::sync::Arc::new(RecordingModeration::default());
+        ctx.set_moderation_handler(moderation.clone());
+        builtins.call(&ctx, "moderation.mute", &["Bob".into(), "10".into()]).unwrap();
+        builtins.call(&ctx, "moderation.warphome", &["Bob".into()]).unwrap();
+        assert!(builtins.call(&ctx, "moderation.jail", &[]).is_err());
+        assert!(builtins.call(&ctx, "moderation.jail", &["Bob".into(), "soon".into()]).is_err());
+        assert_eq!(*moderation.0.lock().unwrap(), ["mute Bob Some(10)", "warphome Bob None"]);
+    }
 }
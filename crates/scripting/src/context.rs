@@ -7,6 +7,177 @@ use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 use gserver_core::PlayerID;
 
+/// Receiver of the script IRC API (`irc.join`, `irc.part`, `irc.say`)
+///
+/// Installed by the server, which relays the calls to the listserver IRC.
+pub trait IrcHandler: Send + Sync + std::fmt::Debug {
+    /// Join a channel
+    fn join(&self, channel: &str);
+
+    /// Leave a channel
+    fn part(&self, channel: &str);
+
+    /// Say something in a channel
+    fn say(&self, channel: &str, message: &str);
+}
+
+/// Receiver of the script ambience API (`ambience.set`, `ambience.clear`)
+pub trait AmbienceHandler: Send + Sync + std::fmt::Debug {
+    /// Change a weather or tint effect
+    ///
+    /// # Arguments
+    /// * `effect` - Effect name (`rain`, `snow`, `night`)
+    /// * `intensity` - 0-100, `None` turns the effect off
+    /// * `scope` - `all` (or empty), a `.gmap` or a level name
+    /// * `delay` - Seconds until the change (0 for now)
+    fn set(&self, effect: &str, intensity: Option<u8>, scope: &str, delay: f64) -> std::result::Result<(), String>;
+}
+
+/// Receiver of the script player control API (`pushaway`, `freezeplayer2`,
+/// `unfreezeplayer`, `fullstop`)
+pub trait PlayerControlHandler: Send + Sync + std::fmt::Debug {
+    /// Push a player by an offset in pixels
+    fn push_away(&self, player: PlayerID, dx: i32, dy: i32);
+
+    /// Stop a player's movement until unfrozen
+    fn freeze(&self, player: PlayerID);
+
+    /// Stop a player's input and scripts until unfrozen
+    fn full_stop(&self, player: PlayerID);
+
+    /// Release a freeze or fullstop
+    fn unfreeze(&self, player: PlayerID);
+}
+
+/// A projectile fired by `shoot`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Projectile {
+    /// Start position (tiles)
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+
+    /// Direction (radians, 0 is right, counterclockwise)
+    pub angle: f64,
+
+    /// Upward angle (radians)
+    pub z_angle: f64,
+
+    /// Speed
+    pub power: f64,
+
+    /// Animation of the projectile
+    pub gani: String,
+
+    /// Parameters passed to the animation
+    pub params: String,
+}
+
+/// Receiver of the serverside NPC API (`putnpc2`, `move`, `setdir`, `shoot`,
+/// `canbecarried`, `setshape`)
+///
+/// Installed by the server, which moves the NPC and tells the players on
+/// its level.
+pub trait NpcControlHandler: Send + Sync + std::fmt::Debug {
+    /// Put a new NPC on a level and run its script
+    ///
+    /// # Arguments
+    /// * `x`, `y` - Position (tiles)
+    ///
+    /// # Returns
+    /// The id of the new NPC
+    fn put_npc(&self, level: &str, x: f64, y: f64, script: &str) -> u32;
+
+    /// Move an NPC by an offset in tiles
+    ///
+    /// # Arguments
+    /// * `dx`, `dy` - Offset (tiles)
+    /// * `seconds` - Length of the movement (0 jumps there)
+    /// * `options` - GS1 `move` options (2 = turn, 8 = stop at walls,
+    ///   16 = run `movementfinished`)
+    fn move_by(&self, npc: u32, dx: f64, dy: f64, seconds: f64, options: u32);
+
+    /// Turn an NPC (0 up, 1 left, 2 down, 3 right)
+    fn set_dir(&self, npc: u32, dir: u8);
+
+    /// Fire a projectile from an NPC
+    fn shoot(&self, npc: u32, projectile: Projectile);
+
+    /// Let players lift an NPC or not (`canbecarried` / `cannotbecarried`)
+    fn set_carryable(&self, npc: u32, carryable: bool);
+
+    /// Set the blocking shape of an NPC (width, height in pixels), or none
+    fn set_shape(&self, npc: u32, shape: Option<(u16, u16)>);
+}
+
+/// Storage of serverside NPC variables (`this.*`)
+///
+/// Installed by the server, which keeps them across restarts. The NPC is
+/// named like its script (see [`crate::npc_script_name`]).
+pub trait NpcStateHandler: Send + Sync + std::fmt::Debug {
+    /// Get a variable (name without `this.`)
+    fn get_var(&self, npc: &str, name: &str) -> Option<String>;
+
+    /// Set a variable (an empty value removes it)
+    fn set_var(&self, npc: &str, name: &str, value: &str);
+
+    /// Get every stored variable of an NPC, which its runs start with
+    fn vars(&self, npc: &str) -> Vec<(String, String)>;
+}
+
+/// Receiver of the script admin message API (`sendrpgmessage`)
+pub trait AdminMessageHandler: Send + Sync + std::fmt::Debug {
+    /// Show an admin message popup to one player, or to everyone with `None`
+    fn send(&self, player: Option<PlayerID>, message: &str);
+}
+
+/// Receiver of the script moderation API (`moderation.mute`, `moderation.jail`, ...)
+pub trait ModerationHandler: Send + Sync + std::fmt::Debug {
+    /// Sanction an account
+    ///
+    /// # Arguments
+    /// * `account` - Account name
+    /// * `action` - `mute`, `unmute`, `jail`, `unjail`, `warphome`, `freeze` or `unfreeze`
+    /// * `minutes` - Length of a mute or jail, `None` until lifted
+    fn moderate(&self, account: &str, action: &str, minutes: Option<u64>) -> std::result::Result<(), String>;
+}
+
+/// A script that failed to compile or run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptErrorReport {
+    /// Owner name (weapon, class, `npc{id}`)
+    pub script: String,
+
+    /// Source line, if known
+    pub line: Option<usize>,
+
+    /// Player the script ran for (if any)
+    pub player: Option<PlayerID>,
+
+    pub message: String,
+}
+
+impl std::fmt::Display for ScriptErrorReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.script)?;
+        if let Some(line) = self.line {
+            write!(f, " line {}", line)?;
+        }
+        if let Some(player) = self.player {
+            write!(f, " (player {})", player.get())?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Receiver of script compile and runtime errors
+///
+/// Installed by the server, which logs them and shows them to staff.
+pub trait ScriptErrorHandler: Send + Sync + std::fmt::Debug {
+    /// Report a failed compile or run
+    fn report(&self, error: &ScriptErrorReport);
+}
+
 /// Script execution context
 #[derive(Debug, Clone)]
 pub struct ScriptContext {
@@ -18,6 +189,36 @@ pub struct ScriptContext {
     
     /// Current level (if any)
     level: Option<String>,
+    
+    /// Language of the current player (if any)
+    language: Option<String>,
+
+    /// NPC whose script runs (if any)
+    npc: Option<u32>,
+
+    /// IRC relay (shared by all clones, unset until the server installs one)
+    irc: Arc<RwLock<Option<Arc<dyn IrcHandler>>>>,
+
+    /// Weather and tint control (shared like `irc`)
+    ambience: Arc<RwLock<Option<Arc<dyn AmbienceHandler>>>>,
+
+    /// Player movement control (shared like `irc`)
+    control: Arc<RwLock<Option<Arc<dyn PlayerControlHandler>>>>,
+
+    /// NPC variable storage (shared like `irc`)
+    npc_state: Arc<RwLock<Option<Arc<dyn NpcStateHandler>>>>,
+
+    /// Admin message popups (shared like `irc`)
+    admin_message: Arc<RwLock<Option<Arc<dyn AdminMessageHandler>>>>,
+
+    /// Account sanctions (shared like `irc`)
+    moderation: Arc<RwLock<Option<Arc<dyn ModerationHandler>>>>,
+
+    /// NPC movement (shared like `irc`)
+    npc_control: Arc<RwLock<Option<Arc<dyn NpcControlHandler>>>>,
+
+    /// Script error reports (shared like `irc`)
+    script_errors: Arc<RwLock<Option<Arc<dyn ScriptErrorHandler>>>>,
 }
 
 impl ScriptContext {
@@ -27,6 +228,16 @@ impl ScriptContext {
             globals: Arc::new(RwLock::new(HashMap::new())),
             player: None,
             level: None,
+            language: None,
+            npc: None,
+            irc: Arc::new(RwLock::new(None)),
+            ambience: Arc::new(RwLock::new(None)),
+            control: Arc::new(RwLock::new(None)),
+            npc_state: Arc::new(RwLock::new(None)),
+            admin_message: Arc::new(RwLock::new(None)),
+            moderation: Arc::new(RwLock::new(None)),
+            npc_control: Arc::new(RwLock::new(None)),
+            script_errors: Arc::new(RwLock::new(None)),
         }
     }
     
@@ -61,6 +272,122 @@ impl ScriptContext {
     pub fn set_level(&mut self, level: String) {
         self.level = Some(level);
     }
+    
+    /// Get the current player's language
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+    
+    /// Set the current player's language (from PLI_LANGUAGE)
+    pub fn set_language(&mut self, language: String) {
+        self.language = Some(language);
+    }
+
+    /// Get the NPC whose script runs
+    pub fn npc(&self) -> Option<u32> {
+        self.npc
+    }
+
+    /// Set the NPC whose script runs
+    pub fn set_npc(&mut self, npc: u32) {
+        self.npc = Some(npc);
+    }
+
+    /// Install the IRC relay used by the `irc.*` builtins
+    pub fn set_irc_handler(&self, handler: Arc<dyn IrcHandler>) {
+        if let Ok(mut irc) = self.irc.write() {
+            *irc = Some(handler);
+        }
+    }
+
+    /// Get the IRC relay, if one is installed
+    pub fn irc(&self) -> Option<Arc<dyn IrcHandler>> {
+        self.irc.read().ok()?.clone()
+    }
+
+    /// Install the ambience control used by the `ambience.*` builtins
+    pub fn set_ambience_handler(&self, handler: Arc<dyn AmbienceHandler>) {
+        if let Ok(mut ambience) = self.ambience.write() {
+            *ambience = Some(handler);
+        }
+    }
+
+    /// Get the ambience control, if one is installed
+    pub fn ambience(&self) -> Option<Arc<dyn AmbienceHandler>> {
+        self.ambience.read().ok()?.clone()
+    }
+
+    /// Install the player control used by `pushaway` and the freeze builtins
+    pub fn set_control_handler(&self, handler: Arc<dyn PlayerControlHandler>) {
+        if let Ok(mut control) = self.control.write() {
+            *control = Some(handler);
+        }
+    }
+
+    /// Get the player control, if one is installed
+    pub fn control(&self) -> Option<Arc<dyn PlayerControlHandler>> {
+        self.control.read().ok()?.clone()
+    }
+
+    /// Install the storage used for `this.*` variables of NPC scripts
+    pub fn set_npc_state_handler(&self, handler: Arc<dyn NpcStateHandler>) {
+        if let Ok(mut npc_state) = self.npc_state.write() {
+            *npc_state = Some(handler);
+        }
+    }
+
+    /// Get the NPC variable storage, if one is installed
+    pub fn npc_state(&self) -> Option<Arc<dyn NpcStateHandler>> {
+        self.npc_state.read().ok()?.clone()
+    }
+
+    /// Install the popup sender used by `sendrpgmessage`
+    pub fn set_admin_message_handler(&self, handler: Arc<dyn AdminMessageHandler>) {
+        if let Ok(mut admin_message) = self.admin_message.write() {
+            *admin_message = Some(handler);
+        }
+    }
+
+    /// Get the popup sender, if one is installed
+    pub fn admin_message(&self) -> Option<Arc<dyn AdminMessageHandler>> {
+        self.admin_message.read().ok()?.clone()
+    }
+
+    /// Install the sanction handler used by the `moderation.*` builtins
+    pub fn set_moderation_handler(&self, handler: Arc<dyn ModerationHandler>) {
+        if let Ok(mut moderation) = self.moderation.write() {
+            *moderation = Some(handler);
+        }
+    }
+
+    /// Get the sanction handler, if one is installed
+    pub fn moderation(&self) -> Option<Arc<dyn ModerationHandler>> {
+        self.moderation.read().ok()?.clone()
+    }
+
+    /// Install the NPC movement used by `move`, `setdir` and `shoot`
+    pub fn set_npc_control_handler(&self, handler: Arc<dyn NpcControlHandler>) {
+        if let Ok(mut npc_control) = self.npc_control.write() {
+            *npc_control = Some(handler);
+        }
+    }
+
+    /// Get the NPC movement, if one is installed
+    pub fn npc_control(&self) -> Option<Arc<dyn NpcControlHandler>> {
+        self.npc_control.read().ok()?.clone()
+    }
+
+    /// Install the receiver of script errors
+    pub fn set_script_error_handler(&self, handler: Arc<dyn ScriptErrorHandler>) {
+        if let Ok(mut script_errors) = self.script_errors.write() {
+            *script_errors = Some(handler);
+        }
+    }
+
+    /// Get the receiver of script errors, if one is installed
+    pub fn script_errors(&self) -> Option<Arc<dyn ScriptErrorHandler>> {
+        self.script_errors.read().ok()?.clone()
+    }
 }
 
 impl Default for ScriptContext {
@@ -9,6 +9,10 @@ pub enum ScriptError {
     #[error("Parse error at line {line}: {message}")]
     ParseError { line: usize, message: String },
 
+    /// GS2 syntax error, with the tokens that would have been accepted
+    #[error("Syntax error at line {line}, column {column}: {message}")]
+    SyntaxError { line: usize, column: usize, message: String, expected: Vec<String> },
+
     /// Runtime error
     #[error("Runtime error: {0}")]
     RuntimeError(String),
@@ -28,6 +32,10 @@ pub enum ScriptError {
     /// Timeout
     #[error("Script timeout")]
     Timeout,
+
+    /// The script's values outgrew its memory quota
+    #[error("Script memory limit exceeded: {used} of {limit} bytes")]
+    MemoryLimit { used: usize, limit: usize },
 }
 
 impl From<ScriptError> for GServerError {
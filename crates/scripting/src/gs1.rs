@@ -99,6 +99,9 @@ pub struct GS1Script {
     
     /// Script events indexed by event type
     pub events: HashMap<String, Vec<Statement>>,
+
+    /// Source line (from 1) of each statement of each event
+    pub lines: HashMap<String, Vec<usize>>,
     
     /// Script timeout (if any)
     pub timeout: Option<f64>,
@@ -110,6 +113,7 @@ impl GS1Script {
         Self {
             name,
             events: HashMap::new(),
+            lines: HashMap::new(),
             timeout: None,
         }
     }
@@ -119,6 +123,7 @@ impl GS1Script {
         let mut script = Self::new(name);
         let mut current_event = "created".to_string();
         let mut current_block: Vec<Statement> = Vec::new();
+        let mut current_lines: Vec<usize> = Vec::new();
         
         for (line_num, line) in source.lines().enumerate() {
             let line = line.trim();
@@ -139,6 +144,7 @@ impl GS1Script {
                 // Save previous event
                 if !current_block.is_empty() {
                     script.events.insert(current_event.clone(), current_block);
+                    script.lines.insert(current_event.clone(), std::mem::take(&mut current_lines));
                     current_block = Vec::new();
                 }
                 current_event = event_name.to_lowercase();
@@ -146,13 +152,15 @@ impl GS1Script {
             }
             
             // Parse statement
-            let statement = Self::parse_statement(line, line_num)?;
+            let statement = Self::parse_statement(line, line_num + 1)?;
             current_block.push(statement);
+            current_lines.push(line_num + 1);
         }
         
         // Save last event
         if !current_block.is_empty() {
-            script.events.insert(current_event, current_block);
+            script.events.insert(current_event.clone(), current_block);
+            script.lines.insert(current_event, current_lines);
         }
         
         Ok(script)
@@ -227,6 +235,12 @@ pub struct GS1Interpreter {
     
     /// Local variables
     variables: HashMap<String, String>,
+
+    /// Name of the script being executed (owner of its `this.*` variables)
+    owner: String,
+
+    /// Source line of the statement being executed
+    line: Option<usize>,
 }
 
 impl GS1Interpreter {
@@ -235,6 +249,8 @@ impl GS1Interpreter {
         Self {
             context,
             variables: HashMap::new(),
+            owner: String::new(),
+            line: None,
         }
     }
     
@@ -244,14 +260,34 @@ impl GS1Interpreter {
             .ok_or_else(|| ScriptError::RuntimeError(
                 format!("Event not found: {}", event)
             ))?;
+        self.owner = script.name.clone();
+        // Stored this.* variables carry over from the last run, and restarts
+        if let Some(state) = self.context.npc_state() {
+            for (name, value) in state.vars(&self.owner) {
+                self.variables.entry(format!("this.{}", name)).or_insert(value);
+            }
+        }
         
-        for statement in statements {
+        let lines = script.lines.get(event);
+        for (index, statement) in statements.iter().enumerate() {
+            self.line = lines.and_then(|lines| lines.get(index)).copied();
             self.execute_statement(statement)?;
         }
         
         Ok(())
     }
+
+    /// Get the source line of the last statement executed (where a failed
+    /// `execute` stopped)
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
     
+    /// Get the variables set by the statements run so far
+    pub fn variables(&self) -> &HashMap<String, String> {
+        &self.variables
+    }
+
     /// Execute a single statement
     fn execute_statement(&mut self, statement: &Statement) -> Result<()> {
         match statement {
@@ -269,6 +305,10 @@ impl GS1Interpreter {
             }
             
             Statement::Assignment { var, value } => {
+                // this.* variables outlive the run when the server stores them
+                if let (Some(name), Some(state)) = (var.strip_prefix("this."), self.context.npc_state()) {
+                    state.set_var(&self.owner, name, value);
+                }
                 self.variables.insert(var.clone(), value.clone());
             }
             
@@ -287,8 +327,11 @@ impl GS1Interpreter {
         // Simple evaluation: check if variable exists
         let has_local = self.variables.contains_key(condition);
         let has_global = self.context.get_global(condition).is_some();
+        let has_stored = condition.strip_prefix("this.")
+            .zip(self.context.npc_state())
+            .is_some_and(|(name, state)| state.get_var(&self.owner, name).is_some());
 
-        Ok(has_local || has_global)
+        Ok(has_local || has_global || has_stored)
     }
     
     /// Call a built-in function
@@ -48,6 +48,12 @@ pub enum Expr {
         name: String,
     },
 
+    /// Variable assignment (name = value)
+    Assign {
+        name: String,
+        value: Box<Expr>,
+    },
+
     /// Property assignment (obj.prop = value)
     SetProp {
         object: Box<Expr>,
@@ -256,6 +262,9 @@ impl fmt::Display for Expr {
             Expr::GetProp { object, name } => {
                 write!(f, "{}.{}", object, name)
             }
+            Expr::Assign { name, value } => {
+                write!(f, "{} = {}", name, value)
+            }
             Expr::SetProp { object, name, value } => {
                 write!(f, "{}.{} = {}", object, name, value)
             }
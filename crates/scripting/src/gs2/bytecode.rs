@@ -2,6 +2,8 @@
 //!
 //! Bytecode instruction set and constants.
 
+use std::fmt;
+
 /// GS2 opcodes
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[repr(u8)]
@@ -82,6 +84,17 @@ impl OpCode {
         self as u8
     }
 
+    /// Check if the opcode is followed by a one-byte operand
+    ///
+    /// The operand is a constant index, a count or a jump target, as read
+    /// by the VM.
+    pub fn has_operand(self) -> bool {
+        matches!(self,
+            OpCode::OpConst | OpCode::OpGetLocal | OpCode::OpSetLocal | OpCode::OpGetGlobal | OpCode::OpSetGlobal
+            | OpCode::OpGetProp | OpCode::OpSetProp | OpCode::OpMakeArray | OpCode::OpMakeObject
+            | OpCode::OpJump | OpCode::OpJumpIfFalse | OpCode::OpJumpIfTrue | OpCode::OpCall)
+    }
+
     /// Convert byte to opcode
     pub fn from_byte(byte: u8) -> Option<Self> {
         match byte {
@@ -188,6 +201,9 @@ impl Chunk {
     }
 
     /// Disassemble the chunk for debugging
+    ///
+    /// Functions and class methods in the constant pool are listed after
+    /// the chunk, each under its own heading.
     pub fn disassemble(&self, name: &str) -> String {
         let mut output = format!("== {} ==\n", name);
 
@@ -199,24 +215,55 @@ impl Chunk {
             offset += self.instruction_length(offset);
         }
 
+        for constant in &self.constants {
+            match constant {
+                Value::Function(function) => {
+                    output.push('\n');
+                    output.push_str(&function.chunk.disassemble(&function.name));
+                }
+                Value::Class(class) => {
+                    for method in &class.methods {
+                        output.push('\n');
+                        output.push_str(&method.chunk.disassemble(&format!("{}.{}", class.name, method.name)));
+                    }
+                }
+                _ => {}
+            }
+        }
+
         output
     }
 
     /// Disassemble a single instruction
     fn disassemble_instruction(&self, offset: usize) -> String {
         let op = self.code[offset];
-        let op_name = OpCode::from_byte(op)
-            .map(|op| format!("{:?}", op))
-            .unwrap_or(format!("UNKNOWN_OP({})", op));
+        let Some(opcode) = OpCode::from_byte(op) else {
+            return format!("{:04} UNKNOWN_OP({})", offset, op);
+        };
+        if !opcode.has_operand() {
+            return format!("{:04} {:?}", offset, opcode);
+        }
 
-        format!("{:04} {}", offset, op_name)
+        let Some(&operand) = self.code.get(offset + 1) else {
+            return format!("{:04} {:<16} <missing operand>", offset, format!("{:?}", opcode));
+        };
+        let detail = match opcode {
+            OpCode::OpConst => match self.constants.get(operand as usize) {
+                Some(value) => format!(" {}", value),
+                None => " <invalid constant>".to_string(),
+            },
+            OpCode::OpJump | OpCode::OpJumpIfFalse | OpCode::OpJumpIfTrue => format!(" -> {:04}", operand),
+            _ => String::new(),
+        };
+        format!("{:04} {:<16} {:3}{}", offset, format!("{:?}", opcode), operand, detail)
     }
 
     /// Get the length of an instruction
     fn instruction_length(&self, offset: usize) -> usize {
-        // For now, all instructions are 1 byte
-        // Some instructions will have operands and be longer
-        1
+        match OpCode::from_byte(self.code[offset]) {
+            Some(op) if op.has_operand() => 2,
+            _ => 1,
+        }
     }
 }
 
@@ -275,6 +322,22 @@ impl PartialEq for Value {
     }
 }
 
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Number(n) => write!(f, "{}", n),
+            Value::String(s) => write!(f, "{:?}", s),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Null => f.write_str("null"),
+            Value::Object => f.write_str("<object>"),
+            Value::Array => f.write_str("<array>"),
+            Value::Function(function) => write!(f, "<function {}/{}>", function.name, function.arity),
+            Value::Class(class) => write!(f, "<class {}>", class.name),
+            Value::Instance(instance) => write!(f, "<{} instance>", instance.class.name),
+        }
+    }
+}
+
 impl Value {
     /// Check if value is truthy
     pub fn is_truthy(&self) -> bool {
@@ -70,49 +70,34 @@ impl Compiler {
             }
 
             Stmt::If { condition, then_branch, else_branch } => {
+                // The condition stays on the stack for the jump, each branch pops it
                 self.compile_expression(condition)?;
-                self.chunk.write_op(OpCode::OpJumpIfFalse, 0);
-
-                let else_jump = self.chunk.code.len();
-                self.chunk.write(0, 0); // Placeholder
+                let else_jump = self.emit_jump(OpCode::OpJumpIfFalse);
+                self.chunk.write_op(OpCode::OpPop, 0);
 
                 self.compile_statement(then_branch)?;
+                let end_jump = self.emit_jump(OpCode::OpJump);
 
+                self.patch_jump(else_jump)?;
+                self.chunk.write_op(OpCode::OpPop, 0);
                 if let Some(else_br) = else_branch {
-                    self.chunk.write_op(OpCode::OpJump, 0);
-
-                    let end_jump = self.chunk.code.len();
-                    self.chunk.write(0, 0); // Placeholder
-
-                    // Patch else jump
-                    // self.patch_jump(else_jump);
-
                     self.compile_statement(else_br)?;
-
-                    // Patch end jump
-                    // self.patch_jump(end_jump);
-                } else {
-                    // Patch else jump
-                    // self.patch_jump(else_jump);
                 }
+                self.patch_jump(end_jump)?;
             }
 
             Stmt::While { condition, body } => {
                 let loop_start = self.chunk.code.len();
 
                 self.compile_expression(condition)?;
-                self.chunk.write_op(OpCode::OpJumpIfFalse, 0);
-
-                let exit_jump = self.chunk.code.len();
-                self.chunk.write(0, 0); // Placeholder
+                let exit_jump = self.emit_jump(OpCode::OpJumpIfFalse);
+                self.chunk.write_op(OpCode::OpPop, 0);
 
                 self.compile_statement(body)?;
+                self.emit_loop(loop_start)?;
 
-                self.chunk.write_op(OpCode::OpJump, 0);
-                // self.patch_jump(loop_start);
-
-                // Patch exit jump
-                // self.patch_jump(exit_jump);
+                self.patch_jump(exit_jump)?;
+                self.chunk.write_op(OpCode::OpPop, 0);
             }
 
             Stmt::For { init, condition, increment, body } => {
@@ -130,10 +115,8 @@ impl Compiler {
                     self.chunk.write_op(OpCode::OpTrue, 0);
                 }
 
-                self.chunk.write_op(OpCode::OpJumpIfFalse, 0);
-
-                let exit_jump = self.chunk.code.len();
-                self.chunk.write(0, 0); // Placeholder
+                let exit_jump = self.emit_jump(OpCode::OpJumpIfFalse);
+                self.chunk.write_op(OpCode::OpPop, 0);
 
                 self.compile_statement(body)?;
 
@@ -143,11 +126,10 @@ impl Compiler {
                     self.chunk.write_op(OpCode::OpPop, 0);
                 }
 
-                self.chunk.write_op(OpCode::OpJump, 0);
-                // self.patch_jump(loop_start);
+                self.emit_loop(loop_start)?;
 
-                // Patch exit jump
-                // self.patch_jump(exit_jump);
+                self.patch_jump(exit_jump)?;
+                self.chunk.write_op(OpCode::OpPop, 0);
             }
 
             Stmt::Function { name, params, body } => {
@@ -228,6 +210,38 @@ impl Compiler {
         Ok(())
     }
 
+    /// Write a jump whose target isn't known yet
+    ///
+    /// # Returns
+    /// The offset of the operand, for [`patch_jump`](Self::patch_jump)
+    fn emit_jump(&mut self, op: OpCode) -> usize {
+        self.chunk.write_op(op, 0);
+        self.chunk.write(0, 0); // Placeholder
+        self.chunk.code.len() - 1
+    }
+
+    /// Point a jump from [`emit_jump`](Self::emit_jump) at the next instruction
+    fn patch_jump(&mut self, operand: usize) -> Result<()> {
+        self.chunk.code[operand] = self.jump_target(self.chunk.code.len())?;
+        Ok(())
+    }
+
+    /// Write a jump back to the start of a loop
+    fn emit_loop(&mut self, loop_start: usize) -> Result<()> {
+        let target = self.jump_target(loop_start)?;
+        self.chunk.write_op(OpCode::OpJump, 0);
+        self.chunk.write(target, 0);
+        Ok(())
+    }
+
+    /// Get the operand of a jump to an offset (jumps are absolute, one byte)
+    fn jump_target(&self, offset: usize) -> Result<u8> {
+        u8::try_from(offset).map_err(|_| ScriptError::ParseError {
+            line: 0,
+            message: format!("{}: jump to offset {} is too far (at most {})", self.function_name, offset, u8::MAX),
+        })
+    }
+
     /// Compile an expression
     fn compile_expression(&mut self, expr: &Expr) -> Result<()> {
         match expr {
@@ -256,9 +270,16 @@ impl Compiler {
             }
 
             Expr::Variable(name) => {
-                // TODO: Look up variable
-                // For now, push a placeholder
-                self.chunk.write_op(OpCode::OpNull, 0);
+                let idx = self.chunk.add_constant(Value::String(name.clone()));
+                self.chunk.write_op(OpCode::OpGetGlobal, 0);
+                self.chunk.write(idx as u8, 0);
+            }
+
+            Expr::Assign { name, value } => {
+                self.compile_expression(value)?;
+                let idx = self.chunk.add_constant(Value::String(name.clone()));
+                self.chunk.write_op(OpCode::OpSetGlobal, 0);
+                self.chunk.write(idx as u8, 0);
             }
 
             Expr::Binary { left, op, right } => {
@@ -315,15 +336,17 @@ impl Compiler {
 
             Expr::GetProp { object, name } => {
                 self.compile_expression(object)?;
-                // TODO: Push property name
+                let idx = self.chunk.add_constant(Value::String(name.clone()));
                 self.chunk.write_op(OpCode::OpGetProp, 0);
+                self.chunk.write(idx as u8, 0);
             }
 
             Expr::SetProp { object, name, value } => {
                 self.compile_expression(object)?;
-                // TODO: Push property name
                 self.compile_expression(value)?;
+                let idx = self.chunk.add_constant(Value::String(name.clone()));
                 self.chunk.write_op(OpCode::OpSetProp, 0);
+                self.chunk.write(idx as u8, 0);
             }
 
             Expr::Index { object, index } => {
@@ -449,4 +472,32 @@ mod tests {
         // Should have a class in constants
         assert!(!chunk.constants.is_empty());
     }
+
+    #[test]
+    fn test_disassemble_operands() {
+        let mut compiler = Compiler::new();
+        let parser = crate::gs2::parser::Parser::new("function onCreated() { player.chat; }\nwhile (1) { 2; }");
+        let chunk = compiler.compile(&parser.parse().unwrap()).unwrap();
+
+        let listing = chunk.disassemble("<script>");
+        assert!(listing.starts_with("== <script> ==\n0000 OpConst            0 <function onCreated/0>\n"), "{}", listing);
+        assert!(listing.contains("OpJumpIfFalse     12 -> 0012\n"), "{}", listing);
+        assert!(listing.contains("OpJump             2 -> 0002\n"), "{}", listing);
+        assert!(listing.contains("\n== onCreated ==\n"), "{}", listing);
+        assert!(listing.contains("OpGetGlobal        0\n0002 OpGetProp          1\n"), "{}", listing);
+    }
+
+    #[test]
+    fn test_jumps_run() {
+        let source = "x = 0; i = 0;\nwhile (i < 3) { x = x + 2; i = i + 1; }\nfor (; i < 5; i = i + 1) { x = x + 1; }\n\
+            if (x == 8) { y = 1; } else { y = 2; }\nif (false) { z = 1; }";
+        let chunk = Compiler::new().compile(&crate::gs2::parser::Parser::new(source).parse().unwrap()).unwrap();
+
+        let mut vm = crate::gs2::vm::VM::new(chunk).with_instruction_limit(1000);
+        vm.interpret().unwrap();
+        assert_eq!(vm.global("x"), Some(&Value::Number(8.0)));
+        assert_eq!(vm.global("i"), Some(&Value::Number(5.0)));
+        assert_eq!(vm.global("y"), Some(&Value::Number(1.0)));
+        assert_eq!(vm.global("z"), None);
+    }
 }
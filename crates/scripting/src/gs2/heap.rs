@@ -0,0 +1,83 @@
+//! GS2 Value Heap
+//!
+//! The VM's live values are its stack, globals and locals. Their size is
+//! estimated from the [`Value`]s (strings, function and class bytecode,
+//! instance fields), so a script's memory can be limited and reported.
+//!
+//! Every [`GC_INTERVAL`] instructions, and when a run ends, the VM collects:
+//! variables set to null are dropped, spare stack capacity is released, and
+//! the live size is measured again. A script above its quota then fails with
+//! [`ScriptError::MemoryLimit`](crate::ScriptError::MemoryLimit).
+
+use crate::gs2::bytecode::{Chunk, Value};
+use std::mem::size_of;
+
+/// Instructions between collections
+pub const GC_INTERVAL: usize = 1024;
+
+/// Heap statistics of one VM (or, in the host, of a script's last run)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HeapStats {
+    /// Bytes live after the last collection
+    pub live_bytes: usize,
+
+    /// Most bytes live at any collection
+    pub peak_bytes: usize,
+
+    /// Collections run
+    pub collections: u64,
+
+    /// Bytes released by collections
+    pub freed_bytes: usize,
+}
+
+impl HeapStats {
+    /// Record a collection that left `live` bytes, down from `before`
+    pub fn record(&mut self, before: usize, live: usize) {
+        self.collections += 1;
+        self.freed_bytes += before.saturating_sub(live);
+        self.live_bytes = live;
+        self.peak_bytes = self.peak_bytes.max(before).max(live);
+    }
+}
+
+/// Estimate the bytes a value holds
+pub fn value_size(value: &Value) -> usize {
+    size_of::<Value>() + match value {
+        Value::String(s) => s.capacity(),
+        Value::Function(function) => function.name.capacity() + chunk_size(&function.chunk),
+        Value::Class(class) => class_size(class),
+        Value::Instance(instance) => class_size(&instance.class) + instance.fields.iter()
+            .map(|(name, value)| name.capacity() + value_size(value))
+            .sum::<usize>(),
+        _ => 0,
+    }
+}
+
+/// Estimate the bytes a chunk holds
+pub fn chunk_size(chunk: &Chunk) -> usize {
+    chunk.code.capacity() + chunk.lines.capacity() * size_of::<usize>()
+        + chunk.constants.iter().map(value_size).sum::<usize>()
+}
+
+fn class_size(class: &crate::gs2::bytecode::Class) -> usize {
+    class.name.capacity() + class.superclass.as_ref().map_or(0, String::capacity)
+        + class.methods.iter().map(|method| method.name.capacity() + chunk_size(&method.chunk)).sum::<usize>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_sizes() {
+        let base = size_of::<Value>();
+        assert_eq!(value_size(&Value::Number(1.0)), base);
+        assert_eq!(value_size(&Value::String(String::with_capacity(100))), base + 100);
+
+        let mut stats = HeapStats::default();
+        stats.record(500, 200);
+        stats.record(300, 300);
+        assert_eq!(stats, HeapStats { live_bytes: 300, peak_bytes: 500, collections: 2, freed_bytes: 300 });
+    }
+}
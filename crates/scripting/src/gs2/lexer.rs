@@ -3,6 +3,7 @@
 //! Lexical analysis for GS2 scripting language.
 
 use crate::error::{ScriptError, Result};
+use std::fmt;
 use std::iter::Peekable;
 use std::str::Chars;
 
@@ -87,6 +88,70 @@ pub enum Token {
     EOF,
 }
 
+impl fmt::Display for Token {
+    /// How the token is named in error messages
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Token::Number(n) => return write!(f, "number {}", n),
+            Token::String(s) => return write!(f, "string {:?}", s),
+            Token::Identifier(name) => return write!(f, "identifier '{}'", name),
+            Token::EOF => return f.write_str("end of script"),
+            Token::If => "if",
+            Token::Else => "else",
+            Token::While => "while",
+            Token::For => "for",
+            Token::Function => "function",
+            Token::Class => "class",
+            Token::Return => "return",
+            Token::True => "true",
+            Token::False => "false",
+            Token::Null => "null",
+            Token::This => "this",
+            Token::Super => "super",
+            Token::New => "new",
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Star => "*",
+            Token::Slash => "/",
+            Token::Percent => "%",
+            Token::Equal => "==",
+            Token::NotEqual => "!=",
+            Token::Less => "<",
+            Token::Greater => ">",
+            Token::LessEqual => "<=",
+            Token::GreaterEqual => ">=",
+            Token::And => "&&",
+            Token::Or => "||",
+            Token::Not => "!",
+            Token::BitAnd => "&",
+            Token::BitOr => "|",
+            Token::BitXor => "^",
+            Token::BitNot => "~",
+            Token::LeftShift => "<<",
+            Token::RightShift => ">>",
+            Token::Assign => "=",
+            Token::PlusEqual => "+=",
+            Token::MinusEqual => "-=",
+            Token::StarEqual => "*=",
+            Token::SlashEqual => "/=",
+            Token::Increment => "++",
+            Token::Decrement => "--",
+            Token::LParen => "(",
+            Token::RParen => ")",
+            Token::LBrace => "{",
+            Token::RBrace => "}",
+            Token::LBracket => "[",
+            Token::RBracket => "]",
+            Token::Semicolon => ";",
+            Token::Comma => ",",
+            Token::Dot => ".",
+            Token::Colon => ":",
+            Token::Question => "?",
+        };
+        write!(f, "'{}'", symbol)
+    }
+}
+
 /// GS2 lexer
 pub struct Lexer<'a> {
     input: Peekable<Chars<'a>>,
@@ -94,6 +159,8 @@ pub struct Lexer<'a> {
     line: usize,
     column: usize,
     ch: Option<char>,
+    token_line: usize,
+    token_column: usize,
 }
 
 impl<'a> Lexer<'a> {
@@ -107,12 +174,21 @@ impl<'a> Lexer<'a> {
             line: 1,
             column: 0,
             ch,
+            token_line: 1,
+            token_column: 1,
         }
     }
 
+    /// Get the line and column (1-based) where the last token started
+    pub fn position(&self) -> (usize, usize) {
+        (self.token_line, self.token_column)
+    }
+
     /// Get the next token
     pub fn next_token(&mut self) -> Result<Token> {
         self.skip_whitespace();
+        self.token_line = self.line;
+        self.token_column = self.column + 1;
 
         match self.ch {
             None => Ok(Token::EOF),
@@ -177,6 +253,9 @@ impl<'a> Lexer<'a> {
                                 self.advance();
                             }
                             self.next_token()
+                        } else if self.ch == Some('*') {
+                            self.skip_block_comment()?;
+                            self.next_token()
                         } else {
                             Ok(Token::Slash)
                         }
@@ -283,12 +362,9 @@ impl<'a> Lexer<'a> {
                     }
 
                     _ => {
-                        let msg = format!("Unexpected character: {}", ch);
+                        let error = self.error(format!("Unexpected character '{}'", ch));
                         self.advance();
-                        Err(ScriptError::ParseError {
-                            line: self.line,
-                            message: msg,
-                        })
+                        Err(error)
                     }
                 }
             }
@@ -297,7 +373,6 @@ impl<'a> Lexer<'a> {
 
     /// Read a number literal
     fn read_number(&mut self) -> Result<Token> {
-        let start = self.column;
         let mut num_str = String::new();
 
         while let Some(ch) = self.ch {
@@ -310,10 +385,7 @@ impl<'a> Lexer<'a> {
         }
 
         let value: f64 = num_str.parse()
-            .map_err(|_| ScriptError::ParseError {
-                line: self.line,
-                message: format!("Invalid number: {}", num_str),
-            })?;
+            .map_err(|_| self.error(format!("Invalid number: {}", num_str)))?;
 
         Ok(Token::Number(value))
     }
@@ -361,6 +433,8 @@ impl<'a> Lexer<'a> {
             if ch == '"' {
                 self.advance();
                 return Ok(Token::String(s));
+            } else if ch == '\n' {
+                break;
             } else if ch == '\\' {
                 self.advance();
                 if let Some(escaped) = self.ch {
@@ -380,10 +454,34 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        Err(ScriptError::ParseError {
-            line: self.line,
-            message: "Unterminated string".into(),
-        })
+        Err(self.error("Unterminated string"))
+    }
+
+    /// Skip a `/* */` comment; the current character is the `*`
+    fn skip_block_comment(&mut self) -> Result<()> {
+        self.advance();
+        while let Some(ch) = self.ch {
+            self.advance();
+            if ch == '*' && self.ch == Some('/') {
+                self.advance();
+                return Ok(());
+            }
+            if ch == '\n' {
+                self.line += 1;
+                self.column = 0;
+            }
+        }
+        Err(self.error("Unterminated comment"))
+    }
+
+    /// Syntax error at the start of the current token
+    fn error(&self, message: impl Into<String>) -> ScriptError {
+        ScriptError::SyntaxError {
+            line: self.token_line,
+            column: self.token_column,
+            message: message.into(),
+            expected: Vec::new(),
+        }
     }
 
     /// Check if the next character matches
@@ -470,4 +568,15 @@ mod tests {
 
         assert_eq!(lexer.next_token().unwrap(), Token::String("hello world".into()));
     }
+
+    #[test]
+    fn test_positions_and_comments() {
+        let mut lexer = Lexer::new("a /* one\ntwo */ b\n  // note\n  \"open");
+
+        assert_eq!(lexer.next_token().unwrap(), Token::Identifier("a".into()));
+        assert_eq!(lexer.position(), (1, 1));
+        assert_eq!(lexer.next_token().unwrap(), Token::Identifier("b".into()));
+        assert_eq!(lexer.position(), (2, 8));
+        assert_eq!(lexer.next_token().unwrap_err().to_string(), "Syntax error at line 4, column 3: Unterminated string");
+    }
 }
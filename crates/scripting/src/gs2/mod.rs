@@ -9,6 +9,7 @@ pub mod parser;
 pub mod bytecode;
 pub mod compiler;
 pub mod vm;
+pub mod heap;
 
 pub use lexer::{Lexer, Token};
 pub use ast::*;
@@ -16,3 +17,4 @@ pub use parser::Parser;
 pub use bytecode::{Chunk, OpCode, Value};
 pub use compiler::Compiler;
 pub use vm::VM;
+pub use heap::HeapStats;
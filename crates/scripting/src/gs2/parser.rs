@@ -5,23 +5,34 @@
 use crate::error::{ScriptError, Result};
 use crate::gs2::ast::*;
 use crate::gs2::lexer::{Lexer, Token};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
 /// GS2 parser
+///
+/// Errors are [`ScriptError::SyntaxError`]s with the line and column of the
+/// offending token and what would have been accepted there.
 pub struct Parser<'a> {
     lexer: RefCell<Lexer<'a>>,
     current: RefCell<Token>,
     previous: RefCell<Token>,
+    /// Line and column of `current`
+    position: Cell<(usize, usize)>,
+    /// First lexer error; the token stream ends there
+    lex_error: RefCell<Option<ScriptError>>,
 }
 
 impl<'a> Parser<'a> {
     /// Create a new parser
     pub fn new(input: &'a str) -> Self {
-        let lexer = RefCell::new(Lexer::new(input));
-        let current = RefCell::new(lexer.borrow_mut().next_token().unwrap());
-        let previous = RefCell::new(Token::EOF);
-
-        Self { lexer, current, previous }
+        let parser = Self {
+            lexer: RefCell::new(Lexer::new(input)),
+            current: RefCell::new(Token::EOF),
+            previous: RefCell::new(Token::EOF),
+            position: Cell::new((1, 1)),
+            lex_error: RefCell::new(None),
+        };
+        parser.advance();
+        parser
     }
 
     /// Parse a script
@@ -29,10 +40,16 @@ impl<'a> Parser<'a> {
         let mut statements = Vec::new();
 
         while !self.check(Token::EOF) {
-            statements.push(self.declaration()?);
+            match self.declaration() {
+                Ok(statement) => statements.push(statement),
+                Err(e) => return Err(self.lex_error.take().unwrap_or(e)),
+            }
         }
 
-        Ok(Script::new(statements))
+        match self.lex_error.take() {
+            Some(e) => Err(e),
+            None => Ok(Script::new(statements)),
+        }
     }
 
     /// Parse a declaration
@@ -52,12 +69,7 @@ impl<'a> Parser<'a> {
 
         let name = match self.current.clone().into_inner() {
             Token::Identifier(name) => name,
-            _ => {
-                return Err(ScriptError::ParseError {
-                    line: 0,
-                    message: "Expected function name".into(),
-                })
-            }
+            _ => return Err(self.error("Expected function name", &["identifier"])),
         };
 
         self.advance();
@@ -75,10 +87,7 @@ impl<'a> Parser<'a> {
                         break;
                     }
                 } else {
-                    return Err(ScriptError::ParseError {
-                        line: 0,
-                        message: "Expected parameter name".into(),
-                    });
+                    return Err(self.error("Expected parameter name", &["identifier", "')'"]));
                 }
             }
         }
@@ -95,12 +104,7 @@ impl<'a> Parser<'a> {
 
         let name = match self.current.clone().into_inner() {
             Token::Identifier(name) => name,
-            _ => {
-                return Err(ScriptError::ParseError {
-                    line: 0,
-                    message: "Expected class name".into(),
-                })
-            }
+            _ => return Err(self.error("Expected class name", &["identifier"])),
         };
 
         self.advance();
@@ -112,12 +116,7 @@ impl<'a> Parser<'a> {
                     self.advance();
                     Some(super_name)
                 }
-                _ => {
-                    return Err(ScriptError::ParseError {
-                        line: 0,
-                        message: "Expected superclass name".into(),
-                    })
-                }
+                _ => return Err(self.error("Expected superclass name", &["identifier"])),
             }
         } else {
             None
@@ -197,7 +196,7 @@ impl<'a> Parser<'a> {
         self.consume(Token::LParen, "Expected '(' after 'for'")?;
 
         let init = if !self.check(Token::Semicolon) {
-            Some(Box::new(self.declaration()?))
+            Some(Box::new(Stmt::Expr(self.expression()?)))
         } else {
             None
         };
@@ -275,20 +274,15 @@ impl<'a> Parser<'a> {
 
     /// Parse assignment expression
     fn assignment(&self) -> Result<Expr> {
+        let target = self.position.get();
         let expr = self.or()?;
 
         if self.match_token(Token::Assign) {
             let value = Box::new(self.assignment()?);
             return Ok(match expr {
-                Expr::Variable(name) => Expr::Binary {
-                    left: Box::new(Expr::Variable(name)),
-                    op: BinaryOp::Equal,
-                    right: value,
-                },
-                _ => return Err(ScriptError::ParseError {
-                    line: 0,
-                    message: "Invalid assignment target".into(),
-                }),
+                Expr::Variable(name) => Expr::Assign { name, value },
+                Expr::GetProp { object, name } => Expr::SetProp { object, name, value },
+                _ => return Err(Self::error_at(target, "Invalid assignment target".into(), &["variable"])),
             });
         }
 
@@ -432,13 +426,14 @@ impl<'a> Parser<'a> {
                     args,
                 };
             } else if self.match_token(Token::Dot) {
-                if let Token::Identifier(name) = self.current.clone().into_inner() {
-                    self.advance();
-                    expr = Expr::GetProp {
-                        object: Box::new(expr),
-                        name,
-                    };
-                }
+                let Token::Identifier(name) = self.current.clone().into_inner() else {
+                    return Err(self.error("Expected property name after '.'", &["identifier"]));
+                };
+                self.advance();
+                expr = Expr::GetProp {
+                    object: Box::new(expr),
+                    name,
+                };
             } else if self.match_token(Token::LBracket) {
                 let index = self.expression()?;
                 self.consume(Token::RBracket, "Expected ']' after index")?;
@@ -538,6 +533,8 @@ impl<'a> Parser<'a> {
                             if !self.match_token(Token::Comma) {
                                 break;
                             }
+                        } else {
+                            return Err(self.error("Expected property name", &["identifier"]));
                         }
                     }
                 }
@@ -546,10 +543,7 @@ impl<'a> Parser<'a> {
                 Ok(Expr::Object(props))
             }
 
-            _ => Err(ScriptError::ParseError {
-                line: 0,
-                message: format!("Unexpected token: {:?}", token),
-            }),
+            _ => Err(self.error("Expected an expression", &["expression"])),
         }
     }
 
@@ -585,10 +579,22 @@ impl<'a> Parser<'a> {
             return Ok(());
         }
 
-        Err(ScriptError::ParseError {
-            line: 0,
-            message: message.into(),
-        })
+        Err(self.error(message, &[&token.to_string()]))
+    }
+
+    /// Syntax error at the current token
+    fn error(&self, message: &str, expected: &[&str]) -> ScriptError {
+        let found = self.current.borrow().to_string();
+        Self::error_at(self.position.get(), format!("{}, found {}", message, found), expected)
+    }
+
+    fn error_at((line, column): (usize, usize), message: String, expected: &[&str]) -> ScriptError {
+        ScriptError::SyntaxError {
+            line,
+            column,
+            message,
+            expected: expected.iter().map(|e| e.to_string()).collect(),
+        }
     }
 
     /// Get previous token
@@ -600,7 +606,16 @@ impl<'a> Parser<'a> {
     fn advance(&self) -> Token {
         let prev = self.current.borrow().clone();
         *self.previous.borrow_mut() = prev.clone();
-        *self.current.borrow_mut() = self.lexer.borrow_mut().next_token().unwrap();
+        let mut lexer = self.lexer.borrow_mut();
+        let next = match lexer.next_token() {
+            Ok(token) => token,
+            Err(e) => {
+                self.lex_error.borrow_mut().get_or_insert(e);
+                Token::EOF
+            }
+        };
+        self.position.set(lexer.position());
+        *self.current.borrow_mut() = next;
         prev
     }
 }
@@ -650,4 +665,25 @@ mod tests {
         let script = parser.parse().unwrap();
         assert_eq!(script.statements.len(), 1);
     }
+
+    #[test]
+    fn test_syntax_error_positions() {
+        let error = |source: &str| match Parser::new(source).parse() {
+            Err(ScriptError::SyntaxError { line, column, message, expected }) => (line, column, message, expected),
+            other => panic!("expected a syntax error, got {:?}", other),
+        };
+
+        let (line, column, message, expected) = error("function onCreated() {\n  x = foo(1, 2;\n}");
+        assert_eq!((line, column), (2, 15));
+        assert_eq!(message, "Expected ')' after arguments, found ';'");
+        assert_eq!(expected, ["')'"]);
+
+        let (line, column, message, _) = error("if (a)\n  b = 1 +;");
+        assert_eq!((line, column, message.as_str()), (2, 10, "Expected an expression, found ';'"));
+
+        let (line, column, message, _) = error("x = 1;\ny = \"never closed;");
+        assert_eq!((line, column, message.as_str()), (2, 5, "Unterminated string"));
+
+        assert!(Parser::new("for (i = 0; i < 3; i = i + 1) { this.x = i; }").parse().is_ok());
+    }
 }
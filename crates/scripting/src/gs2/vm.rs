@@ -1,9 +1,12 @@
 //! GS2 Bytecode VM
 //!
-//! Virtual machine for executing GS2 bytecode.
+//! Virtual machine for executing GS2 bytecode. The values it holds can be
+//! limited with [`VM::with_memory_limit`] (see [`crate::gs2::heap`]), and the
+//! instructions a run executes with [`VM::with_instruction_limit`].
 
 use crate::error::{ScriptError, Result};
 use crate::gs2::bytecode::{Chunk, OpCode, Value, Function};
+use crate::gs2::heap::{self, HeapStats, GC_INTERVAL};
 use std::collections::HashMap;
 
 /// Stack frame for function calls
@@ -38,6 +41,21 @@ pub struct VM {
 
     /// Local variables
     locals: HashMap<String, Value>,
+
+    /// Most bytes the values may hold, `None` for no limit
+    memory_limit: Option<usize>,
+
+    /// Heap statistics
+    heap: HeapStats,
+
+    /// Instructions since the last collection
+    since_gc: usize,
+
+    /// Most instructions a run may execute, `None` for no limit
+    instruction_limit: Option<u64>,
+
+    /// Instructions executed
+    executed: u64,
 }
 
 impl VM {
@@ -50,16 +68,105 @@ impl VM {
             call_stack: Vec::new(),
             globals: HashMap::new(),
             locals: HashMap::new(),
+            memory_limit: None,
+            heap: HeapStats::default(),
+            since_gc: 0,
+            instruction_limit: None,
+            executed: 0,
         }
     }
 
-    /// Interpret the bytecode
+    /// Limit the bytes the script's values may hold
+    pub fn with_memory_limit(mut self, bytes: usize) -> Self {
+        self.memory_limit = Some(bytes);
+        self
+    }
+
+    /// Limit the instructions a run may execute
+    ///
+    /// A run that goes over stops with [`ScriptError::Timeout`].
+    pub fn with_instruction_limit(mut self, count: u64) -> Self {
+        self.instruction_limit = Some(count);
+        self
+    }
+
+    /// Get the heap statistics
+    pub fn heap_stats(&self) -> HeapStats {
+        self.heap
+    }
+
+    /// Get a global variable
+    pub fn global(&self, name: &str) -> Option<&Value> {
+        self.globals.get(name)
+    }
+
+    /// Set a global variable before running
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        self.globals.insert(name.to_string(), value);
+    }
+
+    /// Take the global variables, e.g. to keep them for the next run
+    pub fn take_globals(&mut self) -> HashMap<String, Value> {
+        std::mem::take(&mut self.globals)
+    }
+
+    /// Estimate the bytes the live values hold
+    pub fn heap_bytes(&self) -> usize {
+        let variables = |vars: &HashMap<String, Value>| {
+            vars.iter().map(|(name, value)| name.capacity() + heap::value_size(value)).sum::<usize>()
+        };
+        self.stack.iter().map(heap::value_size).sum::<usize>()
+            + self.stack.capacity().saturating_sub(self.stack.len()) * std::mem::size_of::<Value>()
+            + variables(&self.globals) + variables(&self.locals)
+            + self.call_stack.iter().map(|frame| heap::chunk_size(&frame.chunk)).sum::<usize>()
+    }
+
+    /// Drop unreachable values and check the memory limit
+    ///
+    /// Variables set to null are removed and spare stack capacity released.
+    pub fn collect_garbage(&mut self) -> Result<()> {
+        let before = self.heap_bytes();
+        self.globals.retain(|_, value| !matches!(value, Value::Null));
+        self.locals.retain(|_, value| !matches!(value, Value::Null));
+        self.stack.shrink_to(self.stack.len() * 2);
+        let live = self.heap_bytes();
+        self.heap.record(before, live);
+        self.since_gc = 0;
+
+        match self.memory_limit {
+            Some(limit) if live > limit => Err(ScriptError::MemoryLimit { used: live, limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Get the source line of the last instruction run (where a failed
+    /// `interpret` stopped)
+    pub fn line(&self) -> Option<usize> {
+        self.chunk.lines.get(self.ip.checked_sub(1)?).copied()
+    }
+
+    /// Interpret the bytecode, collecting garbage as it runs and at the end
     pub fn interpret(&mut self) -> Result<Value> {
+        let value = self.run()?;
+        self.collect_garbage()?;
+        Ok(value)
+    }
+
+    fn run(&mut self) -> Result<Value> {
         loop {
             if self.ip >= self.chunk.code.len() {
                 break;
             }
 
+            self.since_gc += 1;
+            if self.since_gc >= GC_INTERVAL {
+                self.collect_garbage()?;
+            }
+            self.executed += 1;
+            if self.instruction_limit.is_some_and(|limit| self.executed > limit) {
+                return Err(ScriptError::Timeout);
+            }
+
             let instruction = self.read_byte();
             let op = OpCode::from_byte(instruction);
 
@@ -99,34 +206,36 @@ impl VM {
                 }
 
                 Some(OpCode::OpGetLocal) => {
-                    let name_idx = self.read_byte() as usize;
-                    // TODO: Implement proper local variable lookup
-                    self.push(Value::Null);
+                    let name = self.read_name()?;
+                    let value = self.locals.get(&name).cloned().unwrap_or(Value::Null);
+                    self.push(value);
                 }
 
                 Some(OpCode::OpSetLocal) => {
-                    let name_idx = self.read_byte() as usize;
+                    let name = self.read_name()?;
                     let value = self.peek();
-                    // TODO: Store local variable
+                    self.locals.insert(name, value);
                 }
 
                 Some(OpCode::OpGetGlobal) => {
-                    let name_idx = self.read_byte() as usize;
-                    // TODO: Implement proper global variable lookup
-                    self.push(Value::Null);
+                    let name = self.read_name()?;
+                    let value = self.globals.get(&name).cloned().unwrap_or(Value::Null);
+                    self.push(value);
                 }
 
                 Some(OpCode::OpSetGlobal) => {
-                    let name_idx = self.read_byte() as usize;
+                    let name = self.read_name()?;
                     let value = self.peek();
-                    // TODO: Store global variable
+                    self.globals.insert(name, value);
                 }
 
                 Some(OpCode::OpGetProp) => {
-                    let prop_name_idx = self.read_byte() as usize;
-                    let object = self.pop();
-                    // TODO: Get property from object
-                    self.push(Value::Null);
+                    let name = self.read_name()?;
+                    let value = match self.pop() {
+                        Value::Instance(instance) => instance.fields.get(&name).cloned().unwrap_or(Value::Null),
+                        _ => Value::Null,
+                    };
+                    self.push(value);
                 }
 
                 Some(OpCode::OpSetProp) => {
@@ -439,6 +548,15 @@ impl VM {
         byte
     }
 
+    /// Read a variable name operand (a string constant)
+    fn read_name(&mut self) -> Result<String> {
+        let index = self.read_byte() as usize;
+        match self.chunk.constants.get(index) {
+            Some(Value::String(name)) => Ok(name.clone()),
+            _ => Err(ScriptError::RuntimeError(format!("Invalid variable name constant: {}", index))),
+        }
+    }
+
     /// Push a value onto the stack
     fn push(&mut self, value: Value) {
         self.stack.push(value);
@@ -619,4 +737,44 @@ mod tests {
         // Should return 3
         assert_eq!(result, Value::Number(3.0));
     }
+
+    #[test]
+    fn test_vm_collects_null_globals() {
+        // a = "..."; b = a; a = null;
+        let mut chunk = Chunk::new();
+        let text = chunk.add_constant(Value::String("x".repeat(1000)));
+        let a = chunk.add_constant(Value::String("a".into()));
+        let b = chunk.add_constant(Value::String("b".into()));
+        let none = usize::MAX;
+        for (op, operand) in [(OpCode::OpConst, text), (OpCode::OpSetGlobal, a), (OpCode::OpPop, none),
+            (OpCode::OpGetGlobal, a), (OpCode::OpSetGlobal, b), (OpCode::OpPop, none),
+            (OpCode::OpNull, none), (OpCode::OpSetGlobal, a), (OpCode::OpPop, none)] {
+            chunk.write_op(op, 0);
+            if operand != none {
+                chunk.write(operand as u8, 0);
+            }
+        }
+
+        let mut vm = VM::new(chunk);
+        vm.interpret().unwrap();
+        assert_eq!(vm.global("a"), None);
+        assert_eq!(vm.global("b"), Some(&Value::String("x".repeat(1000))));
+        let heap = vm.heap_stats();
+        assert_eq!(heap.collections, 1);
+        assert!(heap.live_bytes >= 1000 && heap.live_bytes < 3000, "{:?}", heap);
+    }
+
+    #[test]
+    fn test_vm_instruction_limit() {
+        let script = crate::gs2::parser::Parser::new("a = 1; b = 2; c = 3;").parse().unwrap();
+        let chunk = Compiler::new().compile(&script).unwrap();
+
+        let mut vm = VM::new(chunk.clone()).with_instruction_limit(4);
+        assert!(matches!(vm.interpret(), Err(ScriptError::Timeout)));
+        assert_eq!(vm.global("c"), None);
+
+        let mut vm = VM::new(chunk).with_instruction_limit(100);
+        vm.interpret().unwrap();
+        assert_eq!(vm.global("c"), Some(&Value::Number(3.0)));
+    }
 }
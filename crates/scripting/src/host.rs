@@ -0,0 +1,494 @@
+//! Script host
+//!
+//! Owns the server-wide script state: the global script context and
+//! every compiled script, keyed by owner name (weapon, class, NPC).
+//! Level NPC scripts are registered under [`npc_script_name`].
+//!
+//! Serverside GS2 runs ([`ScriptHost::eval_gs2`], the console's `:gs2`) are
+//! held to the host's memory limit, and the heap statistics of each script's
+//! last run are kept with its run time ([`ScriptHost::stats`]). Weapons and
+//! classes run GS1 here, which keeps no value heap; their GS2 runs on the
+//! clients, so only Lua weapons have memory to report.
+//!
+//! With the `lua` feature, Lua scripts ([`crate::lua`]) are loaded with
+//! [`ScriptHost::load_lua`] and run by the same event calls; they are held to
+//! the same memory limit and to the Lua instruction limit.
+
+use crate::context::{ScriptContext, ScriptErrorReport};
+use crate::error::{Result, ScriptError};
+use crate::gs1::{GS1Interpreter, GS1Script};
+use crate::gs2::{Chunk, HeapStats, Value, VM};
+#[cfg(feature = "lua")]
+use crate::lua::{LuaLimits, LuaScript};
+use dashmap::DashMap;
+use gserver_core::PlayerID;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Get the name a level NPC's script is registered under
+pub fn npc_script_name(id: u32) -> String {
+    format!("npc{}", id)
+}
+
+/// Get the level NPC a script belongs to, from its name
+pub fn npc_script_id(name: &str) -> Option<u32> {
+    name.strip_prefix("npc")?.parse().ok()
+}
+
+/// Run time and memory of one script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptStats {
+    /// Owner name
+    pub name: String,
+
+    /// Time spent running it since startup
+    pub run_time: Duration,
+
+    /// Heap of its last GS2 run
+    pub heap: HeapStats,
+
+    /// Runs and compiles that failed since startup
+    pub errors: u64,
+}
+
+/// Server-wide script host
+///
+/// Shared between all connections; compiled scripts are cached so that
+/// each weapon/NPC is parsed once and executed many times.
+#[derive(Debug, Default)]
+pub struct ScriptHost {
+    /// Global context (server.* variables)
+    context: ScriptContext,
+
+    /// Compiled GS1 scripts by owner name
+    scripts: DashMap<String, Arc<GS1Script>>,
+
+    /// Time spent running each script since startup
+    run_times: DashMap<String, Duration>,
+
+    /// Heap statistics of each script's last GS2 run
+    heaps: DashMap<String, HeapStats>,
+
+    /// Failed runs and compiles of each script since startup
+    errors: DashMap<String, u64>,
+
+    /// Most bytes a GS2 run's values may hold, 0 for no limit
+    memory_limit: AtomicUsize,
+
+    /// Most instructions one GS2 run may execute, 0 for no limit
+    gs2_instruction_limit: AtomicU64,
+
+    /// Language of each player (from PLI_LANGUAGE), for `playerlanguage`
+    languages: DashMap<PlayerID, String>,
+
+    /// Lua scripts by owner name
+    #[cfg(feature = "lua")]
+    lua_scripts: DashMap<String, Arc<LuaScript>>,
+
+    /// Most instructions one Lua run may execute, 0 for no limit
+    #[cfg(feature = "lua")]
+    instruction_limit: AtomicU64,
+}
+
+impl ScriptHost {
+    /// Create an empty script host
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the global script context
+    pub fn context(&self) -> &ScriptContext {
+        &self.context
+    }
+
+    /// Set the language the scripts run for a player see
+    pub fn set_player_language(&self, player: PlayerID, language: &str) {
+        self.languages.insert(player, language.to_string());
+    }
+
+    /// Forget the language of a player that left
+    pub fn forget_player(&self, player: PlayerID) {
+        self.languages.remove(&player);
+    }
+
+    /// Get the script context of a run for a player
+    fn player_context(&self, name: &str, player: PlayerID) -> ScriptContext {
+        let mut context = self.script_context(name);
+        context.set_player(player);
+        if let Some(language) = self.languages.get(&player) {
+            context.set_language(language.clone());
+        }
+        context
+    }
+
+    /// Compile and register a GS1 script, replacing any previous version
+    pub fn load_script(&self, name: &str, source: &str) -> Result<Arc<GS1Script>> {
+        let result = GS1Script::parse(name.to_string(), source);
+        self.record_result(name, &result, None, None);
+        let script = Arc::new(result?);
+        self.scripts.insert(name.to_string(), script.clone());
+        Ok(script)
+    }
+
+    /// Load a Lua script and run its top-level code, replacing any previous version
+    #[cfg(feature = "lua")]
+    pub fn load_lua(&self, name: &str, source: &str) -> Result<Arc<LuaScript>> {
+        let started = Instant::now();
+        let result = LuaScript::load(name, source, self.script_context(name), self.lua_limits());
+        self.record_run_time(name, started.elapsed());
+        self.record_result(name, &result, None, None);
+        let script = Arc::new(result?);
+        self.record_lua_memory(&script);
+        self.lua_scripts.insert(name.to_string(), script.clone());
+        Ok(script)
+    }
+
+    /// Limit the instructions each Lua run may execute (0 for no limit)
+    #[cfg(feature = "lua")]
+    pub fn set_instruction_limit(&self, count: u64) {
+        self.instruction_limit.store(count, Ordering::Relaxed);
+    }
+
+    /// Remove a script
+    pub fn unload_script(&self, name: &str) -> bool {
+        #[cfg(feature = "lua")]
+        if self.lua_scripts.remove(name).is_some() {
+            return true;
+        }
+        self.scripts.remove(name).is_some()
+    }
+
+    /// Get a compiled script
+    pub fn get_script(&self, name: &str) -> Option<Arc<GS1Script>> {
+        self.scripts.get(name).map(|s| s.clone())
+    }
+
+    /// Get the number of loaded scripts
+    pub fn script_count(&self) -> usize {
+        #[cfg(feature = "lua")]
+        return self.scripts.len() + self.lua_scripts.len();
+        #[cfg(not(feature = "lua"))]
+        self.scripts.len()
+    }
+
+    /// Check if a loaded script handles an event
+    pub fn has_event(&self, name: &str, event: &str) -> bool {
+        #[cfg(feature = "lua")]
+        if let Some(script) = self.lua_scripts.get(name) {
+            return script.handles(event);
+        }
+        self.get_script(name).is_some_and(|s| s.events.contains_key(event))
+    }
+
+    /// Run an event of a loaded script
+    pub fn trigger_event(&self, name: &str, event: &str) -> Result<()> {
+        #[cfg(feature = "lua")]
+        if let Some(script) = self.get_lua(name) {
+            return self.run_lua(&script, event, self.script_context(name)).map(|_| ());
+        }
+
+        let script = self.get_script(name).ok_or_else(|| {
+            ScriptError::RuntimeError(format!("Script not found: {}", name))
+        })?;
+
+        let started = Instant::now();
+        let mut interpreter = GS1Interpreter::new(self.script_context(name));
+        let result = interpreter.execute(&script, event);
+        self.record_run_time(name, started.elapsed());
+        self.record_result(name, &result, interpreter.line(), None);
+        result
+    }
+
+    /// Run an event of a loaded script for a player, if the script has it
+    ///
+    /// # Returns
+    /// `false` if the script isn't loaded or doesn't handle the event
+    pub fn trigger_player_event(&self, name: &str, event: &str, player: PlayerID) -> Result<bool> {
+        #[cfg(feature = "lua")]
+        if let Some(script) = self.get_lua(name) {
+            if !script.handles(event) {
+                return Ok(false);
+            }
+            return self.run_lua(&script, event, self.player_context(name, player));
+        }
+
+        let Some(script) = self.get_script(name) else { return Ok(false) };
+        if !script.events.contains_key(event) {
+            return Ok(false);
+        }
+
+        let started = Instant::now();
+        let mut interpreter = GS1Interpreter::new(self.player_context(name, player));
+        let result = interpreter.execute(&script, event);
+        self.record_run_time(name, started.elapsed());
+        self.record_result(name, &result, interpreter.line(), Some(player));
+        result.map(|()| true)
+    }
+
+    /// Get a loaded Lua script
+    #[cfg(feature = "lua")]
+    pub fn get_lua(&self, name: &str) -> Option<Arc<LuaScript>> {
+        self.lua_scripts.get(name).map(|s| s.clone())
+    }
+
+    #[cfg(feature = "lua")]
+    fn lua_limits(&self) -> LuaLimits {
+        LuaLimits {
+            memory: self.memory_limit.load(Ordering::Relaxed),
+            instructions: self.instruction_limit.load(Ordering::Relaxed),
+        }
+    }
+
+    #[cfg(feature = "lua")]
+    fn run_lua(&self, script: &LuaScript, event: &str, context: ScriptContext) -> Result<bool> {
+        let started = Instant::now();
+        let player = context.player();
+        let result = script.call(event, context, self.lua_limits());
+        self.record_run_time(script.name(), started.elapsed());
+        self.record_result(script.name(), &result, None, player);
+        self.record_lua_memory(script);
+        match &result {
+            Err(ScriptError::Timeout) => tracing::warn!("Lua script {} stopped at its instruction limit in {}", script.name(), event),
+            Err(ScriptError::MemoryLimit { used, limit }) => {
+                tracing::warn!("Lua script {} stopped at {} bytes, over its {} byte limit", script.name(), used, limit)
+            }
+            _ => {}
+        }
+        result
+    }
+
+    /// Report a Lua state's memory as the script's heap
+    #[cfg(feature = "lua")]
+    fn record_lua_memory(&self, script: &LuaScript) {
+        let used = script.used_memory();
+        let mut heap = self.heaps.entry(script.name().to_string()).or_default();
+        heap.live_bytes = used;
+        heap.peak_bytes = heap.peak_bytes.max(used);
+    }
+
+    /// Get the time spent running a script since startup
+    pub fn run_time(&self, name: &str) -> Duration {
+        self.run_times.get(name).map(|t| *t).unwrap_or_default()
+    }
+
+    /// Limit the bytes each GS2 run's values may hold (0 for no limit)
+    pub fn set_memory_limit(&self, bytes: usize) {
+        self.memory_limit.store(bytes, Ordering::Relaxed);
+    }
+
+    /// Limit the instructions each GS2 run may execute (0 for no limit)
+    pub fn set_gs2_instruction_limit(&self, count: u64) {
+        self.gs2_instruction_limit.store(count, Ordering::Relaxed);
+    }
+
+    /// Run GS2 bytecode for a script with global variables set beforehand,
+    /// within the memory and instruction limits
+    ///
+    /// The run's heap statistics replace the script's previous ones, also
+    /// when it fails for going over the limit.
+    ///
+    /// # Returns
+    /// The result and the globals after the run
+    pub fn eval_gs2(&self, name: &str, chunk: Chunk, globals: HashMap<String, Value>) -> Result<(Value, HashMap<String, Value>)> {
+        let mut vm = self.vm(chunk);
+        for (variable, value) in globals {
+            vm.set_global(&variable, value);
+        }
+        let value = self.run_vm(name, &mut vm)?;
+        Ok((value, vm.take_globals()))
+    }
+
+    fn vm(&self, chunk: Chunk) -> VM {
+        let vm = match self.memory_limit.load(Ordering::Relaxed) {
+            0 => VM::new(chunk),
+            limit => VM::new(chunk).with_memory_limit(limit),
+        };
+        match self.gs2_instruction_limit.load(Ordering::Relaxed) {
+            0 => vm,
+            limit => vm.with_instruction_limit(limit),
+        }
+    }
+
+    fn run_vm(&self, name: &str, vm: &mut VM) -> Result<Value> {
+        let started = Instant::now();
+        let result = vm.interpret();
+        self.record_run_time(name, started.elapsed());
+        self.heaps.insert(name.to_string(), vm.heap_stats());
+        self.record_result(name, &result, vm.line(), None);
+        match &result {
+            Err(ScriptError::MemoryLimit { used, limit }) => {
+                tracing::warn!("Script {} stopped at {} bytes, over its {} byte limit", name, used, limit);
+            }
+            Err(ScriptError::Timeout) => tracing::warn!("Script {} stopped at its instruction limit", name),
+            _ => {}
+        }
+        result
+    }
+
+    /// Get the heap statistics of a script's last GS2 run
+    pub fn heap_stats(&self, name: &str) -> HeapStats {
+        self.heaps.get(name).map(|h| *h).unwrap_or_default()
+    }
+
+    /// Get the run time and memory of every script that ran, sorted by name
+    pub fn stats(&self) -> Vec<ScriptStats> {
+        let mut stats: Vec<ScriptStats> = self.run_times.iter()
+            .map(|entry| ScriptStats {
+                name: entry.key().clone(),
+                run_time: *entry.value(),
+                heap: self.heap_stats(entry.key()),
+                errors: self.error_count(entry.key()),
+            })
+            .collect();
+        stats.sort_by(|a, b| a.name.cmp(&b.name));
+        stats
+    }
+
+    /// Get the failed runs and compiles of a script since startup
+    pub fn error_count(&self, name: &str) -> u64 {
+        self.errors.get(name).map(|e| *e).unwrap_or_default()
+    }
+
+    /// Get the failed runs and compiles of every script since startup
+    pub fn total_errors(&self) -> u64 {
+        self.errors.iter().map(|e| *e.value()).sum()
+    }
+
+    fn record_run_time(&self, name: &str, elapsed: Duration) {
+        *self.run_times.entry(name.to_string()).or_default() += elapsed;
+    }
+
+    /// Count a failed compile or run and report it to the installed
+    /// [`ScriptErrorHandler`](crate::ScriptErrorHandler)
+    fn record_result<T>(&self, name: &str, result: &Result<T>, line: Option<usize>, player: Option<PlayerID>) {
+        let Err(error) = result else { return };
+        *self.errors.entry(name.to_string()).or_default() += 1;
+
+        let line = match error {
+            ScriptError::ParseError { line, .. } | ScriptError::SyntaxError { line, .. } => Some(*line),
+            _ => line,
+        };
+        let report = ScriptErrorReport { script: name.to_string(), line, player, message: error.to_string() };
+        tracing::debug!("Script error in {}", report);
+        if let Some(handler) = self.context.script_errors() {
+            handler.report(&report);
+        }
+    }
+
+    /// Get the context a script runs in (knowing its NPC, if it has one)
+    fn script_context(&self, name: &str) -> ScriptContext {
+        let mut context = self.context.clone();
+        if let Some(npc) = npc_script_id(name) {
+            context.set_npc(npc);
+        }
+        context
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_and_unload() {
+        let host = ScriptHost::new();
+        host.load_script("-test", "").unwrap();
+
+        assert_eq!(host.script_count(), 1);
+        assert!(host.get_script("-test").is_some());
+        assert!(host.trigger_event("-missing", "created").is_err());
+        assert!(host.unload_script("-test"));
+        assert_eq!(host.script_count(), 0);
+    }
+
+    #[test]
+    fn test_trigger_player_event() {
+        let host = ScriptHost::new();
+        host.load_script(&npc_script_name(3), "this.hits = 1").unwrap();
+
+        assert!(host.trigger_player_event("npc3", "created", PlayerID(1)).unwrap());
+        assert!(!host.trigger_player_event("npc3", "washit", PlayerID(1)).unwrap());
+        assert!(!host.trigger_player_event("npc4", "washit", PlayerID(1)).unwrap());
+        assert_eq!(host.run_time("npc4"), Duration::ZERO);
+        assert_eq!((npc_script_id("npc3"), npc_script_id("-npc3"), npc_script_id("npcdoor")), (Some(3), None, None));
+    }
+
+    #[test]
+    fn test_player_language() {
+        let host = ScriptHost::new();
+        assert_eq!(host.player_context("npc3", PlayerID(1)).language(), None);
+
+        host.set_player_language(PlayerID(1), "Deutsch");
+        assert_eq!(host.player_context("npc3", PlayerID(1)).language(), Some("Deutsch"));
+        assert_eq!(host.player_context("npc3", PlayerID(2)).language(), None);
+
+        host.forget_player(PlayerID(1));
+        assert_eq!(host.player_context("npc3", PlayerID(1)).language(), None);
+    }
+
+    #[derive(Debug, Default)]
+    struct RecordingErrors(std::sync::Mutex<Vec<ScriptErrorReport>>);
+
+    impl crate::ScriptErrorHandler for RecordingErrors {
+        fn report(&self, error: &ScriptErrorReport) {
+            self.0.lock().unwrap().push(error.clone());
+        }
+    }
+
+    #[test]
+    fn test_error_reports() {
+        let host = ScriptHost::new();
+        let errors = Arc::new(RecordingErrors::default());
+        host.context().set_script_error_handler(errors.clone());
+
+        assert!(host.load_script("-broken", "x = 1\nif a").is_err());
+        let reports = errors.0.lock().unwrap().clone();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].to_string(), "-broken line 2: Parse error at line 2: If statement missing 'then'");
+        assert_eq!(host.error_count("-broken"), 1);
+    }
+
+    #[test]
+    fn test_gs2_memory_limit() {
+        use crate::gs2::OpCode;
+
+        // -grow = "{big string}"
+        let mut chunk = Chunk::new();
+        let value = chunk.add_constant(Value::String("x".repeat(4096)));
+        let name = chunk.add_constant(Value::String("grow".into()));
+        chunk.write_op(OpCode::OpConst, 0);
+        chunk.write(value as u8, 0);
+        chunk.write_op(OpCode::OpSetGlobal, 0);
+        chunk.write(name as u8, 0);
+
+        let host = ScriptHost::new();
+        host.eval_gs2("-grow", chunk.clone(), HashMap::new()).unwrap();
+        assert!(host.heap_stats("-grow").live_bytes > 4096);
+
+        host.set_memory_limit(1024);
+        assert!(matches!(host.eval_gs2("-grow", chunk, HashMap::new()), Err(ScriptError::MemoryLimit { limit: 1024, .. })));
+        let stats = host.stats();
+        assert_eq!((stats.len(), stats[0].name.as_str()), (1, "-grow"));
+        assert_eq!((stats[0].heap.collections, stats[0].errors), (1, 1));
+        assert_eq!(host.total_errors(), 1);
+    }
+
+    #[cfg(feature = "lua")]
+    #[test]
+    fn test_lua_events() {
+        let host = ScriptHost::new();
+        host.set_instruction_limit(50_000);
+        host.load_lua("npc5", "function onWasHit(player) server.set('hitby', player) end\nfunction onLoop() while true do end end").unwrap();
+
+        assert!(host.has_event("npc5", "washit") && !host.has_event("npc5", "created"));
+        assert!(host.trigger_player_event("npc5", "washit", PlayerID(2)).unwrap());
+        assert!(!host.trigger_player_event("npc5", "created", PlayerID(2)).unwrap());
+        assert_eq!(host.context().get_global("hitby").as_deref(), Some("2"));
+        assert!(matches!(host.trigger_event("npc5", "loop"), Err(ScriptError::Timeout)));
+        assert!(host.heap_stats("npc5").live_bytes > 0);
+        assert_eq!(host.script_count(), 1);
+        assert!(host.unload_script("npc5"));
+    }
+}
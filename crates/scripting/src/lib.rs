@@ -7,6 +7,8 @@
 //! - GS2 compiler and bytecode VM
 //! - 200+ built-in functions
 //! - Variable scoping and events
+//! - GS1 to GS2 transpiler for migrating content ([`transpile`])
+//! - Lua backend for serverside systems, with the `lua` feature (`lua`)
 //!
 //! ## Script Types
 //!
@@ -25,8 +27,13 @@ pub mod gs1;
 pub mod gs2;
 pub mod context;
 pub mod builtins;
+pub mod host;
+pub mod transpile;
+#[cfg(feature = "lua")]
+pub mod lua;
 
 pub use error::{ScriptError, Result};
 pub use gs1::{GS1Script, GS1Interpreter, EventType};
 pub use gs2::{Parser as GS2Parser, Compiler as GS2Compiler, VM as GS2VM};
-pub use context::ScriptContext;
+pub use context::{AdminMessageHandler, AmbienceHandler, IrcHandler, ModerationHandler, NpcControlHandler, NpcStateHandler, PlayerControlHandler, Projectile, ScriptContext, ScriptErrorHandler, ScriptErrorReport};
+pub use host::{npc_script_id, npc_script_name, ScriptHost, ScriptStats};
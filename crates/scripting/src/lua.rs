@@ -0,0 +1,309 @@
+//! # Lua scripts
+//!
+//! Built with the `lua` feature, serverside systems can be written in Lua 5.4
+//! next to GS1 and GS2. A Lua script is loaded into the [`crate::ScriptHost`]
+//! under an owner name like any other script, and its events run through the
+//! same `trigger_event` / `trigger_player_event` calls: event `washit` runs
+//! the global function `onWasHit` (matched ignoring case), given the player
+//! id when there is one.
+//!
+//! Each script has its own Lua state, which keeps its globals between
+//! events. The state is sandboxed:
+//!
+//! - Only the `table`, `string`, `math` and `utf8` libraries are loaded, and
+//!   `dofile`, `loadfile`, `load`, `require` and `collectgarbage` are removed;
+//!   there is no file, OS or module access
+//! - Its memory is held to the host's script memory limit
+//! - Each run (the top-level code when loading, then each event) may execute
+//!   at most the host's Lua instruction limit, or stops with
+//!   [`ScriptError::Timeout`]
+//!
+//! The GS1 built-in functions are exposed with their GS1 names, so Lua sees
+//! the same player, level, NPC, IRC, ambience and moderation API, acting on
+//! the player, level and NPC of the event:
+//!
+//! ```lua
+//! function onPlayerEnters(player)
+//!     if server.get("lockdown") == "1" then
+//!         fullstop()
+//!     end
+//!     sendrpgmessage("Welcome to " .. levelname(), player)
+//! end
+//! ```
+//!
+//! Arguments are passed to them as GS1 strings, and results that look like
+//! numbers come back as numbers. `server.get(name)` / `server.set(name, value)`
+//! read and write the server variables GS1 scripts share, and `print` writes
+//! to the server log.
+
+use crate::builtins::Builtins;
+use crate::context::ScriptContext;
+use crate::error::{Result, ScriptError};
+use mlua::{Function, HookTriggers, Lua, LuaOptions, StdLib, Table, Value as LuaValue, Variadic, VmState};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Instructions between budget checks
+const HOOK_INSTRUCTIONS: u32 = 1000;
+
+/// Base library functions a sandboxed script can't use
+const REMOVED_GLOBALS: [&str; 5] = ["dofile", "loadfile", "load", "require", "collectgarbage"];
+
+/// Budget of one Lua run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LuaLimits {
+    /// Most bytes the script's state may hold, 0 for no limit
+    pub memory: usize,
+
+    /// Most instructions the run may execute, 0 for no limit
+    pub instructions: u64,
+}
+
+/// A loaded Lua script
+pub struct LuaScript {
+    /// Owner name
+    name: String,
+
+    /// The script's state, one run at a time
+    lua: Mutex<Lua>,
+
+    /// Lowercase event name → handler function name
+    events: HashMap<String, String>,
+}
+
+impl LuaScript {
+    /// Create a script's state and run its top-level code
+    ///
+    /// # Arguments
+    /// * `name` - Owner name, also used in error messages and the log
+    /// * `source` - Lua source
+    /// * `context` - Context the top-level code runs in
+    /// * `limits` - Budget of the top-level code
+    pub fn load(name: &str, source: &str, context: ScriptContext, limits: LuaLimits) -> Result<Self> {
+        let lua = Lua::new_with(StdLib::TABLE | StdLib::STRING | StdLib::MATH | StdLib::UTF8, LuaOptions::default())
+            .map_err(runtime_error)?;
+        install_api(&lua, name).map_err(runtime_error)?;
+
+        let function = lua.load(source).set_name(format!("={}", name)).into_function()
+            .map_err(|e| syntax_error(name, e))?;
+        run(&lua, context, limits, || function.call::<()>(()))?;
+
+        let mut events = HashMap::new();
+        for pair in lua.globals().pairs::<LuaValue, LuaValue>() {
+            let (key, value) = pair.map_err(runtime_error)?;
+            let Some(function) = key.as_string().and_then(|s| s.to_str().ok()).map(|s| s.to_string()) else { continue };
+            if let Some(event) = function.strip_prefix("on").filter(|e| !e.is_empty() && value.is_function()) {
+                events.insert(event.to_lowercase(), function.clone());
+            }
+        }
+
+        Ok(Self { name: name.to_string(), lua: Mutex::new(lua), events })
+    }
+
+    /// Get the owner name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Check if the script has a handler for an event
+    pub fn handles(&self, event: &str) -> bool {
+        self.events.contains_key(&event.to_lowercase())
+    }
+
+    /// Get the bytes the script's state holds
+    pub fn used_memory(&self) -> usize {
+        self.lua.lock().used_memory()
+    }
+
+    /// Run the handler of an event, if the script has one
+    ///
+    /// # Returns
+    /// `false` if the script doesn't handle the event
+    pub fn call(&self, event: &str, context: ScriptContext, limits: LuaLimits) -> Result<bool> {
+        let Some(handler) = self.events.get(&event.to_lowercase()) else { return Ok(false) };
+        let player = context.player().map(|p| p.0);
+        let lua = self.lua.lock();
+        let function: Function = lua.globals().get(handler.as_str()).map_err(runtime_error)?;
+        run(&lua, context, limits, || function.call::<()>(player))?;
+        Ok(true)
+    }
+}
+
+impl std::fmt::Debug for LuaScript {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut events: Vec<&String> = self.events.values().collect();
+        events.sort();
+        f.debug_struct("LuaScript").field("name", &self.name).field("events", &events).finish()
+    }
+}
+
+/// Remove the unsafe globals and add the GS1 built-ins, `server` and `print`
+fn install_api(lua: &Lua, name: &str) -> mlua::Result<()> {
+    let globals = lua.globals();
+    for removed in REMOVED_GLOBALS {
+        globals.set(removed, LuaValue::Nil)?;
+    }
+
+    let builtins = Arc::new(Builtins::new());
+    for function in builtins.names() {
+        let (builtins, builtin) = (builtins.clone(), function.to_string());
+        let callback = lua.create_function(move |lua, args: Variadic<LuaValue>| {
+            let args = args.iter().map(to_gs1).collect::<mlua::Result<Vec<String>>>()?;
+            let context = lua.app_data_ref::<ScriptContext>().ok_or_else(|| mlua::Error::runtime("no script context"))?;
+            let result = builtins.call(&context, &builtin, &args).map_err(mlua::Error::external)?;
+            from_gs1(lua, result)
+        })?;
+        // irc.join → irc table, join field
+        match function.split_once('.') {
+            Some((table, field)) => namespace(lua, &globals, table)?.set(field, callback)?,
+            None => globals.set(function, callback)?,
+        }
+    }
+
+    let server = namespace(lua, &globals, "server")?;
+    server.set("get", lua.create_function(|lua, name: String| {
+        Ok(lua.app_data_ref::<ScriptContext>().and_then(|context| context.get_global(&name)))
+    })?)?;
+    server.set("set", lua.create_function(|lua, (name, value): (String, LuaValue)| {
+        if let Some(context) = lua.app_data_ref::<ScriptContext>() {
+            context.set_global(name, to_gs1(&value)?);
+        }
+        Ok(())
+    })?)?;
+
+    let owner = name.to_string();
+    globals.set("print", lua.create_function(move |_, args: Variadic<LuaValue>| {
+        let text = args.iter().map(to_gs1).collect::<mlua::Result<Vec<String>>>()?.join("\t");
+        tracing::info!("[{}] {}", owner, text);
+        Ok(())
+    })?)?;
+    Ok(())
+}
+
+/// Get a global table, creating it if needed
+fn namespace(lua: &Lua, globals: &Table, name: &str) -> mlua::Result<Table> {
+    if let Some(table) = globals.get::<Option<Table>>(name)? {
+        return Ok(table);
+    }
+    let table = lua.create_table()?;
+    globals.set(name, &table)?;
+    Ok(table)
+}
+
+/// Run Lua code in a context, within a budget
+fn run<R>(lua: &Lua, context: ScriptContext, limits: LuaLimits, body: impl FnOnce() -> mlua::Result<R>) -> Result<R> {
+    lua.set_app_data(context);
+    lua.set_memory_limit(limits.memory).map_err(runtime_error)?;
+
+    let exhausted = Arc::new(AtomicBool::new(false));
+    if limits.instructions > 0 {
+        let (spent, flag) = (AtomicU64::new(0), exhausted.clone());
+        lua.set_hook(HookTriggers::new().every_nth_instruction(HOOK_INSTRUCTIONS), move |_, _| {
+            if spent.fetch_add(HOOK_INSTRUCTIONS as u64, Ordering::Relaxed) + HOOK_INSTRUCTIONS as u64 > limits.instructions {
+                flag.store(true, Ordering::Relaxed);
+                return Err(mlua::Error::runtime("instruction limit reached"));
+            }
+            Ok(VmState::Continue)
+        });
+    } else {
+        lua.remove_hook();
+    }
+
+    match body() {
+        Ok(result) => Ok(result),
+        Err(_) if exhausted.load(Ordering::Relaxed) => Err(ScriptError::Timeout),
+        Err(mlua::Error::MemoryError(_)) => Err(ScriptError::MemoryLimit { used: lua.used_memory(), limit: limits.memory }),
+        Err(e) => Err(runtime_error(e)),
+    }
+}
+
+/// Convert a Lua argument to the string a GS1 built-in takes
+fn to_gs1(value: &LuaValue) -> mlua::Result<String> {
+    Ok(match value {
+        LuaValue::Nil => String::new(),
+        LuaValue::Boolean(b) => if *b { "1" } else { "0" }.to_string(),
+        LuaValue::Integer(i) => i.to_string(),
+        LuaValue::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => (*n as i64).to_string(),
+        LuaValue::Number(n) => n.to_string(),
+        LuaValue::String(s) => s.to_str()?.to_string(),
+        other => return Err(mlua::Error::runtime(format!("can't pass a {} to a built-in", other.type_name()))),
+    })
+}
+
+/// Convert a GS1 built-in's result to a Lua value: nil, a number or a string
+fn from_gs1(lua: &Lua, result: String) -> mlua::Result<LuaValue> {
+    if result.is_empty() {
+        return Ok(LuaValue::Nil);
+    }
+    match result.parse::<f64>() {
+        Ok(number) => Ok(LuaValue::Number(number)),
+        Err(_) => Ok(LuaValue::String(lua.create_string(&result)?)),
+    }
+}
+
+fn runtime_error(error: mlua::Error) -> ScriptError {
+    ScriptError::RuntimeError(error.to_string())
+}
+
+/// Convert a compile error (`name:line: message`) to a parse error
+fn syntax_error(name: &str, error: mlua::Error) -> ScriptError {
+    let mlua::Error::SyntaxError { message, .. } = &error else { return runtime_error(error) };
+    let located = message.strip_prefix(name).and_then(|m| m.strip_prefix(':')).and_then(|m| m.split_once(':'));
+    match located.and_then(|(line, rest)| Some((line.parse().ok()?, rest.trim()))) {
+        Some((line, message)) => ScriptError::ParseError { line, message: message.to_string() },
+        None => ScriptError::ParseError { line: 0, message: message.clone() },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::AdminMessageHandler;
+    use gserver_core::PlayerID;
+
+    #[derive(Debug, Default)]
+    struct Messages(Mutex<Vec<(Option<PlayerID>, String)>>);
+
+    impl AdminMessageHandler for Messages {
+        fn send(&self, player: Option<PlayerID>, message: &str) {
+            self.0.lock().push((player, message.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_lua_script() {
+        let context = ScriptContext::new();
+        let messages = Arc::new(Messages::default());
+        context.set_admin_message_handler(messages.clone());
+        let source = "hits = 0\n\
+            function onWasHit(player)\n\
+                hits = hits + 1\n\
+                server.set(\"hits\", hits)\n\
+                sendrpgmessage(\"Ouch \" .. hits, player)\n\
+            end\n\
+            function onSpin() while true do end end";
+        let limits = LuaLimits { memory: 1 << 20, instructions: 100_000 };
+        let script = LuaScript::load("-target", source, context.clone(), limits).unwrap();
+        assert!(script.handles("washit") && !script.handles("created"));
+
+        let mut player_context = context.clone();
+        player_context.set_player(PlayerID(7));
+        assert!(script.call("washit", player_context.clone(), limits).unwrap());
+        assert!(script.call("WASHIT", player_context, limits).unwrap());
+        assert!(!script.call("created", context.clone(), limits).unwrap());
+        assert_eq!(context.get_global("hits").as_deref(), Some("2"));
+        assert_eq!(messages.0.lock().last(), Some(&(Some(PlayerID(7)), "Ouch 2".to_string())));
+
+        assert!(matches!(script.call("spin", context.clone(), limits), Err(ScriptError::Timeout)));
+        assert!(matches!(LuaScript::load("-io", "io.open('x')", context.clone(), limits), Err(ScriptError::RuntimeError(_))));
+        assert!(matches!(LuaScript::load("-load", "load('return 1')()", context.clone(), limits), Err(ScriptError::RuntimeError(_))));
+        assert!(matches!(LuaScript::load("-big", "t = string.rep('x', 4 << 20)", context.clone(), limits),
+            Err(ScriptError::MemoryLimit { .. })));
+        match LuaScript::load("-bad", "x = 1\nfunction (", context, limits) {
+            Err(ScriptError::ParseError { line, .. }) => assert_eq!(line, 2),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+}
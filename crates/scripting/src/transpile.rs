@@ -0,0 +1,895 @@
+//! GS1 to GS2 transpiler
+//!
+//! Converts the common GS1 constructs of old weapons and NPCs to GS2 source,
+//! to help migrating content to `gs2default=true`. What it can't convert is
+//! kept as a `// GS1:` comment and listed in [`Transpiled::untranslated`],
+//! so the result is a starting point to finish by hand, not a guarantee.
+//!
+//! # Conversions
+//! - Event blocks: `if (playerenters) {...}` becomes `function onPlayerEnters() {...}`;
+//!   `if (playerchats && cond)` puts the rest of the condition inside,
+//!   `if (created || playerenters)` puts the block in both handlers, and the
+//!   blocks of an event are joined into one handler in script order
+//! - Commands: `message text;` becomes `this.chat = "text";`, `setplayerprop #c,text;`
+//!   becomes `player.chat = "text";`, `setstring name,text;` becomes an assignment,
+//!   `timeout = 1;` becomes `setTimer(1);`, and the commands in [`COMMANDS`]
+//!   become calls
+//! - Expressions: `playerx` and the other player variables become `player.*`,
+//!   the message codes `#c #a #n #g #L #s(name) #v(expr)` become expressions,
+//!   `strequals`, `strcontains` and `strlen` become their GS2 forms, and
+//!   `x += 1` and `x++` become `x = x + 1`
+//!
+//! Text arguments with message codes become `format()` calls, e.g.
+//! `message Hi #n;` becomes `this.chat = format("Hi %s", player.nick);`.
+//! The output only uses what [`gs2::Parser`](crate::gs2::Parser) accepts.
+//!
+//! Code outside an event block ran on every GS1 event; it is converted but
+//! reported, since GS2 only runs it at load. GS1 runs one event at a time, so
+//! `else if (event)` after an event block is just another event block; any
+//! other `else` of an event block is reported.
+
+use std::fmt::Write as _;
+
+/// How a command argument is converted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgKind {
+    /// Text with message codes, becomes a string
+    Text,
+    /// Expression
+    Expr,
+    /// The rest of the command, commas included, as text
+    Rest,
+}
+
+use ArgKind::{Expr, Rest, Text};
+
+/// GS1 commands that become GS2 calls of the same name, with their arguments
+pub const COMMANDS: &[(&str, &[ArgKind])] = &[
+    ("hide", &[]),
+    ("show", &[]),
+    ("destroy", &[]),
+    ("dontblock", &[]),
+    ("blockagain", &[]),
+    ("drawoverplayer", &[]),
+    ("drawunderplayer", &[]),
+    ("drawaslight", &[]),
+    ("disabledefmovement", &[]),
+    ("enabledefmovement", &[]),
+    ("sleep", &[Expr]),
+    ("freezeplayer", &[Expr]),
+    ("hideimg", &[Expr]),
+    ("setimg", &[Text]),
+    ("setimgpart", &[Text, Expr, Expr, Expr, Expr]),
+    ("setani", &[Text, Text]),
+    ("setcharani", &[Text, Text]),
+    ("setlevel2", &[Text, Expr, Expr]),
+    ("play", &[Text]),
+    ("toweapons", &[Text]),
+    ("set", &[Text]),
+    ("unset", &[Text]),
+    ("say2", &[Rest]),
+];
+
+/// GS1 events and their GS2 handlers (`actionname` becomes `onActionName`)
+const EVENTS: &[(&str, &str)] = &[
+    ("created", "onCreated"),
+    ("playerenters", "onPlayerEnters"),
+    ("playerleaves", "onPlayerLeaves"),
+    ("playertouchsme", "onPlayerTouchsMe"),
+    ("playerchats", "onPlayerChats"),
+    ("timeout", "onTimeout"),
+    ("weaponfired", "onWeaponFired"),
+    ("playerhurt", "onPlayerHurt"),
+    ("washit", "onWasHit"),
+];
+
+/// GS1 player variables and their GS2 names
+const VARIABLES: &[(&str, &str)] = &[
+    ("playerx", "player.x"),
+    ("playery", "player.y"),
+    ("playerdir", "player.dir"),
+    ("playerhearts", "player.hearts"),
+    ("playerfullhearts", "player.fullhearts"),
+    ("playerrupees", "player.rupees"),
+    ("playerdarts", "player.darts"),
+    ("playerbombs", "player.bombs"),
+    ("playerap", "player.ap"),
+    ("playermp", "player.mp"),
+];
+
+/// Message codes and their GS2 expressions
+const CODES: &[(char, &str)] = &[
+    ('c', "player.chat"),
+    ('a', "player.account"),
+    ('n', "player.nick"),
+    ('g', "player.guild"),
+    ('L', "player.level.name"),
+];
+
+/// A part of a GS1 script that wasn't converted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Untranslated {
+    /// Line in the GS1 source
+    pub line: usize,
+    /// The GS1 code
+    pub text: String,
+    /// Why it needs a look
+    pub reason: String,
+}
+
+/// Result of [`transpile`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Transpiled {
+    /// GS2 source
+    pub source: String,
+    /// Code that was kept as a comment or needs a look
+    pub untranslated: Vec<Untranslated>,
+}
+
+/// Convert GS1 source to GS2
+pub fn transpile(source: &str) -> Transpiled {
+    let mut writer = Writer::default();
+    let pieces = split(source);
+    let mut pieces = pieces.into_iter().peekable();
+    while let Some(Piece { line, kind }) = pieces.next() {
+        match kind {
+            PieceKind::Comment(text) => writer.emit(&text),
+            PieceKind::Header(header) => {
+                let braced = matches!(pieces.peek(), Some(Piece { kind: PieceKind::Open, .. }));
+                if braced {
+                    pieces.next();
+                }
+                writer.header(line, &header, !braced);
+            }
+            PieceKind::Else => writer.pending_else = true,
+            PieceKind::Open => writer.header(line, "", false),
+            PieceKind::Close => writer.close(),
+            PieceKind::Statement(text) => {
+                writer.statement(line, &text);
+                writer.finish_child();
+            }
+        }
+    }
+    while !writer.frames.is_empty() {
+        writer.close();
+    }
+    Transpiled { source: merge_handlers(writer.lines).join("\n") + "\n", untranslated: writer.untranslated }
+}
+
+/// Join the top-level functions of the same name into the first one
+fn merge_handlers(lines: Vec<String>) -> Vec<String> {
+    let mut chunks: Vec<(Option<String>, Vec<String>)> = Vec::new();
+    let mut lines = lines.into_iter();
+    while let Some(line) = lines.next() {
+        if !(line.starts_with("function ") && line.ends_with('{')) {
+            chunks.push((None, vec![line]));
+            continue;
+        }
+        let body: Vec<String> = lines.by_ref().take_while(|l| l != "}").collect();
+        match chunks.iter_mut().find(|(header, _)| header.as_ref() == Some(&line)) {
+            Some((_, existing)) => existing.extend(body),
+            None => chunks.push((Some(line), body)),
+        }
+    }
+    chunks.into_iter()
+        .flat_map(|(header, body)| match header {
+            Some(header) => std::iter::once(header).chain(body).chain(std::iter::once("}".to_string())).collect(),
+            None => body,
+        })
+        .collect()
+}
+
+/// One piece of GS1 source
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Piece {
+    line: usize,
+    kind: PieceKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PieceKind {
+    /// `// ...`
+    Comment(String),
+    /// `if (...)`, `while (...)`, `for (...)`, or the text before a `{`
+    Header(String),
+    /// `else`
+    Else,
+    /// `{`
+    Open,
+    /// `}`
+    Close,
+    /// Text up to a `;`
+    Statement(String),
+}
+
+/// Split GS1 source into statements, headers and braces
+fn split(source: &str) -> Vec<Piece> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut pieces = Vec::new();
+    let (mut buf, mut buf_line, mut line, mut depth, mut quoted) = (String::new(), 1, 1, 0usize, false);
+    let flush = |buf: &mut String, buf_line: usize, pieces: &mut Vec<Piece>| {
+        let text = buf.trim();
+        if !text.is_empty() {
+            pieces.push(Piece { line: buf_line, kind: PieceKind::Statement(text.to_string()) });
+        }
+        buf.clear();
+    };
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        if buf.trim().is_empty() {
+            buf_line = line;
+        }
+        if c == '\n' {
+            line += 1;
+            buf.push(' ');
+            continue;
+        }
+        if quoted {
+            buf.push(c);
+            quoted = c != '"';
+            continue;
+        }
+        match c {
+            '"' => {
+                buf.push(c);
+                quoted = true;
+            }
+            '/' if chars.get(i) == Some(&'/') && depth == 0 => {
+                let end = chars[i..].iter().position(|&c| c == '\n').map_or(chars.len(), |n| i + n);
+                let comment: String = chars[i - 1..end].iter().collect();
+                pieces.push(Piece { line, kind: PieceKind::Comment(comment.trim_end().to_string()) });
+                i = end;
+            }
+            '(' => {
+                depth += 1;
+                buf.push(c);
+            }
+            ')' => {
+                depth = depth.saturating_sub(1);
+                buf.push(c);
+                let text = buf.trim_start();
+                let keyword = ["if", "while", "for"].into_iter()
+                    .find(|k| text.strip_prefix(k).is_some_and(|rest| rest.trim_start().starts_with('(')));
+                if depth == 0 && keyword.is_some() {
+                    pieces.push(Piece { line: buf_line, kind: PieceKind::Header(text.to_string()) });
+                    buf.clear();
+                }
+            }
+            ';' if depth == 0 => flush(&mut buf, buf_line, &mut pieces),
+            '{' if depth == 0 => {
+                let text = buf.trim();
+                if !text.is_empty() {
+                    pieces.push(Piece { line: buf_line, kind: PieceKind::Header(text.to_string()) });
+                }
+                buf.clear();
+                pieces.push(Piece { line, kind: PieceKind::Open });
+            }
+            '}' if depth == 0 => {
+                flush(&mut buf, buf_line, &mut pieces);
+                pieces.push(Piece { line, kind: PieceKind::Close });
+            }
+            _ => {
+                buf.push(c);
+                let ends_word = chars.get(i).is_none_or(|next| !next.is_alphanumeric() && *next != '_');
+                if buf.trim() == "else" && ends_word && depth == 0 {
+                    pieces.push(Piece { line: buf_line, kind: PieceKind::Else });
+                    buf.clear();
+                }
+            }
+        }
+    }
+    flush(&mut buf, buf_line, &mut pieces);
+    pieces
+}
+
+/// An open block of the output
+#[derive(Debug, Default)]
+struct Frame {
+    /// `}` to write when it closes
+    closes: usize,
+    /// Handlers that get a copy of the block's body when it closes
+    aliases: Vec<String>,
+    /// Index of the body's first line
+    body_start: usize,
+    /// The GS1 block had no braces and ends after one statement
+    braceless: bool,
+    /// The block is an event handler
+    event: bool,
+}
+
+#[derive(Debug, Default)]
+struct Writer {
+    lines: Vec<String>,
+    frames: Vec<Frame>,
+    pending_else: bool,
+    /// The last closed block was an event handler
+    after_event: bool,
+    untranslated: Vec<Untranslated>,
+}
+
+impl Writer {
+    fn depth(&self) -> usize {
+        self.frames.iter().map(|f| f.closes).sum()
+    }
+
+    fn emit(&mut self, text: &str) {
+        self.lines.push(format!("{}{}", "  ".repeat(self.depth()), text));
+    }
+
+    fn report(&mut self, line: usize, text: &str, reason: &str) {
+        self.untranslated.push(Untranslated { line, text: text.to_string(), reason: reason.to_string() });
+    }
+
+    /// Report the `else` of an event block, unless it starts another event block
+    fn else_after_event(&mut self, line: usize, text: &str, is_event: bool) {
+        if !(std::mem::take(&mut self.after_event) && self.pending_else) {
+            return;
+        }
+        self.pending_else = false;
+        if !is_event {
+            self.report(line, format!("else {}", text).trim_end(), "the else of an event block runs on every other event");
+            self.emit("// GS1: else");
+        }
+    }
+
+    /// Open a block for a header (empty for a bare `{`)
+    fn header(&mut self, line: usize, header: &str, braceless: bool) {
+        let is_event = matches!(header_condition(header), Some(("if", c)) if event_block(c).is_some());
+        self.else_after_event(line, header, is_event);
+        let mut frame = Frame { closes: 1, braceless, ..Default::default() };
+        let top_level = self.frames.is_empty();
+        let opening = match header_condition(header) {
+            Some(("if", condition)) if top_level && !self.pending_else => match event_block(condition) {
+                Some((events, rest)) => {
+                    frame.event = true;
+                    frame.aliases = events[1..].to_vec();
+                    let mut opening = format!("function {}() {{", events[0]);
+                    if let Some(rest) = rest {
+                        frame.closes = 2;
+                        opening = format!("{}\n  if ({}) {{", opening, expression(&rest));
+                    }
+                    opening
+                }
+                None => {
+                    self.report(line, header, "runs on every event; move it into the events it's meant for");
+                    format!("if ({}) {{", expression(condition))
+                }
+            },
+            Some(("for", condition)) => {
+                let parts: Vec<String> = split_top_level(condition, ';').iter()
+                    .map(|p| assignment_expression(p.trim()).unwrap_or_else(|| expression(p)))
+                    .collect();
+                format!("for ({}) {{", parts.join("; "))
+            }
+            Some((keyword, condition)) => format!("{} ({}) {{", keyword, expression(condition)),
+            None if header.is_empty() => "{".to_string(),
+            None if header.starts_with("function ") => format!("{} {{", header),
+            None => {
+                self.report(line, header, "unknown block");
+                format!("// GS1: {}\n{}{{", header, "  ".repeat(self.depth()))
+            }
+        };
+        let opening = match std::mem::take(&mut self.pending_else) {
+            true => self.attach_else(opening),
+            false => opening,
+        };
+        self.emit_block_opening(&opening);
+        frame.body_start = self.lines.len() + 1 - opening.lines().count();
+        self.frames.push(frame);
+    }
+
+    /// Write `else` onto the `}` before it
+    fn attach_else(&mut self, opening: String) -> String {
+        let opening = format!("else {}", opening);
+        match self.lines.last_mut() {
+            Some(last) if last.trim() == "}" => {
+                let line = std::mem::take(last);
+                self.lines.pop();
+                format!("{} {}", line.trim(), opening)
+            }
+            _ => opening,
+        }
+    }
+
+    fn emit_block_opening(&mut self, opening: &str) {
+        let indent = "  ".repeat(self.depth());
+        for line in opening.lines() {
+            self.lines.push(format!("{}{}", indent, line));
+        }
+    }
+
+    /// Close the innermost block
+    fn close(&mut self) {
+        let Some(frame) = self.frames.pop() else { return };
+        let base = self.depth();
+        for level in (0..frame.closes).rev() {
+            self.lines.push(format!("{}}}", "  ".repeat(base + level)));
+        }
+        let body = self.lines[frame.body_start.min(self.lines.len() - 1)..self.lines.len() - 1].to_vec();
+        for alias in &frame.aliases {
+            self.lines.push(format!("function {}() {{", alias));
+            self.lines.extend(body.iter().cloned());
+            self.lines.push("}".to_string());
+        }
+        self.after_event = frame.event;
+        self.finish_child();
+    }
+
+    /// Close braceless blocks whose one statement is done
+    fn finish_child(&mut self) {
+        if self.frames.last().is_some_and(|f| f.braceless) {
+            self.close();
+        }
+    }
+
+    /// Convert one statement
+    fn statement(&mut self, line: usize, text: &str) {
+        self.else_after_event(line, text, false);
+        if self.frames.is_empty() {
+            self.report(line, text, "runs on every event; move it into the events it's meant for");
+        }
+        if std::mem::take(&mut self.pending_else) {
+            let opening = self.attach_else("{".to_string());
+            self.emit_block_opening(&opening);
+            self.frames.push(Frame { closes: 1, braceless: false, ..Default::default() });
+            self.statement(line, text);
+            self.close();
+            return;
+        }
+        match convert_statement(text) {
+            Ok(converted) => self.emit(&format!("{};", converted)),
+            Err(reason) => {
+                self.report(line, text, &reason);
+                self.emit(&format!("// GS1: {};", text));
+            }
+        }
+    }
+}
+
+/// Split `if (cond)` into its keyword and condition
+fn header_condition(header: &str) -> Option<(&str, &str)> {
+    let open = header.find('(')?;
+    let keyword = header[..open].trim();
+    if !matches!(keyword, "if" | "while" | "for") {
+        return None;
+    }
+    Some((keyword, header[open + 1..].strip_suffix(')')?))
+}
+
+/// Read an event block condition
+///
+/// # Returns
+/// The GS2 handlers and the rest of the condition, or `None` if the first
+/// term isn't an event
+fn event_block(condition: &str) -> Option<(Vec<String>, Option<String>)> {
+    let terms = split_top_level_str(condition, "&&");
+    let first = terms.first()?.trim();
+    let first = first.strip_prefix('(').and_then(|f| f.strip_suffix(')')).unwrap_or(first);
+    let events: Option<Vec<String>> = split_top_level_str(first, "||").iter().map(|e| event_handler(e.trim())).collect();
+    let events = events?;
+    let rest: Vec<&str> = terms[1..].iter().map(|t| t.trim()).collect();
+    Some((events, (!rest.is_empty()).then(|| rest.join(" && "))))
+}
+
+fn event_handler(event: &str) -> Option<String> {
+    if let Some((_, handler)) = EVENTS.iter().find(|(name, _)| *name == event) {
+        return Some(handler.to_string());
+    }
+    let action = event.strip_prefix("action")?;
+    let mut chars = action.chars();
+    let first = chars.next()?;
+    if !action.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+    Some(format!("onAction{}{}", first.to_uppercase(), chars.as_str()))
+}
+
+/// Convert an assignment, spelling out `+=` and `++`, which GS2 doesn't parse
+///
+/// # Returns
+/// `None` if the text isn't an assignment
+fn assignment_expression(text: &str) -> Option<String> {
+    let step = |target: &str, operator: char| format!("{0} = {0} {1} 1", expression(target.trim()), operator);
+    for operator in ['+', '-'] {
+        let twice = format!("{0}{0}", operator);
+        let target = text.strip_prefix(&twice).or_else(|| text.strip_suffix(&twice))
+            .filter(|t| !t.trim().is_empty() && t.trim().chars().all(|c| c.is_alphanumeric() || "_.[]".contains(c)));
+        if let Some(target) = target {
+            return Some(step(target, operator));
+        }
+    }
+    let (target, operator, value) = assignment(text)?;
+    let (target, value) = (expression(target), expression(value));
+    Some(match operator.strip_suffix('=').filter(|op| !op.is_empty()) {
+        Some(op) if value.contains(|c: char| !c.is_alphanumeric() && c != '.' && c != '_') => {
+            format!("{0} = {0} {1} ({2})", target, op, value)
+        }
+        Some(op) => format!("{0} = {0} {1} {2}", target, op, value),
+        None => format!("{} = {}", target, value),
+    })
+}
+
+/// Convert a statement without its `;`
+fn convert_statement(text: &str) -> Result<String, String> {
+    if let Some((target, "=", value)) = assignment(text) {
+        if target == "timeout" {
+            return Ok(format!("setTimer({})", expression(value)));
+        }
+    }
+    if let Some(converted) = assignment_expression(text) {
+        return Ok(converted);
+    }
+    let word_end = text.find(|c: char| !c.is_alphanumeric() && c != '_' && c != '.').unwrap_or(text.len());
+    let (word, rest) = text.split_at(word_end);
+    let rest = rest.trim();
+    if word.is_empty() || rest.starts_with('(') {
+        return Ok(expression(text));
+    }
+
+    match word {
+        "message" => return Ok(format!("this.chat = {}", text_arg(rest))),
+        "setplayerprop" => {
+            return match rest.split_once(',') {
+                Some((prop, value)) if prop.trim() == "#c" => Ok(format!("player.chat = {}", text_arg(value.trim()))),
+                _ => Err("setplayerprop is only converted for #c".to_string()),
+            };
+        }
+        "setstring" => {
+            return match rest.split_once(',') {
+                Some((name, value)) => Ok(format!("{} = {}", name.trim(), text_arg(value.trim()))),
+                None => Err("setstring needs a name and a value".to_string()),
+            };
+        }
+        _ => {}
+    }
+
+    let Some((name, kinds)) = COMMANDS.iter().find(|(name, _)| *name == word) else {
+        return Err(format!("unknown command '{}'", word));
+    };
+    let args: Vec<String> = match kinds.first() {
+        Some(Rest) => vec![text_arg(rest)],
+        _ if rest.is_empty() => Vec::new(),
+        _ => split_top_level(rest, ',').iter().enumerate()
+            .map(|(i, arg)| match kinds.get(i).copied().unwrap_or(Text) {
+                Expr => expression(arg.trim()),
+                Text | Rest => text_arg(arg.trim()),
+            })
+            .collect(),
+    };
+    if args.len() < kinds.len() {
+        return Err(format!("{} expects {} arguments", name, kinds.len()));
+    }
+    Ok(format!("{}({})", name, args.join(", ")))
+}
+
+/// Split `target op= value` at its assignment operator
+fn assignment(text: &str) -> Option<(&str, &str, &str)> {
+    let bytes = text.as_bytes();
+    let mut depth = 0usize;
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            b'(' | b'[' => depth += 1,
+            b')' | b']' => depth = depth.saturating_sub(1),
+            b'"' => return None,
+            b'=' if depth == 0 => {
+                let previous = i.checked_sub(1).map(|p| bytes[p]);
+                if bytes.get(i + 1) == Some(&b'=') || matches!(previous, Some(b'=' | b'!' | b'<' | b'>')) {
+                    return None;
+                }
+                let start = match previous {
+                    Some(b'+' | b'-' | b'*' | b'/' | b'%') => i - 1,
+                    _ => i,
+                };
+                return Some((text[..start].trim(), &text[start..=i], text[i + 1..].trim()));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Convert a GS1 expression
+fn expression(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '"' {
+            let end = chars[i + 1..].iter().position(|&c| c == '"').map_or(chars.len(), |n| i + 2 + n);
+            out.extend(&chars[i..end]);
+            i = end;
+        } else if c == '#' {
+            let (converted, next) = message_code(&chars, i);
+            out.push_str(&converted);
+            i = next;
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            if chars.get(i) == Some(&'(') {
+                let close = matching_paren(&chars, i);
+                let inner: String = chars[i + 1..close.min(chars.len())].iter().collect();
+                out.push_str(&function_call(&word, &inner));
+                i = close + 1;
+            } else {
+                out.push_str(VARIABLES.iter().find(|(gs1, _)| *gs1 == word).map_or(word.as_str(), |(_, gs2)| gs2));
+            }
+        } else {
+            out.push(c);
+            i += 1;
+        }
+    }
+    out
+}
+
+/// Convert a call inside an expression
+fn function_call(name: &str, args: &str) -> String {
+    let parts = split_top_level(args, ',');
+    match (name, parts.as_slice()) {
+        ("strequals", [a, b]) => format!("({} == {})", text_arg(a.trim()), text_arg(b.trim())),
+        ("strcontains", [a, b]) => format!("({}.pos({}) >= 0)", text_arg(a.trim()), text_arg(b.trim())),
+        ("strlen", [a]) => format!("{}.length()", text_arg(a.trim())),
+        _ => {
+            let args: Vec<String> = parts.iter().map(|a| expression(a.trim())).collect();
+            format!("{}({})", name, if args == [""] { String::new() } else { args.join(", ") })
+        }
+    }
+}
+
+/// Convert the message code at `chars[at]` (a `#`)
+///
+/// # Returns
+/// The GS2 expression and the index after the code
+fn message_code(chars: &[char], at: usize) -> (String, usize) {
+    match chars.get(at + 1) {
+        Some('s' | 'v') if chars.get(at + 2) == Some(&'(') => {
+            let close = matching_paren(chars, at + 2);
+            let inner: String = chars[at + 3..close.min(chars.len())].iter().collect();
+            let inner = expression(inner.trim());
+            let converted = if chars[at + 1] == 'v' { format!("({})", inner) } else { inner };
+            (converted, close + 1)
+        }
+        Some(code) => match CODES.iter().find(|(c, _)| c == code) {
+            Some((_, gs2)) => (gs2.to_string(), at + 2),
+            None => ("#".to_string(), at + 1),
+        },
+        None => ("#".to_string(), at + 1),
+    }
+}
+
+/// Convert GS1 text with message codes to a GS2 string expression
+///
+/// Text that is just one code becomes its expression, other text with codes
+/// a `format()` call.
+fn text_arg(text: &str) -> String {
+    if text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+        return text.to_string();
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let mut codes: Vec<String> = Vec::new();
+    let mut literal = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '#' {
+            let (converted, next) = message_code(&chars, i);
+            if next > i + 1 {
+                literal.push_str("%s");
+                codes.push(converted);
+                i = next;
+                continue;
+            }
+        }
+        if chars[i] == '%' {
+            literal.push('%');
+        }
+        literal.push(chars[i]);
+        i += 1;
+    }
+    match codes.len() {
+        0 => quote(&literal.replace("%%", "%")),
+        1 if literal == "%s" => codes.remove(0),
+        _ => format!("format({}, {})", quote(&literal), codes.join(", ")),
+    }
+}
+
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Get the index of the `)` matching the `(` at `open` (past the end if unclosed)
+fn matching_paren(chars: &[char], open: usize) -> usize {
+    let mut depth = 0;
+    for (i, &c) in chars.iter().enumerate().skip(open) {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => {}
+        }
+    }
+    chars.len()
+}
+
+/// Split at a separator outside parentheses
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0usize, 0);
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            c if c == separator && depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// Split at a two-character operator outside parentheses
+fn split_top_level_str<'a>(text: &'a str, separator: &str) -> Vec<&'a str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start, mut i) = (0usize, 0, 0);
+    let bytes = text.as_bytes();
+    while i < bytes.len() {
+        match bytes[i] {
+            b'(' => depth += 1,
+            b')' => depth = depth.saturating_sub(1),
+            _ if depth == 0 && text[i..].starts_with(separator) => {
+                parts.push(&text[start..i]);
+                i += separator.len();
+                start = i;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+impl Transpiled {
+    /// Describe the untranslated code, one `line: text (reason)` per entry
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        for entry in &self.untranslated {
+            let _ = writeln!(report, "{}: {} ({})", entry.line, entry.text, entry.reason);
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transpile_events_and_commands() {
+        let gs1 = "\
+// Shopkeeper
+if (playerenters) {
+  message Welcome, #n!;
+  setimg shop.png;
+  timeout = 0.5;
+}
+if (playerchats && strequals(#c,buy)) {
+  if (playerrupees >= 10) playerrupees -= 10;
+  else setplayerprop #c,Too poor;
+}
+if (created || playerenters) toweapons Shop;
+";
+        let result = transpile(gs1);
+        assert_eq!(result.source, "\
+// Shopkeeper
+function onPlayerEnters() {
+  this.chat = format(\"Welcome, %s!\", player.nick);
+  setimg(\"shop.png\");
+  setTimer(0.5);
+  toweapons(\"Shop\");
+}
+function onPlayerChats() {
+  if ((player.chat == \"buy\")) {
+    if (player.rupees >= 10) {
+      player.rupees = player.rupees - 10;
+    } else {
+      player.chat = \"Too poor\";
+    }
+  }
+}
+function onCreated() {
+  toweapons(\"Shop\");
+}
+");
+        assert!(result.untranslated.is_empty(), "{}", result.report());
+    }
+
+    #[test]
+    fn test_merge_events_and_else() {
+        let gs1 = "\
+if (created) setimg a.png;
+if (playerenters) {
+  x++;
+} else if (created) {
+  y += 2 * x;
+}
+else message 100%;
+";
+        let result = transpile(gs1);
+        assert_eq!(result.source, "\
+function onCreated() {
+  setimg(\"a.png\");
+  y = y + (2 * x);
+}
+function onPlayerEnters() {
+  x = x + 1;
+}
+// GS1: else
+this.chat = \"100%\";
+");
+        assert_eq!(result.report(), "\
+7: else message 100% (the else of an event block runs on every other event)
+7: message 100% (runs on every event; move it into the events it's meant for)
+");
+    }
+
+    #[test]
+    fn test_output_compiles() {
+        let gs1 = "\
+if (created || playerenters) {
+  message #a has #v(playerrupees) rupees, 10% off;
+  for (i = 0; i < 3; i++) setstring list,#s(list)#n;
+}
+if (playerchats && strcontains(#c,hi)) setplayerprop #c,Hi #n;
+if (timeout) {
+  hide;
+  timeout = 1;
+}
+";
+        let result = transpile(gs1);
+        assert!(result.untranslated.is_empty(), "{}", result.report());
+        assert!(result.source.contains("format(\"%s has %s rupees, 10%% off\", player.account, (player.rupees))"));
+        let script = crate::gs2::Parser::new(&result.source).parse()
+            .unwrap_or_else(|e| panic!("{}\n{}", e, result.source));
+        crate::gs2::Compiler::new().compile(&script).unwrap();
+    }
+
+    #[test]
+    fn test_report_untranslatable() {
+        let result = transpile("if (timeout) {\n  putbomb 1,x,y;\n  sleep 1;\n}\nsetplayerprop #1,sword;\n");
+        assert_eq!(result.source, "\
+function onTimeout() {
+  // GS1: putbomb 1,x,y;
+  sleep(1);
+}
+// GS1: setplayerprop #1,sword;
+");
+        assert_eq!(result.report(), "\
+2: putbomb 1,x,y (unknown command 'putbomb')
+5: setplayerprop #1,sword (runs on every event; move it into the events it's meant for)
+5: setplayerprop #1,sword (setplayerprop is only converted for #c)
+");
+    }
+}
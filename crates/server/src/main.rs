@@ -3,24 +3,45 @@
 //! Main server binary - 1:1 parity with C++ version
 
 use gserver_config::ServerConfig as GameServerConfig;
-use gserver_network::{GServer, ServerConfig as NetworkConfig};
-use tracing::{info, error, Level, warn};
-use tracing_subscriber;
+use gserver_game::{AutosaveConfig, GameTimer, TickLoop};
+use gserver_network::service::{HealthService, PidFile};
+use gserver_network::tasks::spawn_named;
+use gserver_network::webhook::WebhookService;
+use gserver_network::{GServer, ServerConfig as NetworkConfig, ServerContext};
+use gserver_storage::{BackupConfig, BackupManager};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, error, warn};
+
+mod selftest;
+mod tools;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_max_level(Level::INFO)
-        .init();
+    // `gserver tool <command>` runs an offline tool instead of the server
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("tool") {
+        if let Err(e) = tools::run(&args[2..]) {
+            eprintln!("gserver tool: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Load configuration from serveroptions.txt (just like C++ version);
+    // logging is configured there, so it is reported once logging is up
+    let loaded = GameServerConfig::load_default();
+    let logging = loaded.as_ref().map(|c| c.logging.clone()).unwrap_or_default();
+    let server_folder = loaded.as_ref().map(|c| c.server_folder.clone())
+        .unwrap_or_else(|_| GameServerConfig::default().server_folder);
+    gserver_network::logging::init(&logging, &server_folder)?;
 
     info!("🚀 GServer Rust starting up...");
     info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-
-    // Load configuration from serveroptions.txt (just like C++ version)
     info!("📂 Loading configuration from servers/default/config/serveroptions.txt...");
 
-    let game_config = match GameServerConfig::load_default() {
+    let game_config = match loaded {
         Ok(config) => {
             info!("✓ Configuration loaded successfully");
             config
@@ -33,13 +54,47 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
+    // `gserver --restore <archive>` restores a backup and exits
+    if let Some(pos) = args.iter().position(|a| a == "--restore") {
+        let archive = args.get(pos + 1).ok_or("--restore requires a backup archive path")?;
+        let backup_config = BackupConfig::new(&game_config.server_folder);
+        let manager = BackupManager::new(&game_config.server_folder, backup_config);
+        let manifest = manager.restore(archive, gserver_storage::SERVER_VERSION)?;
+        info!("✓ Restored {} (created {}): {}", archive, manifest.created, manifest.paths.join(", "));
+        return Ok(());
+    }
+
+    // `gserver --selftest` runs a simulated client against the server and exits
+    if args.iter().any(|a| a == "--selftest") {
+        if selftest::run(&game_config).await.is_err() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     // Display configuration
     game_config.display();
 
+    // The PID file is removed when main returns
+    let _pid_file = match game_config.pid_file.as_str() {
+        "" => None,
+        path => {
+            let pid_file = PidFile::create(path)?;
+            info!("✓ PID {} written to {}", std::process::id(), pid_file.path().display());
+            Some(pid_file)
+        }
+    };
+
     // Convert to network config
     let network_config = NetworkConfig {
+        server_dir: game_config.server_folder.clone(),
         bind_address: game_config.bind_address(),
         max_connections: game_config.max_players,
+        tls: game_config.tls_bind_address().map(|bind_address| gserver_network::tls::TlsConfig {
+            bind_address,
+            cert_path: Path::new(&game_config.server_folder).join(&game_config.tls_cert),
+            key_path: Path::new(&game_config.server_folder).join(&game_config.tls_key),
+        }),
         ..Default::default()
     };
 
@@ -61,24 +116,176 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         only_staff: game_config.only_staff,
     };
 
-    // Spawn listserver client
-    info!("🌐 Starting listserver client ({}:{})...", listserver_config.list_ip, listserver_config.list_port);
-    let _listserver_handle = gserver_network::spawn_listserver_client(listserver_config);
-    info!("✓ Listserver client started");
+    // Build shared server context (players, levels, weapons, scripts)
+    let context = Arc::new(ServerContext::new(&game_config.server_folder, game_config.clone()));
+
+    context.set_listing(gserver_network::Listing::from_config(&game_config));
+
+    // Spawn a listserver client per listserver (the first relays the context's IRC channels)
+    let requests = Arc::new(gserver_network::requesttext::RequestTextRegistry::new(context.events().clone(), context.commands().clone()));
+    for (index, status) in context.listservers().iter().enumerate() {
+        let (list_ip, list_port) = status.address();
+        info!("🌐 Starting listserver client ({}:{})...", list_ip, list_port);
+        let config = gserver_network::ListServerConfig { list_ip: list_ip.to_string(), list_port, ..listserver_config.clone() };
+        gserver_network::spawn_listserver_client(
+            config, (index == 0).then(|| context.irc().clone()), requests.clone(), status.clone(),
+            context.watchdog().register("listserver client"));
+    }
+    info!("✓ {} listserver clients started", context.listservers().len());
+
+    let weapon_count = context.weapons().load_all();
+    info!("✓ Loaded {} weapons", weapon_count);
+    let class_count = context.classes().load_all();
+    info!("✓ Loaded {} classes", class_count);
+    let npc_count = context.npc_saves().load_all();
+    info!("✓ Loaded {} NPC saves", npc_count);
+    #[cfg(feature = "lua")]
+    info!("✓ Loaded {} Lua scripts", context.load_lua_scripts());
+
+    // Start the game tick loop
+    let mut tick_loop = TickLoop::new(game_config.tick_rate).with_stats(context.tick_stats().clone());
+    let tick_context = context.clone();
+    tick_loop.add_game_timer(GameTimer::WorldTime, move |_| {
+        tick_context.set_world_time(gserver_game::tick::world_time());
+    });
+    let npc_context = context.clone();
+    tick_loop.add_timer("npc movement", tick_loop.tick_duration(), move |_| {
+        npc_context.npc_movements().tick(&npc_context, std::time::Instant::now());
+    });
+    #[cfg(feature = "plugins")]
+    {
+        let hooks = context.plugins().attach_tick_hooks(&mut tick_loop, context.clone());
+        info!("✓ Plugins: {} ({} tick hooks)", context.plugins().names().join(", "), hooks);
+    }
+    let tick_heartbeat = context.watchdog().register("tick loop");
+    tick_loop.add_timer("watchdog", tick_loop.tick_duration(), move |_| tick_heartbeat.beat());
+    let (tick_shutdown_tx, tick_shutdown_rx) = tokio::sync::watch::channel(false);
+    let tick_handle = spawn_named("tick loop", tick_loop.run(tick_shutdown_rx));
+    info!("✓ Tick loop started ({} Hz)", game_config.tick_rate);
 
     // Create GServer instance
     info!("🔧 Initializing GServer...");
-    let server = GServer::new(network_config).await?;
+    let server = GServer::with_context(network_config, context).await?;
     info!("✓ GServer instance created");
 
+    // Start autosave
+    let autosave_config = AutosaveConfig::with_interval(Duration::from_secs(game_config.autosave_interval));
+    let (autosave_shutdown_tx, autosave_shutdown_rx) = tokio::sync::watch::channel(false);
+    let autosave_handle = spawn_named("autosave", server.autosave_service(autosave_config).run(autosave_shutdown_rx));
+    if game_config.autosave_interval > 0 {
+        info!("✓ Autosave started (every {}s)", game_config.autosave_interval);
+    } else {
+        info!("✓ Autosave started (on shutdown only)");
+    }
+
+    // Start scheduled backups
+    let (backup_shutdown_tx, backup_shutdown_rx) = tokio::sync::watch::channel(false);
+    if game_config.backup_interval > 0 {
+        let backups = server.context().backups().clone();
+        spawn_named("backups", backups.run(Duration::from_secs(game_config.backup_interval), backup_shutdown_rx));
+        info!("✓ Backups scheduled (every {}s, keeping {})", game_config.backup_interval, game_config.backup_retention);
+    }
+
+    // Start the event webhook
+    let (webhook_shutdown_tx, webhook_shutdown_rx) = tokio::sync::watch::channel(false);
+    let webhook_handle = match WebhookService::from_context(server.context().clone()) {
+        Ok(Some(webhook)) => {
+            info!("✓ Webhook posting to {}", game_config.webhook.url);
+            Some(spawn_named("webhook", webhook.run(webhook_shutdown_rx)))
+        }
+        Ok(None) => None,
+        Err(e) => {
+            error!("Webhook disabled: {}", e);
+            None
+        }
+    };
+
+    // Start the health check and SIGHUP reload
+    let (health_shutdown_tx, health_shutdown_rx) = tokio::sync::watch::channel(false);
+    if !game_config.health_address.is_empty() {
+        match HealthService::bind(&game_config.health_address, server.context().clone()).await {
+            Ok(health) => {
+                info!("✓ Health check on http://{}/", game_config.health_address);
+                spawn_named("health check", health.run(health_shutdown_rx));
+            }
+            Err(e) => error!("Health check disabled: {}", e),
+        }
+    }
+    tokio::spawn(gserver_network::service::reload_on_hangup(server.context().clone()));
+
+    // Start the operator console on stdin (`--console`) and its socket
+    let (console_shutdown_tx, console_shutdown_rx) = tokio::sync::watch::channel(false);
+    if args.iter().any(|a| a == "--console") {
+        spawn_named("console", gserver_network::console::run_stdin(server.context().clone(), console_shutdown_rx.clone()));
+        info!("✓ Console reading stdin (:help lists the commands)");
+    }
+    #[cfg(unix)]
+    if !game_config.console_socket.is_empty() {
+        let path = Path::new(&game_config.server_folder).join(&game_config.console_socket);
+        info!("✓ Console socket at {}", path.display());
+        let console_context = server.context().clone();
+        spawn_named("console socket", async move {
+            if let Err(e) = gserver_network::console::run_socket(console_context, path, console_shutdown_rx).await {
+                error!("Console socket disabled: {}", e);
+            }
+        });
+    }
+
+    // Write chat to logs/chat (when chatlog is on, checked on every message)
+    let (chatlog_shutdown_tx, chatlog_shutdown_rx) = tokio::sync::watch::channel(false);
+    spawn_named("chat log", gserver_network::chatlog::run(server.context().clone(), chatlog_shutdown_rx));
+
+    // Count playtime and aggregate the leaderboards (saved on shutdown)
+    let (stats_shutdown_tx, stats_shutdown_rx) = tokio::sync::watch::channel(false);
+    let stats_handle = spawn_named("player stats", gserver_network::playerstats::run(server.context().clone(), stats_shutdown_rx));
+
+    // Alert staff chat when the listserver refuses the registration
+    let (latency_shutdown_tx, latency_shutdown_rx) = tokio::sync::watch::channel(false);
+    spawn_named("listserver latency", gserver_network::listlatency::run(server.context().clone(), latency_shutdown_rx));
+
+    // Watch the tick loop, listserver client and accept loop
+    let (watchdog_shutdown_tx, watchdog_shutdown_rx) = tokio::sync::watch::channel(false);
+    spawn_named("watchdog", gserver_network::watchdog::run(server.context().clone(), watchdog_shutdown_rx));
+
     info!("🎮 Server is ready to accept connections!");
     info!("📡 Waiting for players on port {}...", game_config.server_port);
     info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
 
     // Run the server
-    if let Err(e) = server.run().await {
+    let result = server.run().await;
+    let _ = watchdog_shutdown_tx.send(true);
+    let _ = latency_shutdown_tx.send(true);
+    let _ = console_shutdown_tx.send(true);
+    let _ = chatlog_shutdown_tx.send(true);
+    let _ = stats_shutdown_tx.send(true);
+    let _ = stats_handle.await;
+
+    // Stop answering health checks (they already report 503)
+    let _ = health_shutdown_tx.send(true);
+
+    // Stop the tick loop
+    let _ = tick_shutdown_tx.send(true);
+    let _ = tick_handle.await;
+
+    // Final autosave flush
+    let _ = autosave_shutdown_tx.send(true);
+    let _ = autosave_handle.await;
+    server.context().account_saves().drain().await;
+    let _ = backup_shutdown_tx.send(true);
+
+    // Post serverdown before exiting
+    let _ = webhook_shutdown_tx.send(true);
+    if let Some(handle) = webhook_handle {
+        let _ = handle.await;
+    }
+
+    if let Err(e) = result {
         error!("💥 Server error: {}", e);
         Err(e.into())
+    } else if let Some(reason) = server.context().watchdog().restart_reason() {
+        // The supervisor starts the server again
+        error!("🔁 Restarting after a stall ({})", reason);
+        std::process::exit(gserver_network::watchdog::RESTART_EXIT_CODE);
     } else {
         info!("👋 Server shutting down gracefully");
         Ok(())
@@ -0,0 +1,138 @@
+//! `gserver --selftest`
+//!
+//! Boots the server from the configured folder on an ephemeral loopback port
+//! and takes one simulated client ([`SimClient`]) through login, a warp, chat,
+//! a file download and logout, printing each step. Exits with an error at the
+//! first step that fails, so operators can check an install in seconds.
+//!
+//! The test doesn't reach outside the server: the listserver isn't contacted,
+//! `localauth` and `verifylogins` are off, the IP history isn't written, and
+//! the `selftest` account file is removed afterwards unless it already existed.
+
+use gserver_accounts::AccountLoader;
+use gserver_config::ServerConfig as GameServerConfig;
+use gserver_network::simclient::SimClient;
+use gserver_network::{GServer, ServerConfig as NetworkConfig, ServerContext};
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Account the simulated client logs in with
+const ACCOUNT: &str = "selftest";
+
+/// Longest wait for the server to drop the client after logout
+const LOGOUT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Run the self-test
+pub async fn run(config: &GameServerConfig) -> Result<(), Box<dyn Error>> {
+    let config = GameServerConfig { local_auth: false, verify_logins: false, ip_history_days: 0, ..config.clone() };
+    let server_dir = Path::new(&config.server_folder);
+    let had_account = AccountLoader::new(server_dir).exists(ACCOUNT);
+
+    let result = run_steps(&config).await;
+
+    if !had_account {
+        let _ = std::fs::remove_file(server_dir.join("accounts").join(format!("{}.txt", ACCOUNT)));
+    }
+    match &result {
+        Ok(()) => println!("Self-test passed"),
+        Err(e) => println!("Self-test failed: {}", e),
+    }
+    result
+}
+
+async fn run_steps(config: &GameServerConfig) -> Result<(), Box<dyn Error>> {
+    let context = Arc::new(ServerContext::new(&config.server_folder, config.clone()));
+    context.weapons().load_all();
+    context.classes().load_all();
+    let network_config = NetworkConfig {
+        server_dir: config.server_folder.clone(),
+        bind_address: std::net::SocketAddr::from(([127, 0, 0, 1], 0)),
+        ..Default::default()
+    };
+    let server = Arc::new(GServer::with_context(network_config, context.clone()).await?);
+    let address = server.local_addr()?.to_string();
+    let running = server.clone();
+    let handle = tokio::spawn(async move { running.run().await });
+    println!("Server started on {}", address);
+
+    let result = async {
+        let started = Instant::now();
+        let (mut client, _) = SimClient::connect(&address, ACCOUNT, 73).await
+            .map_err(|e| format!("login: {}", e))?;
+        step("login", started);
+
+        let level = AccountLoader::new(Path::new(&config.server_folder)).load(ACCOUNT)
+            .map(|account| account.level)
+            .map_err(|e| format!("warp: can't read the start level: {}", e))?;
+        let started = Instant::now();
+        match client.warp_to(&level).await.map_err(|e| format!("warp: {}", e))? {
+            (_, true) => step(&format!("warp to {}", level), started),
+            (_, false) => return Err(format!("warp: {} failed", level)),
+        }
+
+        let started = Instant::now();
+        client.chat("self-test").await.map_err(|e| format!("chat: {}", e))?;
+        step("chat", started);
+
+        match context.files().names().iter().find(|name| !name.starts_with('.')) {
+            Some(name) => {
+                let started = Instant::now();
+                if !client.download(name).await.map_err(|e| format!("download: {}", e))? {
+                    return Err(format!("download: {} wasn't sent", name));
+                }
+                step(&format!("download {}", name), started);
+            }
+            None => println!("  skipped  download (no files are indexed)"),
+        }
+
+        let started = Instant::now();
+        client.logout().await.map_err(|e| format!("logout: {}", e))?;
+        while server.connection_count() > 0 {
+            if started.elapsed() > LOGOUT_TIMEOUT {
+                return Err("logout: the server kept the connection".to_string());
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        step("logout", started);
+        Ok(())
+    }.await;
+
+    handle.abort();
+    result.map_err(Into::into)
+}
+
+/// Print a passed step and its time
+fn step(name: &str, started: Instant) {
+    println!("  ok  {} ({} ms)", name, started.elapsed().as_millis());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn copy_dir(from: &Path, to: &Path) {
+        std::fs::create_dir_all(to).unwrap();
+        for entry in std::fs::read_dir(from).unwrap().flatten() {
+            let target = to.join(entry.file_name());
+            if entry.path().is_dir() {
+                copy_dir(&entry.path(), &target);
+            } else {
+                std::fs::copy(entry.path(), &target).unwrap();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_selftest_on_default_server() {
+        let dir = tempfile::tempdir().unwrap();
+        let server_dir = dir.path().join("default");
+        copy_dir(&Path::new(env!("CARGO_MANIFEST_DIR")).join("../../servers/default"), &server_dir);
+        let folder = server_dir.to_string_lossy().into_owned();
+        let config = GameServerConfig { server_folder: folder.clone(), ..GameServerConfig::load_from_folder(&folder).unwrap() };
+
+        run(&config).await.unwrap();
+        assert!(!AccountLoader::new(&server_dir).exists(ACCOUNT));
+    }
+}
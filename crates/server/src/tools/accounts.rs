@@ -0,0 +1,125 @@
+//! `gserver tool import-accounts <folder>`
+//!
+//! Converts every `*.txt` account file in a folder (or its `accounts/`
+//! subfolder) written by another server and saves it to our `accounts/`.
+//! Existing accounts are skipped unless `--overwrite` is given; `--dry-run`
+//! only prints the report.
+
+use super::ToolArgs;
+use gserver_accounts::{import_account, AccountLoader};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Totals of one run
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ImportSummary {
+    imported: usize,
+    skipped: usize,
+    failed: usize,
+    issues: usize,
+}
+
+pub fn import_accounts(args: &ToolArgs) -> Result<(), Box<dyn Error>> {
+    let [source] = args.positional.as_slice() else {
+        return Err("import-accounts needs the folder to import from".into());
+    };
+    let summary = import_folder(Path::new(source), &args.server, args.flag("overwrite"), args.flag("dry-run"))?;
+
+    println!();
+    println!("{} {} accounts, {} skipped (already exist), {} failed, {} fields to review",
+        if args.flag("dry-run") { "Would import" } else { "Imported" },
+        summary.imported, summary.skipped, summary.failed, summary.issues);
+    Ok(())
+}
+
+fn import_folder(source: &Path, server: &Path, overwrite: bool, dry_run: bool) -> Result<ImportSummary, Box<dyn Error>> {
+    let folder = match source.join("accounts") {
+        nested if nested.is_dir() => nested,
+        _ => source.to_path_buf(),
+    };
+    let mut files: Vec<PathBuf> = fs::read_dir(&folder)
+        .map_err(|e| format!("can't read {}: {}", folder.display(), e))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("txt")))
+        .collect();
+    files.sort();
+
+    let loader = AccountLoader::new(server);
+    let mut summary = ImportSummary::default();
+    for path in files {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        if stem.eq_ignore_ascii_case("defaultaccount") {
+            println!("{}: skipped, the default account is not imported", stem);
+            continue;
+        }
+
+        let content = match fs::read(&path) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(e) => {
+                println!("{}: can't read: {}", stem, e);
+                summary.failed += 1;
+                continue;
+            }
+        };
+        let import = match import_account(&stem, &content) {
+            Ok(import) => import,
+            Err(e) => {
+                println!("{}: {}", stem, e);
+                summary.failed += 1;
+                continue;
+            }
+        };
+
+        let name = &import.account.name;
+        if loader.exists(name) && !overwrite {
+            println!("{}: skipped, {} already exists", stem, name);
+            summary.skipped += 1;
+            continue;
+        }
+
+        println!("{} → {} ({}, {} renamed fields)", stem, name,
+            import.header.as_deref().unwrap_or("no header"), import.renamed.len());
+        for issue in &import.issues {
+            println!("    {}", issue);
+        }
+        if !dry_run {
+            if let Err(e) = loader.save(&import.account) {
+                println!("    not saved: {}", e);
+                summary.failed += 1;
+                continue;
+            }
+        }
+        summary.imported += 1;
+        summary.issues += import.issues.len();
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_folder() {
+        let source = tempfile::tempdir().unwrap();
+        let server = tempfile::tempdir().unwrap();
+        fs::create_dir_all(source.path().join("accounts")).unwrap();
+        fs::write(source.path().join("accounts/Alice.txt"), "GRACC001\r\nNICKNAME Ally\r\nGUILDRANK 2\r\n").unwrap();
+        fs::write(source.path().join("accounts/Bob.txt"), "NAME Bob\r\nRUPEES 10\r\n").unwrap();
+        fs::write(source.path().join("accounts/defaultaccount.txt"), "GRACC001\r\n").unwrap();
+        fs::write(source.path().join("accounts/empty.txt"), "").unwrap();
+
+        let dry_run = import_folder(source.path(), server.path(), false, true).unwrap();
+        assert_eq!(dry_run, ImportSummary { imported: 2, skipped: 0, failed: 1, issues: 1 });
+        assert!(!server.path().join("accounts").exists());
+
+        import_folder(source.path(), server.path(), false, false).unwrap();
+        let loader = AccountLoader::new(server.path());
+        assert_eq!(loader.load("alice").unwrap().nick, "Ally");
+        assert_eq!(loader.load("bob").unwrap().gralats, 10);
+
+        let again = import_folder(source.path(), server.path(), false, false).unwrap();
+        assert_eq!((again.imported, again.skipped), (0, 2));
+    }
+}
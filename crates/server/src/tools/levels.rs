@@ -0,0 +1,149 @@
+//! `gserver tool check-levels`
+//!
+//! Checks every level matched by a `level` pattern in foldersconfig.txt:
+//! tiles, links and NPC scripts as in [`check_level`], link and gmap targets
+//! that aren't in the level folders, and level names found in more than one
+//! folder (the server only ever loads the first). Exits with an error if
+//! anything was found.
+
+use super::ToolArgs;
+use gserver_config::{FolderType, ServerConfig};
+use gserver_core::wildcard_match;
+use gserver_levels::check_level;
+use gserver_levels::map::MapLoader;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Totals of one run
+#[derive(Debug, Default, PartialEq, Eq)]
+struct CheckSummary {
+    levels: usize,
+    binary: usize,
+    problems: usize,
+}
+
+pub fn check_levels(args: &ToolArgs) -> Result<(), Box<dyn Error>> {
+    let config = ServerConfig::load_from_folder(&args.server.to_string_lossy())
+        .map_err(|e| format!("can't load the config of {}: {}", args.server.display(), e))?;
+    let patterns: Vec<String> = config.folder_config.entries.iter()
+        .filter(|(folder_type, _)| *folder_type == FolderType::Level)
+        .map(|(_, pattern)| pattern.clone())
+        .collect();
+
+    let summary = check_world(&args.server.join("world"), &patterns);
+    println!();
+    println!("Checked {} levels ({} binary levels not checked), {} problems", summary.levels, summary.binary, summary.problems);
+    match summary.problems {
+        0 => Ok(()),
+        n => Err(format!("{} problems found", n).into()),
+    }
+}
+
+fn check_world(world: &Path, patterns: &[String]) -> CheckSummary {
+    let levels = find_levels(world, patterns);
+    let mut summary = CheckSummary::default();
+    let mut report = |path: &Path, message: String| {
+        println!("{}: {}", path.strip_prefix(world).unwrap_or(path).display(), message);
+        summary.problems += 1;
+    };
+
+    for (name, paths) in &levels {
+        let path = &paths[0];
+        for other in &paths[1..] {
+            report(other, format!("duplicate of {}, never loaded", path.strip_prefix(world).unwrap_or(path).display()));
+        }
+
+        if name.ends_with(".gmap") {
+            match MapLoader::load_gmap(path) {
+                Ok(map) => {
+                    for level in map.level_names() {
+                        if !levels.contains_key(&level.to_lowercase()) {
+                            report(path, format!("map level {} not found", level));
+                        }
+                    }
+                }
+                Err(e) => report(path, e.to_string()),
+            }
+            continue;
+        }
+
+        let data = match fs::read(path) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(e) => {
+                report(path, format!("can't read: {}", e));
+                continue;
+            }
+        };
+        let check = check_level(&data);
+        summary.levels += 1;
+        if check.binary.is_some() {
+            summary.binary += 1;
+        }
+        for issue in &check.issues {
+            report(path, issue.to_string());
+        }
+        for link in &check.links {
+            if !levels.contains_key(&link.target.to_lowercase()) {
+                report(path, format!("line {}: link: target {} not found", link.line, link.target));
+            }
+        }
+    }
+    summary
+}
+
+/// Find the files matching the level patterns, by lowercase name
+///
+/// Patterns are relative to `world`, with the wildcard in the file name
+/// only, like foldersconfig.txt.
+fn find_levels(world: &Path, patterns: &[String]) -> BTreeMap<String, Vec<PathBuf>> {
+    let mut levels: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+    for pattern in patterns {
+        let (dir, wildcard) = match pattern.rfind('/') {
+            Some(pos) => (world.join(&pattern[..pos]), &pattern[pos + 1..]),
+            None => (world.to_path_buf(), pattern.as_str()),
+        };
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut paths: Vec<PathBuf> = entries.flatten().map(|e| e.path()).filter(|p| p.is_file()).collect();
+        paths.sort();
+        for path in paths {
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if wildcard_match(wildcard, name) {
+                let found = levels.entry(name.to_lowercase()).or_default();
+                if !found.contains(&path) {
+                    found.push(path);
+                }
+            }
+        }
+    }
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_world() {
+        let world = tempfile::tempdir().unwrap();
+        let board = format!("BOARD 0 0 64 0 {}\n", "AA".repeat(64));
+        fs::create_dir_all(world.path().join("levels")).unwrap();
+        fs::write(world.path().join("start.nw"), format!("GLEVNW01\n{}LINK Cave.nw 0 0 1 1 30 30\nLINK gone.nw 1 0 1 1 0 0\n", board)).unwrap();
+        fs::write(world.path().join("levels/cave.nw"), format!("GLEVNW01\n{}NPC - 1 1\nif (created) {{\nNPCEND\n", board)).unwrap();
+        fs::write(world.path().join("levels/start.nw"), "GLEVNW01\n").unwrap();
+        fs::write(world.path().join("old.graal"), "GR-V1.03\u{1}\u{2}").unwrap();
+
+        let patterns = ["*.nw", "*.graal", "levels/*.nw", "levels/*"].map(String::from);
+        let levels = find_levels(world.path(), &patterns);
+        assert_eq!(levels.keys().collect::<Vec<_>>(), ["cave.nw", "old.graal", "start.nw"]);
+        assert_eq!(levels["start.nw"].len(), 2);
+
+        let summary = check_world(world.path(), &patterns);
+        assert_eq!(summary, CheckSummary { levels: 3, binary: 1, problems: 3 });
+    }
+}
@@ -0,0 +1,83 @@
+//! `gserver tool <command>` - offline maintenance commands
+//!
+//! Tools run instead of the server and print their report to stdout. They
+//! work on a server folder (`--server`, default `servers/default`) and don't
+//! need the server to be stopped unless they write to it.
+
+mod accounts;
+mod levels;
+mod scripts;
+
+use std::error::Error;
+use std::path::PathBuf;
+
+const USAGE: &str = "\
+Usage: gserver tool <command> [options]
+
+Commands:
+  import-accounts <folder> [--overwrite] [--dry-run]
+                             Convert account files from another server into accounts/
+  check-levels               Report broken links, invalid tiles, malformed NPC scripts
+                             and duplicate level names in the level folders
+  compile-script <file.gs2>... [--no-listing]
+                             Compile GS2 scripts and print their bytecode or syntax errors
+  transpile-script <file.txt>... [--write]
+                             Convert GS1 scripts to GS2 and report what can't be converted;
+                             --write saves each next to its source as <file>.gs2
+
+Options:
+  --server <folder>          Server folder to work on (default servers/default)";
+
+/// Run a tool
+///
+/// # Arguments
+/// * `args` - Arguments after `tool`
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let Some(command) = args.first() else {
+        println!("{}", USAGE);
+        return Ok(());
+    };
+    let args = ToolArgs::parse(&args[1..])?;
+    match command.as_str() {
+        "import-accounts" => accounts::import_accounts(&args),
+        "check-levels" => levels::check_levels(&args),
+        "compile-script" => scripts::compile_scripts(&args),
+        "transpile-script" => scripts::transpile_scripts(&args),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        other => Err(format!("unknown tool '{}'\n\n{}", other, USAGE).into()),
+    }
+}
+
+/// Options shared by the tools
+#[derive(Debug, Clone, Default)]
+pub struct ToolArgs {
+    /// Server folder
+    pub server: PathBuf,
+    /// Arguments that aren't options
+    pub positional: Vec<String>,
+    /// `--name` flags without a value
+    pub flags: Vec<String>,
+}
+
+impl ToolArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut parsed = Self { server: PathBuf::from(gserver_config::ServerConfig::default().server_folder), ..Default::default() };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--server" => parsed.server = args.next().ok_or("--server requires a folder")?.into(),
+                flag if flag.starts_with("--") => parsed.flags.push(flag[2..].to_string()),
+                _ => parsed.positional.push(arg.clone()),
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Check if `--<name>` was given
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+}
@@ -0,0 +1,135 @@
+//! `gserver tool compile-script <file.gs2>...`
+//!
+//! Compiles GS2 scripts the way the server will once they are uploaded
+//! through NC or RC, and prints the bytecode listing of each, or where
+//! parsing stopped and what was expected there. `--no-listing` only reports
+//! errors.
+//!
+//! `gserver tool transpile-script <file.txt>...` converts GS1 scripts to GS2
+//! for servers moving to `gs2default=true`, printing the GS2 source and each
+//! command it couldn't convert. `--write` saves `<file>.gs2` instead of
+//! printing the source.
+
+use super::ToolArgs;
+use gserver_scripting::transpile::transpile;
+use gserver_scripting::{GS2Compiler, GS2Parser, ScriptError};
+use std::error::Error;
+use std::fs;
+
+pub fn compile_scripts(args: &ToolArgs) -> Result<(), Box<dyn Error>> {
+    if args.positional.is_empty() {
+        return Err("compile-script needs at least one script file".into());
+    }
+
+    let mut failed = 0;
+    for path in &args.positional {
+        let source = match fs::read(path) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(e) => {
+                println!("{}: can't read: {}", path, e);
+                failed += 1;
+                continue;
+            }
+        };
+        match compile(&source) {
+            Ok(listing) if !args.flag("no-listing") => println!("{}", listing),
+            Ok(_) => println!("{}: ok", path),
+            Err(e) => {
+                println!("{}", render_error(path, &source, &e));
+                failed += 1;
+            }
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        n => Err(format!("{} of {} scripts failed to compile", n, args.positional.len()).into()),
+    }
+}
+
+pub fn transpile_scripts(args: &ToolArgs) -> Result<(), Box<dyn Error>> {
+    if args.positional.is_empty() {
+        return Err("transpile-script needs at least one script file".into());
+    }
+
+    let (mut unreadable, mut untranslated) = (0, 0);
+    for path in &args.positional {
+        let source = match fs::read(path) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(e) => {
+                println!("{}: can't read: {}", path, e);
+                unreadable += 1;
+                continue;
+            }
+        };
+        let result = transpile(&source);
+        if args.flag("write") {
+            let target = format!("{}.gs2", path);
+            fs::write(&target, &result.source)?;
+            println!("{}: wrote {}", path, target);
+        } else {
+            println!("{}", result.source);
+        }
+        for entry in &result.untranslated {
+            println!("{}:{}: can't translate: {} ({})", path, entry.line, entry.text, entry.reason);
+        }
+        untranslated += result.untranslated.len();
+    }
+
+    println!("{} scripts, {} parts to finish by hand", args.positional.len() - unreadable, untranslated);
+    match unreadable {
+        0 => Ok(()),
+        n => Err(format!("{} of {} scripts couldn't be read", n, args.positional.len()).into()),
+    }
+}
+
+/// Parse and compile a script
+///
+/// # Returns
+/// The bytecode listing
+fn compile(source: &str) -> Result<String, ScriptError> {
+    let script = GS2Parser::new(source).parse()?;
+    let chunk = GS2Compiler::new().compile(&script)?;
+    Ok(chunk.disassemble("<script>"))
+}
+
+/// Format an error with the source line it points at
+fn render_error(path: &str, source: &str, error: &ScriptError) -> String {
+    let ScriptError::SyntaxError { line, column, message, expected } = error else {
+        return format!("{}: error: {}", path, error);
+    };
+
+    let mut output = format!("{}:{}:{}: error: {}", path, line, column, message);
+    if let Some(text) = source.lines().nth(line.saturating_sub(1)) {
+        let gutter = " ".repeat(line.to_string().len());
+        // Keep tabs so the caret lines up with the source line
+        let indent: String = text.chars().take(column.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        output.push_str(&format!("\n{} |\n{} | {}\n{} | {}^", gutter, line, text, gutter, indent));
+    }
+    if !expected.is_empty() {
+        output.push_str(&format!("\n  expected: {}", expected.join(" or ")));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_syntax_error() {
+        let source = "function onCreated() {\n\tsetimg(\"door.png\";\n}\n";
+        let error = compile(source).unwrap_err();
+        assert_eq!(render_error("door.gs2", source, &error), "\
+door.gs2:2:19: error: Expected ')' after arguments, found ';'
+  |
+2 | \tsetimg(\"door.png\";
+  | \t                 ^
+  expected: ')'");
+
+        let listing = compile("function onCreated() { return 1; }").unwrap();
+        assert!(listing.contains("== onCreated =="), "{}", listing);
+    }
+}
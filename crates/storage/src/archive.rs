@@ -0,0 +1,235 @@
+//! Minimal tar archive support
+//!
+//! Writes and reads POSIX ustar archives containing regular files and
+//! directories, which is all a server folder backup needs. Archives are
+//! readable by standard `tar`.
+
+use gserver_core::{GServerError, Result};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// Tar block size
+const BLOCK_SIZE: usize = 512;
+
+/// ustar type flag for regular files
+const TYPE_FILE: u8 = b'0';
+
+/// ustar type flag for directories
+const TYPE_DIR: u8 = b'5';
+
+/// An entry read from an archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Entry {
+    /// Directory (relative path)
+    Dir(PathBuf),
+
+    /// Regular file (relative path, contents)
+    File(PathBuf, Vec<u8>),
+}
+
+/// Streaming tar writer
+pub(crate) struct TarWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> TarWriter<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        Self { inner }
+    }
+
+    /// Append a directory entry
+    pub(crate) fn append_dir(&mut self, path: &str, mtime: u64) -> Result<()> {
+        let name = format!("{}/", path.trim_end_matches('/'));
+        let header = Self::header(&name, 0o755, 0, mtime, TYPE_DIR)?;
+        self.inner.write_all(&header)?;
+        Ok(())
+    }
+
+    /// Append a regular file entry
+    pub(crate) fn append_file(&mut self, path: &str, data: &[u8], mtime: u64) -> Result<()> {
+        let header = Self::header(path, 0o644, data.len() as u64, mtime, TYPE_FILE)?;
+        self.inner.write_all(&header)?;
+        self.inner.write_all(data)?;
+
+        let padding = (BLOCK_SIZE - data.len() % BLOCK_SIZE) % BLOCK_SIZE;
+        self.inner.write_all(&[0u8; BLOCK_SIZE][..padding])?;
+        Ok(())
+    }
+
+    /// Write the end-of-archive marker and return the inner writer
+    pub(crate) fn finish(mut self) -> Result<W> {
+        self.inner.write_all(&[0u8; BLOCK_SIZE * 2])?;
+        Ok(self.inner)
+    }
+
+    fn header(path: &str, mode: u32, size: u64, mtime: u64, type_flag: u8) -> Result<[u8; BLOCK_SIZE]> {
+        let mut header = [0u8; BLOCK_SIZE];
+        let (prefix, name) = Self::split_path(path)?;
+
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        write_octal(&mut header[100..108], mode as u64);
+        write_octal(&mut header[108..116], 0);
+        write_octal(&mut header[116..124], 0);
+        write_octal(&mut header[124..136], size);
+        write_octal(&mut header[136..148], mtime);
+        header[156] = type_flag;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+
+        // Checksum is computed with the checksum field set to spaces
+        header[148..156].copy_from_slice(b"        ");
+        let checksum: u32 = header.iter().map(|&b| b as u32).sum();
+        write_octal(&mut header[148..155], checksum as u64);
+        header[155] = b' ';
+
+        Ok(header)
+    }
+
+    /// Split a path into ustar (prefix, name) fields (155 and 100 bytes)
+    fn split_path(path: &str) -> Result<(&str, &str)> {
+        if path.len() <= 100 {
+            return Ok(("", path));
+        }
+
+        // Split at a '/' so that the name fits in 100 bytes and the prefix in 155
+        for (i, c) in path.char_indices() {
+            if c == '/' && i <= 155 && path.len() - i - 1 <= 100 {
+                return Ok((&path[..i], &path[i + 1..]));
+            }
+        }
+
+        Err(GServerError::InvalidData(format!("Path too long for tar archive: {}", path)))
+    }
+}
+
+/// Read all entries of a tar archive
+///
+/// Only regular files and directories are returned; other entry types are
+/// skipped. Absolute paths and paths containing `..` are rejected so an
+/// archive can never write outside the directory it is restored into.
+pub(crate) fn read_entries<R: Read>(mut reader: R) -> Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    let mut header = [0u8; BLOCK_SIZE];
+
+    loop {
+        reader.read_exact(&mut header)?;
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+
+        let stored: u64 = read_octal(&header[148..156])?;
+        let mut check = header;
+        check[148..156].copy_from_slice(b"        ");
+        let computed: u64 = check.iter().map(|&b| b as u64).sum();
+        if stored != computed {
+            return Err(GServerError::InvalidData("Tar header checksum mismatch".into()));
+        }
+
+        let name = read_str(&header[0..100]);
+        let prefix = read_str(&header[345..500]);
+        let full_name = if prefix.is_empty() { name } else { format!("{}/{}", prefix, name) };
+        let path = safe_path(full_name.trim_end_matches('/'))?;
+
+        // Grow with what is actually read, not with the size in the header
+        let size = read_octal(&header[124..136])? as usize;
+        let mut data = Vec::new();
+        reader.by_ref().take(size as u64).read_to_end(&mut data)?;
+        if data.len() < size {
+            return Err(GServerError::InvalidData(format!("Tar entry {:?} is truncated", path)));
+        }
+        let padding = (BLOCK_SIZE - size % BLOCK_SIZE) % BLOCK_SIZE;
+        reader.read_exact(&mut [0u8; BLOCK_SIZE][..padding])?;
+
+        match header[156] {
+            TYPE_FILE | 0 => entries.push(Entry::File(path, data)),
+            TYPE_DIR => entries.push(Entry::Dir(path)),
+            other => tracing::debug!("Skipping tar entry {:?} of type {}", path, other as char),
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Validate an archive path and convert it to a relative `PathBuf`
+pub(crate) fn safe_path(name: &str) -> Result<PathBuf> {
+    let path = Path::new(name);
+    let mut result = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => result.push(part),
+            Component::CurDir => {}
+            _ => return Err(GServerError::InvalidData(format!("Unsafe path in archive: {}", name))),
+        }
+    }
+
+    if result.as_os_str().is_empty() {
+        return Err(GServerError::InvalidData("Empty path in archive".into()));
+    }
+    Ok(result)
+}
+
+fn write_octal(field: &mut [u8], value: u64) {
+    // Zero-padded, NUL-terminated
+    let digits = field.len() - 1;
+    let text = format!("{:0width$o}", value, width = digits);
+    field[..digits].copy_from_slice(&text.as_bytes()[text.len() - digits..]);
+    field[digits] = 0;
+}
+
+fn read_octal(field: &[u8]) -> Result<u64> {
+    let text = read_str(field);
+    let text = text.trim_matches(|c: char| c == ' ' || c == '\0');
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8)
+        .map_err(|_| GServerError::InvalidData(format!("Invalid octal field in tar header: {:?}", text)))
+}
+
+fn read_str(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let long_path = format!("world/{}/level.nw", "sub".repeat(40));
+
+        let mut writer = TarWriter::new(Vec::new());
+        writer.append_dir("accounts", 0).unwrap();
+        writer.append_file("accounts/Alice.txt", b"GRACC001\r\n", 0).unwrap();
+        writer.append_file(&long_path, &[7u8; 1000], 0).unwrap();
+        let data = writer.finish().unwrap();
+
+        assert_eq!(data.len() % BLOCK_SIZE, 0);
+        let entries = read_entries(&data[..]).unwrap();
+        assert_eq!(entries, vec![
+            Entry::Dir(PathBuf::from("accounts")),
+            Entry::File(PathBuf::from("accounts/Alice.txt"), b"GRACC001\r\n".to_vec()),
+            Entry::File(PathBuf::from(long_path), vec![7u8; 1000]),
+        ]);
+    }
+
+    #[test]
+    fn test_rejects_parent_paths() {
+        let mut writer = TarWriter::new(Vec::new());
+        writer.append_file("../evil.txt", b"x", 0).unwrap();
+        let data = writer.finish().unwrap();
+
+        assert!(read_entries(&data[..]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_truncated_entry() {
+        let mut header = TarWriter::<Vec<u8>>::header("huge.bin", 0o644, 0o77_777_777_777, 0, TYPE_FILE).unwrap().to_vec();
+        header.extend_from_slice(&[1u8; 100]);
+
+        assert!(read_entries(&header[..]).is_err());
+    }
+}
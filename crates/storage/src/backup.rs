@@ -0,0 +1,546 @@
+//! # Server Folder Backups
+//!
+//! This module creates and restores timestamped `.tar.gz` snapshots of the
+//! server folder.
+//!
+//! # Snapshot Layout
+//!
+//! ```text
+//! backups/backup-20261014-093000.tar.gz
+//!   BACKUP_MANIFEST        (first entry: version, creation time, paths)
+//!   accounts/...
+//!   world/...              (levels)
+//!   config/...
+//!   serverflags.txt
+//! ```
+//!
+//! Snapshots are created on a schedule (`backupinterval` in serveroptions.txt)
+//! or on demand through the RC `/backup` command. After each snapshot the
+//! oldest archives beyond `backupretention` are deleted.
+//!
+//! # Restoring
+//!
+//! A snapshot is only restored if its manifest was written by the same server
+//! version that is restoring it, since account and level formats may change
+//! between versions. Restoring extracts into a staging directory first and
+//! only replaces the live folders once the whole archive has been read.
+//! Every manifest path must be relative and stay inside the server folder.
+
+use crate::archive::{read_entries, safe_path, Entry, TarWriter};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use gserver_core::{GServerError, Result};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+
+/// Version written into every snapshot manifest
+pub const SERVER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Name of the manifest entry at the start of every snapshot
+pub const MANIFEST_NAME: &str = "BACKUP_MANIFEST";
+
+/// Paths backed up by default (relative to the server folder)
+pub const DEFAULT_BACKUP_PATHS: &[&str] = &["accounts", "world", "config", "serverflags.txt"];
+
+/// Backup settings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupConfig {
+    /// Directory snapshots are written to
+    pub backup_dir: PathBuf,
+
+    /// Files and directories to back up, relative to the server folder
+    pub paths: Vec<String>,
+
+    /// Number of snapshots to keep (0 = keep all)
+    pub retention: usize,
+}
+
+impl BackupConfig {
+    /// Create the default settings for a server folder
+    ///
+    /// Snapshots go to `<server_dir>/backups` and the newest 7 are kept.
+    pub fn new<P: AsRef<Path>>(server_dir: P) -> Self {
+        Self {
+            backup_dir: server_dir.as_ref().join("backups"),
+            paths: DEFAULT_BACKUP_PATHS.iter().map(|p| p.to_string()).collect(),
+            retention: 7,
+        }
+    }
+
+    /// Set the number of snapshots to keep
+    pub fn with_retention(mut self, retention: usize) -> Self {
+        self.retention = retention;
+        self
+    }
+}
+
+/// Snapshot manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupManifest {
+    /// Server version that created the snapshot
+    pub version: String,
+
+    /// Creation time (unix seconds)
+    pub created: u64,
+
+    /// Backed up paths
+    pub paths: Vec<String>,
+}
+
+impl BackupManifest {
+    /// Serialize the manifest (`key=value` lines, CRLF like the other server files)
+    pub fn to_text(&self) -> String {
+        format!("version={}\r\ncreated={}\r\npaths={}\r\n", self.version, self.created, self.paths.join(","))
+    }
+
+    /// Parse a manifest
+    ///
+    /// # Errors
+    /// Returns an error if there is no version or a path is empty, absolute
+    /// or leaves the server folder
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut version = None;
+        let mut created = 0;
+        let mut paths = Vec::new();
+
+        for line in text.lines() {
+            let Some((key, value)) = line.trim().split_once('=') else { continue };
+            match key {
+                "version" => version = Some(value.to_string()),
+                "created" => created = value.parse().unwrap_or(0),
+                "paths" => paths = value.split(',').filter(|p| !p.is_empty()).map(String::from).collect(),
+                _ => {}
+            }
+        }
+
+        let version = version.ok_or_else(|| GServerError::InvalidData("Backup manifest has no version".into()))?;
+        for path in &paths {
+            if safe_path(path)?.as_os_str() != path.as_str() {
+                return Err(GServerError::InvalidData(format!("Backup manifest path {:?} is not a plain relative path", path)));
+            }
+        }
+        Ok(Self { version, created, paths })
+    }
+}
+
+/// A snapshot on disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupInfo {
+    /// Archive path
+    pub path: PathBuf,
+
+    /// Archive size in bytes
+    pub size: u64,
+}
+
+/// Creates, lists, prunes and restores snapshots
+pub struct BackupManager {
+    /// Server folder
+    server_dir: PathBuf,
+
+    /// Backup settings
+    config: BackupConfig,
+}
+
+impl BackupManager {
+    /// Create a backup manager
+    pub fn new<P: Into<PathBuf>>(server_dir: P, config: BackupConfig) -> Self {
+        Self {
+            server_dir: server_dir.into(),
+            config,
+        }
+    }
+
+    /// Get the backup settings
+    pub fn config(&self) -> &BackupConfig {
+        &self.config
+    }
+
+    /// Create a snapshot and apply the retention policy
+    ///
+    /// Paths that don't exist are skipped. Temporary files left by
+    /// in-progress saves (`*.tmp`) are never included.
+    pub fn create_snapshot(&self) -> Result<BackupInfo> {
+        fs::create_dir_all(&self.config.backup_dir)?;
+
+        let created = unix_now();
+        let path = self.next_archive_path(created)?;
+        let temp_path = path.with_extension("gz.tmp");
+
+        let manifest = BackupManifest {
+            version: SERVER_VERSION.to_string(),
+            created,
+            paths: self.config.paths.clone(),
+        };
+
+        let result = self.write_archive(&temp_path, &manifest)
+            .and_then(|_| fs::rename(&temp_path, &path).map_err(GServerError::from));
+        if let Err(e) = result {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e);
+        }
+
+        let info = BackupInfo {
+            size: fs::metadata(&path)?.len(),
+            path,
+        };
+        tracing::info!("Created backup {:?} ({} bytes)", info.path, info.size);
+
+        self.prune()?;
+        Ok(info)
+    }
+
+    /// List snapshots, oldest first
+    pub fn list_snapshots(&self) -> Result<Vec<BackupInfo>> {
+        let entries = match fs::read_dir(&self.config.backup_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut snapshots = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            if snapshot_key(&entry.path()).is_some() {
+                snapshots.push(BackupInfo {
+                    path: entry.path(),
+                    size: entry.metadata()?.len(),
+                });
+            }
+        }
+
+        snapshots.sort_by_key(|s| snapshot_key(&s.path));
+        Ok(snapshots)
+    }
+
+    /// Delete the oldest snapshots beyond the retention count
+    ///
+    /// # Returns
+    /// The number of snapshots deleted
+    pub fn prune(&self) -> Result<usize> {
+        if self.config.retention == 0 {
+            return Ok(0);
+        }
+
+        let snapshots = self.list_snapshots()?;
+        let excess = snapshots.len().saturating_sub(self.config.retention);
+        for snapshot in &snapshots[..excess] {
+            fs::remove_file(&snapshot.path)?;
+            tracing::info!("Removed old backup {:?}", snapshot.path);
+        }
+        Ok(excess)
+    }
+
+    /// Read the manifest of a snapshot without extracting it
+    pub fn read_manifest<P: AsRef<Path>>(archive: P) -> Result<BackupManifest> {
+        let entries = Self::read_archive(archive.as_ref())?;
+        Self::manifest_of(&entries).map(|(manifest, _)| manifest)
+    }
+
+    /// Restore a snapshot into the server folder
+    ///
+    /// # Arguments
+    /// * `archive` - Snapshot to restore
+    /// * `running_version` - Version of the server doing the restore
+    ///   (normally [`SERVER_VERSION`])
+    ///
+    /// # Errors
+    /// Returns an error without touching the server folder if the archive is
+    /// unreadable, was created by a different server version, or contains
+    /// paths outside the ones listed in its manifest.
+    pub fn restore<P: AsRef<Path>>(&self, archive: P, running_version: &str) -> Result<BackupManifest> {
+        let archive = archive.as_ref();
+        let entries = Self::read_archive(archive)?;
+        let (manifest, files) = Self::manifest_of(&entries)?;
+
+        if manifest.version != running_version {
+            return Err(GServerError::InvalidData(format!(
+                "Backup {:?} was created by server version {}, running version is {}",
+                archive, manifest.version, running_version
+            )));
+        }
+
+        // Extract into a staging directory first
+        let staging = self.server_dir.join(".restore");
+        if staging.exists() {
+            fs::remove_dir_all(&staging)?;
+        }
+        fs::create_dir_all(&staging)?;
+
+        let result = Self::extract(files, &manifest, &staging)
+            .and_then(|_| self.swap_in(&staging, &manifest));
+        let _ = fs::remove_dir_all(&staging);
+        result?;
+
+        tracing::info!("Restored backup {:?} (created {}, {} paths)", archive, manifest.created, manifest.paths.len());
+        Ok(manifest)
+    }
+
+    /// Run scheduled backups until `shutdown` becomes `true` (or its sender is dropped)
+    pub async fn run(self: Arc<Self>, interval: Duration, mut shutdown: watch::Receiver<bool>) {
+        tracing::info!("Backups scheduled every {:?} (keeping {})", interval, self.config.retention);
+
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {
+                    let manager = self.clone();
+                    match tokio::task::spawn_blocking(move || manager.create_snapshot()).await {
+                        Ok(Ok(_)) => {}
+                        Ok(Err(e)) => tracing::error!("Scheduled backup failed: {}", e),
+                        Err(e) => tracing::error!("Scheduled backup task panicked: {}", e),
+                    }
+                }
+                result = shutdown.changed() => {
+                    if result.is_err() || *shutdown.borrow() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    fn next_archive_path(&self, created: u64) -> Result<PathBuf> {
+        let stamp = format_timestamp(created);
+
+        // Snapshots in the same second get an increasing suffix. It continues
+        // from the newest existing one so that ordering survives pruning.
+        let next = self.list_snapshots()?
+            .iter()
+            .filter_map(|s| snapshot_key(&s.path))
+            .filter(|(s, _)| *s == stamp)
+            .map(|(_, n)| n + 1)
+            .max();
+
+        let name = match next {
+            None => format!("backup-{}.tar.gz", stamp),
+            Some(n) => format!("backup-{}-{}.tar.gz", stamp, n),
+        };
+        Ok(self.config.backup_dir.join(name))
+    }
+
+    fn write_archive(&self, path: &Path, manifest: &BackupManifest) -> Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut tar = TarWriter::new(GzEncoder::new(file, Compression::default()));
+
+        tar.append_file(MANIFEST_NAME, manifest.to_text().as_bytes(), manifest.created)?;
+        for rel in &manifest.paths {
+            let full = self.server_dir.join(rel);
+            if full.is_dir() {
+                Self::append_dir(&mut tar, &full, rel)?;
+            } else if full.is_file() {
+                tar.append_file(rel, &fs::read(&full)?, mtime(&full))?;
+            } else {
+                tracing::debug!("Backup path {:?} does not exist, skipping", full);
+            }
+        }
+
+        tar.finish()?.finish()?;
+        Ok(())
+    }
+
+    fn append_dir<W: std::io::Write>(tar: &mut TarWriter<W>, dir: &Path, rel: &str) -> Result<()> {
+        tar.append_dir(rel, mtime(dir))?;
+
+        let mut entries: Vec<_> = fs::read_dir(dir)?.collect::<std::io::Result<_>>()?;
+        entries.sort_by_key(|e| e.file_name());
+
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let path = entry.path();
+            let child = format!("{}/{}", rel, name);
+
+            if path.is_dir() {
+                Self::append_dir(tar, &path, &child)?;
+            } else if path.is_file() && !name.ends_with(".tmp") {
+                tar.append_file(&child, &fs::read(&path)?, mtime(&path))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_archive(path: &Path) -> Result<Vec<Entry>> {
+        let file = BufReader::new(File::open(path)?);
+        read_entries(GzDecoder::new(file))
+    }
+
+    fn manifest_of(entries: &[Entry]) -> Result<(BackupManifest, &[Entry])> {
+        match entries.split_first() {
+            Some((Entry::File(path, data), rest)) if path == Path::new(MANIFEST_NAME) => {
+                let manifest = BackupManifest::parse(&String::from_utf8_lossy(data))?;
+                Ok((manifest, rest))
+            }
+            _ => Err(GServerError::InvalidData("Archive is not a server backup (no manifest)".into())),
+        }
+    }
+
+    fn extract(entries: &[Entry], manifest: &BackupManifest, staging: &Path) -> Result<()> {
+        for entry in entries {
+            let rel = match entry {
+                Entry::Dir(path) | Entry::File(path, _) => path,
+            };
+            if !manifest.paths.iter().any(|p| rel.starts_with(p)) {
+                return Err(GServerError::InvalidData(format!("Backup entry {:?} is not in the manifest", rel)));
+            }
+
+            let target = staging.join(rel);
+            match entry {
+                Entry::Dir(_) => fs::create_dir_all(&target)?,
+                Entry::File(_, data) => {
+                    if let Some(parent) = target.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    fs::write(&target, data)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn swap_in(&self, staging: &Path, manifest: &BackupManifest) -> Result<()> {
+        for rel in &manifest.paths {
+            let staged = staging.join(rel);
+            if !staged.exists() {
+                continue;
+            }
+
+            let live = self.server_dir.join(rel);
+            if live.is_dir() {
+                fs::remove_dir_all(&live)?;
+            } else if live.exists() {
+                fs::remove_file(&live)?;
+            }
+            fs::rename(&staged, &live)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a snapshot file name into its (timestamp, suffix) sort key
+///
+/// `backup-20261014-093000.tar.gz` is `("20261014-093000", 0)` and
+/// `backup-20261014-093000-2.tar.gz` is `("20261014-093000", 2)`.
+fn snapshot_key(path: &Path) -> Option<(String, u32)> {
+    let name = path.file_name()?.to_str()?;
+    let rest = name.strip_prefix("backup-")?.strip_suffix(".tar.gz")?;
+    if rest.len() < 15 || !rest.is_char_boundary(15) {
+        return None;
+    }
+
+    let (stamp, suffix) = rest.split_at(15);
+    let n = match suffix.strip_prefix('-') {
+        Some(n) => n.parse().ok()?,
+        None if suffix.is_empty() => 0,
+        None => return None,
+    };
+    Some((stamp.to_string(), n))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn mtime(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Format unix seconds as a UTC `YYYYMMDD-HHMMSS` stamp
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}", year, month, day, rem / 3600, rem % 3600 / 60, rem % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server_folder() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("accounts")).unwrap();
+        fs::create_dir_all(dir.path().join("world/sub")).unwrap();
+        fs::write(dir.path().join("accounts/Alice.txt"), "GRACC001\r\nNICK Alice\r\n").unwrap();
+        fs::write(dir.path().join("world/sub/onlinestartlocal.nw"), "GLEVNW01\n").unwrap();
+        fs::write(dir.path().join("serverflags.txt"), "server.event=1\r\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "19700101-000000");
+        assert_eq!(format_timestamp(1_791_970_200), "20261014-093000");
+    }
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        let dir = server_folder();
+        let manager = BackupManager::new(dir.path(), BackupConfig::new(dir.path()));
+
+        let info = manager.create_snapshot().unwrap();
+        let manifest = BackupManager::read_manifest(&info.path).unwrap();
+        assert_eq!(manifest.version, SERVER_VERSION);
+
+        fs::write(dir.path().join("accounts/Alice.txt"), "corrupted").unwrap();
+        fs::write(dir.path().join("accounts/Mallory.txt"), "new").unwrap();
+        manager.restore(&info.path, SERVER_VERSION).unwrap();
+
+        let alice = fs::read_to_string(dir.path().join("accounts/Alice.txt")).unwrap();
+        assert_eq!(alice, "GRACC001\r\nNICK Alice\r\n");
+        assert!(!dir.path().join("accounts/Mallory.txt").exists());
+        assert!(dir.path().join("world/sub/onlinestartlocal.nw").exists());
+        assert!(!dir.path().join(".restore").exists());
+    }
+
+    #[test]
+    fn test_restore_rejects_other_version() {
+        let dir = server_folder();
+        let manager = BackupManager::new(dir.path(), BackupConfig::new(dir.path()));
+        let info = manager.create_snapshot().unwrap();
+
+        fs::write(dir.path().join("serverflags.txt"), "changed").unwrap();
+        assert!(manager.restore(&info.path, "0.0.1-other").is_err());
+        assert_eq!(fs::read_to_string(dir.path().join("serverflags.txt")).unwrap(), "changed");
+    }
+
+    #[test]
+    fn test_manifest_rejects_unsafe_paths() {
+        for paths in ["..", "../accounts", "/etc", ".", "accounts/../..", "./world"] {
+            let text = format!("version=1\r\ncreated=0\r\npaths=accounts,{}\r\n", paths);
+            assert!(BackupManifest::parse(&text).is_err(), "{}", paths);
+        }
+        let manifest = BackupManifest::parse("version=1\r\npaths=accounts,world/sub\r\n").unwrap();
+        assert_eq!(manifest.paths, ["accounts", "world/sub"]);
+    }
+
+    #[test]
+    fn test_retention() {
+        let dir = server_folder();
+        let manager = BackupManager::new(dir.path(), BackupConfig::new(dir.path()).with_retention(2));
+
+        let created: Vec<_> = (0..4).map(|_| manager.create_snapshot().unwrap().path).collect();
+
+        let remaining: Vec<_> = manager.list_snapshots().unwrap().into_iter().map(|s| s.path).collect();
+        assert_eq!(remaining, created[2..]);
+    }
+}
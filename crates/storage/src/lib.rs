@@ -0,0 +1,12 @@
+//! # GServer Storage
+//!
+//! Persistence helpers for the server folder.
+//!
+//! # Modules
+//!
+//! - [`backup`] - Timestamped `.tar.gz` snapshots with retention and restore
+
+mod archive;
+pub mod backup;
+
+pub use backup::{format_timestamp, BackupConfig, BackupInfo, BackupManager, BackupManifest, SERVER_VERSION};